@@ -8,9 +8,18 @@
  * you can obtain one at https://mozilla.org/MPL/2.0/.
  */
 
-use clap::Parser;
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use kolibrie::compression::{open_rdf_file, read_rdf_file};
+use kolibrie::display::{format_table_with_max_width, format_unaligned, DEFAULT_MAX_CELL_WIDTH};
 use kolibrie::execute_query::*;
+use kolibrie::rdf_format::{detect_rdf_format, RdfFormat};
+use kolibrie::remote::{fetch, RemoteOptions};
 use kolibrie::sparql_database::SparqlDatabase;
+use kolibrie::streamertail_optimizer::CancellationToken;
+use std::io::{self, IsTerminal, Read, Write};
+use std::path::PathBuf;
+use std::process::{self, Command as ProcessCommand};
+use std::time::Duration;
 
 #[derive(Parser)]
 #[command(
@@ -19,23 +28,333 @@ use kolibrie::sparql_database::SparqlDatabase;
     author = "Volodymyr Kadzhaia <vkadzhaia@gmail.com>",
     author = "Pieter Bonte <pieter.bonte@kuleuven.be>",
     about = "A CLI tool for Kolibrie",
-    long_about = "Kolibrie CLI - A command-line interface for loading RDF/XML files and executing SPARQL queries against them. Built with Rust using the kolibrie library for high-performance RDF processing."
+    long_about = "Kolibrie CLI - A command-line interface for loading RDF files, executing SPARQL queries against them, converting between RDF serializations and launching the Kolibrie HTTP server. Built with Rust using the kolibrie library for high-performance RDF processing.",
+    args_conflicts_with_subcommands = true
 )]
-struct Args {
-    #[arg(short, long, help = "RDF file to query", value_name = "FILE")]
-    file: String,
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Commands>,
 
-    #[arg(short, long, help = "SPARQL query string", value_name = "QUERY")]
-    query: String,
+    // Kept at the top level so `kolibrie-cli -f data.rdf -q "..."` keeps working
+    #[command(flatten)]
+    query: QueryArgs,
+}
+
+#[derive(Subcommand)]
+enum Commands {
+    /// Load an RDF file and execute a SPARQL query against it
+    Query(QueryArgs),
+    /// Convert RDF data between serializations
+    Convert(ConvertArgs),
+    /// Launch the Kolibrie HTTP server
+    Serve(ServeArgs),
+}
+
+#[derive(Args)]
+struct QueryArgs {
+    #[arg(short, long, help = "RDF file (optionally .gz or .zst) or http(s) URL to query", value_name = "FILE")]
+    file: Option<String>,
+
+    #[arg(
+        long,
+        value_enum,
+        default_value = "auto",
+        help = "Serialization of the RDF file; for URLs `auto` follows the Content-Type"
+    )]
+    format: FormatArg,
+
+    #[arg(long, default_value_t = 30, help = "Seconds allowed for fetching a URL", value_name = "SECS")]
+    timeout: u64,
+
+    #[arg(
+        long,
+        default_value_t = 256 * 1024 * 1024,
+        help = "Largest document accepted from a URL, in bytes",
+        value_name = "BYTES"
+    )]
+    max_size: u64,
+
+    #[arg(
+        short,
+        long,
+        help = "SPARQL query string, or `-` to read it from stdin",
+        value_name = "QUERY",
+        conflicts_with = "query_file"
+    )]
+    query: Option<String>,
+
+    #[arg(long, help = "File containing the SPARQL query", value_name = "FILE")]
+    query_file: Option<PathBuf>,
+
+    #[arg(long, help = "Print tab-separated rows instead of an aligned table")]
+    raw: bool,
+
+    #[arg(
+        long,
+        default_value_t = DEFAULT_MAX_CELL_WIDTH,
+        help = "Longest cell shown in the table; longer values are cut short with `…`",
+        value_name = "CHARS"
+    )]
+    max_width: usize,
+}
+
+#[derive(Args)]
+struct ConvertArgs {
+    #[arg(long, value_enum, default_value = "auto", help = "Serialization of the input data")]
+    from: FormatArg,
+
+    #[arg(long, value_enum, help = "Serialization of the output data")]
+    to: FormatArg,
+
+    #[arg(short, long, help = "Input file (reads stdin when omitted)", value_name = "FILE")]
+    input: Option<PathBuf>,
+}
+
+#[derive(Args)]
+struct ServeArgs {
+    #[arg(
+        long,
+        help = "Path to the kolibrie-http-server binary (defaults to the one next to this CLI, then PATH)",
+        value_name = "PATH"
+    )]
+    server_bin: Option<PathBuf>,
+
+    #[arg(long, help = "RDF file the server loads as its queryable dataset", value_name = "FILE")]
+    dataset: Option<PathBuf>,
+}
+
+#[derive(Clone, Copy, ValueEnum)]
+enum FormatArg {
+    /// Detect the serialization from the data
+    Auto,
+    Rdfxml,
+    Turtle,
+    Ntriples,
+    N3,
+}
+
+impl FormatArg {
+    /// Resolve to a concrete format, inspecting `data` for `auto`
+    fn resolve(self, data: &str) -> RdfFormat {
+        match self {
+            FormatArg::Auto => detect_rdf_format(data),
+            FormatArg::Rdfxml => RdfFormat::RdfXml,
+            FormatArg::Turtle => RdfFormat::Turtle,
+            FormatArg::Ntriples => RdfFormat::NTriples,
+            FormatArg::N3 => RdfFormat::N3,
+        }
+    }
 }
 
 fn main() {
-    let args = Args::parse();
+    let cli = Cli::parse();
+
+    let result = match cli.command {
+        Some(Commands::Query(args)) => run_query(args),
+        Some(Commands::Convert(args)) => run_convert(args),
+        Some(Commands::Serve(args)) => run_serve(args),
+        None => run_query(cli.query),
+    };
+
+    if let Err(message) = result {
+        eprintln!("Error: {}", message);
+        process::exit(1);
+    }
+}
+
+fn run_query(args: QueryArgs) -> Result<(), String> {
+    let Some(file) = args.file else {
+        return Err("--file is required (see `kolibrie-cli query --help`)".to_string());
+    };
+    let query = match (args.query, args.query_file) {
+        (Some(query), _) if query == "-" => {
+            let mut query = String::new();
+            io::stdin()
+                .read_to_string(&mut query)
+                .map_err(|e| format!("cannot read query from stdin: {}", e))?;
+            query
+        }
+        (Some(query), _) => query,
+        (None, Some(path)) => std::fs::read_to_string(&path)
+            .map_err(|e| format!("cannot read {}: {}", path.display(), e))?,
+        (None, None) => {
+            return Err("a query is required: pass --query or --query-file (see `kolibrie-cli query --help`)".to_string())
+        }
+    };
 
     let mut database = SparqlDatabase::new();
-    database.parse_rdf_from_file(&args.file);
+    let is_url = file.starts_with("http://") || file.starts_with("https://");
+    match args.format {
+        _ if is_url => {
+            let options = RemoteOptions {
+                timeout: Duration::from_secs(args.timeout),
+                max_bytes: args.max_size,
+                ..RemoteOptions::default()
+            };
+            match args.format {
+                FormatArg::Auto => database.add_from_url(&file, &options),
+                format => fetch(&file, &options).map(|document| {
+                    database.parse_rdf_with_format(&document.body, format.resolve(&document.body))
+                }),
+            }
+            .map_err(|e| format!("cannot load {}: {}", file, e))?;
+        }
+        // Large files take a while, so show how far loading got on a terminal
+        FormatArg::Rdfxml if io::stderr().is_terminal() => {
+            let read = database.parse_rdf_from_file_with_progress(
+                &file,
+                |read| eprint!("\rLoaded {} triples", read),
+                &CancellationToken::new(),
+            );
+            if read > 0 {
+                eprintln!();
+            }
+        }
+        FormatArg::Rdfxml => database.parse_rdf_from_file(&file),
+        // Streamed line by line so dumps larger than memory can be queried
+        FormatArg::Ntriples => {
+            let mut reader = open_rdf_file(&file)
+                .map_err(|e| format!("cannot read {}: {}", file, e))?;
+            database
+                .parse_ntriples_streaming(&mut reader)
+                .map_err(|e| format!("cannot read {}: {}", file, e))?;
+        }
+        format => {
+            let data = read_rdf_file(&file)
+                .map_err(|e| format!("cannot read {}: {}", file, e))?;
+            database.parse_rdf_with_format(&data, format.resolve(&data));
+        }
+    }
 
     // Execute query
-    let results = execute_query(&args.query, &mut database);
-    println!("Results: {:?}", results);
+    let results = try_execute_query(&query, &mut database).map_err(|e| e.to_string())?;
+    let variables = query_result_variables(&query);
+    if args.raw {
+        print!("{}", format_unaligned(&variables, &results));
+    } else {
+        print!("{}", format_table_with_max_width(&variables, &results, args.max_width));
+    }
+    Ok(())
+}
+
+fn run_convert(args: ConvertArgs) -> Result<(), String> {
+    let mut data = String::new();
+    match &args.input {
+        Some(path) => {
+            data = std::fs::read_to_string(path)
+                .map_err(|e| format!("cannot read {}: {}", path.display(), e))?;
+        }
+        None => {
+            io::stdin()
+                .read_to_string(&mut data)
+                .map_err(|e| format!("cannot read stdin: {}", e))?;
+        }
+    }
+
+    let mut database = SparqlDatabase::new();
+    database.parse_rdf_with_format(&data, args.from.resolve(&data));
+
+    let output = match args.to {
+        // Streamed so large stores are not rendered into one string first
+        FormatArg::Rdfxml => {
+            return database
+                .write_rdf_xml(io::BufWriter::new(io::stdout().lock()))
+                .map_err(|e| format!("cannot write output: {}", e))
+        }
+        FormatArg::Turtle => database.generate_turtle(),
+        FormatArg::Ntriples => database.generate_ntriples(),
+        FormatArg::N3 => return Err("N3 is only supported as an input format".to_string()),
+        FormatArg::Auto => return Err("--to needs an explicit output format".to_string()),
+    };
+
+    io::stdout()
+        .write_all(output.as_bytes())
+        .map_err(|e| format!("cannot write output: {}", e))
+}
+
+fn run_serve(args: ServeArgs) -> Result<(), String> {
+    let server_bin = args.server_bin.unwrap_or_else(default_server_bin);
+
+    let mut command = ProcessCommand::new(&server_bin);
+    if let Some(dataset) = &args.dataset {
+        command.arg("--dataset").arg(dataset);
+    }
+    let status = command
+        .status()
+        .map_err(|e| format!("cannot launch {}: {}", server_bin.display(), e))?;
+
+    if status.success() {
+        Ok(())
+    } else {
+        Err(format!("{} exited with {}", server_bin.display(), status))
+    }
+}
+
+// Prefer the server binary built alongside this CLI, fall back to PATH lookup
+fn default_server_bin() -> PathBuf {
+    let file_name = format!("kolibrie-http-server{}", std::env::consts::EXE_SUFFIX);
+    std::env::current_exe()
+        .ok()
+        .and_then(|exe| exe.parent().map(|dir| dir.join(&file_name)))
+        .filter(|candidate| candidate.is_file())
+        .unwrap_or_else(|| PathBuf::from(file_name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{Cli, Commands, FormatArg};
+    use clap::Parser;
+    use std::path::PathBuf;
+
+    #[test]
+    fn query_subcommand_and_top_level_form_parse_alike() {
+        let query = "SELECT ?s WHERE { ?s ?p ?o }";
+        for argv in [
+            vec!["kolibrie-cli", "query", "-f", "data.ttl", "-q", query, "--format", "turtle", "--raw"],
+            vec!["kolibrie-cli", "-f", "data.ttl", "-q", query, "--format", "turtle", "--raw"],
+        ] {
+            let cli = Cli::try_parse_from(argv).unwrap();
+            let args = match cli.command {
+                Some(Commands::Query(args)) => args,
+                None => cli.query,
+                Some(_) => panic!("parsed as another subcommand"),
+            };
+            assert_eq!(args.file.as_deref(), Some("data.ttl"));
+            assert_eq!(args.query.as_deref(), Some(query));
+            assert!(matches!(args.format, FormatArg::Turtle));
+            assert!(args.raw);
+            assert_eq!(args.timeout, 30);
+        }
+
+        // --query and --query-file are alternatives
+        assert!(Cli::try_parse_from(["kolibrie-cli", "query", "-q", query, "--query-file", "q.rq"]).is_err());
+    }
+
+    #[test]
+    fn convert_subcommand_requires_an_output_format() {
+        let cli = Cli::try_parse_from(["kolibrie-cli", "convert", "--to", "ntriples", "-i", "data.ttl"]).unwrap();
+        let Some(Commands::Convert(args)) = cli.command else {
+            panic!("expected the convert subcommand");
+        };
+        assert!(matches!(args.from, FormatArg::Auto));
+        assert!(matches!(args.to, FormatArg::Ntriples));
+        assert_eq!(args.input, Some(PathBuf::from("data.ttl")));
+
+        assert!(Cli::try_parse_from(["kolibrie-cli", "convert", "--from", "turtle"]).is_err());
+        assert!(Cli::try_parse_from(["kolibrie-cli", "convert", "--to", "json"]).is_err());
+    }
+
+    #[test]
+    fn serve_subcommand_takes_binary_and_dataset() {
+        let cli = Cli::try_parse_from(["kolibrie-cli", "serve", "--server-bin", "./server", "--dataset", "data.rdf"])
+            .unwrap();
+        let Some(Commands::Serve(args)) = cli.command else {
+            panic!("expected the serve subcommand");
+        };
+        assert_eq!(args.server_bin, Some(PathBuf::from("./server")));
+        assert_eq!(args.dataset, Some(PathBuf::from("data.rdf")));
+
+        let cli = Cli::try_parse_from(["kolibrie-cli", "serve"]).unwrap();
+        assert!(matches!(cli.command, Some(Commands::Serve(ref args)) if args.server_bin.is_none() && args.dataset.is_none()));
+    }
 }
@@ -0,0 +1,59 @@
+/*
+ * Copyright © 2026 Volodymyr Kadzhaia
+ * Copyright © 2026 Pieter Bonte
+ * KU Leuven — Stream Intelligence Lab, Belgium
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this file,
+ * you can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+/// Run the CLI with `args`, feeding `stdin`; returns its exit status and stdout
+fn run_cli(args: &[&str], stdin: &str) -> (bool, String) {
+    let mut child = Command::new(env!("CARGO_BIN_EXE_cli"))
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .expect("failed to launch the CLI");
+    child.stdin.take().unwrap().write_all(stdin.as_bytes()).unwrap();
+    let output = child.wait_with_output().unwrap();
+    (output.status.success(), String::from_utf8(output.stdout).unwrap())
+}
+
+#[test]
+fn convert_turtle_to_ntriples_through_stdin_and_stdout() {
+    let turtle = r#"@prefix ex: <http://example.org/> .
+ex:alice ex:knows ex:bob ;
+         ex:name "Alice" .
+"#;
+    let (success, ntriples) = run_cli(&["convert", "--from", "turtle", "--to", "ntriples"], turtle);
+    assert!(success);
+    let mut lines: Vec<&str> = ntriples.lines().collect();
+    lines.sort();
+    assert_eq!(
+        lines,
+        [
+            r#"<http://example.org/alice> <http://example.org/knows> <http://example.org/bob> ."#,
+            r#"<http://example.org/alice> <http://example.org/name> "Alice" ."#,
+        ]
+    );
+
+    // The N-Triples output converts back to the same triples
+    let (success, round_trip) = run_cli(&["convert", "--to", "ntriples"], &ntriples);
+    assert!(success);
+    let mut round_trip: Vec<&str> = round_trip.lines().collect();
+    round_trip.sort();
+    assert_eq!(round_trip, lines);
+}
+
+#[test]
+fn convert_rejects_n3_output() {
+    let (success, output) = run_cli(&["convert", "--to", "n3"], "");
+    assert!(!success);
+    assert!(output.is_empty());
+}
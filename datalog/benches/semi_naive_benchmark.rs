@@ -0,0 +1,175 @@
+/*
+ * Copyright © 2026 Volodymyr Kadzhaia
+ * Copyright © 2026 Pieter Bonte
+ * KU Leuven — Stream Intelligence Lab, Belgium
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this file,
+ * you can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+//! Transitive closure over a 100k-edge graph, with and without the rule
+//! index restricting each semi-naive round to rules that can match the delta.
+//! The graph is made of disjoint chains so the closure stays tractable; the
+//! extra rules over absent predicates stand in for the rest of a rule set.
+//!
+//! A second group matches ten independent two-hop rules over one shared
+//! ABox, one rule after another and in parallel on Rayon.
+
+use criterion::{criterion_group, criterion_main, Criterion};
+use datalog::reasoning::Reasoner;
+use shared::rule::Rule;
+use shared::terms::Term;
+
+const EDGES: usize = 100_000;
+const CHAIN_LENGTH: usize = 4;
+const UNRELATED_RULES: usize = 20;
+const INDEPENDENT_RULES: usize = 10;
+const SHARED_ABOX_NODES: usize = 5_000;
+
+fn rule(premise: Vec<(Term, Term, Term)>, conclusion: Vec<(Term, Term, Term)>) -> Rule {
+    Rule {
+        premise,
+        negative_premise: vec![],
+        filters: vec![],
+        conclusion,
+    }
+}
+
+fn build_reasoner() -> Reasoner {
+    let mut reasoner = Reasoner::new();
+    for chain in 0..EDGES / CHAIN_LENGTH {
+        for step in 0..CHAIN_LENGTH {
+            reasoner.add_abox_triple(
+                &format!("node{}_{}", chain, step),
+                "edge",
+                &format!("node{}_{}", chain, step + 1),
+            );
+        }
+    }
+
+    let mut dict = reasoner.dictionary.write().unwrap();
+    let edge = dict.encode("edge");
+    let path = dict.encode("path");
+    let unrelated: Vec<(u32, u32)> = (0..UNRELATED_RULES)
+        .map(|i| (dict.encode(&format!("unused{}", i)), dict.encode(&format!("derived{}", i))))
+        .collect();
+    drop(dict);
+
+    let var = |name: &str| Term::Variable(name.to_string());
+    reasoner.add_rule(rule(
+        vec![(var("x"), Term::Constant(edge), var("y"))],
+        vec![(var("x"), Term::Constant(path), var("y"))],
+    ));
+    reasoner.add_rule(rule(
+        vec![
+            (var("x"), Term::Constant(path), var("y")),
+            (var("y"), Term::Constant(edge), var("z")),
+        ],
+        vec![(var("x"), Term::Constant(path), var("z"))],
+    ));
+    for (from, to) in unrelated {
+        reasoner.add_rule(rule(
+            vec![(var("x"), Term::Constant(from), var("y"))],
+            vec![(var("x"), Term::Constant(to), var("y"))],
+        ));
+    }
+    reasoner
+}
+
+// Every rule joins its own relation with itself: `(x r_i y), (y r_i z) -> (x hop_i z)`.
+// No conclusion feeds a premise, so all rules are matched in the first round.
+fn build_independent_rules_reasoner() -> Reasoner {
+    let mut reasoner = Reasoner::new();
+    for i in 0..INDEPENDENT_RULES {
+        let relation = format!("r{}", i);
+        for node in 0..SHARED_ABOX_NODES {
+            reasoner.add_abox_triple(
+                &format!("node{}", node),
+                &relation,
+                &format!("node{}", (node * (i + 2) + 1) % SHARED_ABOX_NODES),
+            );
+        }
+    }
+
+    let mut dict = reasoner.dictionary.write().unwrap();
+    let relations: Vec<(u32, u32)> = (0..INDEPENDENT_RULES)
+        .map(|i| (dict.encode(&format!("r{}", i)), dict.encode(&format!("hop{}", i))))
+        .collect();
+    drop(dict);
+
+    let var = |name: &str| Term::Variable(name.to_string());
+    for (relation, hop) in relations {
+        reasoner.add_rule(rule(
+            vec![
+                (var("x"), Term::Constant(relation), var("y")),
+                (var("y"), Term::Constant(relation), var("z")),
+            ],
+            vec![(var("x"), Term::Constant(hop), var("z"))],
+        ));
+    }
+    reasoner
+}
+
+fn report_rounds() {
+    for use_rule_index in [false, true] {
+        let (facts, rounds) = build_reasoner().infer_new_facts_semi_naive_with_stats(use_rule_index);
+        println!(
+            "rule index {}: {} facts inferred in {} rounds",
+            if use_rule_index { "on" } else { "off" },
+            facts.len(),
+            rounds.len()
+        );
+        for (i, round) in rounds.iter().enumerate() {
+            println!(
+                "  round {}: delta {:>7}, rules evaluated {:>3}, delta joins {:>3}",
+                i + 1,
+                round.delta_size,
+                round.rules_evaluated,
+                round.delta_joins
+            );
+        }
+    }
+}
+
+fn semi_naive_benchmark(c: &mut Criterion) {
+    report_rounds();
+
+    let mut group = c.benchmark_group("semi_naive_transitive_closure");
+    group.sample_size(10);
+    group.bench_function("all_rules_per_round", |b| {
+        b.iter_batched(
+            build_reasoner,
+            |mut reasoner| reasoner.infer_new_facts_semi_naive_with_stats(false),
+            criterion::BatchSize::LargeInput,
+        )
+    });
+    group.bench_function("rule_index_per_round", |b| {
+        b.iter_batched(
+            build_reasoner,
+            |mut reasoner| reasoner.infer_new_facts_semi_naive_with_stats(true),
+            criterion::BatchSize::LargeInput,
+        )
+    });
+    group.finish();
+
+    let mut group = c.benchmark_group("semi_naive_independent_rules");
+    group.sample_size(10);
+    for (name, parallel) in [("sequential", false), ("parallel", true)] {
+        group.bench_function(name, |b| {
+            b.iter_batched(
+                || {
+                    let mut reasoner = build_independent_rules_reasoner();
+                    reasoner.parallel_rule_evaluation = parallel;
+                    reasoner
+                },
+                |mut reasoner| reasoner.infer_new_facts_semi_naive(),
+                criterion::BatchSize::LargeInput,
+            )
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, semi_naive_benchmark);
+criterion_main!(benches);
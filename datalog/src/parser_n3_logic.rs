@@ -131,6 +131,10 @@ fn parse_unresolved_rule(input: &str) -> IResult<&str, (Vec<UnresolvedTriple>, V
     Ok((input, (premise_triples, conclusion_triples)))
 }
 
+fn parse_terminated_rule(input: &str) -> IResult<&str, (Vec<UnresolvedTriple>, Vec<UnresolvedTriple>)> {
+    terminated(parse_unresolved_rule, opt(preceded(multispace0, tag(".")))).parse(input)
+}
+
 /// Parsing into unresolved terms
 pub fn parse_n3_rule<'a>(
     input: &'a str,
@@ -236,9 +240,10 @@ pub fn parse_n3_document<'a>(
         .map(|(k, v)| (k.to_string(), v.to_string()))
         .collect();
 
-    let (input, first_rule) = preceded(multispace0, parse_unresolved_rule).parse(input)?;
+    // Each rule may end with the `.` of an N3 statement
+    let (input, first_rule) = preceded(multispace0, parse_terminated_rule).parse(input)?;
     let (input, rest_rules) =
-        many0(preceded(multispace0, parse_unresolved_rule)).parse(input)?;
+        many0(preceded(multispace0, parse_terminated_rule)).parse(input)?;
 
     let (input, _) = multispace0.parse(input)?;
     if !input.is_empty() {
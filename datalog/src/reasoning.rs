@@ -13,18 +13,21 @@ pub mod backward_chaining;
 pub mod rules;
 pub mod repairs;
 pub mod helpers;
+pub mod derivation;
 
 use shared::dictionary::Dictionary;
 use shared::triple::Triple;
 use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use shared::index_manager::*;
 use shared::rule_index::RuleIndex;
-use shared::rule::Rule;
+use shared::rule::{stratify_rules, Rule};
 use shared::provenance::Provenance;
 use shared::tag_store::TagStore;
 use std::sync::Arc;
 use std::sync::RwLock;
 use crate::reasoning::rules::join_rule;
+use crate::reasoning::derivation::DerivationLog;
+use crate::reasoning::materialisation::infer_generic::InferenceBudget;
 
 // Logic part: Knowledge Graph
 
@@ -38,6 +41,12 @@ pub struct Reasoner {
     pub rule_index: RuleIndex,
     pub constraints: Vec<Rule>,
     pub probability_seeds: HashMap<Triple, f64>, // Input probabilities for provenance seeding
+    /// Records how inferred facts were derived; `None` unless tracking is enabled
+    pub derivation_log: Option<DerivationLog>,
+    /// Limits checked by `try_infer_new_facts_semi_naive`; `None` runs the rules to fixpoint
+    pub inference_budget: Option<InferenceBudget>,
+    /// Match the rules of a semi-naive round in parallel; on by default
+    pub parallel_rule_evaluation: bool,
 }
 
 pub fn convert_string_binding_to_u32(
@@ -53,6 +62,31 @@ pub fn convert_string_binding_to_u32(
     result
 }
 
+/// Splits a rule set into layers that the fixpoint loops evaluate in order.
+pub struct RuleStratifier;
+
+impl RuleStratifier {
+    /// Rule indices per layer, lowest first. A rule sits in a later layer than
+    /// every rule whose conclusions it negates, so those conclusions are fully
+    /// derived before it runs. Rules without negation form a single layer.
+    /// An unstratifiable set fails with the same error as `try_add_rule`,
+    /// which `rules` being public lets callers bypass.
+    pub fn strata(rules: &[Rule]) -> Result<Vec<Vec<usize>>, String> {
+        if rules.iter().all(|rule| rule.negative_premise.is_empty()) {
+            return Ok(vec![(0..rules.len()).collect()]);
+        }
+        stratify_rules(rules)
+    }
+
+    /// The rules of each layer from [`strata`](Self::strata), lowest first
+    pub fn stratify(rules: &[Rule]) -> Result<Vec<Vec<Rule>>, String> {
+        Ok(Self::strata(rules)?
+            .into_iter()
+            .map(|layer| layer.into_iter().map(|i| rules[i].clone()).collect())
+            .collect())
+    }
+}
+
 impl Reasoner {
     pub fn new() -> Self {
         Self {
@@ -62,6 +96,24 @@ impl Reasoner {
             rule_index: RuleIndex::new(),
             constraints: Vec::new(),
             probability_seeds: HashMap::new(),
+            derivation_log: None,
+            inference_budget: None,
+            parallel_rule_evaluation: true,
+        }
+    }
+
+    /// Record the rule and premises behind every fact inferred from now on
+    pub fn enable_derivation_tracking(&mut self) {
+        if self.derivation_log.is_none() {
+            self.derivation_log = Some(DerivationLog::new());
+        }
+    }
+
+    /// Derivation chain of an inferred fact, down to the asserted facts it rests on
+    pub fn why(&self, triple: &Triple) -> String {
+        match &self.derivation_log {
+            Some(log) => log.why(triple, &self.dictionary.read().unwrap()),
+            None => String::from("Derivation tracking is disabled; call enable_derivation_tracking before inferring\n"),
         }
     }
 
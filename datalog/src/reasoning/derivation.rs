@@ -0,0 +1,149 @@
+/*
+ * Copyright © 2026 Volodymyr Kadzhaia
+ * Copyright © 2026 Pieter Bonte
+ * KU Leuven — Stream Intelligence Lab, Belgium
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this file,
+ * you can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use shared::dictionary::Dictionary;
+use shared::rule::Rule;
+use shared::terms::{Term, TriplePattern};
+use shared::triple::Triple;
+use std::collections::HashMap;
+use std::fmt::Write;
+
+/// How an inferred fact was first derived
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Derivation {
+    /// Index of the rule in `DerivationLog::rules`
+    pub rule_id: usize,
+    /// Facts matched by the rule's premises, in premise order
+    pub premises: Vec<Triple>,
+}
+
+/// Provenance of inferred facts: which rule derived each one and from which facts.
+///
+/// Only the first derivation of a fact is kept. Its premises were all known
+/// before the fact itself, so following them always ends at asserted facts.
+#[derive(Debug, Clone, Default)]
+pub struct DerivationLog {
+    pub rules: Vec<Rule>,
+    pub derivations: HashMap<Triple, Derivation>,
+}
+
+impl DerivationLog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn get(&self, triple: &Triple) -> Option<&Derivation> {
+        self.derivations.get(triple)
+    }
+
+    pub fn len(&self) -> usize {
+        self.derivations.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.derivations.is_empty()
+    }
+
+    /// Record a derivation unless the fact already has one
+    pub fn record(&mut self, fact: Triple, rule_id: usize, premises: Vec<Triple>) {
+        self.derivations
+            .entry(fact)
+            .or_insert(Derivation { rule_id, premises });
+    }
+
+    /// Add the rules and derivations of `other`, renumbering its rules after ours
+    pub fn merge(&mut self, other: DerivationLog) {
+        let offset = self.rules.len();
+        self.rules.extend(other.rules);
+        for (fact, mut derivation) in other.derivations {
+            derivation.rule_id += offset;
+            self.derivations.entry(fact).or_insert(derivation);
+        }
+    }
+
+    /// Derivation chain of `triple`, one fact per line, premises indented
+    /// below the fact they support
+    pub fn why(&self, triple: &Triple, dict: &Dictionary) -> String {
+        if !self.derivations.contains_key(triple) {
+            return format!("{} was not inferred\n", format_triple(triple, dict));
+        }
+        let mut out = String::new();
+        self.write_chain(triple, dict, 0, &mut out);
+        out
+    }
+
+    fn write_chain(&self, triple: &Triple, dict: &Dictionary, depth: usize, out: &mut String) {
+        let indent = "  ".repeat(depth);
+        match self.derivations.get(triple) {
+            Some(derivation) => {
+                let rule = match self.rules.get(derivation.rule_id) {
+                    Some(rule) => format_rule(rule, dict),
+                    None => String::from("<unknown rule>"),
+                };
+                let _ = writeln!(
+                    out,
+                    "{}{} <- rule {}: {}",
+                    indent,
+                    format_triple(triple, dict),
+                    derivation.rule_id,
+                    rule
+                );
+                for premise in &derivation.premises {
+                    self.write_chain(premise, dict, depth + 1, out);
+                }
+            }
+            None => {
+                let _ = writeln!(out, "{}{} [asserted]", indent, format_triple(triple, dict));
+            }
+        }
+    }
+}
+
+fn decode(id: u32, dict: &Dictionary) -> String {
+    dict.decode(id).map(str::to_string).unwrap_or_else(|| format!("#{}", id))
+}
+
+fn format_triple(triple: &Triple, dict: &Dictionary) -> String {
+    format!(
+        "({}, {}, {})",
+        decode(triple.subject, dict),
+        decode(triple.predicate, dict),
+        decode(triple.object, dict)
+    )
+}
+
+fn format_term(term: &Term, dict: &Dictionary) -> String {
+    match term {
+        Term::Variable(v) => format!("?{}", v),
+        Term::Constant(c) => decode(*c, dict),
+        Term::QuotedTriple(qt) => format!(
+            "<< {} {} {} >>",
+            format_term(&qt.0, dict),
+            format_term(&qt.1, dict),
+            format_term(&qt.2, dict)
+        ),
+    }
+}
+
+fn format_patterns(patterns: &[TriplePattern], dict: &Dictionary) -> String {
+    patterns
+        .iter()
+        .map(|(s, p, o)| format!("({}, {}, {})", format_term(s, dict), format_term(p, dict), format_term(o, dict)))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn format_rule(rule: &Rule, dict: &Dictionary) -> String {
+    format!(
+        "{} => {}",
+        format_patterns(&rule.premise, dict),
+        format_patterns(&rule.conclusion, dict)
+    )
+}
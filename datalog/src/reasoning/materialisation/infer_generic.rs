@@ -2,10 +2,69 @@ use shared::dictionary::Dictionary;
 use shared::rule::Rule;
 use shared::triple::Triple;
 use std::collections::{HashMap, HashSet};
-use crate::reasoning::Reasoner;
+use std::fmt;
+use crate::reasoning::{Reasoner, RuleStratifier};
+use log::warn;
 
 pub type SolutionMapping = HashMap<String, u32>;
 
+/// Default for `InferenceBudget::max_rounds`
+pub const DEFAULT_MAX_INFERENCE_ROUNDS: usize = 10_000;
+
+/// Default for `InferenceBudget::max_derived_facts`
+pub const DEFAULT_MAX_DERIVED_FACTS: usize = 10_000_000;
+
+/// Limits on one materialisation run, so that rules which keep deriving
+/// facts fail instead of running until memory is exhausted
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct InferenceBudget {
+    /// Rounds that derive new facts, counted over all strata
+    pub max_rounds: usize,
+    /// New facts the run may add to the knowledge base
+    pub max_derived_facts: usize,
+}
+
+impl Default for InferenceBudget {
+    fn default() -> Self {
+        InferenceBudget {
+            max_rounds: DEFAULT_MAX_INFERENCE_ROUNDS,
+            max_derived_facts: DEFAULT_MAX_DERIVED_FACTS,
+        }
+    }
+}
+
+/// The limit of an `InferenceBudget` a materialisation ran into
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BudgetExceeded {
+    /// The rules still derived new facts after this many rounds
+    Rounds(usize),
+    /// The rules derived more than this many facts
+    DerivedFacts(usize),
+}
+
+impl fmt::Display for BudgetExceeded {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            BudgetExceeded::Rounds(limit) => {
+                write!(f, "rules still derived new facts after the limit of {} rounds", limit)
+            }
+            BudgetExceeded::DerivedFacts(limit) => {
+                write!(f, "rules derived more than the limit of {} facts", limit)
+            }
+        }
+    }
+}
+
+impl std::error::Error for BudgetExceeded {}
+
+/// Rounds and facts one materialisation run has used so far, carried from
+/// stratum to stratum
+#[derive(Debug, Default)]
+pub(crate) struct BudgetUsage {
+    rounds: usize,
+    derived_facts: usize,
+}
+
 pub trait InferenceStrategy {
 
     /// Applies a single round of inference within the materialisation algorithm.
@@ -23,8 +82,41 @@ pub trait InferenceStrategy {
 
 impl Reasoner {
 
-    /// Generic function that infers all derivable facts using a given strategy, e.g. SemiNaive, or Naive
+    /// Generic function that infers all derivable facts using a given strategy, e.g. SemiNaive, or Naive.
+    /// The layers from `RuleStratifier::stratify` each run to fixpoint, lowest first.
+    /// Unstratifiable rules infer nothing.
     pub fn infer_with_strategy<S: InferenceStrategy>(&mut self, mut strat: S) -> Vec<Triple> {
+        let layers = match RuleStratifier::stratify(&self.rules) {
+            Ok(layers) => layers,
+            Err(err) => {
+                warn!("{}; no rules were evaluated", err);
+                return Vec::new();
+            }
+        };
+        let mut inferred = Vec::new();
+        for layer in layers {
+            inferred.extend(self.infer_with_strategy_and_rules(&mut strat, &layer));
+        }
+        inferred
+    }
+
+    /// Same as `infer_with_strategy`, but only applies `rules` (e.g. one stratum)
+    /// and leaves the strategy with the caller so it can be inspected afterwards
+    pub fn infer_with_strategy_and_rules<S: InferenceStrategy>(&mut self, strat: &mut S, rules: &Vec<Rule>) -> Vec<Triple> {
+        // Without a budget there is no limit to run into
+        self.infer_within_budget(strat, rules, None, &mut BudgetUsage::default())
+            .unwrap_or_default()
+    }
+
+    /// `infer_with_strategy_and_rules` that stops with an error once `budget`
+    /// is used up. The facts derived until then stay in `index_manager`.
+    pub(crate) fn infer_within_budget<S: InferenceStrategy>(
+        &mut self,
+        strat: &mut S,
+        rules: &Vec<Rule>,
+        budget: Option<InferenceBudget>,
+        usage: &mut BudgetUsage,
+    ) -> Result<Vec<Triple>, BudgetExceeded> {
         // In each iteration, facts are added to this list. Use vector to preserve index for initial facts
         let mut all_facts: Vec<Triple> = self.index_manager.query(None, None, None);
         let mut known_facts: HashSet<Triple> = all_facts.iter().cloned().collect();
@@ -33,15 +125,27 @@ impl Reasoner {
         loop {
 
             let mut dict = self.dictionary.write().unwrap();
-            let mut inferred_facts_this_round = strat.infer_round(&mut dict, &self.rules, &all_facts, &known_facts);
+            let mut inferred_facts_this_round = strat.infer_round(&mut dict, rules, &all_facts, &known_facts);
 
             if inferred_facts_this_round.is_empty() {
                 break;
             }
+            if let Some(budget) = budget {
+                if usage.rounds >= budget.max_rounds {
+                    return Err(BudgetExceeded::Rounds(budget.max_rounds));
+                }
+            }
+            usage.rounds += 1;
 
             for fact in inferred_facts_this_round.drain() {
                 // Insert into known_facts first; if it was not present, also store it.
                 if !known_facts.contains(&fact) {
+                    if let Some(budget) = budget {
+                        if usage.derived_facts >= budget.max_derived_facts {
+                            return Err(BudgetExceeded::DerivedFacts(budget.max_derived_facts));
+                        }
+                    }
+                    usage.derived_facts += 1;
                     known_facts.insert(fact.clone()); // Necessary clone apparently
                     self.index_manager.insert(&fact);
                     all_facts.push(fact);
@@ -49,6 +153,6 @@ impl Reasoner {
             }
         }
 
-        all_facts.split_off(idx_before_inference)
+        Ok(all_facts.split_off(idx_before_inference))
     }
 }
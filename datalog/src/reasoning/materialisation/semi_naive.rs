@@ -1,36 +1,90 @@
+use rayon::prelude::*;
 use shared::dictionary::Dictionary;
 use shared::rule::Rule;
+use shared::rule_index::{RuleIndex, WILDCARD};
+use shared::terms::Term;
 use shared::triple::Triple;
 use std::collections::{BTreeMap, HashSet};
-use crate::reasoning::{convert_string_binding_to_u32, Reasoner};
-use crate::reasoning::materialisation::infer_generic::{SolutionMapping, InferenceStrategy};
+use crate::reasoning::{convert_string_binding_to_u32, Reasoner, RuleStratifier};
+use crate::reasoning::derivation::DerivationLog;
+use crate::reasoning::materialisation::infer_generic::{BudgetExceeded, BudgetUsage, InferenceBudget, SolutionMapping, InferenceStrategy};
 use crate::reasoning::materialisation::replace_variables_with_bound_values;
-use crate::reasoning::rules::{evaluate_filters, join_premise_with_hash_join};
+use crate::reasoning::rules::{evaluate_filters, evaluate_negation, join_premise_with_hash_join};
+use log::warn;
+
+/// Work done in one semi-naive round
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct SemiNaiveRound {
+    /// Facts derived in the previous round (all facts in the first round)
+    pub delta_size: usize,
+    /// Rules whose premises were joined against the delta
+    pub rules_evaluated: usize,
+    /// Premise positions joined against the delta
+    pub delta_joins: usize,
+}
 
 struct SemiNaiveStrategy {
     start_idx_for_delta: usize,
+    /// Premise index over this strategy's rules; `None` evaluates every rule each round
+    rule_index: Option<RuleIndex>,
+    rounds: Vec<SemiNaiveRound>,
+    /// Reasoner-wide ids of this strategy's rules, used when recording derivations
+    rule_ids: Vec<usize>,
+    derivations: Option<DerivationLog>,
+    /// Match the round's candidate rules on Rayon
+    parallel: bool,
 }
 
 impl SemiNaiveStrategy {
 
-    /// Makes use of facts inferred from last round (delta) for better efficiency
-    fn find_premise_solutions(&mut self, dict: &Dictionary, rule: &Rule, all_facts: &Vec<Triple>, delta_facts: &[Triple]) -> Vec<SolutionMapping> {
+    fn new(rules: &[Rule], rule_ids: Vec<usize>, use_rule_index: bool, track_derivations: bool, parallel: bool) -> Self {
+        let rule_index = use_rule_index.then(|| {
+            let mut index = RuleIndex::new();
+            for (rule_id, rule) in rules.iter().enumerate() {
+                for premise in &rule.premise {
+                    index.insert_premise_pattern(premise, rule_id);
+                }
+            }
+            index
+        });
+        SemiNaiveStrategy {
+            start_idx_for_delta: 0,
+            rule_index,
+            rounds: Vec::new(),
+            rule_ids,
+            derivations: track_derivations.then(DerivationLog::new),
+            parallel,
+        }
+    }
+
+    /// Makes use of facts inferred from last round (delta) for better efficiency.
+    /// Also returns the number of premise positions joined against the delta.
+    fn find_premise_solutions(dict: &Dictionary, rule: &Rule, all_facts: &[Triple], delta_facts: &[Triple], delta_predicates: Option<&HashSet<u32>>) -> (Vec<SolutionMapping>, usize) {
 
         let nr_premises = rule.premise.len();
         let mut results = Vec::new();
+        let mut delta_joins = 0;
 
         for i in 0..nr_premises {
+            // A premise whose predicate never occurs in the delta cannot match it
+            if let (Some(predicates), Term::Constant(p)) = (delta_predicates, &rule.premise[i].1) {
+                if !predicates.contains(p) {
+                    continue;
+                }
+            }
+            delta_joins += 1;
+
             let mut current_bindings = vec![BTreeMap::new()];
 
             // At least one premise should be satisfied by facts derived from last round (if not, then you simply derive the same things)
-            current_bindings = join_premise_with_hash_join(&rule.premise[i], &delta_facts, current_bindings, dict);
+            current_bindings = join_premise_with_hash_join(&rule.premise[i], delta_facts, current_bindings, dict);
 
             // Join remaining premises with all facts (includes delta facts as well)
             for j in 0..nr_premises {
                 if j == i {
                     continue;
                 }
-                current_bindings = join_premise_with_hash_join(&rule.premise[j], &all_facts, current_bindings, dict);
+                current_bindings = join_premise_with_hash_join(&rule.premise[j], all_facts, current_bindings, dict);
                 if current_bindings.is_empty() {
                     break;
                 }
@@ -43,7 +97,30 @@ impl SemiNaiveStrategy {
             }
         }
 
-        results
+        (results, delta_joins)
+    }
+
+    /// Bindings under which `rule` fires this round: its premises match with at
+    /// least one delta fact, and its filters and negated premises hold
+    fn rule_solutions(dict: &Dictionary, rule: &Rule, all_facts: &[Triple], delta_facts: &[Triple], delta_predicates: Option<&HashSet<u32>>, known_facts: &HashSet<Triple>) -> (Vec<SolutionMapping>, usize) {
+        let (mut binding_sets, delta_joins) = Self::find_premise_solutions(dict, rule, all_facts, delta_facts, delta_predicates);
+        // Stratification guarantees negated atoms were fully derived in an earlier stratum
+        binding_sets.retain(|binding_set| {
+            evaluate_filters(binding_set, &rule.filters, dict)
+                && evaluate_negation(binding_set, &rule.negative_premise, known_facts)
+        });
+        (binding_sets, delta_joins)
+    }
+
+    /// Rules with at least one premise that can match a delta predicate
+    fn candidate_rules(index: &RuleIndex, delta_predicates: &HashSet<u32>) -> Vec<usize> {
+        let mut candidates = index.query_candidate_rules(None, Some(WILDCARD), None);
+        for &predicate in delta_predicates {
+            candidates.extend(index.query_candidate_rules(None, Some(predicate), None));
+        }
+        let mut candidates: Vec<usize> = candidates.into_iter().collect();
+        candidates.sort_unstable();
+        candidates
     }
 
 }
@@ -58,24 +135,59 @@ impl InferenceStrategy for SemiNaiveStrategy {
             let delta_facts = &all_facts[self.start_idx_for_delta..end_idx_for_delta]; // Take derived facts from last round and use as delta
             self.start_idx_for_delta = end_idx_for_delta; // Update the pointer for the next round
 
-            // Loop over each rule
-            for rule in rules {
-                // These are all bindings such that the premise is satisfied for the given rule
-                let binding_sets = self.find_premise_solutions(dictionary, rule, all_facts, delta_facts);
+            // Only rules indexed under a predicate of the delta can fire this round
+            let delta_predicates: Option<HashSet<u32>> = self
+                .rule_index
+                .as_ref()
+                .map(|_| delta_facts.iter().map(|t| t.predicate).collect());
+            let rule_ids: Vec<usize> = match (&self.rule_index, &delta_predicates) {
+                (Some(index), Some(predicates)) => Self::candidate_rules(index, predicates),
+                _ => (0..rules.len()).collect(),
+            };
+            self.rounds.push(SemiNaiveRound {
+                delta_size: delta_facts.len(),
+                rules_evaluated: rule_ids.len(),
+                delta_joins: 0,
+            });
+
+            // Every rule reads the facts as they stood at the start of the round, so
+            // the candidate rules can be matched independently of each other. A rule
+            // fed by another's conclusion sees it in the next round's delta, as it
+            // would sequentially; the solutions are applied in rule order below.
+            let dict: &Dictionary = dictionary;
+            let solve = |&rule_id: &usize| {
+                Self::rule_solutions(dict, &rules[rule_id], all_facts, delta_facts, delta_predicates.as_ref(), known_facts)
+            };
+            let solutions: Vec<(Vec<SolutionMapping>, usize)> = if self.parallel && rule_ids.len() > 1 {
+                rule_ids.par_iter().map(solve).collect()
+            } else {
+                rule_ids.iter().map(solve).collect()
+            };
 
-                // For each binding that satisfies the premises of the rule, get to the conclusion and apply bindings
+            for (rule_id, (binding_sets, delta_joins)) in rule_ids.into_iter().zip(solutions) {
+                let rule = &rules[rule_id];
+                if let Some(round) = self.rounds.last_mut() {
+                    round.delta_joins += delta_joins;
+                }
+
+                // For each binding that satisfies the rule, get to the conclusion and apply bindings
                 for binding_set in &binding_sets {
-                    if evaluate_filters(&binding_set, &rule.filters, dictionary) {
-                        // Loop over each conclusion of the rule, since for the current binding,
-                        // the conclusions of the rule can be inferred (because premises are met)
-                        for conclusion in &rule.conclusion {
-                            
-                            let inferred_fact =
-                                replace_variables_with_bound_values(conclusion, binding_set, dictionary);
-
-                            if !known_facts.contains(&inferred_fact) {
-                                inferred_facts_this_round.insert(inferred_fact);
+                    // Loop over each conclusion of the rule, since for the current binding,
+                    // the conclusions of the rule can be inferred (because premises are met)
+                    for conclusion in &rule.conclusion {
+                        let inferred_fact =
+                            replace_variables_with_bound_values(conclusion, binding_set, dictionary);
+
+                        if !known_facts.contains(&inferred_fact) {
+                            if let Some(log) = self.derivations.as_mut() {
+                                let premises = rule
+                                    .premise
+                                    .iter()
+                                    .map(|premise| replace_variables_with_bound_values(premise, binding_set, dictionary))
+                                    .collect();
+                                log.record(inferred_fact.clone(), self.rule_ids[rule_id], premises);
                             }
+                            inferred_facts_this_round.insert(inferred_fact);
                         }
                     }
                 }
@@ -86,7 +198,67 @@ impl InferenceStrategy for SemiNaiveStrategy {
 }
 
 impl Reasoner {
+    /// Semi-naive materialisation, evaluated stratum by stratum when rules use negation.
+    /// Each round only re-evaluates rules whose premises can match the previous round's delta.
+    /// With derivation tracking enabled, `derivation_log` records how each new fact was derived.
     pub fn infer_new_facts_semi_naive(&mut self) -> Vec<Triple> {
-        self.infer_with_strategy(SemiNaiveStrategy { start_idx_for_delta: 0 })
+        self.infer_new_facts_semi_naive_with_stats(true).0
+    }
+
+    /// Semi-naive materialisation that also reports the work done per round.
+    /// With `use_rule_index` off, every rule is joined against every delta,
+    /// which is useful as a baseline when benchmarking.
+    pub fn infer_new_facts_semi_naive_with_stats(&mut self, use_rule_index: bool) -> (Vec<Triple>, Vec<SemiNaiveRound>) {
+        // Without a budget there is no limit to run into
+        self.semi_naive_within_budget(use_rule_index, None).unwrap_or_default()
+    }
+
+    /// `infer_new_facts_semi_naive` limited by `inference_budget`. Rules that
+    /// are still deriving facts when the budget runs out fail with the limit
+    /// they hit; the reasoner then holds a partial materialisation.
+    pub fn try_infer_new_facts_semi_naive(&mut self) -> Result<Vec<Triple>, BudgetExceeded> {
+        let budget = self.inference_budget;
+        self.semi_naive_within_budget(true, budget).map(|(inferred, _)| inferred)
+    }
+
+    fn semi_naive_within_budget(
+        &mut self,
+        use_rule_index: bool,
+        budget: Option<InferenceBudget>,
+    ) -> Result<(Vec<Triple>, Vec<SemiNaiveRound>), BudgetExceeded> {
+        let strata = match RuleStratifier::strata(&self.rules) {
+            Ok(strata) => strata,
+            Err(err) => {
+                warn!("{}; no rules were evaluated", err);
+                return Ok(Default::default());
+            }
+        };
+
+        let mut inferred = Vec::new();
+        let mut rounds = Vec::new();
+        let mut usage = BudgetUsage::default();
+        let track_derivations = self.derivation_log.is_some();
+        for stratum in strata {
+            let rules: Vec<Rule> = stratum.iter().map(|&i| self.rules[i].clone()).collect();
+            let mut strategy = SemiNaiveStrategy::new(
+                &rules,
+                stratum,
+                use_rule_index,
+                track_derivations,
+                self.parallel_rule_evaluation,
+            );
+            inferred.extend(self.infer_within_budget(&mut strategy, &rules, budget, &mut usage)?);
+            rounds.append(&mut strategy.rounds);
+
+            if let (Some(log), Some(stratum_log)) = (self.derivation_log.as_mut(), strategy.derivations) {
+                for (fact, derivation) in stratum_log.derivations {
+                    log.record(fact, derivation.rule_id, derivation.premises);
+                }
+            }
+        }
+        if let Some(log) = self.derivation_log.as_mut() {
+            log.rules = self.rules.clone();
+        }
+        Ok((inferred, rounds))
     }
 }
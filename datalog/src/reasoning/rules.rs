@@ -164,6 +164,28 @@ pub fn evaluate_filters(
     true
 }
 
+/// Negation-as-failure: true when no negated atom, instantiated with
+/// `bindings`, is among `known_facts`.
+pub fn evaluate_negation(
+    bindings: &HashMap<String, u32>,
+    negative_premise: &[TriplePattern],
+    known_facts: &HashSet<Triple>,
+) -> bool {
+    let resolve = |term: &Term| match term {
+        Term::Variable(v) => bindings.get(v).copied(),
+        Term::Constant(c) => Some(*c),
+        Term::QuotedTriple(_) => None,
+    };
+    negative_premise.iter().all(|(s, p, o)| {
+        match (resolve(s), resolve(p), resolve(o)) {
+            (Some(subject), Some(predicate), Some(object)) => {
+                !known_facts.contains(&Triple { subject, predicate, object })
+            }
+            _ => true,
+        }
+    })
+}
+
 pub fn join_premise_with_hash_join(
     premise: &TriplePattern,
     all_facts: &[Triple],
@@ -183,18 +205,27 @@ impl Reasoner {
     /// Add a dynamic rule to the graph.
     ///
     /// Panics if the rule has unsafe negation (a variable in `negative_premise`
-    /// that is not bound by `premise`). For a non-panicking version use [`try_add_rule`].
+    /// that is not bound by `premise`) or unstratifiable negation. For a
+    /// non-panicking version use [`try_add_rule`].
     ///
     /// [`try_add_rule`]: Self::try_add_rule
     pub fn add_rule(&mut self, rule: Rule) {
         self.try_add_rule(rule).expect("rule safety check failed");
     }
 
-    /// Add a dynamic rule to the graph, returning `Err` if it violates safety.
+    /// Add a dynamic rule to the graph, returning `Err` if it violates safety
+    /// or makes the rule set unstratifiable.
     ///
     /// Safety requirement: every variable in `negative_premise` must appear in `premise`.
+    /// Stratification requirement: no rule may depend negatively on its own
+    /// conclusions, directly or through other rules.
     pub fn try_add_rule(&mut self, rule: Rule) -> Result<(), String> {
         shared::rule::check_rule_safety(&rule)?;
+        if !rule.negative_premise.is_empty() || self.rules.iter().any(|r| !r.negative_premise.is_empty()) {
+            let mut candidate = self.rules.clone();
+            candidate.push(rule.clone());
+            shared::rule::stratify_rules(&candidate)?;
+        }
         let rule_id = self.rules.len();
         self.rules.push(rule.clone());
         for prem in &rule.premise {
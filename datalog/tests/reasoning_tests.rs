@@ -1,4 +1,5 @@
-use datalog::reasoning::Reasoner;
+use datalog::reasoning::{Reasoner, RuleStratifier};
+use datalog::reasoning::materialisation::infer_generic::{BudgetExceeded, InferenceBudget};
 use shared::rule::{FilterCondition, Rule};
 use shared::terms::Term;
 use shared::provenance::{AddMultProbability, MinMaxProbability, BooleanProvenance, Provenance};
@@ -422,6 +423,99 @@ fn fc_uncle_derived() {
     assert!(!inferred(&mut r, "A", "uncle", "C"), "A (parent) should not also be uncle of C");
 }
 
+#[test]
+fn fc_budget_stops_rules_that_keep_deriving() {
+    fn chain_reasoner(len: usize) -> Reasoner {
+        let mut r = Reasoner::new();
+        for i in 0..len {
+            r.add_abox_triple(&format!("N{}", i), "next", &format!("N{}", i + 1));
+        }
+        let next = enc(&r, "next");
+        r.add_rule(rule(
+            vec![
+                (Term::Variable("X".into()), Term::Constant(next), Term::Variable("Y".into())),
+                (Term::Variable("Y".into()), Term::Constant(next), Term::Variable("Z".into())),
+            ],
+            vec![(Term::Variable("X".into()), Term::Constant(next), Term::Variable("Z".into()))],
+        ));
+        r
+    }
+
+    // Closing a 64-link chain takes 6 productive rounds and derives 2016 facts
+    let mut r = chain_reasoner(64);
+    r.inference_budget = Some(InferenceBudget { max_rounds: 5, max_derived_facts: usize::MAX });
+    assert_eq!(r.try_infer_new_facts_semi_naive(), Err(BudgetExceeded::Rounds(5)));
+
+    let mut r = chain_reasoner(64);
+    r.inference_budget = Some(InferenceBudget { max_rounds: 100, max_derived_facts: 1000 });
+    assert_eq!(r.try_infer_new_facts_semi_naive(), Err(BudgetExceeded::DerivedFacts(1000)));
+
+    let mut r = chain_reasoner(64);
+    r.inference_budget = Some(InferenceBudget { max_rounds: 6, max_derived_facts: 2016 });
+    assert_eq!(r.try_infer_new_facts_semi_naive().map(|facts| facts.len()), Ok(2016));
+
+    // Without a budget the same rules run to fixpoint
+    let mut r = chain_reasoner(64);
+    assert_eq!(r.try_infer_new_facts_semi_naive().map(|facts| facts.len()), Ok(2016));
+}
+
+#[test]
+fn fc_parallel_rules_match_sequential_evaluation() {
+    fn reasoner(parallel: bool) -> Reasoner {
+        let mut r = Reasoner::new();
+        r.parallel_rule_evaluation = parallel;
+        for i in 0..8 {
+            r.add_abox_triple(&format!("N{}", i), "next", &format!("N{}", i + 1));
+            r.add_abox_triple(&format!("N{}", i), "worksFor", "Corp");
+        }
+        let next = enc(&r, "next");
+        let reaches = enc(&r, "reaches");
+        let works_for = enc(&r, "worksFor");
+        let employed = enc(&r, "employed");
+        let colleague = enc(&r, "colleague");
+        let var = |name: &str| Term::Variable(name.into());
+        // Independent of each other...
+        r.add_rule(rule(
+            vec![(var("X"), Term::Constant(next), var("Y"))],
+            vec![(var("X"), Term::Constant(reaches), var("Y"))],
+        ));
+        r.add_rule(rule(
+            vec![(var("X"), Term::Constant(works_for), var("Y"))],
+            vec![(var("X"), Term::Constant(employed), var("Y"))],
+        ));
+        // ...while these feed on the conclusions above
+        r.add_rule(rule(
+            vec![
+                (var("X"), Term::Constant(reaches), var("Y")),
+                (var("Y"), Term::Constant(next), var("Z")),
+            ],
+            vec![(var("X"), Term::Constant(reaches), var("Z"))],
+        ));
+        r.add_rule(rule(
+            vec![
+                (var("X"), Term::Constant(employed), var("C")),
+                (var("Y"), Term::Constant(employed), var("C")),
+            ],
+            vec![(var("X"), Term::Constant(colleague), var("Y"))],
+        ));
+        r
+    }
+
+    let (mut sequential, sequential_rounds) = reasoner(false).infer_new_facts_semi_naive_with_stats(true);
+    let (mut parallel, parallel_rounds) = reasoner(true).infer_new_facts_semi_naive_with_stats(true);
+    sequential.sort_by_key(|t| (t.subject, t.predicate, t.object));
+    parallel.sort_by_key(|t| (t.subject, t.predicate, t.object));
+    assert_eq!(parallel, sequential);
+    assert_eq!(parallel_rounds, sequential_rounds);
+    // 36 `reaches` facts, 8 `employed` and 64 `colleague`
+    assert_eq!(parallel.len(), 108);
+
+    let mut r = reasoner(true);
+    r.infer_new_facts_semi_naive();
+    assert!(inferred(&mut r, "N0", "reaches", "N8"));
+    assert!(inferred(&mut r, "N3", "colleague", "N5"));
+}
+
 // Backward chaining
 
 #[test]
@@ -1358,6 +1452,257 @@ fn unsafe_negation_rejected() {
         "error message must mention unsafe negation");
 }
 
+/// Semi-naive: NOT suspended blocks only the suspended member.
+#[test]
+fn naf_semi_naive_active_unless_suspended() {
+    let mut r = Reasoner::new();
+    r.add_abox_triple("alice", "member", "yes");
+    r.add_abox_triple("bob", "member", "yes");
+    r.add_abox_triple("bob", "suspended", "yes");
+
+    let member    = enc(&r, "member");
+    let suspended = enc(&r, "suspended");
+    let active    = enc(&r, "active");
+    let yes       = enc(&r, "yes");
+
+    // RULE: member(?x, yes), NOT suspended(?x, yes) -> active(?x, yes)
+    r.add_rule(naf_rule(
+        vec![(Term::Variable("x".into()), Term::Constant(member),    Term::Constant(yes))],
+        vec![(Term::Variable("x".into()), Term::Constant(suspended), Term::Constant(yes))],
+        vec![(Term::Variable("x".into()), Term::Constant(active),    Term::Constant(yes))],
+    ));
+
+    r.infer_new_facts_semi_naive();
+
+    assert!(inferred(&mut r, "alice", "active", "yes"));
+    assert!(!inferred(&mut r, "bob", "active", "yes"));
+}
+
+/// Semi-naive: a negated predicate derived by another rule is fully
+/// materialised before the NAF rule runs, regardless of rule order.
+#[test]
+fn naf_semi_naive_waits_for_lower_stratum() {
+    let mut r = Reasoner::new();
+    r.add_abox_triple("carol", "member", "yes");
+    r.add_abox_triple("carol", "flagged", "yes");
+    r.add_abox_triple("dave", "member", "yes");
+
+    let member    = enc(&r, "member");
+    let flagged   = enc(&r, "flagged");
+    let suspended = enc(&r, "suspended");
+    let active    = enc(&r, "active");
+    let yes       = enc(&r, "yes");
+
+    r.add_rule(naf_rule(
+        vec![(Term::Variable("x".into()), Term::Constant(member),    Term::Constant(yes))],
+        vec![(Term::Variable("x".into()), Term::Constant(suspended), Term::Constant(yes))],
+        vec![(Term::Variable("x".into()), Term::Constant(active),    Term::Constant(yes))],
+    ));
+    r.add_rule(rule(
+        vec![(Term::Variable("x".into()), Term::Constant(flagged),   Term::Constant(yes))],
+        vec![(Term::Variable("x".into()), Term::Constant(suspended), Term::Constant(yes))],
+    ));
+
+    r.infer_new_facts_semi_naive();
+
+    assert!(inferred(&mut r, "carol", "suspended", "yes"));
+    assert!(!inferred(&mut r, "carol", "active", "yes"),
+        "carol is suspended by a derived fact and must not become active");
+    assert!(inferred(&mut r, "dave", "active", "yes"));
+}
+
+/// Naive: the fixpoint runs layer by layer, so the NAF rule sees the
+/// negated predicate only after the lower layer has derived all of it.
+#[test]
+fn naf_naive_waits_for_lower_stratum() {
+    let mut r = Reasoner::new();
+    r.add_abox_triple("carol", "member", "yes");
+    r.add_abox_triple("carol", "flagged", "yes");
+    r.add_abox_triple("dave", "member", "yes");
+
+    let member    = enc(&r, "member");
+    let flagged   = enc(&r, "flagged");
+    let suspended = enc(&r, "suspended");
+    let active    = enc(&r, "active");
+    let yes       = enc(&r, "yes");
+
+    r.add_rule(naf_rule(
+        vec![(Term::Variable("x".into()), Term::Constant(member),    Term::Constant(yes))],
+        vec![(Term::Variable("x".into()), Term::Constant(suspended), Term::Constant(yes))],
+        vec![(Term::Variable("x".into()), Term::Constant(active),    Term::Constant(yes))],
+    ));
+    r.add_rule(rule(
+        vec![(Term::Variable("x".into()), Term::Constant(flagged),   Term::Constant(yes))],
+        vec![(Term::Variable("x".into()), Term::Constant(suspended), Term::Constant(yes))],
+    ));
+
+    r.infer_new_facts();
+
+    assert!(inferred(&mut r, "carol", "suspended", "yes"));
+    assert!(!inferred(&mut r, "carol", "active", "yes"),
+        "carol is suspended by a derived fact and must not become active");
+    assert!(inferred(&mut r, "dave", "active", "yes"));
+}
+
+/// RuleStratifier puts a rule after every rule whose conclusions it negates,
+/// and keeps positive dependencies in the same layer.
+#[test]
+fn rule_stratifier_layers_negation_after_its_producers() {
+    let r = Reasoner::new();
+    let flagged   = enc(&r, "flagged");
+    let suspended = enc(&r, "suspended");
+    let member    = enc(&r, "member");
+    let active    = enc(&r, "active");
+    let yes       = enc(&r, "yes");
+    let x = || Term::Variable("x".into());
+
+    let active_rule = naf_rule(
+        vec![(x(), Term::Constant(member),    Term::Constant(yes))],
+        vec![(x(), Term::Constant(suspended), Term::Constant(yes))],
+        vec![(x(), Term::Constant(active),    Term::Constant(yes))],
+    );
+    let suspended_rule = rule(
+        vec![(x(), Term::Constant(flagged),   Term::Constant(yes))],
+        vec![(x(), Term::Constant(suspended), Term::Constant(yes))],
+    );
+    let flagged_rule = rule(
+        vec![(x(), Term::Constant(member),  Term::Constant(yes))],
+        vec![(x(), Term::Constant(flagged), Term::Constant(yes))],
+    );
+    let rules = vec![active_rule, suspended_rule, flagged_rule];
+
+    assert_eq!(RuleStratifier::strata(&rules), Ok(vec![vec![1, 2], vec![0]]));
+    let layers = RuleStratifier::stratify(&rules).unwrap();
+    assert_eq!(layers.len(), 2);
+    assert_eq!(layers[1][0].negative_premise, rules[0].negative_premise);
+
+    // Without negation every rule shares one layer
+    assert_eq!(RuleStratifier::strata(&rules[1..]), Ok(vec![vec![0, 1]]));
+}
+
+/// try_add_rule rejects negation through a recursive cycle.
+#[test]
+fn unstratifiable_negation_rejected() {
+    let mut r = Reasoner::new();
+
+    let member    = enc(&r, "member");
+    let suspended = enc(&r, "suspended");
+    let active    = enc(&r, "active");
+    let yes       = enc(&r, "yes");
+
+    // active(?x) :- member(?x), NOT suspended(?x)
+    r.add_rule(naf_rule(
+        vec![(Term::Variable("x".into()), Term::Constant(member),    Term::Constant(yes))],
+        vec![(Term::Variable("x".into()), Term::Constant(suspended), Term::Constant(yes))],
+        vec![(Term::Variable("x".into()), Term::Constant(active),    Term::Constant(yes))],
+    ));
+
+    // suspended(?x) :- active(?x) closes a cycle through negation
+    let result = r.try_add_rule(rule(
+        vec![(Term::Variable("x".into()), Term::Constant(active),    Term::Constant(yes))],
+        vec![(Term::Variable("x".into()), Term::Constant(suspended), Term::Constant(yes))],
+    ));
+    assert!(result.is_err(), "negative cycle must be rejected by try_add_rule");
+    assert!(result.unwrap_err().contains("unstratifiable negation"));
+    assert_eq!(r.rules.len(), 1, "rejected rule must not be added");
+
+    // Pushed past try_add_rule, the set cannot be split into layers
+    r.rules.push(rule(
+        vec![(Term::Variable("x".into()), Term::Constant(active),    Term::Constant(yes))],
+        vec![(Term::Variable("x".into()), Term::Constant(suspended), Term::Constant(yes))],
+    ));
+    assert!(RuleStratifier::strata(&r.rules).unwrap_err().contains("unstratifiable negation"));
+    r.add_abox_triple("alice", "member", "yes");
+    assert!(r.infer_new_facts_semi_naive().is_empty());
+}
+
+/// Semi-naive with the rule index only re-evaluates rules that can match the delta.
+#[test]
+fn semi_naive_rule_index_skips_unaffected_rules() {
+    let build = || {
+        let mut r = Reasoner::new();
+        for i in 0..6 {
+            r.add_abox_triple(&format!("n{}", i), "edge", &format!("n{}", i + 1));
+        }
+        let edge = enc(&r, "edge");
+        let path = enc(&r, "path");
+        let x = || Term::Variable("x".into());
+        let y = || Term::Variable("y".into());
+        let z = || Term::Variable("z".into());
+        r.add_rule(rule(vec![(x(), Term::Constant(edge), y())], vec![(x(), Term::Constant(path), y())]));
+        r.add_rule(rule(
+            vec![(x(), Term::Constant(path), y()), (y(), Term::Constant(edge), z())],
+            vec![(x(), Term::Constant(path), z())],
+        ));
+        // Rules over predicates that never occur in the data
+        for i in 0..10 {
+            let from = enc(&r, &format!("unused{}", i));
+            let to = enc(&r, &format!("derived{}", i));
+            r.add_rule(rule(vec![(x(), Term::Constant(from), y())], vec![(x(), Term::Constant(to), y())]));
+        }
+        r
+    };
+
+    let mut indexed = build();
+    let (mut indexed_facts, indexed_rounds) = indexed.infer_new_facts_semi_naive_with_stats(true);
+    let mut baseline = build();
+    let (mut baseline_facts, baseline_rounds) = baseline.infer_new_facts_semi_naive_with_stats(false);
+
+    indexed_facts.sort();
+    baseline_facts.sort();
+    assert_eq!(indexed_facts, baseline_facts);
+    assert_eq!(indexed_facts.len(), 21, "transitive closure of a 6-edge chain");
+    assert!(inferred(&mut indexed, "n0", "path", "n6"));
+
+    assert_eq!(indexed_rounds.len(), baseline_rounds.len());
+    for (with_index, without) in indexed_rounds.iter().zip(&baseline_rounds) {
+        assert_eq!(with_index.delta_size, without.delta_size);
+        assert!(with_index.rules_evaluated < without.rules_evaluated);
+        assert!(with_index.delta_joins < without.delta_joins);
+    }
+}
+
+/// With derivation tracking on, each inferred fact records its rule and premises.
+#[test]
+fn semi_naive_records_derivations() {
+    let mut r = Reasoner::new();
+    r.add_abox_triple("A", "parent", "B");
+    r.add_abox_triple("B", "parent", "C");
+
+    let parent = enc(&r, "parent");
+    let ancestor = enc(&r, "ancestor");
+    let x = || Term::Variable("x".into());
+    let y = || Term::Variable("y".into());
+    let z = || Term::Variable("z".into());
+    r.add_rule(rule(vec![(x(), Term::Constant(parent), y())], vec![(x(), Term::Constant(ancestor), y())]));
+    r.add_rule(rule(
+        vec![(x(), Term::Constant(parent), y()), (y(), Term::Constant(ancestor), z())],
+        vec![(x(), Term::Constant(ancestor), z())],
+    ));
+
+    r.enable_derivation_tracking();
+    let facts = r.infer_new_facts_semi_naive();
+
+    let a_anc_c = r.query_abox(Some("A"), Some("ancestor"), Some("C"))[0].clone();
+    let a_par_b = r.query_abox(Some("A"), Some("parent"), Some("B"))[0].clone();
+    let b_anc_c = r.query_abox(Some("B"), Some("ancestor"), Some("C"))[0].clone();
+    let log = r.derivation_log.as_ref().unwrap();
+    assert_eq!(log.len(), facts.len());
+    let derivation = log.get(&a_anc_c).unwrap();
+    assert_eq!(derivation.rule_id, 1);
+    assert_eq!(derivation.premises, vec![a_par_b.clone(), b_anc_c]);
+    assert!(log.get(&a_par_b).is_none(), "asserted facts have no derivation");
+
+    let why = r.why(&a_anc_c);
+    let lines: Vec<&str> = why.lines().collect();
+    assert_eq!(lines.len(), 4, "{}", why);
+    assert!(lines[0].starts_with("(A, ancestor, C) <- rule 1"));
+    assert_eq!(lines[1], "  (A, parent, B) [asserted]");
+    assert!(lines[2].starts_with("  (B, ancestor, C) <- rule 0"));
+    assert_eq!(lines[3], "    (B, parent, C) [asserted]");
+    assert!(r.why(&a_par_b).contains("was not inferred"));
+}
+
 // ─── Phase 2 WMC NAF integration tests ──────────────────────────────────────
 
 /// WmcProvenance: independent seeds, active(P=0.8) AND NOT blocked(P=0.3).
@@ -8,24 +8,36 @@
  * you can obtain one at https://mozilla.org/MPL/2.0/.
  */
 
-use datalog::parser_n3_logic::parse_n3_rule;
-use datalog::reasoning::Reasoner;
-use kolibrie::execute_query::{execute_query, execute_query_rayon_parallel2_volcano};
-use kolibrie::parser::process_rule_definition;
+mod metrics;
+mod websocket;
+
+use kolibrie::compression::{decompress, read_rdf_file, Compression};
+use kolibrie::execute_query::{
+    execute_query_with_context, execute_read_query, query_kind, read_prepared, QueryContext, QueryKind,
+};
+use kolibrie::parser::try_process_rule_definition;
+use kolibrie::rdf_format::detect_rdf_format;
 use kolibrie::rsp_engine::{
-    OperationMode, QueryExecutionMode, RSPBuilder, ResultConsumer, SimpleR2R,
+    OperationMode, QueryExecutionMode, RSPBuilder, ResultConsumer, RspBuildError, SimpleR2R,
 };
-use kolibrie::sparql_database::SparqlDatabase;
+use kolibrie::execute_query::query_result_variables;
+use kolibrie::custom_error::SparqlError;
+use kolibrie::sparql_database::{SharedSparqlDatabase, SparqlDatabase};
+use kolibrie::sparql_results::ResultFormat;
+use kolibrie::streamertail_optimizer::{OperatorProfile, QueryProfiler};
+use datalog::reasoning::materialisation::infer_generic::InferenceBudget;
+use log::{debug, error, info, warn};
+use metrics::METRICS;
 use serde::{Deserialize, Serialize};
 use shared::triple::Triple;
 use std::collections::HashMap;
 use std::io::{self, Read, Write};
 use std::net::{TcpListener, TcpStream};
 use std::sync::atomic::{AtomicU64, Ordering};
-use std::sync::mpsc::{self, Sender};
-use std::sync::{Arc, Mutex};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex, RwLock};
 use std::thread;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 
 // ── Session state for persistent RSP engines ────────────────────────────────
 
@@ -33,19 +45,76 @@ struct EngineSession {
     engine: kolibrie::rsp_engine::RSPEngine<Triple, Vec<(String, String)>>,
     /// Lazily set when the SSE client connects.
     sse_sender: Arc<Mutex<Option<Sender<String>>>>,
+    /// Result rows the consumer has forwarded to the SSE client.
+    emitted_rows: Arc<AtomicU64>,
+    /// Last push, SSE connection or write to the SSE client; idle sessions
+    /// are dropped by the reaper.
+    last_activity: Instant,
+}
+
+impl EngineSession {
+    fn touch(&mut self) {
+        self.last_activity = Instant::now();
+    }
+}
+
+/// Live RSP sessions. The map is only write-locked to add or remove a
+/// session; each session has its own lock, so pushes to different sessions
+/// run side by side.
+type Sessions = Arc<RwLock<HashMap<String, Arc<Mutex<EngineSession>>>>>;
+
+/// Default dataset targeted by the SPARQL 1.1 Protocol `/query` and `/update`
+/// operations and by JSON `/query` requests that post no RDF. Preloaded from
+/// `--dataset FILE` at startup.
+type Dataset = SharedSparqlDatabase;
+
+/// Run `query` on the server dataset under its read lock, so queries run side
+/// by side and only `/update` takes the write lock. Queries that would write
+/// to the dataset (updates posted as queries, neural relation declarations)
+/// are not run and yield `None`; callers answer them with
+/// [`UPDATE_ON_QUERY_PATH`].
+fn query_dataset(
+    query: &str,
+    dataset: &Dataset,
+    context: &QueryContext,
+) -> Result<Option<Vec<Vec<String>>>, SparqlError> {
+    let database = read_prepared(dataset)?;
+    match query_kind(query, &database)? {
+        QueryKind::Read => execute_read_query(query, &database, context).map(Some),
+        QueryKind::Write => Ok(None),
+    }
 }
 
-type Sessions = Arc<Mutex<HashMap<String, EngineSession>>>;
+/// Error for a query that writes, sent to a path that only reads the dataset
+const UPDATE_ON_QUERY_PATH: &str = "SPARQL Update requests must be sent to /update";
 
 static SESSION_COUNTER: AtomicU64 = AtomicU64::new(1);
 
 const READ_CHUNK_SIZE: usize = 8 * 1024;
 const MAX_REQUEST_SIZE: usize = 64 * 1024 * 1024;
 const INCOMPLETE_JSON_GRACE_PERIOD: Duration = Duration::from_millis(750);
+/// Time a single query may run before the request fails with 503
+const DEFAULT_QUERY_TIMEOUT: Duration = Duration::from_secs(30);
+/// Triples the server dataset may hold for a JSON `/query` request with rules
+/// and no posted RDF, which runs on a copy of the dataset; overridden by
+/// `KOLIBRIE_RULES_COPY_MAX_TRIPLES` (0 disables the limit)
+const DEFAULT_RULES_COPY_MAX_TRIPLES: usize = 1_000_000;
+/// Minutes an RSP session may go without a push or SSE connection before it
+/// is dropped; overridden by `KOLIBRIE_RSP_SESSION_IDLE_MINUTES` (0 disables)
+const DEFAULT_SESSION_IDLE_MINUTES: u64 = 30;
+/// Upper bound on how often the reaper looks for idle sessions
+const SESSION_REAP_INTERVAL: Duration = Duration::from_secs(60);
+/// Seconds between SSE keep-alive comments on a quiet event stream;
+/// overridden by `KOLIBRIE_SSE_KEEPALIVE_SECS` (0 disables)
+const DEFAULT_SSE_KEEPALIVE_SECS: u64 = 15;
+/// Sent over the SSE channel when a session is dropped, so the client's
+/// event stream ends instead of reconnecting
+const SESSION_CLOSED_MESSAGE: &str = "__SESSION_CLOSED__";
 
 struct HttpRequest {
     method: String,
     path: String,
+    headers: String,
     body: Vec<u8>,
 }
 
@@ -60,7 +129,7 @@ struct QueryRequest {
     #[serde(default)]
     rdf: Option<String>,
     // N3 logic rules in { pattern } => { conclusion } syntax with @prefix declarations.
-    // Parsed by parse_n3_rule (parser_n3_logic.rs) via the Reasoner — completely separate
+    // Applied by SparqlDatabase::apply_n3_rules via the Reasoner — completely separate
     // from the SPARQL RULE syntax handled by process_rule_definition (parser.rs).
     #[serde(default)]
     n3logic: Option<String>,
@@ -70,11 +139,29 @@ struct QueryRequest {
     rules: Option<Vec<String>>,
     #[serde(default = "default_format")]
     format: String,
+    /// Per-query time limit in milliseconds; defaults to `DEFAULT_QUERY_TIMEOUT`
+    #[serde(default)]
+    timeout_ms: Option<u64>,
+    /// Serialize the results as `json`, `xml`, `csv` or `tsv` (or a SPARQL
+    /// results media type) instead of the playground JSON; overrides `Accept`
+    #[serde(default)]
+    result_format: Option<String>,
 }
 
-// Default format is RDF/XML for backwards compatibility
+// Detect the format from the data unless the client names one
 fn default_format() -> String {
-    "rdfxml".to_string()
+    "auto".to_string()
+}
+
+/// Resolve a requested format name, detecting it from `data` for `auto`.
+fn resolve_rdf_format(format: &str, data: &str) -> String {
+    if format.eq_ignore_ascii_case("auto") {
+        let detected = detect_rdf_format(data).name();
+        debug!("Detected RDF format: {}", detected);
+        detected.to_string()
+    } else {
+        format.to_string()
+    }
 }
 
 #[derive(Debug, Serialize)]
@@ -86,8 +173,38 @@ struct QueryResponse {
 struct QueryResult {
     query_index: usize,
     query: String,
+    /// Column names of the rows in `data`
+    variables: Vec<String>,
     data: Vec<Vec<String>>,
     execution_time_ms: f64,
+    /// Per-operator counters, present when the request sets `X-Kolibrie-Profile: true`
+    #[serde(skip_serializing_if = "Option::is_none")]
+    profile: Option<Vec<OperatorProfileJson>>,
+}
+
+#[derive(Debug, Serialize)]
+struct OperatorProfileJson {
+    operator: String,
+    depth: usize,
+    estimated_rows: u64,
+    invocations: u64,
+    rows_in: u64,
+    rows_out: u64,
+    elapsed_ns: u64,
+}
+
+impl From<OperatorProfile> for OperatorProfileJson {
+    fn from(profile: OperatorProfile) -> Self {
+        Self {
+            operator: profile.operator,
+            depth: profile.depth,
+            estimated_rows: profile.estimated_rows,
+            invocations: profile.invocations,
+            rows_in: profile.rows_in,
+            rows_out: profile.rows_out,
+            elapsed_ns: profile.elapsed_ns,
+        }
+    }
 }
 
 #[derive(Debug, Serialize)]
@@ -95,6 +212,27 @@ struct ErrorResponse {
     error: String,
 }
 
+#[derive(Debug, Serialize)]
+struct HealthResponse {
+    status: &'static str,
+    /// Triples in the server-held dataset
+    triples: usize,
+}
+
+/// Body of a 400 for an RSP-QL query the engine could not be built from
+#[derive(Debug, Serialize)]
+struct RspBuildErrorResponse {
+    error: String,
+    /// `parse_error`, `unsupported_feature` or `missing_consumer`
+    kind: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    clause: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    line: Option<usize>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    column: Option<usize>,
+}
+
 // ── RSP-QL stateless endpoint (legacy) ──────────────────────────────────────
 
 #[derive(Debug, Deserialize)]
@@ -151,40 +289,20 @@ struct RspPushRequest {
     ntriples: String,
 }
 
-// ── Helpers ──────────────────────────────────────────────────────────────────
-
-/// Format a decoded dictionary term as an N-Triples token:
-/// - plain URIs → `<uri>`
-/// - literals (start with `"`) and blank nodes (`_:`) → as-is
-fn term_to_ntriples_token(term: &str) -> String {
-    if term.starts_with('"') || term.starts_with("_:") {
-        term.to_string()
-    } else {
-        format!("<{}>", term)
-    }
+#[derive(Debug, Deserialize)]
+struct RspPushBatchRequest {
+    session_id: String,
+    events: Vec<StreamEvent>,
 }
 
-/// Export every triple in `db` as an N-Triples string.
-fn db_to_ntriples(db: &SparqlDatabase) -> String {
-    let dict = db.dictionary.read().unwrap();
-    let mut out = String::new();
-    for triple in &db.triples {
-        if let (Some(s), Some(p), Some(o)) = (
-            dict.decode(triple.subject),
-            dict.decode(triple.predicate),
-            dict.decode(triple.object),
-        ) {
-            out.push_str(&format!(
-                "{} {} {} .\n",
-                term_to_ntriples_token(s),
-                term_to_ntriples_token(p),
-                term_to_ntriples_token(o)
-            ));
-        }
-    }
-    out
+#[derive(Debug, Serialize)]
+struct RspPushBatchResponse {
+    triples_ingested: usize,
+    rows_emitted: u64,
 }
 
+// ── Helpers ──────────────────────────────────────────────────────────────────
+
 /// Convert collected RSP result rows into a table (first row = headers).
 fn results_to_table(results: &[Vec<(String, String)>]) -> Vec<Vec<String>> {
     if results.is_empty() {
@@ -276,10 +394,63 @@ fn strip_hash_comments(text: &str) -> String {
     output
 }
 
+/// `strip_hash_comments` for queries. The executor reads planner hints
+/// (`# HINT ...`, `# kolibrie:join ...`) from the comments a query starts
+/// with, so those lines are kept.
+fn strip_query_comments(query: &str) -> String {
+    let mut output = String::with_capacity(query.len());
+    let mut rest = query;
+    while let Some((line, tail)) = rest.split_once('\n') {
+        let comment = line.trim();
+        if !comment.is_empty() && !comment.starts_with('#') {
+            break;
+        }
+        if is_planner_hint(comment) {
+            output.push_str(line);
+        }
+        output.push('\n');
+        rest = tail;
+    }
+    output.push_str(&strip_hash_comments(rest));
+    output
+}
+
+fn is_planner_hint(comment: &str) -> bool {
+    let comment = comment.trim_start_matches('#').trim_start();
+    comment.starts_with("kolibrie:join")
+        || comment.split_once(char::is_whitespace).is_some_and(|(word, _)| word == "HINT")
+}
+
 fn main() {
-    println!("Starting Kolibrie HTTP Server on 0.0.0.0:8080");
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
+    info!("Starting Kolibrie HTTP Server on 0.0.0.0:8080");
 
-    let sessions: Sessions = Arc::new(Mutex::new(HashMap::new()));
+    let sessions: Sessions = Arc::new(RwLock::new(HashMap::new()));
+    let mut database = SparqlDatabase::new();
+    if let Some(path) = dataset_argument(std::env::args().skip(1)) {
+        let rdf_data = match read_rdf_file(&path) {
+            Ok(rdf_data) => rdf_data,
+            Err(e) => {
+                error!("Cannot read dataset {}: {}", path, e);
+                std::process::exit(1);
+            }
+        };
+        load_rdf(&mut database, &rdf_data, &resolve_rdf_format("auto", &rdf_data));
+        info!("Loaded dataset {} ({} triples)", path, database.count_triples());
+    }
+    database.prepare_for_reads();
+    let dataset: Dataset = Arc::new(RwLock::new(database));
+
+    if let Some(idle_timeout) = session_idle_timeout() {
+        info!("RSP sessions expire after {}s without activity", idle_timeout.as_secs());
+        let sessions = Arc::clone(&sessions);
+        thread::spawn(move || loop {
+            thread::sleep(idle_timeout.min(SESSION_REAP_INTERVAL));
+            for session_id in reap_idle_sessions(&sessions, idle_timeout) {
+                info!("RSP reaper: session {} expired", session_id);
+            }
+        });
+    }
 
     let listener = TcpListener::bind("0.0.0.0:8080").expect("Failed to bind to port 8080");
 
@@ -287,18 +458,32 @@ fn main() {
         match stream {
             Ok(stream) => {
                 let sessions = Arc::clone(&sessions);
+                let dataset = Arc::clone(&dataset);
                 thread::spawn(move || {
-                    handle_client(stream, sessions);
+                    handle_client(stream, sessions, dataset);
                 });
             }
             Err(e) => {
-                eprintln!("Connection failed: {}", e);
+                error!("Connection failed: {}", e);
             }
         }
     }
 }
 
-fn handle_client(mut stream: TcpStream, sessions: Sessions) {
+/// The value of `--dataset FILE` (or `--dataset=FILE`) among the command-line arguments
+fn dataset_argument(mut args: impl Iterator<Item = String>) -> Option<String> {
+    while let Some(arg) = args.next() {
+        if arg == "--dataset" {
+            return args.next();
+        }
+        if let Some(path) = arg.strip_prefix("--dataset=") {
+            return Some(path.to_string());
+        }
+    }
+    None
+}
+
+fn handle_client(mut stream: TcpStream, sessions: Sessions, dataset: Dataset) {
     match read_http_request(&mut stream) {
         Ok(request) => {
             // SSE handler must keep the connection open, so it is handled here
@@ -308,19 +493,30 @@ fn handle_client(mut stream: TcpStream, sessions: Sessions) {
                 rsp_events_sse(&session_id, stream, &sessions);
                 return;
             }
+            if request.method == "GET" && request.path.starts_with("/rsp/ws/") {
+                let session_id = request.path["/rsp/ws/".len()..].to_string();
+                rsp_websocket(&session_id, &request.headers, stream, &sessions);
+                return;
+            }
 
-            let response = handle_request(&request, &sessions);
+            let response = handle_request(&request, &sessions, &dataset);
             let _ = stream.write_all(response.as_bytes());
             let _ = stream.flush();
         }
         Err(e) if e.kind() == io::ErrorKind::InvalidData => {
-            eprintln!("Request rejected: {}", e);
+            warn!("Request rejected: {}", e);
             let response = error_response(413, "Payload Too Large");
             let _ = stream.write_all(response.as_bytes());
             let _ = stream.flush();
         }
+        Err(e) if e.kind() == io::ErrorKind::Unsupported => {
+            warn!("Request rejected: {}", e);
+            let response = error_response(415, "Unsupported Media Type");
+            let _ = stream.write_all(response.as_bytes());
+            let _ = stream.flush();
+        }
         Err(e) => {
-            eprintln!("Failed to read from connection: {}", e);
+            error!("Failed to read from connection: {}", e);
             let response = error_response(400, "Bad Request");
             let _ = stream.write_all(response.as_bytes());
             let _ = stream.flush();
@@ -354,7 +550,10 @@ fn read_http_request(stream: &mut TcpStream) -> io::Result<HttpRequest> {
         if has_chunked_transfer_encoding(&headers) {
             loop {
                 match decode_chunked_body(&request[header_end..])? {
-                    Some(body) => return Ok(HttpRequest { method, path, body }),
+                    Some(body) => {
+                        let body = decode_request_body(&headers, body)?;
+                        return Ok(HttpRequest { method, path, headers, body });
+                    }
                     None => {
                         let size = stream.read(&mut buffer)?;
                         if size == 0 {
@@ -393,7 +592,10 @@ fn read_http_request(stream: &mut TcpStream) -> io::Result<HttpRequest> {
             ensure_request_size(request.len())?;
         }
 
-        if is_json_request(&headers) && json_body_needs_more_bytes(&request[header_end..body_end]) {
+        if is_json_request(&headers)
+            && content_encoding(&headers).is_none()
+            && json_body_needs_more_bytes(&request[header_end..body_end])
+        {
             if body_end < request.len() {
                 body_end = request.len();
             }
@@ -401,7 +603,7 @@ fn read_http_request(stream: &mut TcpStream) -> io::Result<HttpRequest> {
             let extra_bytes =
                 read_until_json_complete(stream, &mut request, header_end, &mut body_end)?;
             if extra_bytes > 0 {
-                eprintln!(
+                debug!(
                     "HTTP JSON body needed {} extra byte(s) beyond the initial framing",
                     extra_bytes
                 );
@@ -409,8 +611,8 @@ fn read_http_request(stream: &mut TcpStream) -> io::Result<HttpRequest> {
         }
 
         request.truncate(body_end);
-        let body = request[header_end..].to_vec();
-        return Ok(HttpRequest { method, path, body });
+        let body = decode_request_body(&headers, request[header_end..].to_vec())?;
+        return Ok(HttpRequest { method, path, headers, body });
     }
 }
 
@@ -475,20 +677,53 @@ fn has_chunked_transfer_encoding(headers: &str) -> bool {
     })
 }
 
+/// The `Content-Encoding` of the request body, if it is not `identity`.
+fn content_encoding(headers: &str) -> Option<&str> {
+    header_value(headers, "content-encoding").filter(|encoding| !encoding.eq_ignore_ascii_case("identity"))
+}
+
+/// Undo a gzip or zstd `Content-Encoding` so that RDF (and the JSON carrying
+/// it) can be posted compressed. Other codings fail with `Unsupported`.
+fn decode_request_body(headers: &str, body: Vec<u8>) -> io::Result<Vec<u8>> {
+    let Some(encoding) = content_encoding(headers) else {
+        return Ok(body);
+    };
+    let compression = Compression::from_content_encoding(encoding).ok_or_else(|| {
+        io::Error::new(
+            io::ErrorKind::Unsupported,
+            format!("unsupported Content-Encoding: {}", encoding),
+        )
+    })?;
+    decompress(&body, compression, MAX_REQUEST_SIZE)
+}
+
 fn is_json_request(headers: &str) -> bool {
-    headers.lines().any(|line| {
-        let Some((name, value)) = line.split_once(':') else {
-            return false;
-        };
-        name.eq_ignore_ascii_case("content-type")
-            && value
-                .split(';')
-                .next()
-                .map(|content_type| content_type.trim().eq_ignore_ascii_case("application/json"))
-                .unwrap_or(false)
+    content_type(headers).is_some_and(|content_type| content_type == "application/json")
+}
+
+fn header_value<'a>(headers: &'a str, header_name: &str) -> Option<&'a str> {
+    headers.lines().find_map(|line| {
+        let (name, value) = line.split_once(':')?;
+        if name.trim().eq_ignore_ascii_case(header_name) {
+            Some(value.trim())
+        } else {
+            None
+        }
     })
 }
 
+/// Whether the client asked for per-operator profiling data
+fn profiling_requested(headers: &str) -> bool {
+    header_value(headers, "x-kolibrie-profile").is_some_and(|value| value.trim().eq_ignore_ascii_case("true"))
+}
+
+/// Media type of the request body, lowercased and without parameters.
+fn content_type(headers: &str) -> Option<String> {
+    header_value(headers, "content-type")
+        .and_then(|value| value.split(';').next())
+        .map(|media_type| media_type.trim().to_ascii_lowercase())
+}
+
 fn json_body_needs_more_bytes(body: &[u8]) -> bool {
     match serde_json::from_slice::<serde_json::Value>(body) {
         Ok(_) => false,
@@ -590,21 +825,52 @@ fn find_crlf(bytes: &[u8]) -> Option<usize> {
     bytes.windows(2).position(|window| window == b"\r\n")
 }
 
-fn handle_request(request: &HttpRequest, sessions: &Sessions) -> String {
+fn handle_request(request: &HttpRequest, sessions: &Sessions, dataset: &Dataset) -> String {
     let method = request.method.as_str();
-    let path = request.path.as_str();
+    let (path, query_string) = request
+        .path
+        .split_once('?')
+        .unwrap_or((request.path.as_str(), ""));
 
     if method == "GET" && path == "/" {
         return serve_playground();
     }
 
+    if method == "GET" && path == "/metrics" {
+        return metrics_response(sessions);
+    }
+
+    if method == "GET" && path == "/health" {
+        return health_response(dataset);
+    }
+
+    if method == "GET" && path == "/query" {
+        return sparql_protocol_get_query(request, query_string, dataset);
+    }
+
     if method == "POST" && path == "/query" {
+        // SPARQL 1.1 Protocol requests; JSON bodies keep using the playground API
+        if matches!(
+            content_type(&request.headers).as_deref(),
+            Some("application/sparql-query") | Some("application/x-www-form-urlencoded")
+        ) {
+            return sparql_protocol_query(request, dataset);
+        }
         return match request_body(&request.body) {
-            Some(body) => execute_sparql_with_context(body),
+            Some(body) => execute_sparql_with_context(
+                body,
+                header_value(&request.headers, "accept"),
+                profiling_requested(&request.headers),
+                dataset,
+            ),
             None => json_error_response("Request body is not valid UTF-8"),
         };
     }
 
+    if method == "POST" && path == "/update" {
+        return sparql_protocol_update(request, dataset);
+    }
+
     if method == "POST" && path == "/rsp-query" {
         return match request_body(&request.body) {
             Some(body) => execute_rsp_query(body),
@@ -626,6 +892,17 @@ fn handle_request(request: &HttpRequest, sessions: &Sessions) -> String {
         };
     }
 
+    if method == "POST" && path == "/rsp/push-batch" {
+        return match request_body(&request.body) {
+            Some(body) => rsp_push_batch(body, sessions),
+            None => json_error_response("Request body is not valid UTF-8"),
+        };
+    }
+
+    if method == "DELETE" && path.starts_with("/rsp/session/") {
+        return rsp_delete_session(&path["/rsp/session/".len()..], sessions);
+    }
+
     if method == "OPTIONS" {
         return cors_response();
     }
@@ -633,32 +910,230 @@ fn handle_request(request: &HttpRequest, sessions: &Sessions) -> String {
     error_response(404, "Not Found")
 }
 
+// ── SPARQL 1.1 Protocol handlers ────────────────────────────────────────────
+
+/// Extract the operation text of a protocol request: the raw body for
+/// `direct_media_type`, or the `param` field of a URL-encoded form. Fails when
+/// the request carries the operation meant for the other endpoint.
+fn protocol_operation(
+    request: &HttpRequest,
+    direct_media_type: &str,
+    param: &str,
+    other_param: &str,
+) -> Result<String, String> {
+    let Some(body) = request_body(&request.body) else {
+        return Err("Request body is not valid UTF-8".to_string());
+    };
+
+    match content_type(&request.headers).as_deref() {
+        Some(media_type) if media_type == direct_media_type => Ok(body.to_string()),
+        Some("application/x-www-form-urlencoded") => {
+            let mut operation = None;
+            for (name, value) in url::form_urlencoded::parse(body.as_bytes()) {
+                if name == other_param {
+                    return Err(format!("The '{}' parameter is not accepted by this endpoint", other_param));
+                }
+                if name == param && operation.is_none() {
+                    operation = Some(value.into_owned());
+                }
+            }
+            operation.ok_or_else(|| format!("Missing '{}' parameter", param))
+        }
+        _ => Err(format!(
+            "Unsupported Content-Type; expected {} or application/x-www-form-urlencoded",
+            direct_media_type
+        )),
+    }
+}
+
+/// Whether `text` is a SPARQL Update request rather than a query, judged by
+/// the first keyword after the prologue.
+fn is_sparql_update(text: &str) -> bool {
+    let text = strip_hash_comments(text);
+    let mut rest = text.trim_start();
+    loop {
+        let upper = rest.get(..6).unwrap_or("").to_ascii_uppercase();
+        if upper == "PREFIX" {
+            // PREFIX name: <iri>
+            match rest.find('>') {
+                Some(end) => rest = rest[end + 1..].trim_start(),
+                None => return false,
+            }
+        } else if upper.starts_with("BASE") {
+            match rest.find('>') {
+                Some(end) => rest = rest[end + 1..].trim_start(),
+                None => return false,
+            }
+        } else {
+            break;
+        }
+    }
+
+    let keyword: String = rest
+        .chars()
+        .take_while(|c| c.is_ascii_alphabetic())
+        .collect::<String>()
+        .to_ascii_uppercase();
+    matches!(
+        keyword.as_str(),
+        "INSERT" | "DELETE" | "LOAD" | "CLEAR" | "DROP" | "CREATE" | "ADD" | "MOVE" | "COPY" | "WITH"
+    )
+}
+
+fn sparql_protocol_query(request: &HttpRequest, dataset: &Dataset) -> String {
+    let query = match protocol_operation(request, "application/sparql-query", "query", "update") {
+        Ok(query) => query,
+        Err(message) => return bad_request(&message),
+    };
+    run_protocol_query(request, &query, dataset)
+}
+
+/// `GET /query?query=...`. The `default-graph-uri` and `named-graph-uri`
+/// parameters are ignored: queries always run against the server dataset.
+fn sparql_protocol_get_query(request: &HttpRequest, query_string: &str, dataset: &Dataset) -> String {
+    let mut query = None;
+    for (name, value) in url::form_urlencoded::parse(query_string.as_bytes()) {
+        if name == "update" {
+            return bad_request("The 'update' parameter is not accepted by this endpoint");
+        }
+        if name == "query" && query.is_none() {
+            query = Some(value.into_owned());
+        }
+    }
+    match query {
+        Some(query) => run_protocol_query(request, &query, dataset),
+        None => bad_request("Missing 'query' parameter"),
+    }
+}
+
+fn run_protocol_query(request: &HttpRequest, query: &str, dataset: &Dataset) -> String {
+    if is_sparql_update(query) {
+        return bad_request(UPDATE_ON_QUERY_PATH);
+    }
+
+    let Some(format) = ResultFormat::negotiate(header_value(&request.headers, "accept"), ResultFormat::Json) else {
+        return error_response(406, "Not Acceptable");
+    };
+
+    let query = strip_query_comments(query);
+    let rows = {
+        let context = QueryContext { timeout: Some(DEFAULT_QUERY_TIMEOUT), ..QueryContext::default() };
+        let start_time = Instant::now();
+        let result = query_dataset(&query, dataset, &context);
+        record_query(start_time, &result);
+        match result {
+            Ok(Some(rows)) => rows,
+            Ok(None) => return bad_request(UPDATE_ON_QUERY_PATH),
+            Err(e) => return sparql_error_response(&e),
+        }
+    };
+    sparql_results_response(format, &query_result_variables(&query), &rows)
+}
+
+fn sparql_results_response(format: ResultFormat, variables: &[String], rows: &[Vec<String>]) -> String {
+    let body = format.serialize(variables, rows);
+    format!(
+        "HTTP/1.1 200 OK\r\n\
+         Content-Type: {}; charset=UTF-8\r\n\
+         Content-Length: {}\r\n\
+         Access-Control-Allow-Origin: *\r\n\
+         \r\n\
+         {}",
+        format.media_type(),
+        body.len(),
+        body
+    )
+}
+
+/// The SPARQL results format a JSON-body `/query` request asks for, or `None`
+/// for the playground response. The body's `result_format` wins over `Accept`,
+/// and `Accept` only selects a serializer when it names a SPARQL results type:
+/// wildcards and plain `application/json` keep the playground JSON.
+fn requested_result_format(
+    accept: Option<&str>,
+    result_format: Option<&str>,
+) -> Result<Option<ResultFormat>, String> {
+    if let Some(name) = result_format {
+        return ResultFormat::from_name(name)
+            .map(Some)
+            .ok_or_else(|| format!("Unsupported result_format '{}'", name));
+    }
+    let Some(accept) = accept else {
+        return Ok(None);
+    };
+    let names_results_type = accept.split(',').any(|range| {
+        let media_type = range.split(';').next().unwrap_or("").trim();
+        !media_type.eq_ignore_ascii_case("application/json")
+            && ResultFormat::from_media_type(media_type).is_some()
+    });
+    if !names_results_type {
+        return Ok(None);
+    }
+    Ok(ResultFormat::negotiate(Some(accept), ResultFormat::Json))
+}
+
+fn sparql_protocol_update(request: &HttpRequest, dataset: &Dataset) -> String {
+    let update = match protocol_operation(request, "application/sparql-update", "update", "query") {
+        Ok(update) => update,
+        Err(message) => return bad_request(&message),
+    };
+    if !is_sparql_update(&update) {
+        return bad_request("SPARQL queries must be sent to /query");
+    }
+
+    let update = strip_hash_comments(&update);
+    // The write lock serializes updates; queries hold the read lock, so they
+    // see the dataset either before or after an update
+    let mut database = dataset.write().unwrap();
+    if let Err(e) = database.try_update(&update) {
+        return sparql_error_response(&e);
+    }
+    database.invalidate_stats_cache();
+    // Indexes and statistics are rebuilt here once, as queries cannot
+    database.prepare_for_reads();
+
+    "HTTP/1.1 204 No Content\r\n\
+     Access-Control-Allow-Origin: *\r\n\
+     \r\n"
+        .to_string()
+}
+
 // ── RSP persistent session handlers ─────────────────────────────────────────
 
 fn request_body(body: &[u8]) -> Option<&str> {
     match std::str::from_utf8(body) {
         Ok(body) => Some(body),
         Err(e) => {
-            eprintln!("Request body is not valid UTF-8: {}", e);
+            warn!("Request body is not valid UTF-8: {}", e);
             None
         }
     }
 }
 
+/// R2R operator of the RSP endpoints; rules that keep deriving facts stop at
+/// the default inference budget instead of holding the session's thread
+fn rsp_r2r() -> Box<SimpleR2R> {
+    let mut r2r = SimpleR2R::with_execution_mode(QueryExecutionMode::Volcano);
+    r2r.item.inference_budget = Some(InferenceBudget::default());
+    Box::new(r2r)
+}
+
 fn rsp_register(body: &str, sessions: &Sessions) -> String {
     let req: RspRegisterRequest = match serde_json::from_str(body) {
         Ok(r) => r,
         Err(e) => {
-            eprintln!("RSP register JSON error: {}", e);
+            warn!("RSP register JSON error: {}", e);
             return json_error_response(&format!("Invalid JSON: {}", e));
         }
     };
 
-    println!("RSP register: building engine for new session");
+    info!("RSP register: building engine for new session");
 
     // The SSE sender is lazily populated when the browser opens the SSE connection.
     let sse_sender: Arc<Mutex<Option<Sender<String>>>> = Arc::new(Mutex::new(None));
     let sse_sender_for_consumer = Arc::clone(&sse_sender);
+    let emitted_rows = Arc::new(AtomicU64::new(0));
+    let emitted_rows_for_consumer = Arc::clone(&emitted_rows);
 
     // Consumer: serialize each result row as JSON and forward to the SSE channel.
     let result_consumer = ResultConsumer::<Vec<(String, String)>> {
@@ -669,12 +1144,14 @@ fn rsp_register(body: &str, sessions: &Sessions) -> String {
                 .collect();
             let json = serde_json::to_string(&map).unwrap_or_default();
             if let Some(tx) = sse_sender_for_consumer.lock().unwrap().as_ref() {
-                let _ = tx.send(json);
+                if tx.send(json).is_ok() {
+                    emitted_rows_for_consumer.fetch_add(1, Ordering::Relaxed);
+                }
             }
         }),
     };
 
-    let r2r = Box::new(SimpleR2R::with_execution_mode(QueryExecutionMode::Volcano));
+    let r2r = rsp_r2r();
 
     let cleaned_query = strip_hash_comments(&req.query);
     let cleaned_n3logic = req.n3logic.as_deref().map(strip_hash_comments);
@@ -702,36 +1179,37 @@ fn rsp_register(body: &str, sessions: &Sessions) -> String {
         {
             Ok(e) => e,
             Err(e) => {
-                eprintln!("RSP build error: {}", e);
-                return json_error_response(&format!("Failed to build RSP engine: {}", e));
+                error!("RSP build error: {}", e);
+                return rsp_build_error_response(&e);
             }
         };
 
     // Load static background data if provided.
     if let Some(static_rdf) = &req.static_rdf {
         if !static_rdf.trim().is_empty() {
+            let static_format = resolve_rdf_format(&req.static_format, static_rdf);
             let cleaned_static_rdf;
-            let static_rdf_for_parse = match req.static_format.as_str() {
+            let static_rdf_for_parse = match static_format.as_str() {
                 "ntriples" | "turtle" => {
                     cleaned_static_rdf = strip_hash_comments(static_rdf);
                     cleaned_static_rdf.as_str()
                 }
                 _ => static_rdf.as_str(),
             };
-            let ntriples = match req.static_format.as_str() {
+            let ntriples = match static_format.as_str() {
                 "ntriples" => static_rdf_for_parse.to_string(),
                 _ => {
                     let mut static_db = SparqlDatabase::new();
-                    match req.static_format.as_str() {
+                    match static_format.as_str() {
                         "turtle" => static_db.parse_turtle(static_rdf_for_parse),
                         _ => static_db.parse_rdf(static_rdf_for_parse),
                     }
-                    db_to_ntriples(&static_db)
+                    static_db.generate_ntriples()
                 }
             };
             if !ntriples.is_empty() {
                 engine.add_static_ntriples(&ntriples);
-                println!(
+                debug!(
                     "RSP register: loaded static data ({} bytes)",
                     ntriples.len()
                 );
@@ -742,12 +1220,18 @@ fn rsp_register(body: &str, sessions: &Sessions) -> String {
     let streams = engine.stream_iris();
     let session_id = SESSION_COUNTER.fetch_add(1, Ordering::Relaxed).to_string();
 
-    sessions
-        .lock()
-        .unwrap()
-        .insert(session_id.clone(), EngineSession { engine, sse_sender });
+    sessions.write().unwrap().insert(
+        session_id.clone(),
+        Arc::new(Mutex::new(EngineSession {
+            engine,
+            sse_sender,
+            emitted_rows,
+            last_activity: Instant::now(),
+        })),
+    );
+    METRICS.record_session_created();
 
-    println!(
+    info!(
         "RSP register: session {} created, streams: {:?}",
         session_id, streams
     );
@@ -775,40 +1259,122 @@ fn rsp_push(body: &str, sessions: &Sessions) -> String {
     let req: RspPushRequest = match serde_json::from_str(body) {
         Ok(r) => r,
         Err(e) => {
-            eprintln!("RSP push JSON error: {}", e);
+            warn!("RSP push JSON error: {}", e);
             return json_error_response(&format!("Invalid JSON: {}", e));
         }
     };
 
+    if push_event(&req, sessions).is_none() {
+        warn!("RSP push: session {} not found", req.session_id);
+        return json_error_response("Session not found");
+    }
+    json_ok()
+}
+
+/// Ingest one pushed event and flush the results it fires; returns the number
+/// of triples added, or `None` when the session does not exist.
+fn push_event(req: &RspPushRequest, sessions: &Sessions) -> Option<usize> {
     let ntriples = strip_hash_comments(&req.ntriples);
     if ntriples.trim().is_empty() {
-        return json_ok();
+        return Some(0);
     }
 
-    let mut sessions_lock = sessions.lock().unwrap();
-    let session = match sessions_lock.get_mut(&req.session_id) {
-        Some(s) => s,
-        None => {
-            eprintln!("RSP push: session {} not found", req.session_id);
-            return json_error_response("Session not found");
+    let session = find_session(sessions, &req.session_id)?;
+    let mut session = session.lock().unwrap();
+    session.touch();
+
+    let ingested = ingest_stream_event(&mut session, &req.stream, req.timestamp, &ntriples);
+    debug!(
+        "RSP push: {} triple(s) to stream '{}' at t={} (session {})",
+        ingested, req.stream, req.timestamp, req.session_id
+    );
+    finish_firing(&mut session);
+    Some(ingested)
+}
+
+/// `/rsp/push` for many events at once: the events are ingested in timestamp
+/// order under a single lock of the session, and pending window results are
+/// flushed once at the end.
+fn rsp_push_batch(body: &str, sessions: &Sessions) -> String {
+    let mut req: RspPushBatchRequest = match serde_json::from_str(body) {
+        Ok(r) => r,
+        Err(e) => {
+            warn!("RSP push-batch JSON error: {}", e);
+            return json_error_response(&format!("Invalid JSON: {}", e));
         }
     };
+    // Stable, so events sharing a timestamp keep their request order
+    req.events.sort_by_key(|event| event.timestamp);
 
-    let triples = session.engine.parse_data(&ntriples);
-    println!(
-        "RSP push: {} triple(s) to stream '{}' at t={} (session {})",
-        triples.len(),
-        req.stream,
-        req.timestamp,
+    let Some(session) = find_session(sessions, &req.session_id) else {
+        warn!("RSP push-batch: session {} not found", req.session_id);
+        return json_error_response("Session not found");
+    };
+    let mut session = session.lock().unwrap();
+    session.touch();
+
+    let rows_before = session.emitted_rows.load(Ordering::Relaxed);
+    let mut triples_ingested = 0;
+    for event in &req.events {
+        let ntriples = strip_hash_comments(&event.ntriples);
+        if ntriples.trim().is_empty() {
+            continue;
+        }
+        triples_ingested += ingest_stream_event(&mut session, &event.stream, event.timestamp, &ntriples);
+    }
+    finish_firing(&mut session);
+
+    let response = RspPushBatchResponse {
+        triples_ingested,
+        rows_emitted: session.emitted_rows.load(Ordering::Relaxed) - rows_before,
+    };
+    debug!(
+        "RSP push-batch: {} event(s), {} triple(s), {} row(s) emitted (session {})",
+        req.events.len(),
+        response.triples_ingested,
+        response.rows_emitted,
         req.session_id
     );
 
+    let json = serde_json::to_string(&response).unwrap_or_default();
+    format!(
+        "HTTP/1.1 200 OK\r\n\
+         Content-Type: application/json\r\n\
+         Content-Length: {}\r\n\
+         Access-Control-Allow-Origin: *\r\n\
+         Access-Control-Allow-Methods: POST, OPTIONS\r\n\
+         Access-Control-Allow-Headers: Content-Type\r\n\
+         \r\n\
+         {}",
+        json.len(),
+        json
+    )
+}
+
+/// The session registered as `session_id`. Only the map's read lock is held,
+/// and only for the lookup.
+fn find_session(sessions: &Sessions, session_id: &str) -> Option<Arc<Mutex<EngineSession>>> {
+    sessions.read().unwrap().get(session_id).cloned()
+}
+
+/// Parse `ntriples` and add them to `stream` at `timestamp`; returns the number
+/// of triples added.
+fn ingest_stream_event(
+    session: &mut EngineSession,
+    stream: &str,
+    timestamp: usize,
+    ntriples: &str,
+) -> usize {
+    let triples = session.engine.parse_data(ntriples);
+    let count = triples.len();
     for triple in triples {
-        session
-            .engine
-            .add_to_stream(&req.stream, triple, req.timestamp);
+        session.engine.add_to_stream(stream, triple, timestamp);
     }
+    METRICS.record_ingested(stream, count);
+    count
+}
 
+fn finish_firing(session: &mut EngineSession) {
     // Flush any pending channel results (multi-window / static-data join case).
     // For single-window queries, the consumer is called directly from add_to_window
     // already, so this is a no-op in the simple case.
@@ -819,23 +1385,92 @@ fn rsp_push(body: &str, sessions: &Sessions) -> String {
     if let Some(tx) = session.sse_sender.lock().unwrap().as_ref() {
         let _ = tx.send("__FIRING_END__".to_string());
     }
+}
 
-    json_ok()
+/// `DELETE /rsp/session/{id}`: drop the session's engine and end its SSE
+/// stream.
+fn rsp_delete_session(session_id: &str, sessions: &Sessions) -> String {
+    let removed = sessions.write().unwrap().remove(session_id);
+    match removed {
+        Some(session) => {
+            close_session(&session.lock().unwrap());
+            info!("RSP delete: session {} removed", session_id);
+            json_ok()
+        }
+        None => json_error_with_status("404 Not Found", "Session not found"),
+    }
+}
+
+/// Tell the SSE client the session is gone and drop its sender, which ends the
+/// forwarding loop in `rsp_events_sse` and closes the connection.
+fn close_session(session: &EngineSession) {
+    METRICS.record_session_closed();
+    if let Some(tx) = session.sse_sender.lock().unwrap().take() {
+        let _ = tx.send(SESSION_CLOSED_MESSAGE.to_string());
+    }
+}
+
+/// Remove every session idle for longer than `idle_timeout`; returns the ids
+/// of the removed sessions.
+fn reap_idle_sessions(sessions: &Sessions, idle_timeout: Duration) -> Vec<String> {
+    let expired: Vec<(String, Arc<Mutex<EngineSession>>)> = {
+        let mut lock = sessions.write().unwrap();
+        // A session locked by a push in progress is not idle
+        let ids: Vec<String> = lock
+            .iter()
+            .filter(|(_, session)| {
+                session
+                    .try_lock()
+                    .is_ok_and(|session| session.last_activity.elapsed() > idle_timeout)
+            })
+            .map(|(id, _)| id.clone())
+            .collect();
+        ids.into_iter()
+            .filter_map(|id| lock.remove(&id).map(|session| (id, session)))
+            .collect()
+    };
+    // Engines are dropped outside the sessions lock
+    expired
+        .into_iter()
+        .map(|(id, session)| {
+            close_session(&session.lock().unwrap());
+            id
+        })
+        .collect()
+}
+
+/// Idle timeout for RSP sessions, or `None` when expiry is disabled.
+fn session_idle_timeout() -> Option<Duration> {
+    let minutes = match std::env::var("KOLIBRIE_RSP_SESSION_IDLE_MINUTES") {
+        Ok(value) => value.trim().parse::<u64>().unwrap_or_else(|_| {
+            warn!(
+                "Invalid KOLIBRIE_RSP_SESSION_IDLE_MINUTES '{}', using {}",
+                value, DEFAULT_SESSION_IDLE_MINUTES
+            );
+            DEFAULT_SESSION_IDLE_MINUTES
+        }),
+        Err(_) => DEFAULT_SESSION_IDLE_MINUTES,
+    };
+    (minutes > 0).then(|| Duration::from_secs(minutes * 60))
 }
 
 /// SSE handler — writes the event-stream headers and then blocks, forwarding
 /// results to the browser as they arrive via an in-process channel.
 fn rsp_events_sse(session_id: &str, mut stream: TcpStream, sessions: &Sessions) {
-    // Clone the Arc so we can release the sessions lock before blocking.
-    let sse_sender_arc = {
-        let lock = sessions.lock().unwrap();
-        match lock.get(session_id) {
-            Some(s) => Arc::clone(&s.sse_sender),
-            None => {
-                let resp = error_response(404, "Session not found");
-                let _ = stream.write_all(resp.as_bytes());
-                return;
-            }
+    // Clone the Arcs so we can release the sessions lock before blocking.
+    let (session, sse_sender_arc) = match find_session(sessions, session_id) {
+        Some(session) => {
+            let sse_sender = {
+                let mut session = session.lock().unwrap();
+                session.touch();
+                Arc::clone(&session.sse_sender)
+            };
+            (session, sse_sender)
+        }
+        None => {
+            let resp = error_response(404, "Session not found");
+            let _ = stream.write_all(resp.as_bytes());
+            return;
         }
     };
 
@@ -857,30 +1492,196 @@ fn rsp_events_sse(session_id: &str, mut stream: TcpStream, sessions: &Sessions)
     }
     stream.flush().ok();
 
-    println!("RSP SSE: client connected for session {}", session_id);
+    info!("RSP SSE: client connected for session {}", session_id);
+    // A connected client keeps the session alive for as long as events or
+    // keep-alives still reach it
+    forward_sse_events(rx, &mut stream, sse_keepalive_interval(), || session.lock().unwrap().touch());
+    info!("RSP SSE: client disconnected for session {}", session_id);
+}
+
+/// Block-forward events until the client disconnects or the tx is dropped.
+/// When no event arrives for `keepalive`, an SSE comment is written instead so
+/// proxies do not close the idle connection; clients never see it as data.
+/// `on_write` runs after each event or keep-alive written to `out`.
+fn forward_sse_events<W: Write>(
+    rx: Receiver<String>,
+    out: &mut W,
+    keepalive: Option<Duration>,
+    mut on_write: impl FnMut(),
+) {
+    loop {
+        let received = match keepalive {
+            Some(interval) => match rx.recv_timeout(interval) {
+                Ok(received) => received,
+                Err(RecvTimeoutError::Timeout) => {
+                    if out.write_all(b": keepalive\n\n").is_err() {
+                        break;
+                    }
+                    out.flush().ok();
+                    on_write();
+                    continue;
+                }
+                Err(RecvTimeoutError::Disconnected) => break,
+            },
+            None => match rx.recv() {
+                Ok(received) => received,
+                Err(_) => break,
+            },
+        };
 
-    // Block-forward events until the client disconnects or the tx is dropped.
-    for received in rx {
         let msg = if received == "__FIRING_END__" {
             // Named event so the browser can flush its firing buffer immediately.
             "event: firing\ndata: {}\n\n".to_string()
+        } else if received == SESSION_CLOSED_MESSAGE {
+            let _ = out.write_all(b"event: closed\ndata: {}\n\n");
+            out.flush().ok();
+            break;
         } else {
             format!("data: {}\n\n", received)
         };
-        if stream.write_all(msg.as_bytes()).is_err() {
+        if out.write_all(msg.as_bytes()).is_err() {
             break;
         }
-        stream.flush().ok();
+        out.flush().ok();
+        on_write();
     }
-
-    println!("RSP SSE: client disconnected for session {}", session_id);
 }
 
-// ── Existing SPARQL and legacy RSP-QL handlers ───────────────────────────────
-
-fn serve_playground() -> String {
-    let html = include_str!("../../web/playground.html");
-    format!(
+/// WebSocket handler — one connection both pushes events and receives the
+/// results. Each text message is an `RspPushRequest`; result rows come back as
+/// text frames, followed by `{"event":"firing"}` once a push is processed.
+fn rsp_websocket(session_id: &str, headers: &str, mut stream: TcpStream, sessions: &Sessions) {
+    let Some(client_key) = websocket::upgrade_key(headers) else {
+        let resp = error_response(400, "Bad Request");
+        let _ = stream.write_all(resp.as_bytes());
+        return;
+    };
+    let results_sender = match find_session(sessions, session_id) {
+        Some(session) => {
+            let mut session = session.lock().unwrap();
+            session.touch();
+            Arc::clone(&session.sse_sender)
+        }
+        None => {
+            let resp = error_response(404, "Session not found");
+            let _ = stream.write_all(resp.as_bytes());
+            return;
+        }
+    };
+
+    let writer = match stream.try_clone() {
+        Ok(writer) => Arc::new(Mutex::new(writer)),
+        Err(e) => {
+            error!("RSP WebSocket: failed to clone connection: {}", e);
+            return;
+        }
+    };
+    if stream.write_all(websocket::handshake_response(client_key).as_bytes()).is_err() {
+        return;
+    }
+    // The connection now lives as long as the client wants it to.
+    stream.set_read_timeout(None).ok();
+
+    let (tx, rx) = mpsc::channel::<String>();
+    results_sender.lock().unwrap().replace(tx);
+    {
+        let writer = Arc::clone(&writer);
+        thread::spawn(move || forward_websocket_results(rx, &writer));
+    }
+
+    info!("RSP WebSocket: client connected for session {}", session_id);
+    let mut messages = websocket::MessageReader::new(&stream);
+    loop {
+        let reply = match messages.read_message() {
+            Ok(websocket::Message::Text(text)) => websocket_push(&text, session_id, sessions),
+            Ok(websocket::Message::Ping(payload)) => {
+                let _ = websocket::write_pong(&mut *writer.lock().unwrap(), &payload);
+                None
+            }
+            Ok(websocket::Message::Binary(_)) => Some("Binary messages are not supported".to_string()),
+            Ok(websocket::Message::Pong(_)) => None,
+            Ok(websocket::Message::Close) => {
+                let _ = websocket::write_close(&mut *writer.lock().unwrap());
+                break;
+            }
+            Err(e) => {
+                if e.kind() == io::ErrorKind::InvalidData {
+                    warn!("RSP WebSocket: {} (session {})", e, session_id);
+                }
+                break;
+            }
+        };
+        if let Some(message) = reply {
+            let error = serde_json::json!({ "error": message }).to_string();
+            if websocket::write_text(&mut *writer.lock().unwrap(), &error).is_err() {
+                break;
+            }
+        }
+    }
+
+    // As with SSE, the sender stays installed until a newer client replaces
+    // it; the forwarder stops at its next write to the closed connection.
+    let _ = stream.shutdown(std::net::Shutdown::Both);
+    info!("RSP WebSocket: client disconnected for session {}", session_id);
+}
+
+/// Ingest one WebSocket push message; returns the error to report, if any.
+fn websocket_push(text: &str, session_id: &str, sessions: &Sessions) -> Option<String> {
+    let req: RspPushRequest = match serde_json::from_str(text) {
+        Ok(req) => req,
+        Err(e) => return Some(format!("Invalid JSON: {}", e)),
+    };
+    if req.session_id != session_id {
+        return Some("session_id does not match the connection".to_string());
+    }
+    match push_event(&req, sessions) {
+        Some(_) => None,
+        None => Some("Session not found".to_string()),
+    }
+}
+
+/// Write results as text frames until the connection's sender is dropped.
+/// Firing and session-closed markers become `{"event": ...}` frames; on the
+/// latter the connection is closed.
+fn forward_websocket_results(rx: Receiver<String>, writer: &Mutex<TcpStream>) {
+    for received in rx {
+        let mut out = writer.lock().unwrap();
+        let written = if received == "__FIRING_END__" {
+            websocket::write_text(&mut *out, r#"{"event":"firing"}"#)
+        } else if received == SESSION_CLOSED_MESSAGE {
+            let _ = websocket::write_text(&mut *out, r#"{"event":"closed"}"#);
+            let _ = websocket::write_close(&mut *out);
+            let _ = out.shutdown(std::net::Shutdown::Both);
+            break;
+        } else {
+            websocket::write_text(&mut *out, &received)
+        };
+        if written.is_err() {
+            break;
+        }
+    }
+}
+
+/// Interval between SSE keep-alive comments, or `None` when disabled.
+fn sse_keepalive_interval() -> Option<Duration> {
+    let seconds = match std::env::var("KOLIBRIE_SSE_KEEPALIVE_SECS") {
+        Ok(value) => value.trim().parse::<u64>().unwrap_or_else(|_| {
+            warn!(
+                "Invalid KOLIBRIE_SSE_KEEPALIVE_SECS '{}', using {}",
+                value, DEFAULT_SSE_KEEPALIVE_SECS
+            );
+            DEFAULT_SSE_KEEPALIVE_SECS
+        }),
+        Err(_) => DEFAULT_SSE_KEEPALIVE_SECS,
+    };
+    (seconds > 0).then(|| Duration::from_secs(seconds))
+}
+
+// ── Existing SPARQL and legacy RSP-QL handlers ───────────────────────────────
+
+fn serve_playground() -> String {
+    let html = include_str!("../../web/playground.html");
+    format!(
         "HTTP/1.1 200 OK\r\n\
          Content-Type: text/html; charset=UTF-8\r\n\
          Content-Length: {}\r\n\
@@ -892,14 +1693,57 @@ fn serve_playground() -> String {
     )
 }
 
-fn execute_sparql_with_context(body: &str) -> String {
+/// Parse `rdf_data` in the named format into `database` and prepare its
+/// statistics and indexes for the optimizer.
+fn load_rdf(database: &mut SparqlDatabase, rdf_data: &str, format: &str) {
+    let cleaned_rdf_data;
+    let rdf_data_for_parse = match format {
+        "ntriples" | "turtle" => {
+            cleaned_rdf_data = strip_hash_comments(rdf_data);
+            cleaned_rdf_data.as_str()
+        }
+        _ => rdf_data,
+    };
+    match format {
+        "ntriples" => {
+            debug!("Parsing N-Triples data with Streamertail optimizer...");
+            database.parse_ntriples_and_add(rdf_data_for_parse);
+        }
+        "turtle" => {
+            debug!("Parsing Turtle dataset...");
+            database.parse_turtle(rdf_data_for_parse);
+        }
+        "n3" => {
+            debug!("Parsing N3 dataset...");
+            database.parse_n3(rdf_data_for_parse);
+        }
+        // "rdfxml", and any format the detection did not recognise
+        _ => {
+            debug!("Parsing RDF/XML data...");
+            database.parse_rdf(rdf_data_for_parse);
+        }
+    }
+    database.get_or_build_stats();
+    database.build_all_indexes();
+}
+
+fn execute_sparql_with_context(
+    body: &str,
+    accept: Option<&str>,
+    profile: bool,
+    dataset: &Dataset,
+) -> String {
     let request: QueryRequest = match serde_json::from_str(body) {
         Ok(req) => req,
         Err(e) => {
-            eprintln!("JSON parse error after {} byte(s): {}", body.len(), e);
+            warn!("JSON parse error after {} byte(s): {}", body.len(), e);
             return json_error_response(&format!("Invalid JSON: {}", e));
         }
     };
+    let result_format = match requested_result_format(accept, request.result_format.as_deref()) {
+        Ok(result_format) => result_format,
+        Err(message) => return json_error_response(&message),
+    };
 
     // Collect all queries
     let mut queries = Vec::new();
@@ -913,6 +1757,9 @@ fn execute_sparql_with_context(body: &str) -> String {
     if queries.is_empty() {
         return json_error_response("No queries provided");
     }
+    if result_format.is_some() && queries.len() > 1 {
+        return json_error_response("SPARQL result formats hold a single result set; send one query");
+    }
 
     // Collect all rules
     let mut rules = Vec::new();
@@ -923,154 +1770,38 @@ fn execute_sparql_with_context(body: &str) -> String {
         rules.extend(multi_rules);
     }
 
-    println!(
+    info!(
         "Processing {} query(ies) and {} rule(s)",
         queries.len(),
         rules.len()
     );
 
-    let mut database = SparqlDatabase::new();
-    let use_optimizer = request.format == "ntriples";
-
-    // Load RDF data once
-    if let Some(rdf_data) = request.rdf {
-        if !rdf_data.trim().is_empty() {
-            let cleaned_rdf_data;
-            let rdf_data_for_parse = match request.format.as_str() {
-                "ntriples" | "turtle" => {
-                    cleaned_rdf_data = strip_hash_comments(&rdf_data);
-                    cleaned_rdf_data.as_str()
-                }
-                _ => rdf_data.as_str(),
-            };
-            match request.format.as_str() {
-                "ntriples" => {
-                    println!("Parsing N-Triples data with Streamertail optimizer...");
-                    database.parse_ntriples_and_add(rdf_data_for_parse);
-                    database.get_or_build_stats();
-                    database.build_all_indexes();
-                }
-                "turtle" => {
-                    println!("Parsing Turtle dataset...");
-                    database.parse_turtle(rdf_data_for_parse);
-                    database.get_or_build_stats();
-                    database.build_all_indexes();
-                }
-                "rdfxml" | _ => {
-                    println!("Parsing RDF/XML data...");
-                    database.parse_rdf(rdf_data_for_parse);
-                    database.get_or_build_stats();
-                    database.build_all_indexes();
-                }
-            }
+    let timeout = request
+        .timeout_ms
+        .map(Duration::from_millis)
+        .unwrap_or(DEFAULT_QUERY_TIMEOUT);
+
+    // Posted RDF is queried on its own. Rules add their inferences to the data
+    // they run on, so without posted RDF a request with rules runs against a
+    // copy of the server dataset; any other request queries the dataset itself.
+    let mut local = match request.rdf {
+        Some(rdf_data) if !rdf_data.trim().is_empty() => {
+            let format = resolve_rdf_format(&request.format, &rdf_data);
+            let mut database = SparqlDatabase::new();
+            load_rdf(&mut database, &rdf_data, &format);
+            Some(database)
         }
-    }
-
-    // Process N3 logic rules (n3logic field) using parse_n3_rule + Reasoner.
-    // Syntax: @prefix declarations followed by { premise } => { conclusion } .
-    // This is completely separate from the SPARQL RULE syntax — it uses the
-    // Reasoner class (datalog/reasoning.rs) exactly as shown in the test example.
-    if let Some(ref n3_rules_text) = request.n3logic {
-        let n3_rules_text = strip_hash_comments(n3_rules_text);
-        if has_n3_rule_text(&n3_rules_text) {
-            println!("Processing N3 logic rules from N3 Logic sub-tab...");
-
-            // Mirror the database dictionary so term encodings are shared
-            let mut kg = Reasoner::new();
-            kg.dictionary = database.dictionary.clone();
-
-            // Decode all triples to owned strings using database.dictionary BEFORE
-            // creating kg's mutable borrow — avoids the RwLockReadGuard conflict.
-            let decoded_triples: Vec<(String, String, String)> = {
-                let dict_guard = kg.dictionary.read().unwrap();
-                database
-                    .triples
-                    .iter()
-                    .map(|triple| {
-                        (
-                            dict_guard.decode(triple.subject).unwrap_or("").to_string(),
-                            dict_guard
-                                .decode(triple.predicate)
-                                .unwrap_or("")
-                                .to_string(),
-                            dict_guard.decode(triple.object).unwrap_or("").to_string(),
-                        )
-                    })
-                    .collect()
-            };
-
-            println!("Decoded triples are: {:?}", decoded_triples);
-
-            // Now load into the Reasoner — no borrow conflict
-            for (s, p, o) in &decoded_triples {
-                kg.add_abox_triple(s, p, o);
-            }
-
-            let n3_text = n3_rules_text.trim();
-            match parse_n3_rule(n3_text, &mut kg) {
-                Ok((_, (prefixes, rule))) => {
-                    println!(
-                        "N3 rule parsed ({} prefix(es), {} premise(s), {} conclusion(s))",
-                        prefixes.len(),
-                        rule.premise.len(),
-                        rule.conclusion.len(),
-                    );
-
-                    // **IMPORTANT**: Register N3 prefixes with the database so SPARQL rules can use them
-                    for (prefix, uri) in prefixes {
-                        database
-                            .prefixes
-                            .insert(prefix.to_string(), uri.to_string());
-                    }
-
-                    // Register the rule and infer new facts
-                    kg.add_rule(rule);
-                    let inferred = kg.infer_new_facts_semi_naive();
-                    println!("N3 rule inferred {} fact(s)", inferred.len());
-
-                    // Push inferred triples into the database triple store
-                    for triple in inferred {
-                        database.triples.insert(triple);
-                    }
-
-                    // Sync the enriched dictionary back so SPARQL can decode the new terms
-                    database.dictionary = kg.dictionary.clone();
-
-                    if !database.triples.is_empty() {
-                        database.invalidate_stats_cache();
-                        database.get_or_build_stats();
-                        database.build_all_indexes();
-                    }
-                }
-                Err(e) => {
-                    eprintln!("N3 rule parse error: {:?}", e);
-                }
+        _ if has_rules(request.n3logic.as_deref(), &rules) => {
+            match copy_for_rules(dataset, rules_copy_max_triples()) {
+                Ok(database) => Some(database),
+                Err(response) => return response,
             }
         }
-    }
-
-    // Process all SPARQL-syntax rules (RULE :Name :- CONSTRUCT { } WHERE { })
-    for (idx, rule_def) in rules.iter().enumerate() {
-        let rule_def = strip_hash_comments(rule_def);
-        if !rule_def.trim().is_empty() {
-            println!("Processing rule {}...", idx + 1);
-            match process_rule_definition(&rule_def, &mut database) {
-                Ok((_, inferred_facts)) => {
-                    println!(
-                        "Rule {} processed, inferred {} facts",
-                        idx + 1,
-                        inferred_facts.len()
-                    );
-                    if !inferred_facts.is_empty() {
-                        database.invalidate_stats_cache();
-                        database.get_or_build_stats();
-                        database.build_all_indexes();
-                    }
-                }
-                Err(e) => {
-                    eprintln!("Rule {} processing error: {:?}", idx + 1, e);
-                }
-            }
+        _ => None,
+    };
+    if let Some(database) = &mut local {
+        if let Err(response) = apply_request_rules(database, request.n3logic.as_deref(), &rules) {
+            return response;
         }
     }
 
@@ -1078,33 +1809,57 @@ fn execute_sparql_with_context(body: &str) -> String {
     let mut all_results = Vec::new();
 
     for (idx, query) in queries.iter().enumerate() {
-        println!("Executing query {}/{}...", idx + 1, queries.len());
+        debug!("Executing query {}/{}...", idx + 1, queries.len());
         let start_time = std::time::Instant::now();
-        let executable_query = strip_hash_comments(query);
+        let executable_query = strip_query_comments(query);
 
-        let results = if use_optimizer {
-            execute_query_rayon_parallel2_volcano(&executable_query, &mut database)
-        } else {
-            execute_query(&executable_query, &mut database)
+        let profiler = profile.then(|| Arc::new(QueryProfiler::new()));
+        let context = QueryContext { timeout: Some(timeout), profiler: profiler.clone(), ..QueryContext::default() };
+        let result = match &mut local {
+            Some(database) => execute_query_with_context(&executable_query, database, &context).map(Some),
+            None => query_dataset(&executable_query, dataset, &context),
+        };
+        record_query(start_time, &result);
+        let results = match result {
+            Ok(Some(results)) => results,
+            Ok(None) => {
+                return json_error_response(&format!("Query {}: {}", idx + 1, UPDATE_ON_QUERY_PATH));
+            }
+            Err(e) => {
+                error!("Query {} failed: {}", idx + 1, e);
+                return json_error_with_status(error_status(&e), &format!("Query {}: {}", idx + 1, e));
+            }
         };
 
         let execution_time = start_time.elapsed().as_secs_f64() * 1000.0;
+        if let Some(profiler) = &profiler {
+            info!("{}", profiler.summary().trim_end());
+        }
 
         all_results.push(QueryResult {
             query_index: idx,
             query: query.clone(),
+            variables: query_result_variables(&executable_query),
             data: results,
             execution_time_ms: execution_time,
+            profile: profiler.map(|profiler| {
+                profiler.profiles().into_iter().map(OperatorProfileJson::from).collect()
+            }),
         });
     }
 
+    if let Some(format) = result_format {
+        let result = &all_results[0];
+        return sparql_results_response(format, &result.variables, &result.data);
+    }
+
     let response = QueryResponse {
         results: all_results,
     };
     let json = match serde_json::to_string(&response) {
         Ok(j) => j,
         Err(e) => {
-            eprintln!("Failed to serialize response: {}", e);
+            error!("Failed to serialize response: {}", e);
             return json_error_response("Failed to serialize results");
         }
     };
@@ -1123,16 +1878,120 @@ fn execute_sparql_with_context(body: &str) -> String {
     )
 }
 
+/// Whether a JSON `/query` request carries rules that would add inferences
+fn has_rules(n3logic: Option<&str>, rules: &[String]) -> bool {
+    n3logic.is_some_and(|text| has_n3_rule_text(&strip_hash_comments(text)))
+        || rules.iter().any(|rule| !strip_hash_comments(rule).trim().is_empty())
+}
+
+/// Copy the server dataset for a JSON `/query` request whose rules run on it.
+/// The copy costs as much memory and time as the dataset is large and is made
+/// again for every such request, so datasets above `max_triples` are refused;
+/// the error is the response asking the client to post its RDF instead.
+fn copy_for_rules(dataset: &Dataset, max_triples: Option<usize>) -> Result<SparqlDatabase, String> {
+    let database = dataset.read().unwrap();
+    let triples = database.triples.len();
+    if let Some(max_triples) = max_triples.filter(|&max_triples| triples > max_triples) {
+        return Err(json_error_response(&format!(
+            "Rules without posted RDF run on a copy of the server dataset, which holds {} triples \
+             (at most {} may be copied); post the RDF together with the rules",
+            triples, max_triples
+        )));
+    }
+    Ok(database.clone())
+}
+
+/// Most triples the server dataset may hold to be copied for a request's
+/// rules, or `None` when any size may be copied.
+fn rules_copy_max_triples() -> Option<usize> {
+    let max_triples = match std::env::var("KOLIBRIE_RULES_COPY_MAX_TRIPLES") {
+        Ok(value) => value.trim().parse::<usize>().unwrap_or_else(|_| {
+            warn!(
+                "Invalid KOLIBRIE_RULES_COPY_MAX_TRIPLES '{}', using {}",
+                value, DEFAULT_RULES_COPY_MAX_TRIPLES
+            );
+            DEFAULT_RULES_COPY_MAX_TRIPLES
+        }),
+        Err(_) => DEFAULT_RULES_COPY_MAX_TRIPLES,
+    };
+    (max_triples > 0).then_some(max_triples)
+}
+
+/// Add the inferences of a JSON `/query` request's rules to `database`; the
+/// error is the response for a rule that exceeded the inference budget
+fn apply_request_rules(database: &mut SparqlDatabase, n3logic: Option<&str>, rules: &[String]) -> Result<(), String> {
+    // Rules that never reach a fixpoint fail the request instead of the thread
+    database.inference_budget = Some(InferenceBudget::default());
+
+    // Process N3 logic rules (n3logic field).
+    // Syntax: @prefix declarations followed by { premise } => { conclusion } .
+    // This is completely separate from the SPARQL RULE syntax; the rules run
+    // through the datalog Reasoner and their prefixes are registered with the
+    // database so SPARQL rules can use them.
+    if let Some(n3_rules_text) = n3logic {
+        let n3_rules_text = strip_hash_comments(n3_rules_text);
+        if has_n3_rule_text(&n3_rules_text) {
+            debug!("Processing N3 logic rules from N3 Logic sub-tab...");
+            match database.apply_n3_rules(&n3_rules_text) {
+                Ok(inferred) => {
+                    debug!("N3 rules inferred {} fact(s)", inferred);
+                    if inferred > 0 {
+                        database.get_or_build_stats();
+                        database.build_all_indexes();
+                    }
+                }
+                Err(e @ SparqlError::InferenceBudgetExceeded(_)) => {
+                    error!("N3 rule error: {}", e);
+                    return Err(json_error_with_status(error_status(&e), &format!("N3 rules: {}", e)));
+                }
+                Err(e) => {
+                    error!("N3 rule error: {}", e);
+                }
+            }
+        }
+    }
+
+    // Process all SPARQL-syntax rules (RULE :Name :- CONSTRUCT { } WHERE { })
+    for (idx, rule_def) in rules.iter().enumerate() {
+        let rule_def = strip_hash_comments(rule_def);
+        if !rule_def.trim().is_empty() {
+            debug!("Processing rule {}...", idx + 1);
+            match try_process_rule_definition(&rule_def, database) {
+                Ok((_, inferred_facts)) => {
+                    debug!(
+                        "Rule {} processed, inferred {} facts",
+                        idx + 1,
+                        inferred_facts.len()
+                    );
+                    if !inferred_facts.is_empty() {
+                        database.invalidate_stats_cache();
+                        database.get_or_build_stats();
+                        database.build_all_indexes();
+                    }
+                }
+                Err(e @ SparqlError::InferenceBudgetExceeded(_)) => {
+                    error!("Rule {} processing error: {}", idx + 1, e);
+                    return Err(json_error_with_status(error_status(&e), &format!("Rule {}: {}", idx + 1, e)));
+                }
+                Err(e) => {
+                    error!("Rule {} processing error: {}", idx + 1, e);
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
 fn execute_rsp_query(body: &str) -> String {
     let request: RspQueryRequest = match serde_json::from_str(body) {
         Ok(req) => req,
         Err(e) => {
-            eprintln!("RSP JSON parse error: {}", e);
+            warn!("RSP JSON parse error: {}", e);
             return json_error_response(&format!("Invalid JSON: {}", e));
         }
     };
 
-    println!(
+    info!(
         "RSP: processing query with {} event(s), static_format={}",
         request.events.len(),
         request.static_format
@@ -1148,7 +2007,7 @@ fn execute_rsp_query(body: &str) -> String {
         }),
     };
 
-    let r2r = Box::new(SimpleR2R::with_execution_mode(QueryExecutionMode::Volcano));
+    let r2r = rsp_r2r();
 
     let start_time = std::time::Instant::now();
     let cleaned_query = strip_hash_comments(&request.query);
@@ -1163,36 +2022,37 @@ fn execute_rsp_query(body: &str) -> String {
         {
             Ok(e) => e,
             Err(e) => {
-                eprintln!("RSP build error: {}", e);
-                return json_error_response(&format!("Failed to build RSP engine: {}", e));
+                error!("RSP build error: {}", e);
+                return rsp_build_error_response(&e);
             }
         };
 
     // Load static background data from the SPARQL tab (if provided).
     if let Some(static_rdf) = &request.static_rdf {
         if !static_rdf.trim().is_empty() {
+            let static_format = resolve_rdf_format(&request.static_format, static_rdf);
             let cleaned_static_rdf;
-            let static_rdf_for_parse = match request.static_format.as_str() {
+            let static_rdf_for_parse = match static_format.as_str() {
                 "ntriples" | "turtle" => {
                     cleaned_static_rdf = strip_hash_comments(static_rdf);
                     cleaned_static_rdf.as_str()
                 }
                 _ => static_rdf.as_str(),
             };
-            let ntriples = match request.static_format.as_str() {
+            let ntriples = match static_format.as_str() {
                 "ntriples" => static_rdf_for_parse.to_string(),
                 _ => {
                     let mut static_db = SparqlDatabase::new();
-                    match request.static_format.as_str() {
+                    match static_format.as_str() {
                         "turtle" => static_db.parse_turtle(static_rdf_for_parse),
                         _ => static_db.parse_rdf(static_rdf_for_parse),
                     }
-                    db_to_ntriples(&static_db)
+                    static_db.generate_ntriples()
                 }
             };
             if !ntriples.is_empty() {
                 engine.add_static_ntriples(&ntriples);
-                println!(
+                debug!(
                     "RSP: loaded static data ({} bytes as N-Triples)",
                     ntriples.len()
                 );
@@ -1210,7 +2070,7 @@ fn execute_rsp_query(body: &str) -> String {
             continue;
         }
         let triples = engine.parse_data(&ntriples);
-        println!(
+        debug!(
             "RSP: pushing {} triple(s) to stream '{}' at t={}",
             triples.len(),
             event.stream,
@@ -1230,7 +2090,7 @@ fn execute_rsp_query(body: &str) -> String {
     let data = results_to_table(&results);
     let total_results = if data.len() > 1 { data.len() - 1 } else { 0 };
 
-    println!(
+    info!(
         "RSP: done — {} result row(s) in {:.2}ms",
         total_results, execution_time
     );
@@ -1243,7 +2103,7 @@ fn execute_rsp_query(body: &str) -> String {
     let json = match serde_json::to_string(&response) {
         Ok(j) => j,
         Err(e) => {
-            eprintln!("RSP serialization error: {}", e);
+            error!("RSP serialization error: {}", e);
             return json_error_response("Failed to serialize RSP results");
         }
     };
@@ -1266,7 +2126,52 @@ fn execute_rsp_query(body: &str) -> String {
 
 #[cfg(test)]
 mod tests {
-    use super::strip_hash_comments;
+    use super::{
+        copy_for_rules, dataset_argument, decode_request_body, error_status, forward_sse_events, handle_request,
+        is_sparql_update, profiling_requested,
+        reap_idle_sessions, requested_result_format, rsp_delete_session, rsp_push_batch, rsp_register,
+        strip_hash_comments, strip_query_comments, websocket, Dataset, HttpRequest, Sessions, SESSION_CLOSED_MESSAGE,
+    };
+    use flate2::write::GzEncoder;
+    use kolibrie::custom_error::SparqlError;
+    use kolibrie::execute_query::{execute_read_query, try_execute_query, QueryContext};
+    use kolibrie::sparql_database::SparqlDatabase;
+    use kolibrie::sparql_results::ResultFormat;
+    use std::io::Write;
+    use std::sync::{Arc, RwLock};
+    use std::time::{Duration, Instant};
+
+    /// Register a one-window session and return its id
+    fn register_session(sessions: &Sessions) -> String {
+        let register = serde_json::json!({
+            "query": "REGISTER ISTREAM <http://out/stream> AS SELECT * \
+                      FROM NAMED WINDOW :w ON ?stream [RANGE 3 STEP 1] \
+                      WHERE { WINDOW :w { ?s a <http://test/IType> . } }",
+        });
+        let response = rsp_register(&register.to_string(), sessions);
+        let body: serde_json::Value =
+            serde_json::from_str(response.split("\r\n\r\n").nth(1).unwrap()).unwrap();
+        body["session_id"].as_str().unwrap().to_string()
+    }
+
+    /// Payload of the next (unmasked) server text frame
+    fn read_server_text(stream: &mut std::net::TcpStream) -> String {
+        use std::io::Read;
+        let mut header = [0u8; 2];
+        stream.read_exact(&mut header).unwrap();
+        assert_eq!(header[0], 0x81, "expected a text frame");
+        let length = match header[1] {
+            126 => {
+                let mut extended = [0u8; 2];
+                stream.read_exact(&mut extended).unwrap();
+                u16::from_be_bytes(extended) as usize
+            }
+            length => length as usize,
+        };
+        let mut payload = vec![0u8; length];
+        stream.read_exact(&mut payload).unwrap();
+        String::from_utf8(payload).unwrap()
+    }
 
     #[test]
     fn strips_hash_comments_without_touching_iris_or_literals() {
@@ -1283,6 +2188,562 @@ FILTER(?s<3) \n\
 
         assert_eq!(strip_hash_comments(input), expected);
     }
+
+    #[test]
+    fn query_comments_keep_leading_planner_hints() {
+        let input = "# HINT USE_INDEX OSP\n\
+# a note\n\
+  # kolibrie:join hash\n\
+SELECT ?s WHERE { ?s ?p ?o } # HINT PARALLEL_JOIN\n\
+# HINT MAX_MEMORY_MB 0";
+        let expected = "# HINT USE_INDEX OSP\n\
+\n\
+  # kolibrie:join hash\n\
+SELECT ?s WHERE { ?s ?p ?o } \n";
+
+        assert_eq!(strip_query_comments(input), expected);
+    }
+
+    #[test]
+    fn query_hints_reach_the_planner() {
+        let dataset: Dataset = Arc::new(RwLock::new(SparqlDatabase::new()));
+        dataset
+            .write()
+            .unwrap()
+            .parse_ntriples_and_add("<http://ex.org/a> <http://ex.org/p> <http://ex.org/b> .\n");
+        let query = |sparql: &str| {
+            let request = HttpRequest {
+                method: "POST".to_string(),
+                path: "/query".to_string(),
+                headers: "POST /query HTTP/1.1\r\nContent-Type: application/json\r\n".to_string(),
+                body: serde_json::json!({ "sparql": sparql }).to_string().into_bytes(),
+            };
+            handle_request(&request, &Default::default(), &dataset)
+        };
+
+        let response = query("# HINT USE_INDEX OSP\n# kolibrie:join hash\nSELECT ?s ?o WHERE { ?s <http://ex.org/p> ?o }");
+        let json: serde_json::Value =
+            serde_json::from_str(response.split("\r\n\r\n").nth(1).unwrap()).unwrap();
+        assert_eq!(json["results"][0]["variables"], serde_json::json!(["s", "o"]), "{}", response);
+        assert_eq!(json["results"][0]["data"], serde_json::json!([["http://ex.org/a", "http://ex.org/b"]]));
+
+        // A hint the planner rejects fails the query instead of being dropped
+        let response = query("# kolibrie:join bogus\nSELECT ?s ?o WHERE { ?s <http://ex.org/p> ?o }");
+        assert!(response.starts_with("HTTP/1.1 400"), "{}", response);
+        assert!(response.contains("unknown join algorithm `bogus`"), "{}", response);
+    }
+
+    #[test]
+    fn distinguishes_sparql_updates_from_queries() {
+        assert!(is_sparql_update(
+            "PREFIX ex: <http://example.org/>\nINSERT DATA { ex:a ex:b ex:c . }"
+        ));
+        assert!(is_sparql_update("# comment\ndelete where { ?s ?p ?o }"));
+        assert!(!is_sparql_update(
+            "PREFIX ex: <http://example.org/>\nSELECT ?s WHERE { ?s ex:insert ?o }"
+        ));
+        assert!(!is_sparql_update("ASK { ?s ?p ?o }"));
+    }
+
+    #[test]
+    fn reads_the_profiling_header() {
+        assert!(profiling_requested("POST /query HTTP/1.1\r\nX-Kolibrie-Profile: true\r\n"));
+        assert!(profiling_requested("POST /query HTTP/1.1\r\nx-kolibrie-profile: TRUE\r\n"));
+        assert!(!profiling_requested("POST /query HTTP/1.1\r\nX-Kolibrie-Profile: false\r\n"));
+        assert!(!profiling_requested("POST /query HTTP/1.1\r\nContent-Type: application/json\r\n"));
+    }
+
+    #[test]
+    fn selects_the_result_format_from_accept_and_body() {
+        assert_eq!(requested_result_format(None, None), Ok(None));
+        assert_eq!(requested_result_format(Some("*/*"), None), Ok(None));
+        assert_eq!(requested_result_format(Some("application/json"), None), Ok(None));
+        assert_eq!(
+            requested_result_format(Some("application/sparql-results+xml, */*;q=0.1"), None),
+            Ok(Some(ResultFormat::Xml))
+        );
+        assert_eq!(requested_result_format(Some("text/csv"), None), Ok(Some(ResultFormat::Csv)));
+        assert_eq!(
+            requested_result_format(Some("text/csv"), Some("tsv")),
+            Ok(Some(ResultFormat::Tsv))
+        );
+        assert!(requested_result_format(None, Some("yaml")).is_err());
+    }
+
+    #[test]
+    fn batch_push_ingests_events_in_timestamp_order() {
+        let sessions: Sessions = Default::default();
+        let session_id = register_session(&sessions);
+
+        let (tx, rx) = std::sync::mpsc::channel();
+        *sessions.read().unwrap()[&session_id].lock().unwrap().sse_sender.lock().unwrap() = Some(tx);
+
+        // Out of order on purpose; each timestamp after the first fires a window
+        let events: Vec<serde_json::Value> = [3, 1, 4, 2]
+            .iter()
+            .map(|ts| {
+                serde_json::json!({
+                    "stream": "?stream",
+                    "timestamp": ts,
+                    "ntriples": format!("<http://test/s{}> a <http://test/IType> .", ts),
+                })
+            })
+            .collect();
+        let batch = serde_json::json!({ "session_id": session_id, "events": events });
+        let response = rsp_push_batch(&batch.to_string(), &sessions);
+        let body: serde_json::Value =
+            serde_json::from_str(response.split("\r\n\r\n").nth(1).unwrap()).unwrap();
+        assert_eq!(body["triples_ingested"], 4);
+        assert_eq!(body["rows_emitted"], 3);
+
+        let messages: Vec<String> = rx.try_iter().collect();
+        let rows: Vec<&String> = messages.iter().filter(|m| *m != "__FIRING_END__").collect();
+        assert_eq!(rows.len(), 3);
+        for (row, ts) in rows.iter().zip([1, 2, 3]) {
+            assert!(row.contains(&format!("s{}", ts)), "{:?}", rows);
+        }
+        assert_eq!(messages.last().map(String::as_str), Some("__FIRING_END__"));
+    }
+
+    #[test]
+    fn register_reports_where_an_rsp_ql_query_is_malformed() {
+        let sessions: Sessions = Default::default();
+        let register = |query: &str| {
+            let response = rsp_register(&serde_json::json!({ "query": query }).to_string(), &sessions);
+            assert!(response.starts_with("HTTP/1.1 400 Bad Request"), "{}", response);
+            serde_json::from_str::<serde_json::Value>(response.split("\r\n\r\n").nth(1).unwrap())
+                .unwrap()
+        };
+
+        let body = register(
+            "REGISTER ISTREAM <http://out/stream> AS SELECT *\n\
+             FROM NAMED WINDOW :w ON ?stream [RANGE soon STEP 1]\n\
+             WHERE { WINDOW :w { ?s a <http://test/IType> . } }",
+        );
+        assert_eq!(body["kind"], "parse_error");
+        assert_eq!(body["clause"], "FROM NAMED WINDOW");
+        assert_eq!(body["line"], 2);
+        assert_eq!(body["column"], 40);
+
+        let body = register(
+            "REGISTER ISTREAM <http://out/stream> AS SELECT *\n\
+             FROM NAMED WINDOW :w ON ?stream [RANGE 3 STEP 1]\n\
+             WHERE { WINDOW :other { ?s a <http://test/IType> . } }",
+        );
+        assert_eq!(body["clause"], "WINDOW");
+        assert!(body["error"].as_str().unwrap().contains(":other"));
+
+        let body = register(
+            "REGISTER NOSTREAM <http://out/stream> AS SELECT *\n\
+             FROM NAMED WINDOW :w ON ?stream [RANGE 3 STEP 1]\n\
+             WHERE { WINDOW :w { ?s a <http://test/IType> . } }",
+        );
+        assert_eq!(body["kind"], "unsupported_feature");
+        assert!(sessions.read().unwrap().is_empty());
+    }
+
+    #[test]
+    fn websocket_pushes_events_and_streams_rows_back() {
+        use std::io::Read;
+        let sessions: Sessions = Default::default();
+        let session_id = register_session(&sessions);
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        let server_sessions = Arc::clone(&sessions);
+        std::thread::spawn(move || {
+            let (stream, _) = listener.accept().unwrap();
+            let dataset: Dataset = Arc::new(RwLock::new(SparqlDatabase::new()));
+            super::handle_client(stream, server_sessions, dataset);
+        });
+
+        let mut client = std::net::TcpStream::connect(address).unwrap();
+        client.set_read_timeout(Some(Duration::from_secs(10))).unwrap();
+        write!(
+            client,
+            "GET /rsp/ws/{} HTTP/1.1\r\nHost: localhost\r\nUpgrade: websocket\r\n\
+             Connection: keep-alive, Upgrade\r\nSec-WebSocket-Key: dGhlIHNhbXBsZSBub25jZQ==\r\n\
+             Sec-WebSocket-Version: 13\r\n\r\n",
+            session_id
+        )
+        .unwrap();
+        let mut handshake = Vec::new();
+        while !handshake.ends_with(b"\r\n\r\n") {
+            let mut byte = [0u8; 1];
+            client.read_exact(&mut byte).unwrap();
+            handshake.push(byte[0]);
+        }
+        let handshake = String::from_utf8(handshake).unwrap();
+        assert!(handshake.starts_with("HTTP/1.1 101 Switching Protocols"), "{}", handshake);
+        assert!(handshake.contains("Sec-WebSocket-Accept: s3pPLMBiTxaQ9kYGzzhZRbK+xOo="), "{}", handshake);
+
+        let mut rows = Vec::new();
+        for ts in 1..=2 {
+            let push = serde_json::json!({
+                "session_id": session_id,
+                "stream": "?stream",
+                "timestamp": ts,
+                "ntriples": format!("<http://test/s{}> a <http://test/IType> .", ts),
+            });
+            client.write_all(&websocket::client_frame(true, 0x1, push.to_string().as_bytes())).unwrap();
+            loop {
+                let text = read_server_text(&mut client);
+                if text == r#"{"event":"firing"}"# {
+                    break;
+                }
+                rows.push(text);
+            }
+        }
+        assert_eq!(rows.len(), 1, "{:?}", rows);
+        assert!(rows[0].contains("s1"), "{:?}", rows);
+
+        let foreign = serde_json::json!({
+            "session_id": "other", "stream": "?stream", "timestamp": 3, "ntriples": "",
+        });
+        client.write_all(&websocket::client_frame(true, 0x1, foreign.to_string().as_bytes())).unwrap();
+        assert!(read_server_text(&mut client).contains("does not match"));
+
+        client.write_all(&websocket::client_frame(true, 0x8, &[])).unwrap();
+        let mut close = [0u8; 2];
+        client.read_exact(&mut close).unwrap();
+        assert_eq!(close, [0x88, 0x00]);
+    }
+
+    #[test]
+    fn websocket_upgrade_requires_the_handshake_headers() {
+        let headers = "GET /rsp/ws/x HTTP/1.1\r\nUpgrade: WebSocket\r\nConnection: Upgrade\r\n\
+                       Sec-WebSocket-Key: abc==\r\nSec-WebSocket-Version: 13\r\n";
+        assert_eq!(websocket::upgrade_key(headers), Some("abc=="));
+        assert_eq!(websocket::upgrade_key(&headers.replace("13", "8")), None);
+        assert_eq!(websocket::upgrade_key(&headers.replace("Connection: Upgrade", "Connection: close")), None);
+    }
+
+    #[test]
+    fn get_query_runs_the_url_encoded_query_against_the_dataset() {
+        let dataset: Dataset = Arc::new(RwLock::new(SparqlDatabase::new()));
+        dataset
+            .write()
+            .unwrap()
+            .parse_ntriples_and_add("<http://ex.org/a> <http://ex.org/p> \"one\" .\n");
+        let get = |path: &str| HttpRequest {
+            method: "GET".to_string(),
+            path: path.to_string(),
+            headers: "GET / HTTP/1.1\r\nAccept: text/csv\r\n".to_string(),
+            body: Vec::new(),
+        };
+
+        let response = handle_request(
+            &get("/query?query=SELECT%20%3Fo%20WHERE%20%7B%20%3Fs%20%3Chttp%3A%2F%2Fex.org%2Fp%3E%20%3Fo%20%7D\
+                  &default-graph-uri=http%3A%2F%2Fex.org%2Fg"),
+            &Default::default(),
+            &dataset,
+        );
+        assert!(response.starts_with("HTTP/1.1 200 OK"), "{}", response);
+        assert!(response.ends_with("\r\n\r\no\r\none\r\n"), "{}", response);
+
+        let missing = handle_request(&get("/query?default-graph-uri=x"), &Default::default(), &dataset);
+        assert!(missing.starts_with("HTTP/1.1 400 Bad Request"), "{}", missing);
+    }
+
+    #[test]
+    fn gzip_encoded_rdf_is_decompressed_before_the_query_runs() {
+        let payload = serde_json::json!({
+            "sparql": "SELECT ?o WHERE { ?s <http://ex.org/p> ?o }",
+            "rdf": "<http://ex.org/a> <http://ex.org/p> \"zipped\" .\n",
+            "format": "ntriples",
+        });
+        let mut encoder = GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(payload.to_string().as_bytes()).unwrap();
+        let compressed = encoder.finish().unwrap();
+
+        let headers = "POST /query HTTP/1.1\r\nContent-Type: application/json\r\nContent-Encoding: gzip\r\n";
+        let request = HttpRequest {
+            method: "POST".to_string(),
+            path: "/query".to_string(),
+            headers: headers.to_string(),
+            body: decode_request_body(headers, compressed).unwrap(),
+        };
+        let dataset: Dataset = Arc::new(RwLock::new(SparqlDatabase::new()));
+        let response = handle_request(&request, &Default::default(), &dataset);
+        assert!(response.starts_with("HTTP/1.1 200 OK"), "{}", response);
+        assert!(response.contains("zipped"), "{}", response);
+
+        let plain = "POST /query HTTP/1.1\r\nContent-Encoding: identity\r\n";
+        assert_eq!(decode_request_body(plain, b"data".to_vec()).unwrap(), b"data");
+        let brotli = "POST /query HTTP/1.1\r\nContent-Encoding: br\r\n";
+        let err = decode_request_body(brotli, b"data".to_vec()).unwrap_err();
+        assert_eq!(err.kind(), std::io::ErrorKind::Unsupported);
+        let corrupt = decode_request_body(headers, b"not gzip".to_vec()).unwrap_err();
+        assert_eq!(corrupt.kind(), std::io::ErrorKind::InvalidInput);
+    }
+
+    #[test]
+    fn json_query_on_posted_turtle_times_out_and_reports_errors() {
+        let mut turtle = String::from("@prefix ex: <http://example.org/> .\n");
+        for i in 0..500 {
+            turtle.push_str(&format!("ex:a{} ex:p \"x\" .\nex:b{} ex:q \"y\" .\n", i, i));
+        }
+        let dataset: Dataset = Arc::new(RwLock::new(SparqlDatabase::new()));
+        let query = |sparql: &str| {
+            let body = serde_json::json!({
+                "rdf": turtle,
+                "format": "turtle",
+                "sparql": sparql,
+                "timeout_ms": 1,
+            });
+            let request = HttpRequest {
+                method: "POST".to_string(),
+                path: "/query".to_string(),
+                headers: "POST /query HTTP/1.1\r\nContent-Type: application/json\r\n".to_string(),
+                body: body.to_string().into_bytes(),
+            };
+            handle_request(&request, &Default::default(), &dataset)
+        };
+
+        // ?a and ?b share no variable, so this is a 500 x 500 cross product
+        let response = query("PREFIX ex: <http://example.org/>\nSELECT ?a ?b WHERE { ?a ex:p ?x . ?b ex:q ?y }");
+        assert!(response.starts_with("HTTP/1.1 503"), "{}", response);
+        let response = query("SELEC ?a WHERE { ?a ?p ?o }");
+        assert!(response.starts_with("HTTP/1.1 400"), "{}", response);
+    }
+
+    #[test]
+    fn json_query_rules_run_on_a_copy_of_the_dataset() {
+        let dataset: Dataset = Arc::new(RwLock::new(SparqlDatabase::new()));
+        dataset
+            .write()
+            .unwrap()
+            .parse_ntriples_and_add("<http://ex.org/a> <http://ex.org/p> <http://ex.org/b> .\n");
+        let body = serde_json::json!({
+            "sparql": "SELECT ?s ?o WHERE { ?s <http://ex.org/q> ?o }",
+            "rule": "RULE :Copy :- CONSTRUCT { ?s <http://ex.org/q> ?o . } \
+                     WHERE { ?s <http://ex.org/p> ?o . }",
+        });
+        let request = HttpRequest {
+            method: "POST".to_string(),
+            path: "/query".to_string(),
+            headers: "POST /query HTTP/1.1\r\nContent-Type: application/json\r\n".to_string(),
+            body: body.to_string().into_bytes(),
+        };
+
+        let response = handle_request(&request, &Default::default(), &dataset);
+        let json: serde_json::Value =
+            serde_json::from_str(response.split("\r\n\r\n").nth(1).unwrap()).unwrap();
+        assert_eq!(
+            json["results"][0]["data"],
+            serde_json::json!([["http://ex.org/a", "http://ex.org/b"]]),
+            "{}",
+            response
+        );
+        // The rule's conclusions stay in the request's copy
+        assert_eq!(dataset.read().unwrap().triples.len(), 1);
+
+        // Without rules the query reads the dataset itself, and an update
+        // posted as a query is refused
+        let post = |sparql: &str| {
+            let request = HttpRequest {
+                method: "POST".to_string(),
+                path: "/query".to_string(),
+                headers: "POST /query HTTP/1.1\r\nContent-Type: application/json\r\n".to_string(),
+                body: serde_json::json!({ "sparql": sparql }).to_string().into_bytes(),
+            };
+            handle_request(&request, &Default::default(), &dataset)
+        };
+        let response = post("SELECT ?s ?o WHERE { ?s <http://ex.org/p> ?o }");
+        let json: serde_json::Value =
+            serde_json::from_str(response.split("\r\n\r\n").nth(1).unwrap()).unwrap();
+        assert_eq!(
+            json["results"][0]["data"],
+            serde_json::json!([["http://ex.org/a", "http://ex.org/b"]]),
+            "{}",
+            response
+        );
+        let response = post("INSERT { <http://ex.org/c> <http://ex.org/p> <http://ex.org/d> } WHERE { }");
+        assert!(response.starts_with("HTTP/1.1 400"), "{}", response);
+        assert!(response.contains("/update"), "{}", response);
+        assert_eq!(dataset.read().unwrap().triples.len(), 1);
+
+        // Datasets above the copy limit are refused instead of copied
+        dataset
+            .write()
+            .unwrap()
+            .parse_ntriples_and_add("<http://ex.org/c> <http://ex.org/p> <http://ex.org/d> .\n");
+        let response = copy_for_rules(&dataset, Some(1)).unwrap_err();
+        assert!(response.starts_with("HTTP/1.1 400"), "{}", response);
+        assert_eq!(copy_for_rules(&dataset, Some(2)).unwrap().triples.len(), 2);
+        assert_eq!(copy_for_rules(&dataset, None).unwrap().triples.len(), 2);
+
+        let args = ["--dataset", "data.ttl"].map(String::from);
+        assert_eq!(dataset_argument(args.into_iter()), Some("data.ttl".to_string()));
+        let args = ["--dataset=data.nt"].map(String::from);
+        assert_eq!(dataset_argument(args.into_iter()), Some("data.nt".to_string()));
+    }
+
+    #[test]
+    fn update_mutates_the_shared_dataset() {
+        let dataset: Dataset = Arc::new(RwLock::new(SparqlDatabase::new()));
+        let update = |text: &str| {
+            let request = HttpRequest {
+                method: "POST".to_string(),
+                path: "/update".to_string(),
+                headers: "POST /update HTTP/1.1\r\nContent-Type: application/sparql-update\r\n".to_string(),
+                body: text.as_bytes().to_vec(),
+            };
+            handle_request(&request, &Default::default(), &dataset)
+        };
+        // Updates leave the dataset prepared for queries under the read lock
+        let objects = || {
+            let database = dataset.read().unwrap();
+            let query = "SELECT ?s ?o WHERE { ?s <http://ex.org/p> ?o }";
+            let mut rows = execute_read_query(query, &database, &QueryContext::default()).unwrap();
+            rows.sort();
+            rows
+        };
+
+        let response = update(
+            "PREFIX ex: <http://ex.org/>\n\
+             INSERT DATA {\n  ex:a ex:p \"one\" .\n  ex:b ex:p \"two\" ; ex:q ex:c .\n}",
+        );
+        assert!(response.starts_with("HTTP/1.1 204 No Content"), "{}", response);
+        assert_eq!(objects(), vec![vec!["http://ex.org/a", "one"], vec!["http://ex.org/b", "two"]]);
+
+        let response = update(
+            "PREFIX ex: <http://ex.org/>\n\
+             DELETE { ?s ex:p ?o } INSERT { ?s ex:p \"renamed\" } WHERE { ?s ex:q ex:c . ?s ex:p ?o }",
+        );
+        assert!(response.starts_with("HTTP/1.1 204 No Content"), "{}", response);
+        assert_eq!(objects(), vec![vec!["http://ex.org/a", "one"], vec!["http://ex.org/b", "renamed"]]);
+
+        let response = update("DELETE DATA { <http://ex.org/a> <http://ex.org/p> \"one\" }");
+        assert!(response.starts_with("HTTP/1.1 204 No Content"), "{}", response);
+        assert_eq!(objects(), vec![vec!["http://ex.org/b", "renamed"]]);
+
+        let response = update("INSERT DATA { <http://ex.org/a> <http://ex.org/p> }");
+        assert!(response.starts_with("HTTP/1.1 400 Bad Request"), "{}", response);
+        assert!(response.contains("line 1"), "{}", response);
+        assert_eq!(dataset.read().unwrap().triples.len(), 2);
+    }
+
+    #[test]
+    fn health_reports_the_dataset_size() {
+        let dataset: Dataset = Arc::new(RwLock::new(SparqlDatabase::new()));
+        let health = || {
+            let request = HttpRequest {
+                method: "GET".to_string(),
+                path: "/health".to_string(),
+                headers: "GET /health HTTP/1.1\r\n".to_string(),
+                body: Vec::new(),
+            };
+            let response = handle_request(&request, &Default::default(), &dataset);
+            assert!(response.starts_with("HTTP/1.1 200 OK"), "{}", response);
+            serde_json::from_str::<serde_json::Value>(response.split("\r\n\r\n").nth(1).unwrap()).unwrap()
+        };
+
+        assert_eq!(health(), serde_json::json!({ "status": "ok", "triples": 0 }));
+        dataset
+            .write()
+            .unwrap()
+            .parse_ntriples_and_add("<http://ex.org/a> <http://ex.org/p> \"one\" .\n");
+        assert_eq!(health()["triples"], 1);
+    }
+
+    #[test]
+    fn deleting_a_session_ends_its_event_stream() {
+        let sessions: Sessions = Default::default();
+        let session_id = register_session(&sessions);
+        let (tx, rx) = std::sync::mpsc::channel();
+        *sessions.read().unwrap()[&session_id].lock().unwrap().sse_sender.lock().unwrap() = Some(tx);
+
+        assert!(rsp_delete_session(&session_id, &sessions).starts_with("HTTP/1.1 200 OK"));
+        assert!(sessions.read().unwrap().is_empty());
+        // The closing event arrives, then the channel disconnects
+        let messages: Vec<String> = rx.iter().collect();
+        assert_eq!(messages, vec![SESSION_CLOSED_MESSAGE.to_string()]);
+
+        assert!(rsp_delete_session(&session_id, &sessions).starts_with("HTTP/1.1 404 Not Found"));
+    }
+
+    #[test]
+    fn reaper_drops_only_idle_sessions() {
+        let sessions: Sessions = Default::default();
+        let idle = register_session(&sessions);
+        let active = register_session(&sessions);
+        let (tx, rx) = std::sync::mpsc::channel();
+        {
+            let lock = sessions.read().unwrap();
+            let mut session = lock[&idle].lock().unwrap();
+            session.last_activity = Instant::now() - Duration::from_secs(10 * 60);
+            *session.sse_sender.lock().unwrap() = Some(tx);
+        }
+
+        let reaped = reap_idle_sessions(&sessions, Duration::from_secs(5 * 60));
+        assert_eq!(reaped, vec![idle]);
+        assert!(sessions.read().unwrap().contains_key(&active));
+        assert_eq!(rx.iter().collect::<Vec<_>>(), vec![SESSION_CLOSED_MESSAGE.to_string()]);
+    }
+
+    #[test]
+    fn reaper_keeps_sessions_whose_event_stream_is_written() {
+        let sessions: Sessions = Default::default();
+        let session_id = register_session(&sessions);
+        let session = sessions.read().unwrap()[&session_id].clone();
+        session.lock().unwrap().last_activity = Instant::now() - Duration::from_secs(10 * 60);
+
+        // A keep-alive reaching the SSE client counts as activity
+        let (tx, rx) = std::sync::mpsc::channel::<String>();
+        let producer = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(100));
+            drop(tx);
+        });
+        let mut out = Vec::new();
+        forward_sse_events(rx, &mut out, Some(Duration::from_millis(20)), || session.lock().unwrap().touch());
+        producer.join().unwrap();
+        assert!(out.starts_with(b": keepalive\n\n"));
+
+        assert!(reap_idle_sessions(&sessions, Duration::from_secs(5 * 60)).is_empty());
+        assert!(sessions.read().unwrap().contains_key(&session_id));
+    }
+
+    #[test]
+    fn sse_stream_sends_keepalives_while_idle() {
+        let (tx, rx) = std::sync::mpsc::channel::<String>();
+        let producer = std::thread::spawn(move || {
+            std::thread::sleep(Duration::from_millis(100));
+            tx.send("{\"s\":\"a\"}".to_string()).unwrap();
+            std::thread::sleep(Duration::from_millis(100));
+        });
+
+        let mut out = Vec::new();
+        forward_sse_events(rx, &mut out, Some(Duration::from_millis(20)), || {});
+        producer.join().unwrap();
+
+        let out = String::from_utf8(out).unwrap();
+        let (before, after) = out.split_once("data: {\"s\":\"a\"}\n\n").unwrap();
+        // Keep-alives are comments, on both sides of the only data event
+        assert!(before.starts_with(": keepalive\n\n"), "{:?}", out);
+        assert!(after.starts_with(": keepalive\n\n"), "{:?}", out);
+        assert_eq!(out.matches("data:").count(), 1);
+        assert!(out
+            .split("\n\n")
+            .filter(|chunk| !chunk.is_empty())
+            .all(|chunk| chunk == ": keepalive" || chunk.starts_with("data: ")));
+    }
+
+    #[test]
+    fn maps_query_errors_to_status_codes() {
+        let mut database = SparqlDatabase::new();
+        let err = try_execute_query("SELEC ?s WHERE { ?s ?p ?o }", &mut database).unwrap_err();
+        assert!(matches!(err, SparqlError::ParseError(_)));
+        assert_eq!(error_status(&err), "400 Bad Request");
+
+        assert_eq!(
+            error_status(&SparqlError::ExecutionError("boom".to_string())),
+            "500 Internal Server Error"
+        );
+        assert_eq!(
+            error_status(&SparqlError::Timeout(Duration::from_secs(1))),
+            "503 Service Unavailable"
+        );
+    }
 }
 
 fn json_ok() -> String {
@@ -1302,19 +2763,49 @@ fn json_ok() -> String {
 }
 
 fn json_error_response(message: &str) -> String {
+    json_error_with_status("400 Bad Request", message)
+}
+
+fn json_error_with_status(status: &str, message: &str) -> String {
     let error = ErrorResponse {
         error: message.to_string(),
     };
-    let json = serde_json::to_string(&error)
+    json_body_with_status(status, &error)
+}
+
+fn rsp_build_error_response(error: &RspBuildError) -> String {
+    if matches!(error, RspBuildError::ParseError { .. }) {
+        METRICS.record_parse_error();
+    }
+    let (kind, clause, position) = match error {
+        RspBuildError::ParseError { clause, position, .. } => {
+            ("parse_error", Some(clause.clone()), *position)
+        }
+        RspBuildError::UnsupportedFeature(_) => ("unsupported_feature", None, None),
+        RspBuildError::MissingConsumer(_) => ("missing_consumer", None, None),
+    };
+    let body = RspBuildErrorResponse {
+        error: format!("Failed to build RSP engine: {}", error),
+        kind,
+        clause,
+        line: position.map(|(line, _)| line),
+        column: position.map(|(_, column)| column),
+    };
+    json_body_with_status("400 Bad Request", &body)
+}
+
+fn json_body_with_status<T: Serialize>(status: &str, body: &T) -> String {
+    let json = serde_json::to_string(body)
         .unwrap_or_else(|_| r#"{"error":"Internal server error"}"#.to_string());
 
     format!(
-        "HTTP/1.1 400 Bad Request\r\n\
+        "HTTP/1.1 {}\r\n\
          Content-Type: application/json\r\n\
          Content-Length: {}\r\n\
          Access-Control-Allow-Origin: *\r\n\
          \r\n\
          {}",
+        status,
         json.len(),
         json
     )
@@ -1323,8 +2814,8 @@ fn json_error_response(message: &str) -> String {
 fn cors_response() -> String {
     "HTTP/1.1 204 No Content\r\n\
      Access-Control-Allow-Origin: *\r\n\
-     Access-Control-Allow-Methods: POST, GET, OPTIONS\r\n\
-     Access-Control-Allow-Headers: Content-Type\r\n\
+     Access-Control-Allow-Methods: POST, GET, DELETE, OPTIONS\r\n\
+     Access-Control-Allow-Headers: Content-Type, Content-Encoding, Accept, X-Kolibrie-Profile\r\n\
      \r\n"
         .to_string()
 }
@@ -1342,3 +2833,75 @@ fn error_response(code: u16, message: &str) -> String {
         message
     )
 }
+
+fn bad_request(message: &str) -> String {
+    format!(
+        "HTTP/1.1 400 Bad Request\r\n\
+         Content-Type: text/plain; charset=UTF-8\r\n\
+         Content-Length: {}\r\n\
+         Access-Control-Allow-Origin: *\r\n\
+         \r\n\
+         {}",
+        message.len(),
+        message
+    )
+}
+
+/// Status line for a failed query: client mistakes are 4xx, engine failures 5xx
+fn error_status(error: &SparqlError) -> &'static str {
+    match error {
+        SparqlError::ParseError(_) | SparqlError::TypeError(_) => "400 Bad Request",
+        // The client has to narrow the query to get a complete answer
+        SparqlError::CartesianProductTooLarge { .. } | SparqlError::JoinResultLimitExceeded(_) => {
+            "400 Bad Request"
+        }
+        // The posted rules do not reach a fixpoint within the budget
+        SparqlError::InferenceBudgetExceeded(_) => "400 Bad Request",
+        SparqlError::UnsupportedFeature(_) => "501 Not Implemented",
+        SparqlError::ExecutionError(_) => "500 Internal Server Error",
+        SparqlError::Timeout(_) => "503 Service Unavailable",
+    }
+}
+
+/// Count a finished SPARQL query, and its parse error if it had one
+fn record_query<T>(start_time: Instant, result: &Result<T, SparqlError>) {
+    METRICS.record_query(start_time.elapsed(), result.is_ok());
+    if let Err(SparqlError::ParseError(_)) = result {
+        METRICS.record_parse_error();
+    }
+}
+
+/// `GET /metrics`: server counters in the Prometheus text exposition format
+fn metrics_response(sessions: &Sessions) -> String {
+    let active_sessions = sessions.read().unwrap().len();
+    let body = METRICS.render(active_sessions);
+    format!(
+        "HTTP/1.1 200 OK\r\n\
+         Content-Type: text/plain; version=0.0.4; charset=utf-8\r\n\
+         Content-Length: {}\r\n\
+         \r\n\
+         {}",
+        body.len(),
+        body
+    )
+}
+
+fn health_response(dataset: &Dataset) -> String {
+    let triples = dataset.read().unwrap().count_triples();
+    json_body_with_status("200 OK", &HealthResponse { status: "ok", triples })
+}
+
+fn sparql_error_response(error: &SparqlError) -> String {
+    let message = error.to_string();
+    format!(
+        "HTTP/1.1 {}\r\n\
+         Content-Type: text/plain; charset=UTF-8\r\n\
+         Content-Length: {}\r\n\
+         Access-Control-Allow-Origin: *\r\n\
+         \r\n\
+         {}",
+        error_status(error),
+        message.len(),
+        message
+    )
+}
@@ -0,0 +1,209 @@
+/*
+ * Copyright © 2026 Volodymyr Kadzhaia
+ * Copyright © 2026 Pieter Bonte
+ * KU Leuven — Stream Intelligence Lab, Belgium
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this file,
+ * you can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+//! Server counters exposed on `GET /metrics` in the Prometheus text format.
+
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Mutex;
+use std::time::Duration;
+
+/// Upper bounds, in seconds, of the query latency histogram buckets
+const LATENCY_BUCKETS: [f64; 11] = [0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+
+pub static METRICS: Metrics = Metrics::new();
+
+pub struct Metrics {
+    queries: AtomicU64,
+    query_errors: AtomicU64,
+    parse_errors: AtomicU64,
+    /// Queries per latency bucket; the last slot counts those above every bound
+    latency_buckets: [AtomicU64; LATENCY_BUCKETS.len() + 1],
+    latency_sum_micros: AtomicU64,
+    sessions_created: AtomicU64,
+    sessions_closed: AtomicU64,
+    triples_ingested: Mutex<BTreeMap<String, u64>>,
+}
+
+impl Metrics {
+    pub const fn new() -> Self {
+        Metrics {
+            queries: AtomicU64::new(0),
+            query_errors: AtomicU64::new(0),
+            parse_errors: AtomicU64::new(0),
+            latency_buckets: [const { AtomicU64::new(0) }; LATENCY_BUCKETS.len() + 1],
+            latency_sum_micros: AtomicU64::new(0),
+            sessions_created: AtomicU64::new(0),
+            sessions_closed: AtomicU64::new(0),
+            triples_ingested: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// Count a query and its latency; `succeeded` is false when it returned an error
+    pub fn record_query(&self, elapsed: Duration, succeeded: bool) {
+        self.queries.fetch_add(1, Ordering::Relaxed);
+        if !succeeded {
+            self.query_errors.fetch_add(1, Ordering::Relaxed);
+        }
+        let seconds = elapsed.as_secs_f64();
+        let bucket = LATENCY_BUCKETS
+            .iter()
+            .position(|bound| seconds <= *bound)
+            .unwrap_or(LATENCY_BUCKETS.len());
+        self.latency_buckets[bucket].fetch_add(1, Ordering::Relaxed);
+        self.latency_sum_micros
+            .fetch_add(elapsed.as_micros() as u64, Ordering::Relaxed);
+    }
+
+    /// A SPARQL query or RSP-QL registration was rejected as unparsable
+    pub fn record_parse_error(&self) {
+        self.parse_errors.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_session_created(&self) {
+        self.sessions_created.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_session_closed(&self) {
+        self.sessions_closed.fetch_add(1, Ordering::Relaxed);
+    }
+
+    pub fn record_ingested(&self, stream: &str, triples: usize) {
+        if triples == 0 {
+            return;
+        }
+        let mut ingested = self.triples_ingested.lock().unwrap();
+        *ingested.entry(stream.to_string()).or_insert(0) += triples as u64;
+    }
+
+    /// The exposition text; `active_sessions` is read from the session table
+    /// by the caller so the gauge cannot drift from it
+    pub fn render(&self, active_sessions: usize) -> String {
+        let mut out = String::new();
+        let counter = |out: &mut String, name: &str, help: &str, value: u64| {
+            let _ = writeln!(out, "# HELP {} {}", name, help);
+            let _ = writeln!(out, "# TYPE {} counter", name);
+            let _ = writeln!(out, "{} {}", name, value);
+        };
+
+        counter(
+            &mut out,
+            "kolibrie_queries_total",
+            "SPARQL queries executed.",
+            self.queries.load(Ordering::Relaxed),
+        );
+        counter(
+            &mut out,
+            "kolibrie_query_errors_total",
+            "SPARQL queries that returned an error.",
+            self.query_errors.load(Ordering::Relaxed),
+        );
+        counter(
+            &mut out,
+            "kolibrie_parse_errors_total",
+            "SPARQL queries and RSP-QL registrations rejected as unparsable.",
+            self.parse_errors.load(Ordering::Relaxed),
+        );
+
+        let _ = writeln!(out, "# HELP kolibrie_query_duration_seconds SPARQL query latency.");
+        let _ = writeln!(out, "# TYPE kolibrie_query_duration_seconds histogram");
+        let mut cumulative = 0;
+        for (bound, bucket) in LATENCY_BUCKETS.iter().zip(&self.latency_buckets) {
+            cumulative += bucket.load(Ordering::Relaxed);
+            let _ = writeln!(
+                out,
+                "kolibrie_query_duration_seconds_bucket{{le=\"{}\"}} {}",
+                bound, cumulative
+            );
+        }
+        cumulative += self.latency_buckets[LATENCY_BUCKETS.len()].load(Ordering::Relaxed);
+        let _ = writeln!(out, "kolibrie_query_duration_seconds_bucket{{le=\"+Inf\"}} {}", cumulative);
+        let _ = writeln!(
+            out,
+            "kolibrie_query_duration_seconds_sum {}",
+            self.latency_sum_micros.load(Ordering::Relaxed) as f64 / 1e6
+        );
+        let _ = writeln!(out, "kolibrie_query_duration_seconds_count {}", cumulative);
+
+        let _ = writeln!(out, "# HELP kolibrie_rsp_sessions_active RSP sessions currently registered.");
+        let _ = writeln!(out, "# TYPE kolibrie_rsp_sessions_active gauge");
+        let _ = writeln!(out, "kolibrie_rsp_sessions_active {}", active_sessions);
+        counter(
+            &mut out,
+            "kolibrie_rsp_sessions_created_total",
+            "RSP sessions registered.",
+            self.sessions_created.load(Ordering::Relaxed),
+        );
+        counter(
+            &mut out,
+            "kolibrie_rsp_sessions_closed_total",
+            "RSP sessions deleted or expired.",
+            self.sessions_closed.load(Ordering::Relaxed),
+        );
+
+        let _ = writeln!(out, "# HELP kolibrie_rsp_triples_ingested_total Triples pushed to RSP streams.");
+        let _ = writeln!(out, "# TYPE kolibrie_rsp_triples_ingested_total counter");
+        for (stream, triples) in self.triples_ingested.lock().unwrap().iter() {
+            let _ = writeln!(
+                out,
+                "kolibrie_rsp_triples_ingested_total{{stream=\"{}\"}} {}",
+                escape_label_value(stream),
+                triples
+            );
+        }
+        out
+    }
+}
+
+fn escape_label_value(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Metrics;
+    use std::time::Duration;
+
+    #[test]
+    fn renders_counters_histogram_and_stream_labels() {
+        let metrics = Metrics::new();
+        metrics.record_query(Duration::from_millis(3), true);
+        metrics.record_query(Duration::from_millis(300), false);
+        metrics.record_query(Duration::from_secs(60), true);
+        metrics.record_parse_error();
+        metrics.record_session_created();
+        metrics.record_ingested(":stream1", 4);
+        metrics.record_ingested(":stream1", 2);
+        metrics.record_ingested("http://ex.org/\"s\"", 1);
+
+        let text = metrics.render(1);
+        for line in [
+            "kolibrie_queries_total 3",
+            "kolibrie_query_errors_total 1",
+            "kolibrie_parse_errors_total 1",
+            "kolibrie_query_duration_seconds_bucket{le=\"0.005\"} 1",
+            "kolibrie_query_duration_seconds_bucket{le=\"0.25\"} 1",
+            "kolibrie_query_duration_seconds_bucket{le=\"0.5\"} 2",
+            "kolibrie_query_duration_seconds_bucket{le=\"+Inf\"} 3",
+            "kolibrie_query_duration_seconds_count 3",
+            "kolibrie_rsp_sessions_active 1",
+            "kolibrie_rsp_sessions_created_total 1",
+            "kolibrie_rsp_sessions_closed_total 0",
+            "kolibrie_rsp_triples_ingested_total{stream=\":stream1\"} 6",
+            "kolibrie_rsp_triples_ingested_total{stream=\"http://ex.org/\\\"s\\\"\"} 1",
+        ] {
+            assert!(text.lines().any(|l| l == line), "missing `{}` in:\n{}", line, text);
+        }
+    }
+}
@@ -0,0 +1,254 @@
+/*
+ * Copyright © 2026 Volodymyr Kadzhaia
+ * Copyright © 2026 Pieter Bonte
+ * KU Leuven — Stream Intelligence Lab, Belgium
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this file,
+ * you can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+//! The subset of RFC 6455 behind `GET /rsp/ws/{session_id}`: the opening
+//! handshake and the frame codec. No extensions or subprotocols are
+//! negotiated.
+
+use crate::header_value;
+use base64::engine::general_purpose::STANDARD;
+use base64::Engine;
+use std::io::{self, Read, Write};
+
+/// Appended to the client's key before hashing (RFC 6455, section 1.3)
+const HANDSHAKE_GUID: &str = "258EAFA5-E914-47DA-95CA-C5AB0DC85B11";
+/// Largest message accepted from a client, matching the HTTP request limit
+const MAX_MESSAGE_SIZE: usize = 64 * 1024 * 1024;
+
+const OPCODE_CONTINUATION: u8 = 0x0;
+const OPCODE_TEXT: u8 = 0x1;
+const OPCODE_BINARY: u8 = 0x2;
+const OPCODE_CLOSE: u8 = 0x8;
+const OPCODE_PING: u8 = 0x9;
+const OPCODE_PONG: u8 = 0xA;
+
+/// A complete message from the client, with fragments reassembled
+#[derive(Debug, PartialEq)]
+pub enum Message {
+    Text(String),
+    Binary(Vec<u8>),
+    Ping(Vec<u8>),
+    Pong(Vec<u8>),
+    Close,
+}
+
+/// The `Sec-WebSocket-Key` of a valid upgrade request, or `None` when the
+/// headers do not ask for a version 13 WebSocket.
+pub fn upgrade_key(headers: &str) -> Option<&str> {
+    let upgrade = header_value(headers, "Upgrade")?;
+    let connection = header_value(headers, "Connection")?;
+    let version = header_value(headers, "Sec-WebSocket-Version")?;
+    let upgrades_connection = connection
+        .split(',')
+        .any(|token| token.trim().eq_ignore_ascii_case("upgrade"));
+    if !upgrade.eq_ignore_ascii_case("websocket") || !upgrades_connection || version.trim() != "13" {
+        return None;
+    }
+    header_value(headers, "Sec-WebSocket-Key").filter(|key| !key.is_empty())
+}
+
+/// `Sec-WebSocket-Accept` value proving the handshake to the client
+pub fn accept_key(client_key: &str) -> String {
+    let mut hasher = sha1_smol::Sha1::new();
+    hasher.update(client_key.trim().as_bytes());
+    hasher.update(HANDSHAKE_GUID.as_bytes());
+    STANDARD.encode(hasher.digest().bytes())
+}
+
+/// `101 Switching Protocols` response completing the handshake
+pub fn handshake_response(client_key: &str) -> String {
+    format!(
+        "HTTP/1.1 101 Switching Protocols\r\n\
+         Upgrade: websocket\r\n\
+         Connection: Upgrade\r\n\
+         Sec-WebSocket-Accept: {}\r\n\
+         \r\n",
+        accept_key(client_key)
+    )
+}
+
+/// Reads client messages, keeping a fragmented data message across the
+/// control frames that may arrive between its fragments.
+pub struct MessageReader<R> {
+    reader: R,
+    /// Opcode and payload of a data message still waiting for its last fragment
+    partial: Option<(u8, Vec<u8>)>,
+}
+
+impl<R: Read> MessageReader<R> {
+    pub fn new(reader: R) -> Self {
+        MessageReader { reader, partial: None }
+    }
+
+    /// Read the next message. Control frames are returned as soon as they
+    /// arrive, even between the fragments of a data message.
+    pub fn read_message(&mut self) -> io::Result<Message> {
+        loop {
+            let (fin, opcode, frame) = read_frame(&mut self.reader)?;
+            let (message_opcode, mut payload) = match (opcode, self.partial.take()) {
+                (OPCODE_CLOSE, partial) => {
+                    self.partial = partial;
+                    return Ok(Message::Close);
+                }
+                (OPCODE_PING, partial) => {
+                    self.partial = partial;
+                    return Ok(Message::Ping(frame));
+                }
+                (OPCODE_PONG, partial) => {
+                    self.partial = partial;
+                    return Ok(Message::Pong(frame));
+                }
+                (OPCODE_TEXT | OPCODE_BINARY, None) => (opcode, Vec::new()),
+                (OPCODE_CONTINUATION, Some(partial)) => partial,
+                _ => return Err(invalid_data(format!("unexpected opcode {:#x}", opcode))),
+            };
+
+            payload.extend_from_slice(&frame);
+            if payload.len() > MAX_MESSAGE_SIZE {
+                return Err(invalid_data("message too large".to_string()));
+            }
+            if !fin {
+                self.partial = Some((message_opcode, payload));
+                continue;
+            }
+            return match message_opcode {
+                OPCODE_TEXT => String::from_utf8(payload)
+                    .map(Message::Text)
+                    .map_err(|_| invalid_data("text message is not UTF-8".to_string())),
+                _ => Ok(Message::Binary(payload)),
+            };
+        }
+    }
+}
+
+/// One frame as `(fin, opcode, unmasked payload)`
+fn read_frame<R: Read>(reader: &mut R) -> io::Result<(bool, u8, Vec<u8>)> {
+    let mut header = [0u8; 2];
+    reader.read_exact(&mut header)?;
+    let fin = header[0] & 0x80 != 0;
+    let opcode = header[0] & 0x0F;
+    if header[1] & 0x80 == 0 {
+        return Err(invalid_data("client frames must be masked".to_string()));
+    }
+
+    let length = match header[1] & 0x7F {
+        126 => {
+            let mut extended = [0u8; 2];
+            reader.read_exact(&mut extended)?;
+            u16::from_be_bytes(extended) as u64
+        }
+        127 => {
+            let mut extended = [0u8; 8];
+            reader.read_exact(&mut extended)?;
+            u64::from_be_bytes(extended)
+        }
+        length => length as u64,
+    };
+    if length > MAX_MESSAGE_SIZE as u64 {
+        return Err(invalid_data("message too large".to_string()));
+    }
+
+    let mut mask = [0u8; 4];
+    reader.read_exact(&mut mask)?;
+    let mut payload = vec![0u8; length as usize];
+    reader.read_exact(&mut payload)?;
+    for (i, byte) in payload.iter_mut().enumerate() {
+        *byte ^= mask[i % 4];
+    }
+    Ok((fin, opcode, payload))
+}
+
+pub fn write_text<W: Write>(writer: &mut W, text: &str) -> io::Result<()> {
+    write_frame(writer, OPCODE_TEXT, text.as_bytes())
+}
+
+pub fn write_pong<W: Write>(writer: &mut W, payload: &[u8]) -> io::Result<()> {
+    write_frame(writer, OPCODE_PONG, payload)
+}
+
+pub fn write_close<W: Write>(writer: &mut W) -> io::Result<()> {
+    write_frame(writer, OPCODE_CLOSE, &[])
+}
+
+/// Write a single unfragmented frame; server frames are never masked
+fn write_frame<W: Write>(writer: &mut W, opcode: u8, payload: &[u8]) -> io::Result<()> {
+    let mut frame = Vec::with_capacity(payload.len() + 10);
+    frame.push(0x80 | opcode);
+    match payload.len() {
+        length if length < 126 => frame.push(length as u8),
+        length if length <= u16::MAX as usize => {
+            frame.push(126);
+            frame.extend_from_slice(&(length as u16).to_be_bytes());
+        }
+        length => {
+            frame.push(127);
+            frame.extend_from_slice(&(length as u64).to_be_bytes());
+        }
+    }
+    frame.extend_from_slice(payload);
+    writer.write_all(&frame)?;
+    writer.flush()
+}
+
+/// A masked client frame, as a browser would send it
+#[cfg(test)]
+pub fn client_frame(fin: bool, opcode: u8, payload: &[u8]) -> Vec<u8> {
+    let mask = [0x37, 0xfa, 0x21, 0x3d];
+    let mut frame = vec![if fin { 0x80 | opcode } else { opcode }];
+    match payload.len() {
+        length if length < 126 => frame.push(0x80 | length as u8),
+        length => {
+            frame.push(0x80 | 126);
+            frame.extend_from_slice(&(length as u16).to_be_bytes());
+        }
+    }
+    frame.extend_from_slice(&mask);
+    frame.extend(payload.iter().enumerate().map(|(i, byte)| byte ^ mask[i % 4]));
+    frame
+}
+
+fn invalid_data(message: String) -> io::Error {
+    io::Error::new(io::ErrorKind::InvalidData, message)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn computes_the_rfc_accept_key() {
+        assert_eq!(accept_key("dGhlIHNhbXBsZSBub25jZQ=="), "s3pPLMBiTxaQ9kYGzzhZRbK+xOo=");
+    }
+
+    #[test]
+    fn reassembles_fragmented_text_between_control_frames() {
+        let mut input = client_frame(false, OPCODE_TEXT, b"Hel");
+        input.extend(client_frame(true, OPCODE_PING, b"?"));
+        input.extend(client_frame(true, OPCODE_CONTINUATION, b"lo"));
+        let mut reader = MessageReader::new(input.as_slice());
+
+        assert_eq!(reader.read_message().unwrap(), Message::Ping(b"?".to_vec()));
+        assert_eq!(reader.read_message().unwrap(), Message::Text("Hello".to_string()));
+    }
+
+    #[test]
+    fn rejects_unmasked_client_frames() {
+        let mut reader = MessageReader::new(&[0x81, 0x01, b'x'][..]);
+        assert_eq!(reader.read_message().unwrap_err().kind(), io::ErrorKind::InvalidData);
+    }
+
+    #[test]
+    fn writes_extended_lengths() {
+        let mut out = Vec::new();
+        write_text(&mut out, &"a".repeat(300)).unwrap();
+        assert_eq!(&out[..4], &[0x81, 126, 0x01, 0x2c]);
+        assert_eq!(out.len(), 304);
+    }
+}
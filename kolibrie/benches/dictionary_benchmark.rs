@@ -0,0 +1,189 @@
+/*
+ * Copyright © 2026 Volodymyr Kadzhaia
+ * Copyright © 2026 Pieter Bonte
+ * KU Leuven — Stream Intelligence Lab, Belgium
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this file,
+ * you can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+//! Dictionary encode/decode on a synthetic dataset.
+//!
+//! Compares the previous map layouts (`BTreeMap` and SipHash `HashMap`)
+//! with `Dictionary`, whose maps hash with `shared::simd_hash`. The dataset
+//! defaults to 10M triples; set `KOLIBRIE_BENCH_TRIPLES` to change it.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use shared::dictionary::Dictionary;
+use shared::simd_hash::{hash_bytes, hash_bytes_scalar};
+use std::collections::{BTreeMap, HashMap};
+
+struct Dataset {
+    terms: Vec<String>,
+    triples: Vec<[u32; 3]>,
+}
+
+fn triple_count() -> usize {
+    std::env::var("KOLIBRIE_BENCH_TRIPLES")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(10_000_000)
+}
+
+fn generate_dataset(triples: usize) -> Dataset {
+    let subjects = (triples / 10).max(1);
+    let predicates = 64;
+    let objects = (triples / 5).max(1);
+
+    let mut terms = Vec::with_capacity(subjects + predicates + objects);
+    for i in 0..subjects {
+        terms.push(format!("http://example.org/resource/person/{:010}", i));
+    }
+    for i in 0..predicates {
+        terms.push(format!("http://example.org/ontology/property{}", i));
+    }
+    for i in 0..objects {
+        terms.push(format!("http://example.org/resource/value/{:010}", i));
+    }
+
+    // Fixed LCG so every run sees the same access pattern
+    let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+    let mut next = |bound: usize| {
+        state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        ((state >> 33) as usize) % bound
+    };
+    let triples = (0..triples)
+        .map(|_| {
+            [
+                next(subjects) as u32,
+                (subjects + next(predicates)) as u32,
+                (subjects + predicates + next(objects)) as u32,
+            ]
+        })
+        .collect();
+
+    Dataset { terms, triples }
+}
+
+fn encode_all<F: FnMut(&str) -> u32>(dataset: &Dataset, mut encode: F) -> u64 {
+    let mut checksum = 0u64;
+    for triple in &dataset.triples {
+        for &term in triple {
+            checksum = checksum.wrapping_add(encode(&dataset.terms[term as usize]) as u64);
+        }
+    }
+    checksum
+}
+
+fn bench_encode(c: &mut Criterion, dataset: &Dataset) {
+    let mut group = c.benchmark_group("dictionary_encode");
+    group.sample_size(10);
+    group.throughput(Throughput::Elements(dataset.triples.len() as u64 * 3));
+
+    group.bench_function("btreemap", |b| {
+        b.iter(|| {
+            let mut map: BTreeMap<String, u32> = BTreeMap::new();
+            let mut next_id = 0u32;
+            black_box(encode_all(dataset, |value| {
+                if let Some(&id) = map.get(value) {
+                    return id;
+                }
+                let id = next_id;
+                map.insert(value.to_string(), id);
+                next_id += 1;
+                id
+            }))
+        })
+    });
+
+    group.bench_function("hashmap_siphash", |b| {
+        b.iter(|| {
+            let mut map: HashMap<String, u32> = HashMap::new();
+            let mut next_id = 0u32;
+            black_box(encode_all(dataset, |value| {
+                if let Some(&id) = map.get(value) {
+                    return id;
+                }
+                let id = next_id;
+                map.insert(value.to_string(), id);
+                next_id += 1;
+                id
+            }))
+        })
+    });
+
+    group.bench_function("dictionary_simd", |b| {
+        b.iter(|| {
+            let mut dict = Dictionary::new();
+            black_box(encode_all(dataset, |value| dict.encode(value)))
+        })
+    });
+
+    group.finish();
+}
+
+fn bench_decode(c: &mut Criterion, dataset: &Dataset) {
+    let mut dict = Dictionary::new();
+    let mut encoded = Vec::with_capacity(dataset.triples.len() * 3);
+    for triple in &dataset.triples {
+        for &term in triple {
+            encoded.push(dict.encode(&dataset.terms[term as usize]));
+        }
+    }
+    let mut by_index = vec![String::new(); dict.next_id as usize];
+    for (id, value) in &dict.id_to_string {
+        by_index[*id as usize] = value.clone();
+    }
+
+    let mut group = c.benchmark_group("dictionary_decode");
+    group.sample_size(10);
+    group.throughput(Throughput::Elements(encoded.len() as u64));
+
+    group.bench_function("vec_index", |b| {
+        b.iter(|| {
+            let mut total = 0usize;
+            for &id in &encoded {
+                total += by_index[id as usize].len();
+            }
+            black_box(total)
+        })
+    });
+
+    group.bench_function("dictionary_simd", |b| {
+        b.iter(|| {
+            let mut total = 0usize;
+            for &id in &encoded {
+                total += dict.decode(id).map_or(0, str::len);
+            }
+            black_box(total)
+        })
+    });
+
+    group.finish();
+}
+
+fn bench_hash(c: &mut Criterion, dataset: &Dataset) {
+    let sample: Vec<&str> = dataset.terms.iter().take(100_000).map(String::as_str).collect();
+    let bytes: u64 = sample.iter().map(|s| s.len() as u64).sum();
+
+    let mut group = c.benchmark_group("term_hash");
+    group.throughput(Throughput::Bytes(bytes));
+    group.bench_function("scalar", |b| {
+        b.iter(|| sample.iter().fold(0u64, |acc, s| acc ^ hash_bytes_scalar(s.as_bytes())))
+    });
+    group.bench_function("simd", |b| {
+        b.iter(|| sample.iter().fold(0u64, |acc, s| acc ^ hash_bytes(s.as_bytes())))
+    });
+    group.finish();
+}
+
+fn dictionary_benchmarks(c: &mut Criterion) {
+    let dataset = generate_dataset(triple_count());
+    bench_hash(c, &dataset);
+    bench_encode(c, &dataset);
+    bench_decode(c, &dataset);
+}
+
+criterion_group!(benches, dictionary_benchmarks);
+criterion_main!(benches);
@@ -0,0 +1,111 @@
+/*
+ * Copyright © 2026 Volodymyr Kadzhaia
+ * Copyright © 2026 Pieter Bonte
+ * KU Leuven — Stream Intelligence Lab, Belgium
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this file,
+ * you can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+//! Index and table scans of SPO, SP, PO and P-only patterns, and of a full
+//! scan, on synthetic datasets of 10k, 100k and 1M triples.
+//!
+//! Each group runs the pattern through `ExecutionEngine` as an `IndexScan`
+//! and as a `TableScan`, and matches it against the triples with
+//! `shared::simd_scan::for_each_match` and with the scalar
+//! `BoundIds::matches`. Throughput is reported per stored triple, so the
+//! time per iteration divided by the dataset size is the latency per triple.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use kolibrie::sparql_database::SparqlDatabase;
+use kolibrie::streamertail_optimizer::{ExecutionEngine, PhysicalOperator};
+use shared::simd_scan::{for_each_match, BoundIds};
+use shared::terms::{Term, TriplePattern};
+use shared::triple::Triple;
+
+const SIZES: [usize; 3] = [10_000, 100_000, 1_000_000];
+
+fn generate_triples(count: usize) -> Vec<Triple> {
+    let subjects = (count / 10).max(1);
+    let predicates = 64;
+    let objects = 1000;
+
+    // Fixed LCG so every run scans the same data
+    let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+    let mut next = |bound: usize| {
+        state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        ((state >> 33) as usize) % bound
+    };
+    (0..count)
+        .map(|_| Triple {
+            subject: next(subjects) as u32,
+            predicate: next(predicates) as u32,
+            object: next(objects) as u32,
+        })
+        .collect()
+}
+
+fn pattern(bound: &BoundIds) -> TriplePattern {
+    let term = |id: Option<u32>, var: &str| match id {
+        Some(id) => Term::Constant(id),
+        None => Term::Variable(var.to_string()),
+    };
+    (
+        term(bound.subject, "s"),
+        term(bound.predicate, "p"),
+        term(bound.object, "o"),
+    )
+}
+
+fn bench_index_scan(c: &mut Criterion) {
+    for size in SIZES {
+        let triples = generate_triples(size);
+        let mut database = SparqlDatabase::new();
+        database.triples = triples.iter().cloned().collect();
+        database.build_all_indexes();
+
+        // Bind positions to a stored triple so every lookup has a match
+        let probe = triples[size / 2].clone();
+        let patterns = [
+            ("spo", BoundIds { subject: Some(probe.subject), predicate: Some(probe.predicate), object: Some(probe.object) }),
+            ("sp", BoundIds { subject: Some(probe.subject), predicate: Some(probe.predicate), object: None }),
+            ("po", BoundIds { subject: None, predicate: Some(probe.predicate), object: Some(probe.object) }),
+            ("p", BoundIds { subject: None, predicate: Some(probe.predicate), object: None }),
+            ("full_scan", BoundIds { subject: None, predicate: None, object: None }),
+        ];
+
+        for (name, bound) in patterns {
+            let mut group = c.benchmark_group(format!("index_scan_{}/{}", name, size));
+            group.sample_size(10);
+            group.throughput(Throughput::Elements(size as u64));
+
+            let index_scan = PhysicalOperator::index_scan(pattern(&bound));
+            group.bench_function("index_scan", |b| {
+                b.iter(|| black_box(ExecutionEngine::execute_with_ids(&index_scan, &database).unwrap().len()))
+            });
+
+            let table_scan = PhysicalOperator::TableScan { pattern: pattern(&bound) };
+            group.bench_function("table_scan", |b| {
+                b.iter(|| black_box(ExecutionEngine::execute_with_ids(&table_scan, &database).unwrap().len()))
+            });
+
+            group.bench_function("simd_match", |b| {
+                b.iter(|| {
+                    let mut matches = 0usize;
+                    for_each_match(&triples, &bound, |_| matches += 1);
+                    black_box(matches)
+                })
+            });
+
+            group.bench_function("scalar_match", |b| {
+                b.iter(|| black_box(triples.iter().filter(|triple| bound.matches(triple)).count()))
+            });
+
+            group.finish();
+        }
+    }
+}
+
+criterion_group!(benches, bench_index_scan);
+criterion_main!(benches);
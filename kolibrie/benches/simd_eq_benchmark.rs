@@ -0,0 +1,70 @@
+/*
+ * Copyright © 2026 Volodymyr Kadzhaia
+ * Copyright © 2026 Pieter Bonte
+ * KU Leuven — Stream Intelligence Lab, Belgium
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this file,
+ * you can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+//! `simd_eq` against the standard slice comparison on 10k random string
+//! pairs, for IRI-sized and long-literal-sized terms. On CPUs with
+//! `avx512bw` `simd_eq` takes the AVX-512 path, elsewhere SSE2 or NEON.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use kolibrie::sparql_database::simd_eq;
+
+const PAIRS: usize = 10_000;
+
+/// Pairs of equal-length strings; every other pair differs in one byte
+fn generate_pairs(len: usize) -> Vec<(String, String)> {
+    // Fixed LCG so every run compares the same strings
+    let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+    let mut next = |bound: usize| {
+        state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        ((state >> 33) as usize) % bound
+    };
+    (0..PAIRS)
+        .map(|i| {
+            let left: String = (0..len).map(|_| (b'a' + next(26) as u8) as char).collect();
+            let mut right = left.clone().into_bytes();
+            if i % 2 == 1 {
+                let at = next(len);
+                right[at] = if right[at] == b'z' { b'a' } else { right[at] + 1 };
+            }
+            (left, String::from_utf8(right).unwrap())
+        })
+        .collect()
+}
+
+fn bench_string_eq(c: &mut Criterion) {
+    for len in [48, 256] {
+        let pairs = generate_pairs(len);
+        let mut group = c.benchmark_group(format!("string_eq_{}_bytes", len));
+        group.throughput(Throughput::Elements(PAIRS as u64));
+
+        group.bench_function("slice_eq", |b| {
+            b.iter(|| {
+                pairs
+                    .iter()
+                    .filter(|(l, r)| black_box(l.as_bytes()) == black_box(r.as_bytes()))
+                    .count()
+            })
+        });
+
+        group.bench_function("simd_eq", |b| {
+            b.iter(|| {
+                pairs
+                    .iter()
+                    .filter(|(l, r)| unsafe { simd_eq(black_box(l.as_bytes()), black_box(r.as_bytes())) })
+                    .count()
+            })
+        });
+
+        group.finish();
+    }
+}
+
+criterion_group!(benches, bench_string_eq);
+criterion_main!(benches);
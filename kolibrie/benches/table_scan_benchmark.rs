@@ -0,0 +1,100 @@
+/*
+ * Copyright © 2026 Volodymyr Kadzhaia
+ * Copyright © 2026 Pieter Bonte
+ * KU Leuven — Stream Intelligence Lab, Belgium
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this file,
+ * you can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+//! Table scan of a pattern with bound positions on a synthetic dataset.
+//!
+//! Compares a `par_iter().filter_map` over the triples with
+//! `shared::simd_scan::for_each_match`, sequentially and over parallel
+//! blocks.
+//! The dataset defaults to 10M triples; set `KOLIBRIE_BENCH_TRIPLES` to
+//! change it.
+
+use criterion::{black_box, criterion_group, criterion_main, Criterion, Throughput};
+use rayon::prelude::*;
+use shared::simd_scan::{for_each_match, BoundIds, SCAN_CHUNK};
+use shared::triple::Triple;
+
+fn triple_count() -> usize {
+    std::env::var("KOLIBRIE_BENCH_TRIPLES")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(10_000_000)
+}
+
+fn generate_triples(count: usize) -> Vec<Triple> {
+    let subjects = (count / 10).max(1);
+    let predicates = 64;
+    let objects = 1000;
+
+    // Fixed LCG so every run scans the same data
+    let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+    let mut next = |bound: usize| {
+        state = state.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+        ((state >> 33) as usize) % bound
+    };
+    (0..count)
+        .map(|_| Triple {
+            subject: next(subjects) as u32,
+            predicate: next(predicates) as u32,
+            object: next(objects) as u32,
+        })
+        .collect()
+}
+
+fn bench_scan(c: &mut Criterion) {
+    let triples = generate_triples(triple_count());
+    let patterns = [
+        ("predicate", BoundIds { subject: None, predicate: Some(7), object: None }),
+        ("predicate_object", BoundIds { subject: None, predicate: Some(7), object: Some(42) }),
+    ];
+
+    for (name, bound) in patterns {
+        let mut group = c.benchmark_group(format!("table_scan_{}", name));
+        group.sample_size(10);
+        group.throughput(Throughput::Elements(triples.len() as u64));
+
+        group.bench_function("par_iter_filter_map", |b| {
+            b.iter(|| {
+                let matches: Vec<&Triple> = triples
+                    .par_iter()
+                    .filter_map(|triple| bound.matches(triple).then_some(triple))
+                    .collect();
+                black_box(matches.len())
+            })
+        });
+
+        group.bench_function("simd_chunks", |b| {
+            b.iter(|| {
+                let mut matches = Vec::new();
+                for_each_match(&triples, &bound, |i| matches.push(&triples[i]));
+                black_box(matches.len())
+            })
+        });
+
+        group.bench_function("par_simd_chunks", |b| {
+            b.iter(|| {
+                let matches: Vec<&Triple> = triples
+                    .par_chunks(SCAN_CHUNK * 1024)
+                    .flat_map_iter(|block| {
+                        let mut matches = Vec::new();
+                        for_each_match(block, &bound, |i| matches.push(&block[i]));
+                        matches
+                    })
+                    .collect();
+                black_box(matches.len())
+            })
+        });
+
+        group.finish();
+    }
+}
+
+criterion_group!(benches, bench_scan);
+criterion_main!(benches);
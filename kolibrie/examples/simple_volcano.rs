@@ -141,7 +141,7 @@ fn simple_scan_example(database: &mut SparqlDatabase) {
 
     // Execute the plan
     let start = Instant::now();
-    let results = physical_plan.execute(database);
+    let results = physical_plan.execute(database).expect("query execution failed");
     let execution_time = start.elapsed();
 
     println!("Execution completed in {:?}", execution_time);
@@ -186,7 +186,7 @@ fn join_example(database: &mut SparqlDatabase) {
 
     // Execute the plan
     let start = Instant::now();
-    let results = physical_plan.execute(database);
+    let results = physical_plan.execute(database).expect("query execution failed");
     let execution_time = start.elapsed();
 
     println!("Execution completed in {:?}", execution_time);
@@ -225,7 +225,7 @@ fn filter_example(database: &mut SparqlDatabase) {
 
     // Execute the plan
     let start = Instant::now();
-    let results = physical_plan.execute(database);
+    let results = physical_plan.execute(database).expect("query execution failed");
     let execution_time = start.elapsed();
 
     println!("Execution completed in {:?}", execution_time);
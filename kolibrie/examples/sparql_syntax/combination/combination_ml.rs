@@ -115,7 +115,7 @@ RULE :TemperatureAlert :-
                     
                     println!("Physical plan optimized.");
                     
-                    let ml_results = optimizer.execute_plan(&physical_plan, &mut database);
+                    let ml_results = optimizer.execute_plan(&physical_plan, &database).expect("query execution failed");
                     
                     let predictions = process_ml_results(ml_results);
                     
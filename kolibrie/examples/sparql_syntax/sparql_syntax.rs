@@ -70,7 +70,7 @@ fn streamertail_optimizer_sparql() {
             patterns.clone(),
             filters.clone(),
             &prefixes.clone(),
-            &mut database,
+            &database,
             &[],
             None,
         );
@@ -84,7 +84,7 @@ fn streamertail_optimizer_sparql() {
 
         // Step 6: Execute the physical plan
         let start = Instant::now();
-        let results = physical_plan.execute(&mut database);
+        let results = physical_plan.execute(&database).expect("query execution failed");
         let duration = start.elapsed();
 
         // Step 7: Extract and print the selected variables
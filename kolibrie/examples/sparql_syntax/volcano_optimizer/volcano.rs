@@ -78,7 +78,7 @@ fn simple_streamertail_optimizer() {
 
     // Step 4: Execute the optimized plan
     let start = Instant::now();
-    let results = best_plan.execute(&mut database);
+    let results = best_plan.execute(&database).expect("query execution failed");
     let duration = start.elapsed();
 
     println!("Query execution time: {:?}", duration);
@@ -164,7 +164,7 @@ fn streamertail_optimizer_multiple_triples() {
 
     // Step 5: Execute the optimized plan
     let start = Instant::now();
-    let results = best_plan.execute(&mut database);
+    let results = best_plan.execute(&database).expect("query execution failed");
     let duration = start.elapsed();
 
     println!("Query execution time: {:?}", duration);
@@ -219,7 +219,7 @@ fn streamertail_optimizer_rdf() {
     let mut prefixes = std::collections::HashMap::new();
     prefixes.insert("ex".to_string(), "http://example.org/".to_string());
 
-    let logical_plan = build_logical_plan(variables, patterns, filters, &prefixes, &mut database, &[], None);
+    let logical_plan = build_logical_plan(variables, patterns, filters, &prefixes, &database, &[], None);
 
     // Step 4: Initialize the optimizer and optimize
     let mut optimizer = Streamertail::new(&database);
@@ -230,7 +230,7 @@ fn streamertail_optimizer_rdf() {
 
     // Step 5: Execute the optimized plan
     let start = Instant::now();
-    let results = best_plan.execute(&mut database);
+    let results = best_plan.execute(&database).expect("query execution failed");
     let duration = start.elapsed();
 
     println!("Query execution time: {:?}", duration);
@@ -348,7 +348,7 @@ fn streamertail_optimizer_index_patterns() {
 
         // Execute the best plan
         let start = Instant::now();
-        let results = best_plan.execute(&mut database);
+        let results = best_plan.execute(&database).expect("query execution failed");
         let duration = start.elapsed();
 
         println!("Execution time: {:?}", duration);
@@ -423,7 +423,7 @@ fn streamertail_optimizer_performance_test() {
     let optimization_time1 = start.elapsed();
 
     let start = Instant::now();
-    let results1 = physical_plan1.execute(&mut database);
+    let results1 = physical_plan1.execute(&database).expect("query execution failed");
     let execution_time1 = start.elapsed();
 
     println!("Optimization time: {:?}", optimization_time1);
@@ -452,7 +452,7 @@ fn streamertail_optimizer_performance_test() {
     let optimization_time2 = start.elapsed();
 
     let start = Instant::now();
-    let results2 = physical_plan2.execute(&mut database);
+    let results2 = physical_plan2.execute(&database).expect("query execution failed");
     let execution_time2 = start.elapsed();
 
     println!("Optimization time: {:?}", optimization_time2);
@@ -482,7 +482,7 @@ fn streamertail_optimizer_performance_test() {
     let optimization_time3 = start.elapsed();
 
     let start = Instant::now();
-    let results3 = physical_plan3.execute(&mut database);
+    let results3 = physical_plan3.execute(&database).expect("query execution failed");
     let execution_time3 = start.elapsed();
 
     println!("Optimization time: {:?}", optimization_time3);
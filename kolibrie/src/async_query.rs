@@ -0,0 +1,36 @@
+/*
+ * Copyright © 2026 Volodymyr Kadzhaia
+ * Copyright © 2026 Pieter Bonte
+ * KU Leuven — Stream Intelligence Lab, Belgium
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this file,
+ * you can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+//! Non-blocking query entry points for async runtimes (enabled by the `async` feature)
+
+use crate::custom_error::SparqlError;
+use crate::execute_query::{execute_shared_query, QueryContext};
+use crate::sparql_database::SharedSparqlDatabase;
+use log::error;
+
+/// Run a SPARQL query on tokio's blocking thread pool so the calling executor
+/// thread stays free while the query runs.
+///
+/// SELECT queries hold the read lock, as in `execute_shared_query`, so
+/// concurrent queries do not wait for each other. Parse and execution
+/// failures, a poisoned lock and a panic inside the query are returned as a
+/// `SparqlError`.
+pub async fn execute_query_async(
+    query: String,
+    db: SharedSparqlDatabase,
+) -> Result<Vec<Vec<String>>, SparqlError> {
+    let handle = tokio::task::spawn_blocking(move || {
+        execute_shared_query(&query, &db, &QueryContext::default())
+    });
+    handle.await.unwrap_or_else(|err| {
+        error!("Async query task failed: {}", err);
+        Err(SparqlError::ExecutionError(format!("async query task failed: {}", err)))
+    })
+}
@@ -0,0 +1,94 @@
+/*
+ * Copyright © 2026 Volodymyr Kadzhaia
+ * Copyright © 2026 Pieter Bonte
+ * KU Leuven — Stream Intelligence Lab, Belgium
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this file,
+ * you can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use flate2::read::GzDecoder;
+use std::fs::File;
+use std::io::{self, BufRead, BufReader, Read};
+use std::path::Path;
+
+/// Compression applied to an RDF file or request body.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Compression {
+    None,
+    Gzip,
+    Zstd,
+}
+
+impl Compression {
+    /// Compression implied by the extension of `path`: `.gz` is gzip and
+    /// `.zst` is zstd; anything else is read as is.
+    pub fn from_path(path: impl AsRef<Path>) -> Self {
+        match path.as_ref().extension().and_then(|ext| ext.to_str()) {
+            Some(ext) if ext.eq_ignore_ascii_case("gz") => Compression::Gzip,
+            Some(ext) if ext.eq_ignore_ascii_case("zst") => Compression::Zstd,
+            _ => Compression::None,
+        }
+    }
+
+    /// Compression named by an HTTP `Content-Encoding` value, or `None` for
+    /// a coding Kolibrie cannot decode.
+    pub fn from_content_encoding(encoding: &str) -> Option<Self> {
+        match encoding.trim().to_ascii_lowercase().as_str() {
+            "" | "identity" => Some(Compression::None),
+            "gzip" | "x-gzip" => Some(Compression::Gzip),
+            "zstd" => Some(Compression::Zstd),
+            _ => None,
+        }
+    }
+
+    /// Wrap `reader` so that reads return the decompressed bytes.
+    pub fn decoder<'a, R: Read + 'a>(&self, reader: R) -> io::Result<Box<dyn Read + 'a>> {
+        Ok(match self {
+            Compression::None => Box::new(reader),
+            Compression::Gzip => Box::new(GzDecoder::new(reader)),
+            Compression::Zstd => Box::new(zstd::Decoder::new(reader)?),
+        })
+    }
+}
+
+/// Open `path` for reading, decompressing it on the fly when its extension
+/// is `.gz` or `.zst`.
+pub fn open_rdf_file(path: impl AsRef<Path>) -> io::Result<Box<dyn BufRead>> {
+    let path = path.as_ref();
+    let file = File::open(path)?;
+    Ok(match Compression::from_path(path) {
+        Compression::None => Box::new(BufReader::new(file)),
+        compression => Box::new(BufReader::new(compression.decoder(file)?)),
+    })
+}
+
+/// Read the whole of `path` as UTF-8, decompressing it first when its
+/// extension is `.gz` or `.zst`.
+pub fn read_rdf_file(path: impl AsRef<Path>) -> io::Result<String> {
+    let mut data = String::new();
+    open_rdf_file(path)?.read_to_string(&mut data)?;
+    Ok(data)
+}
+
+/// Decompress `data`. Corrupt input fails with `InvalidInput`; output larger
+/// than `max_bytes` fails with `InvalidData`, so a small compressed body
+/// cannot expand without bound.
+pub fn decompress(data: &[u8], compression: Compression, max_bytes: usize) -> io::Result<Vec<u8>> {
+    if compression == Compression::None {
+        return Ok(data.to_vec());
+    }
+    let mut output = Vec::new();
+    compression
+        .decoder(data)
+        .and_then(|decoder| decoder.take(max_bytes as u64 + 1).read_to_end(&mut output))
+        .map_err(|e| io::Error::new(io::ErrorKind::InvalidInput, e))?;
+    if output.len() > max_bytes {
+        return Err(io::Error::new(
+            io::ErrorKind::InvalidData,
+            format!("decompressed body exceeds {} byte limit", max_bytes),
+        ));
+    }
+    Ok(output)
+}
@@ -0,0 +1,65 @@
+/*
+ * Copyright © 2026 Volodymyr Kadzhaia
+ * Copyright © 2026 Pieter Bonte
+ * KU Leuven — Stream Intelligence Lab, Belgium
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this file,
+ * you can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use datalog::reasoning::materialisation::infer_generic::BudgetExceeded;
+use std::fmt;
+use std::time::Duration;
+
+/// Errors reported by the query entry points instead of an empty result
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum SparqlError {
+    /// The query text is not valid SPARQL; carries the formatted diagnostic
+    ParseError(String),
+    /// The query parsed but could not be evaluated
+    ExecutionError(String),
+    /// An operand had the wrong type for the operation applied to it
+    TypeError(String),
+    /// The query uses a construct the engine does not implement
+    UnsupportedFeature(String),
+    /// The query ran longer than the allowed duration and was cancelled
+    Timeout(Duration),
+    /// Two join inputs share no variable and their cross product would
+    /// exceed `SparqlDatabase::max_join_results`
+    CartesianProductTooLarge { left: usize, right: usize, limit: usize },
+    /// A join produced more rows than `SparqlDatabase::max_join_results`
+    JoinResultLimitExceeded(usize),
+    /// Rule inference ran past `SparqlDatabase::inference_budget`
+    InferenceBudgetExceeded(BudgetExceeded),
+}
+
+/// Earlier name of `SparqlError`
+pub type CustomError = SparqlError;
+
+impl fmt::Display for SparqlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SparqlError::ParseError(message) => write!(f, "Failed to parse the query: {}", message),
+            SparqlError::ExecutionError(message) => write!(f, "Query execution failed: {}", message),
+            SparqlError::TypeError(message) => write!(f, "Type error: {}", message),
+            SparqlError::UnsupportedFeature(feature) => write!(f, "Unsupported feature: {}", feature),
+            SparqlError::Timeout(limit) => {
+                write!(f, "Query exceeded the time limit of {} ms", limit.as_millis())
+            }
+            SparqlError::CartesianProductTooLarge { left, right, limit } => write!(
+                f,
+                "Cartesian product of {} x {} rows exceeds the limit of {} rows; the query is probably missing a join variable",
+                left, right, limit
+            ),
+            SparqlError::JoinResultLimitExceeded(limit) => {
+                write!(f, "Join produced more than the limit of {} rows", limit)
+            }
+            SparqlError::InferenceBudgetExceeded(exceeded) => {
+                write!(f, "Rule inference stopped: {}", exceeded)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SparqlError {}
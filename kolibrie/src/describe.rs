@@ -0,0 +1,69 @@
+/*
+ * Copyright © 2026 Volodymyr Kadzhaia
+ * Copyright © 2026 Pieter Bonte
+ * KU Leuven — Stream Intelligence Lab, Belgium
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this file,
+ * you can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use crate::sparql_database::SparqlDatabase;
+use shared::triple::Triple;
+use std::collections::HashSet;
+use std::sync::Arc;
+
+/// Concise Bounded Description of a resource, as defined by the W3C member
+/// submission: every triple with the resource as subject, plus, recursively,
+/// the triples of each blank node reached as an object. The recursion stops
+/// at IRIs and literals.
+pub struct ConciseBoundedDescription;
+
+impl ConciseBoundedDescription {
+    /// Description of `resource` (a full IRI, `<IRI>`, prefixed name or `_:label`)
+    /// as a database sharing `db`'s dictionary. Unknown resources give an
+    /// empty database.
+    pub fn compute(resource: &str, db: &SparqlDatabase) -> SparqlDatabase {
+        let mut description = SparqlDatabase::new();
+        description.dictionary = Arc::clone(&db.dictionary);
+        description.quoted_triple_store = Arc::clone(&db.quoted_triple_store);
+        description.prefixes = db.prefixes.clone();
+
+        let resource = if resource.starts_with("_:") {
+            resource.to_string()
+        } else {
+            db.resolve_query_term(resource, &db.prefixes)
+        };
+        let Some(root) = db.dictionary.read().unwrap().string_to_id.get(&resource).copied() else {
+            return description;
+        };
+
+        let dict = db.dictionary.read().unwrap();
+        let is_blank = |id: u32| dict.decode(id).is_some_and(|term| term.starts_with("_:"));
+
+        // Each subject is expanded once, which also ends cycles between blank nodes
+        let mut visited = HashSet::from([root]);
+        let mut pending = vec![root];
+        let mut triples = Vec::new();
+        while let Some(subject) = pending.pop() {
+            for triple in Self::triples_with_subject(db, subject) {
+                if is_blank(triple.object) && visited.insert(triple.object) {
+                    pending.push(triple.object);
+                }
+                triples.push(triple.clone());
+            }
+        }
+        drop(dict);
+
+        for triple in triples {
+            description.add_triple(triple);
+        }
+        description
+    }
+
+    fn triples_with_subject(db: &SparqlDatabase, subject: u32) -> impl Iterator<Item = &Triple> {
+        let first = Triple { subject, predicate: 0, object: 0 };
+        let last = Triple { subject, predicate: u32::MAX, object: u32::MAX };
+        db.triples.range(first..=last)
+    }
+}
@@ -0,0 +1,149 @@
+/*
+ * Copyright © 2026 Volodymyr Kadzhaia
+ * Copyright © 2026 Pieter Bonte
+ * KU Leuven — Stream Intelligence Lab, Belgium
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this file,
+ * you can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+//! Human-readable rendering of query results.
+
+/// Cell width `kolibrie-cli` truncates table cells to unless told otherwise
+pub const DEFAULT_MAX_CELL_WIDTH: usize = 80;
+
+/// Render query results as an aligned ASCII table in the style of `psql`:
+/// a header row, a separator, one line per row and a row count footer.
+/// Every column is as wide as its widest cell; rows shorter than the header
+/// are padded with empty cells.
+pub fn format_table(headers: &[String], rows: &[Vec<String>]) -> String {
+    format_table_with_max_width(headers, rows, usize::MAX)
+}
+
+/// `format_table` with cells longer than `max_cell_width` characters cut
+/// short and ended with `…`, so one long literal cannot widen a column past
+/// the terminal.
+pub fn format_table_with_max_width(headers: &[String], rows: &[Vec<String>], max_cell_width: usize) -> String {
+    let max_cell_width = max_cell_width.max(1);
+    let columns = headers
+        .len()
+        .max(rows.iter().map(|row| row.len()).max().unwrap_or(0));
+    let cell = |row: &[String], column: usize| -> String {
+        // Line breaks inside literals would break the alignment
+        let value = row.get(column)
+            .map(|value| value.replace('\n', "\\n"))
+            .unwrap_or_default();
+        if value.chars().count() > max_cell_width {
+            value.chars().take(max_cell_width - 1).chain(std::iter::once('…')).collect()
+        } else {
+            value
+        }
+    };
+
+    let mut widths = vec![0; columns];
+    for row in std::iter::once(headers).chain(rows.iter().map(|row| row.as_slice())) {
+        for (column, width) in widths.iter_mut().enumerate() {
+            *width = (*width).max(cell(row, column).chars().count());
+        }
+    }
+
+    let render_line = |row: &[String]| -> String {
+        let cells: Vec<String> = widths
+            .iter()
+            .enumerate()
+            .map(|(column, width)| format!(" {:<width$} ", cell(row, column), width = width))
+            .collect();
+        cells.join("|").trim_end().to_string()
+    };
+
+    let mut table = String::new();
+    table.push_str(&render_line(headers));
+    table.push('\n');
+    let separator: Vec<String> = widths.iter().map(|width| "-".repeat(width + 2)).collect();
+    table.push_str(&separator.join("+"));
+    table.push('\n');
+    for row in rows {
+        table.push_str(&render_line(row));
+        table.push('\n');
+    }
+    table.push_str(&match rows.len() {
+        1 => "(1 row)\n".to_string(),
+        count => format!("({} rows)\n", count),
+    });
+    table
+}
+
+/// Render query results unaligned for scripts: the header and then one line
+/// per row, cells separated by tabs. Tabs and line breaks inside values are
+/// written as `\t` and `\n`.
+pub fn format_unaligned(headers: &[String], rows: &[Vec<String>]) -> String {
+    let mut output = String::new();
+    for row in std::iter::once(headers).chain(rows.iter().map(|row| row.as_slice())) {
+        let cells: Vec<String> = row
+            .iter()
+            .map(|value| value.replace('\t', "\\t").replace('\n', "\\n"))
+            .collect();
+        output.push_str(&cells.join("\t"));
+        output.push('\n');
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{format_table, format_table_with_max_width, format_unaligned};
+
+    fn strings(values: &[&str]) -> Vec<String> {
+        values.iter().map(|value| value.to_string()).collect()
+    }
+
+    #[test]
+    fn aligns_columns_to_the_widest_cell() {
+        let headers = strings(&["name", "age"]);
+        let rows = vec![strings(&["alice", "30"]), strings(&["bo", "104"])];
+        assert_eq!(
+            format_table(&headers, &rows),
+            " name  | age\n\
+             -------+-----\n \
+             alice | 30\n \
+             bo    | 104\n\
+             (2 rows)\n"
+        );
+    }
+
+    #[test]
+    fn counts_characters_not_bytes_and_pads_short_rows() {
+        let headers = strings(&["s", "o"]);
+        let rows = vec![strings(&["café"]), strings(&["x", "multi\nline"])];
+        assert_eq!(
+            format_table(&headers, &rows),
+            " s    | o\n\
+             ------+-------------\n \
+             café |\n \
+             x    | multi\\nline\n\
+             (2 rows)\n"
+        );
+        assert_eq!(format_table(&headers, &[]), " s | o\n---+---\n(0 rows)\n");
+    }
+
+    #[test]
+    fn truncates_cells_past_the_width_cap() {
+        let headers = strings(&["name", "comment"]);
+        let rows = vec![strings(&["alice", "a rather long comment"])];
+        assert_eq!(
+            format_table_with_max_width(&headers, &rows, 8),
+            " name  | comment\n\
+             -------+----------\n \
+             alice | a rathe…\n\
+             (1 row)\n"
+        );
+    }
+
+    #[test]
+    fn unaligned_output_separates_cells_with_tabs() {
+        let headers = strings(&["s", "o"]);
+        let rows = vec![strings(&["a", "tab\there"]), strings(&["b", "two\nlines"])];
+        assert_eq!(format_unaligned(&headers, &rows), "s\to\na\ttab\\there\nb\ttwo\\nlines\n");
+    }
+}
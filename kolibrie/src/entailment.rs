@@ -0,0 +1,221 @@
+/*
+ * Copyright © 2026 Volodymyr Kadzhaia
+ * Copyright © 2026 Pieter Bonte
+ * KU Leuven — Stream Intelligence Lab, Belgium
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this file,
+ * you can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use shared::dictionary::Dictionary;
+use shared::rule::Rule;
+use shared::terms::Term;
+use shared::triple::Triple;
+use std::collections::BTreeSet;
+
+pub const RDF_TYPE: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#type";
+pub const RDF_PROPERTY: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#Property";
+pub const RDFS_SUBCLASSOF: &str = "http://www.w3.org/2000/01/rdf-schema#subClassOf";
+pub const RDFS_SUBPROPERTYOF: &str = "http://www.w3.org/2000/01/rdf-schema#subPropertyOf";
+pub const RDFS_DOMAIN: &str = "http://www.w3.org/2000/01/rdf-schema#domain";
+pub const RDFS_RANGE: &str = "http://www.w3.org/2000/01/rdf-schema#range";
+pub const OWL_SAMEAS: &str = "http://www.w3.org/2002/07/owl#sameAs";
+pub const OWL_INVERSEOF: &str = "http://www.w3.org/2002/07/owl#inverseOf";
+pub const OWL_EQUIVALENTCLASS: &str = "http://www.w3.org/2002/07/owl#equivalentClass";
+pub const OWL_EQUIVALENTPROPERTY: &str = "http://www.w3.org/2002/07/owl#equivalentProperty";
+pub const OWL_TRANSITIVEPROPERTY: &str = "http://www.w3.org/2002/07/owl#TransitiveProperty";
+pub const OWL_SYMMETRICPROPERTY: &str = "http://www.w3.org/2002/07/owl#SymmetricProperty";
+
+/// SPARQL 1.1 entailment regime used when answering queries.
+///
+/// `Simple` evaluates queries against the asserted triples only. The other
+/// regimes materialise the entailed triples before a query runs, so basic
+/// graph patterns also match the answers implied by the vocabulary.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum EntailmentRegime {
+    #[default]
+    Simple,
+    Rdf,
+    Rdfs,
+    /// Approximated with the OWL 2 RL rule set below; sound but not complete
+    /// with respect to the OWL 2 Direct Semantics.
+    OwlDirect,
+    OwlRl,
+}
+
+impl EntailmentRegime {
+    /// Build the forward-chaining rules implementing this regime for the
+    /// given data, encoding the vocabulary terms in `dict`.
+    ///
+    /// The reasoner only joins on constant predicates, so rules whose
+    /// predicate is a variable in the W3C tables (rdf1, rdfs2/3/7, the OWL
+    /// property axioms, sameAs replacement) are instantiated once per
+    /// property found in `triples`.
+    pub fn rules(&self, triples: &BTreeSet<Triple>, dict: &mut Dictionary) -> Vec<Rule> {
+        let mut rules = Vec::new();
+        if *self == EntailmentRegime::Simple {
+            return rules;
+        }
+
+        let rdf_type = dict.encode(RDF_TYPE);
+        let sub_class = dict.encode(RDFS_SUBCLASSOF);
+        let sub_property = dict.encode(RDFS_SUBPROPERTYOF);
+        let domain = dict.encode(RDFS_DOMAIN);
+        let range = dict.encode(RDFS_RANGE);
+        let same_as = dict.encode(OWL_SAMEAS);
+        let inverse_of = dict.encode(OWL_INVERSEOF);
+        let equivalent_class = dict.encode(OWL_EQUIVALENTCLASS);
+        let equivalent_property = dict.encode(OWL_EQUIVALENTPROPERTY);
+        let transitive = dict.encode(OWL_TRANSITIVEPROPERTY);
+        let symmetric = dict.encode(OWL_SYMMETRICPROPERTY);
+        let owl = *self != EntailmentRegime::Rdf && *self != EntailmentRegime::Rdfs;
+
+        // Properties used in the data or introduced by the schema
+        let mut properties: BTreeSet<u32> = BTreeSet::new();
+        for triple in triples {
+            properties.insert(triple.predicate);
+            if triple.predicate == sub_property
+                || (owl && (triple.predicate == equivalent_property || triple.predicate == inverse_of))
+            {
+                properties.insert(triple.subject);
+                properties.insert(triple.object);
+            }
+        }
+
+        // rdf1
+        let property_class = dict.encode(RDF_PROPERTY);
+        for &p in &properties {
+            rules.push(rule(
+                vec![(var("x"), c(p), var("y"))],
+                vec![(c(p), c(rdf_type), c(property_class))],
+            ));
+        }
+        if *self == EntailmentRegime::Rdf {
+            return rules;
+        }
+
+        // rdfs9, rdfs11
+        rules.push(rule(
+            vec![(var("c"), c(sub_class), var("d")), (var("x"), c(rdf_type), var("c"))],
+            vec![(var("x"), c(rdf_type), var("d"))],
+        ));
+        rules.push(rule(
+            vec![(var("c"), c(sub_class), var("d")), (var("d"), c(sub_class), var("e"))],
+            vec![(var("c"), c(sub_class), var("e"))],
+        ));
+        // rdfs5
+        rules.push(rule(
+            vec![(var("p"), c(sub_property), var("q")), (var("q"), c(sub_property), var("r"))],
+            vec![(var("p"), c(sub_property), var("r"))],
+        ));
+
+        for triple in triples {
+            let (s, p, o) = (triple.subject, triple.predicate, triple.object);
+            // rdfs2
+            if p == domain {
+                rules.push(rule(
+                    vec![(var("x"), c(s), var("y"))],
+                    vec![(var("x"), c(rdf_type), c(o))],
+                ));
+            }
+            // rdfs3
+            if p == range {
+                rules.push(rule(
+                    vec![(var("x"), c(s), var("y"))],
+                    vec![(var("y"), c(rdf_type), c(o))],
+                ));
+            }
+            // rdfs7
+            if p == sub_property || (owl && p == equivalent_property) {
+                rules.push(rule(
+                    vec![(var("x"), c(s), var("y"))],
+                    vec![(var("x"), c(o), var("y"))],
+                ));
+            }
+            if !owl {
+                continue;
+            }
+            // prp-eqp2
+            if p == equivalent_property {
+                rules.push(rule(
+                    vec![(var("x"), c(o), var("y"))],
+                    vec![(var("x"), c(s), var("y"))],
+                ));
+            }
+            // prp-inv1, prp-inv2
+            if p == inverse_of {
+                rules.push(rule(
+                    vec![(var("x"), c(s), var("y"))],
+                    vec![(var("y"), c(o), var("x"))],
+                ));
+                rules.push(rule(
+                    vec![(var("x"), c(o), var("y"))],
+                    vec![(var("y"), c(s), var("x"))],
+                ));
+            }
+            // prp-symp
+            if p == rdf_type && o == symmetric {
+                rules.push(rule(
+                    vec![(var("x"), c(s), var("y"))],
+                    vec![(var("y"), c(s), var("x"))],
+                ));
+            }
+            // prp-trp
+            if p == rdf_type && o == transitive {
+                rules.push(rule(
+                    vec![(var("x"), c(s), var("y")), (var("y"), c(s), var("z"))],
+                    vec![(var("x"), c(s), var("z"))],
+                ));
+            }
+        }
+        if !owl {
+            return rules;
+        }
+
+        // cax-eqc1, cax-eqc2 via rdfs9
+        rules.push(rule(
+            vec![(var("c"), c(equivalent_class), var("d"))],
+            vec![(var("c"), c(sub_class), var("d")), (var("d"), c(sub_class), var("c"))],
+        ));
+        // eq-sym, eq-trans
+        rules.push(rule(
+            vec![(var("x"), c(same_as), var("y"))],
+            vec![(var("y"), c(same_as), var("x"))],
+        ));
+        rules.push(rule(
+            vec![(var("x"), c(same_as), var("y")), (var("y"), c(same_as), var("z"))],
+            vec![(var("x"), c(same_as), var("z"))],
+        ));
+        // eq-rep-s, eq-rep-o
+        for &p in &properties {
+            rules.push(rule(
+                vec![(var("s"), c(same_as), var("t")), (var("s"), c(p), var("o"))],
+                vec![(var("t"), c(p), var("o"))],
+            ));
+            rules.push(rule(
+                vec![(var("o"), c(same_as), var("t")), (var("s"), c(p), var("o"))],
+                vec![(var("s"), c(p), var("t"))],
+            ));
+        }
+
+        rules
+    }
+}
+
+fn c(id: u32) -> Term {
+    Term::Constant(id)
+}
+
+fn var(name: &str) -> Term {
+    Term::Variable(name.to_string())
+}
+
+fn rule(premise: Vec<(Term, Term, Term)>, conclusion: Vec<(Term, Term, Term)>) -> Rule {
+    Rule {
+        premise,
+        negative_premise: vec![],
+        filters: vec![],
+        conclusion,
+    }
+}
@@ -11,6 +11,25 @@
 use annotate_snippets::{Level, Renderer, Snippet, AnnotationKind, Group, Annotation};
 use nom:: error::Error as NomError;
 
+/// 1-based line and column of the byte `offset` in `input`
+pub fn line_and_column(input: &str, offset: usize) -> (usize, usize) {
+    let mut line_no = 1;
+    let mut col_no = 1;
+
+    for (i, c) in input.char_indices() {
+        if i >= offset {
+            break;
+        }
+        if c == '\n' {
+            line_no += 1;
+            col_no = 1;
+        } else {
+            col_no += 1;
+        }
+    }
+    (line_no, col_no)
+}
+
 pub fn format_parse_error(input: &str, err: nom::Err<NomError<&str>>) -> String {
     match err {
         nom::Err::Error(e) | nom::Err::Failure(e) => {
@@ -18,20 +37,7 @@ pub fn format_parse_error(input: &str, err: nom::Err<NomError<&str>>) -> String
             let offset = input.len() - error_pos.len();
             
             // Calculate line and column numbers
-            let mut line_no = 1;
-            let mut col_no = 1;
-
-            for (i, c) in input.char_indices() {
-                if i >= offset {
-                    break;
-                }
-                if c == '\n' {
-                    line_no += 1;
-                    col_no = 1;
-                } else {
-                    col_no += 1;
-                }
-            }
+            let (line_no, col_no) = line_and_column(input, offset);
 
             // Get the error line
             let lines: Vec<&str> = input.lines().collect();
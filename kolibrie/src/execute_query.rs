@@ -8,16 +8,28 @@
  * you can obtain one at https://mozilla.org/MPL/2.0/.
  */
 
-use crate::sparql_database::SparqlDatabase;
+use crate::custom_error::SparqlError;
+use crate::entailment::EntailmentRegime;
+use crate::partitioned_store::PartitionedTripleStore;
+use crate::query_normalizer::QueryNormalizer;
+use crate::sparql_database::{JoinPattern, SharedSparqlDatabase, SparqlDatabase};
+use crate::utils::{
+    evaluate_coalesce, evaluate_if, evaluate_iri, evaluate_numeric_call, parse_numeric_literal,
+};
 use crate::streamertail_optimizer::*;
 use crate::error_handler::format_parse_error;
 use crate::neural_relations::{
     execute_train_decl, materialize_neural_relations_for_patterns, register_neural_declarations,
 };
 use crate::parser::*;
+use log::error;
+use rayon::ThreadPool;
 use shared::query::*;
+use shared::terms::{Bindings, TriplePattern};
 use shared::triple::Triple;
-use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::{Arc, PoisonError, RwLockReadGuard};
+use std::time::Duration;
 
 pub fn execute_subquery<'a>(
     subquery: &SubQuery<'a>,
@@ -41,12 +53,12 @@ pub fn execute_subquery<'a>(
             None
         };
 
-        results = database.perform_join_par_simd_with_strict_filter_1(
+        results = join_rows(
+            database,
             subject_var,
             resolved_predicate,
             object_var,
             triples_vec,
-            &database.dictionary,
             results,
             literal_filter,
         );
@@ -90,11 +102,12 @@ pub fn execute_subquery<'a>(
                 row.insert(new_var, result);
             }
         } else {
-            eprintln!("UDF {} not found", func_name);
+            error!("UDF {} not found", func_name);
         }
     }
 
     // Return only the variables specified in the SELECT clause
+    let mut seen = HashSet::new();
     results
         .into_iter()
         .map(|mut row| {
@@ -108,6 +121,7 @@ pub fn execute_subquery<'a>(
             }
             new_row
         })
+        .filter(|row| !subquery.distinct || seen.insert(row.clone()))
         .collect()
 }
 
@@ -120,19 +134,30 @@ fn apply_order_by<'a>(
         return results;
     }
 
+    // A column sorts numerically only when every bound value is a number;
+    // deciding per pair would give an inconsistent order for mixed columns
+    let numeric_columns: Vec<bool> = order_conditions
+        .iter()
+        .map(|condition| {
+            results
+                .iter()
+                .filter_map(|row| row.get(condition.variable))
+                .all(|value| parse_numeric_literal(value).is_some())
+        })
+        .collect();
+
     results.sort_by(|a, b| {
-        for condition in &order_conditions {
-            // Borrow from owned vector
+        for (condition, &numeric) in order_conditions.iter().zip(&numeric_columns) {
             let var = condition.variable;
 
-            let val_a = a.get(var).map(|s| s.as_str()).unwrap_or("");
-            let val_b = b.get(var).map(|s| s.as_str()).unwrap_or("");
-
-            let comparison = match (val_a.parse::<f64>(), val_b.parse::<f64>()) {
-                (Ok(num_a), Ok(num_b)) => num_a
-                    .partial_cmp(&num_b)
-                    .unwrap_or(std::cmp::Ordering::Equal),
-                _ => val_a.cmp(val_b),
+            // Unbound values sort first
+            let comparison = match (a.get(var), b.get(var)) {
+                (Some(val_a), Some(val_b)) if numeric => {
+                    let num_a = parse_numeric_literal(val_a).unwrap_or_default();
+                    let num_b = parse_numeric_literal(val_b).unwrap_or_default();
+                    num_a.total_cmp(&num_b)
+                }
+                (val_a, val_b) => val_a.cmp(&val_b),
             };
 
             let final_comparison = match condition.direction {
@@ -156,6 +181,8 @@ fn apply_order_by<'a>(
 pub fn execute_query(sparql: &str, database: &mut SparqlDatabase) -> Vec<Vec<String>> {
     // Register prefixes from the query string first
     database.register_prefixes_from_query(sparql);
+    database.apply_entailment();
+    database.sync_partitions();
 
     let sparql = normalize_query(sparql);
 
@@ -163,6 +190,7 @@ pub fn execute_query(sparql: &str, database: &mut SparqlDatabase) -> Vec<Vec<Str
     let mut final_results: Vec<BTreeMap<&str, String>>;
     let mut selected_variables: Vec<(String, String)> = Vec::new();
     let mut aggregation_vars: Vec<(&str, &str, &str)> = Vec::new();
+    let mut select_expressions: Vec<(SelectExpression, &str)> = Vec::new();
     let group_by_variables: Vec<&str>;
     let mut prefixes;
     let limit_clause: Option<usize>;
@@ -185,6 +213,7 @@ pub fn execute_query(sparql: &str, database: &mut SparqlDatabase) -> Vec<Vec<Str
             _,
             order_conditions,
         ) = combined.sparql;
+        let distinct = combined.distinct;
 
         prefixes = combined.prefixes.clone();
         prefixes.extend(parsed_prefixes);
@@ -210,7 +239,7 @@ pub fn execute_query(sparql: &str, database: &mut SparqlDatabase) -> Vec<Vec<Str
             .collect();
         for train_decl in &normalized_trains {
             if let Err(err) = execute_train_decl(database, train_decl) {
-                eprintln!("Failed to execute TRAIN NEURAL RELATION: {}", err);
+                error!("Failed to execute TRAIN NEURAL RELATION: {}", err);
                 return Vec::new();
             }
         }
@@ -218,31 +247,27 @@ pub fn execute_query(sparql: &str, database: &mut SparqlDatabase) -> Vec<Vec<Str
         // Ensure prefixes from the database are also available
         database.share_prefixes_with(&mut prefixes);
         if let Err(err) = materialize_neural_relations_for_patterns(database, &patterns, &prefixes) {
-            eprintln!("Failed to materialize neural relations: {}", err);
+            error!("Failed to materialize neural relations: {}", err);
             return Vec::new();
         }
 
         // Process the INSERT clause if present
         process_insert_clause(insert_clause, database);
 
-        // If SELECT * is used, gather all variables from patterns
-        if variables == vec![("*", "*", None)] {
-            let mut all_vars = BTreeSet::new();
-            for (subject_var, predicate_var, object_var) in &patterns {
-                all_vars.insert(*subject_var);
-                all_vars.insert(*predicate_var);
-                all_vars.insert(*object_var);
-            }
-            variables = all_vars.into_iter().map(|var| ("VAR", var, None)).collect();
-        }
+        // SELECT * projects every variable the WHERE clause binds
+        variables = expand_select_star(variables, &patterns, &binds, values_clause.as_ref(), &subqueries);
 
         // Process variables for aggregation
-        process_variables(&mut selected_variables, &mut aggregation_vars, variables);
+        process_variables(&mut selected_variables, &mut aggregation_vars, &mut select_expressions, variables);
 
         group_by_variables = group_vars;
 
-        // Convert BTreeSet to a vector of Triple
-        let triples_vec: Vec<Triple> = database.triples.iter().cloned().collect();
+        // Convert BTreeSet to a vector of Triple (partitioned stores are scanned per pattern)
+        let triples_vec: Vec<Triple> = if database.partitioned_store.is_some() {
+            Vec::new()
+        } else {
+            database.triples.iter().cloned().collect()
+        };
 
         // Initialize final_results based on the VALUES clause
         final_results = initialize_results(&values_clause);
@@ -297,12 +322,17 @@ pub fn execute_query(sparql: &str, database: &mut SparqlDatabase) -> Vec<Vec<Str
             let join_subject_static: &'static str = Box::leak(join_subject.into_boxed_str());
             let join_object_static: &'static str = Box::leak(join_object.into_boxed_str());
 
-            final_results = database.perform_join_par_simd_with_strict_filter_1(
+            let pattern_triples = match &database.partitioned_store {
+                Some(store) => scan_partitions(store, &join_predicate, database),
+                None => triples_vec.clone(),
+            };
+
+            final_results = join_rows(
+                database,
                 join_subject_static,
                 join_predicate,
                 join_object_static,
-                triples_vec.clone(),
-                &database.dictionary,
+                pattern_triples,
                 final_results,
                 if !join_object_static.starts_with('?') {
                     Some(join_object_static.to_string())
@@ -330,6 +360,10 @@ pub fn execute_query(sparql: &str, database: &mut SparqlDatabase) -> Vec<Vec<Str
             final_results =
                 group_and_aggregate_results(final_results, &group_by_variables, &aggregation_vars);
         }
+        apply_select_expressions(&mut final_results, &select_expressions, database);
+        if distinct {
+            final_results = distinct_results(final_results, &selected_variables);
+        }
 
         final_results = apply_order_by(final_results, order_conditions);
 
@@ -342,9 +376,9 @@ pub fn execute_query(sparql: &str, database: &mut SparqlDatabase) -> Vec<Vec<Str
         // Enhanced error reporting while keeping the same function signature
         if let Err(err) = parse_result {
             let error_message = format_parse_error(sparql, err);
-            eprintln!("Failed to parse the query: {}", error_message);
+            error!("Failed to parse the query: {}", error_message);
         } else {
-            eprintln!("Failed to parse the query with an unknown error.");
+            error!("Failed to parse the query with an unknown error.");
         }
         return Vec::new();
     }
@@ -353,15 +387,409 @@ pub fn execute_query(sparql: &str, database: &mut SparqlDatabase) -> Vec<Vec<Str
     format_results(final_results, &selected_variables)
 }
 
+/// Run a query like `try_execute_query`, but give up once
+/// `timeout` has elapsed. The execution engine checks the deadline between
+/// operators and periodically inside scans and joins, so an expensive query
+/// (e.g. a Cartesian product) is abandoned instead of running unbounded.
+pub fn execute_query_with_timeout(
+    sparql: &str,
+    database: &mut SparqlDatabase,
+    timeout: Duration,
+) -> Result<Vec<Vec<String>>, SparqlError> {
+    let token = Arc::new(CancellationToken::with_timeout(timeout));
+    let previous = database.cancellation.replace(Arc::clone(&token));
+    let results = try_execute_query(sparql, database);
+    database.cancellation = previous;
+
+    // Errors raised by operators take precedence over the deadline
+    let results = results?;
+    if token.is_cancelled() {
+        return Err(SparqlError::Timeout(timeout));
+    }
+    Ok(results)
+}
+
+/// Run `sparql` with a fresh `QueryProfiler` attached, returning the rows
+/// together with per-operator row counts and timings.
+pub fn execute_query_profiled(
+    sparql: &str,
+    database: &mut SparqlDatabase,
+) -> Result<(Vec<Vec<String>>, Arc<QueryProfiler>), SparqlError> {
+    let profiler = Arc::new(QueryProfiler::new());
+    let previous = database.profiler.replace(Arc::clone(&profiler));
+    let results = try_execute_query(sparql, database);
+    database.profiler = previous;
+    Ok((results?, profiler))
+}
+
+/// Run `sparql` with every join forced to `algorithm` instead of the one the
+/// cost model picks. A `# kolibrie:join <name>` line at the top of the query
+/// does the same and takes precedence; see `JoinAlgorithm` for the names.
+pub fn execute_query_with_hint(
+    sparql: &str,
+    database: &mut SparqlDatabase,
+    algorithm: JoinAlgorithm,
+) -> Result<Vec<Vec<String>>, SparqlError> {
+    let previous = database.join_algorithm.replace(algorithm);
+    let results = try_execute_query(sparql, database);
+    database.join_algorithm = previous;
+    results
+}
+
+/// Settings of a single query. The `&mut SparqlDatabase` entry points take
+/// them from the database's `cancellation`, `profiler` and `join_algorithm`;
+/// queries sharing a database through `execute_read_query` bring their own.
+#[derive(Clone, Default)]
+pub struct QueryContext {
+    /// Give up with `SparqlError::Timeout` once this has elapsed
+    pub timeout: Option<Duration>,
+    /// Records per-operator row counts and timings, as in `execute_query_profiled`
+    pub profiler: Option<Arc<QueryProfiler>>,
+    /// Join algorithm forced on the optimizer, as in `execute_query_with_hint`
+    pub join_algorithm: Option<JoinAlgorithm>,
+}
+
+/// `try_execute_query` with the settings of `context` in place of the
+/// database's own for the duration of the query
+pub fn execute_query_with_context(
+    sparql: &str,
+    database: &mut SparqlDatabase,
+    context: &QueryContext,
+) -> Result<Vec<Vec<String>>, SparqlError> {
+    let profiler = std::mem::replace(&mut database.profiler, context.profiler.clone());
+    let join_algorithm = std::mem::replace(&mut database.join_algorithm, context.join_algorithm);
+    let results = match context.timeout {
+        Some(timeout) => execute_query_with_timeout(sparql, database, timeout),
+        None => try_execute_query(sparql, database),
+    };
+    database.profiler = profiler;
+    database.join_algorithm = join_algorithm;
+    results
+}
+
+/// Whether a query only reads the database or also writes to it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum QueryKind {
+    /// SELECT queries, which `execute_read_query` runs on `&SparqlDatabase`
+    Read,
+    /// Updates, model and neural relation declarations and patterns over
+    /// declared neural relations, which need `&mut SparqlDatabase`
+    Write,
+}
+
+/// Classify `sparql` without running it, so callers holding a shared
+/// database can pick the lock to take. Fails for queries that do not parse.
+pub fn query_kind(sparql: &str, database: &SparqlDatabase) -> Result<QueryKind, SparqlError> {
+    Ok(match classify_query(sparql, database, None)? {
+        ClassifiedQuery::Read(_) => QueryKind::Read,
+        ClassifiedQuery::Write => QueryKind::Write,
+    })
+}
+
+/// A parsed query; SELECT queries come with what evaluating them needs
+enum ClassifiedQuery<'q> {
+    Read(Box<SelectQuery<'q>>),
+    Write,
+}
+
+/// A parsed SELECT query with its planner hints and the prefixes its terms
+/// resolve against
+struct SelectQuery<'q> {
+    combined: CombinedQuery<'q>,
+    hints: QueryHints,
+    prefixes: HashMap<String, String>,
+}
+
+impl<'q> SelectQuery<'q> {
+    /// `database_prefixes` take precedence over the query's own, and include
+    /// its PREFIX declarations once they are registered with the database
+    fn new(
+        combined: CombinedQuery<'q>,
+        mut hints: QueryHints,
+        join_algorithm: Option<JoinAlgorithm>,
+        database_prefixes: &HashMap<String, String>,
+    ) -> Self {
+        hints.join_algorithm = hints.join_algorithm.or(join_algorithm);
+        let mut prefixes = combined.prefixes.clone();
+        prefixes.extend(combined.sparql.5.clone());
+        prefixes.extend(database_prefixes.clone());
+        SelectQuery { combined, hints, prefixes }
+    }
+}
+
+fn classify_query<'q>(
+    sparql: &'q str,
+    database: &SparqlDatabase,
+    join_algorithm: Option<JoinAlgorithm>,
+) -> Result<ClassifiedQuery<'q>, SparqlError> {
+    let hints = query_hints(sparql)?;
+    let sparql = normalize_query(sparql);
+    let combined = match parse_combined_query(sparql) {
+        Ok((_, combined)) => combined,
+        Err(err) => return Err(SparqlError::ParseError(format_parse_error(sparql, err))),
+    };
+    if combined.sparql.0.is_some()
+        || combined.delete_clause.is_some()
+        || !combined.model_decls.is_empty()
+        || !combined.neural_relation_decls.is_empty()
+        || !combined.train_neural_relation_decls.is_empty()
+    {
+        return Ok(ClassifiedQuery::Write);
+    }
+
+    // The prefixes `execute_query_volcano` registers with the database
+    let mut database_prefixes = database.prefixes.clone();
+    database_prefixes.extend(SparqlDatabase::prefixes_declared_in(sparql));
+    let query = SelectQuery::new(combined, hints, join_algorithm, &database_prefixes);
+
+    // Neural relations are materialized into the database when first queried
+    let over_neural_relation = query.combined.sparql.2.iter().any(|(_, predicate, _)| {
+        let predicate = database.resolve_query_term(predicate, &query.prefixes);
+        database.neural_relation_decls.contains_key(&predicate)
+    });
+    if over_neural_relation {
+        return Ok(ClassifiedQuery::Write);
+    }
+    Ok(ClassifiedQuery::Read(Box::new(query)))
+}
+
+/// Run a SELECT query without modifying `database`, so that queries holding
+/// the read lock of a `SharedSparqlDatabase` run side by side. The database
+/// must have gone through `SparqlDatabase::prepare_for_reads` since its last
+/// write. Queries of `QueryKind::Write` fail with
+/// `SparqlError::UnsupportedFeature`. The query cache is not consulted.
+pub fn execute_read_query(
+    sparql: &str,
+    database: &SparqlDatabase,
+    context: &QueryContext,
+) -> Result<Vec<Vec<String>>, SparqlError> {
+    match classify_query(sparql, database, context.join_algorithm)? {
+        ClassifiedQuery::Read(query) => evaluate_read_query(database, *query, context),
+        ClassifiedQuery::Write => Err(SparqlError::UnsupportedFeature(
+            "the query writes to the database, which execute_read_query only reads".to_string(),
+        )),
+    }
+}
+
+fn evaluate_read_query(
+    database: &SparqlDatabase,
+    query: SelectQuery<'_>,
+    context: &QueryContext,
+) -> Result<Vec<Vec<String>>, SparqlError> {
+    if !database.is_prepared_for_reads() {
+        return Err(SparqlError::ExecutionError(
+            "the database changed since prepare_for_reads".to_string(),
+        ));
+    }
+    let stats = database
+        .cached_stats
+        .clone()
+        .expect("database stats should be available");
+    let cancel = match context.timeout {
+        Some(timeout) => CancellationToken::with_timeout(timeout),
+        None => CancellationToken::new(),
+    };
+    let results = evaluate_select(database, query, stats, context.profiler.clone(), &cancel)?;
+
+    match context.timeout {
+        Some(timeout) if cancel.is_cancelled() => Err(SparqlError::Timeout(timeout)),
+        _ => Ok(results),
+    }
+}
+
+/// Run a query on a database shared between threads. Queries of
+/// `QueryKind::Read` run under the read lock, alongside each other; the
+/// others take the write lock, and leave the database prepared for the
+/// reads that follow.
+pub fn execute_shared_query(
+    sparql: &str,
+    database: &SharedSparqlDatabase,
+    context: &QueryContext,
+) -> Result<Vec<Vec<String>>, SparqlError> {
+    {
+        let database = read_prepared(database)?;
+        if let ClassifiedQuery::Read(query) = classify_query(sparql, &database, context.join_algorithm)? {
+            return evaluate_read_query(&database, *query, context);
+        }
+    }
+    let mut database = database.write().map_err(lock_poisoned)?;
+    let results = execute_query_with_context(sparql, &mut database, context);
+    database.prepare_for_reads();
+    results
+}
+
+/// Read lock on `database` once it is ready for `execute_read_query`. A
+/// database left unprepared by a write is prepared under the write lock first.
+pub fn read_prepared(
+    database: &SharedSparqlDatabase,
+) -> Result<RwLockReadGuard<'_, SparqlDatabase>, SparqlError> {
+    loop {
+        let guard = database.read().map_err(lock_poisoned)?;
+        if guard.is_prepared_for_reads() {
+            return Ok(guard);
+        }
+        drop(guard);
+        database.write().map_err(lock_poisoned)?.prepare_for_reads();
+    }
+}
+
+fn lock_poisoned<T>(_: PoisonError<T>) -> SparqlError {
+    SparqlError::ExecutionError("database lock poisoned; query not executed".to_string())
+}
+
+/// Run a query like `try_execute_query`, but on `pool` instead of the global
+/// Rayon pool, so its parallel scans, joins and parser workers use at most
+/// `pool.current_num_threads()` threads. Build the pool once with
+/// `rayon::ThreadPoolBuilder` and reuse it across queries.
+pub fn execute_query_in_pool(
+    sparql: &str,
+    database: &mut SparqlDatabase,
+    pool: &ThreadPool,
+) -> Result<Vec<Vec<String>>, SparqlError> {
+    pool.install(|| try_execute_query(sparql, database))
+}
+
 pub fn execute_query_rayon_parallel2_volcano(
     sparql: &str,
     database: &mut SparqlDatabase,
 ) -> Vec<Vec<String>> {
+    match try_execute_query(sparql, database) {
+        Ok(results) => results,
+        Err(err) => {
+            error!("{}", err);
+            Vec::new()
+        }
+    }
+}
+
+/// Run a query through the Streamertail optimizer, reporting parse and
+/// execution failures as a `SparqlError` instead of an empty result.
+/// With `SparqlDatabase::enable_query_cache`, SELECT results are served from
+/// the cache until a write touches one of the query's patterns.
+pub fn try_execute_query(
+    sparql: &str,
+    database: &mut SparqlDatabase,
+) -> Result<Vec<Vec<String>>, SparqlError> {
+    // Profiled queries always run so the profiler sees the operators
+    let dependencies = match database.query_cache {
+        Some(_) if database.profiler.is_none() => query_dependencies(sparql, database),
+        _ => None,
+    };
+    let Some(patterns) = dependencies else {
+        return try_execute_query_uncached(sparql, database);
+    };
+
+    let key = query_cache_key(sparql, &database.prefixes);
+    let triple_count = database.count_triples();
+    if let Some(rows) = database.query_cache.as_mut().and_then(|cache| cache.get(&key, triple_count)) {
+        return Ok(rows);
+    }
+
+    let rows = try_execute_query_uncached(sparql, database)?;
+    // Rows of a query cancelled by a timeout are partial
+    if database.cancellation.as_ref().is_some_and(|token| token.is_cancelled()) {
+        return Ok(rows);
+    }
+    let triple_count = database.count_triples();
+    if let Some(cache) = database.query_cache.as_mut() {
+        cache.insert(&key, patterns, rows.clone(), triple_count);
+    }
+    Ok(rows)
+}
+
+/// Key `try_execute_query` caches the result of `sparql` under: its
+/// `QueryNormalizer` form, so that queries written differently share an entry
+pub fn query_cache_key(sparql: &str, prefixes: &HashMap<String, String>) -> String {
+    QueryNormalizer::normalise(normalize_query(sparql), prefixes)
+}
+
+/// Triple patterns scanned by `sparql`, resolved the way the optimizer
+/// resolves them, or `None` when the query's result can change without a
+/// write to one of those patterns (updates, rules, RSP, neural relations,
+/// entailment) and must not be cached.
+fn query_dependencies(sparql: &str, database: &mut SparqlDatabase) -> Option<Vec<TriplePattern>> {
+    if database.entailment_regime != EntailmentRegime::Simple
+        || !database.neural_relation_decls.is_empty()
+    {
+        return None;
+    }
+
+    let sparql = normalize_query(sparql);
+    database.register_prefixes_from_query(sparql);
+    let (_, combined) = parse_combined_query(sparql).ok()?;
+    if combined.rule.is_some()
+        || combined.ml_predict.is_some()
+        || combined.register_clause.is_some()
+        || combined.retrieve_clause.is_some()
+        || combined.delete_clause.is_some()
+        || !combined.model_decls.is_empty()
+        || !combined.neural_relation_decls.is_empty()
+        || !combined.train_neural_relation_decls.is_empty()
+    {
+        return None;
+    }
+
+    let (insert_clause, _, patterns, _, _, parsed_prefixes, _, _, subqueries, _, window_blocks, _) =
+        combined.sparql;
+    if insert_clause.is_some() || !window_blocks.is_empty() {
+        return None;
+    }
+
+    let mut prefixes = combined.prefixes.clone();
+    prefixes.extend(parsed_prefixes);
+    database.share_prefixes_with(&mut prefixes);
+
+    let all_patterns = patterns
+        .iter()
+        .chain(subqueries.iter().flat_map(|subquery| subquery.patterns.iter()));
+    let mut dependencies = Vec::new();
+    for (subject, predicate, object) in all_patterns {
+        // Rule calls are answered by inference at query time
+        if *predicate == "RULECALL" {
+            return None;
+        }
+        let (subject, predicate, object) =
+            resolve_triple_pattern(subject, predicate, object, database, &prefixes);
+        dependencies.push(convert_pattern_to_triple(&subject, &predicate, &object, &prefixes, database));
+    }
+    Some(dependencies)
+}
+
+fn try_execute_query_uncached(
+    sparql: &str,
+    database: &mut SparqlDatabase,
+) -> Result<Vec<Vec<String>>, SparqlError> {
+    // Operators report errors through the cancellation token, so make sure
+    // one is installed for the duration of the query
+    let installed = database.cancellation.is_none();
+    let token = Arc::clone(
+        database
+            .cancellation
+            .get_or_insert_with(|| Arc::new(CancellationToken::new())),
+    );
+    let results = execute_query_volcano(sparql, database);
+    if installed {
+        database.cancellation = None;
+    }
+
+    match token.take_error() {
+        Some(err) => Err(err),
+        None => results,
+    }
+}
+
+fn execute_query_volcano(
+    sparql: &str,
+    database: &mut SparqlDatabase,
+) -> Result<Vec<Vec<String>>, SparqlError> {
+    let hints = query_hints(sparql)?;
     let sparql = normalize_query(sparql);
 
-    let limit_clause: Option<usize>;
     // Register prefixes from the query string first
     database.register_prefixes_from_query(&sparql);
+    database.apply_entailment();
+    database.sync_partitions();
+    database.ensure_indexes();
 
     let combined_parse = parse_combined_query(&sparql);
 
@@ -387,8 +815,10 @@ pub fn execute_query_rayon_parallel2_volcano(
             .collect();
         for train_decl in &normalized_trains {
             if let Err(err) = execute_train_decl(database, train_decl) {
-                eprintln!("Failed to execute TRAIN NEURAL RELATION: {}", err);
-                return Vec::new();
+                return Err(SparqlError::ExecutionError(format!(
+                    "Failed to execute TRAIN NEURAL RELATION: {}",
+                    err
+                )));
             }
         }
 
@@ -422,7 +852,11 @@ pub fn execute_query_rayon_parallel2_volcano(
                     .map(|(s, p, o)| format!("{} {} {}", wrap_term(s), wrap_term(p), wrap_term(o)))
                     .collect();
                 let select_query = format!("SELECT {} WHERE {{ {} . }}", var_list, pattern_strs.join(" . "));
-                let where_results = execute_query_rayon_parallel2_volcano(&select_query, database);
+                let where_results = try_execute_query(&select_query, database)?;
+                // A cancelled WHERE evaluation is partial; deleting from it would be wrong
+                if database.cancellation.as_ref().is_some_and(|token| token.is_cancelled()) {
+                    return Ok(Vec::new());
+                }
 
                 // Map variable names to column indices
                 let var_names: Vec<&str> = select_vars.iter().cloned().collect();
@@ -464,19 +898,79 @@ pub fn execute_query_rayon_parallel2_volcano(
                 }
             }
             database.get_or_build_stats();
-            return Vec::new();
+            return Ok(Vec::new());
         }
     }
 
-    if let Ok((_, combined)) = combined_parse
-    {
+    let combined = match combined_parse {
+        Ok((_, combined)) => combined,
+        Err(err) => return Err(SparqlError::ParseError(format_parse_error(sparql, err))),
+    };
+
+    let mut query = SelectQuery::new(combined, hints, database.join_algorithm, &database.prefixes);
+    if let Err(err) = materialize_neural_relations_for_patterns(database, &query.combined.sparql.2, &query.prefixes) {
+        return Err(SparqlError::ExecutionError(format!(
+            "Failed to materialize neural relations: {}",
+            err
+        )));
+    }
+
+    // Process the INSERT clause if present using the existing helper function
+    if query.combined.sparql.0.is_some() {
+        process_insert_clause(query.combined.sparql.0.take(), database);
+        database.get_or_build_stats();
+        return Ok(Vec::new());
+    }
+
+    let stats = database.get_or_build_stats();
+    let cancel = database.cancellation.clone().unwrap_or_default();
+    evaluate_select(database, query, stats, database.profiler.clone(), &cancel)
+}
+
+/// Evaluate a SELECT query on `database`, which is only read: the SELECT
+/// path of both `execute_query_volcano` and `execute_read_query`
+fn evaluate_select(
+    database: &SparqlDatabase,
+    query: SelectQuery<'_>,
+    stats: Arc<DatabaseStats>,
+    profiler: Option<Arc<QueryProfiler>>,
+    cancel: &CancellationToken,
+) -> Result<Vec<Vec<String>>, SparqlError> {
+    SelectEvaluation {
+        database,
+        prefixes: &query.prefixes,
+        stats,
+        profiler,
+        cancel,
+    }
+    .run(query.combined, query.hints)
+}
+
+/// What evaluating a SELECT query needs besides the query: the database,
+/// which is only read, and the per-query settings
+struct SelectEvaluation<'a> {
+    database: &'a SparqlDatabase,
+    prefixes: &'a HashMap<String, String>,
+    stats: Arc<DatabaseStats>,
+    profiler: Option<Arc<QueryProfiler>>,
+    cancel: &'a CancellationToken,
+}
+
+impl SelectEvaluation<'_> {
+    fn optimizer(&self) -> Streamertail {
+        let mut optimizer = Streamertail::with_cached_stats(Arc::clone(&self.stats));
+        optimizer.profiler = self.profiler.clone();
+        optimizer
+    }
+
+    fn run(&self, combined: CombinedQuery<'_>, hints: QueryHints) -> Result<Vec<Vec<String>>, SparqlError> {
         let (
-            insert_clause,
+            _,
             mut variables,
             patterns,
             filters,
             group_vars,
-            parsed_prefixes,
+            _,
             values_clause,
             binds,
             subqueries,
@@ -485,44 +979,20 @@ pub fn execute_query_rayon_parallel2_volcano(
             order_conditions,
         ) = combined.sparql;
 
-        let mut prefixes = combined.prefixes.clone();
-        prefixes.extend(parsed_prefixes);
-        database.share_prefixes_with(&mut prefixes);
-        if let Err(err) = materialize_neural_relations_for_patterns(database, &patterns, &prefixes) {
-            eprintln!("Failed to materialize neural relations: {}", err);
-            return Vec::new();
-        }
-
-        limit_clause = limit;
-
-        // Process the INSERT clause if present using the existing helper function
-        if let Some(insert_clause) = insert_clause {
-            process_insert_clause(Some(insert_clause), database);
-            database.get_or_build_stats();
-            return Vec::new();
-        }
-
-        // If SELECT * is used, gather all variables from patterns
-        if variables == vec![("*", "*", None)] {
-            let mut all_vars = BTreeSet::new();
-            for (subject_var, predicate_var, object_var) in &patterns {
-                all_vars.insert(*subject_var);
-                all_vars.insert(*predicate_var);
-                all_vars.insert(*object_var);
-            }
-            variables = all_vars.into_iter().map(|var| ("VAR", var, None)).collect();
-        }
+        // SELECT * projects every variable the WHERE clause binds
+        variables = expand_select_star(variables, &patterns, &binds, values_clause.as_ref(), &subqueries);
 
         // Process variables for aggregation using the existing helper function
         let mut selected_variables: Vec<(String, String)> = Vec::new();
         let mut aggregation_vars: Vec<(&str, &str, &str)> = Vec::new();
-        process_variables(&mut selected_variables, &mut aggregation_vars, variables);
+        let mut select_expressions: Vec<(SelectExpression, &str)> = Vec::new();
+        process_variables(&mut selected_variables, &mut aggregation_vars, &mut select_expressions, variables);
 
         let resolved_patterns: Vec<(&str, &str, &str)> = patterns
             .iter()
             .map(|(subject_var, predicate, object_var)| {
                 let (resolved_subject, resolved_predicate, resolved_object) =
-                    resolve_triple_pattern(subject_var, predicate, object_var, database, &prefixes);
+                    resolve_triple_pattern(subject_var, predicate, object_var, self.database, self.prefixes);
                 
                 // Leak strings to get 'static lifetime
                 let subject_static: &'static str = Box::leak(resolved_subject.into_boxed_str());
@@ -536,44 +1006,56 @@ pub fn execute_query_rayon_parallel2_volcano(
         // Build indexes before optimization - this is crucial for performance
         // database.build_all_indexes();
 
+        // Subqueries are evaluated up front; only their projected variables
+        // are joined into the outer query
+        let subquery_plans: Vec<LogicalOperator> = subqueries
+            .iter()
+            .map(|subquery| evaluate_subquery_to_buffer(subquery, self))
+            .collect::<Result<_, _>>()?;
+
+        // The projection keeps the inputs of computed SELECT expressions,
+        // which are evaluated on the results
+        let mut plan_variables: Vec<(&str, &str)> = selected_variables
+            .iter()
+            .map(|(t, v)| (t.as_str(), v.as_str()))
+            .collect();
+        for (expression, _) in &select_expressions {
+            plan_variables.extend(expression.variables().into_iter().map(|var| ("VAR", var)));
+        }
+
+        // DISTINCT applies to the solutions as selected, so it can only run
+        // in the projection when nothing is grouped or computed afterwards
+        let distinct_in_plan = combined.distinct
+            && group_vars.is_empty()
+            && aggregation_vars.is_empty()
+            && select_expressions.is_empty();
+
         // Use Volcano optimizer for CPU execution
-        let mut logical_plan = build_logical_plan(
-            selected_variables
-                .iter()
-                .map(|(t, v)| (t.as_str(), v.as_str()))
-                .collect(),
+        let logical_plan = build_logical_plan_with_subplans(
+            plan_variables,
             resolved_patterns,
             filters.clone(),
-            &prefixes,
-            database,
+            self.prefixes,
+            self.database,
             &binds,
             values_clause.as_ref(),
-        ); 
-
-        // Integrate subqueries into the logical plan
-        for subquery in &subqueries {
-            let subquery_plan = build_logical_plan_from_subquery(
-                subquery,
-                &prefixes,
-                database,
-            );
-        
-            // Join the subquery with the main query
-            logical_plan = LogicalOperator::join(logical_plan, subquery_plan);
-        }
+            subquery_plans,
+        );
+        let logical_plan = match logical_plan {
+            LogicalOperator::Projection { predicate, variables, .. } if distinct_in_plan => {
+                LogicalOperator::Projection { predicate, variables, distinct: true }
+            }
+            plan => plan,
+        };
 
-        if database.cached_stats.is_none() {
-            database.get_or_build_stats();
-        }
-        
-        let stats = database
-            .cached_stats
-            .as_ref()
-            .expect("database stats should be available");
-        let mut optimizer = Streamertail::with_cached_stats(stats.clone());
+        let mut optimizer = self.optimizer();
+        optimizer.hints = hints;
+        optimizer.set_selected_variables(
+            selected_variables.iter().map(|(_, var)| var.clone()).collect(),
+        );
 
         let optimized_plan = optimizer.find_best_plan(&logical_plan);
-        let results = optimized_plan.execute(database);
+        let results = optimized_plan.execute_cancellable(self.database, self.cancel)?;
 
         let results_owned: Vec<HashMap<String, String>> = results.into_iter().collect();
 
@@ -604,33 +1086,25 @@ pub fn execute_query_rayon_parallel2_volcano(
             optimizer_results
         };
 
-        for subquery in subqueries {
-            let subquery_results =
-                execute_subquery(&subquery, database, &prefixes, final_results.clone());
-            final_results = merge_results(final_results, subquery_results);
-        }
-
         if !group_vars.is_empty() {
             final_results =
                 group_and_aggregate_results(final_results, &group_vars, &aggregation_vars);
         }
+        apply_select_expressions(&mut final_results, &select_expressions, self.database);
+        if combined.distinct && !distinct_in_plan {
+            final_results = distinct_results(final_results, &selected_variables);
+        }
 
         final_results = apply_order_by(final_results, order_conditions);
 
-        if let Some(limit_value) = limit_clause {
+        if let Some(limit_value) = limit {
             if limit_value > 0 {
                 final_results.truncate(limit_value);
             }
         }
 
-        return format_results(final_results, &selected_variables);
-    } else if let Err(err) = combined_parse {
-        let error_message = format_parse_error(sparql, err);
-        eprintln!("Failed to parse the query: {}", error_message);
-        return Vec::new();
+        Ok(format_results(final_results, &selected_variables))
     }
-
-    Vec::new()
 }
 
 // Convert the final BTreeMap results into Vec<Vec<String>>
@@ -643,28 +1117,72 @@ fn format_results(
         .map(|result| {
             selected_variables
                 .iter()
-                .map(|(_, var)| {
-                    // Strip '?' prefix from the variable we're looking for
-                    let var_stripped = var.strip_prefix('?').unwrap_or(var);
-                    
-                    // Try multiple lookup strategies
-                    result.get(var_stripped)           // without prefix
-                        .or_else(|| result.get(var.as_str()))  // with prefix
-                        .or_else(|| {
-                            // Try with ? added if not present
-                            let with_prefix = format!("?{}", var_stripped);
-                            result.get(with_prefix.as_str())
-                        })
-                        .cloned()
-                        .unwrap_or_default()
-                })
+                .map(|(_, var)| selected_value(&result, var).cloned().unwrap_or_default())
                 .collect()
         })
         .collect()
 }
 
+// Value of a selected variable in a result row, whether or not the row's keys carry the '?'
+fn selected_value<'r>(result: &'r BTreeMap<&str, String>, var: &str) -> Option<&'r String> {
+    // Strip '?' prefix from the variable we're looking for
+    let var_stripped = var.strip_prefix('?').unwrap_or(var);
+
+    // Try multiple lookup strategies
+    result.get(var_stripped)           // without prefix
+        .or_else(|| result.get(var))   // with prefix
+        .or_else(|| {
+            // Try with ? added if not present
+            let with_prefix = format!("?{}", var_stripped);
+            result.get(with_prefix.as_str())
+        })
+}
+
+/// SELECT DISTINCT: keep the first row for each combination of selected values
+fn distinct_results<'a>(
+    results: Vec<BTreeMap<&'a str, String>>,
+    selected_variables: &[(String, String)],
+) -> Vec<BTreeMap<&'a str, String>> {
+    let mut seen = HashSet::new();
+    results
+        .into_iter()
+        .filter(|result| {
+            let values: Vec<Option<String>> = selected_variables
+                .iter()
+                .map(|(_, var)| selected_value(result, var).cloned())
+                .collect();
+            seen.insert(values)
+        })
+        .collect()
+}
+
+/// Column names (without the leading `?`) of the rows returned by
+/// `execute_query_rayon_parallel2_volcano` for `sparql`, in the same order.
+/// Returns an empty list for queries that do not project variables.
+pub fn query_result_variables(sparql: &str) -> Vec<String> {
+    let Ok((_, combined)) = parse_combined_query(normalize_query(sparql)) else {
+        return Vec::new();
+    };
+    let (insert_clause, variables, patterns, _, _, _, values_clause, binds, subqueries, ..) = combined.sparql;
+    if insert_clause.is_some() || combined.delete_clause.is_some() {
+        return Vec::new();
+    }
+
+    let variables = expand_select_star(variables, &patterns, &binds, values_clause.as_ref(), &subqueries);
+
+    let mut selected_variables: Vec<(String, String)> = Vec::new();
+    let mut aggregation_vars: Vec<(&str, &str, &str)> = Vec::new();
+    let mut select_expressions: Vec<(SelectExpression, &str)> = Vec::new();
+    process_variables(&mut selected_variables, &mut aggregation_vars, &mut select_expressions, variables);
+    selected_variables
+        .into_iter()
+        .map(|(_, var)| var.strip_prefix('?').unwrap_or(&var).to_string())
+        .collect()
+}
+
 // Helper function to normalize the query by removing any RULE prefix
 fn normalize_query(sparql: &str) -> &str {
+    let sparql = skip_leading_comments(sparql);
     if sparql.contains("RULE") {
         if let Some(pos) = sparql.find("SELECT") {
             &sparql[pos..]
@@ -676,6 +1194,35 @@ fn normalize_query(sparql: &str) -> &str {
     }
 }
 
+/// `sparql` without the `#` comment lines, such as hints, it starts with
+fn skip_leading_comments(mut sparql: &str) -> &str {
+    loop {
+        sparql = sparql.trim_start();
+        match sparql.strip_prefix('#') {
+            Some(comment) => sparql = comment.split_once('\n').map_or("", |(_, rest)| rest),
+            None => return sparql,
+        }
+    }
+}
+
+/// Planner hints from the comments `sparql` starts with: `# kolibrie:join
+/// <name>` forces a join algorithm and `# HINT <hint>` lines are read by
+/// `QueryHints::apply`
+fn query_hints(sparql: &str) -> Result<QueryHints, SparqlError> {
+    let comments = &sparql[..sparql.len() - skip_leading_comments(sparql).len()];
+    let mut hints = QueryHints::default();
+    for line in comments.lines() {
+        let comment = line.trim().trim_start_matches('#').trim();
+        if let Some(name) = comment.strip_prefix("kolibrie:join") {
+            let algorithm = name.trim().parse().map_err(SparqlError::ParseError)?;
+            hints.join_algorithm = Some(algorithm);
+        } else if let Some(("HINT", hint)) = comment.split_once(char::is_whitespace) {
+            hints.apply(hint).map_err(SparqlError::ParseError)?;
+        }
+    }
+    Ok(hints)
+}
+
 // Helper function to process INSERT clause
 fn process_insert_clause(insert_clause: Option<InsertClause>, database: &mut SparqlDatabase) {
     if let Some(insert_clause) = insert_clause {
@@ -716,6 +1263,7 @@ fn process_delete_clause(delete_clause: Option<DeleteClause>, database: &mut Spa
 fn process_variables<'a>(
     selected_variables: &mut Vec<(String, String)>,
     aggregation_vars: &mut Vec<(&'a str, &'a str, &'a str)>,
+    select_expressions: &mut Vec<(SelectExpression<'a>, &'a str)>,
     variables: Vec<(&'a str, &'a str, Option<&'a str>)>,
 ) {
     for (agg_type, var, opt_output_var) in variables {
@@ -727,12 +1275,52 @@ fn process_variables<'a>(
             };
             aggregation_vars.push((agg_type, var, output_var));
             selected_variables.push(("VAR".to_string(), output_var.to_string()));
+        } else if let (Some(expression), Some(output_var)) =
+            (select_expression(agg_type, var), opt_output_var)
+        {
+            select_expressions.push((expression, output_var));
+            selected_variables.push(("VAR".to_string(), output_var.to_string()));
         } else {
             selected_variables.push((agg_type.to_string(), var.to_string()));
         }
     }
 }
 
+// Evaluate computed projections such as (ROUND(?price) AS ?rounded) on each
+// row; rows where no value can be computed leave the variable unbound
+fn apply_select_expressions<'a>(
+    results: &mut [BTreeMap<&'a str, String>],
+    select_expressions: &[(SelectExpression<'a>, &'a str)],
+    database: &SparqlDatabase,
+) {
+    for (expression, output_var) in select_expressions {
+        for row in results.iter_mut() {
+            if let Some(value) = evaluate_select_expression(expression, row, database) {
+                row.insert(*output_var, value);
+            }
+        }
+    }
+}
+
+// Value of a computed projection or BIND expression on one row; `None` when
+// it raises an error, such as an unbound argument
+fn evaluate_select_expression<'a>(
+    expression: &SelectExpression<'a>,
+    row: &BTreeMap<&'a str, String>,
+    database: &SparqlDatabase,
+) -> Option<String> {
+    let resolve = |var: &str| row.get(var).cloned();
+    match expression {
+        SelectExpression::NumericFunction(func, args) => evaluate_numeric_call(*func, args, &resolve),
+        SelectExpression::Coalesce(args) => evaluate_coalesce(args, &resolve),
+        SelectExpression::If(condition, then, otherwise) => {
+            let holds = database.evaluate_filter_expression(row, condition);
+            evaluate_if(holds, then, otherwise, &resolve)
+        }
+        SelectExpression::Iri(arg) => evaluate_iri(arg, &resolve),
+    }
+}
+
 // Helper function to initialize results based on VALUES clause
 fn initialize_results(values_clause: &Option<ValuesClause>) -> Vec<BTreeMap<&'static str, String>> {
     if let Some(values_clause) = values_clause {
@@ -919,6 +1507,69 @@ fn find_highest_sensor_value(
     highest_value
 }
 
+/// Candidate triples for one pattern, scanned across all shards in parallel.
+/// Only a bound predicate narrows the scan; the join checks the rest.
+fn scan_partitions(
+    store: &PartitionedTripleStore,
+    predicate: &str,
+    database: &SparqlDatabase,
+) -> Vec<Triple> {
+    let predicate_id = if predicate.starts_with('?') {
+        None
+    } else {
+        match database.dictionary.read().unwrap().string_to_id.get(predicate) {
+            Some(&id) => Some(id),
+            None => return Vec::new(),
+        }
+    };
+    store.scan(None, predicate_id, None)
+}
+
+/// Join rows of decoded values with the triples of one pattern. The rows are
+/// encoded as `Binding`s for the join and decoded again afterwards, giving
+/// each variable back the `&'a str` it was borrowed from.
+fn join_rows<'a>(
+    database: &SparqlDatabase,
+    subject_var: &'a str,
+    predicate: String,
+    object_var: &'a str,
+    triples: Vec<Triple>,
+    rows: Vec<BTreeMap<&'a str, String>>,
+    literal_filter: Option<String>,
+) -> Vec<BTreeMap<&'a str, String>> {
+    let mut variables: HashMap<&str, &'a str> = HashMap::new();
+    variables.insert(subject_var, subject_var);
+    variables.insert(object_var, object_var);
+
+    let mut bindings = Vec::with_capacity(rows.len());
+    {
+        let mut dictionary = database.dictionary.write().unwrap();
+        for row in &rows {
+            variables.extend(row.keys().map(|var| (*var, *var)));
+            bindings.push(Binding::encode(
+                row.iter().map(|(var, value)| (*var, value.as_str())),
+                &mut dictionary,
+            ));
+        }
+    }
+
+    let pattern = JoinPattern { subject_var, predicate, object_var, literal_filter };
+    let joined =
+        database.perform_join_par_simd_with_strict_filter_1(pattern, triples, &database.dictionary, bindings);
+
+    // Quoted triples have no dictionary entry, so rows binding one are dropped
+    let dictionary = database.dictionary.read().unwrap();
+    joined
+        .iter()
+        .filter_map(|binding| {
+            binding
+                .iter()
+                .map(|(var, id)| Some((variables[var], dictionary.decode(id)?.to_string())))
+                .collect()
+        })
+        .collect()
+}
+
 // Helper function to resolve triple pattern terms
 fn resolve_triple_pattern(
     subject_var: &str,
@@ -1048,6 +1699,17 @@ fn process_bind_clauses<'a>(
                     row.insert(new_var, is_qt.to_string());
                 }
             }
+        } else if func_name.eq_ignore_ascii_case("IF") || func_name.eq_ignore_ascii_case("IRI") {
+            // `parse_bind` passes the argument text of IF and IRI on whole
+            let Some(expression) = args.first().and_then(|args| select_expression(func_name, args)) else {
+                error!("Invalid arguments to {}", func_name);
+                continue;
+            };
+            for row in final_results.iter_mut() {
+                if let Some(value) = evaluate_select_expression(&expression, row, database) {
+                    row.insert(new_var, value);
+                }
+            }
         } else if let Some(func) = database.udfs.get(func_name) {
             for row in final_results.iter_mut() {
                 let resolved_args: Vec<&str> = args
@@ -1064,7 +1726,7 @@ fn process_bind_clauses<'a>(
                 row.insert(new_var, result);
             }
         } else {
-            eprintln!("UDF {} not found", func_name);
+            error!("UDF {} not found", func_name);
         }
     }
 }
@@ -1090,7 +1752,7 @@ pub fn group_and_aggregate_results<'a>(
         let mut agg_values: HashMap<&'a str, f64> = HashMap::new();
         for (_, var, output_var_name) in aggregation_vars {
             if let Some(value_str) = result.get(*var) {
-                if let Ok(value) = value_str.parse::<f64>() {
+                if let Some(value) = parse_numeric_literal(value_str) {
                     agg_values.insert(*output_var_name, value);
                 }
             }
@@ -1148,6 +1810,98 @@ pub fn group_and_aggregate_results<'a>(
         .collect()
 }
 
+/// Evaluate a nested SELECT on its own (patterns, filters, binds, grouping,
+/// projection and limit) and return its solutions as an in-memory buffer.
+/// Variables the subquery does not project stay invisible to the outer query.
+fn evaluate_subquery_to_buffer(
+    subquery: &SubQuery,
+    evaluation: &SelectEvaluation,
+) -> Result<LogicalOperator, SparqlError> {
+    let database = evaluation.database;
+    let prefixes = evaluation.prefixes;
+    let mut selected_variables: Vec<(String, String)> = Vec::new();
+    let mut aggregation_vars: Vec<(&str, &str, &str)> = Vec::new();
+    let mut select_expressions: Vec<(SelectExpression, &str)> = Vec::new();
+    let variables = expand_select_star(
+        subquery.variables.clone(),
+        &subquery.patterns,
+        &subquery.binds,
+        subquery._values_clause.as_ref(),
+        &[],
+    );
+    process_variables(&mut selected_variables, &mut aggregation_vars, &mut select_expressions, variables);
+
+    let resolved_patterns: Vec<(&str, &str, &str)> = subquery
+        .patterns
+        .iter()
+        .map(|(subject_var, predicate, object_var)| {
+            let (resolved_subject, resolved_predicate, resolved_object) =
+                resolve_triple_pattern(subject_var, predicate, object_var, database, prefixes);
+            let subject_static: &'static str = Box::leak(resolved_subject.into_boxed_str());
+            let predicate_static: &'static str = Box::leak(resolved_predicate.into_boxed_str());
+            let object_static: &'static str = Box::leak(resolved_object.into_boxed_str());
+            (subject_static, predicate_static, object_static)
+        })
+        .collect();
+
+    // No projection here: grouping needs the inner variables
+    let inner_plan = build_logical_plan(
+        Vec::new(),
+        resolved_patterns,
+        subquery.filters.clone(),
+        prefixes,
+        database,
+        &subquery.binds,
+        None,
+    );
+
+    let rows: Vec<HashMap<String, String>> = evaluation
+        .optimizer()
+        .find_best_plan(&inner_plan)
+        .execute_cancellable(database, evaluation.cancel)?;
+
+    let mut results: Vec<BTreeMap<&str, String>> = rows
+        .into_iter()
+        .map(|row| {
+            row.into_iter()
+                .map(|(k, v)| {
+                    let key: &str = Box::leak(format!("?{}", k.trim_start_matches('?')).into_boxed_str());
+                    (key, v)
+                })
+                .collect()
+        })
+        .collect();
+
+    if !subquery.group_vars.is_empty() || !aggregation_vars.is_empty() {
+        results = group_and_aggregate_results(results, &subquery.group_vars, &aggregation_vars);
+    }
+    apply_select_expressions(&mut results, &select_expressions, database);
+    if subquery.distinct {
+        results = distinct_results(results, &selected_variables);
+    }
+    if let Some(limit) = subquery.limit {
+        results.truncate(limit);
+    }
+
+    let projected: Vec<&str> = selected_variables.iter().map(|(_, var)| var.as_str()).collect();
+
+    let mut dict = database.dictionary.write().unwrap();
+    let content: Bindings = results
+        .iter()
+        .map(|row| {
+            projected
+                .iter()
+                .filter_map(|var| {
+                    let value = row.get(*var)?;
+                    Some((var.trim_start_matches('?').to_string(), dict.encode(value)))
+                })
+                .collect()
+        })
+        .collect();
+
+    Ok(LogicalOperator::buffer(content, "subquery".to_string()))
+}
+
 fn merge_results<'a>(
     main_results: Vec<BTreeMap<&'a str, String>>,
     subquery_results: Vec<BTreeMap<&'a str, String>>,
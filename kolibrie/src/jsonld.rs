@@ -0,0 +1,295 @@
+/*
+ * Copyright © 2026 Volodymyr Kadzhaia
+ * Copyright © 2026 Pieter Bonte
+ * KU Leuven — Stream Intelligence Lab, Belgium
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this file,
+ * you can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+//! Conversion of JSON-LD documents to N-Triples.
+//!
+//! Covers the subset Linked Data endpoints commonly serve: inline `@context`
+//! objects (terms, prefixes, `@vocab`, `"@type": "@id"` coercion), node
+//! objects with `@id` and `@type`, nested nodes, `@graph`, `@value` objects
+//! with `@type` or `@language`, and `@list`/`@set` arrays (list members
+//! become plain values). Remote contexts are not fetched, so properties that
+//! only they define are skipped.
+
+use log::warn;
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+
+const RDF_TYPE: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#type";
+const XSD: &str = "http://www.w3.org/2001/XMLSchema#";
+
+/// Convert a JSON-LD document to N-Triples text, one triple per line.
+pub fn jsonld_to_ntriples(data: &str) -> Result<String, String> {
+    let document: Value = serde_json::from_str(data).map_err(|e| format!("invalid JSON-LD: {}", e))?;
+    let mut converter = Converter::default();
+    converter.top_level(&document, &Context::default());
+    Ok(converter.output)
+}
+
+#[derive(Clone, Default)]
+struct Context {
+    vocab: Option<String>,
+    /// Term or prefix -> IRI, and whether string values are coerced to IRIs
+    /// (`Some("@id")`) or to a datatype
+    terms: HashMap<String, (String, Option<String>)>,
+}
+
+impl Context {
+    /// A copy of `self` with the definitions of a `@context` value applied
+    fn extend(&self, definition: &Value) -> Context {
+        let mut context = self.clone();
+        match definition {
+            Value::Array(items) => {
+                for item in items {
+                    context = context.extend(item);
+                }
+            }
+            Value::Object(map) => {
+                if let Some(Value::String(vocab)) = map.get("@vocab") {
+                    context.vocab = Some(context.expand(vocab));
+                }
+                for (term, value) in map {
+                    if term.starts_with('@') {
+                        continue;
+                    }
+                    let definition = match value {
+                        Value::String(iri) => Some((iri.clone(), None)),
+                        Value::Object(entry) => entry.get("@id").and_then(Value::as_str).map(|iri| {
+                            let coercion = entry.get("@type").and_then(Value::as_str).map(str::to_string);
+                            (iri.to_string(), coercion)
+                        }),
+                        _ => None,
+                    };
+                    if let Some((iri, coercion)) = definition {
+                        let iri = context.expand(&iri);
+                        let coercion = coercion.map(|c| if c == "@id" { c } else { context.expand(&c) });
+                        context.terms.insert(term.clone(), (iri, coercion));
+                    }
+                }
+            }
+            Value::String(url) => warn!("JSON-LD remote context {} is not fetched", url),
+            _ => {}
+        }
+        context
+    }
+
+    /// Expand a term, compact IRI or IRI; relative names fall back to `@vocab`
+    fn expand(&self, name: &str) -> String {
+        if let Some((iri, _)) = self.terms.get(name) {
+            return iri.clone();
+        }
+        if let Some((prefix, local)) = name.split_once(':') {
+            if let Some((iri, _)) = self.terms.get(prefix) {
+                return format!("{}{}", iri, local);
+            }
+            return name.to_string();
+        }
+        match &self.vocab {
+            Some(vocab) => format!("{}{}", vocab, name),
+            None => name.to_string(),
+        }
+    }
+
+    /// Expand a property key, or `None` when it does not map to an absolute IRI
+    fn property(&self, key: &str) -> Option<String> {
+        let iri = self.expand(key);
+        iri.contains(':').then_some(iri)
+    }
+}
+
+#[derive(Default)]
+struct Converter {
+    output: String,
+    blank_nodes: usize,
+}
+
+impl Converter {
+    fn top_level(&mut self, value: &Value, context: &Context) {
+        match value {
+            Value::Array(items) => {
+                for item in items {
+                    self.top_level(item, context);
+                }
+            }
+            Value::Object(map) => {
+                let context = match map.get("@context") {
+                    Some(definition) => context.extend(definition),
+                    None => context.clone(),
+                };
+                // A document made only of a context and a graph has no node of its own
+                let is_graph_container = map.keys().all(|key| key == "@context" || key == "@graph");
+                if let Some(graph) = map.get("@graph") {
+                    self.top_level(graph, &context);
+                }
+                if !is_graph_container {
+                    self.node(map, &context);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Emit the triples of a node object and return its subject term
+    fn node(&mut self, map: &Map<String, Value>, context: &Context) -> String {
+        let context = match map.get("@context") {
+            Some(definition) => context.extend(definition),
+            None => context.clone(),
+        };
+        let subject = match map.get("@id").and_then(Value::as_str) {
+            Some(id) => self.resource(id, &context),
+            None => self.fresh_blank_node(),
+        };
+
+        for (key, value) in map {
+            match key.as_str() {
+                "@type" => {
+                    for class in as_list(value) {
+                        if let Some(class) = class.as_str() {
+                            let class = self.resource(class, &context);
+                            self.emit(&subject, &format!("<{}>", RDF_TYPE), &class);
+                        }
+                    }
+                }
+                "@graph" => self.top_level(value, &context),
+                key if key.starts_with('@') => {}
+                key => {
+                    let Some(predicate) = context.property(key) else {
+                        warn!("JSON-LD property {} has no IRI mapping; skipped", key);
+                        continue;
+                    };
+                    let coercion = context.terms.get(key).and_then(|(_, coercion)| coercion.clone());
+                    let predicate = format!("<{}>", predicate);
+                    for item in as_list(value) {
+                        if let Some(object) = self.object(item, coercion.as_deref(), &context) {
+                            self.emit(&subject, &predicate, &object);
+                        }
+                    }
+                }
+            }
+        }
+        subject
+    }
+
+    fn object(&mut self, value: &Value, coercion: Option<&str>, context: &Context) -> Option<String> {
+        match value {
+            Value::Null => None,
+            Value::Bool(flag) => Some(typed_literal(&flag.to_string(), &format!("{}boolean", XSD))),
+            Value::Number(number) => {
+                let datatype = if number.is_f64() { "double" } else { "integer" };
+                Some(typed_literal(&number.to_string(), &format!("{}{}", XSD, datatype)))
+            }
+            Value::String(text) => Some(match coercion {
+                Some("@id") => self.resource(text, context),
+                Some(datatype) => typed_literal(text, datatype),
+                None => format!("\"{}\"", escape(text)),
+            }),
+            Value::Object(map) => {
+                if let Some(literal) = map.get("@value") {
+                    let text = match literal {
+                        Value::String(text) => text.clone(),
+                        other => other.to_string(),
+                    };
+                    if let Some(datatype) = map.get("@type").and_then(Value::as_str) {
+                        return Some(typed_literal(&text, &context.expand(datatype)));
+                    }
+                    if let Some(language) = map.get("@language").and_then(Value::as_str) {
+                        return Some(format!("\"{}\"@{}", escape(&text), language));
+                    }
+                    return self.object(literal, coercion, context);
+                }
+                Some(self.node(map, context))
+            }
+            Value::Array(_) => None,
+        }
+    }
+
+    /// An IRI or blank node term for a node identifier
+    fn resource(&self, id: &str, context: &Context) -> String {
+        if id.starts_with("_:") {
+            id.to_string()
+        } else {
+            format!("<{}>", context.expand(id))
+        }
+    }
+
+    fn fresh_blank_node(&mut self) -> String {
+        self.blank_nodes += 1;
+        format!("_:jsonld{}", self.blank_nodes)
+    }
+
+    fn emit(&mut self, subject: &str, predicate: &str, object: &str) {
+        self.output.push_str(&format!("{} {} {} .\n", subject, predicate, object));
+    }
+}
+
+/// The members of a value, looking through arrays and `@list`/`@set` objects
+fn as_list(value: &Value) -> Vec<&Value> {
+    match value {
+        Value::Array(items) => items.iter().flat_map(as_list).collect(),
+        Value::Object(map) if !map.contains_key("@value") => {
+            match map.get("@list").or_else(|| map.get("@set")) {
+                Some(items) => as_list(items),
+                None => vec![value],
+            }
+        }
+        _ => vec![value],
+    }
+}
+
+fn typed_literal(text: &str, datatype: &str) -> String {
+    format!("\"{}\"^^<{}>", escape(text), datatype)
+}
+
+fn escape(text: &str) -> String {
+    text.replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::jsonld_to_ntriples;
+
+    #[test]
+    fn converts_contexts_nodes_and_literals() {
+        let document = r#"{
+            "@context": {
+                "@vocab": "http://schema.org/",
+                "ex": "http://example.org/",
+                "knows": { "@id": "http://xmlns.com/foaf/0.1/knows", "@type": "@id" }
+            },
+            "@graph": [
+                {
+                    "@id": "ex:alice",
+                    "@type": "Person",
+                    "name": [{ "@value": "Alice", "@language": "en" }, "Al \"the\" ice"],
+                    "age": 30,
+                    "knows": "ex:bob",
+                    "address": { "city": "Leuven" }
+                }
+            ]
+        }"#;
+        let ntriples = jsonld_to_ntriples(document).unwrap();
+        let lines: Vec<&str> = ntriples.lines().collect();
+        for expected in [
+            "<http://example.org/alice> <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> <http://schema.org/Person> .",
+            "<http://example.org/alice> <http://schema.org/name> \"Alice\"@en .",
+            "<http://example.org/alice> <http://schema.org/name> \"Al \\\"the\\\" ice\" .",
+            "<http://example.org/alice> <http://schema.org/age> \"30\"^^<http://www.w3.org/2001/XMLSchema#integer> .",
+            "<http://example.org/alice> <http://xmlns.com/foaf/0.1/knows> <http://example.org/bob> .",
+            "<http://example.org/alice> <http://schema.org/address> _:jsonld1 .",
+            "_:jsonld1 <http://schema.org/city> \"Leuven\" .",
+        ] {
+            assert!(lines.contains(&expected), "missing `{}` in:\n{}", expected, ntriples);
+        }
+        assert_eq!(lines.len(), 7, "{}", ntriples);
+        assert!(jsonld_to_ntriples("{ not json").is_err());
+    }
+}
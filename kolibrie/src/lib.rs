@@ -8,19 +8,38 @@
  * you can obtain one at https://mozilla.org/MPL/2.0/.
  */
 
+#[cfg(feature = "async")]
+pub mod async_query;
+pub mod compression;
+pub mod custom_error;
+pub mod describe;
+pub mod display;
+pub mod entailment;
 pub mod error_handler;
 pub mod execute_ml;
 pub mod execute_ml_train;
 pub mod execute_query;
+pub mod jsonld;
 pub mod ml_feature_loader;
 pub mod ml_predict_candle;
 pub mod ml_predict_runtime;
 pub mod neural_relations;
 pub mod parser;
+pub mod partitioned_store;
+pub mod pattern_cache;
+pub mod prefix_index;
 pub mod query_builder;
+pub mod query_cache;
+pub mod query_normalizer;
+#[cfg(feature = "remote")]
+pub mod remote;
 pub mod rsp_engine;
 pub mod sparql_database;
+pub mod sparql_results;
+pub mod text_index;
+pub mod tuning;
 pub mod utils;
 pub mod streamertail_optimizer;
 pub mod rsp;
 pub mod query_engine;
+pub mod rdf_format;
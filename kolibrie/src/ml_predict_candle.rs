@@ -120,7 +120,7 @@ pub fn try_candle_predict(
 ///
 /// Requires exactly one relation for the model, otherwise callers fall back to Python
 pub fn try_candle_predict_by_model_name(
-    database: &mut SparqlDatabase,
+    database: &SparqlDatabase,
     model_name: &str,
     input_rows: &[HashMap<String, u32>],
 ) -> CandleResult<Option<CandleDispatch>> {
@@ -10,20 +10,22 @@
 
 use nom::{
     branch::alt,
-    bytes::complete::{tag, take_until, take_while1},
+    bytes::complete::{tag, tag_no_case, take_until, take_while1},
     character::complete::{char, multispace0, multispace1, space0, space1},
     combinator::{opt, recognize},
-    multi::{many0, many1, separated_list1},
+    multi::{many0, many1, separated_list0, separated_list1},
     sequence::{delimited, preceded, terminated},
     IResult,
     Parser
 };
 use rayon::str;
+use log::{debug, warn};
 use crate::neural_relations::{
     execute_train_decl, materialize_neural_relations_for_patterns,
     register_neural_declarations,
 };
 use crate::sparql_database::SparqlDatabase;
+use crate::custom_error::SparqlError;
 use datalog::reasoning::Reasoner;
 use shared::triple::Triple;
 use shared::dictionary::Dictionary;
@@ -34,6 +36,7 @@ use shared::query::*;
 // Add RSP imports
 use crate::rsp::s2r::{CSPARQLWindow, Report, ReportStrategy, Tick, WindowTriple, ContentContainer};
 use crate::rsp::r2s::{Relation2StreamOperator, StreamOperator};
+use std::borrow::Cow;
 use std::collections::HashMap;
 
 // Helper function to recognize identifiers
@@ -62,9 +65,116 @@ pub fn variable(input: &str) -> IResult<&str, &str> {
     recognize((char('?'), identifier)).parse(input)
 }
 
-// Parser for a literal value within double quotes
+// Parser for a quoted string body as written, honouring backslash escapes so
+// that an escaped quote does not end the string
+fn string_body(quote: char) -> impl Fn(&str) -> IResult<&str, &str> {
+    move |input: &str| {
+        let error = || nom::Err::Error(nom::error::Error::new(input, nom::error::ErrorKind::Char));
+        let body = input.strip_prefix(quote).ok_or_else(error)?;
+        let mut escaped = false;
+        for (idx, ch) in body.char_indices() {
+            match ch {
+                _ if escaped => escaped = false,
+                '\\' => escaped = true,
+                _ if ch == quote => return Ok((&body[idx + 1..], &body[..idx])),
+                _ => {}
+            }
+        }
+        Err(error())
+    }
+}
+
+/// The value of a SPARQL string body: `\t`, `\n`, `\r`, `\b`, `\f`, `\"`,
+/// `\'`, `\\`, `\uXXXX` and `\UXXXXXXXX` are replaced by the characters they
+/// stand for; an unknown escape is kept as written
+pub fn unescape_literal(body: &str) -> Cow<'_, str> {
+    if !body.contains('\\') {
+        return Cow::Borrowed(body);
+    }
+    let mut value = String::with_capacity(body.len());
+    let mut chars = body.chars();
+    while let Some(c) = chars.next() {
+        if c != '\\' {
+            value.push(c);
+            continue;
+        }
+        let rest = chars.as_str();
+        let unescaped = match chars.next() {
+            Some('t') => Some('\t'),
+            Some('n') => Some('\n'),
+            Some('r') => Some('\r'),
+            Some('b') => Some('\u{8}'),
+            Some('f') => Some('\u{c}'),
+            Some(quoted @ ('"' | '\'' | '\\')) => Some(quoted),
+            Some(width @ ('u' | 'U')) => {
+                let digits = if width == 'u' { 4 } else { 8 };
+                let code = rest
+                    .get(1..1 + digits)
+                    .filter(|hex| hex.chars().all(|c| c.is_ascii_hexdigit()))
+                    .and_then(|hex| u32::from_str_radix(hex, 16).ok());
+                match code.and_then(char::from_u32) {
+                    Some(decoded) => {
+                        chars = rest[1 + digits..].chars();
+                        Some(decoded)
+                    }
+                    None => None,
+                }
+            }
+            _ => None,
+        };
+        match unescaped {
+            Some(decoded) => value.push(decoded),
+            None => {
+                // Keep the backslash and re-read what follows it
+                value.push('\\');
+                chars = rest.chars();
+            }
+        }
+    }
+    Cow::Owned(value)
+}
+
+// Parser for a literal value within double or single quotes, yielding its
+// unescaped value. Parsed queries borrow their terms, so a value that differs
+// from the text (only when it has escapes) is leaked like resolved query terms
 pub fn parse_literal(input: &str) -> IResult<&str, &str> {
-    delimited(char('"'), take_while1(|c| c != '"'), char('"')).parse(input)
+    let (input, body) = alt((string_body('"'), string_body('\''))).parse(input)?;
+    let value = match unescape_literal(body) {
+        Cow::Borrowed(value) => value,
+        Cow::Owned(value) => Box::leak(value.into_boxed_str()),
+    };
+    Ok((input, value))
+}
+
+// Parser for a language-tagged literal, kept whole as it is stored: "chat"@fr
+pub fn parse_language_literal(input: &str) -> IResult<&str, &str> {
+    recognize((
+        string_body('"'),
+        char('@'),
+        take_while1(|c: char| c.is_ascii_alphanumeric() || c == '-'),
+    )).parse(input)
+}
+
+// Parser for a typed literal, kept whole as it is stored: "42"^^xsd:integer
+pub fn parse_typed_literal(input: &str) -> IResult<&str, &str> {
+    recognize((
+        string_body('"'),
+        tag("^^"),
+        alt((parse_full_uri, prefixed_identifier)),
+    )).parse(input)
+}
+
+// Parser for a literal in a FILTER comparison; a datatype such as
+// `^^xsd:dateTime` is accepted and dropped, as comparisons go by lexical
+// value, while a language-tagged literal is kept whole as it is stored
+pub fn parse_comparison_literal(input: &str) -> IResult<&str, &str> {
+    alt((
+        parse_language_literal,
+        terminated(
+            parse_literal,
+            opt(preceded(tag("^^"), alt((parse_full_uri, prefixed_identifier)))),
+        ),
+    )).parse(input)
 }
 
 // Parser for a URI within angle brackets
@@ -80,9 +190,7 @@ pub fn parse_full_uri(input: &str) -> IResult<&str, &str> {
 // Parser for a full literal including quotes and optional lang/datatype
 pub fn parse_full_literal(input: &str) -> IResult<&str, &str> {
     recognize((
-        char('"'),
-        take_while1(|c: char| c != '"'),
-        char('"'),
+        string_body('"'),
         opt(alt((
             recognize((tag("^^"), parse_full_uri)),
             recognize((char('@'), identifier)),
@@ -150,6 +258,8 @@ pub fn parse_predicate_object(input: &str) -> IResult<&str, (&str, &str)> {
         parse_quoted_triple,          // << s p o >> (RDF-star)
         parse_uri,                    // <http://...>
         variable,                     // ?variable
+        parse_language_literal,       // "literal"@lang
+        parse_typed_literal,          // "literal"^^datatype
         parse_literal,                // "literal"
         recognize((char(':'), identifier)), // :localname (like :Stream)
         prefixed_identifier,          // prefix:localname
@@ -202,8 +312,7 @@ pub fn parse_value_term(input: &str) -> IResult<&str, Value> {
         delimited(char('<'), take_while1(|c| c != '>'), char('>'))
             .map(|s: &str| Value::Term(s.to_string())),
         // Parse Literal in ""
-        delimited(char('"'), take_while1(|c| c != '"'), char('"'))
-            .map(|s: &str| Value::Term(s.to_string())),
+        parse_literal.map(|s: &str| Value::Term(s.to_string())),
         // Parse prefixed name
         prefixed_identifier.map(|s| Value::Term(s.to_string())),
         // Parse identifier
@@ -272,21 +381,84 @@ pub fn parse_aggregate(input: &str) -> IResult<&str, (&str, &str, Option<&str>)>
 }
 
 pub fn parse_select(input: &str) -> IResult<&str, Vec<(&str, &str, Option<&str>)>> {
+    let (input, (_, variables)) = parse_select_clause(input)?;
+    Ok((input, variables))
+}
+
+/// Projection of a SELECT clause: per item its kind (`VAR`, an aggregate or
+/// a function), the variable or arguments it reads, and its `AS` alias
+pub type SelectProjection<'a> = Vec<(&'a str, &'a str, Option<&'a str>)>;
+
+/// `SELECT [DISTINCT] ...`, returning whether `DISTINCT` was given along with
+/// the projection
+pub fn parse_select_clause(input: &str) -> IResult<&str, (bool, SelectProjection<'_>)> {
     let (input, _) = tag("SELECT").parse(input)?;
     let (input, _) = space1.parse(input)?;
+    let (input, distinct) = opt(terminated(tag("DISTINCT"), space1)).parse(input)?;
+    let distinct = distinct.is_some();
 
     // Check if the next token is '*'
     if let Ok((input, _)) = tag::<_, _, nom::error::Error<&str>>("*").parse(input) {
-        return Ok((input, vec![("*", "*", None)]));
+        return Ok((input, (distinct, vec![("*", "*", None)])));
     }
 
     // Parse variables or aggregation functions
     let (input, variables) = separated_list1(
         space1,
-        alt((variable.map(|var| ("VAR", var, None)), parse_aggregate)),
+        alt((variable.map(|var| ("VAR", var, None)), parse_aggregate, parse_select_expression)),
     ).parse(input)?;
 
-    Ok((input, variables))
+    Ok((input, (distinct, variables)))
+}
+
+/// Replace the `SELECT *` marker produced by `parse_select` with every variable
+/// the WHERE clause binds, in order of first appearance: triple patterns
+/// (including those nested in quoted triples), BIND targets, VALUES variables
+/// and the projections of subqueries. Explicit projections are returned as is.
+pub fn expand_select_star<'a>(
+    variables: Vec<(&'a str, &'a str, Option<&'a str>)>,
+    patterns: &[(&'a str, &'a str, &'a str)],
+    binds: &[(&'a str, Vec<&'a str>, &'a str)],
+    values_clause: Option<&ValuesClause<'a>>,
+    subqueries: &[SubQuery<'a>],
+) -> Vec<(&'a str, &'a str, Option<&'a str>)> {
+    if variables != vec![("*", "*", None)] {
+        return variables;
+    }
+
+    let mut bound: Vec<&'a str> = Vec::new();
+    let mut add = |var: &'a str| {
+        if !bound.contains(&var) {
+            bound.push(var);
+        }
+    };
+    for (subject, predicate, object) in patterns {
+        for term in [*subject, *predicate, *object] {
+            term.split(|c: char| c.is_whitespace() || c == '<' || c == '>')
+                .filter(|token| token.starts_with('?') && token.len() > 1)
+                .for_each(&mut add);
+        }
+    }
+    for (_, _, new_var) in binds {
+        add(new_var);
+    }
+    if let Some(values_clause) = values_clause {
+        values_clause.variables.iter().for_each(|var| add(var));
+    }
+    for subquery in subqueries {
+        let projected = expand_select_star(
+            subquery.variables.clone(),
+            &subquery.patterns,
+            &subquery.binds,
+            subquery._values_clause.as_ref(),
+            &[],
+        );
+        for (agg_type, var, alias) in projected {
+            add(if agg_type == "VAR" { var } else { alias.unwrap_or(var) });
+        }
+    }
+
+    bound.into_iter().map(|var| ("VAR", var, None)).collect()
 }
 
 // Parse a basic arithmetic operand (variable, literal, or number)
@@ -409,7 +581,7 @@ fn parse_arithmetic_comparison(input: &str) -> IResult<&str, FilterExpression<'_
         )),
         // variable/literal/number
         variable,
-        parse_literal,
+        parse_comparison_literal,
         take_while1(|c: char| c.is_digit(10) || c == '.'),
         // parenthesized expression
         recognize(delimited(
@@ -439,7 +611,7 @@ fn parse_arithmetic_comparison(input: &str) -> IResult<&str, FilterExpression<'_
         )),
         // variable/literal/number
         variable,
-        parse_literal,
+        parse_comparison_literal,
         take_while1(|c: char| c.is_digit(10) || c == '.'),
         // arithmetic expression
         recognize((
@@ -471,7 +643,7 @@ pub fn parse_comparison(input: &str) -> IResult<&str, FilterExpression<'_>> {
     // Parse variable or literal on left side
     let (input, left) = alt((
         variable,
-        parse_literal,
+        parse_comparison_literal,
         take_while1(|c: char| c.is_digit(10)),
     )).parse(input)?;
     
@@ -492,7 +664,7 @@ pub fn parse_comparison(input: &str) -> IResult<&str, FilterExpression<'_>> {
     // Parse variable or literal on right side
     let (input, right) = alt((
         variable,
-        parse_literal,
+        parse_comparison_literal,
         take_while1(|c: char| c.is_digit(10)),
     )).parse(input)?;
     
@@ -545,6 +717,333 @@ fn parse_function_call(input: &str) -> IResult<&str, FilterExpression<'_>> {
     Ok((input, FilterExpression::FunctionCall(func_name, args)))
 }
 
+// Parse a function call compared against a value: ex:upper(?name) = "ALICE"
+fn parse_function_comparison(input: &str) -> IResult<&str, FilterExpression<'_>> {
+    let (input, _) = multispace0.parse(input)?;
+    let (input, func_name) = alt((prefixed_identifier, identifier)).parse(input)?;
+    let (input, _) = multispace0.parse(input)?;
+    let (input, _) = char('(').parse(input)?;
+    let (input, _) = multispace0.parse(input)?;
+    let (input, args) = separated_list1(
+        (multispace0, char(','), multispace0),
+        alt((variable, parse_literal, take_while1(|c: char| c.is_ascii_digit() || c == '.'))),
+    ).parse(input)?;
+    let (input, _) = multispace0.parse(input)?;
+    let (input, _) = char(')').parse(input)?;
+    let (input, _) = multispace0.parse(input)?;
+    let (input, operator) = alt((
+        tag("="), tag("!="), tag(">="),
+        tag("<="), tag(">"), tag("<"),
+    )).parse(input)?;
+    let (input, _) = multispace0.parse(input)?;
+    let (input, value) = alt((
+        variable,
+        parse_comparison_literal,
+        take_while1(|c: char| c.is_ascii_digit() || c == '.'),
+    )).parse(input)?;
+    let (input, _) = multispace0.parse(input)?;
+    Ok((input, FilterExpression::FunctionComparison(func_name, args, operator, value)))
+}
+
+// Parser for a string literal that may be empty, dropping a language tag or
+// datatype
+fn parse_string_literal(input: &str) -> IResult<&str, &str> {
+    terminated(
+        parse_literal,
+        opt(alt((
+            preceded(tag("^^"), alt((parse_full_uri, prefixed_identifier))),
+            preceded(char('@'), take_while1(|c: char| c.is_alphanumeric() || c == '-')),
+        ))),
+    ).parse(input)
+}
+
+// Parse the name of a built-in string function such as STRLEN or ENCODE_FOR_URI
+fn string_function_name(input: &str) -> IResult<&str, StringFunction> {
+    let (rest, name) = take_while1(|c: char| c.is_ascii_alphabetic() || c == '_').parse(input)?;
+    match StringFunction::from_name(name) {
+        Some(func) => Ok((rest, func)),
+        None => Err(nom::Err::Error(nom::error::Error::new(
+            input,
+            nom::error::ErrorKind::Tag,
+        ))),
+    }
+}
+
+// Parse a string function argument: a variable, a (possibly empty) string
+// literal, a number, or a nested call
+fn parse_string_expression(input: &str) -> IResult<&str, StringExpression<'_>> {
+    alt((
+        |i| {
+            let (i, (func, args)) = parse_string_function_call(i)?;
+            Ok((i, StringExpression::Call(func, args)))
+        },
+        |i| variable(i).map(|(i, var)| (i, StringExpression::Variable(var))),
+        |i| parse_string_literal(i).map(|(i, literal)| (i, StringExpression::Literal(literal))),
+        |i| {
+            recognize((opt(char('-')), take_while1(|c: char| c.is_ascii_digit() || c == '.')))
+                .parse(i)
+                .map(|(i, number)| (i, StringExpression::Literal(number)))
+        },
+    )).parse(input)
+}
+
+// Parse a string function call: STRLEN(?name), CONCAT(?first, " ", ?last)
+fn parse_string_function_call(input: &str) -> IResult<&str, (StringFunction, Vec<StringExpression<'_>>)> {
+    let (input, func) = string_function_name(input)?;
+    let (input, _) = multispace0.parse(input)?;
+    let (input, _) = char('(').parse(input)?;
+    let (input, _) = multispace0.parse(input)?;
+    let (input, args) = separated_list0(
+        (multispace0, char(','), multispace0),
+        parse_string_expression,
+    ).parse(input)?;
+    let (input, _) = multispace0.parse(input)?;
+    let (input, _) = char(')').parse(input)?;
+    Ok((input, (func, args)))
+}
+
+// Parse a string function compared against a value: STRLEN(?name) > 3
+fn parse_string_function_comparison(input: &str) -> IResult<&str, FilterExpression<'_>> {
+    let (input, _) = multispace0.parse(input)?;
+    let (input, (func, args)) = parse_string_function_call(input)?;
+    let (input, _) = multispace0.parse(input)?;
+    let (input, operator) = alt((
+        tag("="), tag("!="), tag(">="),
+        tag("<="), tag(">"), tag("<"),
+    )).parse(input)?;
+    let (input, _) = multispace0.parse(input)?;
+    let (input, value) = alt((
+        variable,
+        parse_string_literal,
+        recognize((opt(char('-')), take_while1(|c: char| c.is_ascii_digit() || c == '.'))),
+    )).parse(input)?;
+    let (input, _) = multispace0.parse(input)?;
+    Ok((input, FilterExpression::StringFunctionComparison(func, args, operator, value)))
+}
+
+// Parse a string function used as a condition: CONTAINS(?name, "li")
+fn parse_string_function_filter(input: &str) -> IResult<&str, FilterExpression<'_>> {
+    let (input, _) = multispace0.parse(input)?;
+    let (input, (func, args)) = parse_string_function_call(input)?;
+    let (input, _) = multispace0.parse(input)?;
+    Ok((input, FilterExpression::StringFunction(func, args)))
+}
+
+// Parse COALESCE and its arguments: COALESCE(?nick, ?name, "anonymous")
+fn parse_coalesce_call(input: &str) -> IResult<&str, Vec<StringExpression<'_>>> {
+    let (input, _) = tag_no_case("COALESCE").parse(input)?;
+    let (input, _) = multispace0.parse(input)?;
+    let (input, _) = char('(').parse(input)?;
+    let (input, _) = multispace0.parse(input)?;
+    let (input, args) = separated_list1(
+        (multispace0, char(','), multispace0),
+        parse_string_expression,
+    ).parse(input)?;
+    let (input, _) = multispace0.parse(input)?;
+    let (input, _) = char(')').parse(input)?;
+    Ok((input, args))
+}
+
+// Parse COALESCE used as a condition: COALESCE(?nick, ?name)
+fn parse_coalesce_filter(input: &str) -> IResult<&str, FilterExpression<'_>> {
+    let (input, _) = multispace0.parse(input)?;
+    let (input, args) = parse_coalesce_call(input)?;
+    let (input, _) = multispace0.parse(input)?;
+    Ok((input, FilterExpression::Coalesce(args)))
+}
+
+// Parse the arguments of IF: a condition and the two branches,
+// ?age >= 18, "adult", "minor"
+fn parse_if_arguments(
+    input: &str,
+) -> IResult<&str, (FilterExpression<'_>, StringExpression<'_>, StringExpression<'_>)> {
+    let (input, condition) = parse_or(input)?;
+    let (input, _) = (multispace0, char(','), multispace0).parse(input)?;
+    let (input, then) = parse_string_expression(input)?;
+    let (input, _) = (multispace0, char(','), multispace0).parse(input)?;
+    let (input, otherwise) = parse_string_expression(input)?;
+    Ok((input, (condition, then, otherwise)))
+}
+
+// Recognize IF or IRI with the text of its arguments, for SELECT and BIND
+// items that keep the text: IF(?age >= 18, "adult", "minor"), IRI(?page)
+fn parse_if_or_iri_call(input: &str) -> IResult<&str, (&str, &str)> {
+    alt((
+        (
+            tag_no_case("IF"),
+            delimited(
+                (multispace0, char('('), multispace0),
+                recognize(parse_if_arguments),
+                (multispace0, char(')')),
+            ),
+        ),
+        (
+            tag_no_case("IRI"),
+            delimited(
+                (multispace0, char('('), multispace0),
+                recognize(parse_string_expression),
+                (multispace0, char(')')),
+            ),
+        ),
+    )).parse(input)
+}
+
+// Parse an optional comparison after IF or IRI: = "adult", = <http://example.org/>
+fn parse_value_comparison(input: &str) -> IResult<&str, Option<(&str, &str)>> {
+    opt((
+        delimited(
+            multispace0,
+            alt((tag("="), tag("!="), tag(">="), tag("<="), tag(">"), tag("<"))),
+            multispace0,
+        ),
+        alt((
+            variable,
+            parse_uri,
+            parse_string_literal,
+            recognize((opt(char('-')), take_while1(|c: char| c.is_ascii_digit() || c == '.'))),
+        )),
+    )).parse(input)
+}
+
+// Parse IF used as a condition: IF(?vip, ?rate, 0) > 5
+fn parse_if_filter(input: &str) -> IResult<&str, FilterExpression<'_>> {
+    let (input, _) = multispace0.parse(input)?;
+    let (input, _) = tag_no_case("IF").parse(input)?;
+    let (input, _) = (multispace0, char('('), multispace0).parse(input)?;
+    let (input, (condition, then, otherwise)) = parse_if_arguments(input)?;
+    let (input, _) = (multispace0, char(')')).parse(input)?;
+    let (input, comparison) = parse_value_comparison(input)?;
+    let (input, _) = multispace0.parse(input)?;
+    Ok((input, FilterExpression::If(Box::new(condition), then, otherwise, comparison)))
+}
+
+// Parse IRI used as a condition: IRI(?page) = <http://example.org/>
+fn parse_iri_filter(input: &str) -> IResult<&str, FilterExpression<'_>> {
+    let (input, _) = multispace0.parse(input)?;
+    let (input, _) = tag_no_case("IRI").parse(input)?;
+    let (input, _) = (multispace0, char('('), multispace0).parse(input)?;
+    let (input, arg) = parse_string_expression(input)?;
+    let (input, _) = (multispace0, char(')')).parse(input)?;
+    let (input, comparison) = parse_value_comparison(input)?;
+    let (input, _) = multispace0.parse(input)?;
+    Ok((input, FilterExpression::Iri(arg, comparison)))
+}
+
+// Parse the name of a built-in numeric function such as ABS or ROUND
+fn numeric_function_name(input: &str) -> IResult<&str, NumericFunction> {
+    let (rest, name) = take_while1(|c: char| c.is_ascii_alphabetic()).parse(input)?;
+    match NumericFunction::from_name(name) {
+        Some(func) => Ok((rest, func)),
+        None => Err(nom::Err::Error(nom::error::Error::new(
+            input,
+            nom::error::ErrorKind::Tag,
+        ))),
+    }
+}
+
+// Parse the arguments of a numeric function: (?a - ?b), (?price), ()
+fn parse_numeric_arguments(input: &str) -> IResult<&str, Vec<ArithmeticExpression<'_>>> {
+    let (input, _) = multispace0.parse(input)?;
+    let (input, _) = char('(').parse(input)?;
+    let (input, _) = multispace0.parse(input)?;
+    let (input, args) = separated_list0(
+        (multispace0, char(','), multispace0),
+        parse_arithmetic_expression,
+    ).parse(input)?;
+    let (input, _) = multispace0.parse(input)?;
+    let (input, _) = char(')').parse(input)?;
+    Ok((input, args))
+}
+
+// Parse a numeric function compared against a value: ABS(?diff) < 0.01
+fn parse_numeric_function_comparison(input: &str) -> IResult<&str, FilterExpression<'_>> {
+    let (input, _) = multispace0.parse(input)?;
+    let (input, func) = numeric_function_name(input)?;
+    let (input, args) = parse_numeric_arguments(input)?;
+    let (input, _) = multispace0.parse(input)?;
+    let (input, operator) = alt((
+        tag("="), tag("!="), tag(">="),
+        tag("<="), tag(">"), tag("<"),
+    )).parse(input)?;
+    let (input, _) = multispace0.parse(input)?;
+    let (input, value) = alt((
+        variable,
+        parse_comparison_literal,
+        recognize((opt(char('-')), take_while1(|c: char| c.is_ascii_digit() || c == '.'))),
+    )).parse(input)?;
+    let (input, _) = multispace0.parse(input)?;
+    Ok((input, FilterExpression::NumericFunction(func, args, operator, value)))
+}
+
+/// Parse a computed projection such as `(ROUND(?price) AS ?rounded)` into
+/// `(function name, argument text, Some(output variable))`, the same shape as
+/// an aggregate; `select_expression` turns it into a `SelectExpression`.
+pub fn parse_select_expression(input: &str) -> IResult<&str, (&str, &str, Option<&str>)> {
+    let (input, _) = char('(').parse(input)?;
+    let (input, _) = multispace0.parse(input)?;
+    let (input, (name, args)) = alt((
+        (
+            recognize(numeric_function_name),
+            delimited(
+                (multispace0, char('(')),
+                recognize(separated_list0(
+                    (multispace0, char(','), multispace0),
+                    parse_arithmetic_expression,
+                )),
+                (multispace0, char(')')),
+            ),
+        ),
+        (
+            tag_no_case("COALESCE"),
+            delimited(
+                (multispace0, char('('), multispace0),
+                recognize(separated_list1(
+                    (multispace0, char(','), multispace0),
+                    parse_string_expression,
+                )),
+                (multispace0, char(')')),
+            ),
+        ),
+        parse_if_or_iri_call,
+    )).parse(input)?;
+    let (input, _) = multispace1.parse(input)?;
+    let (input, _) = tag("AS").parse(input)?;
+    let (input, _) = multispace1.parse(input)?;
+    let (input, output) = variable(input)?;
+    let (input, _) = multispace0.parse(input)?;
+    let (input, _) = char(')').parse(input)?;
+    Ok((input, (name, args, Some(output))))
+}
+
+/// Build the `SelectExpression` of a SELECT item parsed by
+/// `parse_select_expression`; `None` for plain variables and aggregates.
+pub fn select_expression<'a>(name: &str, args: &'a str) -> Option<SelectExpression<'a>> {
+    if name.eq_ignore_ascii_case("COALESCE") {
+        let (rest, args) = separated_list1(
+            (multispace0, char(','), multispace0),
+            parse_string_expression,
+        ).parse(args).ok()?;
+        return rest.trim().is_empty().then_some(SelectExpression::Coalesce(args));
+    }
+    if name.eq_ignore_ascii_case("IF") {
+        let (rest, (condition, then, otherwise)) = parse_if_arguments(args).ok()?;
+        return rest.trim().is_empty().then_some(SelectExpression::If(condition, then, otherwise));
+    }
+    if name.eq_ignore_ascii_case("IRI") {
+        let (rest, arg) = parse_string_expression(args).ok()?;
+        return rest.trim().is_empty().then_some(SelectExpression::Iri(arg));
+    }
+    let func = NumericFunction::from_name(name)?;
+    let (rest, args) = separated_list0(
+        (multispace0, char(','), multispace0),
+        parse_arithmetic_expression,
+    ).parse(args).ok()?;
+    if !rest.trim().is_empty() {
+        return None;
+    }
+    Some(SelectExpression::NumericFunction(func, args))
+}
+
 fn parse_standalone_arith(input: &str) -> IResult<&str, FilterExpression<'_>> {
     let (input, _) = multispace0.parse(input)?;
     let (input, expr) = parse_arithmetic_expression(input)?;
@@ -554,6 +1053,13 @@ fn parse_standalone_arith(input: &str) -> IResult<&str, FilterExpression<'_>> {
 // Parse a basic term (comparison, parenthesized expression, or negation)
 fn parse_term(input: &str) -> IResult<&str, FilterExpression<'_>> {
     alt((
+        parse_coalesce_filter,
+        parse_if_filter,
+        parse_iri_filter,
+        parse_string_function_comparison,
+        parse_string_function_filter,
+        parse_numeric_function_comparison,
+        parse_function_comparison,
         parse_function_call,
         parse_comparison,
         parse_arithmetic_comparison,
@@ -612,16 +1118,23 @@ pub fn parse_bind(input: &str) -> IResult<&str, (&str, Vec<&str>, &str)> {
     let (input, _) = tag("BIND").parse(input)?;
     let (input, _) = multispace0.parse(input)?;
     let (input, _) = char('(').parse(input)?;
-    let (input, func_name) = identifier(input)?;
-    let (input, _) = char('(').parse(input)?;
-
-    // Allow multiple arguments for CONCAT
-    let (input, args) = separated_list1(
-        (multispace0, char(','), multispace0),
-        alt((variable, parse_literal)),
-    ).parse(input)?;
-
-    let (input, _) = char(')').parse(input)?;
+    let (input, (func_name, args)) = alt((
+        // IF and IRI keep their argument text whole; `select_expression`
+        // parses it where the bind is evaluated
+        |i| parse_if_or_iri_call(i).map(|(i, (name, args))| (i, (name, vec![args]))),
+        (
+            identifier,
+            // Allow multiple arguments for CONCAT
+            delimited(
+                char('('),
+                separated_list1(
+                    (multispace0, char(','), multispace0),
+                    alt((variable, parse_literal)),
+                ),
+                char(')'),
+            ),
+        ),
+    )).parse(input)?;
     let (input, _) = multispace1.parse(input)?;
     let (input, _) = tag("AS").parse(input)?;
     let (input, _) = multispace1.parse(input)?;
@@ -637,11 +1150,12 @@ pub fn parse_subquery<'a>(input: &'a str) -> IResult<&'a str, SubQuery<'a>> {
     let (input, _) = multispace0.parse(input)?;
 
     // Parse SELECT clause
-    let (input, variables) = parse_select(input)?;
+    let (input, (distinct, variables)) = parse_select_clause(input)?;
 
     // Parse WHERE clause (recursive)
     let (input, (patterns, filters, values_clause, binds, _, _, _)) = parse_where(input)?;
 
+    let (input, group_vars) = opt(preceded(multispace0, parse_group_by)).parse(input)?;
     let (input, limit) = opt(preceded(multispace0, parse_limit)).parse(input)?;
 
     let (input, _) = multispace0.parse(input)?;
@@ -655,7 +1169,9 @@ pub fn parse_subquery<'a>(input: &'a str) -> IResult<&'a str, SubQuery<'a>> {
             filters,
             binds,
             _values_clause: values_clause,
+            group_vars: group_vars.unwrap_or_default(),
             limit,
+            distinct,
         },
     ))
 }
@@ -694,12 +1210,27 @@ pub fn parse_window_block(input: &str) -> IResult<&str, WindowBlock<'_>> {
     }))
 }
 
-/// Parse `NOT triple_block` — negation-as-failure body atom.
+/// Parse `NOT triple_block` or `NOT { triple_block . ... }` — negation-as-failure body atoms.
 /// Returns the list of negated triple patterns.
 fn parse_not_triple_block(input: &str) -> IResult<&str, Vec<(&str, &str, &str)>> {
     let (input, _) = multispace0.parse(input)?;
     let (input, _) = tag("NOT").parse(input)?;
-    let (input, _) = multispace1.parse(input)?;
+    let (input, space) = multispace0.parse(input)?;
+    if let Ok((input, _)) = char::<_, nom::error::Error<&str>>('{').parse(input) {
+        let (input, _) = multispace0.parse(input)?;
+        let (input, blocks) = separated_list1(
+            (multispace0, char('.'), multispace0),
+            parse_triple_block,
+        ).parse(input)?;
+        let (input, _) = (multispace0, opt(char('.')), multispace0, char('}')).parse(input)?;
+        return Ok((input, blocks.into_iter().flatten().collect()));
+    }
+    if space.is_empty() {
+        return Err(nom::Err::Error(nom::error::Error::new(
+            input,
+            nom::error::ErrorKind::MultiSpace,
+        )));
+    }
     parse_triple_block(input)
 }
 
@@ -1003,6 +1534,77 @@ pub fn parse_delete(input: &str) -> IResult<&str, DeleteClause<'_>> {
     Ok((input, DeleteClause { triples }))
 }
 
+// Triples separated by dots, as inside a data block or update template
+fn parse_triple_list(input: &str) -> IResult<&str, Vec<(&str, &str, &str)>> {
+    let (input, _) = multispace0.parse(input)?;
+    let (input, triple_blocks) =
+        separated_list0((multispace0, char('.'), multispace0), parse_triple_block).parse(input)?;
+    let (input, _) = multispace0.parse(input)?;
+    let (input, _) = opt((char('.'), multispace0)).parse(input)?;
+    Ok((input, triple_blocks.into_iter().flatten().collect()))
+}
+
+fn parse_triple_template(input: &str) -> IResult<&str, Vec<(&str, &str, &str)>> {
+    delimited(char('{'), parse_triple_list, char('}')).parse(input)
+}
+
+// Parse a SPARQL Update request. `INSERT { }` and `DELETE { }` without a
+// WHERE clause are read as ground data, as `handle_update` always accepted.
+pub fn parse_update(input: &str) -> IResult<&str, UpdateOperation<'_>> {
+    let mut input = input;
+    let mut prefixes = HashMap::new();
+    while let Ok((rest, (prefix, uri))) = parse_prefix(input) {
+        prefixes.insert(prefix.to_string(), uri.to_string());
+        input = rest;
+    }
+    let (input, _) = multispace0.parse(input)?;
+
+    let mut operation = UpdateOperation {
+        prefixes,
+        delete_triples: Vec::new(),
+        insert_triples: Vec::new(),
+        where_clause: None,
+    };
+
+    let mut insert_data = preceded((tag("INSERT"), multispace1, tag("DATA"), multispace0), parse_triple_template);
+    let mut delete_data = preceded((tag("DELETE"), multispace1, tag("DATA"), multispace0), parse_triple_template);
+    let mut delete_where = preceded((tag("DELETE"), multispace1, tag("WHERE"), multispace0, char('{')), parse_balanced);
+
+    let input = if let Ok((rest, triples)) = insert_data.parse(input) {
+        operation.insert_triples = triples;
+        rest
+    } else if let Ok((rest, triples)) = delete_data.parse(input) {
+        operation.delete_triples = triples;
+        rest
+    } else if let Ok((rest, body)) = delete_where.parse(input) {
+        // The pattern doubles as the template, so it may only hold triples
+        let (unparsed, triples) = parse_triple_list(body)?;
+        if !unparsed.trim().is_empty() {
+            return Err(nom::Err::Error(nom::error::Error::new(unparsed, nom::error::ErrorKind::Tag)));
+        }
+        operation.delete_triples = triples;
+        operation.where_clause = Some(body);
+        rest
+    } else {
+        let (input, delete) = opt(preceded((tag("DELETE"), multispace0), parse_triple_template)).parse(input)?;
+        let (input, _) = multispace0.parse(input)?;
+        let (input, insert) = opt(preceded((tag("INSERT"), multispace0), parse_triple_template)).parse(input)?;
+        if delete.is_none() && insert.is_none() {
+            return Err(nom::Err::Error(nom::error::Error::new(input, nom::error::ErrorKind::Tag)));
+        }
+        let (input, where_clause) =
+            opt(preceded((multispace0, tag("WHERE"), multispace0, char('{')), parse_balanced)).parse(input)?;
+        operation.delete_triples = delete.unwrap_or_default();
+        operation.insert_triples = insert.unwrap_or_default();
+        operation.where_clause = where_clause;
+        input
+    };
+
+    let (input, _) = multispace0.parse(input)?;
+    let (input, _) = opt((char(';'), multispace0)).parse(input)?;
+    Ok((input, operation))
+}
+
 pub fn parse_construct_clause(input: &str) -> IResult<&str, Vec<(&str, &str, &str)>> {
     let (input, _) = multispace0.parse(input)?;
     let (input, _) = tag("CONSTRUCT").parse(input)?;
@@ -2191,6 +2793,9 @@ pub fn parse_combined_query(input: &str) -> IResult<&str, CombinedQuery<'_>> {
     let (input, delete_clause) = opt(parse_delete).parse(input)?;
     let (input, _) = multispace0.parse(input)?;
 
+    // SELECT DISTINCT is only recorded here; the clause itself is parsed below
+    let distinct = parse_select_clause(input).is_ok_and(|(_, (distinct, _))| distinct);
+
     // Parse the SPARQL query part
     let (input, sparql_parse) = if input.trim().is_empty() && delete_clause.is_none() {
         // No remaining input - create empty SPARQL parse result
@@ -2215,6 +2820,7 @@ pub fn parse_combined_query(input: &str) -> IResult<&str, CombinedQuery<'_>> {
             rule: rule_opt,
             ml_predict,
             sparql: sparql_parse,
+            distinct,
             delete_clause,
         },
     ))
@@ -2348,7 +2954,7 @@ pub fn convert_combined_rule<'a>(
                 },
                 _ => {
                     // Return an empty vector instead of panicking
-                    println!("Warning: Unsupported filter expression type - skipping");
+                    warn!("Unsupported filter expression type - skipping");
                     vec![]
                 }
             }
@@ -2363,49 +2969,49 @@ pub fn convert_combined_rule<'a>(
 
     // Handle windowing information if present
     if !cr.window_clause.is_empty() {
-        println!("Processing rule with {} windows:", cr.window_clause.len());
+        debug!("Processing rule with {} windows:", cr.window_clause.len());
         for (idx, window_clause) in cr.window_clause.iter().enumerate() {
-            println!("  Window {}: IRI: {}", idx + 1, window_clause.window_iri);
-            println!("    Stream IRI: {}", window_clause.stream_iri);
-            println!("    Window Type: {:?}", window_clause.window_spec.window_type);
-            println!("    Width: {}", window_clause.window_spec.width);
+            debug!("  Window {}: IRI: {}", idx + 1, window_clause.window_iri);
+            debug!("    Stream IRI: {}", window_clause.stream_iri);
+            debug!("    Window Type: {:?}", window_clause.window_spec.window_type);
+            debug!("    Width: {}", window_clause.window_spec.width);
             if let Some(slide) = window_clause.window_spec.slide {
-                println!("    Slide: {}", slide);
+                debug!("    Slide: {}", slide);
             }
             if let Some(report) = window_clause.window_spec.report_strategy {
-                println!("    Report Strategy: {}", report);
+                debug!("    Report Strategy: {}", report);
             }
             if let Some(tick) = window_clause.window_spec.tick {
-                println!("    Tick: {}", tick);
+                debug!("    Tick: {}", tick);
             }
         }
     }
 
     // Handle stream type if present
     if let Some(stream_type) = &cr.stream_type {
-        println!("Stream Type: {:?}", stream_type);
+        debug!("Stream Type: {:?}", stream_type);
     }
 
     // Handle ML.PREDICT: wire output variable into conclusion triples
     if let Some(ml_predict) = &cr.ml_predict {
-        println!("Processing rule with ML.PREDICT");
+        debug!("Processing rule with ML.PREDICT");
 
         let ml_output_var = ml_predict.output.trim_start_matches('?');
-        println!("ML output variable: ?{}", ml_output_var);
+        debug!("ML output variable: ?{}", ml_output_var);
 
         // Check if the conclusion triples contain the ML output variable
         for (i, conclusion) in conclusion_triples.iter_mut().enumerate() {
-            println!("Checking conclusion pattern {}: {:?}", i, conclusion);
+            debug!("Checking conclusion pattern {}: {:?}", i, conclusion);
 
             // Check if the conclusion contains variables that need ML output
             match &mut conclusion.2 {
                 Term::Variable(var) if var == ml_output_var => {
-                    println!("Found ML output variable ?{} in conclusion object position", ml_output_var);
+                    debug!("Found ML output variable ?{} in conclusion object position", ml_output_var);
                 },
                 Term::Variable(var) if var == "level" => {
                     // Replace generic 'level' variable with ML output variable
                     *var = ml_output_var.to_string();
-                    println!("Replaced ?level with ML output variable ?{}", ml_output_var);
+                    debug!("Replaced ?level with ML output variable ?{}", ml_output_var);
                 },
                 _ => {}
             }
@@ -2413,14 +3019,14 @@ pub fn convert_combined_rule<'a>(
             // Also check subject and predicate positions
             match &mut conclusion.0 {
                 Term::Variable(var) if var == ml_output_var => {
-                    println!("Found ML output variable ?{} in conclusion subject position", ml_output_var);
+                    debug!("Found ML output variable ?{} in conclusion subject position", ml_output_var);
                 },
                 _ => {}
             }
 
             match &mut conclusion.1 {
                 Term::Variable(var) if var == ml_output_var => {
-                    println!("Found ML output variable ?{} in conclusion predicate position", ml_output_var);
+                    debug!("Found ML output variable ?{} in conclusion predicate position", ml_output_var);
                 },
                 _ => {}
             }
@@ -2436,10 +3042,24 @@ pub fn convert_combined_rule<'a>(
 }
 
 
+/// `try_process_rule_definition` with the error as a message
 pub fn process_rule_definition(
     rule_input: &str,
     database: &mut SparqlDatabase,
 ) -> Result<(Rule, Vec<Triple>), String> {
+    try_process_rule_definition(rule_input, database).map_err(|err| match err {
+        SparqlError::ParseError(message) | SparqlError::ExecutionError(message) => message,
+        err => err.to_string(),
+    })
+}
+
+/// Parse a rule, run it over the database and add the facts it infers.
+/// Fails with `SparqlError::InferenceBudgetExceeded`, adding none of the
+/// inferred facts, when inference runs past `database.inference_budget`.
+pub fn try_process_rule_definition(
+    rule_input: &str,
+    database: &mut SparqlDatabase,
+) -> Result<(Rule, Vec<Triple>), SparqlError> {
     // First, register any prefixes from the rule with the database
     database.register_prefixes_from_query(rule_input);
 
@@ -2472,14 +3092,16 @@ pub fn process_rule_definition(
             })
             .collect();
         for train_decl in &normalized_trains {
-            execute_train_decl(database, train_decl).map_err(|err| err.to_string())?;
+            execute_train_decl(database, train_decl)
+                .map_err(|err| SparqlError::ExecutionError(err.to_string()))?;
         }
 
         let mut rule = combined
             .rule
-            .ok_or_else(|| "Failed to parse rule definition".to_string())?;
+            .ok_or_else(|| SparqlError::ParseError("Failed to parse rule definition".to_string()))?;
 
-        materialize_neural_relations_for_patterns(database, &rule.body.0, &rule_prefixes)?;
+        materialize_neural_relations_for_patterns(database, &rule.body.0, &rule_prefixes)
+            .map_err(SparqlError::ExecutionError)?;
 
         // Execute ML.PREDICT (if present) before converting the rule: Candle-first
         // dispatch for registered NEURAL RELATION predicates, Python fallback otherwise.
@@ -2493,11 +3115,12 @@ pub fn process_rule_definition(
                 database,
                 &rule_prefixes,
             )
-            .map_err(|err| err.to_string())?;
+            .map_err(|err| SparqlError::ExecutionError(err.to_string()))?;
         }
 
         let mut kg = Reasoner::new();
         kg.dictionary = database.dictionary.clone();
+        kg.inference_budget = database.inference_budget;
         for triple in database.triples.iter() {
             kg.index_manager.insert(triple);
         }
@@ -2510,7 +3133,7 @@ pub fn process_rule_definition(
 
         // Check if this rule has windowing - if so, set up RSP processing
         if !rule.window_clause.is_empty() {
-            println!("Setting up RSP window processing for rule with {} windows", rule.window_clause.len());
+            debug!("Setting up RSP window processing for rule with {} windows", rule.window_clause.len());
 
             let mut all_stream_results: Vec<Triple> = Vec::new();
             let mut rsp_windows: Vec<CSPARQLWindow<WindowTriple>> = Vec::new();
@@ -2525,7 +3148,8 @@ pub fn process_rule_definition(
 
             // Create a window for each window clause
             for window_clause in &rule.window_clause {
-                let mut rsp_window = create_rsp_window(&window_clause.window_spec)?;
+                let mut rsp_window = create_rsp_window(&window_clause.window_spec)
+                    .map_err(SparqlError::ExecutionError)?;
 
                 // Process existing triples through the window
                 let mut current_time = 1;
@@ -2549,7 +3173,7 @@ pub fn process_rule_definition(
                 let _stream_op_clone = stream_operator.clone();
 
                 rsp_window.register_callback(Box::new(move |content: ContentContainer<WindowTriple>| {
-                    println!("Processing window content with {} triples", content.len());
+                    debug!("Processing window content with {} triples", content.len());
 
                     // Convert window content back to Knowledge Graph format
                     let mut window_kg = kg_clone.clone();
@@ -2561,26 +3185,28 @@ pub fn process_rule_definition(
                     window_kg.add_rule(rule_clone.clone());
                     let window_inferred = window_kg.infer_new_facts_semi_naive();
 
-                    println!("Window processing inferred {} facts", window_inferred.len());
+                    debug!("Window processing inferred {} facts", window_inferred.len());
                 }));
 
                 rsp_windows.push(rsp_window);
             }
 
             // Add the rule to the main knowledge graph
-            kg.add_rule(dynamic_rule.clone());
+            kg.try_add_rule(dynamic_rule.clone()).map_err(SparqlError::ExecutionError)?;
 
             // For immediate processing, also infer from current data
-            let inferred_facts = kg.infer_new_facts_semi_naive();
+            let inferred_facts = kg
+                .try_infer_new_facts_semi_naive()
+                .map_err(SparqlError::InferenceBudgetExceeded)?;
 
             // Apply stream operator to results
-            let eval_time = database.triples.len().saturating_add(1);
+            let eval_time = database.count_triples().saturating_add(1);
 
             for _window_clause in &rsp_windows {
                 let mut r2s_operator = Relation2StreamOperator::new(stream_operator.clone(), 0);
                 let stream_results = r2s_operator.eval(inferred_facts.clone(), eval_time);
 
-                println!("Stream operator ({:?}) produced {} results", stream_operator.clone(), stream_results.len());
+                debug!("Stream operator ({:?}) produced {} results", stream_operator.clone(), stream_results.len());
 
                 // Add inferred facts to the database
                 for triple in stream_results.iter() {
@@ -2600,7 +3226,7 @@ pub fn process_rule_definition(
         if rule.prob_annotation.is_some() {
             let ann = rule.prob_annotation.as_ref().unwrap();
 
-            kg.add_rule(dynamic_rule.clone());
+            kg.try_add_rule(dynamic_rule.clone()).map_err(SparqlError::ExecutionError)?;
             register_rule_predicates(&dynamic_rule, database);
 
             // Choose provenance based on annotation, then materialize tags as RDF-star
@@ -2710,23 +3336,33 @@ pub fn process_rule_definition(
 
             Ok((dynamic_rule, inferred_facts))
         } else {
-            kg.add_rule(dynamic_rule.clone());
+            kg.try_add_rule(dynamic_rule.clone()).map_err(SparqlError::ExecutionError)?;
 
             // Register rule predicates
             register_rule_predicates(&dynamic_rule, database);
 
+            if database.derivation_log.is_some() {
+                kg.enable_derivation_tracking();
+            }
+
             // Infer new facts based on the rule
-            let inferred_facts = kg.infer_new_facts_semi_naive();
+            let inferred_facts = kg
+                .try_infer_new_facts_semi_naive()
+                .map_err(SparqlError::InferenceBudgetExceeded)?;
 
             // Add inferred facts to the database
             for triple in inferred_facts.iter() {
                 database.triples.insert(triple.clone());
             }
 
+            if let (Some(log), Some(rule_log)) = (database.derivation_log.as_mut(), kg.derivation_log.take()) {
+                log.merge(rule_log);
+            }
+
             Ok((dynamic_rule, inferred_facts))
         }
     } else {
-        Err("Failed to parse rule definition".to_string())
+        Err(SparqlError::ParseError("Failed to parse rule definition".to_string()))
     }
 }
 
@@ -2735,18 +3371,18 @@ pub fn process_retrieve_clause(
     retrieve_clause: &RetrieveClause,
     database: &mut SparqlDatabase,
 ) -> Result<Vec<Triple>, String> {
-    println!("Processing RETRIEVE clause:");
-    println!("  Mode: {:?}", retrieve_clause.mode);
-    println!("  State: {:?}", retrieve_clause.state);
-    println!("  Variable: {}", retrieve_clause.variable);
-    println!("  From IRI: {}", retrieve_clause.from_iri);
-    println!("  Graph patterns: {} triples", retrieve_clause.graph_pattern.len());
+    debug!("Processing RETRIEVE clause:");
+    debug!("  Mode: {:?}", retrieve_clause.mode);
+    debug!("  State: {:?}", retrieve_clause.state);
+    debug!("  Variable: {}", retrieve_clause.variable);
+    debug!("  From IRI: {}", retrieve_clause.from_iri);
+    debug!("  Graph patterns: {} triples", retrieve_clause.graph_pattern.len());
     
     // Convert graph patterns to triple patterns for matching
     let mut retrieved_triples = Vec::new();
     
     for pattern in &retrieve_clause.graph_pattern {
-        println!("  Pattern: {} {} {}", pattern.0, pattern.1, pattern.2);
+        debug!("  Pattern: {} {} {}", pattern.0, pattern.1, pattern.2);
         
         // Create a temporary knowledge graph to match patterns
         let mut kg = Reasoner::new();
@@ -2775,7 +3411,7 @@ pub fn process_retrieve_clause(
         }
     }
     
-    println!("Retrieved {} matching triples", retrieved_triples.len());
+    debug!("Retrieved {} matching triples", retrieved_triples.len());
     Ok(retrieved_triples)
 }
 
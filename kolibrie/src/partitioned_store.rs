@@ -0,0 +1,183 @@
+/*
+ * Copyright © 2026 Volodymyr Kadzhaia
+ * Copyright © 2026 Pieter Bonte
+ * KU Leuven — Stream Intelligence Lab, Belgium
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this file,
+ * you can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use rayon::prelude::*;
+use shared::triple::Triple;
+use std::collections::BTreeSet;
+use std::sync::RwLock;
+
+/// Triple store split into shards by subject ID, each behind its own lock.
+///
+/// Writers touching different subjects do not contend, and pattern scans
+/// run over all shards in parallel. A scan with a bound subject only visits
+/// the one shard that can hold it.
+#[derive(Debug)]
+pub struct PartitionedTripleStore {
+    shards: Vec<RwLock<BTreeSet<Triple>>>,
+}
+
+impl PartitionedTripleStore {
+    pub fn new(num_shards: usize) -> Self {
+        let num_shards = num_shards.max(1);
+        PartitionedTripleStore {
+            shards: (0..num_shards).map(|_| RwLock::new(BTreeSet::new())).collect(),
+        }
+    }
+
+    /// One shard per Rayon worker thread
+    pub fn with_default_shards() -> Self {
+        Self::new(rayon::current_num_threads())
+    }
+
+    pub fn from_triples<'a, I>(triples: I, num_shards: usize) -> Self
+    where
+        I: IntoIterator<Item = &'a Triple>,
+    {
+        let mut store = Self::new(num_shards);
+        for triple in triples {
+            let shard = store.shard_for(triple.subject);
+            store.shards[shard].get_mut().unwrap().insert(triple.clone());
+        }
+        store
+    }
+
+    pub fn num_shards(&self) -> usize {
+        self.shards.len()
+    }
+
+    /// Shard holding every triple with this subject
+    pub fn shard_for(&self, subject: u32) -> usize {
+        // Fibonacci hashing spreads sequential dictionary IDs across shards
+        let hash = (subject as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15);
+        ((hash >> 32) as usize) % self.shards.len()
+    }
+
+    pub fn add_triple(&self, triple: Triple) -> bool {
+        let shard = self.shard_for(triple.subject);
+        self.shards[shard].write().unwrap().insert(triple)
+    }
+
+    pub fn remove_triple(&self, triple: &Triple) -> bool {
+        let shard = self.shard_for(triple.subject);
+        self.shards[shard].write().unwrap().remove(triple)
+    }
+
+    pub fn contains(&self, triple: &Triple) -> bool {
+        let shard = self.shard_for(triple.subject);
+        self.shards[shard].read().unwrap().contains(triple)
+    }
+
+    pub fn len(&self) -> usize {
+        self.shards.iter().map(|shard| shard.read().unwrap().len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.shards.iter().all(|shard| shard.read().unwrap().is_empty())
+    }
+
+    pub fn clear(&self) {
+        for shard in &self.shards {
+            shard.write().unwrap().clear();
+        }
+    }
+
+    /// Collect the triples matching a pattern; `None` is a wildcard.
+    /// Shards are scanned in parallel and their matches concatenated.
+    pub fn scan(&self, subject: Option<u32>, predicate: Option<u32>, object: Option<u32>) -> Vec<Triple> {
+        let matches = |triple: &Triple| {
+            subject.is_none_or(|s| triple.subject == s)
+                && predicate.is_none_or(|p| triple.predicate == p)
+                && object.is_none_or(|o| triple.object == o)
+        };
+
+        if let Some(s) = subject {
+            let shard = self.shards[self.shard_for(s)].read().unwrap();
+            let start = Triple { subject: s, predicate: 0, object: 0 };
+            return shard
+                .range(start..)
+                .take_while(|triple| triple.subject == s)
+                .filter(|triple| matches(triple))
+                .cloned()
+                .collect();
+        }
+
+        self.shards
+            .par_iter()
+            .map(|shard| {
+                shard
+                    .read()
+                    .unwrap()
+                    .iter()
+                    .filter(|triple| matches(triple))
+                    .cloned()
+                    .collect::<Vec<Triple>>()
+            })
+            .flatten()
+            .collect()
+    }
+
+    /// All triples, in shard order
+    pub fn to_vec(&self) -> Vec<Triple> {
+        self.scan(None, None, None)
+    }
+}
+
+impl Clone for PartitionedTripleStore {
+    fn clone(&self) -> Self {
+        PartitionedTripleStore {
+            shards: self
+                .shards
+                .iter()
+                .map(|shard| RwLock::new(shard.read().unwrap().clone()))
+                .collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn triple(subject: u32, predicate: u32, object: u32) -> Triple {
+        Triple { subject, predicate, object }
+    }
+
+    #[test]
+    fn routes_subjects_to_a_single_shard() {
+        let store = PartitionedTripleStore::new(4);
+        for s in 0..100 {
+            store.add_triple(triple(s, 1, s + 1));
+            store.add_triple(triple(s, 2, s + 2));
+        }
+        assert_eq!(store.len(), 200);
+        assert!(!store.add_triple(triple(5, 1, 6)));
+
+        let shard = store.shard_for(7);
+        let count = store.shards[shard].read().unwrap().iter().filter(|t| t.subject == 7).count();
+        assert_eq!(count, 2);
+    }
+
+    #[test]
+    fn scan_matches_bound_positions() {
+        let store = PartitionedTripleStore::new(3);
+        for s in 0..50 {
+            store.add_triple(triple(s, s % 2, 1000));
+        }
+
+        assert_eq!(store.scan(None, Some(0), None).len(), 25);
+        assert_eq!(store.scan(Some(4), None, None), vec![triple(4, 0, 1000)]);
+        assert!(store.scan(Some(4), Some(1), None).is_empty());
+        assert_eq!(store.scan(None, None, Some(1000)).len(), 50);
+
+        assert!(store.remove_triple(&triple(4, 0, 1000)));
+        assert!(!store.contains(&triple(4, 0, 1000)));
+        assert_eq!(store.to_vec().len(), 49);
+    }
+}
@@ -0,0 +1,157 @@
+/*
+ * Copyright © 2026 Volodymyr Kadzhaia
+ * Copyright © 2026 Pieter Bonte
+ * KU Leuven — Stream Intelligence Lab, Belgium
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this file,
+ * you can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use lru::LruCache;
+use shared::triple::Triple;
+use std::num::NonZeroUsize;
+use std::sync::Mutex;
+
+/// Bound subject, predicate and object of a scanned pattern; `None` is a variable
+pub type PatternKey = (Option<u32>, Option<u32>, Option<u32>);
+
+/// Entries kept by `SparqlDatabase::enable_pattern_cache` when no capacity is given
+pub const DEFAULT_PATTERN_CACHE_CAPACITY: usize = 1024;
+
+/// Index matches of the most recently scanned triple patterns, evicted in
+/// least-recently-used order.
+///
+/// Enabled with `SparqlDatabase::enable_pattern_cache` and consulted by the
+/// execution engine's index scans. `add_triple` and `delete_triple` drop the
+/// entries whose pattern matches the modified triple; index rebuilds and
+/// writes that change the store size behind the database's back drop all
+/// entries.
+#[derive(Debug)]
+pub struct PatternCache {
+    // Scans only borrow the database, so lookups need interior mutability
+    state: Mutex<CacheState>,
+}
+
+#[derive(Debug, Clone)]
+struct CacheState {
+    entries: LruCache<PatternKey, Vec<Triple>>,
+    /// Store size the cached matches were computed against
+    triple_count: usize,
+    hits: u64,
+    misses: u64,
+}
+
+impl CacheState {
+    fn sync_triple_count(&mut self, triple_count: usize) {
+        if self.triple_count != triple_count {
+            self.entries.clear();
+            self.triple_count = triple_count;
+        }
+    }
+}
+
+impl PatternCache {
+    /// A cache holding at most `capacity` patterns (at least one)
+    pub fn new(capacity: usize) -> Self {
+        let capacity = NonZeroUsize::new(capacity).unwrap_or(NonZeroUsize::MIN);
+        PatternCache {
+            state: Mutex::new(CacheState {
+                entries: LruCache::new(capacity),
+                triple_count: 0,
+                hits: 0,
+                misses: 0,
+            }),
+        }
+    }
+
+    /// The triples matching `key` in a store of `triple_count` triples,
+    /// computed with `scan` and remembered when not cached yet
+    pub fn get_or_scan<F>(&self, key: PatternKey, triple_count: usize, scan: F) -> Vec<Triple>
+    where
+        F: FnOnce() -> Vec<Triple>,
+    {
+        {
+            let mut state = self.state.lock().unwrap();
+            state.sync_triple_count(triple_count);
+            if let Some(triples) = state.entries.get(&key) {
+                let triples = triples.clone();
+                state.hits += 1;
+                return triples;
+            }
+            state.misses += 1;
+        }
+
+        // Scan without holding the lock so parallel scans are not serialised
+        let triples = scan();
+        let mut state = self.state.lock().unwrap();
+        if state.triple_count == triple_count {
+            state.entries.put(key, triples.clone());
+        }
+        triples
+    }
+
+    /// Drop the entries whose pattern matches `triple`, which was just added
+    /// to or removed from a store that now holds `triple_count` triples.
+    /// Returns how many entries were invalidated.
+    pub fn invalidate_triple(&mut self, triple: &Triple, triple_count: usize) -> usize {
+        let state = self.state.get_mut().unwrap();
+        if state.triple_count.abs_diff(triple_count) != 1 {
+            let invalidated = state.entries.len();
+            state.entries.clear();
+            state.triple_count = triple_count;
+            return invalidated;
+        }
+        state.triple_count = triple_count;
+
+        let affected: Vec<PatternKey> = state
+            .entries
+            .iter()
+            .map(|(key, _)| *key)
+            .filter(|key| key_matches(key, triple))
+            .collect();
+        for key in &affected {
+            state.entries.pop(key);
+        }
+        affected.len()
+    }
+
+    pub fn contains(&self, key: &PatternKey) -> bool {
+        self.state.lock().unwrap().entries.contains(key)
+    }
+
+    pub fn len(&self) -> usize {
+        self.state.lock().unwrap().entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn hits(&self) -> u64 {
+        self.state.lock().unwrap().hits
+    }
+
+    pub fn misses(&self) -> u64 {
+        self.state.lock().unwrap().misses
+    }
+
+    pub fn clear(&mut self) {
+        self.state.get_mut().unwrap().entries.clear();
+    }
+}
+
+impl Clone for PatternCache {
+    fn clone(&self) -> Self {
+        PatternCache {
+            state: Mutex::new(self.state.lock().unwrap().clone()),
+        }
+    }
+}
+
+fn key_matches(key: &PatternKey, triple: &Triple) -> bool {
+    let bound_matches = |bound: Option<u32>, id: u32| bound.is_none_or(|bound| bound == id);
+    bound_matches(key.0, triple.subject)
+        && bound_matches(key.1, triple.predicate)
+        && bound_matches(key.2, triple.object)
+}
@@ -0,0 +1,107 @@
+/*
+ * Copyright © 2026 Volodymyr Kadzhaia
+ * Copyright © 2026 Pieter Bonte
+ * KU Leuven — Stream Intelligence Lab, Belgium
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this file,
+ * you can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::collections::HashMap;
+
+/// Reverse of a prefix map (namespace IRI to prefix), for writing IRIs as
+/// prefixed names. A lookup probes one namespace per distinct namespace
+/// length, longest first, instead of scanning every prefix.
+#[derive(Debug, Clone, Default)]
+pub struct PrefixIndex {
+    namespaces: HashMap<String, String>,
+    /// Distinct lengths of the keys of `namespaces`, longest first
+    lengths: Vec<usize>,
+}
+
+impl PrefixIndex {
+    /// Index `prefixes` (prefix to namespace). When several prefixes share a
+    /// namespace, the shortest, then alphabetically first, is used.
+    pub fn new(prefixes: &HashMap<String, String>) -> Self {
+        let mut namespaces: HashMap<String, String> = HashMap::new();
+        for (prefix, namespace) in prefixes {
+            if namespace.is_empty() {
+                continue;
+            }
+            namespaces
+                .entry(namespace.clone())
+                .and_modify(|current| {
+                    if (prefix.len(), prefix.as_str()) < (current.len(), current.as_str()) {
+                        *current = prefix.clone();
+                    }
+                })
+                .or_insert_with(|| prefix.clone());
+        }
+        let mut lengths: Vec<usize> = namespaces.keys().map(String::len).collect();
+        lengths.sort_unstable_by(|a, b| b.cmp(a));
+        lengths.dedup();
+        PrefixIndex { namespaces, lengths }
+    }
+
+    /// `iri` as a prefixed name such as `ex:alice`. Where one namespace
+    /// extends another, the longest one that leaves a valid local name wins;
+    /// `None` if no namespace does.
+    pub fn shorten(&self, iri: &str) -> Option<String> {
+        self.lengths.iter().find_map(|&len| {
+            let prefix = self.namespaces.get(iri.get(..len)?)?;
+            let local = &iri[len..];
+            is_local_name(local).then(|| format!("{}:{}", prefix, local))
+        })
+    }
+}
+
+// Whether `local` can follow `prefix:` in Turtle and SPARQL without escapes:
+// letters, digits, `_`, `-`, `.` and `:`, neither starting with `-` or `.`
+// nor ending with `.`. The empty local name is valid.
+pub(crate) fn is_local_name(local: &str) -> bool {
+    local.chars().all(|c| c.is_alphanumeric() || matches!(c, '_' | '-' | '.' | ':'))
+        && !local.starts_with(['-', '.'])
+        && !local.ends_with('.')
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn index(prefixes: &[(&str, &str)]) -> PrefixIndex {
+        PrefixIndex::new(
+            &prefixes
+                .iter()
+                .map(|(prefix, namespace)| (prefix.to_string(), namespace.to_string()))
+                .collect(),
+        )
+    }
+
+    #[test]
+    fn test_longest_namespace_wins() {
+        let index = index(&[
+            ("ex", "http://example.org/"),
+            ("people", "http://example.org/people/"),
+        ]);
+        assert_eq!(index.shorten("http://example.org/people/alice").as_deref(), Some("people:alice"));
+        assert_eq!(index.shorten("http://example.org/bob").as_deref(), Some("ex:bob"));
+        assert_eq!(index.shorten("http://example.org/people/").as_deref(), Some("people:"));
+        assert_eq!(index.shorten("http://other.org/bob"), None);
+    }
+
+    #[test]
+    fn test_invalid_local_names_fall_back() {
+        let index = index(&[("ex", "http://example.org/"), ("a", "http://example.org/a")]);
+        // `a:.b` would start with a dot, so the shorter namespace is used
+        assert_eq!(index.shorten("http://example.org/a.b").as_deref(), Some("ex:a.b"));
+        assert_eq!(index.shorten("http://example.org/a/b"), None);
+        assert_eq!(index.shorten("http://example.org/end."), None);
+    }
+
+    #[test]
+    fn test_shared_namespace_uses_shortest_prefix() {
+        let index = index(&[("example", "http://example.org/"), ("ex", "http://example.org/"), ("", "")]);
+        assert_eq!(index.shorten("http://example.org/alice").as_deref(), Some("ex:alice"));
+    }
+}
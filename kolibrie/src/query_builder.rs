@@ -8,15 +8,22 @@
  * you can obtain one at https://mozilla.org/MPL/2.0/.
  */
 
+use crate::prefix_index::is_local_name;
 use crate::sparql_database::SparqlDatabase;
+use crate::text_index::TextPattern;
+use crate::utils::ntriples_term;
 use crate::rsp::r2s::{Relation2StreamOperator, StreamOperator};
 use crate::rsp::s2r::{ContentContainer, ReportStrategy, Tick, WindowTriple};
 use crate::rsp::window_runner::{WindowRunner, WindowSpec};
+use shared::dictionary::Dictionary;
 use shared::triple::Triple;
 use std::collections::{BTreeMap, BTreeSet, HashSet};
 use std::fmt;
 use std::sync::{Arc, Mutex};
 
+/// `(subject, predicate, object)` terms of the triples in an update request
+type UpdateTriples<'t> = [(&'t str, &'t str, &'t str)];
+
 pub struct QueryBuilder<'a> {
     db: &'a SparqlDatabase,
     subject_filter: Option<TripleFilter>,
@@ -464,9 +471,14 @@ impl<'a> QueryBuilder<'a> {
     fn apply_filters(self) -> BTreeSet<Triple> {
         let mut results = BTreeSet::new();
         let dict = self.db.dictionary.read().unwrap();
+        let indexed = self.index_lookup(&dict);
+        let candidates: Box<dyn Iterator<Item = &Triple>> = match &indexed {
+            Some(triples) => Box::new(triples.iter()),
+            None => Box::new(self.db.triples.iter()),
+        };
         
         // Apply basic filters
-        for triple in &self.db.triples {
+        for triple in candidates {
             let mut matches = true;
             
             // Check subject filter
@@ -545,6 +557,49 @@ impl<'a> QueryBuilder<'a> {
         results
     }
     
+    /// Triples matching the exact subject, predicate and object filters, read
+    /// from the database's indexes, or else those `text_lookup` finds. `None`
+    /// when neither applies or the indexes are behind `triples`; the caller
+    /// then scans every triple.
+    fn index_lookup(&self, dict: &Dictionary) -> Option<Vec<Triple>> {
+        if !self.db.indexes_current() {
+            return None;
+        }
+        let exact_id = |filter: &Option<TripleFilter>| match filter {
+            Some(TripleFilter::Exact(term)) => Some(dict.string_to_id.get(term).copied()),
+            _ => None,
+        };
+        match (
+            exact_id(&self.subject_filter),
+            exact_id(&self.predicate_filter),
+            exact_id(&self.object_filter),
+        ) {
+            (None, None, None) => self.text_lookup(dict),
+            // A term missing from the dictionary is in no triple
+            (Some(None), _, _) | (_, Some(None), _) | (_, _, Some(None)) => Some(Vec::new()),
+            (s, p, o) => Some(self.db.index_manager.query(s.flatten(), p.flatten(), o.flatten())),
+        }
+    }
+    
+    /// Triples whose subject, predicate or object may contain the text of a
+    /// `Contains` filter, found through the database's text index. `None`
+    /// without such a filter or a text index covering the dictionary.
+    fn text_lookup(&self, dict: &Dictionary) -> Option<Vec<Triple>> {
+        let text_index = self.db.current_text_index(dict)?;
+        let filters = [&self.subject_filter, &self.predicate_filter, &self.object_filter];
+        let (position, ids) = filters.iter().enumerate().find_map(|(position, filter)| match filter {
+            Some(TripleFilter::Contains(text)) => Some((position, text_index.candidates(&TextPattern::Contains(text))?)),
+            _ => None,
+        })?;
+        let mut triples = Vec::new();
+        for id in ids {
+            let mut key = [None; 3];
+            key[position] = Some(id);
+            triples.extend(self.db.index_manager.query(key[0], key[1], key[2]));
+        }
+        Some(triples)
+    }
+    
     // Helper method to apply a filter to a string value
     fn apply_filter(filter: &TripleFilter, value: &str) -> bool {
         match filter {
@@ -620,6 +675,80 @@ impl<'a> QueryBuilder<'a> {
         joined_triples
     }
 
+    /// `INSERT DATA` request adding `triples`. Terms may already be SPARQL
+    /// (`?x`, `<iri>`, `"text"`, `ex:name`) or be values as returned by
+    /// `get_decoded_triples`, so matched triples can be written back.
+    pub fn insert_data(&self, triples: &[(&str, &str, &str)]) -> String {
+        self.update_request(&[("INSERT DATA", triples)])
+    }
+
+    /// `DELETE DATA` request removing `triples`
+    pub fn delete_data(&self, triples: &[(&str, &str, &str)]) -> String {
+        self.update_request(&[("DELETE DATA", triples)])
+    }
+
+    /// `DELETE WHERE` request removing every match of `where_patterns`
+    pub fn delete_where(&self, where_patterns: &[(&str, &str, &str)]) -> String {
+        self.update_request(&[("DELETE WHERE", where_patterns)])
+    }
+
+    /// `INSERT { } WHERE { }` request adding `template` once per match of
+    /// `where_patterns`
+    pub fn insert_where(
+        &self,
+        template: &[(&str, &str, &str)],
+        where_patterns: &[(&str, &str, &str)],
+    ) -> String {
+        self.update_request(&[("INSERT", template), ("WHERE", where_patterns)])
+    }
+
+    // PREFIX declarations for the prefixed names used, then each keyword
+    // followed by its group of triples
+    fn update_request(&self, clauses: &[(&str, &UpdateTriples<'_>)]) -> String {
+        let mut used_prefixes = BTreeMap::new();
+        let mut body = String::new();
+        for (keyword, triples) in clauses {
+            body.push_str(keyword);
+            body.push_str(" {\n");
+            for (s, p, o) in triples.iter() {
+                body.push_str(&format!(
+                    "  {} {} {} .\n",
+                    self.update_term(s, &mut used_prefixes),
+                    self.update_term(p, &mut used_prefixes),
+                    self.update_term(o, &mut used_prefixes),
+                ));
+            }
+            body.push_str("}\n");
+        }
+
+        let mut request = String::new();
+        for (prefix, namespace) in used_prefixes {
+            request.push_str(&format!("PREFIX {}: <{}>\n", prefix, namespace));
+        }
+        request.push_str(&body);
+        request
+    }
+
+    // A term in SPARQL syntax. Variables and terms that already are SPARQL
+    // (`<iri>`, `"literal"`, `_:b`, `<< s p o >>`) are kept, a name whose
+    // prefix the database knows stays a prefixed name, other absolute IRIs
+    // (stored without brackets) get `<>` and anything else is a plain literal.
+    fn update_term(&self, term: &str, used_prefixes: &mut BTreeMap<&'a str, &'a str>) -> String {
+        let db: &'a SparqlDatabase = self.db;
+        if term.starts_with(['?', '$', '<', '"']) || term.starts_with("_:") {
+            return term.to_string();
+        }
+        if let Some((prefix, local)) = term.split_once(':') {
+            if let Some((prefix, namespace)) = db.prefixes.get_key_value(prefix) {
+                if !prefix.is_empty() && is_local_name(local) {
+                    used_prefixes.insert(prefix, namespace);
+                    return term.to_string();
+                }
+            }
+        }
+        ntriples_term(term)
+    }
+
     /// Configure windowing for stream processing
     pub fn window(mut self, width: usize, slide: usize) -> Self {
         let mut spec = self.window_spec.unwrap_or_default();
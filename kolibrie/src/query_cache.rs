@@ -0,0 +1,189 @@
+/*
+ * Copyright © 2026 Volodymyr Kadzhaia
+ * Copyright © 2026 Pieter Bonte
+ * KU Leuven — Stream Intelligence Lab, Belgium
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this file,
+ * you can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use shared::terms::{Term, TriplePattern};
+use shared::triple::Triple;
+use std::collections::{HashMap, HashSet};
+
+/// Records the triple patterns each cached query scans, so a write only
+/// invalidates the queries whose patterns could match the modified triple.
+#[derive(Debug, Clone, Default)]
+pub struct DependencyTracker {
+    /// Queries with a pattern whose predicate is the key
+    by_predicate: HashMap<u32, HashSet<String>>,
+    /// Queries with a pattern whose predicate is a variable
+    any_predicate: HashSet<String>,
+    patterns: HashMap<String, Vec<TriplePattern>>,
+}
+
+impl DependencyTracker {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn register(&mut self, query: &str, patterns: Vec<TriplePattern>) {
+        self.remove(query);
+        for pattern in &patterns {
+            match &pattern.1 {
+                Term::Constant(predicate) => {
+                    self.by_predicate.entry(*predicate).or_default().insert(query.to_string());
+                }
+                _ => {
+                    self.any_predicate.insert(query.to_string());
+                }
+            }
+        }
+        self.patterns.insert(query.to_string(), patterns);
+    }
+
+    pub fn remove(&mut self, query: &str) {
+        if self.patterns.remove(query).is_none() {
+            return;
+        }
+        self.any_predicate.remove(query);
+        self.by_predicate.retain(|_, queries| {
+            queries.remove(query);
+            !queries.is_empty()
+        });
+    }
+
+    /// Queries with a pattern that matches `triple`
+    pub fn affected_queries(&self, triple: &Triple) -> Vec<String> {
+        let candidates = self
+            .by_predicate
+            .get(&triple.predicate)
+            .into_iter()
+            .flatten()
+            .chain(&self.any_predicate);
+
+        let mut affected: Vec<String> = candidates
+            .filter(|query| {
+                self.patterns
+                    .get(*query)
+                    .is_some_and(|patterns| patterns.iter().any(|p| pattern_may_match(p, triple)))
+            })
+            .cloned()
+            .collect();
+        affected.sort();
+        affected.dedup();
+        affected
+    }
+
+    pub fn clear(&mut self) {
+        self.by_predicate.clear();
+        self.any_predicate.clear();
+        self.patterns.clear();
+    }
+}
+
+// Variables and quoted-triple terms match anything
+fn pattern_may_match(pattern: &TriplePattern, triple: &Triple) -> bool {
+    let term_may_match = |term: &Term, id: u32| match term {
+        Term::Constant(constant) => *constant == id,
+        Term::Variable(_) | Term::QuotedTriple(_) => true,
+    };
+    term_may_match(&pattern.0, triple.subject)
+        && term_may_match(&pattern.1, triple.predicate)
+        && term_may_match(&pattern.2, triple.object)
+}
+
+/// Results of SELECT queries, kept until a write touches one of the patterns
+/// they depend on (incremental view maintenance).
+///
+/// Enabled with `SparqlDatabase::enable_query_cache`. `add_triple` and
+/// `delete_triple` invalidate only the affected entries; bulk loads that write
+/// `SparqlDatabase::triples` directly are detected by the changed store size
+/// and drop the whole cache.
+#[derive(Debug, Clone, Default)]
+pub struct QueryCache {
+    results: HashMap<String, Vec<Vec<String>>>,
+    tracker: DependencyTracker,
+    /// Store size the cached results were computed against
+    triple_count: usize,
+    pub hits: u64,
+    pub misses: u64,
+}
+
+impl QueryCache {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Cached rows for `query` against a store of `triple_count` triples
+    pub fn get(&mut self, query: &str, triple_count: usize) -> Option<Vec<Vec<String>>> {
+        self.sync_triple_count(triple_count);
+        match self.results.get(query) {
+            Some(rows) => {
+                self.hits += 1;
+                Some(rows.clone())
+            }
+            None => {
+                self.misses += 1;
+                None
+            }
+        }
+    }
+
+    pub fn insert(
+        &mut self,
+        query: &str,
+        patterns: Vec<TriplePattern>,
+        rows: Vec<Vec<String>>,
+        triple_count: usize,
+    ) {
+        self.sync_triple_count(triple_count);
+        self.tracker.register(query, patterns);
+        self.results.insert(query.to_string(), rows);
+    }
+
+    /// Drop the entries that depend on `triple`, which was just added to or
+    /// removed from a store that now holds `triple_count` triples.
+    /// Returns how many entries were invalidated.
+    pub fn invalidate_triple(&mut self, triple: &Triple, triple_count: usize) -> usize {
+        if self.triple_count.abs_diff(triple_count) != 1 {
+            let invalidated = self.results.len();
+            self.clear();
+            self.triple_count = triple_count;
+            return invalidated;
+        }
+        self.triple_count = triple_count;
+
+        let affected = self.tracker.affected_queries(triple);
+        for query in &affected {
+            self.tracker.remove(query);
+            self.results.remove(query);
+        }
+        affected.len()
+    }
+
+    fn sync_triple_count(&mut self, triple_count: usize) {
+        if self.triple_count != triple_count {
+            self.clear();
+            self.triple_count = triple_count;
+        }
+    }
+
+    pub fn contains(&self, query: &str) -> bool {
+        self.results.contains_key(query)
+    }
+
+    pub fn len(&self) -> usize {
+        self.results.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.results.is_empty()
+    }
+
+    pub fn clear(&mut self) {
+        self.results.clear();
+        self.tracker.clear();
+    }
+}
@@ -8,7 +8,7 @@
  * you can obtain one at https://mozilla.org/MPL/2.0/.
  */
 
-use crate::execute_query::execute_query_rayon_parallel2_volcano;
+use crate::execute_query::{execute_query_profiled, execute_query_rayon_parallel2_volcano};
 use crate::sparql_database::SparqlDatabase;
 
 #[derive(Debug, Clone, Default)]
@@ -74,14 +74,26 @@ impl QueryEngine {
             StorageMode::Static
         };
 
+        let will_use_volcano = matches!(storage_mode, StorageMode::Static | StorageMode::Hybrid);
+        let plan = if will_use_volcano { self.physical_plan(sparql) } else { None };
+
         QueryExplanation {
-            will_use_volcano: matches!(storage_mode, StorageMode::Static | StorageMode::Hybrid),
+            will_use_volcano,
             storage_mode,
             has_windowing,
             window_clauses: extract_window_clauses(sparql),
+            plan,
         }
     }
 
+    /// The physical plan the optimizer picks for `sparql`, found by running
+    /// it profiled on a copy of the data so the engine's state is untouched
+    fn physical_plan(&self, sparql: &str) -> Option<String> {
+        let mut database = self.database.clone();
+        let (_, profiler) = execute_query_profiled(sparql, &mut database).ok()?;
+        profiler.plans().pop()
+    }
+
     pub fn stats(&self) -> QueryEngineStats {
         QueryEngineStats {
             memory_triple_count: self.database.index_manager.query(None, None, None).len(),
@@ -108,6 +120,8 @@ pub struct QueryExplanation {
     pub will_use_volcano: bool,
     pub has_windowing: bool,
     pub window_clauses: Vec<String>,
+    /// Optimized physical plan as an indented operator tree, for static queries
+    pub plan: Option<String>,
 }
 
 #[derive(Debug, Clone)]
@@ -205,5 +219,6 @@ mod tests {
         assert_eq!(explanation.storage_mode, StorageMode::Static);
         assert!(explanation.will_use_volcano);
         assert!(!explanation.has_windowing);
+        assert_eq!(explanation.plan.as_deref(), Some("Projection(?s, ?p, ?o)\n  TableScan(?s, ?p, ?o)"));
     }
 }
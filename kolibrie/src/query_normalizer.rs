@@ -0,0 +1,407 @@
+/*
+ * Copyright © 2026 Volodymyr Kadzhaia
+ * Copyright © 2026 Pieter Bonte
+ * KU Leuven — Stream Intelligence Lab, Belgium
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this file,
+ * you can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::collections::HashMap;
+
+const RDF_TYPE: &str = "<http://www.w3.org/1999/02/22-rdf-syntax-ns#type>";
+
+/// Words written in upper case in the normalised form; other words (UDF
+/// names, `true`, ...) keep their case because it may matter
+const KEYWORDS: &[&str] = &[
+    "ASK", "AS", "ASC", "BASE", "BIND", "BY", "CONSTRUCT", "DATA", "DELETE", "DESC", "DESCRIBE",
+    "DISTINCT", "EXISTS", "FILTER", "FROM", "GRAPH", "GROUP", "HAVING", "IN", "INSERT", "LIMIT",
+    "MINUS", "NAMED", "NOT", "OFFSET", "OPTIONAL", "ORDER", "PREFIX", "REDUCED", "SELECT",
+    "SERVICE", "SILENT", "UNDEF", "UNION", "USING", "VALUES", "WHERE", "WITH",
+];
+
+/// Rewrites SPARQL queries into a canonical text, so that queries which
+/// differ only in how they are written share a query cache entry.
+///
+/// The normalised form is a token sequence, not meant to be executed:
+/// - comments, `PREFIX` declarations and layout are dropped, and keywords
+///   are upper-cased
+/// - prefixed names (and `a`) are expanded to full IRIs, using the query's
+///   own declarations before the given prefixes
+/// - consecutive triple patterns of a group are sorted, most bound (most
+///   selective) first, then by text; a trailing `.` is added to each
+/// - `DISTINCT` is dropped from a single-variable SELECT whose WHERE clause
+///   is one triple pattern with no other variable, as the triple set cannot
+///   produce that variable twice
+pub struct QueryNormalizer;
+
+impl QueryNormalizer {
+    pub fn normalise(query: &str, prefixes: &HashMap<String, String>) -> String {
+        let tokens = tokenize(query);
+
+        // The query's declarations apply to the whole query and take precedence
+        let mut namespaces = prefixes.clone();
+        let mut body = Vec::with_capacity(tokens.len());
+        let mut i = 0;
+        while i < tokens.len() {
+            if tokens[i].eq_ignore_ascii_case("PREFIX") && i + 2 < tokens.len() && is_iri(&tokens[i + 2]) {
+                if let Some(prefix) = tokens[i + 1].strip_suffix(':') {
+                    let iri = &tokens[i + 2];
+                    namespaces.insert(prefix.to_string(), iri[1..iri.len() - 1].to_string());
+                    i += 3;
+                    continue;
+                }
+            }
+            body.push(tokens[i].clone());
+            i += 1;
+        }
+
+        let expanded: Vec<String> = body.into_iter().map(|token| expand(token, &namespaces)).collect();
+        let mut canonical = Vec::with_capacity(expanded.len());
+        emit_with_groups(&expanded, &mut canonical);
+        drop_redundant_distinct(&mut canonical);
+        canonical.join(" ")
+    }
+}
+
+/// Split `query` into tokens: IRIs, literals, variables, words (keywords,
+/// prefixed names, numbers) and punctuation, skipping layout and comments
+fn tokenize(query: &str) -> Vec<String> {
+    let chars: Vec<char> = query.chars().collect();
+    let mut tokens = Vec::new();
+    let mut i = 0;
+    let is_word_char = |c: char| c.is_alphanumeric() || matches!(c, '_' | '-' | ':' | '.' | '%');
+
+    while i < chars.len() {
+        let c = chars[i];
+        let start = i;
+        match c {
+            c if c.is_whitespace() => {
+                i += 1;
+                continue;
+            }
+            '#' => {
+                while i < chars.len() && chars[i] != '\n' {
+                    i += 1;
+                }
+                continue;
+            }
+            '"' | '\'' => {
+                // Short or long (`"""`) string, with backslash escapes
+                let long = chars.get(i + 1) == Some(&c) && chars.get(i + 2) == Some(&c);
+                let quote = if long { 3 } else { 1 };
+                i += quote;
+                while i < chars.len() {
+                    if chars[i] == '\\' {
+                        i += 2;
+                    } else if chars[i] == c && (!long || chars[i..].starts_with(&[c, c, c])) {
+                        i += quote;
+                        break;
+                    } else {
+                        i += 1;
+                    }
+                }
+            }
+            '<' => {
+                let iri_end = chars[i + 1..]
+                    .iter()
+                    .position(|&ch| ch == '>' || ch.is_whitespace() || "<\"{}|^`\\".contains(ch))
+                    .map(|offset| i + 1 + offset)
+                    .filter(|&end| chars[end] == '>');
+                i = match iri_end {
+                    Some(end) => end + 1,
+                    None if matches!(chars.get(i + 1), Some('<' | '=')) => i + 2,
+                    None => i + 1,
+                };
+            }
+            '?' | '$' if chars.get(i + 1).is_some_and(|ch| ch.is_alphanumeric() || *ch == '_') => {
+                i += 1;
+                while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '_') {
+                    i += 1;
+                }
+            }
+            '@' => {
+                i += 1;
+                while i < chars.len() && (chars[i].is_alphanumeric() || chars[i] == '-') {
+                    i += 1;
+                }
+            }
+            c if is_word_char(c) && c != '.' && c != '-' => {
+                while i < chars.len() && is_word_char(chars[i]) {
+                    i += 1;
+                }
+                // A name cannot end with `.`, which ends the triple instead
+                while chars[i - 1] == '.' && i - 1 > start {
+                    i -= 1;
+                }
+            }
+            _ => {
+                let pair: String = chars[i..chars.len().min(i + 2)].iter().collect();
+                i += if matches!(pair.as_str(), ">>" | ">=" | "!=" | "&&" | "||" | "^^") { 2 } else { 1 };
+            }
+        }
+        tokens.push(chars[start..i].iter().collect());
+    }
+    tokens
+}
+
+fn is_iri(token: &str) -> bool {
+    token.len() >= 2 && token.starts_with('<') && token.ends_with('>') && token != "<=" && token != ">>"
+}
+
+fn is_variable(token: &str) -> bool {
+    token.len() > 1 && token.starts_with(['?', '$'])
+}
+
+fn is_keyword(token: &str) -> bool {
+    KEYWORDS.contains(&token)
+}
+
+// Expand prefixed names and `a`, and upper-case keywords
+fn expand(token: String, namespaces: &HashMap<String, String>) -> String {
+    if token == "a" {
+        return RDF_TYPE.to_string();
+    }
+    let upper = token.to_ascii_uppercase();
+    if is_keyword(&upper) {
+        return upper;
+    }
+    if !token.starts_with(|c: char| c.is_ascii_digit()) && !token.starts_with("_:") {
+        if let Some((prefix, local)) = token.split_once(':') {
+            if let Some(namespace) = namespaces.get(prefix) {
+                return format!("<{}{}>", namespace, local);
+            }
+        }
+    }
+    token
+}
+
+/// Whether `token` can start a triple pattern
+fn starts_triple(token: &str) -> bool {
+    is_variable(token)
+        || is_iri(token)
+        || token == "<<"
+        || token == "["
+        || token.starts_with(['"', '\''])
+        || token.starts_with("_:")
+        || (token.contains(':') && !is_keyword(token))
+}
+
+fn bracket_delta(token: &str) -> isize {
+    match token {
+        "(" | "[" | "{" | "<<" => 1,
+        ")" | "]" | "}" | ">>" => -1,
+        _ => 0,
+    }
+}
+
+/// Index of the `}` closing the `{` at `open`, or the end of `tokens`
+fn closing_brace(tokens: &[String], open: usize) -> usize {
+    let mut depth = 0;
+    for (i, token) in tokens.iter().enumerate().skip(open) {
+        match token.as_str() {
+            "{" => depth += 1,
+            "}" => {
+                depth -= 1;
+                if depth == 0 {
+                    return i;
+                }
+            }
+            _ => {}
+        }
+    }
+    tokens.len()
+}
+
+/// Copy `tokens`, canonicalising the content of every `{ }` group except the
+/// data blocks of `VALUES`
+fn emit_with_groups(tokens: &[String], out: &mut Vec<String>) {
+    let mut i = 0;
+    while i < tokens.len() {
+        match tokens[i].as_str() {
+            "VALUES" => {
+                let end = tokens[i..]
+                    .iter()
+                    .position(|token| token == "{")
+                    .map_or(tokens.len(), |open| closing_brace(tokens, i + open) + 1)
+                    .min(tokens.len());
+                out.extend_from_slice(&tokens[i..end]);
+                i = end;
+            }
+            "{" => {
+                let close = closing_brace(tokens, i);
+                out.push("{".to_string());
+                canonical_group(&tokens[i + 1..close], out);
+                if close < tokens.len() {
+                    out.push("}".to_string());
+                }
+                i = close + 1;
+            }
+            _ => {
+                out.push(tokens[i].clone());
+                i += 1;
+            }
+        }
+    }
+}
+
+/// Canonicalise the content of a group: runs of triple statements are sorted,
+/// everything else (filters, optional parts, subgroups) keeps its place
+fn canonical_group(tokens: &[String], out: &mut Vec<String>) {
+    let mut run: Vec<Vec<String>> = Vec::new();
+    let flush = |run: &mut Vec<Vec<String>>, out: &mut Vec<String>| {
+        run.sort_by_cached_key(|statement| {
+            let variables = statement.iter().filter(|token| is_variable(token)).count();
+            (variables, statement.join(" "))
+        });
+        for statement in run.drain(..) {
+            out.extend(statement);
+        }
+    };
+
+    let mut i = 0;
+    while i < tokens.len() {
+        if starts_triple(&tokens[i]) {
+            // A statement ends at `.` or where a keyword or group follows it
+            let mut depth = 0;
+            let mut end = i;
+            while end < tokens.len() {
+                let token = tokens[end].as_str();
+                if depth == 0 && (token == "." || token == "{" || token == "}" || is_keyword(token)) {
+                    break;
+                }
+                depth += bracket_delta(token);
+                end += 1;
+            }
+            let mut statement = Vec::with_capacity(end - i + 1);
+            emit_with_groups(&tokens[i..end], &mut statement);
+            statement.push(".".to_string());
+            run.push(statement);
+            i = if tokens.get(end).is_some_and(|token| token == ".") { end + 1 } else { end };
+            continue;
+        }
+
+        flush(&mut run, out);
+        if tokens[i] == "." {
+            i += 1;
+            continue;
+        }
+        // Any other element runs until its first bracketed part closes (the
+        // data block for `VALUES`), or a `.`
+        let values = tokens[i] == "VALUES";
+        let mut depth = 0;
+        let mut end = i;
+        while end < tokens.len() {
+            let token = tokens[end].as_str();
+            if depth == 0 && token == "." && end > i {
+                break;
+            }
+            let delta = bracket_delta(token);
+            depth += delta;
+            end += 1;
+            if depth == 0 && delta < 0 && (!values || token == "}") {
+                break;
+            }
+        }
+        emit_with_groups(&tokens[i..end], out);
+        i = end;
+    }
+    flush(&mut run, out);
+}
+
+/// Drop `DISTINCT` from `SELECT DISTINCT ?v WHERE { s p o . }` when `?v` is
+/// the pattern's only variable and occurs in it once
+fn drop_redundant_distinct(tokens: &mut Vec<String>) {
+    let mut i = 0;
+    while i + 3 < tokens.len() {
+        if tokens[i] == "SELECT" && tokens[i + 1] == "DISTINCT" && is_variable(&tokens[i + 2]) {
+            let open = if tokens[i + 3] == "WHERE" { i + 4 } else { i + 3 };
+            let content = tokens.get(open + 1..open + 6);
+            if tokens.get(open).is_some_and(|token| token == "{") {
+                if let Some([s, p, o, dot, close]) = content {
+                    let terms = [s, p, o];
+                    let simple = terms.iter().all(|term| bracket_delta(term) == 0);
+                    let variables: Vec<&&String> = terms.iter().filter(|term| is_variable(term)).collect();
+                    if simple && dot == "." && close == "}" && variables.len() == 1 && **variables[0] == tokens[i + 2] {
+                        tokens.remove(i + 1);
+                    }
+                }
+            }
+        }
+        i += 1;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn normalise(query: &str) -> String {
+        let prefixes = HashMap::from([
+            ("rdf".to_string(), "http://www.w3.org/1999/02/22-rdf-syntax-ns#".to_string()),
+            ("ex".to_string(), "http://example.org/".to_string()),
+        ]);
+        QueryNormalizer::normalise(query, &prefixes)
+    }
+
+    #[test]
+    fn expands_prefixed_names_and_the_a_keyword() {
+        let expected = "SELECT ?s WHERE { ?s <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> ?t . }";
+        assert_eq!(normalise("SELECT ?s WHERE { ?s rdf:type ?t }"), expected);
+        assert_eq!(normalise("select ?s where {\n  ?s a ?t .\n}"), expected);
+        assert_eq!(
+            normalise("# people\nSELECT ?s WHERE { ?s <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> ?t }"),
+            expected
+        );
+        // The query's own declaration wins over the given prefixes
+        assert_eq!(
+            normalise("PREFIX ex: <http://other.org/>\nSELECT ?s WHERE { ?s ex:p \"ex:p\" }"),
+            "SELECT ?s WHERE { ?s <http://other.org/p> \"ex:p\" . }"
+        );
+    }
+
+    #[test]
+    fn sorts_triple_patterns_most_bound_first() {
+        let first = normalise("SELECT * WHERE { ?s ?p ?o . ?s ex:name ?n . ?s a ex:Person . FILTER(?n != \"x\") }");
+        let second = normalise("SELECT * WHERE { ?s a ex:Person . ?s ?p ?o . ?s ex:name ?n FILTER(?n != \"x\") }");
+        assert_eq!(first, second);
+        assert_eq!(
+            first,
+            "SELECT * WHERE { ?s <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> <http://example.org/Person> . \
+             ?s <http://example.org/name> ?n . ?s ?p ?o . FILTER ( ?n != \"x\" ) }"
+        );
+
+        // OPTIONAL parts stay where they are; only the runs around them are sorted
+        let optional = normalise("SELECT * WHERE { ?s ?p ?o . ?s ex:a 1 OPTIONAL { ?s ex:c ?c . ?s ex:b 2 } }");
+        assert_eq!(
+            optional,
+            "SELECT * WHERE { ?s <http://example.org/a> 1 . ?s ?p ?o . \
+             OPTIONAL { ?s <http://example.org/b> 2 . ?s <http://example.org/c> ?c . } }"
+        );
+        assert_eq!(normalise("SELECT * WHERE { VALUES ?x { 2 1 } ?x ex:p ?y }"), "SELECT * WHERE { VALUES ?x { 2 1 } ?x <http://example.org/p> ?y . }");
+    }
+
+    #[test]
+    fn drops_distinct_only_when_the_set_semantics_already_guarantee_it() {
+        assert_eq!(
+            normalise("SELECT DISTINCT ?s WHERE { ?s a ex:Person }"),
+            normalise("SELECT ?s WHERE { ?s a ex:Person }")
+        );
+        assert_ne!(
+            normalise("SELECT DISTINCT ?s WHERE { ?s ?p ex:Person }"),
+            normalise("SELECT ?s WHERE { ?s ?p ex:Person }")
+        );
+        assert_ne!(
+            normalise("SELECT DISTINCT ?s WHERE { ?s a ex:Person . ?s ex:name ?n }"),
+            normalise("SELECT ?s WHERE { ?s a ex:Person . ?s ex:name ?n }")
+        );
+    }
+
+    #[test]
+    fn keeps_distinct_queries_apart() {
+        assert_ne!(normalise("SELECT ?s WHERE { ?s ex:p \"a b\" }"), normalise("SELECT ?s WHERE { ?s ex:p \"a  b\" }"));
+        assert_ne!(normalise("SELECT ?s WHERE { ?s ex:p ?o } LIMIT 1"), normalise("SELECT ?s WHERE { ?s ex:p ?o } LIMIT 2"));
+        assert_ne!(normalise("SELECT ?s WHERE { ?s ex:p ?o FILTER(?o < 5) }"), normalise("SELECT ?s WHERE { ?s ex:p ?o FILTER(?o > 5) }"));
+    }
+}
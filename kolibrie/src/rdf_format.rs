@@ -0,0 +1,149 @@
+/*
+ * Copyright © 2026 Volodymyr Kadzhaia
+ * Copyright © 2026 Pieter Bonte
+ * KU Leuven — Stream Intelligence Lab, Belgium
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this file,
+ * you can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use log::warn;
+
+/// RDF serializations understood by the `SparqlDatabase` parsers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RdfFormat {
+    RdfXml,
+    Turtle,
+    NTriples,
+    N3,
+}
+
+impl RdfFormat {
+    /// Format used when the input cannot be classified; RDF/XML was the only
+    /// format accepted before detection existed.
+    pub const DEFAULT: RdfFormat = RdfFormat::RdfXml;
+
+    /// Parse a user-facing format name. `auto` is handled by the callers.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "rdfxml" | "rdf/xml" | "xml" => Some(RdfFormat::RdfXml),
+            "turtle" | "ttl" => Some(RdfFormat::Turtle),
+            "ntriples" | "n-triples" | "nt" => Some(RdfFormat::NTriples),
+            "n3" => Some(RdfFormat::N3),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            RdfFormat::RdfXml => "rdfxml",
+            RdfFormat::Turtle => "turtle",
+            RdfFormat::NTriples => "ntriples",
+            RdfFormat::N3 => "n3",
+        }
+    }
+}
+
+/// Guess the serialization of `data`.
+///
+/// A leading `<?xml`/`<rdf:RDF` means RDF/XML, `@prefix`/`PREFIX` or Turtle
+/// shorthand (` a `, trailing `;`/`,`) means Turtle, `{ ... } =>` means N3,
+/// and lines made only of `<iri>`/`_:b`/literal terms ending in ` .` mean
+/// N-Triples. Input matching none of these falls back to
+/// [`RdfFormat::DEFAULT`] with a warning.
+pub fn detect_rdf_format(data: &str) -> RdfFormat {
+    let data = data.trim_start_matches('\u{feff}');
+    let statements: Vec<&str> = data
+        .lines()
+        .map(str::trim)
+        .filter(|line| !line.is_empty() && !line.starts_with('#'))
+        .collect();
+
+    let Some(first) = statements.first() else {
+        return RdfFormat::DEFAULT;
+    };
+
+    if first.starts_with("<?xml") || first.starts_with("<rdf:RDF") || first.starts_with("<!DOCTYPE") {
+        return RdfFormat::RdfXml;
+    }
+
+    let has_directives = statements.iter().any(|line| {
+        let upper = line.get(..7).unwrap_or(line).to_ascii_uppercase();
+        line.starts_with("@prefix") || line.starts_with("@base") || upper.starts_with("PREFIX ") || upper.starts_with("BASE ")
+    });
+    if data.contains("=>") && data.contains('{') && data.contains('}') {
+        return RdfFormat::N3;
+    }
+    if has_directives {
+        return RdfFormat::Turtle;
+    }
+
+    if statements.iter().all(|line| is_ntriples_line(line)) {
+        return RdfFormat::NTriples;
+    }
+
+    let turtle_shorthand = statements.iter().any(|line| {
+        line.contains(" a ") || line.ends_with(';') || line.ends_with(',') || line.contains("[]")
+    });
+    if turtle_shorthand {
+        return RdfFormat::Turtle;
+    }
+
+    warn!(
+        "Could not detect the RDF format of the input, assuming {}",
+        RdfFormat::DEFAULT.name()
+    );
+    RdfFormat::DEFAULT
+}
+
+fn is_ntriples_line(line: &str) -> bool {
+    let Some(body) = line.strip_suffix('.') else {
+        return false;
+    };
+    let body = body.trim_end();
+    (body.starts_with('<') || body.starts_with("_:")) && {
+        // The predicate of an N-Triples statement is always a full IRI
+        let after_subject = match body.strip_prefix("_:") {
+            Some(rest) => rest.split_once(char::is_whitespace).map(|(_, rest)| rest),
+            None => body.split_once('>').map(|(_, rest)| rest),
+        };
+        after_subject.is_some_and(|rest| rest.trim_start().starts_with('<'))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_rdf_xml() {
+        let data = "<?xml version=\"1.0\"?>\n<rdf:RDF xmlns:rdf=\"http://www.w3.org/1999/02/22-rdf-syntax-ns#\"/>";
+        assert_eq!(detect_rdf_format(data), RdfFormat::RdfXml);
+        assert_eq!(detect_rdf_format("<rdf:RDF></rdf:RDF>"), RdfFormat::RdfXml);
+    }
+
+    #[test]
+    fn detects_turtle() {
+        let data = "@prefix ex: <http://example.org/> .\nex:a ex:p ex:b .";
+        assert_eq!(detect_rdf_format(data), RdfFormat::Turtle);
+        assert_eq!(detect_rdf_format("<http://e/a> a <http://e/C> ;\n  <http://e/p> \"x\" ."), RdfFormat::Turtle);
+    }
+
+    #[test]
+    fn detects_ntriples() {
+        let data = "# comment\n<http://e/a> <http://e/p> <http://e/b> .\n_:b0 <http://e/p> \"lit\"@en .\n";
+        assert_eq!(detect_rdf_format(data), RdfFormat::NTriples);
+    }
+
+    #[test]
+    fn detects_n3_rules() {
+        let data = "@prefix ex: <http://example.org/> .\n{ ?x ex:p ?y } => { ?y ex:q ?x } .";
+        assert_eq!(detect_rdf_format(data), RdfFormat::N3);
+    }
+
+    #[test]
+    fn ambiguous_input_falls_back_to_default() {
+        assert_eq!(detect_rdf_format("just some text"), RdfFormat::DEFAULT);
+    }
+}
@@ -0,0 +1,229 @@
+/*
+ * Copyright © 2026 Volodymyr Kadzhaia
+ * Copyright © 2026 Pieter Bonte
+ * KU Leuven — Stream Intelligence Lab, Belgium
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this file,
+ * you can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+//! Loading RDF documents over HTTP(S) (enabled by the `remote` feature)
+
+use crate::rdf_format::{detect_rdf_format, RdfFormat};
+use crate::sparql_database::SparqlDatabase;
+use std::fmt;
+use std::io::Read;
+use std::time::Duration;
+
+/// Formats the loader asks for, best first
+const ACCEPT: &str = "text/turtle, application/n-triples, application/rdf+xml;q=0.9, \
+                      application/ld+json;q=0.8, text/n3;q=0.5, */*;q=0.1";
+
+/// Limits applied while fetching a document
+#[derive(Debug, Clone)]
+pub struct RemoteOptions {
+    /// Limit for the whole request, from connecting to reading the last byte
+    pub timeout: Duration,
+    /// Largest response body accepted, in bytes
+    pub max_bytes: u64,
+    /// Redirects followed before giving up (303 See Other included)
+    pub max_redirects: u32,
+}
+
+impl Default for RemoteOptions {
+    fn default() -> Self {
+        RemoteOptions {
+            timeout: Duration::from_secs(30),
+            max_bytes: 256 * 1024 * 1024,
+            max_redirects: 5,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RemoteError {
+    /// The request could not be sent or the response not read
+    Fetch(String),
+    /// The server answered with a non-success status
+    Status(u16),
+    /// The body is larger than `RemoteOptions::max_bytes`
+    TooLarge(u64),
+    /// The `Content-Type` is not an RDF serialization
+    UnsupportedContentType(String),
+    /// The body is not valid UTF-8 or not valid for its format
+    Parse(String),
+}
+
+impl fmt::Display for RemoteError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            RemoteError::Fetch(message) => write!(f, "Failed to fetch the document: {}", message),
+            RemoteError::Status(status) => write!(f, "The server answered with HTTP {}", status),
+            RemoteError::TooLarge(limit) => write!(f, "The document exceeds the limit of {} bytes", limit),
+            RemoteError::UnsupportedContentType(content_type) => {
+                write!(f, "Content-Type {} is not a supported RDF serialization", content_type)
+            }
+            RemoteError::Parse(message) => write!(f, "Failed to parse the document: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for RemoteError {}
+
+/// Serialization of a fetched document
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RemoteFormat {
+    Rdf(RdfFormat),
+    JsonLd,
+}
+
+impl RemoteFormat {
+    /// Map a media type (parameters are ignored) to a format. Generic types
+    /// such as `text/plain` are classified from the body.
+    pub fn from_content_type(content_type: &str, body: &str) -> Option<Self> {
+        let essence = content_type.split(';').next().unwrap_or("").trim().to_ascii_lowercase();
+        match essence.as_str() {
+            "application/rdf+xml" | "application/xml" | "text/xml" => Some(RemoteFormat::Rdf(RdfFormat::RdfXml)),
+            "text/turtle" | "application/x-turtle" => Some(RemoteFormat::Rdf(RdfFormat::Turtle)),
+            "application/n-triples" => Some(RemoteFormat::Rdf(RdfFormat::NTriples)),
+            "text/n3" | "text/rdf+n3" => Some(RemoteFormat::Rdf(RdfFormat::N3)),
+            "application/ld+json" | "application/json" => Some(RemoteFormat::JsonLd),
+            "text/plain" | "application/octet-stream" | "" => Some(RemoteFormat::Rdf(detect_rdf_format(body))),
+            _ => None,
+        }
+    }
+}
+
+/// A fetched document and the media type the server declared for it
+#[derive(Debug, Clone)]
+pub struct RemoteDocument {
+    pub content_type: String,
+    pub body: String,
+}
+
+/// GET `url`, following redirects and asking for RDF serializations
+pub fn fetch(url: &str, options: &RemoteOptions) -> Result<RemoteDocument, RemoteError> {
+    let agent = ureq::AgentBuilder::new()
+        .timeout(options.timeout)
+        .redirects(options.max_redirects)
+        .build();
+    let response = match agent.get(url).set("Accept", ACCEPT).call() {
+        Ok(response) => response,
+        Err(ureq::Error::Status(status, _)) => return Err(RemoteError::Status(status)),
+        Err(ureq::Error::Transport(transport)) => return Err(RemoteError::Fetch(transport.to_string())),
+    };
+
+    let declared_length = response
+        .header("Content-Length")
+        .and_then(|length| length.trim().parse::<u64>().ok());
+    if declared_length.is_some_and(|length| length > options.max_bytes) {
+        return Err(RemoteError::TooLarge(options.max_bytes));
+    }
+    let content_type = response.content_type().to_string();
+
+    // Read one byte past the limit to tell a full-size body from a larger one
+    let mut bytes = Vec::new();
+    response
+        .into_reader()
+        .take(options.max_bytes.saturating_add(1))
+        .read_to_end(&mut bytes)
+        .map_err(|e| RemoteError::Fetch(e.to_string()))?;
+    if bytes.len() as u64 > options.max_bytes {
+        return Err(RemoteError::TooLarge(options.max_bytes));
+    }
+    let body = String::from_utf8(bytes).map_err(|e| RemoteError::Parse(e.to_string()))?;
+    Ok(RemoteDocument { content_type, body })
+}
+
+impl SparqlDatabase {
+    /// Fetch the RDF document at `url` into a new database, picking the
+    /// parser from the response `Content-Type`.
+    pub fn load_from_url(url: &str) -> Result<Self, RemoteError> {
+        Self::load_from_url_with_options(url, &RemoteOptions::default())
+    }
+
+    pub fn load_from_url_with_options(url: &str, options: &RemoteOptions) -> Result<Self, RemoteError> {
+        let mut database = SparqlDatabase::new();
+        database.add_from_url(url, options)?;
+        Ok(database)
+    }
+
+    /// Fetch the RDF document at `url` and add its triples to this database
+    pub fn add_from_url(&mut self, url: &str, options: &RemoteOptions) -> Result<(), RemoteError> {
+        let document = fetch(url, options)?;
+        match RemoteFormat::from_content_type(&document.content_type, &document.body) {
+            Some(RemoteFormat::Rdf(format)) => self.parse_rdf_with_format(&document.body, format),
+            Some(RemoteFormat::JsonLd) => self.parse_jsonld(&document.body).map_err(RemoteError::Parse)?,
+            None => return Err(RemoteError::UnsupportedContentType(document.content_type)),
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+    use std::net::TcpListener;
+    use std::thread;
+
+    /// Serve `responses` to consecutive connections on a local port
+    fn serve(responses: Vec<String>) -> String {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let address = listener.local_addr().unwrap();
+        thread::spawn(move || {
+            for response in responses {
+                let (mut stream, _) = listener.accept().unwrap();
+                let mut request = [0u8; 4096];
+                let _ = stream.read(&mut request);
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+        });
+        format!("http://{}", address)
+    }
+
+    fn ok(content_type: &str, body: &str) -> String {
+        format!(
+            "HTTP/1.1 200 OK\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            content_type,
+            body.len(),
+            body
+        )
+    }
+
+    #[test]
+    fn follows_see_other_and_parses_by_content_type() {
+        let turtle = "@prefix ex: <http://example.org/> .\nex:a ex:p ex:b .\n";
+        let base = serve(vec![
+            "HTTP/1.1 303 See Other\r\nLocation: /data\r\nContent-Length: 0\r\nConnection: close\r\n\r\n"
+                .to_string(),
+            ok("text/turtle; charset=utf-8", turtle),
+        ]);
+        let database = SparqlDatabase::load_from_url(&format!("{}/resource", base)).unwrap();
+        assert_eq!(database.triples.len(), 1);
+
+        let base = serve(vec![ok("application/ld+json", r#"{"@id": "http://ex.org/a", "http://ex.org/p": "v"}"#)]);
+        let database = SparqlDatabase::load_from_url(&base).unwrap();
+        assert_eq!(database.triples.len(), 1);
+    }
+
+    #[test]
+    fn rejects_errors_large_bodies_and_unknown_types() {
+        let base = serve(vec!["HTTP/1.1 404 Not Found\r\nContent-Length: 0\r\nConnection: close\r\n\r\n".to_string()]);
+        assert_eq!(SparqlDatabase::load_from_url(&base).err(), Some(RemoteError::Status(404)));
+
+        let base = serve(vec![ok("application/n-triples", "<http://ex.org/a> <http://ex.org/p> \"v\" .\n")]);
+        let options = RemoteOptions { max_bytes: 10, ..RemoteOptions::default() };
+        assert_eq!(
+            SparqlDatabase::load_from_url_with_options(&base, &options).err(),
+            Some(RemoteError::TooLarge(10))
+        );
+
+        let base = serve(vec![ok("image/png", "png")]);
+        assert_eq!(
+            SparqlDatabase::load_from_url(&base).err(),
+            Some(RemoteError::UnsupportedContentType("image/png".to_string()))
+        );
+    }
+}
@@ -7,24 +7,27 @@
 * you can obtain one at https://mozilla.org/MPL/2.0/.
 */
 
-use crate::parser::parse_combined_query;
+use crate::error_handler::line_and_column;
+use crate::parser::{parse_combined_query, parse_from_named_window, parse_register_clause};
 use crate::rsp::r2r::R2ROperator;
 use crate::rsp::r2s::StreamOperator;
 use crate::rsp::s2r::{ReportStrategy, Tick};
 use crate::rsp::simple_r2r::SimpleR2R;
 use crate::rsp_engine::{
     CrossWindowReasoningMode, OperationMode, QueryExecutionMode, RSPEngine, RSPQueryPlan,
-    RSPWindow, ResultConsumer,
+    RSPWindow, ResultConsumer, RspBuildError,
 };
 use crate::sparql_database::SparqlDatabase;
 use crate::streamertail_optimizer::{
     build_logical_plan, LogicalOperator, PhysicalOperator, Streamertail,
 };
+use log::debug;
 use shared::query::{StreamType, SyncPolicy, WindowBlock, WindowClause};
 use shared::rule::Rule;
 use shared::terms::Term;
 use shared::triple::Triple;
 use std::any::TypeId;
+use std::collections::HashMap;
 use std::fmt::Debug;
 use std::hash::Hash;
 use std::sync::Arc;
@@ -56,6 +59,8 @@ pub struct RSPBuilder<'a, I, O> {
     sparql_rules: Vec<String>,
     cross_window_rules: Option<&'a str>,
     cross_window_reasoning_mode: CrossWindowReasoningMode,
+    prefix_registry: HashMap<String, String>,
+    incremental_evaluation: bool,
 }
 
 impl<'a, I, O> RSPBuilder<'a, I, O>
@@ -78,6 +83,8 @@ where
             sparql_rules: Vec::new(),
             cross_window_rules: None,
             cross_window_reasoning_mode: CrossWindowReasoningMode::Incremental,
+            prefix_registry: HashMap::new(),
+            incremental_evaluation: false,
         }
     }
 
@@ -127,11 +134,22 @@ where
         self
     }
 
+    /// N3 rules re-evaluated on every window firing. The triples they infer
+    /// are visible to that firing's query only: they are dropped before the
+    /// next firing and never added to the stream.
     pub fn add_rules(mut self, rules: &'a str) -> RSPBuilder<'a, I, O> {
         self.rules = Some(rules);
         self
     }
 
+    /// Register a prefix for the events passed to `RSPEngine::parse_data`.
+    /// Once any prefix is registered, events are read as Turtle, so they may
+    /// use prefixed names such as `ex:sensor1` instead of full IRIs.
+    pub fn add_prefix(mut self, prefix: &str, iri: &str) -> RSPBuilder<'a, I, O> {
+        self.prefix_registry.insert(prefix.to_string(), iri.to_string());
+        self
+    }
+
     pub fn add_consumer(mut self, consumer: ResultConsumer<O>) -> RSPBuilder<'a, I, O> {
         self.result_consumer = Some(consumer);
         self
@@ -155,11 +173,44 @@ where
         self
     }
 
+    /// Update each window's results with the triples that entered and left the
+    /// window since its previous evaluation, instead of evaluating the query
+    /// over the whole window. Applies to window queries made of triple
+    /// patterns, with an R2R operator that supports it (`SimpleR2R` does);
+    /// other windows are still evaluated in full. Off by default.
+    pub fn set_incremental_evaluation(mut self, incremental: bool) -> RSPBuilder<'a, I, O> {
+        self.incremental_evaluation = incremental;
+        self
+    }
+
     /// Parse the RSP-QL query and extract window configurations
-    fn parse_rsp_ql_query<'b>(&self, query: &'b str) -> Result<RSPQueryConfig<'b>, String> {
+    fn parse_rsp_ql_query<'b>(
+        &self,
+        query: &'b str,
+    ) -> Result<RSPQueryConfig<'b>, RspBuildError> {
         match parse_combined_query(query) {
             Ok((_, parsed_query)) => {
                 if let Some(register_clause) = &parsed_query.register_clause {
+                    // Every WINDOW block has to read from a declared window
+                    for block in &register_clause.query.window_blocks {
+                        let declared = register_clause
+                            .query
+                            .window_clause
+                            .iter()
+                            .any(|wc| wc.window_iri == block.window_name);
+                        if !declared {
+                            return Err(RspBuildError::ParseError {
+                                clause: "WINDOW".to_string(),
+                                position: offset_in(query, block.window_name)
+                                    .map(|offset| line_and_column(query, offset)),
+                                message: format!(
+                                    "window {} is not declared in a FROM NAMED WINDOW clause",
+                                    block.window_name
+                                ),
+                            });
+                        }
+                    }
+
                     let mut windows = Vec::new();
                     let mut database = SparqlDatabase::new();
                     database.set_prefixes(parsed_query.prefixes.clone());
@@ -168,7 +219,7 @@ where
                         let window = self.create_rsp_window(
                             window_clause,
                             &register_clause.query.window_blocks,
-                            &mut database,
+                            &database,
                         )?;
                         windows.push(window);
                     }
@@ -178,7 +229,12 @@ where
                         StreamType::RStream => StreamOperator::RSTREAM,
                         StreamType::IStream => StreamOperator::ISTREAM,
                         StreamType::DStream => StreamOperator::DSTREAM,
-                        StreamType::Custom(_) => StreamOperator::RSTREAM, // Default fallback
+                        StreamType::Custom(name) => {
+                            return Err(RspBuildError::UnsupportedFeature(format!(
+                                "stream operator {} (expected RSTREAM, ISTREAM or DSTREAM)",
+                                name
+                            )))
+                        }
                     };
 
                     // Extract static patterns from WHERE clause (outside window blocks)
@@ -201,10 +257,10 @@ where
                         sync_policy,
                     })
                 } else {
-                    Err("No REGISTER clause found in RSP-QL query".to_string())
+                    Err(locate_register_error(query, None))
                 }
             }
-            Err(e) => Err(format!("Failed to parse RSP-QL query: {:?}", e)),
+            Err(e) => Err(locate_register_error(query, Some(e))),
         }
     }
 
@@ -213,8 +269,8 @@ where
         &self,
         window_clause: &WindowClause,
         window_blocks: &[WindowBlock],
-        database: &mut SparqlDatabase,
-    ) -> Result<RSPWindow, String> {
+        database: &SparqlDatabase,
+    ) -> Result<RSPWindow, RspBuildError> {
         // Find the corresponding window block for this window
         let spo_query = LogicalOperator::scan((
             Term::Variable("s".to_string()),
@@ -227,18 +283,18 @@ where
             .map(|block| {
                 // Convert window block patterns to query plan
                 for (j, (s, p, o)) in block.patterns.iter().enumerate() {
-                    println!(" Registering     {}: {} {} {}", j + 1, s, p, o);
+                    debug!(" Registering     {}: {} {} {}", j + 1, s, p, o);
                 }
                 let op = build_logical_plan(
                     Vec::new(),
                     block.patterns.clone(),
                     Vec::new(),
-                    &database.prefixes.clone(),
+                    &database.prefixes,
                     database,
                     &[],
                     None,
                 );
-                println!("\tResults in {:?}", op);
+                debug!("\tResults in {:?}", op);
                 op
             })
             .unwrap_or_else(|| spo_query);
@@ -276,14 +332,16 @@ where
     }
 
     /// Create RSP-QL query plan using Volcano optimizer
-    fn create_rsp_query_plan(query_config: &RSPQueryConfig) -> Result<RSPQueryPlan, String> {
+    fn create_rsp_query_plan(
+        query_config: &RSPQueryConfig,
+    ) -> Result<RSPQueryPlan, RspBuildError> {
         let mut window_plans = Vec::new();
 
         // Create individual window plans
         for window in &query_config.windows {
             window_plans.push(window.query.clone());
         }
-        let mut database = query_config.database.clone();
+        let database = &query_config.database;
 
         // Create static data plan if there are static patterns
         let static_data_plan = if !query_config.static_patterns.is_empty() {
@@ -291,8 +349,8 @@ where
                 Vec::new(),
                 query_config.static_patterns.clone(),
                 Vec::new(),
-                &database.prefixes.clone(),
-                &mut database,
+                &database.prefixes,
+                database,
                 &[],
                 None,
             );
@@ -302,20 +360,20 @@ where
         };
 
         // Create physical plans from the logical ones
-        let mut optimizer = Streamertail::new(&database);
+        let mut optimizer = Streamertail::new(database);
 
         let static_data_plan = match static_data_plan {
             Some(v) => Some(optimizer.find_best_plan(&v)),
             None => None,
         };
 
-        println!("logical window plans {:?}", window_plans);
+        debug!("logical window plans {:?}", window_plans);
 
         let window_plans = window_plans
             .iter()
             .map(|v| optimizer.find_best_plan(v))
             .collect();
-        println!("physical window plans {:?}", window_plans);
+        debug!("physical window plans {:?}", window_plans);
 
         Ok(RSPQueryPlan {
             window_plans,
@@ -323,12 +381,13 @@ where
         })
     }
 
-    pub fn build(mut self) -> Result<RSPEngine<I, O>, String> {
-        let rsp_ql_query = self
-            .rsp_ql_query
-            .take()
-            .ok_or("Please provide RSP-QL query")?;
-        let mut r2r = self.r2r.take().ok_or("Please provide R2R operator!")?;
+    pub fn build(mut self) -> Result<RSPEngine<I, O>, RspBuildError> {
+        let rsp_ql_query = self.rsp_ql_query.take().ok_or_else(|| {
+            RspBuildError::MissingConsumer("RSP-QL query, set it with add_rsp_ql_query".to_string())
+        })?;
+        let mut r2r = self.r2r.take().ok_or_else(|| {
+            RspBuildError::MissingConsumer("R2R operator, set it with add_r2r".to_string())
+        })?;
         let triples = self.triples.take().unwrap_or("");
         let syntax = self.syntax.clone();
         let rules = self.rules.take().unwrap_or("");
@@ -340,16 +399,16 @@ where
 
         if cross_window_rules.is_some() {
             if TypeId::of::<I>() != TypeId::of::<Triple>() {
-                return Err(
+                return Err(RspBuildError::UnsupportedFeature(
                     "Cross-window SDS+ reasoning currently requires Triple input items"
                         .to_string(),
-                );
+                ));
             }
             if r2r.as_any_mut().downcast_mut::<SimpleR2R>().is_none() {
-                return Err(
+                return Err(RspBuildError::UnsupportedFeature(
                     "Cross-window SDS+ reasoning currently requires the SimpleR2R operator"
                         .to_string(),
-                );
+                ));
             }
         }
 
@@ -376,6 +435,93 @@ where
             self.sparql_rules,
             cross_window_rules,
             self.cross_window_reasoning_mode,
+            self.prefix_registry,
+            self.incremental_evaluation,
         )
     }
 }
+
+/// Byte offset of `part` in `query` when `part` is a slice of it
+fn offset_in(query: &str, part: &str) -> Option<usize> {
+    let offset = (part.as_ptr() as usize).checked_sub(query.as_ptr() as usize)?;
+    (offset <= query.len()).then_some(offset)
+}
+
+/// Name of the RSP-QL clause that contains byte `offset` of `query`
+fn clause_at(query: &str, offset: usize) -> &'static str {
+    const CLAUSES: [&str; 6] = ["PREFIX", "REGISTER", "SELECT", "FROM NAMED WINDOW", "WHERE", "WINDOW"];
+    let mut clause = ("query", 0);
+    for name in CLAUSES {
+        for (start, _) in query.match_indices(name) {
+            // The WINDOW of FROM NAMED WINDOW does not open a window block
+            if name == "WINDOW" && query[..start].trim_end().ends_with("NAMED") {
+                continue;
+            }
+            if start <= offset && start >= clause.1 {
+                clause = (name, start);
+            }
+        }
+    }
+    clause.0
+}
+
+/// Turn a nom failure on `query` into a `ParseError` naming the clause and position
+fn rsp_ql_parse_error(query: &str, error: nom::Err<nom::error::Error<&str>>) -> RspBuildError {
+    match error {
+        nom::Err::Error(e) | nom::Err::Failure(e) => {
+            let offset = offset_in(query, e.input).unwrap_or(query.len());
+            let token: String = e
+                .input
+                .split_whitespace()
+                .next()
+                .map(|token| token.chars().take(32).collect())
+                .unwrap_or_default();
+            let message = if token.is_empty() {
+                "unexpected end of query".to_string()
+            } else {
+                format!("unexpected `{}`", token)
+            };
+            RspBuildError::ParseError {
+                clause: clause_at(query, offset).to_string(),
+                position: Some(line_and_column(query, offset)),
+                message,
+            }
+        }
+        nom::Err::Incomplete(_) => RspBuildError::ParseError {
+            clause: "query".to_string(),
+            position: None,
+            message: "incomplete RSP-QL query".to_string(),
+        },
+    }
+}
+
+/// The REGISTER clause is optional in the combined grammar, so a malformed one
+/// is silently skipped; parse it on its own to find where it actually breaks
+fn locate_register_error(
+    query: &str,
+    error: Option<nom::Err<nom::error::Error<&str>>>,
+) -> RspBuildError {
+    if let Some(start) = query.find("REGISTER") {
+        if let Err(e) = parse_register_clause(&query[start..]) {
+            let mut error = rsp_ql_parse_error(query, e.clone());
+            // A FROM NAMED WINDOW after the first that fails to parse ends the
+            // window list, so the error surfaces at its start; look inside it
+            if let nom::Err::Error(inner) | nom::Err::Failure(inner) = &e {
+                if inner.input.trim_start().starts_with("FROM") {
+                    if let Err(window_error) = parse_from_named_window(inner.input) {
+                        error = rsp_ql_parse_error(query, window_error);
+                    }
+                }
+            }
+            return error;
+        }
+    }
+    match error {
+        Some(e) => rsp_ql_parse_error(query, e),
+        None => RspBuildError::ParseError {
+            clause: "REGISTER".to_string(),
+            position: None,
+            message: "No REGISTER clause found in RSP-QL query".to_string(),
+        },
+    }
+}
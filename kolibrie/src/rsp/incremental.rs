@@ -0,0 +1,312 @@
+/*
+ * Copyright © 2026 Volodymyr Kadzhaia
+ * Copyright © 2026 Pieter Bonte
+ * KU Leuven — Stream Intelligence Lab, Belgium
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this file,
+ * you can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use crate::streamertail_optimizer::PhysicalOperator;
+use shared::index_manager::UnifiedIndex;
+use shared::terms::{Term, TriplePattern};
+use shared::triple::Triple;
+use std::collections::HashSet;
+
+/// Solutions of a window query kept up to date from the triples added to and
+/// removed from the store, instead of evaluating the query over the whole
+/// window on every firing.
+///
+/// A solution binds every variable of the query's basic graph pattern, so it
+/// fixes the triple each pattern matched: it is dropped once one of those
+/// triples is removed (negative delta), and the solutions using an added
+/// triple are found by joining it with the rest of the store (positive delta).
+pub struct IncrementalView {
+    patterns: Vec<TriplePattern>,
+    /// Variables of `patterns` without their `?`, sorted
+    variables: Vec<String>,
+    /// One id per entry of `variables`
+    solutions: HashSet<Vec<u32>>,
+    /// Net store changes since the last `refresh`
+    added: HashSet<Triple>,
+    removed: HashSet<Triple>,
+    /// Set by the first `refresh`, which evaluates the query in full
+    evaluated: bool,
+}
+
+impl IncrementalView {
+    /// A view of `plan`, or `None` unless the plan only scans and joins triple
+    /// patterns without quoted triples
+    pub fn for_plan(plan: &PhysicalOperator) -> Option<Self> {
+        let mut patterns = Vec::new();
+        collect_patterns(plan, &mut patterns)?;
+        let mut variables: Vec<String> = patterns
+            .iter()
+            .flat_map(|(s, p, o)| [s, p, o])
+            .filter_map(|term| match term {
+                Term::Variable(name) => Some(variable_name(name).to_string()),
+                _ => None,
+            })
+            .collect();
+        variables.sort_unstable();
+        variables.dedup();
+        Some(IncrementalView {
+            patterns,
+            variables,
+            solutions: HashSet::new(),
+            added: HashSet::new(),
+            removed: HashSet::new(),
+            evaluated: false,
+        })
+    }
+
+    /// Note that `triple` was added to the store
+    pub fn record_added(&mut self, triple: &Triple) {
+        if !self.removed.remove(triple) {
+            self.added.insert(triple.clone());
+        }
+    }
+
+    /// Note that `triple` was removed from the store
+    pub fn record_removed(&mut self, triple: &Triple) {
+        if !self.added.remove(triple) {
+            self.removed.insert(triple.clone());
+        }
+    }
+
+    /// Apply the changes recorded since the last call to the solutions and
+    /// return them as (variable, id) pairs sorted by variable. `index` is the
+    /// store after those changes.
+    pub fn refresh(&mut self, index: &UnifiedIndex) -> Vec<Vec<(String, u32)>> {
+        if !self.evaluated {
+            self.evaluated = true;
+            let unbound = vec![None; self.variables.len()];
+            let all: Vec<usize> = (0..self.patterns.len()).collect();
+            let mut solutions = HashSet::new();
+            self.extend(unbound, &all, index, &mut solutions);
+            self.solutions = solutions;
+        } else {
+            if !self.removed.is_empty() {
+                let removed = std::mem::take(&mut self.removed);
+                let patterns = &self.patterns;
+                let variables = &self.variables;
+                self.solutions.retain(|solution| {
+                    !patterns
+                        .iter()
+                        .any(|pattern| removed.contains(&instantiate(pattern, variables, solution)))
+                });
+            }
+
+            let mut new_solutions = HashSet::new();
+            for triple in &self.added {
+                for (i, pattern) in self.patterns.iter().enumerate() {
+                    let unbound = vec![None; self.variables.len()];
+                    if let Some(binding) = self.unify(pattern, triple, unbound) {
+                        let rest: Vec<usize> = (0..self.patterns.len()).filter(|&j| j != i).collect();
+                        self.extend(binding, &rest, index, &mut new_solutions);
+                    }
+                }
+            }
+            self.solutions.extend(new_solutions);
+        }
+        self.added.clear();
+        self.removed.clear();
+
+        self.solutions
+            .iter()
+            .map(|solution| self.variables.iter().cloned().zip(solution.iter().copied()).collect())
+            .collect()
+    }
+
+    /// Join `binding` with the `remaining` patterns over `index`, most bound
+    /// pattern first, adding every complete binding to `out`
+    fn extend(
+        &self,
+        binding: Vec<Option<u32>>,
+        remaining: &[usize],
+        index: &UnifiedIndex,
+        out: &mut HashSet<Vec<u32>>,
+    ) {
+        let Some(position) = (0..remaining.len())
+            .max_by_key(|&k| self.bound_terms(&self.patterns[remaining[k]], &binding))
+        else {
+            out.insert(binding.into_iter().map(|id| id.unwrap_or_default()).collect());
+            return;
+        };
+        let pattern = &self.patterns[remaining[position]];
+        let rest: Vec<usize> = remaining
+            .iter()
+            .enumerate()
+            .filter(|&(k, _)| k != position)
+            .map(|(_, &i)| i)
+            .collect();
+        let lookup = |term: &Term| self.resolve(term, &binding);
+        for triple in index.query(lookup(&pattern.0), lookup(&pattern.1), lookup(&pattern.2)) {
+            if let Some(extended) = self.unify(pattern, &triple, binding.clone()) {
+                self.extend(extended, &rest, index, out);
+            }
+        }
+    }
+
+    /// `binding` extended so that `pattern` matches `triple`, if it can be
+    fn unify(&self, pattern: &TriplePattern, triple: &Triple, mut binding: Vec<Option<u32>>) -> Option<Vec<Option<u32>>> {
+        for (term, id) in [
+            (&pattern.0, triple.subject),
+            (&pattern.1, triple.predicate),
+            (&pattern.2, triple.object),
+        ] {
+            match term {
+                Term::Constant(constant) if *constant != id => return None,
+                Term::Variable(name) => {
+                    let slot = &mut binding[self.variable_index(name)];
+                    match slot {
+                        Some(bound) if *bound != id => return None,
+                        _ => *slot = Some(id),
+                    }
+                }
+                _ => {}
+            }
+        }
+        Some(binding)
+    }
+
+    fn resolve(&self, term: &Term, binding: &[Option<u32>]) -> Option<u32> {
+        match term {
+            Term::Constant(id) => Some(*id),
+            Term::Variable(name) => binding[self.variable_index(name)],
+            Term::QuotedTriple(_) => None,
+        }
+    }
+
+    fn bound_terms(&self, pattern: &TriplePattern, binding: &[Option<u32>]) -> usize {
+        [&pattern.0, &pattern.1, &pattern.2]
+            .into_iter()
+            .filter(|term| self.resolve(term, binding).is_some())
+            .count()
+    }
+
+    fn variable_index(&self, name: &str) -> usize {
+        self.variables
+            .binary_search_by(|variable| variable.as_str().cmp(variable_name(name)))
+            .expect("variables hold every variable of the patterns")
+    }
+}
+
+/// The triple `pattern` matched under a complete `solution`
+fn instantiate(pattern: &TriplePattern, variables: &[String], solution: &[u32]) -> Triple {
+    let id = |term: &Term| match term {
+        Term::Constant(id) => *id,
+        Term::Variable(name) => {
+            let name = variable_name(name);
+            solution[variables.binary_search_by(|variable| variable.as_str().cmp(name)).unwrap()]
+        }
+        Term::QuotedTriple(_) => 0,
+    };
+    Triple {
+        subject: id(&pattern.0),
+        predicate: id(&pattern.1),
+        object: id(&pattern.2),
+    }
+}
+
+fn variable_name(name: &str) -> &str {
+    name.strip_prefix('?').unwrap_or(name)
+}
+
+// The triple patterns of a plan made of scans and joins
+fn collect_patterns(plan: &PhysicalOperator, patterns: &mut Vec<TriplePattern>) -> Option<()> {
+    let supported = |pattern: &TriplePattern| {
+        ![&pattern.0, &pattern.1, &pattern.2]
+            .into_iter()
+            .any(|term| matches!(term, Term::QuotedTriple(_)))
+    };
+    match plan {
+        PhysicalOperator::TableScan { pattern } | PhysicalOperator::IndexScan { pattern, .. } => {
+            supported(pattern).then(|| patterns.push(pattern.clone()))
+        }
+        PhysicalOperator::StarJoin { patterns: star, .. } => {
+            star.iter().all(supported).then(|| patterns.extend(star.iter().cloned()))
+        }
+        PhysicalOperator::HashJoin { left, right }
+        | PhysicalOperator::NestedLoopJoin { left, right }
+        | PhysicalOperator::ParallelJoin { left, right }
+        | PhysicalOperator::OptimizedHashJoin { left, right } => {
+            collect_patterns(left, patterns)?;
+            collect_patterns(right, patterns)
+        }
+        PhysicalOperator::Profile { inner, .. } => collect_patterns(inner, patterns),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn var(name: &str) -> Term {
+        Term::Variable(name.to_string())
+    }
+
+    fn triple(subject: u32, predicate: u32, object: u32) -> Triple {
+        Triple { subject, predicate, object }
+    }
+
+    fn sorted(mut rows: Vec<Vec<(String, u32)>>) -> Vec<Vec<(String, u32)>> {
+        rows.sort();
+        rows
+    }
+
+    fn row(pairs: &[(&str, u32)]) -> Vec<(String, u32)> {
+        pairs.iter().map(|(name, id)| (name.to_string(), *id)).collect()
+    }
+
+    #[test]
+    fn applies_added_and_removed_triples_to_the_join() {
+        const KNOWS: u32 = 1;
+        const AGE: u32 = 2;
+        // ?a knows ?b . ?b age ?age
+        let plan = PhysicalOperator::HashJoin {
+            left: Box::new(PhysicalOperator::table_scan((var("?a"), Term::Constant(KNOWS), var("?b")))),
+            right: Box::new(PhysicalOperator::table_scan((var("?b"), Term::Constant(AGE), var("?age")))),
+        };
+        let mut view = IncrementalView::for_plan(&plan).unwrap();
+        let mut index = UnifiedIndex::new();
+        index.insert(&triple(10, KNOWS, 11));
+        index.insert(&triple(11, AGE, 30));
+        assert_eq!(view.refresh(&index), vec![row(&[("a", 10), ("age", 30), ("b", 11)])]);
+
+        // A second friend of 11 and an age for 12, who has no friends yet
+        for added in [triple(13, KNOWS, 11), triple(12, AGE, 40)] {
+            index.insert(&added);
+            view.record_added(&added);
+        }
+        assert_eq!(
+            sorted(view.refresh(&index)),
+            vec![row(&[("a", 10), ("age", 30), ("b", 11)]), row(&[("a", 13), ("age", 30), ("b", 11)])]
+        );
+
+        // Removing 11's age drops both solutions; re-adding 10's friendship changes nothing
+        index.delete(&triple(11, AGE, 30));
+        view.record_removed(&triple(11, AGE, 30));
+        index.delete(&triple(10, KNOWS, 11));
+        view.record_removed(&triple(10, KNOWS, 11));
+        index.insert(&triple(10, KNOWS, 11));
+        view.record_added(&triple(10, KNOWS, 11));
+        index.insert(&triple(10, KNOWS, 12));
+        view.record_added(&triple(10, KNOWS, 12));
+        assert_eq!(view.refresh(&index), vec![row(&[("a", 10), ("age", 40), ("b", 12)])]);
+    }
+
+    #[test]
+    fn other_operators_are_not_maintained() {
+        let scan = PhysicalOperator::table_scan((var("?s"), var("?p"), var("?o")));
+        let projection = PhysicalOperator::Projection {
+            input: Box::new(scan.clone()),
+            variables: vec!["s".to_string()],
+            distinct: false,
+        };
+        assert!(IncrementalView::for_plan(&scan).is_some());
+        assert!(IncrementalView::for_plan(&projection).is_none());
+    }
+}
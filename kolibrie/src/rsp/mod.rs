@@ -9,8 +9,11 @@
  */
 
 pub mod builder;
+pub mod incremental;
 pub mod r2r;
 pub mod r2s;
 pub mod s2r;
 pub mod simple_r2r;
-pub mod window_runner;
\ No newline at end of file
+pub mod window_runner;
+pub mod window_sync;
+pub mod worker_pool;
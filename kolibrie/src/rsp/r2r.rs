@@ -8,9 +8,11 @@
  * you can obtain one at https://mozilla.org/MPL/2.0/.
  */
 
+use crate::custom_error::SparqlError;
 use crate::streamertail_optimizer::PhysicalOperator;
 
 use std::any::Any;
+use std::collections::HashMap;
 
 /// Helper trait to allow downcasting mutable trait objects to their concrete types.
 /// Implementations should return a mutable Any reference to enable `downcast_mut`.
@@ -24,7 +26,26 @@ pub trait R2ROperator<I, R, O>: Send + AsAnyMut {
     fn add(&mut self, data: I);
     fn remove(&mut self, data: &I);
     fn materialize(&mut self) -> Vec<I>;
-    fn execute_query(&mut self, op: &PhysicalOperator) -> Vec<O>;
+    /// Results of `op` over the current items. A failed query (e.g. a join
+    /// over the store's result limit) is an error rather than an empty result.
+    fn execute_query(&mut self, op: &PhysicalOperator) -> Result<Vec<O>, SparqlError>;
+
+    /// Results of `op` for `view` (one per window), updated with the items
+    /// added and removed since the previous call for that view instead of
+    /// evaluated in full. `None` when the operator or the query does not
+    /// support this; callers then use `execute_query`.
+    fn execute_query_incremental(&mut self, view: &str, op: &PhysicalOperator) -> Option<Vec<O>> {
+        let _ = (view, op);
+        None
+    }
 
     fn parse_data(&mut self, data: &str) -> Vec<I>;
+
+    /// Parse Turtle `data`, resolving prefixed names with `prefixes` as well as
+    /// the data's own declarations. Operators without a Turtle parser read the
+    /// data with `parse_data`.
+    fn parse_turtle_data(&mut self, data: &str, prefixes: &HashMap<String, String>) -> Vec<I> {
+        let _ = prefixes;
+        self.parse_data(data)
+    }
 }
\ No newline at end of file
@@ -94,6 +94,8 @@ where
 {
     elements: HashMap<I, usize>,
     last_timestamp_changed: usize,
+    /// Close time of the window this content was reported for
+    window_close: usize,
     origin: String
 }
 
@@ -105,6 +107,7 @@ where
         ContentContainer {
             elements: HashMap::new(),
             last_timestamp_changed: 0,
+            window_close: 0,
             origin: String::default()
         }
     }
@@ -112,6 +115,7 @@ where
         ContentContainer {
             elements: HashMap::new(),
             last_timestamp_changed: 0,
+            window_close: 0,
             origin: origin.to_string()
         }
     }
@@ -128,6 +132,10 @@ where
     pub fn get_last_timestamp_changed(&self) -> usize {
         self.last_timestamp_changed
     }
+    /// The tick this content was reported at: the close time of its window
+    pub fn get_window_close(&self) -> usize {
+        self.window_close
+    }
 
     pub fn iter(&self) -> Keys<'_, I, usize> {
         self.elements.keys()
@@ -218,15 +226,17 @@ where
                         self.app_time = ts;
                         // notify consumers
                         debug!("Window triggers! {:?}", max_window);
+                        let mut content = max_window.1.clone();
+                        content.window_close = max_window.0.close;
                         // multithreaded consumer using channel
                         if let Some(sender) = &self.consumer {
-                            if let Err(e) = sender.send(max_window.1.clone()) {
+                            if let Err(e) = sender.send(content.clone()) {
                                 warn!("Failed to send window content to consumer: {:?}", e);
                             }
                         }
                         // single threaded consumer using callback
                         if let Some(call_back) = &mut self.call_back {
-                            (call_back)(max_window.1.clone());
+                            (call_back)(content);
                         }
                     }
                 }
@@ -282,6 +292,8 @@ where
     }
     pub fn flush(&mut self) {
         let mut merged = ContentContainer::new_with_origin(&self.uri);
+        // The flush reports everything still open, as of the last window to close
+        merged.window_close = self.active_windows.keys().map(|w| w.close).max().unwrap_or(self.app_time);
         for content in self.active_windows.values() {
             for (item, ts) in content.iter_with_timestamps() {
                 merged.add(item.clone(), ts);
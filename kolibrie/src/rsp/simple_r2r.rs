@@ -7,14 +7,17 @@
 * you can obtain one at https://mozilla.org/MPL/2.0/.
 */
 
+use crate::custom_error::SparqlError;
+use crate::rsp::incremental::IncrementalView;
 use crate::rsp::r2r::{AsAnyMut, R2ROperator};
 use crate::rsp_engine::QueryExecutionMode;
 use crate::sparql_database::SparqlDatabase;
 use crate::streamertail_optimizer::{ExecutionEngine, PhysicalOperator};
-use datalog::parser_n3_logic::parse_n3_rule;
+use datalog::parser_n3_logic::parse_n3_document;
 use datalog::reasoning::Reasoner;
 use shared::rule::Rule;
 use shared::triple::Triple;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 
 #[cfg(not(test))]
@@ -26,7 +29,11 @@ pub struct SimpleR2R {
     pub item: SparqlDatabase,
     pub execution_mode: QueryExecutionMode,
     pub rules: Vec<Rule>,
-    derived_triples: Vec<Triple>,
+    /// Triples inferred by the last `materialize`, evicted by the next one
+    derived_triples: HashSet<Triple>,
+    /// Results kept by `execute_query_incremental`, by view; `None` for
+    /// queries it cannot maintain
+    incremental_views: HashMap<String, Option<IncrementalView>>,
 }
 
 impl SimpleR2R {
@@ -35,7 +42,8 @@ impl SimpleR2R {
             item: SparqlDatabase::new(),
             execution_mode: QueryExecutionMode::Standard,
             rules: Vec::new(),
-            derived_triples: Vec::new(),
+            derived_triples: HashSet::new(),
+            incremental_views: HashMap::new(),
         }
     }
 
@@ -44,13 +52,32 @@ impl SimpleR2R {
             item: SparqlDatabase::new(),
             execution_mode,
             rules: Vec::new(),
-            derived_triples: Vec::new(),
+            derived_triples: HashSet::new(),
+            incremental_views: HashMap::new(),
         }
     }
 
     pub fn add_reasoning_rules(&mut self, rules: Vec<Rule>) {
         self.rules.extend(rules);
     }
+
+    // Store changes go through these two, so incremental views see every one
+    fn insert_triple(&mut self, triple: Triple) {
+        if !self.item.triples.contains(&triple) {
+            for view in self.incremental_views.values_mut().flatten() {
+                view.record_added(&triple);
+            }
+        }
+        self.item.add_triple(triple);
+    }
+
+    fn delete_triple(&mut self, triple: &Triple) {
+        if self.item.delete_triple(triple) {
+            for view in self.incremental_views.values_mut().flatten() {
+                view.record_removed(triple);
+            }
+        }
+    }
 }
 
 /// Allow downcasting from trait objects by exposing Any for mutable references.
@@ -74,38 +101,31 @@ impl R2ROperator<Triple, Vec<PhysicalOperator>, Vec<(String, String)>> for Simpl
         if data.trim().is_empty() {
             return Ok(());
         }
+        // Same document parser as `SparqlDatabase::apply_n3_rules`: shared
+        // `@prefix` declarations, each rule optionally ending with `.`
         let mut temp_reasoner = Reasoner::new();
         temp_reasoner.dictionary = Arc::clone(&self.item.dictionary);
-        let mut remaining = data;
-        loop {
-            match parse_n3_rule(remaining, &mut temp_reasoner) {
-                Ok((rest, (_, rule))) => {
-                    self.rules.push(rule);
-                    remaining = rest;
-                    if remaining.trim().is_empty() {
-                        break;
-                    }
-                }
-                Err(_) => return Err("Failed to parse N3 rules"),
-            }
-        }
+        let (_, (_, rules)) = parse_n3_document(data.trim(), &mut temp_reasoner)
+            .map_err(|_| "Failed to parse N3 rules")?;
+        self.rules.extend(rules);
         Ok(())
     }
 
     fn add(&mut self, data: Triple) {
-        self.item.add_triple(data);
+        // A streamed triple that was also inferred must outlive the inference
+        self.derived_triples.remove(&data);
+        self.insert_triple(data);
     }
 
     fn remove(&mut self, data: &Triple) {
-        self.item.delete_triple(data);
+        self.delete_triple(data);
     }
 
     fn materialize(&mut self) -> Vec<Triple> {
         // Evict derived triples from the previous cycle
-        for t in &self.derived_triples {
-            self.item.delete_triple(t);
+        for t in std::mem::take(&mut self.derived_triples) {
+            self.delete_triple(&t);
         }
-        self.derived_triples.clear();
 
         if self.rules.is_empty() {
             return Vec::new();
@@ -117,32 +137,72 @@ impl R2ROperator<Triple, Vec<PhysicalOperator>, Vec<(String, String)>> for Simpl
             reasoner.index_manager.insert(triple);
         }
         reasoner.rules = self.rules.clone();
-
-        let derived = reasoner.infer_new_facts_semi_naive();
+        reasoner.inference_budget = self.item.inference_budget;
+
+        // A cycle whose rules run past the budget derives nothing
+        let derived = match reasoner.try_infer_new_facts_semi_naive() {
+            Ok(derived) => derived,
+            Err(e) => {
+                error!("materialize: {}", e);
+                return Vec::new();
+            }
+        };
         debug!("materialize: {} facts derived by reasoning", derived.len());
         for t in &derived {
-            self.item.add_triple(t.clone());
-            self.derived_triples.push(t.clone());
+            self.insert_triple(t.clone());
+            self.derived_triples.insert(t.clone());
         }
         derived
     }
 
-    fn execute_query(&mut self, op: &PhysicalOperator) -> Vec<Vec<(String, String)>> {
+    fn execute_query(&mut self, op: &PhysicalOperator) -> Result<Vec<Vec<(String, String)>>, SparqlError> {
         debug!("SimpleR2R executing query with PhysicalOperator");
 
         // Execute the physical operator using the Volcano execution engine.
         // The engine returns Vec<HashMap<String,String>> (bindings per row).
-        ExecutionEngine::execute(op, &mut self.item)
+        Ok(ExecutionEngine::execute(op, &self.item)?
             .into_iter()
             .map(|hashmap| {
                 let mut v: Vec<(String, String)> = hashmap.into_iter().collect();
                 v.sort_unstable_by(|a, b| a.0.cmp(&b.0));
                 v
             })
-            .collect()
+            .collect())
+    }
+
+    fn execute_query_incremental(&mut self, view: &str, op: &PhysicalOperator) -> Option<Vec<Vec<(String, String)>>> {
+        let view = self
+            .incremental_views
+            .entry(view.to_string())
+            .or_insert_with(|| IncrementalView::for_plan(op))
+            .as_mut()?;
+        let solutions = view.refresh(&self.item.index_manager);
+
+        // Decoded as `ExecutionEngine::execute` does
+        let dict = self.item.dictionary.read().unwrap();
+        let qt_store = self.item.quoted_triple_store.read().unwrap();
+        Some(
+            solutions
+                .into_iter()
+                .map(|solution| {
+                    solution
+                        .into_iter()
+                        .map(|(variable, id)| {
+                            let decoded = dict.decode_term(id, &qt_store).unwrap_or_else(|| "unknown".to_string());
+                            (variable, decoded)
+                        })
+                        .collect()
+                })
+                .collect(),
+        )
     }
 
     fn parse_data(&mut self, data: &str) -> Vec<Triple> {
         self.item.parse_and_encode_ntriples(data)
     }
+
+    fn parse_turtle_data(&mut self, data: &str, prefixes: &HashMap<String, String>) -> Vec<Triple> {
+        self.item.prefixes.extend(prefixes.iter().map(|(prefix, iri)| (prefix.clone(), iri.clone())));
+        self.item.parse_and_encode_turtle(data)
+    }
 }
@@ -0,0 +1,250 @@
+/*
+ * Copyright © 2026 Volodymyr Kadzhaia
+ * Copyright © 2026 Pieter Bonte
+ * KU Leuven — Stream Intelligence Lab, Belgium
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this file,
+ * you can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use crate::rsp_engine::WindowResult;
+use shared::query::SyncPolicy;
+use std::collections::{BTreeSet, HashMap};
+
+/// Evaluations of the windows of a multi-window query, waiting to be joined.
+///
+/// Every evaluation carries its tick: the close time of the window it ran on.
+/// Under the Wait policy a join at tick `t` combines, per window, the latest
+/// evaluation at or before `t`, so a 10s window and a 5s window are joined on
+/// what each of them held at `t`. The join is only emitted once every window
+/// has evaluated since the previous join and has moved past `t`.
+pub struct WindowSync {
+    window_iris: Vec<String>,
+    /// Evaluations newer than the last join, per window, in tick order
+    pending: HashMap<String, Vec<WindowResult>>,
+    /// Most recent evaluation of each window
+    latest: HashMap<String, WindowResult>,
+    /// Time up to which each window has reported all of its evaluations
+    progress: HashMap<String, usize>,
+    last_joined: Option<usize>,
+    finished: bool,
+}
+
+impl WindowSync {
+    pub fn new(window_iris: Vec<String>) -> Self {
+        WindowSync {
+            window_iris,
+            pending: HashMap::new(),
+            latest: HashMap::new(),
+            progress: HashMap::new(),
+            last_joined: None,
+            finished: false,
+        }
+    }
+
+    /// Record an evaluation. A window reports its ticks in order, so it has
+    /// also progressed up to the evaluation's tick
+    pub fn record(&mut self, result: WindowResult) {
+        self.advance(&result.window_iri, result.tick);
+        self.latest.insert(result.window_iri.clone(), result.clone());
+        self.pending
+            .entry(result.window_iri.clone())
+            .or_default()
+            .push(result);
+    }
+
+    /// Note that `window_iri` has received an event at `ts`: every window of
+    /// it closing at or before `ts` has been evaluated
+    pub fn advance(&mut self, window_iri: &str, ts: usize) {
+        let progress = self.progress.entry(window_iri.to_string()).or_insert(0);
+        *progress = (*progress).max(ts);
+    }
+
+    /// No more events arrive, so every pending tick can be decided
+    pub fn finish(&mut self) {
+        self.finished = true;
+    }
+
+    /// Whether an evaluation arrived since the last join
+    pub fn has_pending(&self) -> bool {
+        self.pending.values().any(|results| !results.is_empty())
+    }
+
+    /// The joins that are due under `policy`, in tick order, as the
+    /// evaluation each window contributes.
+    /// Cross-window SDS+ reasoning expires triples by their own timestamps, so
+    /// there Wait joins the latest evaluations once every window has fired
+    pub fn take_due(
+        &mut self,
+        policy: &SyncPolicy,
+        cross_window: bool,
+    ) -> Vec<HashMap<String, WindowResult>> {
+        match policy {
+            SyncPolicy::Steal => self.take_latest().into_iter().collect(),
+            SyncPolicy::Wait | SyncPolicy::Timeout { .. } if cross_window => {
+                let all_fired = self.window_iris.iter().all(|iri| {
+                    self.pending
+                        .get(iri)
+                        .is_some_and(|results| !results.is_empty())
+                });
+                if all_fired {
+                    self.skip_pending();
+                    vec![self.latest.clone()]
+                } else {
+                    Vec::new()
+                }
+            }
+            SyncPolicy::Wait | SyncPolicy::Timeout { .. } => self.take_aligned(),
+        }
+    }
+
+    /// The latest evaluation of every window, when one arrived since the last
+    /// join and every window has evaluated at least once
+    pub fn take_latest(&mut self) -> Option<HashMap<String, WindowResult>> {
+        if !self.has_pending() || self.latest.len() < self.window_iris.len() {
+            return None;
+        }
+        self.skip_pending();
+        Some(self.latest.clone())
+    }
+
+    /// Give up on the evaluations waiting for a join
+    pub fn skip_pending(&mut self) {
+        if let Some(tick) = self.pending.values().flatten().map(|result| result.tick).max() {
+            self.last_joined = Some(self.last_joined.map_or(tick, |last| last.max(tick)));
+        }
+        self.pending.clear();
+    }
+
+    fn take_aligned(&mut self) -> Vec<HashMap<String, WindowResult>> {
+        // Ticks beyond the slowest window may still get evaluations of it
+        let horizon = if self.finished {
+            usize::MAX
+        } else {
+            self.window_iris
+                .iter()
+                .map(|iri| self.progress.get(iri).copied().unwrap_or(0))
+                .min()
+                .unwrap_or(0)
+        };
+        let ticks: BTreeSet<usize> = self
+            .pending
+            .values()
+            .flatten()
+            .map(|result| result.tick)
+            .filter(|tick| *tick <= horizon)
+            .collect();
+
+        let mut joins = Vec::new();
+        for tick in ticks {
+            let join: Option<HashMap<String, WindowResult>> = self
+                .window_iris
+                .iter()
+                .map(|iri| {
+                    let result = self.pending.get(iri)?.iter().rev().find(|r| r.tick <= tick)?;
+                    Some((iri.clone(), result.clone()))
+                })
+                .collect();
+            if let Some(join) = join {
+                joins.push(join);
+                self.last_joined = Some(tick);
+                for results in self.pending.values_mut() {
+                    results.retain(|result| result.tick > tick);
+                }
+            }
+        }
+
+        // An evaluation followed by a later one within the horizon is never joined
+        for results in self.pending.values_mut() {
+            if let Some(newest) = results.iter().rposition(|result| result.tick <= horizon) {
+                results.drain(..newest);
+            }
+        }
+        joins
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn evaluation(window_iri: &str, tick: usize) -> WindowResult {
+        WindowResult {
+            window_iri: window_iri.to_string(),
+            results: Vec::new(),
+            timestamp: tick.saturating_sub(1),
+            tick,
+            raw_triples: Vec::new(),
+        }
+    }
+
+    fn ticks(joins: &[HashMap<String, WindowResult>]) -> Vec<Vec<(String, usize)>> {
+        joins
+            .iter()
+            .map(|join| {
+                let mut ticks: Vec<(String, usize)> =
+                    join.iter().map(|(iri, r)| (iri.clone(), r.tick)).collect();
+                ticks.sort();
+                ticks
+            })
+            .collect()
+    }
+
+    #[test]
+    fn wait_joins_evaluations_of_the_same_tick() {
+        let mut sync = WindowSync::new(vec!["w10".to_string(), "w5".to_string()]);
+        sync.record(evaluation("w5", 5));
+        sync.advance("w5", 5);
+        sync.advance("w10", 5);
+        assert!(sync.take_due(&SyncPolicy::Wait, false).is_empty());
+
+        // w10 reports tick 10 before w5 has seen time 10
+        sync.record(evaluation("w10", 10));
+        assert!(sync.take_due(&SyncPolicy::Wait, false).is_empty());
+
+        sync.record(evaluation("w5", 10));
+        let joins = sync.take_due(&SyncPolicy::Wait, false);
+        assert_eq!(
+            ticks(&joins),
+            vec![vec![("w10".to_string(), 10), ("w5".to_string(), 10)]]
+        );
+
+        // Tick 15 only has a w5 evaluation
+        sync.record(evaluation("w5", 15));
+        sync.advance("w10", 16);
+        assert!(sync.take_due(&SyncPolicy::Wait, false).is_empty());
+    }
+
+    #[test]
+    fn finish_joins_windows_whose_streams_stopped() {
+        let mut sync = WindowSync::new(vec!["a".to_string(), "b".to_string()]);
+        sync.record(evaluation("a", 2));
+        sync.record(evaluation("a", 4));
+        sync.record(evaluation("b", 10));
+        assert!(sync.take_due(&SyncPolicy::Wait, false).is_empty());
+
+        sync.finish();
+        let joins = sync.take_due(&SyncPolicy::Wait, false);
+        assert_eq!(
+            ticks(&joins),
+            vec![vec![("a".to_string(), 4), ("b".to_string(), 10)]]
+        );
+        assert!(!sync.has_pending());
+    }
+
+    #[test]
+    fn steal_joins_the_latest_evaluations() {
+        let mut sync = WindowSync::new(vec!["a".to_string(), "b".to_string()]);
+        sync.record(evaluation("a", 2));
+        assert!(sync.take_due(&SyncPolicy::Steal, false).is_empty());
+        sync.record(evaluation("b", 20));
+        sync.record(evaluation("b", 22));
+        let joins = sync.take_due(&SyncPolicy::Steal, false);
+        assert_eq!(
+            ticks(&joins),
+            vec![vec![("a".to_string(), 2), ("b".to_string(), 22)]]
+        );
+        assert!(sync.take_due(&SyncPolicy::Steal, false).is_empty());
+    }
+}
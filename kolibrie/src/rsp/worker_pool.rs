@@ -0,0 +1,120 @@
+/*
+ * Copyright © 2026 Volodymyr Kadzhaia
+ * Copyright © 2026 Pieter Bonte
+ * KU Leuven — Stream Intelligence Lab, Belgium
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this file,
+ * you can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use crate::utils;
+use crossbeam::channel::{unbounded, Sender};
+use std::thread::{self, JoinHandle};
+
+type Job = Box<dyn FnOnce() + Send + 'static>;
+
+/// Fixed set of threads that evaluate windows in `OperationMode::MultiThread`.
+///
+/// Jobs are routed by key: every job submitted under the same key runs on the
+/// same worker, in submission order. The engine keys jobs by window, so the
+/// evaluations of one window never overlap or reorder, while different windows
+/// run side by side.
+pub struct WorkerPool {
+    /// `None` asks the worker to exit once the jobs queued before it are done
+    senders: Vec<Sender<Option<Job>>>,
+    handles: Vec<JoinHandle<()>>,
+}
+
+impl WorkerPool {
+    pub fn new(size: usize) -> Self {
+        let size = size.max(1);
+        let mut senders = Vec::with_capacity(size);
+        let mut handles = Vec::with_capacity(size);
+        for _ in 0..size {
+            let (sender, receiver) = unbounded::<Option<Job>>();
+            senders.push(sender);
+            handles.push(thread::spawn(move || {
+                while let Ok(Some(job)) = receiver.recv() {
+                    job();
+                }
+            }));
+        }
+        WorkerPool { senders, handles }
+    }
+
+    /// One worker per key, up to `utils::num_threads`
+    pub fn for_keys(keys: usize) -> Self {
+        Self::new(keys.min(utils::num_threads()))
+    }
+
+    pub fn size(&self) -> usize {
+        self.senders.len()
+    }
+
+    /// A handle that queues jobs for `key` without borrowing the pool
+    pub fn submitter(&self, key: usize) -> JobSubmitter {
+        JobSubmitter {
+            sender: self.senders[key % self.senders.len()].clone(),
+        }
+    }
+
+    /// Wait until every queued job has run, then stop the workers
+    pub fn join(&mut self) {
+        for sender in &self.senders {
+            let _ = sender.send(None);
+        }
+        for handle in self.handles.drain(..) {
+            let _ = handle.join();
+        }
+    }
+}
+
+impl Drop for WorkerPool {
+    fn drop(&mut self) {
+        // Workers of a pool that was never joined exit after their queued jobs
+        for sender in &self.senders {
+            let _ = sender.send(None);
+        }
+    }
+}
+
+/// Queues jobs on the worker a `WorkerPool` assigned to one key
+#[derive(Clone)]
+pub struct JobSubmitter {
+    sender: Sender<Option<Job>>,
+}
+
+impl JobSubmitter {
+    /// Queue `job`; it is dropped without running when the pool has been joined
+    pub fn submit(&self, job: impl FnOnce() + Send + 'static) {
+        let _ = self.sender.send(Some(Box::new(job)));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn jobs_with_the_same_key_run_in_order() {
+        let mut pool = WorkerPool::new(3);
+        let seen = Arc::new(Mutex::new(Vec::new()));
+        for key in 0..3 {
+            let submitter = pool.submitter(key);
+            for i in 0..100 {
+                let seen = Arc::clone(&seen);
+                submitter.submit(move || seen.lock().unwrap().push((key, i)));
+            }
+        }
+        pool.join();
+
+        let seen = seen.lock().unwrap();
+        assert_eq!(seen.len(), 300);
+        for key in 0..3 {
+            let order: Vec<usize> = seen.iter().filter(|(k, _)| *k == key).map(|(_, i)| *i).collect();
+            assert_eq!(order, (0..100).collect::<Vec<_>>());
+        }
+    }
+}
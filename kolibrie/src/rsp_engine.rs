@@ -7,10 +7,72 @@
 * you can obtain one at [https://mozilla.org/MPL/2.0/](https://mozilla.org/MPL/2.0/).
 */
 
+//! RDF stream processing in-process, without the HTTP server.
+//!
+//! An [`RSPBuilder`] turns an RSP-QL query into an [`RSPEngine`]: each
+//! `FROM NAMED WINDOW` becomes a window over a stream, evaluated by an R2R
+//! operator such as [`SimpleR2R`] whenever the window closes. Events are parsed
+//! with [`RSPEngine::parse_data`] and pushed with [`RSPEngine::add_to_stream`];
+//! every result row, after the query's RSTREAM/ISTREAM/DSTREAM operator, is
+//! handed to the [`ResultConsumer`]. [`RSPEngine::stop`] evaluates the windows
+//! that are still open.
+//!
+//! Counting the readings of a tumbling window of 10 time units:
+//!
+//! ```
+//! use kolibrie::rsp_engine::{OperationMode, RSPBuilder, RSPEngine, ResultConsumer, SimpleR2R};
+//! use shared::triple::Triple;
+//! use std::sync::{Arc, Mutex};
+//!
+//! // The consumer is called once per result row
+//! let rows = Arc::new(Mutex::new(Vec::new()));
+//! let sink = Arc::clone(&rows);
+//! let consumer = ResultConsumer {
+//!     function: Arc::new(move |row: Vec<(String, String)>| sink.lock().unwrap().push(row)),
+//! };
+//!
+//! let mut engine: RSPEngine<Triple, Vec<(String, String)>> = RSPBuilder::new()
+//!     .add_rsp_ql_query(
+//!         r#"
+//!         REGISTER RSTREAM <http://example.org/out> AS
+//!         SELECT ?reading
+//!         FROM NAMED WINDOW :w ON <http://example.org/sensors> [RANGE 10 STEP 10]
+//!         WHERE { WINDOW :w { ?reading a <http://example.org/Reading> . } }
+//!         "#,
+//!     )
+//!     .add_consumer(consumer)
+//!     .add_r2r(Box::new(SimpleR2R::new()))
+//!     .set_operation_mode(OperationMode::SingleThread)
+//!     .build()
+//!     .expect("valid RSP-QL query");
+//!
+//! // One reading per time unit
+//! fn push(engine: &mut RSPEngine<Triple, Vec<(String, String)>>, ts: usize) {
+//!     let event = format!("<http://example.org/reading{}> a <http://example.org/Reading> .", ts);
+//!     for triple in engine.parse_data(&event) {
+//!         engine.add_to_stream("http://example.org/sensors", triple, ts);
+//!     }
+//! }
+//! for ts in 0..10 {
+//!     push(&mut engine, ts);
+//! }
+//! assert!(rows.lock().unwrap().is_empty(), "the first window is still open");
+//!
+//! // The reading at 10 closes the window [0, 10)
+//! push(&mut engine, 10);
+//! assert_eq!(rows.lock().unwrap().len(), 10);
+//!
+//! // Stopping evaluates the window [10, 20), which holds one reading
+//! engine.stop();
+//! assert_eq!(rows.lock().unwrap().len(), 11);
+//! ```
+
 use crate::rsp::r2r::R2ROperator;
 use crate::rsp::r2s::Relation2StreamOperator;
 use crate::rsp::s2r::{ContentContainer, ReportStrategy, Tick};
 use crate::rsp::window_runner::{WindowRunner, WindowSpec};
+use crate::rsp::window_sync::WindowSync;
+use crate::rsp::worker_pool::WorkerPool;
 
 #[cfg(not(test))]
 use log::{debug, error}; // Use log crate when building application
@@ -19,16 +81,17 @@ use shared::rule::Rule;
 use std::collections::HashMap;
 use std::fmt::Debug;
 use std::hash::Hash;
-use crossbeam::channel::{unbounded, RecvTimeoutError, Receiver, Sender};
+use crossbeam::channel::{after, never, select, unbounded, Receiver, Sender};
 use std::collections::HashSet;
 use std::sync::{Arc, Mutex};
-use std::thread;
+use std::thread::{self, JoinHandle};
 use std::time::Instant;
 #[cfg(test)]
 use std::{println as debug, println as error};
 
+use crate::custom_error::SparqlError;
 use crate::parser::process_rule_definition;
-use crate::sparql_database::SparqlDatabase;
+use crate::sparql_database::{SharedSparqlDatabase, SparqlDatabase};
 use crate::streamertail_optimizer::{ExecutionEngine, LogicalOperator, PhysicalOperator};
 use datalog::cross_window_sds::{
     all_component_iris, sds_with_expiry_to_external, Sds, WindowData, WindowedTriple,
@@ -46,6 +109,27 @@ use std::sync::RwLock;
 pub use crate::rsp::builder::{RSPBuilder, RSPQueryConfig};
 pub use crate::rsp::simple_r2r::SimpleR2R;
 
+/// Where windows are evaluated.
+///
+/// `SingleThread` evaluates a window inside the `add_to_stream` call that
+/// closes it and joins windows on later calls.
+///
+/// `MultiThread` hands evaluations to a worker pool with one worker per
+/// window, up to the number of cores, so ingestion never waits for a query.
+/// Multi-window and static-data joins run on a separate coordinator thread.
+/// The consumer is called from those threads, under these guarantees:
+/// - a window's evaluations run one at a time, in tick order, so a
+///   single-window query delivers its results in tick order
+/// - different windows evaluate concurrently and finish in any order. Under
+///   the Wait policy the coordinator still joins them on aligned ticks and
+///   emits the joins in tick order; Steal joins whatever is latest
+/// - windows share the R2R store, so their queries take turns on it
+/// - `stop()` returns once every evaluation, including the final flush, has
+///   been delivered
+///
+/// `add_to_stream` takes `&mut self`, so several producer threads share the
+/// engine behind a lock. Windows see events in the order the producers take
+/// that lock, and events older than the window's clock are not re-evaluated.
 #[derive(Clone, Copy)]
 pub enum OperationMode {
     SingleThread,
@@ -64,6 +148,42 @@ pub enum CrossWindowReasoningMode {
     Naive,
 }
 
+/// Reasons `RSPBuilder::build` can refuse to create an engine
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RspBuildError {
+    /// The RSP-QL query (or the N3 or cross-window rules) could not be parsed.
+    /// `clause` names the clause the error falls in and `position` is the
+    /// 1-based (line, column) in the query text when it is known
+    ParseError {
+        clause: String,
+        position: Option<(usize, usize)>,
+        message: String,
+    },
+    /// The query or the builder configuration uses something the engine does not support
+    UnsupportedFeature(String),
+    /// A component the engine cannot run without (RSP-QL query, R2R operator) was not provided
+    MissingConsumer(String),
+}
+
+impl std::fmt::Display for RspBuildError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            RspBuildError::ParseError { clause, position: Some((line, column)), message } => write!(
+                f,
+                "Invalid {} clause at line {}, column {}: {}",
+                clause, line, column, message
+            ),
+            RspBuildError::ParseError { clause, position: None, message } => {
+                write!(f, "Invalid {}: {}", clause, message)
+            }
+            RspBuildError::UnsupportedFeature(feature) => write!(f, "Unsupported feature: {}", feature),
+            RspBuildError::MissingConsumer(component) => write!(f, "Missing {}", component),
+        }
+    }
+}
+
+impl std::error::Error for RspBuildError {}
+
 /// Window configuration extracted from parsed RSP-QL query
 #[derive(Debug, Clone)]
 pub struct RSPWindow {
@@ -89,11 +209,16 @@ pub struct WindowResult {
     pub window_iri: String,
     pub results: Vec<HashMap<String, String>>, // Variable bindings
     pub timestamp: usize,
+    /// Close time of the evaluated window; joins across windows align on it
+    pub tick: usize,
     pub raw_triples: Vec<(Triple, u64)>,
 }
 
 const CROSS_WINDOW_STATIC_IRI: &str = "urn:kolibrie:static:";
 
+/// Receives the engine's output, one result row per call. Called from the
+/// thread that pushes events in `SingleThread` mode and from worker threads in
+/// `MultiThread` mode, hence `Send + Sync`
 pub struct ResultConsumer<I> {
     pub function: Arc<dyn Fn(I) -> () + Send + Sync>,
 }
@@ -101,7 +226,7 @@ pub struct ResultConsumer<I> {
 /// Macro to generate the window processing logic
 macro_rules! create_window_processor {
     ($window_iri:expr, $query:expr, $query_execution_mode:expr,
-     $r2r_store:expr, $has_joins:expr, $cross_window_enabled:expr,
+     $r2r_store:expr, $has_joins:expr, $cross_window_enabled:expr, $incremental:expr,
      $window_result_sender:expr, $r2s_consumer_func:expr) => {{
         let mut prev_window_triples: Vec<I> = Vec::new();
         move |content: ContentContainer<I>| {
@@ -111,6 +236,7 @@ macro_rules! create_window_processor {
             );
 
             let ts = content.get_last_timestamp_changed();
+            let tick = content.get_window_close();
             if $cross_window_enabled {
                 let raw_triples: Vec<(Triple, u64)> = content
                     .iter_with_timestamps()
@@ -125,6 +251,7 @@ macro_rules! create_window_processor {
                     window_iri: $window_iri.clone(),
                     results: Vec::new(),
                     timestamp: ts,
+                    tick,
                     raw_triples,
                 };
 
@@ -136,22 +263,48 @@ macro_rules! create_window_processor {
 
             let mut store = $r2r_store.lock().unwrap();
 
-            // Evict triples from the previous firing of this window
-            for t in &prev_window_triples {
-                store.remove(t);
-            }
-            prev_window_triples.clear();
+            if $incremental {
+                // Only the triples that left or entered the window change the store
+                let current: Vec<I> = content.into_iter().collect();
+                let current_set: HashSet<&I> = current.iter().collect();
+                let previous_set: HashSet<&I> = prev_window_triples.iter().collect();
+                for t in prev_window_triples.iter().filter(|t| !current_set.contains(t)) {
+                    store.remove(t);
+                }
+                for t in current.iter().filter(|t| !previous_set.contains(t)) {
+                    store.add(t.clone());
+                }
+                prev_window_triples = current;
+            } else {
+                // Evict triples from the previous firing of this window
+                for t in &prev_window_triples {
+                    store.remove(t);
+                }
+                prev_window_triples.clear();
 
-            // Add current window triples and track them for next eviction
-            for t in content.into_iter() {
-                prev_window_triples.push(t.clone());
-                store.add(t);
+                // Add current window triples and track them for next eviction
+                for t in content.into_iter() {
+                    prev_window_triples.push(t.clone());
+                    store.add(t);
+                }
             }
 
             // Run forward-chaining inference to materialise derived facts
             store.materialize();
 
-            let results = store.execute_query(&$query);
+            let incremental_results = if $incremental {
+                store.execute_query_incremental(&$window_iri, &$query).map(Ok)
+            } else {
+                None
+            };
+            let results = match incremental_results.unwrap_or_else(|| store.execute_query(&$query)) {
+                Ok(results) => results,
+                Err(e) => {
+                    // Passing on no rows would read as an empty window to R2S
+                    error!("Query over window {} failed, skipping this evaluation: {}", $window_iri, e);
+                    return;
+                }
+            };
             debug!("Got # results {} for window {}", results.len(), $window_iri);
 
             // Release lock early to reduce contention
@@ -174,6 +327,7 @@ macro_rules! create_window_processor {
                     window_iri: $window_iri.clone(),
                     results: mapped_results,
                     timestamp: ts,
+                    tick,
                     raw_triples: Vec::new(),
                 };
 
@@ -192,25 +346,19 @@ macro_rules! register_window {
     (SingleThread, $window:expr, $processor:expr) => {
         $window.register_callback(Box::new($processor));
     };
-    (MultiThread, $window:expr, $processor:expr, $window_iri:expr) => {{
-        let receiver = $window.register();
-        thread::spawn(move || {
-            loop {
-                match receiver.recv() {
-                    Ok(content) => {
-                        $processor(content);
-                    }
-                    Err(_) => {
-                        debug!("Shutting down window {}!", $window_iri);
-                        break;
-                    }
-                }
-            }
-            debug!("Shutdown complete for window {}!", $window_iri);
-        });
+    (MultiThread, $window:expr, $processor:expr, $submitter:expr) => {{
+        // The window's evaluations all go to one worker, which runs them in order
+        let processor = Arc::new(Mutex::new($processor));
+        let submitter = $submitter;
+        $window.register_callback(Box::new(move |content| {
+            let processor = Arc::clone(&processor);
+            submitter.submit(move || (processor.lock().unwrap())(content));
+        }));
     }};
 }
 
+/// A running RSP-QL query: its windows, the R2R operator evaluating them and
+/// the consumer of the results. Built with [`RSPBuilder`]
 pub struct RSPEngine<I, O>
 where
     I: Eq + PartialEq + Clone + Debug + Hash + Send,
@@ -227,12 +375,16 @@ where
     window_result_receiver: Receiver<WindowResult>,
     // RSP-QL Query Plan using Volcano optimizer
     rsp_query_plan: RSPQueryPlan,
-    /// Latest materialized results per window (replace semantics); SingleThread only.
-    single_thread_last_materialized: Arc<Mutex<HashMap<String, Vec<HashMap<String, String>>>>>,
+    /// Window evaluations waiting to be joined; SingleThread only.
+    single_thread_sync: Mutex<WindowSync>,
+    /// Evaluates windows; MultiThread only.
+    worker_pool: Option<WorkerPool>,
+    /// Finish signal and thread of the MultiThread join coordinator
+    coordinator: Option<(Sender<()>, JoinHandle<()>)>,
     /// Synchronization policy governing multi-window coordination.
     sync_policy: SyncPolicy,
     /// Separate store for static background triples (never touched by window processors).
-    static_db: Arc<Mutex<SparqlDatabase>>,
+    static_db: SharedSparqlDatabase,
     /// R2S operator for stream-type filtering (RSTREAM/ISTREAM/DSTREAM).
     r2s_operator: Arc<Mutex<Relation2StreamOperator<O>>>,
     /// Opt-in cross-window SDS+ reasoning state.
@@ -244,6 +396,11 @@ where
     cross_window_dictionary: Option<Arc<RwLock<Dictionary>>>,
     cross_window_output_iris: Arc<Vec<String>>,
     cross_window_reasoning_mode: CrossWindowReasoningMode,
+    /// Prefixes registered with `RSPBuilder::add_prefix`; when any are
+    /// present, `parse_data` reads events as Turtle
+    prefix_registry: HashMap<String, String>,
+    /// Set by `RSPBuilder::set_incremental_evaluation`
+    incremental_evaluation: bool,
 }
 
 impl<I, O> RSPEngine<I, O>
@@ -266,7 +423,9 @@ where
         sparql_rules: Vec<String>,
         cross_window_rules: Option<&str>,
         cross_window_reasoning_mode: CrossWindowReasoningMode,
-    ) -> Result<RSPEngine<I, O>, String> {
+        prefix_registry: HashMap<String, String>,
+        incremental_evaluation: bool,
+    ) -> Result<RSPEngine<I, O>, RspBuildError> {
         let mut store = r2r;
 
         // The PhysicalOperator plans created in `rsp_query_plan` contain integer IDs (constants)
@@ -297,7 +456,7 @@ where
         if let Some(d) = &shared_dict {
             static_sdb.dictionary = Arc::clone(d);
         }
-        let static_db = Arc::new(Mutex::new(static_sdb));
+        let static_db = Arc::new(RwLock::new(static_sdb));
 
         let mut parsed_cross_window_rules = Vec::new();
         let mut cross_window_context = None;
@@ -306,8 +465,10 @@ where
             let dict = shared_dict
                 .as_ref()
                 .ok_or_else(|| {
-                    "Cross-window SDS+ reasoning requires a SimpleR2R shared dictionary"
-                        .to_string()
+                    RspBuildError::UnsupportedFeature(
+                        "Cross-window SDS+ reasoning requires a SimpleR2R shared dictionary"
+                            .to_string(),
+                    )
                 })?;
             let mut reasoner = datalog::reasoning::Reasoner::new();
             reasoner.dictionary = Arc::clone(dict);
@@ -317,7 +478,11 @@ where
                 .map(|w| (w.window_iri.clone(), w.width as u64))
                 .collect();
             let (rules, context) = parse_n3_rules_for_sds(n3_rules, &mut reasoner, window_widths)
-                .map_err(|e| format!("Failed to parse cross-window N3 rules: {}", e))?;
+                .map_err(|e| RspBuildError::ParseError {
+                    clause: "cross-window N3 rules".to_string(),
+                    position: None,
+                    message: e.to_string(),
+                })?;
             let window_iris: HashSet<String> = query_config
                 .windows
                 .iter()
@@ -339,10 +504,11 @@ where
             _ => (),
         }
 
-        match store.load_rules(rules) {
-            Ok(_) => debug!("Rules loaded successfully"),
-            Err(e) => error!("Failed to load rules: {:?}", e),
-        }
+        store.load_rules(rules).map_err(|e| RspBuildError::ParseError {
+            clause: "N3 rules".to_string(),
+            position: None,
+            message: e.to_string(),
+        })?;
 
         if !reasoning_rules.is_empty() {
             if let Some(simple_r2r) = store.as_any_mut().downcast_mut::<SimpleR2R>() {
@@ -400,7 +566,11 @@ where
             window_result_sender: result_sender,
             window_result_receiver: result_receiver,
             rsp_query_plan,
-            single_thread_last_materialized: Arc::new(Mutex::new(HashMap::new())),
+            single_thread_sync: Mutex::new(WindowSync::new(
+                query_config.windows.iter().map(|w| w.window_iri.clone()).collect(),
+            )),
+            worker_pool: None,
+            coordinator: None,
             sync_policy,
             static_db,
             r2s_operator,
@@ -412,6 +582,8 @@ where
             cross_window_dictionary: shared_dict,
             cross_window_output_iris: Arc::new(cross_window_output_iris),
             cross_window_reasoning_mode,
+            prefix_registry,
+            incremental_evaluation,
         };
 
         match operation_mode {
@@ -422,7 +594,7 @@ where
                         || engine.windows.len() > 1
                         || engine.rsp_query_plan.static_data_plan.is_some();
                     if has_joins {
-                        engine.start_cross_window_coordinator();
+                        engine.coordinator = Some(engine.start_cross_window_coordinator());
                     }
                 }
             }
@@ -436,15 +608,18 @@ where
         let has_joins = self.cross_window_enabled
             || self.windows.len() > 1
             || self.rsp_query_plan.static_data_plan.is_some();
+        if matches!(operation_mode, OperationMode::MultiThread) {
+            self.worker_pool = Some(WorkerPool::for_keys(self.windows.len()));
+        }
 
         for (window_idx, window) in self.windows.iter_mut().enumerate() {
             let query = self.rsp_query_plan.window_plans[window_idx].clone();
             let window_iri = self.window_configs[window_idx].window_iri.clone();
-            let window_iri_for_thread = window_iri.clone(); // Clone for MultiThread usage
             let query_execution_mode = self.query_execution_mode;
             let window_result_sender = self.window_result_sender.clone();
             let r2r_store = self.r2r.clone();
             let cross_window_enabled = self.cross_window_enabled;
+            let incremental_evaluation = self.incremental_evaluation;
 
             let r2s_consumer_func: Arc<dyn Fn(Vec<O>, usize) + Send + Sync> = if has_joins {
                 Arc::new(|_, _| {})
@@ -460,13 +635,14 @@ where
             };
 
             // Create processor using macro
-            let mut processor = create_window_processor!(
+            let processor = create_window_processor!(
                 window_iri,
                 query,
                 query_execution_mode,
                 r2r_store,
                 has_joins,
                 cross_window_enabled,
+                incremental_evaluation,
                 window_result_sender,
                 r2s_consumer_func
             );
@@ -477,7 +653,9 @@ where
                     register_window!(SingleThread, window, processor);
                 }
                 OperationMode::MultiThread => {
-                    register_window!(MultiThread, window, processor, window_iri_for_thread);
+                    if let Some(pool) = &self.worker_pool {
+                        register_window!(MultiThread, window, processor, pool.submitter(window_idx));
+                    }
                 }
             }
         }
@@ -485,13 +663,13 @@ where
 
     /// Start a coordinator thread that collects and joins results from multiple windows
     /// (and optionally joins with static background data), respecting `sync_policy`.
-    fn start_cross_window_coordinator(&self)
+    /// Sending on the returned channel makes it join what is pending and exit.
+    fn start_cross_window_coordinator(&self) -> (Sender<()>, JoinHandle<()>)
     where
         O: From<Vec<(String, String)>>,
     {
         let receiver = self.window_result_receiver.clone();
         let consumer = self.r2s_consumer.function.clone();
-        let num_windows = self.windows.len();
         let static_data_plan = self.rsp_query_plan.static_data_plan.clone();
         let static_db = self.static_db.clone();
         let sync_policy = self.sync_policy.clone();
@@ -505,15 +683,48 @@ where
         let cross_window_reasoning_mode = self.cross_window_reasoning_mode;
         let window_configs = self.window_configs.clone();
         let window_plans = self.rsp_query_plan.window_plans.clone();
+        let (finish_sender, finish_receiver) = unbounded::<()>();
 
-        thread::spawn(move || {
-            // Latest results per window (replace semantics)
-            let mut last_materialized: HashMap<String, Vec<HashMap<String, String>>> = HashMap::new();
-            // Windows that have fired since the last reset
-            let mut cycle_triggered: HashSet<String> = HashSet::new();
-            // When the first window fired in the current cycle
+        let handle = thread::spawn(move || {
+            let mut window_sync =
+                WindowSync::new(window_configs.iter().map(|w| w.window_iri.clone()).collect());
+            // When the oldest evaluation still waiting for a join arrived
             let mut cycle_start: Option<Instant> = None;
-            let mut max_ts: usize = 0;
+
+            let emit_join = |join: HashMap<String, WindowResult>| {
+                let ts = join.values().map(|r| r.timestamp).max().unwrap_or(0);
+                if cross_window_enabled {
+                    if let Some(dict) = &cross_window_dictionary {
+                        {
+                            let mut latest = cross_window_latest_contents.lock().unwrap();
+                            for (window_iri, result) in &join {
+                                latest.insert(window_iri.clone(), result.raw_triples.clone());
+                            }
+                        }
+                        emit_cross_window_results(
+                            &window_configs,
+                            &window_plans,
+                            &static_data_plan,
+                            &static_db,
+                            &r2s_operator,
+                            ts,
+                            &consumer,
+                            &cross_window_rules,
+                            &cross_window_sds_plus,
+                            &cross_window_latest_contents,
+                            dict,
+                            &cross_window_output_iris,
+                            cross_window_reasoning_mode,
+                        );
+                    }
+                } else {
+                    let materialized: HashMap<String, Vec<HashMap<String, String>>> = join
+                        .into_iter()
+                        .map(|(window_iri, result)| (window_iri, result.results))
+                        .collect();
+                    emit_results(&materialized, &static_data_plan, &static_db, &r2s_operator, ts, &consumer);
+                }
+            };
 
             loop {
                 // Compute recv timeout when policy has a finite deadline
@@ -524,173 +735,110 @@ where
                     _ => None,
                 };
 
-                // Receive next window result (or timeout/disconnect)
-                let maybe_result: Option<WindowResult> = if let Some(remaining) = timeout_remaining {
-                    match receiver.recv_timeout(remaining) {
-                        Ok(r) => Some(r),
-                        Err(RecvTimeoutError::Timeout) => {
-                            // Deadline elapsed
-                            if !cycle_triggered.is_empty() {
-                                match &sync_policy {
-                                    SyncPolicy::Timeout { fallback: Fallback::Steal, .. } => {
-                                        if last_materialized.len() == num_windows {
-                                            if cross_window_enabled {
-                                                if let Some(dict) = &cross_window_dictionary {
-                                                    emit_cross_window_results(
-                                                        &window_configs,
-                                                        &window_plans,
-                                                        &static_data_plan,
-                                                        &static_db,
-                                                        &r2s_operator,
-                                                        max_ts,
-                                                        &consumer,
-                                                        &cross_window_rules,
-                                                        &cross_window_sds_plus,
-                                                        &cross_window_latest_contents,
-                                                        dict,
-                                                        &cross_window_output_iris,
-                                                        cross_window_reasoning_mode,
-                                                    );
-                                                }
-                                            } else {
-                                                emit_results(&last_materialized, &static_data_plan, &static_db, &r2s_operator, max_ts, &consumer);
-                                            }
-                                        }
-                                    }
-                                    SyncPolicy::Timeout { fallback: Fallback::Drop, .. } => {
-                                        // discard this cycle
-                                    }
-                                    _ => {}
-                                }
-                                cycle_triggered.clear();
-                                cycle_start = None;
-                                max_ts = 0;
+                let deadline = timeout_remaining.map_or_else(never, after);
+
+                // Receive next window result (or timeout/finish/disconnect)
+                let window_result = select! {
+                    recv(receiver) -> r => match r {
+                        Ok(r) => r,
+                        Err(_) => break,
+                    },
+                    recv(finish_receiver) -> _ => {
+                        // The engine stopped, so no evaluation is still on its way
+                        while let Ok(wr) = receiver.try_recv() {
+                            window_sync.record(wr);
+                        }
+                        window_sync.finish();
+                        for join in window_sync.take_due(&sync_policy, cross_window_enabled) {
+                            emit_join(join);
+                        }
+                        break;
+                    },
+                    recv(deadline) -> _ => {
+                        // Deadline elapsed
+                        if let SyncPolicy::Timeout { fallback: Fallback::Steal, .. } = &sync_policy {
+                            if let Some(join) = window_sync.take_latest() {
+                                emit_join(join);
                             }
-                            continue;
                         }
-                        Err(RecvTimeoutError::Disconnected) => break,
-                    }
-                } else {
-                    match receiver.recv() {
-                        Ok(r) => Some(r),
-                        Err(_) => break,
-                    }
+                        window_sync.skip_pending();
+                        cycle_start = None;
+                        continue;
+                    },
                 };
 
-                if let Some(window_result) = maybe_result {
-                    debug!(
-                        "Coordinator received {} results from window: {}",
-                        window_result.results.len(),
-                        window_result.window_iri
-                    );
+                debug!(
+                    "Coordinator received {} results from window: {} at tick {}",
+                    window_result.results.len(),
+                    window_result.window_iri,
+                    window_result.tick
+                );
+                window_sync.record(window_result);
 
-                    max_ts = max_ts.max(window_result.timestamp);
-                    if cross_window_enabled {
-                        cross_window_latest_contents.lock().unwrap().insert(
-                            window_result.window_iri.clone(),
-                            window_result.raw_triples.clone(),
-                        );
-                    }
-                    // Update last_materialized (replace)
-                    last_materialized.insert(
-                        window_result.window_iri.clone(),
-                        window_result.results.clone(),
-                    );
-                    if cycle_triggered.is_empty() {
-                        cycle_start = Some(Instant::now());
-                    }
-                    cycle_triggered.insert(window_result.window_iri.clone());
-
-                    // Drain any additional pending results
-                    while let Ok(wr) = receiver.try_recv() {
-                        max_ts = max_ts.max(wr.timestamp);
-                        if cross_window_enabled {
-                            cross_window_latest_contents
-                                .lock()
-                                .unwrap()
-                                .insert(wr.window_iri.clone(), wr.raw_triples.clone());
-                        }
-                        last_materialized.insert(wr.window_iri.clone(), wr.results.clone());
-                        cycle_triggered.insert(wr.window_iri.clone());
-                    }
+                // Drain any additional pending results
+                while let Ok(wr) = receiver.try_recv() {
+                    window_sync.record(wr);
+                }
 
-                    if cycle_triggered.len() == num_windows {
-                        // All windows fired this cycle
-                        if cross_window_enabled {
-                            if let Some(dict) = &cross_window_dictionary {
-                                emit_cross_window_results(
-                                    &window_configs,
-                                    &window_plans,
-                                    &static_data_plan,
-                                    &static_db,
-                                    &r2s_operator,
-                                    max_ts,
-                                    &consumer,
-                                    &cross_window_rules,
-                                    &cross_window_sds_plus,
-                                    &cross_window_latest_contents,
-                                    dict,
-                                    &cross_window_output_iris,
-                                    cross_window_reasoning_mode,
-                                );
-                            }
-                        } else {
-                            emit_results(&last_materialized, &static_data_plan, &static_db, &r2s_operator, max_ts, &consumer);
-                        }
-                        cycle_triggered.clear();
-                        cycle_start = None;
-                        max_ts = 0;
-                    } else {
-                        match &sync_policy {
-                            SyncPolicy::Steal => {
-                                // Emit immediately using stale data from non-firing windows
-                                if last_materialized.len() == num_windows {
-                                    if cross_window_enabled {
-                                        if let Some(dict) = &cross_window_dictionary {
-                                            emit_cross_window_results(
-                                                &window_configs,
-                                                &window_plans,
-                                                &static_data_plan,
-                                                &static_db,
-                                                &r2s_operator,
-                                                max_ts,
-                                                &consumer,
-                                                &cross_window_rules,
-                                                &cross_window_sds_plus,
-                                                &cross_window_latest_contents,
-                                                dict,
-                                                &cross_window_output_iris,
-                                                cross_window_reasoning_mode,
-                                            );
-                                        }
-                                    } else {
-                                        emit_results(&last_materialized, &static_data_plan, &static_db, &r2s_operator, max_ts, &consumer);
-                                    }
-                                }
-                                cycle_triggered.clear();
-                                cycle_start = None;
-                                max_ts = 0;
-                            }
-                            SyncPolicy::Wait | SyncPolicy::Timeout { .. } => {
-                                // Keep waiting for remaining windows
-                                debug!(
-                                    "Coordinator: waiting for more windows ({}/{}) — have: {:?}",
-                                    cycle_triggered.len(),
-                                    num_windows,
-                                    cycle_triggered.iter().collect::<Vec<_>>()
-                                );
-                            }
-                        }
-                    }
+                for join in window_sync.take_due(&sync_policy, cross_window_enabled) {
+                    emit_join(join);
+                }
+
+                if window_sync.has_pending() {
+                    debug!("Coordinator: waiting for more windows to reach the pending tick");
+                    cycle_start.get_or_insert_with(Instant::now);
+                } else {
+                    cycle_start = None;
                 }
             }
 
             debug!("Coordinator: shutdown complete");
         });
+        (finish_sender, handle)
     }
 
     /// Add data to appropriate window based on stream IRI
     pub fn add_to_stream(&mut self, stream_iri: &str, event_item: I, ts: usize) {
+        self.drain_single_thread_results();
+
+        for window_idx in self.windows_for_stream(stream_iri) {
+            if let Some(window) = self.windows.get_mut(window_idx) {
+                window.add_to_window(event_item.clone(), ts);
+            }
+            self.advance_window(window_idx, ts);
+        }
+    }
+
+    /// Async variant of `add_to_stream`. The windows are evaluated on tokio's
+    /// blocking thread pool with `engine` locked, as in `execute_query_async`,
+    /// so the calling executor thread stays free while they run.
+    #[cfg(feature = "async")]
+    pub async fn add_to_stream_async(engine: Arc<Mutex<Self>>, stream_iri: &str, event_item: I, ts: usize)
+    where
+        Self: Send,
+    {
+        let stream_iri = stream_iri.to_string();
+        let handle = tokio::task::spawn_blocking(move || {
+            engine.lock().unwrap().add_to_stream(&stream_iri, event_item, ts);
+        });
+        if let Err(err) = handle.await {
+            error!("Stream event task failed: {}", err);
+        }
+    }
+
+    /// Every evaluation of window `window_idx` up to `ts` has now been reported
+    fn advance_window(&mut self, window_idx: usize, ts: usize) {
+        if let Some(window_config) = self.window_configs.get(window_idx) {
+            self.single_thread_sync
+                .get_mut()
+                .unwrap()
+                .advance(&window_config.window_iri, ts);
+        }
+    }
+
+    /// In SingleThread mode, emit results of earlier window evaluations that
+    /// still need joining across windows or with static data
+    fn drain_single_thread_results(&mut self) {
         if matches!(self.operation_mode, OperationMode::SingleThread)
             && (self.cross_window_enabled
                 || self.windows.len() > 1
@@ -698,7 +846,10 @@ where
         {
             self.process_single_thread_window_results();
         }
+    }
 
+    /// Indices of the windows that consume events from `stream_iri`
+    fn windows_for_stream(&self, stream_iri: &str) -> Vec<usize> {
         fn normalize_stream_iri(s: &str) -> String {
             let s = s.trim();
             // Some callers might pass a full IRI in `<...>` form.
@@ -710,69 +861,57 @@ where
 
         let input_norm = normalize_stream_iri(stream_iri);
 
-        // Find windows that match this stream IRI
-        for (window_idx, window_config) in self.window_configs.iter().enumerate() {
-            // Variable stream (e.g. `?s`) matches any stream.
-            if window_config.stream_iri.starts_with('?') {
-                if let Some(window) = self.windows.get_mut(window_idx) {
-                    window.add_to_window(event_item.clone(), ts);
-                }
-                continue;
-            }
-
-            let cfg_norm = normalize_stream_iri(&window_config.stream_iri);
-            if cfg_norm == input_norm {
-                if let Some(window) = self.windows.get_mut(window_idx) {
-                    window.add_to_window(event_item.clone(), ts);
-                }
-            }
-        }
+        self.window_configs
+            .iter()
+            .enumerate()
+            .filter(|(_, window_config)| {
+                // Variable stream (e.g. `?s`) matches any stream.
+                window_config.stream_iri.starts_with('?')
+                    || normalize_stream_iri(&window_config.stream_iri) == input_norm
+            })
+            .map(|(window_idx, _)| window_idx)
+            .collect()
     }
 
     pub fn process_single_thread_window_results(&mut self)
     where
         O: From<Vec<(String, String)>>,
     {
+        // In MultiThread mode the coordinator thread owns the window results
+        if matches!(self.operation_mode, OperationMode::MultiThread) {
+            return;
+        }
         let consumer = self.r2s_consumer.function.clone();
-        let num_windows = self.windows.len();
-        let sync_policy = self.sync_policy.clone();
 
-        // Drain all pending channel results; update last_materialized with replace semantics.
-        let mut last_mat = self.single_thread_last_materialized.lock().unwrap();
-        let mut had_new_results = false;
-        let mut max_ts: usize = 0;
+        let mut window_sync = self.single_thread_sync.lock().unwrap();
         while let Ok(window_result) = self.window_result_receiver.try_recv() {
-            max_ts = max_ts.max(window_result.timestamp);
-            if self.cross_window_enabled {
-                self.cross_window_latest_contents.lock().unwrap().insert(
-                    window_result.window_iri.clone(),
-                    window_result.raw_triples.clone(),
-                );
-            }
-            let rows = last_mat
-                .entry(window_result.window_iri.clone())
-                .or_default();
-            rows.extend(window_result.results);
-            had_new_results = true;
+            window_sync.record(window_result);
         }
-
-        if !had_new_results {
-            return;
+        // Timeout has no wall-clock timer in a single-threaded context and is treated as Wait
+        let joins = window_sync.take_due(&self.sync_policy, self.cross_window_enabled);
+        if joins.is_empty() && window_sync.has_pending() {
+            debug!("SingleThread: waiting for all windows to reach the pending tick");
         }
+        drop(window_sync);
 
-        // Check whether to emit based on policy.
-        if last_mat.len() == num_windows {
-            debug!("SingleThread: all {} windows materialized, emitting", num_windows);
-            let static_data_plan = self.rsp_query_plan.static_data_plan.clone();
+        let static_data_plan = self.rsp_query_plan.static_data_plan.clone();
+        for join in joins {
+            let ts = join.values().map(|r| r.timestamp).max().unwrap_or(0);
             if self.cross_window_enabled {
                 if let Some(dict) = &self.cross_window_dictionary {
+                    {
+                        let mut latest = self.cross_window_latest_contents.lock().unwrap();
+                        for (window_iri, result) in &join {
+                            latest.insert(window_iri.clone(), result.raw_triples.clone());
+                        }
+                    }
                     emit_cross_window_results(
                         &self.window_configs,
                         &self.rsp_query_plan.window_plans,
                         &static_data_plan,
                         &self.static_db,
                         &self.r2s_operator,
-                        max_ts,
+                        ts,
                         &consumer,
                         &self.cross_window_rules,
                         &self.cross_window_sds_plus,
@@ -783,24 +922,12 @@ where
                     );
                 }
             } else {
-                emit_results(&*last_mat, &static_data_plan, &self.static_db, &self.r2s_operator, max_ts, &consumer);
-            }
-
-            match sync_policy {
-                // Wait: require all windows to fire again before next emission.
-                // Timeout: no wall-clock timer in single-threaded context; treat as Wait.
-                SyncPolicy::Wait | SyncPolicy::Timeout { .. } => {
-                    last_mat.clear();
-                }
-                // Steal: keep last_mat so stale data from non-firing windows is reused.
-                SyncPolicy::Steal => {}
+                let materialized: HashMap<String, Vec<HashMap<String, String>>> = join
+                    .into_iter()
+                    .map(|(window_iri, result)| (window_iri, result.results))
+                    .collect();
+                emit_results(&materialized, &static_data_plan, &self.static_db, &self.r2s_operator, ts, &consumer);
             }
-        } else {
-            debug!(
-                "SingleThread: waiting for more windows ({}/{})",
-                last_mat.len(),
-                num_windows
-            );
         }
     }
 
@@ -810,6 +937,9 @@ where
         for window in &mut self.windows {
             window.add_to_window(event_item.clone(), ts);
         }
+        for window_idx in 0..self.windows.len() {
+            self.advance_window(window_idx, ts);
+        }
     }
 
     pub fn stop(&mut self) {
@@ -817,13 +947,30 @@ where
             window.flush();
             window.stop();
         }
+        // The flushed windows are the last evaluations, so every pending tick can be joined
+        self.single_thread_sync.get_mut().unwrap().finish();
         if matches!(self.operation_mode, OperationMode::SingleThread) {
             self.process_single_thread_window_results();
         }
+        // MultiThread: let the workers deliver the flushed evaluations, then the coordinator
+        if let Some(pool) = &mut self.worker_pool {
+            pool.join();
+        }
+        if let Some((finish, handle)) = self.coordinator.take() {
+            let _ = finish.send(());
+            let _ = handle.join();
+        }
     }
 
+    /// Parse an event into stream items: N-Triples, or Turtle using the
+    /// registered prefixes once `RSPBuilder::add_prefix` was called
     pub fn parse_data(&mut self, data: &str) -> Vec<I> {
-        self.r2r.lock().unwrap().parse_data(data)
+        let mut r2r = self.r2r.lock().unwrap();
+        if self.prefix_registry.is_empty() {
+            r2r.parse_data(data)
+        } else {
+            r2r.parse_turtle_data(data, &self.prefix_registry)
+        }
     }
 
     /// Pre-populate the static background store with N-Triples data.
@@ -831,7 +978,7 @@ where
     /// leak into window query results.  They are only visible when `emit_results`
     /// joins the window output with the static-data plan.
     pub fn add_static_ntriples(&mut self, data: &str) {
-        let mut db = self.static_db.lock().unwrap();
+        let mut db = self.static_db.write().unwrap();
         db.parse_ntriples_and_add(data);
         db.get_or_build_stats();
         db.build_all_indexes();
@@ -864,7 +1011,7 @@ where
 fn emit_results<O>(
     last_materialized: &HashMap<String, Vec<HashMap<String, String>>>,
     static_data_plan: &Option<PhysicalOperator>,
-    static_db: &Arc<Mutex<SparqlDatabase>>,
+    static_db: &SharedSparqlDatabase,
     r2s: &Arc<Mutex<Relation2StreamOperator<O>>>,
     ts: usize,
     consumer: &Arc<dyn Fn(O) -> () + Send + Sync>,
@@ -874,7 +1021,13 @@ fn emit_results<O>(
     let joined = join_window_results(last_materialized);
 
     let final_results = if let Some(ref plan) = static_data_plan {
-        let static_bindings = execute_plan_as_bindings(static_db, plan);
+        let static_bindings = match execute_plan_as_bindings(static_db, plan) {
+            Ok(bindings) => bindings,
+            Err(e) => {
+                error!("Static data query failed, skipping this evaluation: {}", e);
+                return;
+            }
+        };
         debug!("emit_results: static bindings = {}", static_bindings.len());
         natural_join(&joined, &static_bindings)
     } else {
@@ -958,17 +1111,17 @@ fn join_window_results(window_buffers: &HashMap<String, Vec<HashMap<String, Stri
 /// Execute a physical plan against the static-data `SparqlDatabase` and return the results as
 /// a list of variable-binding maps.
 fn execute_plan_as_bindings(
-    static_db: &Arc<Mutex<SparqlDatabase>>,
+    static_db: &SharedSparqlDatabase,
     plan: &PhysicalOperator,
-) -> Vec<HashMap<String, String>> {
-    let mut db = static_db.lock().unwrap();
-    ExecutionEngine::execute(plan, &mut *db)
+) -> Result<Vec<HashMap<String, String>>, SparqlError> {
+    let db = static_db.read().unwrap();
+    ExecutionEngine::execute(plan, &db)
 }
 
 fn build_cross_window_sds(
     window_configs: &[RSPWindow],
     latest_contents: &HashMap<String, Vec<(Triple, u64)>>,
-    static_db: &Arc<Mutex<SparqlDatabase>>,
+    static_db: &SharedSparqlDatabase,
     dict: &Arc<RwLock<Dictionary>>,
     output_iris: &[String],
 ) -> Sds {
@@ -1010,7 +1163,7 @@ fn build_cross_window_sds(
     }
 
     let static_triples = {
-        let db = static_db.lock().unwrap();
+        let db = static_db.read().unwrap();
         let dict_r = dict.read().unwrap();
         db.triples
             .iter()
@@ -1036,7 +1189,7 @@ fn execute_window_plans_on_external_buckets(
     window_plans: &[PhysicalOperator],
     external_buckets: &HashMap<String, Vec<Triple>>,
     dict: &Arc<RwLock<Dictionary>>,
-) -> HashMap<String, Vec<HashMap<String, String>>> {
+) -> Result<HashMap<String, Vec<HashMap<String, String>>>, SparqlError> {
     let mut materialized = HashMap::new();
 
     for (window, plan) in window_configs.iter().zip(window_plans.iter()) {
@@ -1049,18 +1202,18 @@ fn execute_window_plans_on_external_buckets(
             }
         }
 
-        let results = ExecutionEngine::execute(plan, &mut db);
+        let results = ExecutionEngine::execute(plan, &db)?;
         materialized.insert(window.window_iri.clone(), results);
     }
 
-    materialized
+    Ok(materialized)
 }
 
 fn emit_cross_window_results<O>(
     window_configs: &[RSPWindow],
     window_plans: &[PhysicalOperator],
     static_data_plan: &Option<PhysicalOperator>,
-    static_db: &Arc<Mutex<SparqlDatabase>>,
+    static_db: &SharedSparqlDatabase,
     r2s: &Arc<Mutex<Relation2StreamOperator<O>>>,
     ts: usize,
     consumer: &Arc<dyn Fn(O) -> () + Send + Sync>,
@@ -1094,12 +1247,18 @@ fn emit_cross_window_results<O>(
         CrossWindowReasoningMode::Naive => naive_sds_plus(rules, &sds, dict, ts as u64),
     };
 
-    let materialized = execute_window_plans_on_external_buckets(
+    let materialized = match execute_window_plans_on_external_buckets(
         window_configs,
         window_plans,
         &external_buckets,
         dict,
-    );
+    ) {
+        Ok(materialized) => materialized,
+        Err(e) => {
+            error!("Cross-window query failed, skipping this evaluation: {}", e);
+            return;
+        }
+    };
 
     emit_results(
         &materialized,
@@ -9,20 +9,36 @@
  */
 
 use shared::dictionary::Dictionary;
-use shared::query::{FilterExpression, ModelDecl, NeuralRelationDecl, TrainNeuralRelationDecl};
+use shared::query::{Binding, FilterExpression, ModelDecl, NeuralRelationDecl, TrainNeuralRelationDecl};
 use shared::quoted_triple_store::{QuotedTripleStore, is_quoted_triple_id};
 use shared::triple::Triple;
+use crate::compression::open_rdf_file;
+use crate::custom_error::SparqlError;
+use crate::error_handler::format_parse_error;
+use crate::entailment::EntailmentRegime;
 use crate::parser;
+use crate::rdf_format::RdfFormat;
 use crate::utils;
-use crate::utils::ClonableFn;
+use crate::utils::{
+    compare_filter_values, evaluate_coalesce, evaluate_if_filter, evaluate_iri_filter,
+    evaluate_numeric_filter, evaluate_string_filter, lookup_udf, parse_numeric_literal, ClonableFn,
+};
 use shared::index_manager::UnifiedIndex;
+use log::{error, warn};
+use datalog::parser_n3_logic::parse_n3_document;
+use datalog::reasoning::Reasoner;
+use datalog::reasoning::derivation::DerivationLog;
+use datalog::reasoning::materialisation::infer_generic::InferenceBudget;
 use crate::query_builder::QueryBuilder;
 use crossbeam::channel::unbounded;
 use crossbeam::scope;
 use percent_encoding::percent_decode;
-use quick_xml::events::Event;
+use quick_xml::escape::{escape, resolve_predefined_entity};
+use quick_xml::events::attributes::Attribute;
+use quick_xml::events::{BytesRef, BytesStart, Event};
 use quick_xml::name::QName;
 use quick_xml::Reader;
+use rand::Rng;
 use rayon::prelude::*;
 #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
 use std::arch::x86_64::*;
@@ -30,15 +46,46 @@ use std::arch::x86_64::*;
 use std::arch::aarch64::*;
 use std::collections::{BTreeMap, BTreeSet, HashMap};
 use std::sync::Arc;
+use std::io::{BufRead, Write};
 use std::sync::{Mutex, RwLock};
 use url::Url;
-use crate::streamertail_optimizer::DatabaseStats;
-
-const MIN_CHUNK_SIZE: usize = 1024;
-const HASHMAP_INITIAL_CAPACITY: usize = 4096;
-
-const MIN_CHUNK_SIZE1: usize = 1024;
-const HASHMAP_INITIAL_CAPACITY1: usize = 1024;
+use crate::streamertail_optimizer::{CancellationToken, DatabaseStats, JoinAlgorithm, QueryProfiler};
+use crate::partitioned_store::PartitionedTripleStore;
+use crate::pattern_cache::{PatternCache, DEFAULT_PATTERN_CACHE_CAPACITY};
+use crate::text_index::TextIndex;
+use crate::query_cache::QueryCache;
+use crate::tuning::TuningConfig;
+use crate::prefix_index::PrefixIndex;
+
+/// Lines buffered by `parse_ntriples_streaming` before they are parsed and inserted
+const NTRIPLES_FLUSH_THRESHOLD: usize = 100_000;
+
+/// Triples read by `parse_rdf_from_file_with_progress` between two progress
+/// reports, and the size of the batches it inserts, under the default
+/// `TuningConfig::parse_batch_size`
+pub const PARSE_PROGRESS_INTERVAL: usize = 8192;
+
+/// Namespace bound to `rdf:` in every RDF/XML document Kolibrie writes
+const RDF_NAMESPACE: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#";
+
+/// Default for `SparqlDatabase::max_join_results`
+pub const DEFAULT_MAX_JOIN_RESULTS: usize = 1_000_000;
+
+/// A database shared between threads. Plans run through `ExecutionEngine`
+/// and the lookup helpers only need `read()`; adding or removing triples
+/// takes `write()`.
+pub type SharedSparqlDatabase = Arc<RwLock<SparqlDatabase>>;
+
+/// Triple pattern `subject_var predicate object_var` that the
+/// `perform_join_*` methods join the bindings with; `literal_filter`, when
+/// set, is the only object the pattern matches.
+#[derive(Debug, Clone)]
+pub struct JoinPattern<'a> {
+    pub subject_var: &'a str,
+    pub predicate: String,
+    pub object_var: &'a str,
+    pub literal_filter: Option<String>,
+}
 
 #[derive(Debug, Clone)]
 pub struct SparqlDatabase {
@@ -57,6 +104,41 @@ pub struct SparqlDatabase {
     pub probability_seeds: HashMap<Triple, f64>,
     pub cached_stats: Option<Arc<DatabaseStats>>,
     pub quoted_triple_store: Arc<RwLock<QuotedTripleStore>>,
+    pub entailment_regime: EntailmentRegime,
+    /// Triples materialised by the active entailment regime (not asserted)
+    pub entailed_triples: BTreeSet<Triple>,
+    pub entailment_dirty: bool,
+    entailed_from_len: usize,
+    /// Set on the databases `union` and `par_join` return; their indexes are
+    /// built by `ensure_indexes` when they are first queried
+    indexes_pending: bool,
+    /// Subject-sharded copy of `triples` scanned in parallel by `execute_query`
+    pub partitioned_store: Option<PartitionedTripleStore>,
+    /// How rule-inferred triples were derived; `None` unless tracking is enabled
+    pub derivation_log: Option<DerivationLog>,
+    /// Checked by the execution engine while a query runs; set by `execute_query_with_timeout`
+    pub cancellation: Option<Arc<CancellationToken>>,
+    /// Largest intermediate join result the engine will build; bigger joins
+    /// fail with a `SparqlError` instead of being truncated
+    pub max_join_results: usize,
+    /// Handed to the optimizer by the query entry points; set by `execute_query_profiled`
+    pub profiler: Option<Arc<QueryProfiler>>,
+    /// Join algorithm the optimizer must use; set by `execute_query_with_hint`
+    pub join_algorithm: Option<JoinAlgorithm>,
+    /// SELECT results reused until a write touches their patterns; see `enable_query_cache`
+    pub query_cache: Option<QueryCache>,
+    /// Index matches of recently scanned triple patterns; see `enable_pattern_cache`
+    pub pattern_cache: Option<PatternCache>,
+    /// Trigrams of the dictionary's terms for `CONTAINS`/`REGEX` filters; see `enable_text_index`
+    pub text_index: Option<TextIndex>,
+    /// Whether `build_all_indexes` also rebuilds `text_index`
+    pub text_index_enabled: bool,
+    /// Batch and chunk sizes of bulk loading and the parallel joins
+    pub tuning: TuningConfig,
+    /// Limits on the rounds and facts of one rule inference; `None` runs
+    /// rules to fixpoint. Exceeding it fails `try_process_rule_definition`
+    /// and `apply_n3_rules` with `SparqlError::InferenceBudgetExceeded`.
+    pub inference_budget: Option<InferenceBudget>,
 }
 
 #[allow(dead_code)]
@@ -78,6 +160,32 @@ impl SparqlDatabase {
             probability_seeds: HashMap::new(),
             cached_stats: None,
             quoted_triple_store: Arc::new(RwLock::new(QuotedTripleStore::new())),
+            entailment_regime: EntailmentRegime::Simple,
+            entailed_triples: BTreeSet::new(),
+            entailment_dirty: false,
+            entailed_from_len: 0,
+            indexes_pending: false,
+            partitioned_store: None,
+            derivation_log: None,
+            cancellation: None,
+            profiler: None,
+            join_algorithm: None,
+            query_cache: None,
+            pattern_cache: None,
+            text_index: None,
+            text_index_enabled: false,
+            max_join_results: DEFAULT_MAX_JOIN_RESULTS,
+            tuning: TuningConfig::default(),
+            inference_budget: None,
+        }
+    }
+
+    /// An empty database using `config` instead of the default batch and
+    /// chunk sizes; see `TuningConfig` for what each one affects
+    pub fn new_with_config(config: TuningConfig) -> Self {
+        Self {
+            tuning: config,
+            ..Self::new()
         }
     }
 
@@ -95,9 +203,17 @@ impl SparqlDatabase {
             let mut qt = self.quoted_triple_store.write().unwrap();
             qt.encode(s_id, p_id, o_id)
         } else {
+            let typed = utils::canonical_typed_literal(trimmed, |datatype| {
+                self.resolve_query_term(datatype, &self.prefixes)
+            });
             // Strip angle brackets from URIs
-            let cleaned = if trimmed.starts_with('<') && trimmed.ends_with('>') {
+            let cleaned = if let Some(typed) = typed.as_deref() {
+                typed
+            } else if trimmed.starts_with('<') && trimmed.ends_with('>') {
                 &trimmed[1..trimmed.len() - 1]
+            } else if !utils::language_tag(trimmed).is_empty() {
+                // Language-tagged literals keep their quotes: "chat"@fr
+                trimmed
             } else if trimmed.starts_with('"') {
                 // Handle literal: strip quotes, keep value
                 if let Some(close_pos) = trimmed[1..].find('"') {
@@ -199,6 +315,19 @@ impl SparqlDatabase {
         self.prefixes=prefixes;
     }
 
+    /// Reverse map of `self.prefixes`, from namespace to prefix. Build it once
+    /// when writing many IRIs; it does not see later changes to the prefixes.
+    pub fn prefix_index(&self) -> PrefixIndex {
+        PrefixIndex::new(&self.prefixes)
+    }
+
+    /// `iri` as a prefixed name using `self.prefixes` (`ex:alice`), taking the
+    /// longest namespace that leaves a valid local name; `None` if none does.
+    /// Indexes the prefixes on every call; use `prefix_index` for many IRIs.
+    pub fn shorten_iri(&self, iri: &str) -> Option<String> {
+        self.prefix_index().shorten(iri)
+    }
+
     pub fn get_or_build_stats(&mut self) -> Arc<DatabaseStats> {
         if let Some(stats) = &self.cached_stats {
             return stats.clone();  // ← Clone the Arc (cheap), not the DatabaseStats
@@ -217,19 +346,337 @@ impl SparqlDatabase {
         QueryBuilder::new(self)
     }
 
+    /// Number of asserted triples. O(1): the partitioned store mirrors
+    /// `triples` rather than replacing it, and the entailed triples are a
+    /// subset of `triples`, so this is the difference of the two lengths.
+    pub fn count_triples(&self) -> usize {
+        self.triples.len() - self.entailed_triples.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.count_triples() == 0
+    }
+
+    /// The triples in `triples` that were asserted, in store order; queries
+    /// also see the entailed ones, while the serializers write only these.
+    pub fn asserted_triples(&self) -> impl Iterator<Item = &Triple> + '_ {
+        self.triples.iter().filter(|triple| !self.entailed_triples.contains(triple))
+    }
+
+    /// Subjects of the triples `?s p o`, answered from the predicate-object
+    /// index without building a query. `p` and `o` are written as in a query:
+    /// full or `<...>` IRIs, prefixed names from `self.prefixes`, or literals.
+    /// Like the query engine, this reads the indexes, which bulk loads that
+    /// bypass `add_triple` leave to `build_all_indexes`.
+    pub fn get_subjects_for_predicate_object(&self, p: &str, o: &str) -> Vec<String> {
+        match (self.stored_term_id(p), self.stored_term_id(o)) {
+            (Some(p), Some(o)) => self.decode_sorted(self.index_manager.scan_po(p, o).into_iter().flatten()),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Objects of the triples `s p ?o`, from the subject-predicate index.
+    /// See `get_subjects_for_predicate_object` for how terms are written.
+    pub fn get_objects_for_subject_predicate(&self, s: &str, p: &str) -> Vec<String> {
+        match (self.stored_term_id(s), self.stored_term_id(p)) {
+            (Some(s), Some(p)) => self.decode_sorted(self.index_manager.scan_sp(s, p).into_iter().flatten()),
+            _ => Vec::new(),
+        }
+    }
+
+    /// Distinct subjects of the triples `?s p ?o`, from the
+    /// predicate-subject index. See `get_subjects_for_predicate_object` for
+    /// how terms are written.
+    pub fn get_subjects_for_predicate(&self, p: &str) -> Vec<String> {
+        match self.stored_term_id(p).and_then(|p| self.index_manager.pso.get(&p)) {
+            Some(subjects) => self.decode_sorted(subjects.keys()),
+            None => Vec::new(),
+        }
+    }
+
+    /// Dictionary id of a term written as in a query, or `None` when no
+    /// stored triple can contain it
+    fn stored_term_id(&self, term: &str) -> Option<u32> {
+        let term = self.resolve_query_term(term, &self.prefixes);
+        self.dictionary.read().unwrap().string_to_id.get(&term).copied()
+    }
+
+    fn decode_sorted<'a>(&self, ids: impl Iterator<Item = &'a u32>) -> Vec<String> {
+        let mut terms: Vec<String> = ids.filter_map(|&id| self.decode_any(id)).collect();
+        terms.sort_unstable();
+        terms
+    }
+
     pub fn add_triple(&mut self, triple: Triple) {
-        self.triples.insert(triple.clone());
+        // Asserting a previously entailed triple makes it survive re-materialisation
+        self.entailed_triples.remove(&triple);
+        if let Some(store) = &self.partitioned_store {
+            store.add_triple(triple.clone());
+        }
+        let inserted = self.triples.insert(triple.clone());
         self.index_manager.insert(&triple);
+        self.entailment_dirty = true;
+        if inserted {
+            if let Some(cache) = &mut self.query_cache {
+                cache.invalidate_triple(&triple, self.triples.len());
+            }
+            if let Some(cache) = &mut self.pattern_cache {
+                cache.invalidate_triple(&triple, self.triples.len());
+            }
+        }
     }
     
     pub fn delete_triple(&mut self, triple: &Triple) -> bool {
         let removed = self.triples.remove(triple);
         if removed {
+            if let Some(store) = &self.partitioned_store {
+                store.remove_triple(triple);
+            }
             self.index_manager.delete(triple);
+            self.entailed_triples.remove(triple);
+            self.entailment_dirty = true;
+            if let Some(cache) = &mut self.query_cache {
+                cache.invalidate_triple(triple, self.triples.len());
+            }
+            if let Some(cache) = &mut self.pattern_cache {
+                cache.invalidate_triple(triple, self.triples.len());
+            }
         }
         removed
     }
 
+    /// Cache SELECT results; `add_triple` and `delete_triple` invalidate only
+    /// the cached queries with a pattern matching the modified triple.
+    pub fn enable_query_cache(&mut self) {
+        if self.query_cache.is_none() {
+            self.query_cache = Some(QueryCache::new());
+        }
+    }
+
+    pub fn disable_query_cache(&mut self) {
+        self.query_cache = None;
+    }
+
+    /// Cache the index matches of the last `capacity` triple patterns scanned
+    /// by the execution engine (`DEFAULT_PATTERN_CACHE_CAPACITY` when `None`).
+    /// Writes through `add_triple` and `delete_triple` drop only the patterns
+    /// matching the modified triple.
+    pub fn enable_pattern_cache(&mut self, capacity: Option<usize>) {
+        if self.pattern_cache.is_none() {
+            let capacity = capacity.unwrap_or(DEFAULT_PATTERN_CACHE_CAPACITY);
+            self.pattern_cache = Some(PatternCache::new(capacity));
+        }
+    }
+
+    pub fn disable_pattern_cache(&mut self) {
+        self.pattern_cache = None;
+    }
+
+    /// Build the text index now and rebuild it with every `build_all_indexes`.
+    /// It holds each term's trigrams, so it costs memory roughly proportional
+    /// to the dictionary's text.
+    pub fn enable_text_index(&mut self) {
+        self.text_index_enabled = true;
+        self.build_text_index();
+    }
+
+    pub fn disable_text_index(&mut self) {
+        self.text_index_enabled = false;
+        self.text_index = None;
+    }
+
+    /// Index the trigrams of every dictionary term, so that `CONTAINS` and
+    /// `REGEX` filters on a scanned variable and `QueryBuilder` substring
+    /// filters only look at the terms that can match. Terms encoded later are
+    /// not covered; until the next build those filters scan as before.
+    pub fn build_text_index(&mut self) {
+        let dict = self.dictionary.read().unwrap();
+        self.text_index = Some(TextIndex::build(&dict));
+    }
+
+    /// The text index, when it covers every term of `dict`
+    pub fn current_text_index(&self, dict: &Dictionary) -> Option<&TextIndex> {
+        self.text_index.as_ref().filter(|index| index.covers(dict))
+    }
+
+    /// Shard the triples by subject into `num_shards` partitions so that
+    /// `execute_query` scans each pattern across shards in parallel.
+    pub fn enable_partitioning(&mut self, num_shards: usize) {
+        self.partitioned_store = Some(PartitionedTripleStore::from_triples(&self.triples, num_shards));
+    }
+
+    pub fn disable_partitioning(&mut self) {
+        self.partitioned_store = None;
+    }
+
+    /// Rebuild the partitions if `triples` was modified directly (e.g. by a
+    /// parser) rather than through `add_triple`/`delete_triple`. Staleness is
+    /// detected from the triple count alone, so a direct edit that keeps the
+    /// count (replacing one triple by another) needs `enable_partitioning`.
+    pub fn sync_partitions(&mut self) {
+        let stale = match &self.partitioned_store {
+            Some(store) => store.len() != self.triples.len(),
+            None => false,
+        };
+        if stale {
+            let num_shards = self.partitioned_store.as_ref().unwrap().num_shards();
+            self.enable_partitioning(num_shards);
+        }
+    }
+
+    /// Build the indexes of a database returned by `union` or `par_join`,
+    /// which skip them until the result is queried. Does nothing otherwise.
+    pub fn ensure_indexes(&mut self) {
+        if self.indexes_pending {
+            self.build_all_indexes();
+        }
+    }
+
+    /// Bring what queries derive lazily from the triples up to date:
+    /// entailment, partitions, indexes and optimizer statistics. Queries
+    /// through `execute_read_query` cannot do this themselves, so it has to
+    /// run after each write.
+    pub fn prepare_for_reads(&mut self) {
+        self.apply_entailment();
+        self.sync_partitions();
+        self.ensure_indexes();
+        self.get_or_build_stats();
+    }
+
+    /// Whether `prepare_for_reads` has nothing left to do
+    pub fn is_prepared_for_reads(&self) -> bool {
+        let entailment_current = (self.entailment_regime == EntailmentRegime::Simple
+            && self.entailed_triples.is_empty())
+            || (!self.entailment_dirty && self.triples.len() == self.entailed_from_len);
+        let partitions_current = self
+            .partitioned_store
+            .as_ref()
+            .is_none_or(|store| store.len() == self.triples.len());
+        entailment_current && partitions_current && !self.indexes_pending && self.cached_stats.is_some()
+    }
+
+    /// Whether `index_manager` holds exactly the triples in `triples`, so
+    /// lookups may read it instead of scanning. Bulk loads and direct writes
+    /// to `triples` leave it behind until `build_all_indexes`.
+    pub fn indexes_current(&self) -> bool {
+        !self.indexes_pending && self.index_manager.len() == self.triples.len()
+    }
+
+    /// Record the rule and premises behind each triple inferred by
+    /// `process_rule_definition` from now on.
+    pub fn enable_derivation_tracking(&mut self) {
+        if self.derivation_log.is_none() {
+            self.derivation_log = Some(DerivationLog::new());
+        }
+    }
+
+    /// Derivation chain of an inferred triple, down to the asserted triples it rests on
+    pub fn why(&self, triple: &Triple) -> String {
+        match &self.derivation_log {
+            Some(log) => log.why(triple, &self.dictionary.read().unwrap()),
+            None => String::from("Derivation tracking is disabled; call enable_derivation_tracking before processing rules\n"),
+        }
+    }
+
+    /// Select the entailment regime used to answer queries. The entailed
+    /// triples are (re)materialised lazily by `apply_entailment`, which the
+    /// query entry points call before evaluating a query.
+    pub fn set_entailment_regime(&mut self, regime: EntailmentRegime) {
+        if self.entailment_regime != regime {
+            self.entailment_regime = regime;
+            self.entailment_dirty = true;
+        }
+    }
+
+    /// Bring the entailed triples in line with the asserted data and the
+    /// active regime. Previously entailed triples are retracted first, so
+    /// deletions and regime changes are reflected as well.
+    pub fn apply_entailment(&mut self) {
+        if self.entailment_regime == EntailmentRegime::Simple && self.entailed_triples.is_empty() {
+            self.entailment_dirty = false;
+            return;
+        }
+        if !self.entailment_dirty && self.triples.len() == self.entailed_from_len {
+            return;
+        }
+
+        for triple in std::mem::take(&mut self.entailed_triples) {
+            if self.triples.remove(&triple) {
+                self.index_manager.delete(&triple);
+            }
+        }
+
+        if self.entailment_regime != EntailmentRegime::Simple {
+            let mut kg = Reasoner::new();
+            kg.dictionary = self.dictionary.clone();
+            for triple in self.triples.iter() {
+                kg.index_manager.insert(triple);
+            }
+            let rules = {
+                let mut dict = self.dictionary.write().unwrap();
+                self.entailment_regime.rules(&self.triples, &mut dict)
+            };
+            for rule in rules {
+                kg.add_rule(rule);
+            }
+
+            for triple in kg.infer_new_facts_semi_naive() {
+                if self.triples.insert(triple.clone()) {
+                    self.index_manager.insert(&triple);
+                    self.entailed_triples.insert(triple);
+                }
+            }
+        }
+
+        self.entailment_dirty = false;
+        self.entailed_from_len = self.triples.len();
+        self.invalidate_stats_cache();
+        if let Some(cache) = &mut self.pattern_cache {
+            cache.clear();
+        }
+    }
+
+    /// Parse an N3 rule document (`@prefix` declarations followed by
+    /// `{ premise } => { conclusion } .` rules), run the rules to a fixpoint
+    /// over the stored triples and add the inferred triples to the database.
+    /// Returns the number of triples added, or an error once the rules
+    /// exceed `inference_budget`.
+    pub fn apply_n3_rules(&mut self, n3_text: &str) -> Result<usize, SparqlError> {
+        // The reasoner shares the dictionary, so rule constants and inferred
+        // triples use the database's term ids
+        let mut kg = Reasoner::new();
+        kg.dictionary = self.dictionary.clone();
+
+        let (prefixes, rules) = match parse_n3_document(n3_text.trim(), &mut kg) {
+            Ok((_, parsed)) => parsed,
+            Err(e) => return Err(SparqlError::ParseError(format!("Invalid N3 rules: {:?}", e))),
+        };
+        self.prefixes.extend(prefixes);
+
+        for triple in self.triples.iter() {
+            kg.index_manager.insert(triple);
+        }
+        for rule in rules {
+            kg.add_rule(rule);
+        }
+        kg.inference_budget = self.inference_budget;
+
+        let mut added = 0;
+        let inferred = kg
+            .try_infer_new_facts_semi_naive()
+            .map_err(SparqlError::InferenceBudgetExceeded)?;
+        for triple in inferred {
+            if !self.triples.contains(&triple) {
+                self.add_triple(triple);
+                added += 1;
+            }
+        }
+        if added > 0 {
+            self.invalidate_stats_cache();
+        }
+        Ok(added)
+    }
+
     /// Helper function that accepts parts of a triple, constructs a Triple, and adds it
     pub fn add_triple_parts(&mut self, subject: &str, predicate: &str, object: &str) {
         let mut dict = self.dictionary.write().unwrap();
@@ -275,70 +722,193 @@ impl SparqlDatabase {
     }
 
     pub fn generate_rdf_xml(&mut self) -> String {
-        let mut xml = String::new();
-        xml.push_str("<?xml version=\"1.0\"?>\n");
-        xml.push_str("<rdf:RDF");
-    
+        let mut xml = Vec::new();
+        self.write_rdf_xml(&mut xml).expect("writing to a Vec cannot fail");
+        String::from_utf8(xml).expect("RDF/XML output is UTF-8")
+    }
+
+    /// Stream the store as RDF/XML to `writer`, one `rdf:Description` per
+    /// subject. `triples` is ordered by subject id, so each subject's group is
+    /// written as soon as the next subject starts and the document is never
+    /// held in memory. Wrap unbuffered writers in a `BufWriter`.
+    ///
+    /// Predicates become `prefix:local` element names from `self.prefixes`;
+    /// a predicate IRI outside every stored namespace gets a generated `nsN`
+    /// declaration. Objects that are http(s) IRIs are written as
+    /// `rdf:resource`, typed and language-tagged literals carry `rdf:datatype`
+    /// or `xml:lang`, everything else is escaped element text.
+    pub fn write_rdf_xml<W: Write>(&self, mut writer: W) -> std::io::Result<()> {
+        let dict = self.dictionary.read().unwrap();
+
+        // Element names need every namespace declared on the root, so resolve
+        // the (few) distinct predicates before streaming the triples
+        let mut generated = Vec::new();
+        let mut element_names: HashMap<u32, Option<String>> = HashMap::new();
+        for triple in self.asserted_triples() {
+            element_names.entry(triple.predicate).or_insert_with(|| {
+                let predicate = dict.decode(triple.predicate).unwrap_or_default();
+                let name = self.rdf_xml_element_name(predicate, &mut generated);
+                if name.is_none() {
+                    warn!("Predicate {} has no valid XML element name; its triples are not written", predicate);
+                }
+                name
+            });
+        }
+
+        writer.write_all(b"<?xml version=\"1.0\"?>\n<rdf:RDF")?;
+
         // Write namespace declarations (from the stored prefixes)
         for (prefix, uri) in &self.prefixes {
             if prefix.is_empty() {
-                xml.push_str(&format!(" xmlns=\"{}\"", uri));
-            } else {
-                xml.push_str(&format!(" xmlns:{}=\"{}\"", prefix, uri));
+                write!(writer, " xmlns=\"{}\"", escape(uri.as_str()))?;
+            } else if prefix != "rdf" {
+                write!(writer, " xmlns:{}=\"{}\"", prefix, escape(uri.as_str()))?;
             }
         }
-        // Always include the standard RDF namespace
-        xml.push_str(" xmlns:rdf=\"http://www.w3.org/1999/02/22-rdf-syntax-ns#\"");
-        xml.push_str(">\n");
-    
-        // Group triples by subject
-        let dict = self.dictionary.read().unwrap();
-        let mut subjects: BTreeMap<String, Vec<(String, String)>> = BTreeMap::new();
-        for triple in &self.triples {
-            let subject = dict.decode(triple.subject);
-            let predicate = dict.decode(triple.predicate);
-            let object = dict.decode(triple.object);
-            subjects.entry(subject.unwrap().to_string()).or_default().push((predicate.unwrap().to_string(), object.unwrap().to_string()));
+        for (prefix, uri) in &generated {
+            write!(writer, " xmlns:{}=\"{}\"", prefix, escape(uri.as_str()))?;
         }
-        drop(dict);
-    
-        // For each subject, create an <rdf:Description> element.
-        for (subject, po_pairs) in subjects {
-            xml.push_str(&format!("  <rdf:Description rdf:about=\"{}\">\n", subject));
-            for (predicate, object) in po_pairs {
-                xml.push_str(&format!("    <{}>{}</{}>\n", predicate, object, predicate));
+        // Always include the standard RDF namespace
+        writeln!(writer, " xmlns:rdf=\"{}\">", RDF_NAMESPACE)?;
+
+        let mut current_subject = None;
+        for triple in self.asserted_triples() {
+            let Some(Some(element)) = element_names.get(&triple.predicate) else {
+                continue;
+            };
+            if current_subject != Some(triple.subject) {
+                if current_subject.is_some() {
+                    writer.write_all(b"  </rdf:Description>\n")?;
+                }
+                current_subject = Some(triple.subject);
+                let subject = dict.decode(triple.subject).unwrap_or_default();
+                writeln!(writer, "  <rdf:Description rdf:about=\"{}\">", escape(subject))?;
+            }
+            let object = dict.decode(triple.object).unwrap_or_default();
+            let lang = utils::language_tag(object);
+            if object.starts_with("http://") || object.starts_with("https://") {
+                writeln!(writer, "    <{} rdf:resource=\"{}\"/>", element, escape(object))?;
+            } else if let Some((lexical, datatype)) = typed_literal_parts(object) {
+                writeln!(
+                    writer,
+                    "    <{} rdf:datatype=\"{}\">{}</{}>",
+                    element,
+                    escape(datatype),
+                    escape(lexical),
+                    element
+                )?;
+            } else if !lang.is_empty() {
+                let lexical = &object[1..object.len() - lang.len() - 2];
+                writeln!(writer, "    <{} xml:lang=\"{}\">{}</{}>", element, lang, escape(lexical), element)?;
+            } else {
+                writeln!(writer, "    <{}>{}</{}>", element, escape(object), element)?;
             }
-            xml.push_str("  </rdf:Description>\n");
         }
-    
-        xml.push_str("</rdf:RDF>\n");
-        xml
+        if current_subject.is_some() {
+            writer.write_all(b"  </rdf:Description>\n")?;
+        }
+
+        writer.write_all(b"</rdf:RDF>\n")?;
+        writer.flush()
     }
 
-    /// Serializes all triples as N-Triples-star format
-    pub fn generate_ntriples(&self) -> String {
-        let mut output = String::new();
-        for triple in &self.triples {
-            let s = self.decode_any(triple.subject).unwrap_or_default();
-            let p = self.decode_any(triple.predicate).unwrap_or_default();
-            let o = self.decode_any(triple.object).unwrap_or_default();
+    /// `prefix:local` element name for a predicate: kept as is when it is
+    /// already a name with a declared prefix, otherwise the longest stored
+    /// (or already generated) namespace of the IRI, otherwise a new `nsN`
+    /// namespace pushed onto `generated`. `None` when the IRI does not end in
+    /// a valid XML local name, as RDF/XML cannot express such predicates.
+    fn rdf_xml_element_name(&self, predicate: &str, generated: &mut Vec<(String, String)>) -> Option<String> {
+        if let Some((prefix, local)) = predicate.split_once(':') {
+            let declared = prefix == "rdf" || (!prefix.is_empty() && self.prefixes.contains_key(prefix));
+            if declared && is_xml_local_name(local) {
+                return Some(predicate.to_string());
+            }
+        }
+
+        let longest = self
+            .prefixes
+            .iter()
+            .chain(generated.iter().map(|(prefix, uri)| (prefix, uri)))
+            .filter(|(prefix, uri)| !prefix.is_empty() && prefix.as_str() != "rdf" && !uri.is_empty())
+            .map(|(prefix, uri)| (prefix.as_str(), uri.as_str()))
+            .chain(std::iter::once(("rdf", RDF_NAMESPACE)))
+            .filter_map(|(prefix, uri)| predicate.strip_prefix(uri).map(|local| (prefix, uri, local)))
+            .filter(|(_, _, local)| is_xml_local_name(local))
+            .max_by_key(|(_, uri, _)| uri.len());
+        if let Some((prefix, _, local)) = longest {
+            return Some(format!("{}:{}", prefix, local));
+        }
+
+        // Longest suffix that is a valid local name; the rest is the namespace
+        let mut split = predicate.len();
+        for (position, ch) in predicate.char_indices().rev() {
+            if !is_xml_name_char(ch) {
+                break;
+            }
+            if is_xml_name_start_char(ch) {
+                split = position;
+            }
+        }
+        if split == 0 || split == predicate.len() {
+            return None;
+        }
+        let (namespace, local) = predicate.split_at(split);
+        let prefix = (0..)
+            .map(|n| format!("ns{}", n))
+            .find(|prefix| !self.prefixes.contains_key(prefix) && generated.iter().all(|(p, _)| p != prefix))
+            .unwrap();
+        generated.push((prefix.clone(), namespace.to_string()));
+        Some(format!("{}:{}", prefix, local))
+    }
 
-            let s_str = if s.starts_with("<<") { s } else { format!("<{}>", s) };
-            let p_str = format!("<{}>", p);
-            let o_str = if o.starts_with("<<") {
-                o
-            } else if o.starts_with("http://") || o.starts_with("https://") {
-                format!("<{}>", o)
+    /// Dictionary form of RDF/XML element text: `rdf:datatype` makes it a
+    /// typed literal as stored by `resolve_term` and `xml:lang` a `"text"@lang`
+    /// literal; plain text is stored as is.
+    fn rdf_xml_literal(&self, text: &str, datatype: Option<&str>, lang: Option<&str>) -> String {
+        if let Some(datatype) = datatype.filter(|d| !d.is_empty()) {
+            let datatype = if datatype.contains("://") {
+                format!("<{}>", datatype)
             } else {
-                format!("\"{}\"", o)
+                datatype.to_string()
             };
+            let literal = format!("\"{}\"^^{}", text, datatype);
+            return utils::canonical_typed_literal(&literal, |d| self.resolve_term(d))
+                .unwrap_or_else(|| text.to_string());
+        }
+        match lang.filter(|l| !l.is_empty()) {
+            Some(lang) => format!("\"{}\"@{}", text, lang),
+            None => text.to_string(),
+        }
+    }
+
+    /// Decoded `(subject, predicate, object)` of every asserted triple, in
+    /// store order. Each triple is decoded when the iterator reaches it;
+    /// quoted triples decode to their `<< s p o >>` form.
+    pub fn iter_triples_as_strings<'a>(&'a self) -> impl Iterator<Item = (String, String, String)> + 'a {
+        self.asserted_triples().map(move |triple| {
+            (
+                self.decode_any(triple.subject).unwrap_or_default(),
+                self.decode_any(triple.predicate).unwrap_or_default(),
+                self.decode_any(triple.object).unwrap_or_default(),
+            )
+        })
+    }
 
-            output.push_str(&format!("{} {} {} .\n", s_str, p_str, o_str));
+    /// Serializes all asserted triples as N-Triples-star format
+    pub fn generate_ntriples(&self) -> String {
+        let mut output = String::new();
+        for (s, p, o) in self.iter_triples_as_strings() {
+            output.push_str(&format!(
+                "{} {} {} .\n",
+                utils::ntriples_term(&s),
+                utils::ntriples_term(&p),
+                utils::ntriples_term(&o)
+            ));
         }
         output
     }
 
-    /// Serializes all triples as Turtle-star format with prefix declarations
+    /// Serializes all asserted triples as Turtle-star format with prefix declarations
     pub fn generate_turtle(&self) -> String {
         let mut output = String::new();
 
@@ -352,7 +922,7 @@ impl SparqlDatabase {
 
         // Group triples by subject, then by predicate
         let mut subjects: std::collections::BTreeMap<String, std::collections::BTreeMap<String, Vec<String>>> = std::collections::BTreeMap::new();
-        for triple in &self.triples {
+        for triple in self.asserted_triples() {
             let s = self.decode_any(triple.subject).unwrap_or_default();
             let p = self.decode_any(triple.predicate).unwrap_or_default();
             let o = self.decode_any(triple.object).unwrap_or_default();
@@ -360,12 +930,7 @@ impl SparqlDatabase {
         }
 
         for (subject, predicates) in &subjects {
-            let s_str = if subject.starts_with("<<") {
-                subject.clone()
-            } else {
-                format!("<{}>", subject)
-            };
-            output.push_str(&s_str);
+            output.push_str(&utils::ntriples_term(subject));
 
             let pred_count = predicates.len();
             for (i, (predicate, objects)) in predicates.iter().enumerate() {
@@ -374,20 +939,14 @@ impl SparqlDatabase {
                 } else {
                     output.push_str(" ;\n    ");
                 }
-                output.push_str(&format!("<{}>", predicate));
+                output.push_str(&utils::ntriples_term(predicate));
 
                 for (j, obj) in objects.iter().enumerate() {
                     if j > 0 {
                         output.push_str(" ,");
                     }
                     output.push(' ');
-                    if obj.starts_with("<<") {
-                        output.push_str(obj);
-                    } else if obj.starts_with("http://") || obj.starts_with("https://") {
-                        output.push_str(&format!("<{}>", obj));
-                    } else {
-                        output.push_str(&format!("\"{}\"", obj));
-                    }
+                    output.push_str(&utils::ntriples_term(obj));
                 }
 
                 if i == pred_count - 1 {
@@ -401,13 +960,13 @@ impl SparqlDatabase {
     pub fn parse_rdf(&mut self, rdf_xml: &str) {
         let mut reader = Reader::from_str(rdf_xml);
 
-        let mut current_subject = Vec::with_capacity(128);
-        let mut current_predicate = Vec::with_capacity(128);
+        let mut state = RdfXmlParser::default();
 
         let (sender, receiver) = unbounded::<Vec<Triple>>();
         let dictionary = Arc::clone(&self.dictionary);
         let triples_set = Arc::new(Mutex::new(Vec::new()));
-        let num_threads = utils::get_num_cpus();
+        let num_threads = utils::num_threads();
+        let batch_size = self.tuning.parse_batch_size.max(1);
 
         // Crossbeam scope to manage threads
         scope(|s| {
@@ -434,121 +993,59 @@ impl SparqlDatabase {
             }
 
             // Parsing and sending chunks
-            let mut triples = Vec::with_capacity(8192);
+            let mut triples = Vec::with_capacity(batch_size);
+            let mut parsed = Vec::new();
             loop {
                 match reader.read_event() {
-                    Ok(Event::Start(ref e)) => match e.name() {
-                        QName(b"rdf:RDF") => {
-                            for attr in e.attributes().filter_map(Result::ok) {
-                                let key = attr.key;
-                                let value = attr.value;
-                                if key.as_ref().starts_with(b"xmlns:") {
-                                    let prefix = std::str::from_utf8(&key.as_ref()[6..])
-                                        .unwrap_or("")
-                                        .to_string();
-                                    let uri = std::str::from_utf8(&value).unwrap_or("").to_string();
-                                    self.prefixes.insert(prefix, uri);
-                                } else if key.as_ref() == b"xmlns" {
-                                    // Default namespace
-                                    let uri = std::str::from_utf8(&value).unwrap_or("").to_string();
-                                    self.prefixes.insert("".to_string(), uri);
-                                }
-                            }
-                        }
-                        QName(b"rdf:Description") => {
-                            for attr in e.attributes().filter_map(Result::ok) {
-                                if attr.key == QName(b"rdf:about") {
-                                    current_subject.truncate(0);
-                                    current_subject.extend_from_slice(&attr.value);
-                                }
-                            }
-                        }
-                        QName(b"rdfs:Class") | QName(b"rdf:type") => {
-                            current_predicate.truncate(0);
-                            current_predicate.extend_from_slice(b"rdf:type");
-                        }
-                        QName(b"rdfs:subClassOf") => {
-                            current_predicate.truncate(0);
-                            current_predicate.extend_from_slice(b"rdfs:subClassOf");
-                        }
-                        QName(b"rdfs:label") => {
-                            current_predicate.truncate(0);
-                            current_predicate.extend_from_slice(b"rdfs:label");
-                        }
-                        name => {
-                            let name_str =
-                                std::str::from_utf8(name.as_ref()).unwrap_or("").to_string();
-                            let resolved_predicate = self.resolve_term(&name_str);
-                            current_predicate = resolved_predicate.clone().into_bytes();
-                        }
-                    },
-                    Ok(Event::Empty(ref e)) => {
-                        if let Ok(predicate) = std::str::from_utf8(e.name().as_ref()) {
-                            let resolved_predicate = self.resolve_term(predicate);
-                            let mut object = Vec::with_capacity(128);
-                            for attr in e.attributes().filter_map(Result::ok) {
-                                if attr.key == QName(b"rdf:resource") {
-                                    object.extend_from_slice(&attr.value);
-                                }
-                            }
-                            if !object.is_empty() {
-                                if let (Ok(subject_str), Ok(object_str)) = (
-                                    std::str::from_utf8(&current_subject),
-                                    std::str::from_utf8(&object),
-                                ) {
-                                    // Lock the dictionary for encoding
-                                    let mut dict = dictionary.write().unwrap();
-                                    let triple = Triple {
-                                        subject: dict.encode(subject_str),
-                                        predicate: dict.encode(&resolved_predicate),
-                                        object: dict.encode(object_str),
-                                    };
-                                    drop(dict); // Release the lock
-                                    triples.push(triple);
-                                }
+                    Ok(Event::Start(ref e)) if e.name() == QName(b"rdf:RDF") => {
+                        for attr in e.attributes().filter_map(Result::ok) {
+                            let key = attr.key;
+                            let value = attr.value;
+                            if key.as_ref().starts_with(b"xmlns:") {
+                                let prefix = std::str::from_utf8(&key.as_ref()[6..])
+                                    .unwrap_or("")
+                                    .to_string();
+                                let uri = std::str::from_utf8(&value).unwrap_or("").to_string();
+                                self.prefixes.insert(prefix, uri);
+                            } else if key.as_ref() == b"xmlns" {
+                                // Default namespace
+                                let uri = std::str::from_utf8(&value).unwrap_or("").to_string();
+                                self.prefixes.insert("".to_string(), uri);
                             }
                         }
                     }
+                    Ok(Event::Start(ref e)) => state.start(self, e),
+                    Ok(Event::Empty(ref e)) => state.empty(self, e, &mut parsed),
                     Ok(Event::Text(e)) => {
-                        // Use Reader's decode method and trim whitespace
-                        if let Ok(object_str) = reader.decoder().decode(e.as_ref()) {
-                            let trimmed_object = object_str.trim();
-                            // Skip empty or whitespace-only text
-                            if !trimmed_object.is_empty() {
-                                if let Ok(subject_str) = std::str::from_utf8(&current_subject) {
-                                    if let Ok(predicate_str) = std::str::from_utf8(&current_predicate) {
-                                        let resolved_predicate = self.resolve_term(predicate_str);
-                                        // Lock the dictionary for encoding
-                                        let mut dict = dictionary.write().unwrap();
-                                        let triple = Triple {
-                                            subject: dict.encode(subject_str),
-                                            predicate: dict.encode(&resolved_predicate),
-                                            object: dict.encode(trimmed_object),
-                                        };
-                                        drop(dict); // Release the lock
-                                        triples.push(triple);
-                                    }
-                                }
-                            }
-                        }
-                    }
-                    Ok(Event::End(ref e)) => {
-                        if e.name() == QName(b"rdf:Description") {
-                            current_subject.truncate(0);
-                            current_predicate.truncate(0);
+                        if let Ok(text) = reader.decoder().decode(e.as_ref()) {
+                            state.text(&text);
                         }
                     }
+                    Ok(Event::GeneralRef(ref e)) => state.reference(e),
+                    Ok(Event::End(_)) => state.end(self, &mut parsed),
                     Ok(Event::Eof) => break,
                     Err(e) => {
-                        eprintln!("Error reading XML: {:?}", e);
+                        error!("Error reading XML: {:?}", e);
                         break;
                     }
                     _ => {}
                 }
 
-                if triples.len() >= 8192 {
+                if !parsed.is_empty() {
+                    // Lock the dictionary for encoding
+                    let mut dict = dictionary.write().unwrap();
+                    for (subject, predicate, object) in parsed.drain(..) {
+                        triples.push(Triple {
+                            subject: dict.encode(&subject),
+                            predicate: dict.encode(&predicate),
+                            object: dict.encode(&object),
+                        });
+                    }
+                }
+
+                if triples.len() >= batch_size {
                     sender.send(triples).unwrap();
-                    triples = Vec::with_capacity(8192);
+                    triples = Vec::with_capacity(batch_size);
                 }
             }
 
@@ -570,13 +1067,36 @@ impl SparqlDatabase {
         }
     }
 
+    /// Parse `data` with the parser matching `format`.
+    pub fn parse_rdf_with_format(&mut self, data: &str, format: RdfFormat) {
+        match format {
+            RdfFormat::RdfXml => self.parse_rdf(data),
+            RdfFormat::Turtle => self.parse_turtle(data),
+            RdfFormat::NTriples => self.parse_ntriples_and_add(data),
+            RdfFormat::N3 => self.parse_n3(data),
+        }
+    }
+
+    /// Parse an RDF/XML file, decompressing it first when `filename` ends in
+    /// `.gz` or `.zst`.
     pub fn parse_rdf_from_file(&mut self, filename: &str) {
-        let file = std::fs::File::open(filename).expect("Cannot open file");
-        let reader = std::io::BufReader::new(file);
-        let mut xml_reader = Reader::from_reader(reader);
+        self.parse_rdf_from_file_with_progress(filename, |_| {}, &CancellationToken::new());
+    }
 
-        let mut current_subject = Vec::with_capacity(128);
-        let mut current_predicate = Vec::with_capacity(128);
+    /// Parse an RDF/XML file like `parse_rdf_from_file`, calling `on_progress`
+    /// with the running number of triples read after each batch of
+    /// `tuning.parse_batch_size` triples (`PARSE_PROGRESS_INTERVAL` by
+    /// default) and after the last, smaller one. Once
+    /// `cancellation` is cancelled, parsing stops at the next report; the
+    /// triples read until then are kept. Returns the number of triples read.
+    pub fn parse_rdf_from_file_with_progress(
+        &mut self,
+        filename: &str,
+        on_progress: impl Fn(usize),
+        cancellation: &CancellationToken,
+    ) -> usize {
+        let reader = open_rdf_file(filename).expect("Cannot open file");
+        let mut xml_reader = Reader::from_reader(reader);
 
         // First, read prefixes before spawning worker threads
         let mut buf = Vec::new();
@@ -604,11 +1124,11 @@ impl SparqlDatabase {
                     }
                 }
                 Ok(Event::Eof) => {
-                    eprintln!("Reached EOF before reading prefixes.");
+                    warn!("Reached EOF before reading prefixes.");
                     break;
                 }
                 Err(e) => {
-                    eprintln!("Error reading XML: {:?}", e);
+                    error!("Error reading XML: {:?}", e);
                     break;
                 }
                 _ => {}
@@ -617,116 +1137,80 @@ impl SparqlDatabase {
         }
 
         // Continue reading and parsing the rest of the file
-        let mut triples = Vec::with_capacity(8192);
+        let batch_size = self.tuning.parse_batch_size.max(1);
+        let mut triples = Vec::with_capacity(batch_size);
+        let mut state = RdfXmlParser::default();
+        let mut parsed = Vec::new();
+        let mut read = 0;
         loop {
             match xml_reader.read_event_into(&mut buf) {
-                Ok(Event::Start(ref e)) => match e.name() {
-                    QName(b"rdf:Description") => {
-                        for attr in e.attributes().filter_map(Result::ok) {
-                            if attr.key == QName(b"rdf:about") {
-                                current_subject.clear();
-                                current_subject.extend_from_slice(&attr.value);
-                            }
-                        }
-                    }
-                    QName(b"rdfs:Class") | QName(b"rdf:type") => {
-                        current_predicate.clear();
-                        current_predicate.extend_from_slice(b"rdf:type");
-                    }
-                    QName(b"rdfs:subClassOf") => {
-                        current_predicate.clear();
-                        current_predicate.extend_from_slice(b"rdfs:subClassOf");
-                    }
-                    QName(b"rdfs:label") => {
-                        current_predicate.clear();
-                        current_predicate.extend_from_slice(b"rdfs:label");
-                    }
-                    name => {
-                        let name_str = std::str::from_utf8(name.as_ref()).unwrap_or("").to_string();
-                        let resolved_predicate = self.resolve_term(&name_str);
-                        current_predicate = resolved_predicate.clone().into_bytes();
-                    }
-                },
-                Ok(Event::Empty(ref e)) => {
-                    if let Ok(predicate) = std::str::from_utf8(e.name().as_ref()) {
-                        let resolved_predicate = self.resolve_term(predicate);
-                        let mut object = Vec::with_capacity(128);
-                        for attr in e.attributes().filter_map(Result::ok) {
-                            if attr.key == QName(b"rdf:resource") {
-                                object.extend_from_slice(&attr.value);
-                            }
-                        }
-                        if !object.is_empty() {
-                            if let (Ok(subject_str), Ok(object_str)) = (
-                                std::str::from_utf8(&current_subject),
-                                std::str::from_utf8(&object),
-                            ) {
-                                let mut dict = self.dictionary.write().unwrap();
-                                let triple = Triple {
-                                    subject: dict.encode(subject_str),
-                                    predicate: dict.encode(&resolved_predicate),
-                                    object: dict.encode(object_str),
-                                };
-                                drop(dict);
-                                triples.push(triple);
-                            }
-                        }
-                    }
-                }
+                Ok(Event::Start(ref e)) => state.start(self, e),
+                Ok(Event::Empty(ref e)) => state.empty(self, e, &mut parsed),
                 Ok(Event::Text(e)) => {
-                    // Use Reader's decode method and trim whitespace
-                    if let Ok(object_str) = xml_reader.decoder().decode(e.as_ref()) {
-                        let trimmed_object = object_str.trim();
-                        // Skip empty or whitespace-only text
-                        if !trimmed_object.is_empty() {
-                            if let Ok(subject_str) = std::str::from_utf8(&current_subject) {
-                                if let Ok(predicate_str) = std::str::from_utf8(&current_predicate) {
-                                    let resolved_predicate = self.resolve_term(predicate_str);
-                                    let mut dict = self.dictionary.write().unwrap();
-                                    let triple = Triple {
-                                        subject: dict.encode(subject_str),
-                                        predicate: dict.encode(&resolved_predicate),
-                                        object: dict.encode(trimmed_object),
-                                    };
-                                    drop(dict);
-                                    triples.push(triple);
-                                }
-                            }
-                        }
-                    }
-                }
-                Ok(Event::End(ref e)) => {
-                    if e.name() == QName(b"rdf:Description") {
-                        current_subject.clear();
-                        current_predicate.clear();
+                    if let Ok(text) = xml_reader.decoder().decode(e.as_ref()) {
+                        state.text(&text);
                     }
                 }
+                Ok(Event::GeneralRef(ref e)) => state.reference(e),
+                Ok(Event::End(_)) => state.end(self, &mut parsed),
                 Ok(Event::Eof) => break,
                 Err(e) => {
-                    eprintln!("Error reading XML: {:?}", e);
+                    error!("Error reading XML: {:?}", e);
                     break;
                 }
                 _ => {}
             }
 
+            if !parsed.is_empty() {
+                let mut dict = self.dictionary.write().unwrap();
+                for (subject, predicate, object) in parsed.drain(..) {
+                    triples.push(Triple {
+                        subject: dict.encode(&subject),
+                        predicate: dict.encode(&predicate),
+                        object: dict.encode(&object),
+                    });
+                }
+            }
+
             buf.clear();
 
-            if triples.len() >= 8192 {
+            if triples.len() >= batch_size {
+                read += triples.len();
                 // Process triples in parallel using Rayon
                 let local_triples: BTreeSet<Triple> = triples.into_par_iter().collect();
                 self.triples.extend(local_triples);
-                triples = Vec::with_capacity(8192);
+                triples = Vec::with_capacity(batch_size);
+                on_progress(read);
+                if cancellation.is_cancelled() {
+                    return read;
+                }
             }
         }
 
         if !triples.is_empty() {
+            read += triples.len();
             let local_triples: BTreeSet<Triple> = triples.into_par_iter().collect();
             self.triples.extend(local_triples);
+            on_progress(read);
         }
+        read
     }
 
 
+    /// Parse Turtle(-star) data. Prefix declarations are collected first so
+    /// every chunk sees them; chunks of whole statements are then parsed in
+    /// parallel and their triples encoded into this database's dictionary.
     pub fn parse_turtle(&mut self, turtle_data: &str) {
+        for triple in self.parse_and_encode_turtle(turtle_data) {
+            self.add_triple(triple);
+        }
+    }
+
+    /// Parse Turtle(-star) data as `parse_turtle` does, without storing the
+    /// triples: they are only encoded. Prefixed names resolve against
+    /// `self.prefixes`, which the data's own declarations extend.
+    pub fn parse_and_encode_turtle(&mut self, turtle_data: &str) -> Vec<Triple> {
+        let mut lines = Vec::new();
         for raw_line in turtle_data.lines() {
             let line = raw_line.trim();
 
@@ -749,108 +1233,136 @@ impl SparqlDatabase {
                     let uri = parts[1].trim_start_matches('<').trim_end_matches('>').to_string();
                     self.prefixes.insert(prefix, uri);
                 } else {
-                    eprintln!("Invalid prefix declaration: {}", line);
+                    warn!("Invalid prefix declaration: {}", line);
                 }
                 continue;
             }
 
-            // Tokenize, but keep ; , . as delimiters only when outside URIs, literals, and quoted triples.
-            let tokens = Self::tokenize_turtle_star_line(line);
+            lines.push(line);
+        }
 
-            let mut subject_raw: Option<String> = None;
-            let mut predicate_raw: Option<String> = None;
-            let mut object_tokens: Vec<String> = Vec::new();
-
-            let mut expect_subject = true;
-            let mut expect_predicate = false;
-            let mut expect_object = false;
-
-            let flush_object = |this: &mut Self,
-                                    subject_raw: &Option<String>,
-                                    predicate_raw: &Option<String>,
-                                    object_tokens: &mut Vec<String>| {
-                if let (Some(s_raw), Some(p_raw)) = (subject_raw.as_ref(), predicate_raw.as_ref()) {
-                    if object_tokens.is_empty() {
-                        return;
-                    }
+        // Close a chunk only after a line ending a statement, so statements
+        // spanning several lines stay in one chunk
+        let chunk_size = 1000;
+        let mut chunks: Vec<&[&str]> = Vec::new();
+        let mut chunk_start = 0;
+        for (i, line) in lines.iter().enumerate() {
+            if i + 1 - chunk_start >= chunk_size && line.ends_with('.') {
+                chunks.push(&lines[chunk_start..=i]);
+                chunk_start = i + 1;
+            }
+        }
+        if chunk_start < lines.len() {
+            chunks.push(&lines[chunk_start..]);
+        }
 
-                    let object_raw = object_tokens.join(" ");
+        let partial_results: Vec<Vec<(String, String, String)>> = chunks
+            .par_iter()
+            .map(|chunk| self.parse_turtle_statements(chunk))
+            .collect();
 
-                    // Handle annotation syntax {| ... |}
-                    let (object_part, annotations) = if let Some(ann_start) = object_raw.find("{|") {
-                        let obj = object_raw[..ann_start].trim().to_string();
+        partial_results
+            .into_iter()
+            .flatten()
+            .map(|(subject, predicate, object)| {
+                if subject.starts_with("<<") || object.starts_with("<<") {
+                    Triple {
+                        subject: self.encode_term_star(&subject),
+                        predicate: self.encode_term_star(&predicate),
+                        object: self.encode_term_star(&object),
+                    }
+                } else {
+                    let mut dict = self.dictionary.write().unwrap();
+                    Triple {
+                        subject: dict.encode(&subject),
+                        predicate: dict.encode(&predicate),
+                        object: dict.encode(&object),
+                    }
+                }
+            })
+            .collect()
+    }
 
-                        if let Some(ann_end) = object_raw.find("|}") {
-                            let ann_content = object_raw[ann_start + 2..ann_end].trim();
-                            let ann_parts: Vec<&str> =
-                                ann_content.splitn(2, char::is_whitespace).collect();
+    // Parse Turtle statements (prefix declarations already removed) into
+    // resolved subject, predicate and object strings. A statement runs until
+    // `.`, or until the end of a line that ends with an object.
+    fn parse_turtle_statements(&self, lines: &[&str]) -> Vec<(String, String, String)> {
+        let mut triples = Vec::new();
+
+        let mut subject_raw: Option<String> = None;
+        let mut predicate_raw: Option<String> = None;
+        let mut object_tokens: Vec<String> = Vec::new();
+
+        let mut expect_subject = true;
+        let mut expect_predicate = false;
+        let mut expect_object = false;
+
+        let mut flush_object = |subject_raw: &Option<String>,
+                                predicate_raw: &Option<String>,
+                                object_tokens: &mut Vec<String>| {
+            if let (Some(s_raw), Some(p_raw)) = (subject_raw.as_ref(), predicate_raw.as_ref()) {
+                if object_tokens.is_empty() {
+                    return;
+                }
 
-                            if ann_parts.len() == 2 {
-                                (obj, vec![(ann_parts[0].to_string(), ann_parts[1].to_string())])
-                            } else {
-                                (obj, vec![])
-                            }
-                        } else {
-                            (object_raw, vec![])
-                        }
-                    } else {
-                        (object_raw, vec![])
-                    };
+                let object_raw = object_tokens.join(" ");
 
-                    let subject = this.resolve_query_term(&Self::clean_turtle_term(s_raw), &this.prefixes);
-                    let predicate = this.resolve_query_term(&Self::clean_turtle_term(p_raw), &this.prefixes);
-                    let object = this.resolve_query_term(&Self::clean_turtle_term(&object_part), &this.prefixes);
+                // Handle annotation syntax {| ... |}
+                let (object_part, annotations) = if let Some(ann_start) = object_raw.find("{|") {
+                    let obj = object_raw[..ann_start].trim().to_string();
 
-                    // Emit the main triple
-                    if subject.starts_with("<<") || object.starts_with("<<") {
-                        let s_id = this.encode_term_star(&subject);
-                        let p_id = this.encode_term_star(&predicate);
-                        let o_id = this.encode_term_star(&object);
-                        let triple = Triple {
-                            subject: s_id,
-                            predicate: p_id,
-                            object: o_id,
-                        };
-                        this.add_triple(triple);
+                    if let Some(ann_end) = object_raw.find("|}") {
+                        let ann_content = object_raw[ann_start + 2..ann_end].trim();
+                        let ann_parts: Vec<&str> =
+                            ann_content.splitn(2, char::is_whitespace).collect();
+
+                        if ann_parts.len() == 2 {
+                            (obj, vec![(ann_parts[0].to_string(), ann_parts[1].to_string())])
+                        } else {
+                            (obj, vec![])
+                        }
                     } else {
-                        let mut dict = this.dictionary.write().unwrap();
-                        let triple = Triple {
-                            subject: dict.encode(&subject),
-                            predicate: dict.encode(&predicate),
-                            object: dict.encode(&object),
-                        };
-                        drop(dict);
-                        this.add_triple(triple);
+                        (object_raw, vec![])
                     }
+                } else {
+                    (object_raw, vec![])
+                };
 
-                    // Emit annotation triples, if any
-                    for (ann_pred, ann_obj) in &annotations {
-                        let qt_str = format!("<< {} {} {} >>", subject, predicate, object);
-                        let qt_id = this.encode_term_star(&qt_str);
-
-                        let ann_p_id = this.encode_term_star(
-                            &this.resolve_query_term(&Self::clean_turtle_term(ann_pred), &this.prefixes),
-                        );
-                        let ann_o_id = this.encode_term_star(
-                            &this.resolve_query_term(&Self::clean_turtle_term(ann_obj), &this.prefixes),
-                        );
-
-                        let ann_triple = Triple {
-                            subject: qt_id,
-                            predicate: ann_p_id,
-                            object: ann_o_id,
-                        };
-                        this.add_triple(ann_triple);
-                    }
+                let subject = self.resolve_query_term(&Self::clean_turtle_term(s_raw), &self.prefixes);
+                // `a` in predicate position is the Turtle shorthand for rdf:type
+                let predicate = if p_raw == "a" {
+                    "http://www.w3.org/1999/02/22-rdf-syntax-ns#type".to_string()
+                } else {
+                    self.resolve_query_term(&Self::clean_turtle_term(p_raw), &self.prefixes)
+                };
+                let object = self.resolve_query_term(&Self::clean_turtle_term(&object_part), &self.prefixes);
 
-                    object_tokens.clear();
-                }
-            };
+                // Annotation triples have the main triple quoted as their subject
+                let annotation_triples: Vec<(String, String, String)> = annotations
+                    .iter()
+                    .map(|(ann_pred, ann_obj)| {
+                        (
+                            format!("<< {} {} {} >>", subject, predicate, object),
+                            self.resolve_query_term(&Self::clean_turtle_term(ann_pred), &self.prefixes),
+                            self.resolve_query_term(&Self::clean_turtle_term(ann_obj), &self.prefixes),
+                        )
+                    })
+                    .collect();
+                triples.push((subject, predicate, object));
+                triples.extend(annotation_triples);
+
+                object_tokens.clear();
+            }
+        };
+
+        for line in lines {
+            // Tokenize, but keep ; , . as delimiters only when outside URIs, literals, and quoted triples.
+            let tokens = Self::tokenize_turtle_star_line(line);
 
             for token in tokens {
                 match token.as_str() {
                     "." => {
-                        flush_object(self, &subject_raw, &predicate_raw, &mut object_tokens);
+                        flush_object(&subject_raw, &predicate_raw, &mut object_tokens);
                         subject_raw = None;
                         predicate_raw = None;
                         expect_subject = true;
@@ -858,13 +1370,13 @@ impl SparqlDatabase {
                         expect_object = false;
                     }
                     ";" => {
-                        flush_object(self, &subject_raw, &predicate_raw, &mut object_tokens);
+                        flush_object(&subject_raw, &predicate_raw, &mut object_tokens);
                         predicate_raw = None;
                         expect_predicate = true;
                         expect_object = false;
                     }
                     "," => {
-                        flush_object(self, &subject_raw, &predicate_raw, &mut object_tokens);
+                        flush_object(&subject_raw, &predicate_raw, &mut object_tokens);
                         expect_object = true;
                     }
                     _ => {
@@ -886,9 +1398,18 @@ impl SparqlDatabase {
                 }
             }
 
-            // Flush any trailing object if the line does not end with '.'
-            flush_object(self, &subject_raw, &predicate_raw, &mut object_tokens);
+            // A line ending with an object but no '.' still ends its statement
+            if !object_tokens.is_empty() {
+                flush_object(&subject_raw, &predicate_raw, &mut object_tokens);
+                subject_raw = None;
+                predicate_raw = None;
+                expect_subject = true;
+                expect_predicate = false;
+                expect_object = false;
+            }
         }
+
+        triples
     }
 
     /// Tokenize a Turtle-star line, keeping `<< ... >>` and punctuation structure intact.
@@ -1006,6 +1527,9 @@ impl SparqlDatabase {
             term[1..term.len() - 1].to_string()
         } else if term.starts_with('"') && term.ends_with('"') {
             term[1..term.len() - 1].to_string()
+        } else if !utils::language_tag(term).is_empty() || term.contains("\"^^") {
+            // Tagged and typed literals keep their quotes: "chat"@fr, "42"^^xsd:integer
+            term.to_string()
         } else {
             term.trim_matches('"').to_string()
         }
@@ -1042,7 +1566,7 @@ impl SparqlDatabase {
                             let uri = parts[1].trim_start_matches('<').trim_end_matches('>').to_string();
                             local_db.prefixes.insert(prefix, uri);
                         } else {
-                            eprintln!("Invalid prefix declaration: {}", line);
+                            warn!("Invalid prefix declaration: {}", line);
                         }
                     } else {
                         statement.push_str(line);
@@ -1073,6 +1597,14 @@ impl SparqlDatabase {
     }
 
     // Parse_ntriples and add to DB function
+    /// Parse a JSON-LD document (see `crate::jsonld` for the supported
+    /// subset) and add its triples; fails only on malformed JSON.
+    pub fn parse_jsonld(&mut self, jsonld_data: &str) -> Result<(), String> {
+        let ntriples = crate::jsonld::jsonld_to_ntriples(jsonld_data)?;
+        self.parse_ntriples_and_add(&ntriples);
+        Ok(())
+    }
+
     pub fn parse_ntriples_and_add(&mut self, ntriples_data: &str) {
         let partial_results = self.parse_ntriples(ntriples_data);
 
@@ -1082,6 +1614,45 @@ impl SparqlDatabase {
         }
     }
 
+    /// Stream N-Triples from `reader` into the database one line at a time,
+    /// so inputs larger than memory can be loaded. Returns the number of
+    /// triples parsed.
+    pub fn parse_ntriples_streaming(&mut self, reader: &mut dyn BufRead) -> std::io::Result<usize> {
+        self.parse_ntriples_streaming_with_threshold(reader, NTRIPLES_FLUSH_THRESHOLD)
+    }
+
+    /// Like `parse_ntriples_streaming`, but holds at most `flush_threshold`
+    /// pending lines before they are parsed and inserted.
+    pub fn parse_ntriples_streaming_with_threshold(
+        &mut self,
+        reader: &mut dyn BufRead,
+        flush_threshold: usize,
+    ) -> std::io::Result<usize> {
+        let flush_threshold = flush_threshold.max(1);
+        let mut pending = String::new();
+        let mut pending_lines = 0;
+        let mut parsed = 0;
+
+        loop {
+            let read = reader.read_line(&mut pending)?;
+            if read > 0 {
+                pending_lines += 1;
+            }
+            if pending_lines >= flush_threshold || (read == 0 && pending_lines > 0) {
+                let triples = self.parse_and_encode_ntriples(&pending);
+                parsed += triples.len();
+                for triple in triples {
+                    self.add_triple(triple);
+                }
+                pending.clear();
+                pending_lines = 0;
+            }
+            if read == 0 {
+                return Ok(parsed);
+            }
+        }
+    }
+
     // Parses ntriples
     pub fn parse_ntriples(&mut self, ntriples_data: &str) -> Vec<Vec<(String, String, String)>> {
         let lines: Vec<&str> = ntriples_data.lines().collect();
@@ -1103,7 +1674,7 @@ impl SparqlDatabase {
 
                     // N-Triples must end with a dot
                     if !line.ends_with('.') {
-                        eprintln!("Invalid N-Triples line (missing dot): {}", line);
+                        warn!("Invalid N-Triples line (missing dot): {}", line);
                         continue;
                     }
 
@@ -1294,7 +1865,7 @@ impl SparqlDatabase {
             let object = self.clean_ntriples_term(&parts[2]);
             Some((subject, predicate, object))
         } else {
-            eprintln!("Invalid N-Triples line (expected 3 parts, got {}): {}", parts.len(), line);
+            warn!("Invalid N-Triples line (expected 3 parts, got {}): {}", parts.len(), line);
             None
         }
     }
@@ -1322,9 +1893,10 @@ impl SparqlDatabase {
                 if rest.is_empty() {
                     return literal_value.to_string();
                 } else if rest.starts_with("^^") {
-                    return literal_value.to_string();
+                    return utils::canonical_typed_literal(term, str::to_string)
+                        .unwrap_or_else(|| literal_value.to_string());
                 } else if rest.starts_with("@") {
-                    return format!("{}{}", literal_value, rest);
+                    return format!("\"{}\"{}", literal_value, rest);
                 }
             }
         }
@@ -1407,10 +1979,11 @@ impl SparqlDatabase {
                 let mut result = literal.to_string();
                 if rest.starts_with("^^") {
                     // It's a typed literal
-                    let datatype = rest[2..].trim();
-                    let resolved_datatype = self.resolve_term(datatype);
-                    result.push_str("^^");
-                    result.push_str(&resolved_datatype);
+                    if let Some(typed) = utils::canonical_typed_literal(term.trim(), |datatype| {
+                        self.resolve_term(datatype)
+                    }) {
+                        result = typed;
+                    }
                 } else if rest.starts_with('@') {
                     // It's a language-tagged literal
                     result.push_str(rest);
@@ -1430,7 +2003,7 @@ impl SparqlDatabase {
             if let Some(uri) = self.prefixes.get(prefix) {
                 format!("{}{}", uri, local_name)
             } else {
-                eprintln!("Unknown prefix: {}", prefix);
+                warn!("Unknown prefix: {}", prefix);
                 term.to_string()
             }
         } else {
@@ -1440,16 +2013,18 @@ impl SparqlDatabase {
 
     // Method to automatically extract and register prefixes from a query string
     pub fn register_prefixes_from_query(&mut self, query: &str) {
+        self.prefixes.extend(Self::prefixes_declared_in(query));
+    }
+
+    /// The `PREFIX` declarations of `query` as (prefix, namespace) pairs
+    pub fn prefixes_declared_in(query: &str) -> Vec<(String, String)> {
         // Simple regex to extract PREFIX declarations
         let prefix_pattern = regex::Regex::new(r"PREFIX\s+([a-zA-Z0-9_]+):\s*<([^>]+)>").unwrap();
-        
-        for captures in prefix_pattern.captures_iter(query) {
-            if captures.len() >= 3 {
-                let prefix = captures[1].to_string();
-                let uri = captures[2].to_string();
-                self.prefixes.insert(prefix, uri);
-            }
-        }
+
+        prefix_pattern
+            .captures_iter(query)
+            .map(|captures| (captures[1].to_string(), captures[2].to_string()))
+            .collect()
     }
     
     // Method to ensure prefixes are properly shared between components
@@ -1464,6 +2039,15 @@ impl SparqlDatabase {
             // Keep quoted triple patterns as-is (they'll be handled downstream)
             return term.to_string();
         }
+        if term.starts_with("_:") {
+            // Blank node labels are stored as written, not as prefixed names
+            return term.to_string();
+        }
+        if let Some(literal) = utils::canonical_typed_literal(term, |datatype| {
+            self.resolve_query_term(datatype, prefixes)
+        }) {
+            return literal;
+        }
         if term.starts_with('<') && term.ends_with('>') {
             term.trim_start_matches('<')
                 .trim_end_matches('>')
@@ -1486,7 +2070,7 @@ impl SparqlDatabase {
             else if let Some(uri) = self.prefixes.get(prefix) {
                 format!("{}{}", uri, local_name)
             } else {
-                eprintln!("Unknown prefix in query: {}", prefix);
+                warn!("Unknown prefix in query: {}", prefix);
                 term.to_string()
             }
         } else {
@@ -1535,13 +2119,17 @@ impl SparqlDatabase {
                                 // For simple expressions without arithmetic operators, use the SIMD approach
                                 if let Some(var_value_str) = result.get(var) {
                                     // First, try parsing both values as numbers
-                                    let var_value_num = var_value_str.parse::<i32>();
-                                    let filter_value_num = value.parse::<i32>();
+                                    let var_value_num = var_value_str.parse::<i64>();
+                                    let filter_value_num = value.parse::<i64>();
     
-                                    if var_value_num.is_ok() && filter_value_num.is_ok() {
+                                    if let (Ok(var_wide), Ok(filter_wide)) = (var_value_num, filter_value_num) {
                                         // Both values are numeric, perform SIMD numeric comparison
-                                        let var_value = var_value_num.unwrap();
-                                        let filter_value = filter_value_num.unwrap();
+                                        // The 32-bit lanes below would wrap anything outside i32
+                                        let (var_value, filter_value) =
+                                            match (i32::try_from(var_wide), i32::try_from(filter_wide)) {
+                                                (Ok(var_value), Ok(filter_value)) => (var_value, filter_value),
+                                                _ => return compare_i64_simd(var_wide, filter_wide, operator),
+                                            };
     
                                         // On x86 (SSE2) or x86_64 (SSE2) use SIMD intrinsics
                                         #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
@@ -1778,6 +2366,34 @@ impl SparqlDatabase {
                                 _ => false,
                             }
                         }
+                        FilterExpression::FunctionComparison(func_name, args, operator, value) => {
+                            self.evaluate_function_comparison(result, func_name, args, operator, value)
+                        }
+                        FilterExpression::StringFunction(func, args) => {
+                            evaluate_string_filter(*func, args, None, &|var: &str| result.get(var).cloned())
+                        }
+                        FilterExpression::StringFunctionComparison(func, args, operator, value) => {
+                            evaluate_string_filter(*func, args, Some((operator, value)), &|var: &str| {
+                                result.get(var).cloned()
+                            })
+                        }
+                        FilterExpression::NumericFunction(func, args, operator, value) => {
+                            evaluate_numeric_filter(*func, args, operator, value, &|var: &str| {
+                                result.get(var).cloned()
+                            })
+                        }
+                        FilterExpression::Coalesce(args) => {
+                            evaluate_coalesce(args, &|var: &str| result.get(var).cloned()).is_some()
+                        }
+                        FilterExpression::If(condition, then, otherwise, comparison) => {
+                            let holds = self.evaluate_filter_expression(result, condition);
+                            evaluate_if_filter(holds, then, otherwise, *comparison, &|var: &str| {
+                                result.get(var).cloned()
+                            })
+                        }
+                        FilterExpression::Iri(arg, comparison) => {
+                            evaluate_iri_filter(arg, *comparison, &|var: &str| result.get(var).cloned())
+                        }
                     }
                 })
             })
@@ -1796,7 +2412,7 @@ impl SparqlDatabase {
                 if operand.starts_with('?') {
                     if let Some(var_value) = result.get(*operand) {
                         // Parse the variable value as a number
-                        var_value.parse::<f64>().map_err(|_| format!("Cannot parse '{}' as a number", var_value))
+                        parse_numeric_literal(var_value).ok_or_else(|| format!("Cannot parse '{}' as a number", var_value))
                     } else {
                         Err(format!("Variable '{}' not found", operand))
                     }
@@ -1864,13 +2480,13 @@ impl SparqlDatabase {
                 },
                 Err(e) => {
                     // Print the error
-                    eprintln!("Failed to parse arithmetic expression '{}': {:?}", expr_to_parse, e);
+                    warn!("Failed to parse arithmetic expression '{}': {:?}", expr_to_parse, e);
                     
                     // If parsing fails, try to treat it as a simple operand
                     if expr_to_parse.starts_with('?') {
                         // It's a variable
                         if let Some(var_value) = result.get(expr_to_parse) {
-                            var_value.parse::<f64>().map_err(|_| format!("Cannot parse '{}' as a number", var_value))
+                            parse_numeric_literal(var_value).ok_or_else(|| format!("Cannot parse '{}' as a number", var_value))
                         } else {
                             Err(format!("Variable '{}' not found", expr_to_parse))
                         }
@@ -1885,7 +2501,7 @@ impl SparqlDatabase {
             if expr_to_parse.starts_with('?') {
                 // It's a variable
                 if let Some(var_value) = result.get(expr_to_parse) {
-                    var_value.parse::<f64>().map_err(|_| format!("Cannot parse '{}' as a number", var_value))
+                    parse_numeric_literal(var_value).ok_or_else(|| format!("Cannot parse '{}' as a number", var_value))
                 } else {
                     Err(format!("Variable '{}' not found", expr_to_parse))
                 }
@@ -1897,7 +2513,7 @@ impl SparqlDatabase {
     }
 
     // Helper method to evaluate a filter expression against a result
-    fn evaluate_filter_expression<'a>(
+    pub(crate) fn evaluate_filter_expression<'a>(
         &self,
         result: &BTreeMap<&'a str, String>,
         filter_expr: &FilterExpression<'a>
@@ -1984,62 +2600,159 @@ impl SparqlDatabase {
                     _ => false,
                 }
             }
+            FilterExpression::FunctionComparison(func_name, args, operator, value) => {
+                self.evaluate_function_comparison(result, func_name, args, operator, value)
+            }
+            FilterExpression::StringFunction(func, args) => {
+                evaluate_string_filter(*func, args, None, &|var: &str| result.get(var).cloned())
+            }
+            FilterExpression::StringFunctionComparison(func, args, operator, value) => {
+                evaluate_string_filter(*func, args, Some((operator, value)), &|var: &str| {
+                    result.get(var).cloned()
+                })
+            }
+            FilterExpression::NumericFunction(func, args, operator, value) => {
+                evaluate_numeric_filter(*func, args, operator, value, &|var: &str| {
+                    result.get(var).cloned()
+                })
+            }
+            FilterExpression::Coalesce(args) => {
+                evaluate_coalesce(args, &|var: &str| result.get(var).cloned()).is_some()
+            }
+            FilterExpression::If(condition, then, otherwise, comparison) => {
+                let holds = self.evaluate_filter_expression(result, condition);
+                evaluate_if_filter(holds, then, otherwise, *comparison, &|var: &str| {
+                    result.get(var).cloned()
+                })
+            }
+            FilterExpression::Iri(arg, comparison) => {
+                evaluate_iri_filter(arg, *comparison, &|var: &str| result.get(var).cloned())
+            }
         }
     }
 
-    pub fn union(&mut self, other: &SparqlDatabase) -> Self {
-        // Create a new dictionary by cloning and merging
-        let self_dict = self.dictionary.read().unwrap();
-        let other_dict = other.dictionary.read().unwrap();
-        let mut merged_dictionary = self_dict.clone();
-        drop(self_dict);
+    // Call a UDF with the row's values and compare its result against `value`
+    fn evaluate_function_comparison(
+        &self,
+        result: &BTreeMap<&str, String>,
+        func_name: &str,
+        args: &[&str],
+        operator: &str,
+        value: &str,
+    ) -> bool {
+        let Some(func) = lookup_udf(&self.udfs, func_name) else {
+            error!("UDF {} not found", func_name);
+            return false;
+        };
+        let resolve = |term: &str| -> Option<String> {
+            if term.starts_with('?') {
+                result.get(term).cloned()
+            } else {
+                Some(term.to_string())
+            }
+        };
+        let Some(resolved_args) = args.iter().map(|arg| resolve(arg)).collect::<Option<Vec<String>>>() else {
+            return false;
+        };
+        let Some(rhs) = resolve(value) else {
+            return false;
+        };
+        let output = func.call(resolved_args.iter().map(String::as_str).collect());
+        compare_filter_values(&output, operator, &rhs)
+    }
 
-        // Re-encode triples from the other database using the merged dictionary
-        let mut re_encoded_triples = BTreeSet::new();
-        for triple in &other.triples {
-            let subject =
-                merged_dictionary.encode(other_dict.decode(triple.subject).unwrap());
-            let predicate =
-                merged_dictionary.encode(other_dict.decode(triple.predicate).unwrap());
-            let object = merged_dictionary.encode(other_dict.decode(triple.object).unwrap());
-            re_encoded_triples.insert(Triple {
-                subject,
-                predicate,
-                object,
-            });
+    /// The triples of both databases, encoded in this database's dictionary;
+    /// terms only `other` knows are added to it. The result shares the
+    /// dictionary and quoted triple store with `self` rather than copying them.
+    pub fn union(&self, other: &SparqlDatabase) -> Self {
+        if self.shares_ids_with(other) {
+            // Same ID space: the triple sets can be merged as they are
+            let union_triples = self.triples.union(&other.triples).cloned().collect();
+            let mut merged_seeds = self.probability_seeds.clone();
+            merged_seeds.extend(other.probability_seeds.iter().map(|(triple, prob)| (triple.clone(), *prob)));
+            return self.derive(union_triples, merged_seeds);
         }
 
-        // Merge the triples
-        let union_triples: BTreeSet<Triple> =
-            self.triples.union(&re_encoded_triples).cloned().collect();
+        let mut id_map = HashMap::new();
+        let mut re_encode = |triple: &Triple| Triple {
+            subject: self.re_encode_id(other, triple.subject, &mut id_map),
+            predicate: self.re_encode_id(other, triple.predicate, &mut id_map),
+            object: self.re_encode_id(other, triple.object, &mut id_map),
+        };
+
+        let mut union_triples = self.triples.clone();
+        union_triples.extend(other.triples.iter().map(&mut re_encode));
         let mut merged_seeds = self.probability_seeds.clone();
-        for (triple, prob) in &other.probability_seeds {
-            let subject = merged_dictionary.encode(other_dict.decode(triple.subject).unwrap());
-            let predicate = merged_dictionary.encode(other_dict.decode(triple.predicate).unwrap());
-            let object = merged_dictionary.encode(other_dict.decode(triple.object).unwrap());
-            merged_seeds.insert(Triple { subject, predicate, object }, *prob);
-        }
+        merged_seeds.extend(
+            other
+                .probability_seeds
+                .iter()
+                .map(|(triple, prob)| (re_encode(triple), *prob)),
+        );
+
+        self.derive(union_triples, merged_seeds)
+    }
 
-        Self {
-            triples: union_triples,
-            dictionary: Arc::new(RwLock::new(merged_dictionary)),
-            prefixes: self.prefixes.clone(),
-            udfs: HashMap::new(),
-            index_manager: UnifiedIndex::new(),
-            rule_map: HashMap::new(),
-            model_decls: self.model_decls.clone(),
-            neural_relation_decls: self.neural_relation_decls.clone(),
-            train_neural_relation_decls: self.train_neural_relation_decls.clone(),
-            neural_model_artifacts: self.neural_model_artifacts.clone(),
-            neural_materialized_triples: self.neural_materialized_triples.clone(),
-            ml_predict_materialized_triples: self.ml_predict_materialized_triples.clone(),
-            probability_seeds: merged_seeds,
-            cached_stats: None,
-            quoted_triple_store: Arc::clone(&self.quoted_triple_store),
+    /// Whether `other` encodes terms and quoted triples with the same
+    /// dictionary and quoted triple store, as clones of one database do
+    pub fn shares_ids_with(&self, other: &SparqlDatabase) -> bool {
+        Arc::ptr_eq(&self.dictionary, &other.dictionary)
+            && Arc::ptr_eq(&self.quoted_triple_store, &other.quoted_triple_store)
+    }
+
+    /// Triples added and removed going from `self` to `other`, as
+    /// `(in other but not in self, in self but not in other)`. Both sets are
+    /// encoded in `self`'s dictionary; terms only `other` knows are added to it.
+    pub fn diff(&self, other: &SparqlDatabase) -> (BTreeSet<Triple>, BTreeSet<Triple>) {
+        let re_encoded_triples: BTreeSet<Triple> = if self.shares_ids_with(other) {
+            // Snapshots cloned from one another already share an ID space
+            other.triples.clone()
+        } else {
+            let mut id_map = HashMap::new();
+            other
+                .triples
+                .iter()
+                .map(|triple| Triple {
+                    subject: self.re_encode_id(other, triple.subject, &mut id_map),
+                    predicate: self.re_encode_id(other, triple.predicate, &mut id_map),
+                    object: self.re_encode_id(other, triple.object, &mut id_map),
+                })
+                .collect()
+        };
+
+        let added = re_encoded_triples.difference(&self.triples).cloned().collect();
+        let removed = self.triples.difference(&re_encoded_triples).cloned().collect();
+        (added, removed)
+    }
+
+    // Map an ID from `other`'s dictionary (or quoted triple store) into ours
+    fn re_encode_id(&self, other: &SparqlDatabase, id: u32, id_map: &mut HashMap<u32, u32>) -> u32 {
+        if let Some(&mapped) = id_map.get(&id) {
+            return mapped;
         }
+        let mapped = if is_quoted_triple_id(id) {
+            let parts = other.quoted_triple_store.read().unwrap().decode(id);
+            match parts {
+                Some((s, p, o)) => {
+                    let s = self.re_encode_id(other, s, id_map);
+                    let p = self.re_encode_id(other, p, id_map);
+                    let o = self.re_encode_id(other, o, id_map);
+                    self.quoted_triple_store.write().unwrap().encode(s, p, o)
+                }
+                None => id,
+            }
+        } else {
+            let term = other.dictionary.read().unwrap().decode(id).map(str::to_string);
+            match term {
+                Some(term) => self.dictionary.write().unwrap().encode(&term),
+                None => id,
+            }
+        };
+        id_map.insert(id, mapped);
+        mapped
     }
 
-    pub fn par_join(&mut self, other: &SparqlDatabase, predicate: &str) -> Self {
+    pub fn par_join(&self, other: &SparqlDatabase, predicate: &str) -> Self {
         let mut dict = self.dictionary.write().unwrap();
         let predicate_id = dict.encode(predicate);
         drop(dict);
@@ -2097,8 +2810,88 @@ impl SparqlDatabase {
                 },
             );
 
+        self.derive(joined_triples, HashMap::new())
+    }
+
+    /// About `n` triples forming a representative subset, in subject order.
+    /// Subjects are drawn by reservoir sampling and every triple of a drawn
+    /// subject is kept, so sampled entities keep all their properties; the
+    /// result is all triples when `n` is at least the store size.
+    pub fn sample_triples<R: Rng + ?Sized>(&self, n: usize, rng: &mut R) -> Vec<Triple> {
+        if n >= self.triples.len() {
+            return self.triples.iter().cloned().collect();
+        }
+        // `triples` is ordered by subject, so each subject's triples are adjacent
+        let subjects = || {
+            let mut previous = None;
+            self.triples.iter().filter_map(move |triple| {
+                if previous != Some(triple.subject) {
+                    previous = Some(triple.subject);
+                    Some(triple.subject)
+                } else {
+                    None
+                }
+            })
+        };
+        let subject_count = subjects().count();
+        let wanted = (n * subject_count).div_ceil(self.triples.len()).max(1);
+
+        let mut reservoir = Vec::with_capacity(wanted);
+        for (seen, subject) in subjects().enumerate() {
+            if seen < wanted {
+                reservoir.push(subject);
+            } else {
+                let slot = rng.random_range(0..=seen);
+                if slot < wanted {
+                    reservoir[slot] = subject;
+                }
+            }
+        }
+        reservoir.sort_unstable();
+        reservoir
+            .into_iter()
+            .flat_map(|subject| {
+                let first = Triple { subject, predicate: 0, object: 0 };
+                let last = Triple { subject, predicate: u32::MAX, object: u32::MAX };
+                self.triples.range(first..=last).cloned()
+            })
+            .collect()
+    }
+
+    /// A new database with about `n` of these triples, chosen as by
+    /// `sample_triples`, for developing and testing against large datasets.
+    /// Its dictionary holds only the terms of the sampled triples; prefixes
+    /// and UDFs are copied and its indexes are built.
+    pub fn sample(&self, n: usize) -> SparqlDatabase {
+        let triples = self.sample_triples(n, &mut rand::rng());
+        let mut sampled = SparqlDatabase::new_with_config(self.tuning);
+        sampled.prefixes = self.prefixes.clone();
+        sampled.udfs = self.udfs.clone();
+        {
+            let mut copier = TermCopier {
+                source: &self.dictionary.read().unwrap(),
+                source_quoted: &self.quoted_triple_store.read().unwrap(),
+                target: &mut sampled.dictionary.write().unwrap(),
+                target_quoted: &mut sampled.quoted_triple_store.write().unwrap(),
+                copied: HashMap::new(),
+            };
+            for triple in triples {
+                sampled.triples.insert(Triple {
+                    subject: copier.copy(triple.subject),
+                    predicate: copier.copy(triple.predicate),
+                    object: copier.copy(triple.object),
+                });
+            }
+        }
+        sampled.build_all_indexes();
+        sampled
+    }
+
+    /// A database holding `triples` that shares this one's dictionary and
+    /// quoted triple store. Its indexes are left for `ensure_indexes`.
+    fn derive(&self, triples: BTreeSet<Triple>, probability_seeds: HashMap<Triple, f64>) -> Self {
         Self {
-            triples: joined_triples,
+            triples,
             dictionary: Arc::clone(&self.dictionary),
             prefixes: self.prefixes.clone(),
             udfs: HashMap::new(),
@@ -2110,52 +2903,51 @@ impl SparqlDatabase {
             neural_model_artifacts: self.neural_model_artifacts.clone(),
             neural_materialized_triples: self.neural_materialized_triples.clone(),
             ml_predict_materialized_triples: self.ml_predict_materialized_triples.clone(),
-            probability_seeds: HashMap::new(),
+            probability_seeds,
             cached_stats: None,
             quoted_triple_store: Arc::clone(&self.quoted_triple_store),
+            entailment_regime: self.entailment_regime,
+            entailed_triples: BTreeSet::new(),
+            entailment_dirty: true,
+            entailed_from_len: 0,
+            indexes_pending: true,
+            partitioned_store: None,
+            derivation_log: None,
+            cancellation: None,
+            profiler: None,
+            join_algorithm: None,
+            query_cache: None,
+            pattern_cache: None,
+            text_index: None,
+            text_index_enabled: self.text_index_enabled,
+            max_join_results: self.max_join_results,
+            tuning: self.tuning,
+            inference_budget: self.inference_budget,
         }
     }
 
-    pub fn perform_join<'a>(
+    pub fn perform_join(
         &self,
-        subject_var: &'a str,
-        predicate: &'a str,
-        object_var: &'a str,
+        subject_var: &str,
+        predicate: &str,
+        object_var: &str,
         triples: Vec<Triple>,
-        dictionary: &'a Dictionary,
-        final_results: Vec<BTreeMap<&'a str, String>>,
-    ) -> Vec<BTreeMap<&'a str, String>> {
+        dictionary: &Dictionary,
+        final_results: Vec<Binding>,
+    ) -> Vec<Binding> {
+        let Some((predicate_id, _)) = join_term_ids(dictionary, predicate, None) else {
+            return Vec::new();
+        };
+        let subject_key: Arc<str> = Arc::from(subject_var);
+        let object_key: Arc<str> = Arc::from(object_var);
         let mut new_results = Vec::new();
 
         for triple in triples {
-            let subject = dictionary.decode(triple.subject).unwrap();
-            let pred = dictionary.decode(triple.predicate).unwrap();
-            let object = dictionary.decode(triple.object).unwrap();
-
-            if pred == predicate {
+            if triple.predicate == predicate_id {
                 for result in &final_results {
-                    let mut extended_result = result.clone();
-                    let mut valid_extension = true;
-
-                    // Check and extend the result with the subject
-                    if let Some(existing_subject) = extended_result.get(subject_var) {
-                        if existing_subject != &subject {
-                            valid_extension = false;
-                        }
-                    } else {
-                        extended_result.insert(subject_var, subject.to_string());
-                    }
-
-                    // Check and extend the result with the object
-                    if let Some(existing_object) = extended_result.get(object_var) {
-                        if existing_object != &object {
-                            valid_extension = false;
-                        }
-                    } else {
-                        extended_result.insert(object_var, object.to_string());
-                    }
-
-                    if valid_extension {
+                    if let Some(extended_result) =
+                        extend_binding(result, &subject_key, triple.subject, &object_key, triple.object)
+                    {
                         new_results.push(extended_result);
                     }
                 }
@@ -2165,52 +2957,48 @@ impl SparqlDatabase {
         new_results
     }
 
-    pub fn perform_join_par_simd_with_strict_filter_1<'a>(
+    pub fn perform_join_par_simd_with_strict_filter_1(
         &self,
-        subject_var: &'a str,
-        predicate: String,
-        object_var: &'a str,
+        pattern: JoinPattern<'_>,
         triples: Vec<Triple>,
         dictionary: &Arc<RwLock<Dictionary>>,
-        final_results: Vec<BTreeMap<&'a str, String>>,
-        literal_filter: Option<String>,
-    ) -> Vec<BTreeMap<&'a str, String>> {
+        final_results: Vec<Binding>,
+    ) -> Vec<Binding> {
+        let JoinPattern { subject_var, predicate, object_var, literal_filter } = pattern;
         if final_results.is_empty() {
             return Vec::new();
         }
 
-        let dictionary = dictionary.read().unwrap();
-
-        let predicate_bytes = predicate.as_bytes();
-        let literal_filter_bytes = literal_filter.as_ref().map(|s| s.as_bytes());
+        let term_ids = join_term_ids(&dictionary.read().unwrap(), &predicate, literal_filter.as_deref());
+        let Some((predicate_id, literal_filter_id)) = term_ids else {
+            return Vec::new();
+        };
+        let subject_key: Arc<str> = Arc::from(subject_var);
+        let object_key: Arc<str> = Arc::from(object_var);
 
-        // Partition final_results into groups based on variable bindings
-        let mut both_vars_bound: HashMap<(String, String), Vec<BTreeMap<&'a str, String>>> =
-            HashMap::new();
-        let mut subject_var_bound: HashMap<String, Vec<BTreeMap<&'a str, String>>> = HashMap::new();
-        let mut object_var_bound: HashMap<String, Vec<BTreeMap<&'a str, String>>> = HashMap::new();
-        let mut neither_var_bound: Vec<BTreeMap<&'a str, String>> = Vec::new();
+        // Partition final_results by the term IDs bound to the pattern's variables
+        let mut both_vars_bound: HashMap<(u32, u32), Vec<Binding>> = HashMap::new();
+        let mut subject_var_bound: HashMap<u32, Vec<Binding>> = HashMap::new();
+        let mut object_var_bound: HashMap<u32, Vec<Binding>> = HashMap::new();
+        let mut neither_var_bound: Vec<Binding> = Vec::new();
 
         for result in final_results {
-            let subject_binding = result.get(subject_var).cloned();
-            let object_binding = result.get(object_var).cloned();
-
-            match (subject_binding, object_binding) {
-                (Some(subj_val), Some(obj_val)) => {
+            match (result.id(subject_var), result.id(object_var)) {
+                (Some(subj_id), Some(obj_id)) => {
                     both_vars_bound
-                        .entry((subj_val.clone(), obj_val.clone()))
+                        .entry((subj_id, obj_id))
                         .or_default()
                         .push(result);
                 }
-                (Some(subj_val), None) => {
+                (Some(subj_id), None) => {
                     subject_var_bound
-                        .entry(subj_val.clone())
+                        .entry(subj_id)
                         .or_default()
                         .push(result);
                 }
-                (None, Some(obj_val)) => {
+                (None, Some(obj_id)) => {
                     object_var_bound
-                        .entry(obj_val.clone())
+                        .entry(obj_id)
                         .or_default()
                         .push(result);
                 }
@@ -2228,89 +3016,29 @@ impl SparqlDatabase {
             let mut local_results = Vec::new();
 
             for triple in chunk {
-                if let (Some(subject), Some(pred), Some(object)) = (
-                    dictionary.decode(triple.subject),
-                    dictionary.decode(triple.predicate),
-                    dictionary.decode(triple.object),
-                ) {
-                    // SIMD predicate comparison
-                    if pred.as_bytes() != predicate_bytes {
-                        continue;
-                    }
-
-                    // SIMD literal filter comparison
-                    if let Some(filter_bytes) = literal_filter_bytes {
-                        if object.as_bytes() != filter_bytes {
-                            continue;
-                        }
-                    }
-
-                    // Process group both_vars_bound
-                    {
-                        let key = (subject.to_string(), object.to_string());
-                        if let Some(results_vec) = both_vars_bound.get(&key) {
-                            for result in results_vec {
-                                let extended_result = result.clone();
-                                local_results.push(extended_result);
-                            }
-                        }
-                    }
+                // Predicate and literal filter comparison
+                if triple.predicate != predicate_id
+                    || literal_filter_id.is_some_and(|id| triple.object != id)
+                {
+                    continue;
+                }
 
-                    // Process group subject_var_bound
-                    {
-                        if let Some(results_vec) = subject_var_bound.get(subject) {
-                            for result in results_vec {
-                                let mut extended_result = result.clone();
-                                // Extend object_var
-                                if let Some(existing_object) = extended_result.get(object_var) {
-                                    if existing_object != &object {
-                                        continue; // Inconsistent variable binding
-                                    }
-                                } else {
-                                    extended_result.insert(object_var, object.to_string());
-                                }
-                                local_results.push(extended_result);
-                            }
-                        }
-                    }
+                // Process group both_vars_bound
+                if let Some(results_vec) = both_vars_bound.get(&(triple.subject, triple.object)) {
+                    local_results.extend(results_vec.iter().cloned());
+                }
 
-                    // Process group object_var_bound
+                // Process groups subject_var_bound, object_var_bound and neither_var_bound
+                let partially_bound = subject_var_bound
+                    .get(&triple.subject)
+                    .into_iter()
+                    .chain(object_var_bound.get(&triple.object))
+                    .flatten()
+                    .chain(&neither_var_bound);
+                for result in partially_bound {
+                    if let Some(extended_result) =
+                        extend_binding(result, &subject_key, triple.subject, &object_key, triple.object)
                     {
-                        if let Some(results_vec) = object_var_bound.get(object) {
-                            for result in results_vec {
-                                let mut extended_result = result.clone();
-                                // Extend subject_var
-                                if let Some(existing_subject) = extended_result.get(subject_var) {
-                                    if existing_subject != &subject {
-                                        continue; // Inconsistent variable binding
-                                    }
-                                } else {
-                                    extended_result.insert(subject_var, subject.to_string());
-                                }
-                                local_results.push(extended_result);
-                            }
-                        }
-                    }
-
-                    // Process group neither_var_bound
-                    for result in &neither_var_bound {
-                        let mut extended_result = result.clone();
-                        // Extend subject_var
-                        if let Some(existing_subject) = extended_result.get(subject_var) {
-                            if existing_subject != &subject {
-                                continue; // Inconsistent variable binding
-                            }
-                        } else {
-                            extended_result.insert(subject_var, subject.to_string());
-                        }
-                        // Extend object_var
-                        if let Some(existing_object) = extended_result.get(object_var) {
-                            if existing_object != &object {
-                                continue; // Inconsistent variable binding
-                            }
-                        } else {
-                            extended_result.insert(object_var, object.to_string());
-                        }
                         local_results.push(extended_result);
                     }
                 }
@@ -2324,45 +3052,45 @@ impl SparqlDatabase {
         results.into_inner().unwrap()
     }
 
-    pub fn perform_join_par_simd_with_strict_filter_2<'a>(
+    pub fn perform_join_par_simd_with_strict_filter_2(
         &self,
-        subject_var: &'a str,
-        predicate: String,
-        object_var: &'a str,
+        pattern: JoinPattern<'_>,
         triples: Vec<Triple>,
-        dictionary: &'a Dictionary,
-        final_results: Vec<BTreeMap<&'a str, String>>,
-        literal_filter: Option<String>,
-    ) -> Vec<BTreeMap<&'a str, String>> {
+        dictionary: &Dictionary,
+        final_results: Vec<Binding>,
+    ) -> Vec<Binding> {
+        let JoinPattern { subject_var, predicate, object_var, literal_filter } = pattern;
         if final_results.is_empty() {
             return Vec::new();
         }
 
-        let predicate_bytes = predicate.as_bytes();
-        let literal_filter_bytes = literal_filter.as_ref().map(|s| s.as_bytes());
+        let Some((predicate_id, literal_filter_id)) =
+            join_term_ids(dictionary, &predicate, literal_filter.as_deref())
+        else {
+            return Vec::new();
+        };
+        let subject_key: Arc<str> = Arc::from(subject_var);
+        let object_key: Arc<str> = Arc::from(object_var);
 
-        // Partition final_results into groups based on variable bindings.
-        let mut both_vars_bound: HashMap<(String, String), Vec<BTreeMap<&'a str, String>>> = HashMap::new();
-        let mut subject_var_bound: HashMap<String, Vec<BTreeMap<&'a str, String>>> = HashMap::new();
-        let mut object_var_bound: HashMap<String, Vec<BTreeMap<&'a str, String>>> = HashMap::new();
-        let mut neither_var_bound: Vec<BTreeMap<&'a str, String>> = Vec::new();
+        // Partition final_results by the term IDs bound to the pattern's variables.
+        let mut both_vars_bound: HashMap<(u32, u32), Vec<Binding>> = HashMap::new();
+        let mut subject_var_bound: HashMap<u32, Vec<Binding>> = HashMap::new();
+        let mut object_var_bound: HashMap<u32, Vec<Binding>> = HashMap::new();
+        let mut neither_var_bound: Vec<Binding> = Vec::new();
 
         for result in final_results {
-            let subject_binding = result.get(subject_var).cloned();
-            let object_binding = result.get(object_var).cloned();
-
-            match (subject_binding, object_binding) {
-                (Some(subj_val), Some(obj_val)) => {
+            match (result.id(subject_var), result.id(object_var)) {
+                (Some(subj_id), Some(obj_id)) => {
                     both_vars_bound
-                        .entry((subj_val.clone(), obj_val.clone()))
+                        .entry((subj_id, obj_id))
                         .or_default()
                         .push(result);
                 }
-                (Some(subj_val), None) => {
-                    subject_var_bound.entry(subj_val.clone()).or_default().push(result);
+                (Some(subj_id), None) => {
+                    subject_var_bound.entry(subj_id).or_default().push(result);
                 }
-                (None, Some(obj_val)) => {
-                    object_var_bound.entry(obj_val.clone()).or_default().push(result);
+                (None, Some(obj_id)) => {
+                    object_var_bound.entry(obj_id).or_default().push(result);
                 }
                 (None, None) => {
                     neither_var_bound.push(result);
@@ -2378,88 +3106,48 @@ impl SparqlDatabase {
             let mut local_results = Vec::new();
 
             for triple in chunk {
-                if let (Some(subject), Some(pred), Some(object)) = (
-                    dictionary.decode(triple.subject),
-                    dictionary.decode(triple.predicate),
-                    dictionary.decode(triple.object),
-                ) {
-                    // SIMD predicate comparison using simd_eq.
-                    if !unsafe { simd_eq(pred.as_bytes(), predicate_bytes) } {
+                // Predicate comparison.
+                if triple.predicate != predicate_id {
+                    continue;
+                }
+
+                // Literal filter comparison.
+                if let Some(filter_id) = literal_filter_id {
+                    if triple.object != filter_id {
                         continue;
                     }
+                }
 
-                    // SIMD literal filter comparison.
-                    if let Some(filter_bytes) = literal_filter_bytes {
-                        if !unsafe { simd_eq(object.as_bytes(), filter_bytes) } {
-                            continue;
-                        }
+                // Process group both_vars_bound.
+                if let Some(results_vec) = both_vars_bound.get(&(triple.subject, triple.object)) {
+                    for result in results_vec {
+                        local_results.push(result.clone());
                     }
+                }
 
-                    // Process group both_vars_bound.
-                    {
-                        let key = (subject.to_string(), object.to_string());
-                        if let Some(results_vec) = both_vars_bound.get(&key) {
-                            for result in results_vec {
-                                local_results.push(result.clone());
-                            }
-                        }
+                // Process group subject_var_bound.
+                if let Some(results_vec) = subject_var_bound.get(&triple.subject) {
+                    for result in results_vec {
+                        let mut extended_result = result.clone();
+                        extended_result.bind(Arc::clone(&object_key), triple.object);
+                        local_results.push(extended_result);
                     }
+                }
 
-                    // Process group subject_var_bound.
-                    {
-                        if let Some(results_vec) = subject_var_bound.get(subject) {
-                            for result in results_vec {
-                                let mut extended_result = result.clone();
-                                // Extend object_var.
-                                if let Some(existing_object) = extended_result.get(object_var) {
-                                    if existing_object != &object {
-                                        continue; // Inconsistent variable binding.
-                                    }
-                                } else {
-                                    extended_result.insert(object_var, object.to_string());
-                                }
-                                local_results.push(extended_result);
-                            }
-                        }
+                // Process group object_var_bound.
+                if let Some(results_vec) = object_var_bound.get(&triple.object) {
+                    for result in results_vec {
+                        let mut extended_result = result.clone();
+                        extended_result.bind(Arc::clone(&subject_key), triple.subject);
+                        local_results.push(extended_result);
                     }
+                }
 
-                    // Process group object_var_bound.
+                // Process group neither_var_bound.
+                for result in &neither_var_bound {
+                    if let Some(extended_result) =
+                        extend_binding(result, &subject_key, triple.subject, &object_key, triple.object)
                     {
-                        if let Some(results_vec) = object_var_bound.get(object) {
-                            for result in results_vec {
-                                let mut extended_result = result.clone();
-                                // Extend subject_var.
-                                if let Some(existing_subject) = extended_result.get(subject_var) {
-                                    if existing_subject != &subject {
-                                        continue; // Inconsistent variable binding.
-                                    }
-                                } else {
-                                    extended_result.insert(subject_var, subject.to_string());
-                                }
-                                local_results.push(extended_result);
-                            }
-                        }
-                    }
-
-                    // Process group neither_var_bound.
-                    for result in &neither_var_bound {
-                        let mut extended_result = result.clone();
-                        // Extend subject_var.
-                        if let Some(existing_subject) = extended_result.get(subject_var) {
-                            if existing_subject != &subject {
-                                continue; // Inconsistent variable binding.
-                            }
-                        } else {
-                            extended_result.insert(subject_var, subject.to_string());
-                        }
-                        // Extend object_var.
-                        if let Some(existing_object) = extended_result.get(object_var) {
-                            if existing_object != &object {
-                                continue; // Inconsistent variable binding.
-                            }
-                        } else {
-                            extended_result.insert(object_var, object.to_string());
-                        }
                         local_results.push(extended_result);
                     }
                 }
@@ -2473,50 +3161,49 @@ impl SparqlDatabase {
         results.into_inner().unwrap()
     }
 
-    pub fn perform_join_sequential<'a>(
+    pub fn perform_join_sequential(
         &self,
-        subject_var: &'a str,
-        predicate: String,
-        object_var: &'a str,
+        pattern: JoinPattern<'_>,
         triples: Vec<Triple>,
-        dictionary: &'a Dictionary,
-        final_results: Vec<BTreeMap<&'a str, String>>,
-        literal_filter: Option<String>,
-    ) -> Vec<BTreeMap<&'a str, String>> {
+        dictionary: &Dictionary,
+        final_results: Vec<Binding>,
+    ) -> Vec<Binding> {
+        let JoinPattern { subject_var, predicate, object_var, literal_filter } = pattern;
         if final_results.is_empty() {
             return Vec::new();
         }
 
-        let predicate_bytes = predicate.as_bytes();
-        let literal_filter_bytes = literal_filter.as_ref().map(|s| s.as_bytes());
+        let Some((predicate_id, literal_filter_id)) =
+            join_term_ids(dictionary, &predicate, literal_filter.as_deref())
+        else {
+            return Vec::new();
+        };
+        let subject_key: Arc<str> = Arc::from(subject_var);
+        let object_key: Arc<str> = Arc::from(object_var);
 
-        // Partition final_results into groups based on variable bindings.
-        let mut both_vars_bound: HashMap<(String, String), Vec<BTreeMap<&'a str, String>>> =
-            HashMap::new();
-        let mut subject_var_bound: HashMap<String, Vec<BTreeMap<&'a str, String>>> = HashMap::new();
-        let mut object_var_bound: HashMap<String, Vec<BTreeMap<&'a str, String>>> = HashMap::new();
-        let mut neither_var_bound: Vec<BTreeMap<&'a str, String>> = Vec::new();
+        // Partition final_results by the term IDs bound to the pattern's variables.
+        let mut both_vars_bound: HashMap<(u32, u32), Vec<Binding>> = HashMap::new();
+        let mut subject_var_bound: HashMap<u32, Vec<Binding>> = HashMap::new();
+        let mut object_var_bound: HashMap<u32, Vec<Binding>> = HashMap::new();
+        let mut neither_var_bound: Vec<Binding> = Vec::new();
 
         for result in final_results {
-            let subject_binding = result.get(subject_var).cloned();
-            let object_binding = result.get(object_var).cloned();
-
-            match (subject_binding, object_binding) {
-                (Some(subj_val), Some(obj_val)) => {
+            match (result.id(subject_var), result.id(object_var)) {
+                (Some(subj_id), Some(obj_id)) => {
                     both_vars_bound
-                        .entry((subj_val.clone(), obj_val.clone()))
+                        .entry((subj_id, obj_id))
                         .or_default()
                         .push(result);
                 }
-                (Some(subj_val), None) => {
+                (Some(subj_id), None) => {
                     subject_var_bound
-                        .entry(subj_val.clone())
+                        .entry(subj_id)
                         .or_default()
                         .push(result);
                 }
-                (None, Some(obj_val)) => {
+                (None, Some(obj_id)) => {
                     object_var_bound
-                        .entry(obj_val.clone())
+                        .entry(obj_id)
                         .or_default()
                         .push(result);
                 }
@@ -2530,88 +3217,48 @@ impl SparqlDatabase {
 
         // Process triples sequentially.
         for triple in triples {
-            if let (Some(subject), Some(pred), Some(object)) = (
-                dictionary.decode(triple.subject),
-                dictionary.decode(triple.predicate),
-                dictionary.decode(triple.object),
-            ) {
-                // Check if the predicate matches.
-                if pred.as_bytes() != predicate_bytes {
-                    continue;
-                }
+            // Check if the predicate matches.
+            if triple.predicate != predicate_id {
+                continue;
+            }
 
-                // Check the literal filter if provided.
-                if let Some(filter_bytes) = literal_filter_bytes {
-                    if object.as_bytes() != filter_bytes {
-                        continue;
-                    }
+            // Check the literal filter if provided.
+            if let Some(filter_id) = literal_filter_id {
+                if triple.object != filter_id {
+                    continue;
                 }
+            }
 
-                // Process group where both variables are already bound.
-                {
-                    let key = (subject.to_string(), object.to_string());
-                    if let Some(results_vec) = both_vars_bound.get(&key) {
-                        for result in results_vec {
-                            results.push(result.clone());
-                        }
-                    }
+            // Process group where both variables are already bound.
+            if let Some(results_vec) = both_vars_bound.get(&(triple.subject, triple.object)) {
+                for result in results_vec {
+                    results.push(result.clone());
                 }
+            }
 
-                // Process group where only subject_var is bound.
-                {
-                    if let Some(results_vec) = subject_var_bound.get(subject) {
-                        for result in results_vec {
-                            let mut extended_result = result.clone();
-                            // Extend the object_var binding.
-                            if let Some(existing_object) = extended_result.get(object_var) {
-                                if existing_object != &object {
-                                    continue; // Inconsistent variable binding.
-                                }
-                            } else {
-                                extended_result.insert(object_var, object.to_string());
-                            }
-                            results.push(extended_result);
-                        }
-                    }
+            // Process group where only subject_var is bound.
+            if let Some(results_vec) = subject_var_bound.get(&triple.subject) {
+                for result in results_vec {
+                    let mut extended_result = result.clone();
+                    extended_result.bind(Arc::clone(&object_key), triple.object);
+                    results.push(extended_result);
                 }
+            }
 
-                // Process group where only object_var is bound.
-                {
-                    if let Some(results_vec) = object_var_bound.get(object) {
-                        for result in results_vec {
-                            let mut extended_result = result.clone();
-                            // Extend the subject_var binding.
-                            if let Some(existing_subject) = extended_result.get(subject_var) {
-                                if existing_subject != &subject {
-                                    continue; // Inconsistent variable binding.
-                                }
-                            } else {
-                                extended_result.insert(subject_var, subject.to_string());
-                            }
-                            results.push(extended_result);
-                        }
-                    }
+            // Process group where only object_var is bound.
+            if let Some(results_vec) = object_var_bound.get(&triple.object) {
+                for result in results_vec {
+                    let mut extended_result = result.clone();
+                    extended_result.bind(Arc::clone(&subject_key), triple.subject);
+                    results.push(extended_result);
                 }
+            }
 
-                // Process group where neither variable is bound.
-                for result in &neither_var_bound {
-                    let mut extended_result = result.clone();
-                    // Extend the subject_var binding.
-                    if let Some(existing_subject) = extended_result.get(subject_var) {
-                        if existing_subject != &subject {
-                            continue; // Inconsistent variable binding.
-                        }
-                    } else {
-                        extended_result.insert(subject_var, subject.to_string());
-                    }
-                    // Extend the object_var binding.
-                    if let Some(existing_object) = extended_result.get(object_var) {
-                        if existing_object != &object {
-                            continue; // Inconsistent variable binding.
-                        }
-                    } else {
-                        extended_result.insert(object_var, object.to_string());
-                    }
+            // Process group where neither variable is bound.
+            for result in &neither_var_bound {
+                if let Some(extended_result) =
+                    extend_binding(result, &subject_key, triple.subject, &object_key, triple.object)
+                {
                     results.push(extended_result);
                 }
             }
@@ -2620,50 +3267,49 @@ impl SparqlDatabase {
         results
     }
 
-    pub fn perform_join_sequential_simd<'a>(
+    pub fn perform_join_sequential_simd(
         &self,
-        subject_var: &'a str,
-        predicate: String,
-        object_var: &'a str,
+        pattern: JoinPattern<'_>,
         triples: Vec<Triple>,
-        dictionary: &'a Dictionary,
-        final_results: Vec<BTreeMap<&'a str, String>>,
-        literal_filter: Option<String>,
-    ) -> Vec<BTreeMap<&'a str, String>> {
+        dictionary: &Dictionary,
+        final_results: Vec<Binding>,
+    ) -> Vec<Binding> {
+        let JoinPattern { subject_var, predicate, object_var, literal_filter } = pattern;
         if final_results.is_empty() {
             return Vec::new();
         }
 
-        let predicate_bytes = predicate.as_bytes();
-        let literal_filter_bytes = literal_filter.as_ref().map(|s| s.as_bytes());
+        let Some((predicate_id, literal_filter_id)) =
+            join_term_ids(dictionary, &predicate, literal_filter.as_deref())
+        else {
+            return Vec::new();
+        };
+        let subject_key: Arc<str> = Arc::from(subject_var);
+        let object_key: Arc<str> = Arc::from(object_var);
 
-        // Partition final_results into groups based on variable bindings.
-        let mut both_vars_bound: HashMap<(String, String), Vec<BTreeMap<&'a str, String>>> =
-            HashMap::new();
-        let mut subject_var_bound: HashMap<String, Vec<BTreeMap<&'a str, String>>> = HashMap::new();
-        let mut object_var_bound: HashMap<String, Vec<BTreeMap<&'a str, String>>> = HashMap::new();
-        let mut neither_var_bound: Vec<BTreeMap<&'a str, String>> = Vec::new();
+        // Partition final_results by the term IDs bound to the pattern's variables.
+        let mut both_vars_bound: HashMap<(u32, u32), Vec<Binding>> = HashMap::new();
+        let mut subject_var_bound: HashMap<u32, Vec<Binding>> = HashMap::new();
+        let mut object_var_bound: HashMap<u32, Vec<Binding>> = HashMap::new();
+        let mut neither_var_bound: Vec<Binding> = Vec::new();
 
         for result in final_results {
-            let subject_binding = result.get(subject_var).cloned();
-            let object_binding = result.get(object_var).cloned();
-
-            match (subject_binding, object_binding) {
-                (Some(subj_val), Some(obj_val)) => {
+            match (result.id(subject_var), result.id(object_var)) {
+                (Some(subj_id), Some(obj_id)) => {
                     both_vars_bound
-                        .entry((subj_val.clone(), obj_val.clone()))
+                        .entry((subj_id, obj_id))
                         .or_default()
                         .push(result);
                 }
-                (Some(subj_val), None) => {
+                (Some(subj_id), None) => {
                     subject_var_bound
-                        .entry(subj_val.clone())
+                        .entry(subj_id)
                         .or_default()
                         .push(result);
                 }
-                (None, Some(obj_val)) => {
+                (None, Some(obj_id)) => {
                     object_var_bound
-                        .entry(obj_val.clone())
+                        .entry(obj_id)
                         .or_default()
                         .push(result);
                 }
@@ -2673,152 +3319,99 @@ impl SparqlDatabase {
             }
         }
 
-        let mut results = Vec::new();
-
-        // Process triples sequentially.
-        for triple in triples {
-            if let (Some(subject), Some(pred), Some(object)) = (
-                dictionary.decode(triple.subject),
-                dictionary.decode(triple.predicate),
-                dictionary.decode(triple.object),
-            ) {
-                // Use SIMD-based comparison for the predicate.
-                if !simd_bytes_eq(pred.as_bytes(), predicate_bytes) {
-                    continue;
-                }
-
-                // Use SIMD-based comparison for the literal filter if provided.
-                if let Some(filter_bytes) = literal_filter_bytes {
-                    if !simd_bytes_eq(object.as_bytes(), filter_bytes) {
-                        continue;
-                    }
-                }
+        // Matching triples are found first, so the probes below only see hits.
+        let matching_triples = triples.into_iter().filter(|triple| {
+            triple.predicate == predicate_id
+                && literal_filter_id.is_none_or(|filter_id| triple.object == filter_id)
+        });
 
-                // Process group where both variables are already bound.
-                {
-                    let key = (subject.to_string(), object.to_string());
-                    if let Some(results_vec) = both_vars_bound.get(&key) {
-                        for result in results_vec {
-                            results.push(result.clone());
-                        }
-                    }
-                }
+        let mut results = Vec::new();
 
-                // Process group where only subject_var is bound.
-                {
-                    if let Some(results_vec) = subject_var_bound.get(subject) {
-                        for result in results_vec {
-                            let mut extended_result = result.clone();
-                            // Extend the object_var binding.
-                            if let Some(existing_object) = extended_result.get(object_var) {
-                                if existing_object != &object {
-                                    continue; // Inconsistent variable binding.
-                                }
-                            } else {
-                                extended_result.insert(object_var, object.to_string());
-                            }
-                            results.push(extended_result);
-                        }
-                    }
-                }
+        for triple in matching_triples {
+            // Process group where both variables are already bound.
+            if let Some(results_vec) = both_vars_bound.get(&(triple.subject, triple.object)) {
+                results.extend(results_vec.iter().cloned());
+            }
 
-                // Process group where only object_var is bound.
-                {
-                    if let Some(results_vec) = object_var_bound.get(object) {
-                        for result in results_vec {
-                            let mut extended_result = result.clone();
-                            // Extend the subject_var binding.
-                            if let Some(existing_subject) = extended_result.get(subject_var) {
-                                if existing_subject != &subject {
-                                    continue; // Inconsistent variable binding.
-                                }
-                            } else {
-                                extended_result.insert(subject_var, subject.to_string());
-                            }
-                            results.push(extended_result);
-                        }
-                    }
-                }
+            // Process group where only subject_var is bound.
+            if let Some(results_vec) = subject_var_bound.get(&triple.subject) {
+                results.extend(results_vec.iter().map(|result| {
+                    let mut extended_result = result.clone();
+                    extended_result.bind(Arc::clone(&object_key), triple.object);
+                    extended_result
+                }));
+            }
 
-                // Process group where neither variable is bound.
-                for result in &neither_var_bound {
+            // Process group where only object_var is bound.
+            if let Some(results_vec) = object_var_bound.get(&triple.object) {
+                results.extend(results_vec.iter().map(|result| {
                     let mut extended_result = result.clone();
-                    // Extend the subject_var binding.
-                    if let Some(existing_subject) = extended_result.get(subject_var) {
-                        if existing_subject != &subject {
-                            continue; // Inconsistent variable binding.
-                        }
-                    } else {
-                        extended_result.insert(subject_var, subject.to_string());
-                    }
-                    // Extend the object_var binding.
-                    if let Some(existing_object) = extended_result.get(object_var) {
-                        if existing_object != &object {
-                            continue; // Inconsistent variable binding.
-                        }
-                    } else {
-                        extended_result.insert(object_var, object.to_string());
-                    }
-                    results.push(extended_result);
-                }
+                    extended_result.bind(Arc::clone(&subject_key), triple.subject);
+                    extended_result
+                }));
             }
+
+            // Process group where neither variable is bound.
+            results.extend(neither_var_bound.iter().filter_map(|result| {
+                extend_binding(result, &subject_key, triple.subject, &object_key, triple.object)
+            }));
         }
 
         results
     }
 
-    pub fn perform_join_par_simd_with_strict_filter_3<'a>(
+    pub fn perform_join_par_simd_with_strict_filter_3(
         &self,
-        subject_var: &'a str,
-        predicate: String,
-        object_var: &'a str,
+        pattern: JoinPattern<'_>,
         triples: Vec<Triple>,
-        dictionary: &'a Dictionary,
-        final_results: Vec<BTreeMap<&'a str, String>>,
-        literal_filter: Option<String>,
-    ) -> Vec<BTreeMap<&'a str, String>> {
+        dictionary: &Dictionary,
+        final_results: Vec<Binding>,
+    ) -> Vec<Binding> {
+        let JoinPattern { subject_var, predicate, object_var, literal_filter } = pattern;
         // Early return for empty joins
         if final_results.is_empty() {
             return Vec::new();
         }
 
-        // Pre-fetch predicate and filter bytes to avoid string comparisons
-        let predicate_bytes = predicate.as_bytes();
-        let literal_filter_bytes = literal_filter.as_ref().map(|s| s.as_bytes());
+        // Look up the predicate and filter IDs once so triples are matched on IDs
+        let Some((predicate_id, literal_filter_id)) =
+            join_term_ids(dictionary, &predicate, literal_filter.as_deref())
+        else {
+            return Vec::new();
+        };
+        let subject_key: Arc<str> = Arc::from(subject_var);
+        let object_key: Arc<str> = Arc::from(object_var);
 
         // Preallocate with capacity estimation to avoid rehashing
-        let estimated_capacity = (final_results.len() / 4).max(HASHMAP_INITIAL_CAPACITY);
+        let estimated_capacity = (final_results.len() / 4).max(self.tuning.join_hashmap_capacity);
         
         // Use with_capacity to preallocate hashmap space
-        let mut both_vars_bound: HashMap<(String, String), Vec<usize>> = 
+        let mut both_vars_bound: HashMap<(u32, u32), Vec<usize>> = 
             HashMap::with_capacity(estimated_capacity);
-        let mut subject_var_bound: HashMap<String, Vec<usize>> = 
+        let mut subject_var_bound: HashMap<u32, Vec<usize>> = 
             HashMap::with_capacity(estimated_capacity);
-        let mut object_var_bound: HashMap<String, Vec<usize>> = 
+        let mut object_var_bound: HashMap<u32, Vec<usize>> = 
             HashMap::with_capacity(estimated_capacity);
         let mut neither_var_bound: Vec<usize> = Vec::with_capacity(final_results.len() / 2);
 
-        // Pre-compute and classify bindings - this is serial but much faster than doing it in parallel
+        // Pre-compute and classify bindings by term ID - this is serial but much faster than doing it in parallel
         for (idx, result) in final_results.iter().enumerate() {
-            let subject_binding = result.get(subject_var);
-            let object_binding = result.get(object_var);
-
-            match (subject_binding, object_binding) {
-                (Some(subj_val), Some(obj_val)) => {
+            match (result.id(subject_var), result.id(object_var)) {
+                (Some(subj_id), Some(obj_id)) => {
                     both_vars_bound
-                        .entry((subj_val.clone(), obj_val.clone()))
+                        .entry((subj_id, obj_id))
                         .or_insert_with(|| Vec::with_capacity(4))
                         .push(idx);
                 }
-                (Some(subj_val), None) => {
+                (Some(subj_id), None) => {
                     subject_var_bound
-                        .entry(subj_val.clone())
+                        .entry(subj_id)
                         .or_insert_with(|| Vec::with_capacity(8))
                         .push(idx);
                 }
-                (None, Some(obj_val)) => {
+                (None, Some(obj_id)) => {
                     object_var_bound
-                        .entry(obj_val.clone())
+                        .entry(obj_id)
                         .or_insert_with(|| Vec::with_capacity(8))
                         .push(idx);
                 }
@@ -2828,15 +3421,19 @@ impl SparqlDatabase {
             }
         }
 
-        // Immutable shared references for threading
-        let final_results_arc = Arc::new(final_results);
-        let both_vars_bound_arc = Arc::new(both_vars_bound);
-        let subject_var_bound_arc = Arc::new(subject_var_bound);
-        let object_var_bound_arc = Arc::new(object_var_bound);
-        let neither_var_bound_arc = Arc::new(neither_var_bound);
+        // Shared by reference with the worker threads
+        let partitions = PartitionedBindings {
+            subject_var: subject_key,
+            object_var: object_key,
+            both_vars_bound,
+            subject_var_bound,
+            object_var_bound,
+            neither_var_bound,
+            results: final_results,
+        };
 
         // Calculate optimal chunk size based on available processors and dataset size
-        let chunk_size = (triples.len() / rayon::current_num_threads()).max(MIN_CHUNK_SIZE);
+        let chunk_size = (triples.len() / rayon::current_num_threads()).max(self.tuning.join_chunk_size).max(1);
         
         // Process triples in chunks for better cache locality and load balancing
         let results = triples
@@ -2848,53 +3445,16 @@ impl SparqlDatabase {
                 // Process each triple in the chunk
                 for triple in triple_chunk {
                     // Step 1: Quick predicate check first (early filter)
-                    let pred_opt = dictionary.decode(triple.predicate);
-                    if pred_opt.is_none() || pred_opt.as_ref().unwrap().as_bytes() != predicate_bytes {
+                    if triple.predicate != predicate_id {
                         continue;
                     }
                     
                     // Step 2: Filter check if needed
-                    if let Some(filter_bytes) = &literal_filter_bytes {
-                        let obj_opt = dictionary.decode(triple.object);
-                        if obj_opt.is_none() || obj_opt.as_ref().unwrap().as_bytes() != *filter_bytes {
-                            continue;
-                        }
-                        
-                        // Decode subject only if predicate and object pass filters
-                        if let Some(subj) = dictionary.decode(triple.subject) {
-                            process_join(
-                                &subj,
-                                obj_opt.unwrap(),
-                                subject_var,
-                                object_var,
-                                &both_vars_bound_arc,
-                                &subject_var_bound_arc,
-                                &object_var_bound_arc,
-                                &neither_var_bound_arc,
-                                &final_results_arc,
-                                &mut local_results,
-                            );
-                        }
-                    } else {
-                        // No filter - decode both subject and object
-                        let subj_opt = dictionary.decode(triple.subject);
-                        let obj_opt = dictionary.decode(triple.object);
-                        
-                        if let (Some(subj), Some(obj)) = (subj_opt, obj_opt) {
-                            process_join(
-                                &subj,
-                                &obj,
-                                subject_var,
-                                object_var,
-                                &both_vars_bound_arc,
-                                &subject_var_bound_arc,
-                                &object_var_bound_arc,
-                                &neither_var_bound_arc,
-                                &final_results_arc,
-                                &mut local_results,
-                            );
-                        }
+                    if literal_filter_id.is_some_and(|filter_id| triple.object != filter_id) {
+                        continue;
                     }
+
+                    process_join(triple.subject, triple.object, &partitions, &mut local_results);
                 }
                 
                 local_results
@@ -2904,56 +3464,56 @@ impl SparqlDatabase {
         results
     }
 
-    pub fn perform_join_par_simd_with_strict_filter_4<'a>(
+    pub fn perform_join_par_simd_with_strict_filter_4(
         &self,
-        subject_var: &'a str,
-        predicate: String,
-        object_var: &'a str,
+        pattern: JoinPattern<'_>,
         triples: Vec<Triple>,
-        dictionary: &'a Dictionary,
-        final_results: Vec<BTreeMap<&'a str, String>>,
-        literal_filter: Option<String>,
-    ) -> Vec<BTreeMap<&'a str, String>> {
+        dictionary: &Dictionary,
+        final_results: Vec<Binding>,
+    ) -> Vec<Binding> {
+        let JoinPattern { subject_var, predicate, object_var, literal_filter } = pattern;
         // Early return for empty joins
         if final_results.is_empty() {
             return Vec::new();
         }
 
-        // Pre-fetch predicate and filter bytes to avoid string comparisons
-        let predicate_bytes = predicate.as_bytes();
-        let literal_filter_bytes = literal_filter.as_ref().map(|s| s.as_bytes());
+        // Look up the predicate and filter IDs once so triples are matched on IDs
+        let Some((predicate_id, literal_filter_id)) =
+            join_term_ids(dictionary, &predicate, literal_filter.as_deref())
+        else {
+            return Vec::new();
+        };
+        let subject_key: Arc<str> = Arc::from(subject_var);
+        let object_key: Arc<str> = Arc::from(object_var);
 
-        let estimated_capacity = (final_results.len() / 3).max(HASHMAP_INITIAL_CAPACITY1);
+        let estimated_capacity = (final_results.len() / 3).max(self.tuning.folding_join_hashmap_capacity);
         
-        let mut both_vars_bound: HashMap<(String, String), Vec<usize>> = 
+        let mut both_vars_bound: HashMap<(u32, u32), Vec<usize>> = 
             HashMap::with_capacity(estimated_capacity / 2);  // This tends to be smaller
-        let mut subject_var_bound: HashMap<String, Vec<usize>> = 
+        let mut subject_var_bound: HashMap<u32, Vec<usize>> = 
             HashMap::with_capacity(estimated_capacity);
-        let mut object_var_bound: HashMap<String, Vec<usize>> = 
+        let mut object_var_bound: HashMap<u32, Vec<usize>> = 
             HashMap::with_capacity(estimated_capacity);
         let mut neither_var_bound: Vec<usize> = Vec::with_capacity(final_results.len() / 2);
 
-        // Pre-compute and classify bindings - this is serial but much faster than doing it in parallel
+        // Pre-compute and classify bindings by term ID - this is serial but much faster than doing it in parallel
         for (idx, result) in final_results.iter().enumerate() {
-            let subject_binding = result.get(subject_var);
-            let object_binding = result.get(object_var);
-
-            match (subject_binding, object_binding) {
-                (Some(subj_val), Some(obj_val)) => {
+            match (result.id(subject_var), result.id(object_var)) {
+                (Some(subj_id), Some(obj_id)) => {
                     both_vars_bound
-                        .entry((subj_val.clone(), obj_val.clone()))
+                        .entry((subj_id, obj_id))
                         .or_insert_with(|| Vec::with_capacity(4))
                         .push(idx);
                 }
-                (Some(subj_val), None) => {
+                (Some(subj_id), None) => {
                     subject_var_bound
-                        .entry(subj_val.clone())
+                        .entry(subj_id)
                         .or_insert_with(|| Vec::with_capacity(8))
                         .push(idx);
                 }
-                (None, Some(obj_val)) => {
+                (None, Some(obj_id)) => {
                     object_var_bound
-                        .entry(obj_val.clone())
+                        .entry(obj_id)
                         .or_insert_with(|| Vec::with_capacity(8))
                         .push(idx);
                 }
@@ -2963,14 +3523,18 @@ impl SparqlDatabase {
             }
         }
 
-        // Immutable shared references for threading
-        let final_results_arc = Arc::new(final_results);
-        let both_vars_bound_arc = Arc::new(both_vars_bound);
-        let subject_var_bound_arc = Arc::new(subject_var_bound);
-        let object_var_bound_arc = Arc::new(object_var_bound);
-        let neither_var_bound_arc = Arc::new(neither_var_bound);
+        // Shared by reference with the worker threads
+        let partitions = PartitionedBindings {
+            subject_var: subject_key,
+            object_var: object_key,
+            both_vars_bound,
+            subject_var_bound,
+            object_var_bound,
+            neither_var_bound,
+            results: final_results,
+        };
 
-        let chunk_size = ((triples.len() / rayon::current_num_threads()) * 3 / 2).max(MIN_CHUNK_SIZE1);
+        let chunk_size = ((triples.len() / rayon::current_num_threads()) * 3 / 2).max(self.tuning.join_chunk_size).max(1);
         
         let results = triples
             .par_chunks(chunk_size)
@@ -2980,17 +3544,10 @@ impl SparqlDatabase {
                     // Create a local result buffer
                     process_triple_chunk(
                         triple_chunk,
-                        predicate_bytes,
-                        &literal_filter_bytes,
-                        subject_var,
-                        object_var,
-                        &both_vars_bound_arc,
-                        &subject_var_bound_arc,
-                        &object_var_bound_arc,
-                        &neither_var_bound_arc,
-                        &final_results_arc,
+                        predicate_id,
+                        literal_filter_id,
+                        &partitions,
                         &mut local_results,
-                        dictionary,
                     );
                     
                     local_results
@@ -3059,31 +3616,110 @@ impl SparqlDatabase {
     }
 
     pub fn handle_update(&mut self, update: &str) -> String {
-        use crate::parser::{parse_insert, parse_delete};
+        match self.try_update(update) {
+            Ok(()) => "Update Successful".to_string(),
+            Err(e) => {
+                warn!("{}", e);
+                "Update Failed".to_string()
+            }
+        }
+    }
+
+    /// Apply a SPARQL Update request: `INSERT DATA`, `DELETE DATA`,
+    /// `DELETE WHERE` or `DELETE { } INSERT { } WHERE { }`. The WHERE clause
+    /// is evaluated before any change, then all deletions are applied before
+    /// the insertions.
+    pub fn try_update(&mut self, update: &str) -> Result<(), SparqlError> {
+        let operation = match parser::parse_update(update) {
+            Ok((rest, operation)) if rest.trim().is_empty() => operation,
+            Ok((rest, _)) => {
+                let error = nom::error::Error::new(rest, nom::error::ErrorKind::Eof);
+                return Err(SparqlError::ParseError(format_parse_error(update, nom::Err::Error(error))));
+            }
+            Err(e) => return Err(SparqlError::ParseError(format_parse_error(update, e))),
+        };
+
+        let templates = [&operation.delete_triples, &operation.insert_triples];
+        let mut variables: Vec<&str> = Vec::new();
+        for (s, p, o) in templates.into_iter().flatten() {
+            for term in [*s, *p, *o] {
+                if term.starts_with('?') && !variables.contains(&term) {
+                    variables.push(term);
+                }
+            }
+        }
 
-        let trimmed = update.trim();
-        if trimmed.starts_with("INSERT") {
-            if let Ok((_, insert_clause)) = parse_insert(trimmed) {
-                for (subject, predicate, object) in insert_clause.triples {
-                    let subject_id = self.encode_term_star(subject);
-                    let predicate_id = self.encode_term_star(predicate);
-                    let object_id = self.encode_term_star(object);
-                    self.add_triple(Triple { subject: subject_id, predicate: predicate_id, object: object_id });
+        let solutions: Vec<HashMap<&str, String>> = match operation.where_clause {
+            None => {
+                if let Some(variable) = variables.first() {
+                    return Err(SparqlError::ParseError(format!(
+                        "Variable {} in an update without a WHERE clause",
+                        variable
+                    )));
                 }
-                return "Update Successful".to_string();
+                vec![HashMap::new()]
             }
-        } else if trimmed.starts_with("DELETE") {
-            if let Ok((_, delete_clause)) = parse_delete(trimmed) {
-                for (subject, predicate, object) in delete_clause.triples {
-                    let subject_id = self.encode_term_star(subject);
-                    let predicate_id = self.encode_term_star(predicate);
-                    let object_id = self.encode_term_star(object);
-                    self.delete_triple(&Triple { subject: subject_id, predicate: predicate_id, object: object_id });
+            Some(pattern) => {
+                let mut query = String::new();
+                for (prefix, iri) in &operation.prefixes {
+                    query.push_str(&format!("PREFIX {}: <{}>\n", prefix, iri));
                 }
-                return "Update Successful".to_string();
+                let projection = if variables.is_empty() { "*".to_string() } else { variables.join(" ") };
+                query.push_str(&format!("SELECT {} WHERE {{{}}}", projection, pattern));
+
+                crate::execute_query::try_execute_query(&query, self)?
+                    .into_iter()
+                    .map(|row| {
+                        variables
+                            .iter()
+                            .zip(row)
+                            .filter(|(_, value)| !value.is_empty())
+                            .map(|(variable, value)| (*variable, value))
+                            .collect()
+                    })
+                    .collect()
             }
+        };
+
+        let mut deletions = Vec::new();
+        let mut insertions = Vec::new();
+        for solution in &solutions {
+            deletions.extend(self.instantiate_template(&operation.delete_triples, solution, &operation.prefixes));
+            insertions.extend(self.instantiate_template(&operation.insert_triples, solution, &operation.prefixes));
         }
-        "Update Failed".to_string()
+        for triple in &deletions {
+            self.delete_triple(triple);
+        }
+        for triple in insertions {
+            self.add_triple(triple);
+        }
+        self.invalidate_stats_cache();
+        Ok(())
+    }
+
+    /// The triples of an update template under one solution; triples using a
+    /// variable the solution leaves unbound are skipped
+    fn instantiate_template(
+        &self,
+        template: &[(&str, &str, &str)],
+        solution: &HashMap<&str, String>,
+        prefixes: &HashMap<String, String>,
+    ) -> Vec<Triple> {
+        let encode = |term: &str| -> Option<u32> {
+            if term.starts_with('?') {
+                solution.get(term).map(|value| self.encode_term_star(value))
+            } else if term.starts_with("<<") || (term.starts_with('"') && !term.contains("\"^^")) {
+                Some(self.encode_term_star(term))
+            } else {
+                Some(self.encode_term_star(&self.resolve_query_term(term, prefixes)))
+            }
+        };
+        template
+            .iter()
+            .filter_map(|(s, p, o)| {
+                Some(Triple { subject: encode(s)?, predicate: encode(p)?, object: encode(o)? })
+            })
+            .collect()
     }
 
     pub fn handle_http_request(&mut self, request: &str) -> String {
@@ -3166,12 +3802,20 @@ impl SparqlDatabase {
         F: Fn(Vec<&str>) -> String + Send + Sync + 'static,
     {
         self.udfs.insert(name.to_string(), ClonableFn::new(f));
+        // Cached results may have been computed with the previous function
+        if let Some(cache) = &mut self.query_cache {
+            cache.clear();
+        }
     }
 
     /// Rebuild all indexes from the current state of `self.triples`.
     pub fn build_all_indexes(&mut self) {
+        self.indexes_pending = false;
         // Clear existing indexes
         self.index_manager.clear();
+        if let Some(cache) = &mut self.pattern_cache {
+            cache.clear();
+        }
         
         // Get all triples as a vector for parallel processing
         let triples: Vec<Triple> = self.triples.iter().cloned().collect();
@@ -3199,6 +3843,10 @@ impl SparqlDatabase {
         
         // Optimize the final merged index
         self.index_manager.optimize();
+
+        if self.text_index_enabled {
+            self.build_text_index();
+        }
     }
 
     /// Triple to string
@@ -3220,6 +3868,261 @@ impl SparqlDatabase {
     }
 }
 
+/// Re-encodes terms of one database into another's dictionary and quoted
+/// triple store, each term once
+struct TermCopier<'a> {
+    source: &'a Dictionary,
+    source_quoted: &'a QuotedTripleStore,
+    target: &'a mut Dictionary,
+    target_quoted: &'a mut QuotedTripleStore,
+    /// Source ID to target ID of the terms copied so far
+    copied: HashMap<u32, u32>,
+}
+
+impl TermCopier<'_> {
+    fn copy(&mut self, id: u32) -> u32 {
+        if let Some(&copied) = self.copied.get(&id) {
+            return copied;
+        }
+        let copied = match self.source_quoted.decode(id).filter(|_| is_quoted_triple_id(id)) {
+            Some((subject, predicate, object)) => {
+                let (subject, predicate, object) = (self.copy(subject), self.copy(predicate), self.copy(object));
+                self.target_quoted.encode(subject, predicate, object)
+            }
+            None => self.target.encode(self.source.decode(id).unwrap_or_default()),
+        };
+        self.copied.insert(id, copied);
+        copied
+    }
+}
+
+/// Append the text an entity or character reference (`&amp;`, `&#233;`)
+/// stands for; unknown entities are kept verbatim.
+fn push_xml_reference(text: &mut String, reference: &BytesRef) {
+    let name = reference.decode().unwrap_or_default();
+    if let Ok(Some(ch)) = reference.resolve_char_ref() {
+        text.push(ch);
+    } else if let Some(value) = resolve_predefined_entity(&name) {
+        text.push_str(value);
+    } else {
+        text.push('&');
+        text.push_str(&name);
+        text.push(';');
+    }
+}
+
+/// Unescaped value of the attribute named `key` on `element`, if present
+fn attribute_value(element: &BytesStart, key: &[u8]) -> Option<String> {
+    element
+        .attributes()
+        .filter_map(Result::ok)
+        .find(|attr| attr.key.as_ref() == key)
+        .map(|attr| unescaped_value(&attr))
+}
+
+/// Lexical form and datatype IRI of a stored `"lex"^^<iri>` literal
+fn typed_literal_parts(value: &str) -> Option<(&str, &str)> {
+    let (lexical, datatype) = value.strip_prefix('"')?.rsplit_once("\"^^<")?;
+    Some((lexical, datatype.strip_suffix('>')?))
+}
+
+/// Attribute value with its references resolved, or the raw value if it is malformed
+fn unescaped_value(attr: &Attribute) -> String {
+    attr.unescape_value()
+        .map(|value| value.into_owned())
+        .unwrap_or_else(|_| String::from_utf8_lossy(&attr.value).into_owned())
+}
+
+/// Characters that may start an XML name; non-ASCII is accepted wholesale
+fn is_xml_name_start_char(ch: char) -> bool {
+    ch.is_ascii_alphabetic() || ch == '_' || !ch.is_ascii()
+}
+
+fn is_xml_name_char(ch: char) -> bool {
+    is_xml_name_start_char(ch) || ch.is_ascii_digit() || ch == '-' || ch == '.'
+}
+
+/// Whether `name` can follow `prefix:` in an XML element name
+fn is_xml_local_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(is_xml_name_start_char) && chars.all(is_xml_name_char)
+}
+
+/// An open element of an RDF/XML document
+enum RdfXmlFrame {
+    /// A node element (`rdf:Description`) and the resource it describes
+    Node { subject: String, lang: Option<String> },
+    /// A property element of `subject`. Its object is the resource named by
+    /// `rdf:resource`/`rdf:nodeID` or the node nested inside it, and otherwise
+    /// a literal made of its text.
+    Property {
+        subject: String,
+        predicate: String,
+        object: Option<String>,
+        text: String,
+        datatype: Option<String>,
+        lang: Option<String>,
+    },
+    /// Any other element, kept so that end tags pair up
+    Other,
+}
+
+impl RdfXmlFrame {
+    fn lang(&self) -> Option<&String> {
+        match self {
+            RdfXmlFrame::Node { lang, .. } | RdfXmlFrame::Property { lang, .. } => lang.as_ref(),
+            RdfXmlFrame::Other => None,
+        }
+    }
+}
+
+/// Element stack of the striped RDF/XML syntax shared by `parse_rdf` and
+/// `parse_rdf_from_file`. Node and property elements alternate, so a
+/// description nested in a property element becomes that property's object.
+/// `rdf:nodeID="x"` names the blank node `_:x`; descriptions with neither
+/// `rdf:about` nor `rdf:nodeID` get a fresh blank node.
+#[derive(Default)]
+struct RdfXmlParser {
+    stack: Vec<RdfXmlFrame>,
+}
+
+impl RdfXmlParser {
+    fn start(&mut self, db: &SparqlDatabase, element: &BytesStart) {
+        let lang = attribute_value(element, b"xml:lang")
+            .or_else(|| self.stack.last().and_then(RdfXmlFrame::lang).cloned());
+        let frame = match self.stack.last_mut() {
+            Some(RdfXmlFrame::Node { subject, .. }) => {
+                let name = std::str::from_utf8(element.name().as_ref()).unwrap_or("").to_string();
+                let name = if name == "rdfs:Class" { "rdf:type".to_string() } else { name };
+                RdfXmlFrame::Property {
+                    subject: subject.clone(),
+                    predicate: db.resolve_term(&name),
+                    object: Self::object_attribute(element),
+                    text: String::new(),
+                    datatype: attribute_value(element, b"rdf:datatype"),
+                    lang,
+                }
+            }
+            None | Some(RdfXmlFrame::Property { .. }) if element.name() == QName(b"rdf:Description") => {
+                let subject = Self::node_subject(db, element);
+                if let Some(RdfXmlFrame::Property { object, .. }) = self.stack.last_mut() {
+                    *object = Some(subject.clone());
+                }
+                RdfXmlFrame::Node { subject, lang }
+            }
+            _ => RdfXmlFrame::Other,
+        };
+        self.stack.push(frame);
+    }
+
+    fn empty(&mut self, db: &SparqlDatabase, element: &BytesStart, triples: &mut Vec<(String, String, String)>) {
+        match self.stack.last_mut() {
+            Some(RdfXmlFrame::Node { subject, .. }) => {
+                if let Some(object) = Self::object_attribute(element) {
+                    let name = element.name();
+                    let predicate = db.resolve_term(std::str::from_utf8(name.as_ref()).unwrap_or(""));
+                    triples.push((subject.clone(), predicate, object));
+                }
+            }
+            None | Some(RdfXmlFrame::Property { .. }) if element.name() == QName(b"rdf:Description") => {
+                let subject = Self::node_subject(db, element);
+                if let Some(RdfXmlFrame::Property { object, .. }) = self.stack.last_mut() {
+                    *object = Some(subject);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    fn text(&mut self, content: &str) {
+        if let Some(RdfXmlFrame::Property { text, .. }) = self.stack.last_mut() {
+            text.push_str(content);
+        }
+    }
+
+    fn reference(&mut self, reference: &BytesRef) {
+        if let Some(RdfXmlFrame::Property { text, .. }) = self.stack.last_mut() {
+            push_xml_reference(text, reference);
+        }
+    }
+
+    fn end(&mut self, db: &SparqlDatabase, triples: &mut Vec<(String, String, String)>) {
+        let Some(RdfXmlFrame::Property { subject, predicate, object, text, datatype, lang }) = self.stack.pop()
+        else {
+            return;
+        };
+        let object = match object {
+            Some(object) => object,
+            // Skip empty or whitespace-only text
+            None if text.trim().is_empty() => return,
+            None => db.rdf_xml_literal(text.trim(), datatype.as_deref(), lang.as_deref()),
+        };
+        triples.push((subject, predicate, object));
+    }
+
+    /// The resource a node element describes
+    fn node_subject(db: &SparqlDatabase, element: &BytesStart) -> String {
+        if let Some(about) = attribute_value(element, b"rdf:about") {
+            return about;
+        }
+        if let Some(node_id) = attribute_value(element, b"rdf:nodeID") {
+            return format!("_:{}", node_id);
+        }
+        // Labelled with the id it is about to be given, so each label is new
+        let mut dict = db.dictionary.write().unwrap();
+        let label = format!("_:rdfxml{}", dict.next_id);
+        dict.encode(&label);
+        label
+    }
+
+    /// The object a property element names with `rdf:resource` or `rdf:nodeID`
+    fn object_attribute(element: &BytesStart) -> Option<String> {
+        attribute_value(element, b"rdf:resource")
+            .or_else(|| attribute_value(element, b"rdf:nodeID").map(|node_id| format!("_:{}", node_id)))
+    }
+}
+
+/// Compare two 64-bit integers for a FILTER operator, using SSE4.2's
+/// `_mm_cmpgt_epi64` when the CPU has it
+fn compare_i64_simd(left: i64, right: i64, operator: &str) -> bool {
+    #[cfg(target_arch = "x86_64")]
+    {
+        if is_x86_feature_detected!("sse4.2") {
+            return unsafe { compare_i64_sse42(left, right, operator) };
+        }
+    }
+    match operator {
+        "=" => left == right,
+        "!=" => left != right,
+        ">" => left > right,
+        ">=" => left >= right,
+        "<" => left < right,
+        "<=" => left <= right,
+        _ => false,
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "sse4.2")]
+unsafe fn compare_i64_sse42(left: i64, right: i64, operator: &str) -> bool {
+    let left_simd = _mm_set1_epi64x(left);
+    let right_simd = _mm_set1_epi64x(right);
+    let eq = _mm_cmpeq_epi64(left_simd, right_simd);
+    let all_lanes = |cmp: __m128i| _mm_movemask_epi8(cmp) == 0xFFFF;
+    match operator {
+        "=" => all_lanes(eq),
+        "!=" => !all_lanes(eq),
+        ">" => all_lanes(_mm_cmpgt_epi64(left_simd, right_simd)),
+        ">=" => all_lanes(_mm_or_si128(eq, _mm_cmpgt_epi64(left_simd, right_simd))),
+        "<" => all_lanes(_mm_cmpgt_epi64(right_simd, left_simd)),
+        "<=" => all_lanes(_mm_or_si128(eq, _mm_cmpgt_epi64(right_simd, left_simd))),
+        _ => false,
+    }
+}
+
+/// Byte-wise slice equality using the widest vector registers available:
+/// AVX-512 when the CPU supports it (detected at runtime unless the build
+/// already targets `avx512bw`), otherwise SSE2 or NEON.
 #[cfg_attr(any(target_arch = "x86", target_arch = "x86_64"), target_feature(enable = "sse2"))]
 #[cfg_attr(target_arch = "aarch64", target_feature(enable = "neon"))]
 pub unsafe fn simd_eq(a: &[u8], b: &[u8]) -> bool {
@@ -3227,6 +4130,13 @@ pub unsafe fn simd_eq(a: &[u8], b: &[u8]) -> bool {
         return false;
     }
 
+    #[cfg(target_arch = "x86_64")]
+    {
+        if cfg!(target_feature = "avx512bw") || is_x86_feature_detected!("avx512bw") {
+            return simd_eq_avx512(a, b);
+        }
+    }
+
     // SSE2 implementation for x86/x86_64
     #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
     {
@@ -3290,269 +4200,187 @@ pub unsafe fn simd_eq(a: &[u8], b: &[u8]) -> bool {
     }
 }
 
-#[inline]
-fn simd_bytes_eq(a: &[u8], b: &[u8]) -> bool {
-    if a.len() != b.len() {
-        return false;
-    }
-    #[cfg(any(target_arch = "x86", target_arch = "x86_64"))]
-    unsafe {
-        use std::arch::x86_64::*;
-        let mut i = 0;
-        let len = a.len();
-        while i + 16 <= len {
-            let a_chunk = _mm_loadu_si128(a.as_ptr().add(i) as *const __m128i);
-            let b_chunk = _mm_loadu_si128(b.as_ptr().add(i) as *const __m128i);
-            let cmp = _mm_cmpeq_epi8(a_chunk, b_chunk);
-            // If all 16 bytes match, _mm_movemask_epi8 returns 0xFFFF.
-            if _mm_movemask_epi8(cmp) != 0xFFFF {
-                return false;
-            }
-            i += 16;
+/// AVX-512 body of `simd_eq`: 64 bytes per comparison, with a masked load
+/// for the tail so no byte past either slice is read
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx512f,avx512bw")]
+unsafe fn simd_eq_avx512(a: &[u8], b: &[u8]) -> bool {
+    let len = a.len();
+    let mut i = 0;
+    while i + 64 <= len {
+        let va = _mm512_loadu_si512(a.as_ptr().add(i) as *const _);
+        let vb = _mm512_loadu_si512(b.as_ptr().add(i) as *const _);
+        if _mm512_cmpeq_epi8_mask(va, vb) != u64::MAX {
+            return false;
         }
-        // Compare any remaining bytes.
-        for j in i..len {
-            if a[j] != b[j] {
-                return false;
-            }
-        }
-        true
+        i += 64;
     }
-    #[cfg(not(any(target_arch = "x86", target_arch = "x86_64")))]
-    {
-        // Fallback on non-x86 architectures.
-        a == b
+
+    let remaining = len - i;
+    if remaining == 0 {
+        return true;
+    }
+    let mask: __mmask64 = (1u64 << remaining) - 1;
+    let va = _mm512_maskz_loadu_epi8(mask, a.as_ptr().add(i) as *const i8);
+    let vb = _mm512_maskz_loadu_epi8(mask, b.as_ptr().add(i) as *const i8);
+    _mm512_mask_cmpeq_epi8_mask(mask, va, vb) == mask
+}
+
+/// The IDs a join matches triples on, or `None` when the predicate or the
+/// literal filter is not a stored term, so no triple can match
+fn join_term_ids(
+    dictionary: &Dictionary,
+    predicate: &str,
+    literal_filter: Option<&str>,
+) -> Option<(u32, Option<u32>)> {
+    let predicate_id = dictionary.string_to_id.get(predicate).copied()?;
+    let literal_filter_id = match literal_filter {
+        Some(filter) => Some(dictionary.string_to_id.get(filter).copied()?),
+        None => None,
+    };
+    Some((predicate_id, literal_filter_id))
+}
+
+/// `result` extended with a triple's subject and object, or `None` when it
+/// already binds either variable to a different term
+#[inline(always)]
+fn extend_binding(
+    result: &Binding,
+    subject_var: &Arc<str>,
+    subject_id: u32,
+    object_var: &Arc<str>,
+    object_id: u32,
+) -> Option<Binding> {
+    let mut extended_result = result.clone();
+    match extended_result.id(subject_var) {
+        Some(existing_subject) if existing_subject != subject_id => return None,
+        Some(_) => {}
+        None => extended_result.bind(Arc::clone(subject_var), subject_id),
+    }
+    match extended_result.id(object_var) {
+        Some(existing_object) if existing_object != object_id => return None,
+        Some(_) => {}
+        None => extended_result.bind(Arc::clone(object_var), object_id),
     }
+    Some(extended_result)
+}
+
+/// The bindings a parallel join probes with each matching triple, grouped by
+/// which of the pattern's variables they already bind. The groups hold
+/// indices into `results`.
+struct PartitionedBindings {
+    subject_var: Arc<str>,
+    object_var: Arc<str>,
+    both_vars_bound: HashMap<(u32, u32), Vec<usize>>,
+    subject_var_bound: HashMap<u32, Vec<usize>>,
+    object_var_bound: HashMap<u32, Vec<usize>>,
+    neither_var_bound: Vec<usize>,
+    results: Vec<Binding>,
 }
 
 #[inline(always)]
-fn process_join<'a>(
-    subject: &str,
-    object: &str,
-    subject_var: &'a str,
-    object_var: &'a str,
-    both_vars_bound: &Arc<HashMap<(String, String), Vec<usize>>>,
-    subject_var_bound: &Arc<HashMap<String, Vec<usize>>>,
-    object_var_bound: &Arc<HashMap<String, Vec<usize>>>,
-    neither_var_bound: &Arc<Vec<usize>>,
-    final_results_arc: &Arc<Vec<BTreeMap<&'a str, String>>>,
-    local_results: &mut Vec<BTreeMap<&'a str, String>>,
+fn process_join(
+    subject_id: u32,
+    object_id: u32,
+    partitions: &PartitionedBindings,
+    local_results: &mut Vec<Binding>,
 ) {
     // Check both_vars_bound - most restrictive case first
-    if let Some(result_indices) = both_vars_bound.get(&(subject.to_string(), object.to_string())) {
+    if let Some(result_indices) = partitions.both_vars_bound.get(&(subject_id, object_id)) {
         for &idx in result_indices {
-            local_results.push(final_results_arc[idx].clone());
+            local_results.push(partitions.results[idx].clone());
         }
     }
 
-    // Process subject_var_bound
-    if let Some(result_indices) = subject_var_bound.get(subject) {
+    // Process subject_var_bound - bind the object variable
+    if let Some(result_indices) = partitions.subject_var_bound.get(&subject_id) {
         for &idx in result_indices {
-            let base_result = &final_results_arc[idx];
-            // Check for object consistency if it exists
-            if let Some(existing_object) = base_result.get(object_var) {
-                if existing_object == object {
-                    local_results.push(base_result.clone());
-                }
-            } else {
-                // Bind the object variable
-                let mut extended_result = base_result.clone();
-                extended_result.insert(object_var, object.to_string());
-                local_results.push(extended_result);
-            }
+            let mut extended_result = partitions.results[idx].clone();
+            extended_result.bind(Arc::clone(&partitions.object_var), object_id);
+            local_results.push(extended_result);
         }
     }
 
-    // Process object_var_bound
-    if let Some(result_indices) = object_var_bound.get(object) {
+    // Process object_var_bound - bind the subject variable
+    if let Some(result_indices) = partitions.object_var_bound.get(&object_id) {
         for &idx in result_indices {
-            let base_result = &final_results_arc[idx];
-            // Check for subject consistency if it exists
-            if let Some(existing_subject) = base_result.get(subject_var) {
-                if existing_subject == subject {
-                    local_results.push(base_result.clone());
-                }
-            } else {
-                // Bind the subject variable
-                let mut extended_result = base_result.clone();
-                extended_result.insert(subject_var, subject.to_string());
-                local_results.push(extended_result);
-            }
+            let mut extended_result = partitions.results[idx].clone();
+            extended_result.bind(Arc::clone(&partitions.subject_var), subject_id);
+            local_results.push(extended_result);
         }
     }
 
     // Process neither_var_bound - least restrictive case last
-    for &idx in neither_var_bound.iter() {
-        let base_result = &final_results_arc[idx];
-        
-        // Check both consistency constraints
-        let subject_consistent = base_result
-            .get(subject_var)
-            .map_or(true, |existing| existing == subject);
-        let object_consistent = base_result
-            .get(object_var)
-            .map_or(true, |existing| existing == object);
-
-        if subject_consistent && object_consistent {
-            let mut extended_result = base_result.clone();
-            
-            // Only insert if not already present
-            if !base_result.contains_key(subject_var) {
-                extended_result.insert(subject_var, subject.to_string());
-            }
-            if !base_result.contains_key(object_var) {
-                extended_result.insert(object_var, object.to_string());
-            }
-            
+    for &idx in partitions.neither_var_bound.iter() {
+        if let Some(extended_result) =
+            extend_binding(&partitions.results[idx], &partitions.subject_var, subject_id, &partitions.object_var, object_id)
+        {
             local_results.push(extended_result);
         }
     }
 }
 
 #[inline(always)]
-fn process_triple_chunk<'a>(
+fn process_triple_chunk(
     triple_chunk: &[Triple],
-    predicate_bytes: &[u8],
-    literal_filter_bytes: &Option<&[u8]>,
-    subject_var: &'a str,
-    object_var: &'a str,
-    both_vars_bound: &Arc<HashMap<(String, String), Vec<usize>>>,
-    subject_var_bound: &Arc<HashMap<String, Vec<usize>>>,
-    object_var_bound: &Arc<HashMap<String, Vec<usize>>>,
-    neither_var_bound: &Arc<Vec<usize>>,
-    final_results_arc: &Arc<Vec<BTreeMap<&'a str, String>>>,
-    local_results: &mut Vec<BTreeMap<&'a str, String>>,
-    dictionary: &'a Dictionary,
+    predicate_id: u32,
+    literal_filter_id: Option<u32>,
+    partitions: &PartitionedBindings,
+    local_results: &mut Vec<Binding>,
 ) {
-    // Pre-filter triples to avoid unnecessary decoding
+    // Pre-filter triples on their IDs
     for triple in triple_chunk {
-        let pred_opt = dictionary.decode(triple.predicate);
-        if pred_opt.is_none() || pred_opt.as_ref().unwrap().as_bytes() != predicate_bytes {
+        if triple.predicate != predicate_id {
             continue;
         }
         
-        if let Some(filter_bytes) = literal_filter_bytes {
-            let obj_opt = dictionary.decode(triple.object);
-            if obj_opt.is_none() || obj_opt.as_ref().unwrap().as_bytes() != *filter_bytes {
+        if let Some(filter_id) = literal_filter_id {
+            if triple.object != filter_id {
                 continue;
             }
-            
-            if let Some(subj) = dictionary.decode(triple.subject) {
-                process_join_efficiently(
-                    &subj,
-                    obj_opt.unwrap(),
-                    subject_var,
-                    object_var,
-                    both_vars_bound,
-                    subject_var_bound,
-                    object_var_bound,
-                    neither_var_bound,
-                    final_results_arc,
-                    local_results,
-                );
-            }
-        } else {
-            let subj_opt = dictionary.decode(triple.subject);
-            let obj_opt = dictionary.decode(triple.object);
-            
-            if let (Some(subj), Some(obj)) = (subj_opt, obj_opt) {
-                process_join_efficiently(
-                    &subj,
-                    &obj,
-                    subject_var,
-                    object_var,
-                    both_vars_bound,
-                    subject_var_bound,
-                    object_var_bound,
-                    neither_var_bound,
-                    final_results_arc,
-                    local_results,
-                );
-            }
         }
+
+        process_join_efficiently(
+            triple.subject,
+            triple.object,
+            partitions,
+            local_results,
+        );
     }
 }
 
 
 #[inline(always)]
-fn process_join_efficiently<'a>(
-    subject: &str,
-    object: &str,
-    subject_var: &'a str,
-    object_var: &'a str,
-    both_vars_bound: &Arc<HashMap<(String, String), Vec<usize>>>,
-    subject_var_bound: &Arc<HashMap<String, Vec<usize>>>,
-    object_var_bound: &Arc<HashMap<String, Vec<usize>>>,
-    neither_var_bound: &Arc<Vec<usize>>,
-    final_results_arc: &Arc<Vec<BTreeMap<&'a str, String>>>,
-    local_results: &mut Vec<BTreeMap<&'a str, String>>,
+fn process_join_efficiently(
+    subject_id: u32,
+    object_id: u32,
+    partitions: &PartitionedBindings,
+    local_results: &mut Vec<Binding>,
 ) {
-    if let Some(result_indices) = both_vars_bound.get(&(subject.to_string(), object.to_string())) {
-        for &idx in result_indices {
-            // Clone efficiently with pre-allocation
-            let result = final_results_arc[idx].clone();
-            local_results.push(result);
-        }
-        return; // Early return after handling the most restrictive case
+    if let Some(result_indices) = partitions.both_vars_bound.get(&(subject_id, object_id)) {
+        local_results.extend(result_indices.iter().map(|&idx| partitions.results[idx].clone()));
     }
 
     // Check for subject var bound - second most restrictive
-    if let Some(result_indices) = subject_var_bound.get(subject) {
-        for &idx in result_indices {
-            let base_result = &final_results_arc[idx];
-            // Check for object consistency if it exists
-            if let Some(existing_object) = base_result.get(object_var) {
-                if existing_object == object {
-                    local_results.push(base_result.clone());
-                }
-            } else {
-                let mut extended_result = base_result.clone();
-                extended_result.insert(object_var, object.to_string());
-                local_results.push(extended_result);
-            }
-        }
+    if let Some(result_indices) = partitions.subject_var_bound.get(&subject_id) {
+        local_results.extend(result_indices.iter().map(|&idx| {
+            let mut extended_result = partitions.results[idx].clone();
+            extended_result.bind(Arc::clone(&partitions.object_var), object_id);
+            extended_result
+        }));
     }
 
     // Check for object var bound
-    if let Some(result_indices) = object_var_bound.get(object) {
-        for &idx in result_indices {
-            let base_result = &final_results_arc[idx];
-            if let Some(existing_subject) = base_result.get(subject_var) {
-                if existing_subject == subject {
-                    local_results.push(base_result.clone());
-                }
-            } else {
-                let mut extended_result = base_result.clone();
-                extended_result.insert(subject_var, subject.to_string());
-                local_results.push(extended_result);
-            }
-        }
+    if let Some(result_indices) = partitions.object_var_bound.get(&object_id) {
+        local_results.extend(result_indices.iter().map(|&idx| {
+            let mut extended_result = partitions.results[idx].clone();
+            extended_result.bind(Arc::clone(&partitions.subject_var), subject_id);
+            extended_result
+        }));
     }
 
     // Process least restrictive case - neither var bound
-    for &idx in neither_var_bound.iter() {
-        let base_result = &final_results_arc[idx];
-        
-        // Check both consistency constraints
-        let subject_consistent = base_result
-            .get(subject_var)
-            .map_or(true, |existing| existing == subject);
-        let object_consistent = base_result
-            .get(object_var)
-            .map_or(true, |existing| existing == object);
-
-        if subject_consistent && object_consistent {
-            let mut extended_result = base_result.clone();
-            
-            // Only insert if not already present
-            if !base_result.contains_key(subject_var) {
-                extended_result.insert(subject_var, subject.to_string());
-            }
-            if !base_result.contains_key(object_var) {
-                extended_result.insert(object_var, object.to_string());
-            }
-            
-            local_results.push(extended_result);
-        }
-    }
+    local_results.extend(partitions.neither_var_bound.iter().filter_map(|&idx| {
+        extend_binding(&partitions.results[idx], &partitions.subject_var, subject_id, &partitions.object_var, object_id)
+    }));
 }
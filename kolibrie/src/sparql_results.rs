@@ -0,0 +1,370 @@
+/*
+ * Copyright © 2026 Volodymyr Kadzhaia
+ * Copyright © 2026 Pieter Bonte
+ * KU Leuven — Stream Intelligence Lab, Belgium
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this file,
+ * you can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+//! Serializers for the W3C SPARQL 1.1 query result formats (JSON, XML, CSV
+//! and TSV), working on the decoded rows produced by the query executor.
+
+/// A SPARQL result serialization, identified by its media type.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ResultFormat {
+    Json,
+    Xml,
+    Csv,
+    Tsv,
+}
+
+impl ResultFormat {
+    pub fn media_type(&self) -> &'static str {
+        match self {
+            ResultFormat::Json => "application/sparql-results+json",
+            ResultFormat::Xml => "application/sparql-results+xml",
+            ResultFormat::Csv => "text/csv",
+            ResultFormat::Tsv => "text/tab-separated-values",
+        }
+    }
+
+    /// Map a single media type (parameters such as `charset` are ignored) to a
+    /// result format. Generic JSON/XML types are accepted as aliases.
+    pub fn from_media_type(media_type: &str) -> Option<Self> {
+        let essence = media_type.split(';').next().unwrap_or("").trim().to_ascii_lowercase();
+        match essence.as_str() {
+            "application/sparql-results+json" | "application/json" => Some(ResultFormat::Json),
+            "application/sparql-results+xml" | "application/xml" | "text/xml" => Some(ResultFormat::Xml),
+            "text/csv" => Some(ResultFormat::Csv),
+            "text/tab-separated-values" => Some(ResultFormat::Tsv),
+            _ => None,
+        }
+    }
+
+    /// Map a short format name (`json`, `xml`, `csv`, `tsv`) or a media type
+    /// to a result format.
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.trim().to_ascii_lowercase().as_str() {
+            "json" => Some(ResultFormat::Json),
+            "xml" => Some(ResultFormat::Xml),
+            "csv" => Some(ResultFormat::Csv),
+            "tsv" => Some(ResultFormat::Tsv),
+            _ => ResultFormat::from_media_type(name),
+        }
+    }
+
+    /// Pick the format for an HTTP `Accept` header, honouring `q` weights.
+    /// Returns `Some(default)` for a missing header or wildcards and `None`
+    /// when none of the acceptable types is supported.
+    pub fn negotiate(accept: Option<&str>, default: ResultFormat) -> Option<Self> {
+        let Some(accept) = accept.filter(|a| !a.trim().is_empty()) else {
+            return Some(default);
+        };
+
+        let mut candidates: Vec<(f32, usize, ResultFormat)> = Vec::new();
+        for (position, range) in accept.split(',').enumerate() {
+            let mut parts = range.split(';');
+            let media_range = parts.next().unwrap_or("").trim();
+            let quality = parts
+                .filter_map(|param| param.trim().strip_prefix("q="))
+                .find_map(|q| q.trim().parse::<f32>().ok())
+                .unwrap_or(1.0);
+            if quality <= 0.0 {
+                continue;
+            }
+            let format = match media_range {
+                "*/*" | "application/*" => Some(default),
+                "text/*" => Some(ResultFormat::Tsv),
+                _ => ResultFormat::from_media_type(media_range),
+            };
+            if let Some(format) = format {
+                candidates.push((quality, position, format));
+            }
+        }
+
+        candidates
+            .into_iter()
+            .max_by(|a, b| a.0.total_cmp(&b.0).then(b.1.cmp(&a.1)))
+            .map(|(_, _, format)| format)
+    }
+
+    pub fn serialize(&self, variables: &[String], rows: &[Vec<String>]) -> String {
+        match self {
+            ResultFormat::Json => to_sparql_json(variables, rows),
+            ResultFormat::Xml => to_sparql_xml(variables, rows),
+            ResultFormat::Csv => to_csv(variables, rows),
+            ResultFormat::Tsv => to_tsv(variables, rows),
+        }
+    }
+}
+
+/// RDF term kinds distinguished by the result formats.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ResultTerm<'a> {
+    Iri(&'a str),
+    BlankNode(&'a str),
+    Literal {
+        value: &'a str,
+        datatype: Option<&'a str>,
+        lang: Option<&'a str>,
+    },
+}
+
+/// Classify a decoded dictionary term. IRIs are stored without angle
+/// brackets, blank nodes with their `_:` prefix, and literals either bare or
+/// in N-Triples form (`"v"`, `"v"@en`, `"v"^^<dt>`).
+pub fn classify_term(term: &str) -> ResultTerm<'_> {
+    if let Some(label) = term.strip_prefix("_:") {
+        return ResultTerm::BlankNode(label);
+    }
+    if let Some(inner) = term.strip_prefix('<').and_then(|t| t.strip_suffix('>')) {
+        return ResultTerm::Iri(inner);
+    }
+    if let Some(rest) = term.strip_prefix('"') {
+        if let Some(end) = rest.rfind('"') {
+            let value = &rest[..end];
+            let suffix = &rest[end + 1..];
+            if let Some(datatype) = suffix.strip_prefix("^^") {
+                let datatype = datatype
+                    .strip_prefix('<')
+                    .and_then(|d| d.strip_suffix('>'))
+                    .unwrap_or(datatype);
+                return ResultTerm::Literal { value, datatype: Some(datatype), lang: None };
+            }
+            if let Some(lang) = suffix.strip_prefix('@') {
+                return ResultTerm::Literal { value, datatype: None, lang: Some(lang) };
+            }
+            if suffix.is_empty() {
+                return ResultTerm::Literal { value, datatype: None, lang: None };
+            }
+        }
+    }
+    if looks_like_iri(term) {
+        return ResultTerm::Iri(term);
+    }
+    ResultTerm::Literal { value: term, datatype: None, lang: None }
+}
+
+fn looks_like_iri(term: &str) -> bool {
+    let Some((scheme, rest)) = term.split_once(':') else {
+        return false;
+    };
+    !scheme.is_empty()
+        && !rest.is_empty()
+        && scheme.chars().next().is_some_and(|c| c.is_ascii_alphabetic())
+        && scheme.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+        && !term.chars().any(|c| c.is_whitespace() || matches!(c, '"' | '<' | '>'))
+}
+
+pub fn to_sparql_json(variables: &[String], rows: &[Vec<String>]) -> String {
+    let bindings: Vec<serde_json::Value> = rows
+        .iter()
+        .map(|row| {
+            let mut binding = serde_json::Map::new();
+            for (var, value) in variables.iter().zip(row.iter()) {
+                if value.is_empty() {
+                    continue;
+                }
+                let term = match classify_term(value) {
+                    ResultTerm::Iri(iri) => serde_json::json!({ "type": "uri", "value": iri }),
+                    ResultTerm::BlankNode(label) => serde_json::json!({ "type": "bnode", "value": label }),
+                    ResultTerm::Literal { value, datatype, lang } => {
+                        let mut literal = serde_json::json!({ "type": "literal", "value": value });
+                        if let Some(datatype) = datatype {
+                            literal["datatype"] = serde_json::Value::from(datatype);
+                        }
+                        if let Some(lang) = lang {
+                            literal["xml:lang"] = serde_json::Value::from(lang);
+                        }
+                        literal
+                    }
+                };
+                binding.insert(var.clone(), term);
+            }
+            serde_json::Value::Object(binding)
+        })
+        .collect();
+
+    serde_json::json!({
+        "head": { "vars": variables },
+        "results": { "bindings": bindings },
+    })
+    .to_string()
+}
+
+pub fn to_sparql_xml(variables: &[String], rows: &[Vec<String>]) -> String {
+    let mut xml = String::from(
+        "<?xml version=\"1.0\"?>\n<sparql xmlns=\"http://www.w3.org/2005/sparql-results#\">\n  <head>\n",
+    );
+    for var in variables {
+        xml.push_str(&format!("    <variable name=\"{}\"/>\n", escape_xml(var)));
+    }
+    xml.push_str("  </head>\n  <results>\n");
+    for row in rows {
+        xml.push_str("    <result>\n");
+        for (var, value) in variables.iter().zip(row.iter()) {
+            if value.is_empty() {
+                continue;
+            }
+            let term = match classify_term(value) {
+                ResultTerm::Iri(iri) => format!("<uri>{}</uri>", escape_xml(iri)),
+                ResultTerm::BlankNode(label) => format!("<bnode>{}</bnode>", escape_xml(label)),
+                ResultTerm::Literal { value, datatype: Some(datatype), .. } => format!(
+                    "<literal datatype=\"{}\">{}</literal>",
+                    escape_xml(datatype),
+                    escape_xml(value)
+                ),
+                ResultTerm::Literal { value, lang: Some(lang), .. } => format!(
+                    "<literal xml:lang=\"{}\">{}</literal>",
+                    escape_xml(lang),
+                    escape_xml(value)
+                ),
+                ResultTerm::Literal { value, .. } => format!("<literal>{}</literal>", escape_xml(value)),
+            };
+            xml.push_str(&format!("      <binding name=\"{}\">{}</binding>\n", escape_xml(var), term));
+        }
+        xml.push_str("    </result>\n");
+    }
+    xml.push_str("  </results>\n</sparql>\n");
+    xml
+}
+
+pub fn to_csv(variables: &[String], rows: &[Vec<String>]) -> String {
+    let mut csv = variables.iter().map(|v| escape_csv(v)).collect::<Vec<_>>().join(",");
+    csv.push_str("\r\n");
+    for row in rows {
+        let fields: Vec<String> = row
+            .iter()
+            .map(|value| match classify_term(value) {
+                ResultTerm::Iri(iri) => escape_csv(iri),
+                ResultTerm::BlankNode(label) => format!("_:{}", label),
+                ResultTerm::Literal { value, .. } => escape_csv(value),
+            })
+            .collect();
+        csv.push_str(&fields.join(","));
+        csv.push_str("\r\n");
+    }
+    csv
+}
+
+pub fn to_tsv(variables: &[String], rows: &[Vec<String>]) -> String {
+    let mut tsv = variables.iter().map(|v| format!("?{}", v)).collect::<Vec<_>>().join("\t");
+    tsv.push('\n');
+    for row in rows {
+        let fields: Vec<String> = row
+            .iter()
+            .map(|value| {
+                if value.is_empty() {
+                    return String::new();
+                }
+                match classify_term(value) {
+                    ResultTerm::Iri(iri) => format!("<{}>", iri),
+                    ResultTerm::BlankNode(label) => format!("_:{}", label),
+                    ResultTerm::Literal { value, datatype, lang } => {
+                        let mut literal = format!("\"{}\"", escape_tsv_literal(value));
+                        if let Some(datatype) = datatype {
+                            literal.push_str(&format!("^^<{}>", datatype));
+                        } else if let Some(lang) = lang {
+                            literal.push_str(&format!("@{}", lang));
+                        }
+                        literal
+                    }
+                }
+            })
+            .collect();
+        tsv.push_str(&fields.join("\t"));
+        tsv.push('\n');
+    }
+    tsv
+}
+
+fn escape_xml(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn escape_csv(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn escape_tsv_literal(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\t', "\\t")
+        .replace('\n', "\\n")
+        .replace('\r', "\\r")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn classifies_decoded_terms() {
+        assert_eq!(classify_term("http://example.org/a"), ResultTerm::Iri("http://example.org/a"));
+        assert_eq!(classify_term("_:b0"), ResultTerm::BlankNode("b0"));
+        assert_eq!(
+            classify_term("\"42\"^^<http://www.w3.org/2001/XMLSchema#integer>"),
+            ResultTerm::Literal {
+                value: "42",
+                datatype: Some("http://www.w3.org/2001/XMLSchema#integer"),
+                lang: None,
+            }
+        );
+        assert_eq!(
+            classify_term("\"chat\"@fr"),
+            ResultTerm::Literal { value: "chat", datatype: None, lang: Some("fr") }
+        );
+        assert_eq!(
+            classify_term("John Smith"),
+            ResultTerm::Literal { value: "John Smith", datatype: None, lang: None }
+        );
+    }
+
+    #[test]
+    fn negotiates_accept_header() {
+        let default = ResultFormat::Json;
+        assert_eq!(ResultFormat::negotiate(None, default), Some(ResultFormat::Json));
+        assert_eq!(ResultFormat::negotiate(Some("*/*"), default), Some(ResultFormat::Json));
+        assert_eq!(
+            ResultFormat::negotiate(Some("application/sparql-results+xml"), default),
+            Some(ResultFormat::Xml)
+        );
+        assert_eq!(
+            ResultFormat::negotiate(
+                Some("application/sparql-results+json;q=0.5, text/tab-separated-values"),
+                default
+            ),
+            Some(ResultFormat::Tsv)
+        );
+        assert_eq!(ResultFormat::negotiate(Some("image/png"), default), None);
+    }
+
+    #[test]
+    fn serializes_sparql_json() {
+        let vars = vec!["s".to_string(), "name".to_string()];
+        let rows = vec![vec!["http://example.org/a".to_string(), "Alice".to_string()]];
+        let json: serde_json::Value = serde_json::from_str(&to_sparql_json(&vars, &rows)).unwrap();
+        assert_eq!(json["head"]["vars"], serde_json::json!(["s", "name"]));
+        assert_eq!(json["results"]["bindings"][0]["s"]["type"], "uri");
+        assert_eq!(json["results"]["bindings"][0]["name"]["value"], "Alice");
+    }
+
+    #[test]
+    fn serializes_tsv_and_csv() {
+        let vars = vec!["s".to_string(), "o".to_string()];
+        let rows = vec![vec!["http://example.org/a".to_string(), "a, b".to_string()]];
+        assert_eq!(to_tsv(&vars, &rows), "?s\t?o\n<http://example.org/a>\t\"a, b\"\n");
+        assert_eq!(to_csv(&vars, &rows), "s,o\r\nhttp://example.org/a,\"a, b\"\r\n");
+    }
+}
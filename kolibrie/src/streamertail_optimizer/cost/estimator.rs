@@ -45,7 +45,7 @@ impl<'a> CostEstimator<'a> {
             PhysicalOperator::TableScan { pattern } => {
                 self.estimate_cardinality(pattern) * CostConstants::COST_PER_ROW_SCAN
             }
-            PhysicalOperator::IndexScan { pattern } => {
+            PhysicalOperator::IndexScan { pattern, .. } => {
                 let cardinality = self.estimate_cardinality(pattern);
                 let bound_count = self.count_bound_variables(pattern);
 
@@ -187,6 +187,7 @@ impl<'a> CostEstimator<'a> {
                 
                 input_cost + python_overhead + (cardinality * per_row_cost)
             }
+            PhysicalOperator::Profile { inner, .. } => self.estimate_cost(inner),
         }
     }
 
@@ -197,26 +198,28 @@ impl<'a> CostEstimator<'a> {
             // Fully bound - always returns 0 or 1
             (Term::Constant(_), Term::Constant(_), Term::Constant(_)) => 1,
 
-            // Two bounds - use actual index stats
+            // Two bounds - combine the selectivities of both bound terms
             (Term::Constant(s), Term::Constant(p), Term::Variable(_)) => {
-                // Look up actual SPO cardinality
-                self.stats.get_subject_cardinality(*s)
-                    .min(self.stats.get_predicate_cardinality(*p))
-                    .max(1)
+                self.combined_cardinality(
+                    self.stats.get_subject_cardinality(*s),
+                    self.predicate_cardinality(*p),
+                )
             }
 
             (Term::Constant(s), Term::Variable(_), Term::Constant(o)) => {
                 // S*O pattern
-                self.stats.get_subject_cardinality(*s)
-                    .min(self.stats.get_object_cardinality(*o))
-                    .max(1)
+                self.combined_cardinality(
+                    self.stats.get_subject_cardinality(*s),
+                    self.stats.get_object_cardinality(*o),
+                )
             }
 
             (Term::Variable(_), Term::Constant(p), Term::Constant(o)) => {
                 // *PO pattern
-                self.stats.get_predicate_cardinality(*p)
-                    .min(self.stats.get_object_cardinality(*o))
-                    .max(1)
+                self.combined_cardinality(
+                    self.predicate_cardinality(*p),
+                    self.stats.get_object_cardinality(*o),
+                )
             }
 
             // One bound - use predicate/subject/object cardinality directly
@@ -225,8 +228,8 @@ impl<'a> CostEstimator<'a> {
             }
 
             (Term::Variable(_), Term::Constant(p), Term::Variable(_)) => {
-                // This is the KEY one - should return ACTUAL predicate cardinality!
-                self.stats.get_predicate_cardinality(*p).max(1)
+                // `rdf:type` matches far more triples than `foaf:homepage`
+                self.predicate_cardinality(*p).max(1)
             }
 
             (Term::Variable(_), Term::Variable(_), Term::Constant(o)) => {
@@ -255,6 +258,25 @@ impl<'a> CostEstimator<'a> {
         }
     }
 
+    /// Triples with predicate `p`: its count in the statistics, or, for a
+    /// predicate the statistics do not know (they may come from a sample),
+    /// the fraction of the triples an average predicate has
+    fn predicate_cardinality(&self, p: u32) -> u64 {
+        match self.stats.predicate_cardinalities.get(&p) {
+            Some(&cardinality) => cardinality,
+            None => self.stats.total_triples / (self.stats.predicate_cardinalities.len() as u64).max(1),
+        }
+    }
+
+    /// Triples matching two bound terms that match `a` and `b` triples on
+    /// their own, taking the terms as independent: `a * b / total`, at most
+    /// the smaller of the two and at least 1
+    fn combined_cardinality(&self, a: u64, b: u64) -> u64 {
+        let total = self.stats.total_triples.max(1) as f64;
+        let estimate = (a as f64 * b as f64 / total).ceil() as u64;
+        estimate.min(a).min(b).max(1)
+    }
+
     /// Estimates the selectivity of a condition
     pub fn estimate_selectivity(&self, condition: &Condition) -> f64 {
         self.estimate_filter_selectivity(&condition.expression)
@@ -299,13 +321,26 @@ impl<'a> CostEstimator<'a> {
                     _ => 0.5,
                 }
             }
+            FilterExpression::FunctionComparison(_, _, op, _)
+            | FilterExpression::StringFunctionComparison(_, _, op, _)
+            | FilterExpression::NumericFunction(_, _, op, _) => {
+                // Same as a plain comparison on the function's result
+                self.estimate_filter_selectivity(&FilterExpression::Comparison("", op, ""))
+            }
+            FilterExpression::If(_, _, _, Some((op, _))) | FilterExpression::Iri(_, Some((op, _))) => {
+                self.estimate_filter_selectivity(&FilterExpression::Comparison("", op, ""))
+            }
+            FilterExpression::StringFunction(..)
+            | FilterExpression::Coalesce(..)
+            | FilterExpression::If(..)
+            | FilterExpression::Iri(..) => 0.5,
         }
     }
 
     /// Extracts the predicate ID from a physical operator if it's a scan
     fn extract_predicate_from_physical(&self, plan: &PhysicalOperator) -> Option<u32> {
         match plan {
-            PhysicalOperator::TableScan { pattern } | PhysicalOperator::IndexScan { pattern } => {
+            PhysicalOperator::TableScan { pattern } | PhysicalOperator::IndexScan { pattern, .. } => {
                 if let Term::Constant(pred_id) = pattern.1 {
                     Some(pred_id)
                 } else {
@@ -334,7 +369,7 @@ impl<'a> CostEstimator<'a> {
     pub fn estimate_output_cardinality(&self, plan: &PhysicalOperator) -> u64 {
         match plan {
             PhysicalOperator::TableScan { pattern } => self.estimate_cardinality(pattern),
-            PhysicalOperator::IndexScan { pattern } => self.estimate_cardinality(pattern),
+            PhysicalOperator::IndexScan { pattern, .. } => self.estimate_cardinality(pattern),
             PhysicalOperator::Filter { input, condition } => {
                 let input_cardinality = self.estimate_output_cardinality(input);
                 let selectivity = self.estimate_selectivity(condition);
@@ -404,6 +439,7 @@ impl<'a> CostEstimator<'a> {
                 // ML.PREDICT doesn't change cardinality, just adds a column
                 self.estimate_output_cardinality(input)
             }
+            PhysicalOperator::Profile { inner, .. } => self.estimate_output_cardinality(inner),
         }
     }
 
@@ -480,4 +516,27 @@ mod tests {
         let pattern = (Term::Constant(1), Term::Constant(2), Term::Constant(3));
         assert_eq!(estimator.count_bound_variables(&pattern), 3);
     }
+
+    #[test]
+    fn test_bound_predicates_use_their_own_cardinality() {
+        const TYPE: u32 = 1;
+        const HOMEPAGE: u32 = 2;
+        const UNKNOWN: u32 = 3;
+        const ALICE: u32 = 10;
+        let mut stats = create_test_stats();
+        stats.predicate_cardinalities.insert(TYPE, 800);
+        stats.predicate_cardinalities.insert(HOMEPAGE, 200);
+        stats.subject_cardinalities.insert(ALICE, 10);
+        let estimator = CostEstimator::new(&stats);
+        let var = |name: &str| Term::Variable(name.to_string());
+
+        assert_eq!(estimator.estimate_cardinality(&(var("s"), Term::Constant(TYPE), var("o"))), 800);
+        assert_eq!(estimator.estimate_cardinality(&(var("s"), Term::Constant(HOMEPAGE), var("o"))), 200);
+        // Not in the statistics: the average of the known predicates
+        assert_eq!(estimator.estimate_cardinality(&(var("s"), Term::Constant(UNKNOWN), var("o"))), 500);
+
+        // Alice has 1% of the triples, so about 8 of the type triples and 2 homepages
+        assert_eq!(estimator.estimate_cardinality(&(Term::Constant(ALICE), Term::Constant(TYPE), var("o"))), 8);
+        assert_eq!(estimator.estimate_cardinality(&(Term::Constant(ALICE), Term::Constant(HOMEPAGE), var("o"))), 2);
+    }
 }
@@ -0,0 +1,72 @@
+/*
+ * Copyright © 2026 Volodymyr Kadzhaia
+ * Copyright © 2026 Pieter Bonte
+ * KU Leuven — Stream Intelligence Lab, Belgium
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this file,
+ * you can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use crate::custom_error::SparqlError;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+/// Cooperative cancellation for a running query.
+///
+/// The execution engine polls the token between operators and every few
+/// thousand rows inside scans and joins; once it reports cancelled, operators
+/// stop producing rows and the partial result is discarded by the caller.
+/// Operators that refuse to continue (e.g. an oversized Cartesian product)
+/// cancel the token with the error that explains why.
+#[derive(Debug, Default)]
+pub struct CancellationToken {
+    cancelled: AtomicBool,
+    deadline: Option<Instant>,
+    error: Mutex<Option<SparqlError>>,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Token that cancels itself once `timeout` has elapsed
+    pub fn with_timeout(timeout: Duration) -> Self {
+        Self {
+            deadline: Instant::now().checked_add(timeout),
+            ..Self::default()
+        }
+    }
+
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    /// Cancel the query because of `error`; only the first error is kept
+    pub fn fail(&self, error: SparqlError) {
+        if let Ok(mut slot) = self.error.lock() {
+            slot.get_or_insert(error);
+        }
+        self.cancel();
+    }
+
+    /// The error passed to `fail`, if any
+    pub fn take_error(&self) -> Option<SparqlError> {
+        self.error.lock().ok().and_then(|mut slot| slot.take())
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        if self.cancelled.load(Ordering::Relaxed) {
+            return true;
+        }
+        match self.deadline {
+            Some(deadline) if Instant::now() >= deadline => {
+                self.cancel();
+                true
+            }
+            _ => false,
+        }
+    }
+}
@@ -0,0 +1,128 @@
+/*
+ * Copyright © 2026 Volodymyr Kadzhaia
+ * Copyright © 2026 Pieter Bonte
+ * KU Leuven — Stream Intelligence Lab, Belgium
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this file,
+ * you can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, HashSet};
+use std::hash::{Hash, Hasher};
+
+/// Inputs with at least this many rows are screened with a bloom filter
+/// before the exact duplicate check
+const BLOOM_FILTER_THRESHOLD: usize = 65_536;
+
+/// About a 1% false-positive rate
+const BLOOM_BITS_PER_ROW: usize = 10;
+const BLOOM_PROBES: u64 = 7;
+
+/// Bloom filter over 64-bit row hashes, probed by double hashing
+struct BloomFilter {
+    bits: Vec<u64>,
+    len: u64,
+}
+
+impl BloomFilter {
+    fn with_capacity(rows: usize) -> Self {
+        let words = (rows * BLOOM_BITS_PER_ROW).div_ceil(64).max(1);
+        Self {
+            bits: vec![0; words],
+            len: words as u64 * 64,
+        }
+    }
+
+    /// Add `hash`, returning whether it was (probably) present already
+    fn insert(&mut self, hash: u64) -> bool {
+        let step = hash.rotate_left(32) | 1;
+        let mut present = true;
+        for probe in 0..BLOOM_PROBES {
+            let bit = hash.wrapping_add(probe.wrapping_mul(step)) % self.len;
+            let (word, mask) = ((bit / 64) as usize, 1u64 << (bit % 64));
+            present &= self.bits[word] & mask != 0;
+            self.bits[word] |= mask;
+        }
+        present
+    }
+}
+
+/// Keep the first row of each distinct combination of `variables`, in input
+/// order. An unbound variable is a value of its own, so `{x: 1}` and
+/// `{x: 1, y: 2}` are different solutions.
+///
+/// Large inputs take a first pass through a bloom filter: a row whose hash
+/// the filter has not seen cannot be a duplicate, so only the rows it flags
+/// are kept in the exact set, which stays proportional to the duplicates
+/// instead of the result.
+pub fn distinct_rows(rows: Vec<HashMap<String, u32>>, variables: &[String]) -> Vec<HashMap<String, u32>> {
+    let key = |row: &HashMap<String, u32>| -> Vec<Option<u32>> {
+        variables.iter().map(|var| row.get(var).copied()).collect()
+    };
+
+    if rows.len() < BLOOM_FILTER_THRESHOLD {
+        let mut seen = HashSet::with_capacity(rows.len());
+        return rows.into_iter().filter(|row| seen.insert(key(row))).collect();
+    }
+
+    let hashes: Vec<u64> = rows
+        .iter()
+        .map(|row| {
+            let mut hasher = DefaultHasher::new();
+            key(row).hash(&mut hasher);
+            hasher.finish()
+        })
+        .collect();
+
+    // Hashes that may occur more than once; all other rows are unique
+    let mut bloom = BloomFilter::with_capacity(rows.len());
+    let candidates: HashSet<u64> = hashes.iter().copied().filter(|&hash| bloom.insert(hash)).collect();
+    drop(bloom);
+
+    let mut seen = HashSet::new();
+    rows.into_iter()
+        .zip(hashes)
+        .filter(|(row, hash)| !candidates.contains(hash) || seen.insert(key(row)))
+        .map(|(row, _)| row)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn row(pairs: &[(&str, u32)]) -> HashMap<String, u32> {
+        pairs.iter().map(|(var, id)| (var.to_string(), *id)).collect()
+    }
+
+    #[test]
+    fn keeps_first_occurrence_of_each_projected_row() {
+        let rows = vec![
+            row(&[("type", 1), ("s", 10)]),
+            row(&[("type", 2), ("s", 11)]),
+            row(&[("type", 1), ("s", 12)]),
+            row(&[("s", 13)]),
+            row(&[("s", 14)]),
+        ];
+        let distinct = distinct_rows(rows, &["type".to_string()]);
+        let kept: Vec<u32> = distinct.iter().map(|row| row["s"]).collect();
+        assert_eq!(kept, vec![10, 11, 13]);
+    }
+
+    #[test]
+    fn bloom_filtered_pass_matches_exact_deduplication() {
+        let vars = vec!["x".to_string(), "y".to_string()];
+        let rows: Vec<_> = (0..BLOOM_FILTER_THRESHOLD as u32 * 2)
+            .map(|i| row(&[("x", i % 1000), ("y", i % 7)]))
+            .collect();
+        let mut exact = HashSet::new();
+        let expected: Vec<_> = rows
+            .iter()
+            .filter(|row| exact.insert((row["x"], row["y"])))
+            .cloned()
+            .collect();
+        assert_eq!(distinct_rows(rows, &vars), expected);
+    }
+}
@@ -9,29 +9,72 @@
  */
 
 use super::super::operators::PhysicalOperator;
-
+use super::super::types::{Condition, IndexOrder};
+use super::super::utils::scan_equality_binding;
+use super::cancellation::CancellationToken;
+use super::distinct::distinct_rows;
+use super::profiler::profiled_input_rows;
+
+use crate::custom_error::SparqlError;
+use crate::pattern_cache::PatternKey;
 use crate::sparql_database::SparqlDatabase;
+use crate::parser::select_expression;
+use crate::utils::{evaluate_if, evaluate_iri, parse_numeric_literal};
+use log::{debug, error, warn};
 use ml::MLPredictionResult;
 use rayon::prelude::*;
 
+use shared::query::SelectExpression;
 use shared::terms::{Term, TriplePattern};
 use shared::quoted_triple_store::is_quoted_triple_id;
+use shared::simd_scan::{for_each_match, BoundIds};
+use shared::triple::Triple;
 
 use std::collections::{HashMap, HashSet};
+use std::time::Instant;
+
+/// Rows processed between cancellation checks in sequential loops
+const CANCELLATION_CHECK_INTERVAL: usize = 4096;
+
+/// Triples buffered from the store per SIMD table scan pass
+const TABLE_SCAN_BLOCK: usize = 1024;
+
+/// Stop the query with `error`, which the entry points return once the
+/// operators have unwound
+fn abort(token: &CancellationToken, error: SparqlError) -> Vec<HashMap<String, u32>> {
+    token.fail(error);
+    Vec::new()
+}
 
 /// Execution engine for physical operators
 pub struct ExecutionEngine;
 
 impl ExecutionEngine {
-    /// Executes a physical operator and returns string results
+    /// Executes a physical operator and returns string results. Operator
+    /// failures, such as a join over `max_join_results`, are returned as
+    /// errors; a query cancelled through `database.cancellation` returns the
+    /// rows found so far.
     pub fn execute(
         operator: &PhysicalOperator,
-        database: &mut SparqlDatabase,
-    ) -> Vec<HashMap<String, String>> {
-        let id_results = Self::execute_with_ids(operator, database);
+        database: &SparqlDatabase,
+    ) -> Result<Vec<HashMap<String, String>>, SparqlError> {
+        match &database.cancellation {
+            Some(token) => Self::execute_cancellable(operator, database, token),
+            None => Self::execute_cancellable(operator, database, &CancellationToken::new()),
+        }
+    }
+
+    /// Like `execute`, checking `token` instead of `database.cancellation`, so
+    /// queries sharing a database can be cancelled one at a time
+    pub fn execute_cancellable(
+        operator: &PhysicalOperator,
+        database: &SparqlDatabase,
+        token: &CancellationToken,
+    ) -> Result<Vec<HashMap<String, String>>, SparqlError> {
+        let id_results = Self::execute_with_ids_cancellable(operator, database, token)?;
 
         // Convert ID results to string results only at the final step
-        id_results
+        Ok(id_results
         .into_par_iter()
         .map(|id_result| {
             let dict = database.dictionary.read().unwrap();
@@ -47,44 +90,81 @@ impl ExecutionEngine {
             drop(dict);
             result
         })
-        .collect()
+        .collect())
     }
 
     /// Executes a physical operator and returns ID-based results for performance
     pub fn execute_with_ids(
         operator: &PhysicalOperator,
-        database: &mut SparqlDatabase,
+        database: &SparqlDatabase,
+    ) -> Result<Vec<HashMap<String, u32>>, SparqlError> {
+        match &database.cancellation {
+            Some(token) => Self::execute_with_ids_cancellable(operator, database, token),
+            None => Self::execute_with_ids_cancellable(operator, database, &CancellationToken::new()),
+        }
+    }
+
+    /// Like `execute_with_ids`, checking `token` instead of `database.cancellation`
+    pub fn execute_with_ids_cancellable(
+        operator: &PhysicalOperator,
+        database: &SparqlDatabase,
+        token: &CancellationToken,
+    ) -> Result<Vec<HashMap<String, u32>>, SparqlError> {
+        let rows = Self::run(operator, database, token);
+        match token.take_error() {
+            Some(err) => Err(err),
+            None => Ok(rows),
+        }
+    }
+
+    fn run(
+        operator: &PhysicalOperator,
+        database: &SparqlDatabase,
+        cancel: &CancellationToken,
     ) -> Vec<HashMap<String, u32>> {
+        // Stop between operators once the query is cancelled
+        if cancel.is_cancelled() {
+            return Vec::new();
+        }
+
         match operator {
             PhysicalOperator::TableScan { pattern } => {
                 if Self::has_quoted_triple_term(pattern) {
-                    Self::resolve_quoted_triple_scan(database, pattern)
+                    Self::resolve_quoted_triple_scan(database, pattern, cancel)
                 } else {
-                    Self::execute_table_scan_with_ids(database, pattern)
+                    Self::execute_table_scan_with_ids(database, pattern, cancel)
                 }
             }
-            PhysicalOperator::IndexScan { pattern } => {
+            PhysicalOperator::IndexScan { pattern, index } => {
                 if Self::has_quoted_triple_term(pattern) {
-                    Self::resolve_quoted_triple_scan(database, pattern)
+                    Self::resolve_quoted_triple_scan(database, pattern, cancel)
+                } else if let Some(index) = index {
+                    Self::execute_ordered_index_scan_with_ids(database, pattern, *index)
                 } else {
-                    Self::execute_index_scan_with_ids(database, pattern)
+                    Self::execute_index_scan_with_ids(database, pattern, cancel)
                 }
             }
             PhysicalOperator::Filter { input, condition } => {
-                let input_results = Self::execute_with_ids(input, database);
+                if let Some(rows) = Self::execute_bound_scan_with_ids(input, condition, database, cancel) {
+                    return rows;
+                }
+                if let Some(rows) = Self::execute_text_search_with_ids(input, condition, database, cancel) {
+                    return rows;
+                }
+                let input_results = Self::run(input, database, cancel);
                 // Use parallel filtering
                 input_results
                 .into_par_iter()
                 .filter(|result| {
                     let dict = database.dictionary.read().unwrap();
-                    let result = condition.evaluate_with_ids(result, &*dict);
+                    let result = condition.evaluate_with_ids(result, &dict, &database.udfs);
                     drop(dict);
                     result
                 })
                 .collect()
             }
-            PhysicalOperator::Projection { input, variables } => {
-                let input_results = Self::execute_with_ids(input, database);
+            PhysicalOperator::Projection { input, variables, distinct } => {
+                let input_results = Self::run(input, database, cancel);
 
                 // Strip '?' prefix from projection variables for matching
                 let stripped_vars: Vec<String> = variables
@@ -102,35 +182,39 @@ impl ExecutionEngine {
                         result
                     })
                     .collect();
-                projected
+                if *distinct {
+                    distinct_rows(projected, &stripped_vars)
+                } else {
+                    projected
+                }
             }
             PhysicalOperator::OptimizedHashJoin { left, right } => {
-                let left_results = Self::execute_with_ids(left, database);
-                let right_results = Self::execute_with_ids(right, database);
-                Self::execute_optimized_hash_join_with_ids(left_results, right_results)
+                let left_results = Self::run(left, database, cancel);
+                let right_results = Self::run(right, database, cancel);
+                Self::execute_optimized_hash_join_with_ids(left_results, right_results, cancel, database.max_join_results)
             }
             PhysicalOperator::HashJoin { left, right } => {
-                let left_results = Self::execute_with_ids(left, database);
-                let right_results = Self::execute_with_ids(right, database);
-                Self::execute_hash_join_with_ids(left_results, right_results)
+                let left_results = Self::run(left, database, cancel);
+                let right_results = Self::run(right, database, cancel);
+                Self::execute_hash_join_with_ids(left_results, right_results, cancel, database.max_join_results)
             }
             PhysicalOperator::NestedLoopJoin { left, right } => {
-                let left_results = Self::execute_with_ids(left, database);
-                let right_results = Self::execute_with_ids(right, database);
-                Self::execute_nested_loop_join_with_ids(left_results, right_results)
+                let left_results = Self::run(left, database, cancel);
+                let right_results = Self::run(right, database, cancel);
+                Self::execute_nested_loop_join_with_ids(left_results, right_results, cancel)
             }
             PhysicalOperator::ParallelJoin { left, right } => {
-                Self::execute_parallel_join_with_ids(left, right, database)
+                Self::execute_parallel_join_with_ids(left, right, database, cancel)
             }
             PhysicalOperator::StarJoin { join_var, patterns } => {
-                Self::execute_star_join_with_ids(database, join_var, patterns)
+                Self::execute_star_join_with_ids(database, join_var, patterns, cancel)
             }
             PhysicalOperator::InMemoryBuffer { content, origin: _ } => {
                 content.clone() // TODO: make sure we dont have to clone here
             }
             PhysicalOperator:: Subquery { inner, projected_vars } => {
                 // Execute the inner query with IDs
-                let inner_results = Self::execute_with_ids(inner, database);
+                let inner_results = Self::run(inner, database, cancel);
                 
                 // Project only the requested variables
                 inner_results
@@ -142,7 +226,7 @@ impl ExecutionEngine {
                     .collect()
             }
             PhysicalOperator::Bind { input, function_name, arguments, output_variable } => {
-                let mut input_results = Self::execute_with_ids(input, database);
+                let mut input_results = Self::run(input, database, cancel);
                 let output_var = output_variable.strip_prefix('?').unwrap_or(output_variable);
 
                 if function_name == "CONCAT" {
@@ -179,6 +263,57 @@ impl ExecutionEngine {
                     }
                     drop(dict_write);
                     
+                    input_results
+                } else if function_name.eq_ignore_ascii_case("COALESCE") {
+                    // The first bound variable keeps its ID; a literal is encoded
+                    let mut dict_write = database.dictionary.write().unwrap();
+                    for row in &mut input_results {
+                        let value = arguments.iter().find_map(|arg| match arg.strip_prefix('?') {
+                            Some(var) => row.get(var).copied(),
+                            None => Some(dict_write.encode(arg.trim_matches('"'))),
+                        });
+                        if let Some(id) = value {
+                            row.insert(output_var.to_string(), id);
+                        }
+                    }
+                    drop(dict_write);
+
+                    input_results
+                } else if function_name.eq_ignore_ascii_case("IF") || function_name.eq_ignore_ascii_case("IRI") {
+                    // `parse_bind` passes the argument text on whole. The IF
+                    // condition becomes a `Condition`, whose terms are leaked
+                    // to 'static as planned filters are
+                    let text: &'static str = Box::leak(arguments.join(",").into_boxed_str());
+                    let expression = select_expression(function_name, text);
+                    let condition = match &expression {
+                        Some(SelectExpression::If(condition, ..)) => Some(Condition::from_filter(condition.clone())),
+                        _ => None,
+                    };
+                    let mut dict_write = database.dictionary.write().unwrap();
+                    for row in &mut input_results {
+                        let value = {
+                            let resolve = |var: &str| {
+                                let &id = row.get(var.strip_prefix('?').unwrap_or(var))?;
+                                dict_write.decode(id).map(str::to_string)
+                            };
+                            match &expression {
+                                Some(SelectExpression::If(_, then, otherwise)) => {
+                                    let holds = condition
+                                        .as_ref()
+                                        .is_some_and(|c| c.evaluate_with_ids(row, &dict_write, &database.udfs));
+                                    evaluate_if(holds, then, otherwise, &resolve)
+                                }
+                                Some(SelectExpression::Iri(arg)) => evaluate_iri(arg, &resolve),
+                                _ => None,
+                            }
+                        };
+                        if let Some(value) = value {
+                            let id = dict_write.encode(&value);
+                            row.insert(output_var.to_string(), id);
+                        }
+                    }
+                    drop(dict_write);
+
                     input_results
                 } else if let Some(func) = database.udfs.get(function_name.as_str()) {
                     // Similar fix for UDF
@@ -272,10 +407,19 @@ impl ExecutionEngine {
                     drop(dict_write);
                     input_results
                 } else {
-                    eprintln!("Function {} not found", function_name);
+                    warn!("Function {} not found", function_name);
                     input_results
                 }
             }
+            PhysicalOperator::Profile { inner, profiler } => {
+                let rows_in_before = profiled_input_rows(inner);
+                let start = Instant::now();
+                let results = Self::run(inner, database, cancel);
+                let elapsed = start.elapsed();
+                let rows_in = profiled_input_rows(inner) - rows_in_before;
+                profiler.lock().unwrap().record(rows_in, results.len() as u64, elapsed);
+                results
+            }
             PhysicalOperator::Values { variables, values } => {
                 let stripped_vars: Vec<String> = variables
                     .iter()
@@ -314,17 +458,17 @@ impl ExecutionEngine {
                 output_variable,
             } => {
                 // Execute the input operator first
-                let input_results = Self::execute_with_ids(input, database);
+                let input_results = Self::run(input, database, cancel);
 
                 if input_results.is_empty() {
                     return input_results;
                 }
 
-                println!("[ML.PREDICT] Executing prediction with model: {}", model_name);
-                println!("[ML.PREDICT] Model path: {}", model_path);
-                println!("[ML.PREDICT] Input variables: {:?}", input_variables);
-                println!("[ML.PREDICT] Output variable: {}", output_variable);
-                println!("[ML.PREDICT] Input rows: {}", input_results.len());
+                debug!("[ML.PREDICT] Executing prediction with model: {}", model_name);
+                debug!("[ML.PREDICT] Model path: {}", model_path);
+                debug!("[ML.PREDICT] Input variables: {:?}", input_variables);
+                debug!("[ML.PREDICT] Output variable: {}", output_variable);
+                debug!("[ML.PREDICT] Input rows: {}", input_results.len());
 
                 // Try Candle first: when the model name maps to exactly one registered
                 // NEURAL RELATION, run the trained Candle MLP. Otherwise fall back to
@@ -335,7 +479,7 @@ impl ExecutionEngine {
                     &input_results,
                 ) {
                     Ok(Some(dispatch)) => {
-                        println!("[ML.PREDICT] Dispatched to Candle (model={})", model_name);
+                        debug!("[ML.PREDICT] Dispatched to Candle (model={})", model_name);
                         return Self::merge_candle_predictions(
                             input_results,
                             dispatch.predictions,
@@ -344,10 +488,10 @@ impl ExecutionEngine {
                         );
                     }
                     Ok(None) => {
-                        println!("[ML.PREDICT] No Candle registration for model '{}', falling back to Python", model_name);
+                        debug!("[ML.PREDICT] No Candle registration for model '{}', falling back to Python", model_name);
                     }
                     Err(e) => {
-                        eprintln!("[ML.PREDICT] Candle dispatch error: {}", e);
+                        error!("[ML.PREDICT] Candle dispatch error: {}", e);
                         return input_results;
                     }
                 }
@@ -361,7 +505,7 @@ impl ExecutionEngine {
                         Self::merge_ml_predictions(input_results, predictions, output_variable, database)
                     }
                     Err(e) => {
-                        eprintln!("[ML.PREDICT] Error executing ML model: {}", e);
+                        error!("[ML.PREDICT] Error executing ML model: {}", e);
                         input_results
                     }
                 }
@@ -376,14 +520,14 @@ impl ExecutionEngine {
         database: &SparqlDatabase,
     ) -> Vec<Vec<f64>> {
         if let Some(first_row) = input_results.first() {
-            println!("[ML.PREDICT DEBUG] First row keys: {:?}", first_row.keys().collect::<Vec<_>>());
-            println!("[ML.PREDICT DEBUG] Input variables to check: {:?}", input_variables);
+            debug!("[ML.PREDICT DEBUG] First row keys: {:?}", first_row.keys().collect::<Vec<_>>());
+            debug!("[ML.PREDICT DEBUG] Input variables to check: {:?}", input_variables);
             
             // Show what values decode to
             let dict = database.dictionary.read().unwrap();
             for (key, &id) in first_row {
                 if let Some(value) = dict.decode(id) {
-                    println!("[ML.PREDICT DEBUG]   {} -> {} (parses as f64: {})", 
+                    debug!("[ML.PREDICT DEBUG]   {} -> {} (parses as f64: {})", 
                         key, value, value.parse::<f64>().is_ok());
                 }
             }
@@ -399,7 +543,7 @@ impl ExecutionEngine {
                     let var_stripped = var.strip_prefix('?').unwrap_or(var);
                     if let Some(&id) = first_row.get(var_stripped) {
                         if let Some(value_str) = dict.decode(id) {
-                            value_str.parse::<f64>().is_ok()
+                            parse_numeric_literal(value_str).is_some()
                         } else {
                             false
                         }
@@ -415,7 +559,7 @@ impl ExecutionEngine {
             return Vec::new();
         };
         
-        println!("[ML.PREDICT] Numeric feature variables: {:?}", numeric_vars);
+        debug!("[ML.PREDICT] Numeric feature variables: {:?}", numeric_vars);
         
         // Now extract only numeric features
         let dict = database.dictionary.read().unwrap();
@@ -429,7 +573,7 @@ impl ExecutionEngine {
                         
                         if let Some(&id) = row.get(var_stripped) {
                             if let Some(value_str) = dict.decode(id) {
-                                value_str.parse::<f64>().ok()
+                                parse_numeric_literal(value_str)
                             } else {
                                 None
                             }
@@ -453,10 +597,10 @@ impl ExecutionEngine {
         use ml::MLHandler;
         use ml::generate_ml_models;
 
-        println!("[ML.PREDICT] Initializing ML handler...");
+        debug!("[ML.PREDICT] Initializing ML handler...");
         let mut ml_handler = MLHandler::new()?;
 
-        println!("[ML.PREDICT] Looking for models in: {}", model_dir);
+        debug!("[ML.PREDICT] Looking for models in: {}", model_dir);
 
         let model_dir_path = std::path::PathBuf::from(model_dir);
         std::fs::create_dir_all(&model_dir_path)?;
@@ -472,7 +616,7 @@ impl ExecutionEngine {
             .count() >= 1;
 
         if !models_exist {
-            println!("[ML.PREDICT] Models not found. Generating models...");
+            debug!("[ML.PREDICT] Models not found. Generating models...");
             // Derive script name from model_name: "fraud_predictor" -> "fraud_predictor.py"
             let script_name = format!("{}.py", model_name);
             let predictor_script = model_dir_path
@@ -486,7 +630,7 @@ impl ExecutionEngine {
             }
         }
         
-        println!("[ML.PREDICT] Discovering models and analyzing schemas...");
+        debug!("[ML.PREDICT] Discovering models and analyzing schemas...");
         let model_ids = ml_handler.discover_and_load_models(&model_dir_path, model_name)?;
         
         if model_ids.is_empty() {
@@ -494,16 +638,16 @@ impl ExecutionEngine {
         }
         
         let best_model_name = ml_handler.best_model.as_deref().unwrap_or(&model_ids[0]);
-        println!("[ML.PREDICT] Using best model: {}", best_model_name);
+        debug!("[ML.PREDICT] Using best model: {}", best_model_name);
         
-        println!("[ML.PREDICT] Running predictions on {} samples...", input_data.len());
+        debug!("[ML.PREDICT] Running predictions on {} samples...", input_data.len());
         let start = std::time::Instant::now();
         
         let result = ml_handler.predict(best_model_name, input_data)?;
         
         let elapsed = start.elapsed();
-        println!("[ML.PREDICT] Prediction completed in {:.3}s", elapsed.as_secs_f64());
-        println!("[ML.PREDICT] Throughput: {:.1} predictions/sec", 
+        debug!("[ML.PREDICT] Prediction completed in {:.3}s", elapsed.as_secs_f64());
+        debug!("[ML.PREDICT] Throughput: {:.1} predictions/sec", 
             result.predictions.len() as f64 / elapsed.as_secs_f64());
         
         Ok(result)
@@ -514,7 +658,7 @@ impl ExecutionEngine {
         mut input_results: Vec<HashMap<String, u32>>,
         predictions: Vec<String>,
         output_variable: &str,
-        database: &mut SparqlDatabase,
+        database: &SparqlDatabase,
     ) -> Vec<HashMap<String, u32>> {
         let output_var = output_variable.strip_prefix('?').unwrap_or(output_variable);
 
@@ -527,7 +671,7 @@ impl ExecutionEngine {
         }
         drop(dict);
 
-        println!("[ML.PREDICT] Candle: merged {} predictions", predictions.len());
+        debug!("[ML.PREDICT] Candle: merged {} predictions", predictions.len());
         input_results
     }
 
@@ -536,7 +680,7 @@ impl ExecutionEngine {
         mut input_results: Vec<HashMap<String, u32>>,
         predictions: MLPredictionResult,
         output_variable: &str,
-        database: &mut SparqlDatabase,
+        database: &SparqlDatabase,
     ) -> Vec<HashMap<String, u32>> {
         let output_var = output_variable.strip_prefix('?').unwrap_or(output_variable);
         
@@ -550,19 +694,155 @@ impl ExecutionEngine {
         }
         drop(dict);
         
-        println!("[ML.PREDICT] Successfully added {} predictions", predictions.predictions.len());
+        debug!("[ML.PREDICT] Successfully added {} predictions", predictions.predictions.len());
         input_results
     }
 
+    /// Runs `Filter(Scan)` for a `?v = "c"` condition as one index scan with
+    /// `?v` bound to the id of `c`; `None` when the filter has to be evaluated
+    /// row by row
+    fn execute_bound_scan_with_ids(
+        input: &PhysicalOperator,
+        condition: &Condition,
+        database: &SparqlDatabase,
+        cancel: &CancellationToken,
+    ) -> Option<Vec<HashMap<String, u32>>> {
+        let (pattern, index) = match input {
+            PhysicalOperator::TableScan { pattern } => (pattern, None),
+            PhysicalOperator::IndexScan { pattern, index } => (pattern, *index),
+            _ => return None,
+        };
+        if Self::has_quoted_triple_term(pattern) {
+            return None;
+        }
+        let (variable, constant) = scan_equality_binding(pattern, condition)?;
+        // A constant missing from the dictionary cannot match any triple
+        let Some(id) = database.dictionary.read().unwrap().string_to_id.get(constant).copied() else {
+            return Some(Vec::new());
+        };
+
+        let bind = |term: &Term| match term {
+            Term::Variable(v) if v.strip_prefix('?').unwrap_or(v) == variable => Term::Constant(id),
+            _ => term.clone(),
+        };
+        let bound = (bind(&pattern.0), bind(&pattern.1), bind(&pattern.2));
+        let mut rows = match index {
+            Some(index) => Self::execute_ordered_index_scan_with_ids(database, &bound, index),
+            None => Self::execute_index_scan_with_ids(database, &bound, cancel),
+        };
+        for row in &mut rows {
+            row.insert(variable.to_string(), id);
+        }
+        Some(rows)
+    }
+
+    /// A scan filtered by `CONTAINS`/`REGEX` on one of its variables, answered
+    /// from the text index: the filter is checked once per candidate term, and
+    /// the scan runs with the variable bound to each term that passes. `None`
+    /// unless the input is a plain scan and the index covers the dictionary.
+    fn execute_text_search_with_ids(
+        input: &PhysicalOperator,
+        condition: &Condition,
+        database: &SparqlDatabase,
+        cancel: &CancellationToken,
+    ) -> Option<Vec<HashMap<String, u32>>> {
+        let (pattern, index) = match input {
+            PhysicalOperator::TableScan { pattern } => (pattern, None),
+            PhysicalOperator::IndexScan { pattern, index } => (pattern, *index),
+            _ => return None,
+        };
+        if Self::has_quoted_triple_term(pattern) {
+            return None;
+        }
+        let (variable, text_pattern) = condition.text_pattern()?;
+        let is_variable = |term: &Term| matches!(term, Term::Variable(v) if v.strip_prefix('?').unwrap_or(v) == variable);
+        if ![&pattern.0, &pattern.1, &pattern.2].into_iter().any(is_variable) {
+            return None;
+        }
+
+        let matching: Vec<u32> = {
+            let dict = database.dictionary.read().unwrap();
+            let candidates = database.current_text_index(&dict)?.candidates(&text_pattern)?;
+            candidates
+                .into_iter()
+                .filter(|&id| {
+                    let row = HashMap::from([(variable.to_string(), id)]);
+                    condition.evaluate_with_ids(&row, &dict, &database.udfs)
+                })
+                .collect()
+        };
+
+        let mut rows = Vec::new();
+        for id in matching {
+            let bind = |term: &Term| if is_variable(term) { Term::Constant(id) } else { term.clone() };
+            let bound = (bind(&pattern.0), bind(&pattern.1), bind(&pattern.2));
+            let mut bound_rows = match index {
+                Some(index) => Self::execute_ordered_index_scan_with_ids(database, &bound, index),
+                None => Self::execute_index_scan_with_ids(database, &bound, cancel),
+            };
+            for row in &mut bound_rows {
+                row.insert(variable.to_string(), id);
+            }
+            rows.extend(bound_rows);
+        }
+        Some(rows)
+    }
+
     /// Executes a table scan with ID-based results
     fn execute_table_scan_with_ids(
         database: &SparqlDatabase,
         pattern: &TriplePattern,
+        cancel: &CancellationToken,
     ) -> Vec<HashMap<String, u32>> {
         let mut results = Vec::new();
 
-        // Iterate through all triples in the database
-        for triple in &database.triples {
+        let bound = |term: &Term| match term {
+            Term::Constant(id) => Some(*id),
+            _ => None,
+        };
+        let bound_ids = BoundIds {
+            subject: bound(&pattern.0),
+            predicate: bound(&pattern.1),
+            object: bound(&pattern.2),
+        };
+
+        // With partitioning enabled, shards are pre-filtered in parallel on the bound positions
+        let partition_matches;
+        let triples: Box<dyn Iterator<Item = &Triple> + '_> = match &database.partitioned_store {
+            Some(store) => {
+                partition_matches = store.scan(bound_ids.subject, bound_ids.predicate, bound_ids.object);
+                Box::new(partition_matches.iter())
+            }
+            None => Box::new(database.triples.iter()),
+        };
+
+
+        // Bound positions are compared a block of triples at a time with SIMD,
+        // and variables are only bound for the triples that match
+        let has_quoted_triple = [&pattern.0, &pattern.1, &pattern.2]
+            .into_iter()
+            .any(|term| matches!(term, Term::QuotedTriple(_)));
+        if !has_quoted_triple && !bound_ids.is_unbound() {
+            let mut block = Vec::with_capacity(TABLE_SCAN_BLOCK);
+            for (scanned, triple) in triples.enumerate() {
+                if scanned % CANCELLATION_CHECK_INTERVAL == 0 && cancel.is_cancelled() {
+                    return Vec::new();
+                }
+                block.push(triple.clone());
+                if block.len() == TABLE_SCAN_BLOCK {
+                    Self::push_block_matches(pattern, &block, &bound_ids, &mut results);
+                    block.clear();
+                }
+            }
+            Self::push_block_matches(pattern, &block, &bound_ids, &mut results);
+            return results;
+        }
+
+        // Iterate through all candidate triples in the database
+        for (scanned, triple) in triples.enumerate() {
+            if scanned % CANCELLATION_CHECK_INTERVAL == 0 && cancel.is_cancelled() {
+                return Vec::new();
+            }
             let mut bindings = HashMap::new();
             let mut matches = true;
 
@@ -631,17 +911,43 @@ impl ExecutionEngine {
         results
     }
 
+    /// Bind the pattern's variables for each triple of `block` that matches
+    /// `bound`
+    fn push_block_matches(
+        pattern: &TriplePattern,
+        block: &[Triple],
+        bound: &BoundIds,
+        results: &mut Vec<HashMap<String, u32>>,
+    ) {
+        for_each_match(block, bound, |i| {
+            let triple = &block[i];
+            let mut bindings = HashMap::new();
+            for (term, id) in [
+                (&pattern.0, triple.subject),
+                (&pattern.1, triple.predicate),
+                (&pattern.2, triple.object),
+            ] {
+                if let Term::Variable(var) = term {
+                    bindings.insert(var.strip_prefix('?').unwrap_or(var).to_string(), id);
+                }
+            }
+            results.push(bindings);
+        });
+    }
+
     /// Executes a star join: multiple patterns sharing the same subject
     fn execute_star_join_with_ids(
         database: &SparqlDatabase,
         join_var: &str,
         patterns: &[TriplePattern],
+        cancel: &CancellationToken,
     ) -> Vec<HashMap<String, u32>> {
         if patterns.is_empty() {
             return Vec::new();
         }
         
         let join_var_stripped = join_var.strip_prefix('?').unwrap_or(join_var);
+        let max_rows = database.max_join_results;
 
         // Find the most selective pattern
         let mut pattern_estimates: Vec<(usize, u64)> = patterns
@@ -659,7 +965,7 @@ impl ExecutionEngine {
         let (most_selective_idx, first_card) = pattern_estimates[0];
         let most_selective_pattern = &patterns[most_selective_idx];
 
-        let mut results = Self::execute_index_scan_with_ids(database, most_selective_pattern);
+        let mut results = Self::execute_index_scan_with_ids(database, most_selective_pattern, cancel);
 
         if results.is_empty() {
             return Vec::new();
@@ -676,13 +982,16 @@ impl ExecutionEngine {
                 // Process one-by-one with strict memory control
                 let mut new_results = Vec::new();
 
-                for binding in results.iter().take(100_000) {  // Hard limit on input size
+                for (processed, binding) in results.iter().enumerate() {
+                    if processed % CANCELLATION_CHECK_INTERVAL == 0 && cancel.is_cancelled() {
+                        return Vec::new();
+                    }
                     if let Some(&join_value) = binding.get(join_var_stripped) {
                         let mut bound_bindings = HashMap::new();
                         bound_bindings.insert(join_var_stripped.to_string(), join_value);
 
                         let bound_pattern = Self::bind_pattern(pattern, &bound_bindings);
-                        let matches = Self::execute_index_scan_with_ids(database, &bound_pattern);
+                        let matches = Self::execute_index_scan_with_ids(database, &bound_pattern, cancel);
 
                         for match_binding in matches {
                             let mut merged = binding.clone();
@@ -691,10 +1000,8 @@ impl ExecutionEngine {
                             }
                             new_results.push(merged);
 
-                            // Hard stop if we exceed 500K results
-                            if new_results.len() >= 500_000 {
-                                results = new_results;
-                                return results;  // Early exit
+                            if new_results.len() > max_rows {
+                                return abort(cancel, SparqlError::JoinResultLimitExceeded(max_rows));
                             }
                         }
                     }
@@ -706,12 +1013,15 @@ impl ExecutionEngine {
                 results = results
                 .into_par_iter()
                 .flat_map(|binding| {
+                    if cancel.is_cancelled() {
+                        return Vec::new();
+                    }
                     if let Some(&join_value) = binding.get(join_var_stripped) {
                         let mut bound_bindings = HashMap::new();
                         bound_bindings.insert(join_var_stripped.to_string(), join_value);
 
                         let bound_pattern = Self::bind_pattern(pattern, &bound_bindings);
-                        let matches = Self::execute_index_scan_with_ids(database, &bound_pattern);
+                        let matches = Self::execute_index_scan_with_ids(database, &bound_pattern, cancel);
 
                         matches
                         .into_iter()
@@ -730,9 +1040,12 @@ impl ExecutionEngine {
                 .collect();
             }
 
-            if results.is_empty() {
+            if results.is_empty() || cancel.is_cancelled() {
                 return Vec::new();
             }
+            if results.len() > max_rows {
+                return abort(cancel, SparqlError::JoinResultLimitExceeded(max_rows));
+            }
         }
 
         results
@@ -758,6 +1071,8 @@ impl ExecutionEngine {
     fn execute_optimized_hash_join_with_ids(
         left_results: Vec<HashMap<String, u32>>,
         right_results: Vec<HashMap<String, u32>>,
+        cancel: &CancellationToken,
+        max_rows: usize,
     ) -> Vec<HashMap<String, u32>> {
         if left_results.is_empty() || right_results.is_empty() {
             return Vec::new();
@@ -770,7 +1085,7 @@ impl ExecutionEngine {
 
         if common_vars.is_empty() {
             // Cartesian product if no common variables
-            return Self::cartesian_product_join(left_results, right_results);
+            return Self::cartesian_product_join(left_results, right_results, cancel, max_rows);
         }
 
         // Build hash table from smaller relation
@@ -792,6 +1107,9 @@ impl ExecutionEngine {
         probe_side
         .par_iter()
         .flat_map(|probe_tuple| {
+            if cancel.is_cancelled() {
+                return Vec::new();
+            }
             let key: Vec<u32> = common_vars.iter().map(|var| probe_tuple[var]).collect();
 
             if let Some(matching_tuples) = hash_table.get(&key) {
@@ -814,6 +1132,8 @@ impl ExecutionEngine {
     fn execute_hash_join_with_ids(
         left_results: Vec<HashMap<String, u32>>,
         right_results: Vec<HashMap<String, u32>>,
+        cancel: &CancellationToken,
+        max_rows: usize,
     ) -> Vec<HashMap<String, u32>> {
         if left_results.is_empty() || right_results.is_empty() {
             return Vec::new();
@@ -825,7 +1145,7 @@ impl ExecutionEngine {
         let common_vars: Vec<String> = left_vars.intersection(&right_vars).cloned().collect();
 
         if common_vars.is_empty() {
-            return Self::cartesian_product_join(left_results, right_results);
+            return Self::cartesian_product_join(left_results, right_results, cancel, max_rows);
         }
 
         // Simple hash join implementation
@@ -839,7 +1159,10 @@ impl ExecutionEngine {
         }
 
         // Probe with right results
-        for right_tuple in right_results {
+        for (probed, right_tuple) in right_results.into_iter().enumerate() {
+            if probed % CANCELLATION_CHECK_INTERVAL == 0 && cancel.is_cancelled() {
+                return Vec::new();
+            }
             let key: Vec<u32> = common_vars.iter().map(|var| right_tuple[var]).collect();
 
             if let Some(matching_left_tuples) = hash_table.get(&key) {
@@ -862,9 +1185,11 @@ impl ExecutionEngine {
     fn execute_nested_loop_join_with_ids(
         left_results: Vec<HashMap<String, u32>>,
         right_results: Vec<HashMap<String, u32>>,
+        cancel: &CancellationToken,
     ) -> Vec<HashMap<String, u32>> {
-        left_results
+        let results: Vec<HashMap<String, u32>> = left_results
         .into_iter()
+        .take_while(|_| !cancel.is_cancelled())
         .flat_map(|left_tuple| {
             right_results
             .iter()
@@ -881,7 +1206,12 @@ impl ExecutionEngine {
             })
             .collect::<Vec<_>>()
         })
-        .collect()
+        .collect();
+
+        if cancel.is_cancelled() {
+            return Vec::new();
+        }
+        results
     }
 
     /// Executes a bind join - uses left results to directly probe right index
@@ -889,10 +1219,25 @@ impl ExecutionEngine {
         left_results: Vec<HashMap<String, u32>>,
         right_pattern: &TriplePattern,
         database: &SparqlDatabase,
+        cancel: &CancellationToken,
     ) -> Vec<HashMap<String, u32>> {
-        // Safety limits
+        // Running output size, checked against the configured join limit
         let total_results = std::sync::atomic::AtomicUsize::new(0);
-        let max_total = 1_000_000;
+        let max_total = database.max_join_results;
+
+        // Without a shared variable every left row meets the same right rows
+        let shares_variable = left_results.first().is_some_and(|first| {
+            [&right_pattern.0, &right_pattern.1, &right_pattern.2]
+                .iter()
+                .any(|term| match term {
+                    Term::Variable(var) => first.contains_key(var.strip_prefix('?').unwrap_or(var)),
+                    _ => false,
+                })
+        });
+        if !left_results.is_empty() && !shares_variable {
+            let right_results = Self::execute_index_scan_with_ids(database, right_pattern, cancel);
+            return Self::cartesian_product_join(left_results, right_results, cancel, max_total);
+        }
 
         let chunk_size = (left_results.len() / rayon::current_num_threads()).max(1).max(100);
 
@@ -900,32 +1245,27 @@ impl ExecutionEngine {
         .par_chunks(chunk_size)
         .flat_map(|chunk| {
             chunk.iter().flat_map(|left_tuple| {
-                // Check global limit
-                if total_results.load(std::sync::atomic::Ordering::Relaxed) >= max_total {
+                if cancel.is_cancelled() {
                     return Vec::new();
                 }
 
                 let bound_pattern = Self::bind_pattern(right_pattern, left_tuple);
-                let matches = Self::execute_index_scan_with_ids(database, &bound_pattern);
+                let matches = Self::execute_index_scan_with_ids(database, &bound_pattern, cancel);
 
-                // Limit matches per binding
-                let match_limit = matches.len().min(10_000);
+                // Check global limit
+                let total = total_results.fetch_add(matches.len(), std::sync::atomic::Ordering::Relaxed) + matches.len();
+                if total > max_total {
+                    return abort(cancel, SparqlError::JoinResultLimitExceeded(max_total));
+                }
 
                 matches.into_iter()
-                .take(match_limit)  // Apply limit
                 .map(|right_tuple| {
                     let mut result = left_tuple.clone();
                     for (k, v) in right_tuple {
                         result.entry(k).or_insert(v);
                     }
-                    // Track total
-                    total_results.fetch_add(1, std::sync::atomic::Ordering::Relaxed);
                     result
                 })
-                .take_while(|_| {
-                    // Stop if limit reached
-                    total_results.load(std::sync::atomic::Ordering::Relaxed) < max_total
-                })
                 .collect::<Vec<_>>()
             }).collect::<Vec<_>>()
         })
@@ -974,26 +1314,37 @@ impl ExecutionEngine {
     fn execute_parallel_join_with_ids(
         left: &PhysicalOperator,
         right: &PhysicalOperator,
-        database: &mut SparqlDatabase,
+        database: &SparqlDatabase,
+        cancel: &CancellationToken,
     ) -> Vec<HashMap<String, u32>> {
         // Execute left side first
-        let left_results = Self::execute_with_ids(left, database);
+        let left_results = Self::run(left, database, cancel);
 
         // If right side is an index scan, use bind join
         if let Some(right_pattern) = Self::extract_pattern(right) {
-            return Self::execute_bind_join_with_ids(left_results, right_pattern, database);
+            return Self::execute_bind_join_with_ids(left_results, right_pattern, database, cancel);
         }
 
         // Execute right side
-        let right_results = Self::execute_with_ids(right, database);
+        let right_results = Self::run(right, database, cancel);
 
         // If both sides are sorted by join key, use merge join
         if Self::can_use_merge_join(&left_results, &right_results) {
-            return Self::execute_merge_join_with_ids(left_results, right_results);
+            return Self::execute_merge_join_with_ids(
+                left_results,
+                right_results,
+                cancel,
+                database.max_join_results,
+            );
         }
 
         // Hash join for unsorted data
-        Self::execute_hash_join_with_ids(left_results, right_results)
+        Self::execute_hash_join_with_ids(
+            left_results,
+            right_results,
+            cancel,
+            database.max_join_results,
+        )
     }
 
     /// Check if we can use merge join (both sides have same join variables)
@@ -1018,6 +1369,8 @@ impl ExecutionEngine {
     fn execute_merge_join_with_ids(
         mut left_results: Vec<HashMap<String, u32>>,
         mut right_results: Vec<HashMap<String, u32>>,
+        cancel: &CancellationToken,
+        max_rows: usize,
     ) -> Vec<HashMap<String, u32>> {
         if left_results.is_empty() || right_results.is_empty() {
             return Vec::new();
@@ -1029,7 +1382,7 @@ impl ExecutionEngine {
         let common_vars: Vec<String> = left_vars.intersection(&right_vars).cloned().collect();
 
         if common_vars.is_empty() {
-            return Self::cartesian_product_join(left_results, right_results);
+            return Self::cartesian_product_join(left_results, right_results, cancel, max_rows);
         }
 
         // Sort both sides by join key
@@ -1064,6 +1417,9 @@ impl ExecutionEngine {
         left_results
         .par_iter()
         .flat_map(|left_tuple| {
+            if cancel.is_cancelled() {
+                return Vec::new();
+            }
             let key: Vec<u32> = common_vars.iter().filter_map(|v| left_tuple.get(v).copied()).collect();
 
             if let Some(right_indices) = right_index.get(&key) {
@@ -1098,14 +1454,31 @@ impl ExecutionEngine {
         true
     }
 
-    /// Performs cartesian product join when no common variables exist
+    /// Performs cartesian product join when no common variables exist.
+    /// Refuses (rather than truncates) products larger than `max_rows`.
     fn cartesian_product_join(
         left_results: Vec<HashMap<String, u32>>,
         right_results: Vec<HashMap<String, u32>>,
+        cancel: &CancellationToken,
+        max_rows: usize,
     ) -> Vec<HashMap<String, u32>> {
+        if left_results.len().saturating_mul(right_results.len()) > max_rows {
+            return abort(
+                cancel,
+                SparqlError::CartesianProductTooLarge {
+                    left: left_results.len(),
+                    right: right_results.len(),
+                    limit: max_rows,
+                },
+            );
+        }
+
         left_results
         .into_par_iter()
         .flat_map(|left_tuple| {
+            if cancel.is_cancelled() {
+                return Vec::new();
+            }
             right_results
             .iter()
             .map(|right_tuple| {
@@ -1122,7 +1495,9 @@ impl ExecutionEngine {
     fn extract_pattern(operator: &PhysicalOperator) -> Option<&TriplePattern> {
         match operator {
             PhysicalOperator::TableScan { pattern } => Some(pattern),
-            PhysicalOperator::IndexScan { pattern } => Some(pattern),
+            PhysicalOperator::IndexScan { pattern, .. } => Some(pattern),
+            // The scan is probed by the join rather than run, so its profile stays empty
+            PhysicalOperator::Profile { inner, .. } => Self::extract_pattern(inner),
             _ => None,
         }
     }
@@ -1159,13 +1534,14 @@ impl ExecutionEngine {
     fn resolve_quoted_triple_scan(
         database: &SparqlDatabase,
         pattern: &TriplePattern,
+        cancel: &CancellationToken,
     ) -> Vec<HashMap<String, u32>> {
         // Find which positions contain QuotedTriple terms
         let has_qt_subject = matches!(&pattern.0, Term::QuotedTriple(_));
         let has_qt_object = matches!(&pattern.2, Term::QuotedTriple(_));
 
         if !has_qt_subject && !has_qt_object {
-            return Self::execute_index_scan_with_ids(database, pattern);
+            return Self::execute_index_scan_with_ids(database, pattern, cancel);
         }
 
         // Collect all quoted triple entries upfront to avoid holding the lock
@@ -1211,7 +1587,7 @@ impl ExecutionEngine {
             let concrete_pattern = (concrete_subject, pattern.1.clone(), concrete_object);
 
             // Execute the concrete pattern against the index
-            let outer_results = Self::execute_index_scan_with_ids(database, &concrete_pattern);
+            let outer_results = Self::execute_index_scan_with_ids(database, &concrete_pattern, cancel);
 
             // Merge inner and outer bindings
             for outer_row in outer_results {
@@ -1240,7 +1616,17 @@ impl ExecutionEngine {
     fn execute_index_scan_with_ids(
         database: &SparqlDatabase,
         pattern: &TriplePattern,
+        cancel: &CancellationToken,
     ) -> Vec<HashMap<String, u32>> {
+        if let Some(cache) = &database.pattern_cache {
+            if let Some(key) = Self::pattern_cache_key(pattern) {
+                let triples = cache.get_or_scan(key, database.count_triples(), || {
+                    database.index_manager.query(key.0, key.1, key.2)
+                });
+                return Self::bind_cached_triples(pattern, &triples);
+            }
+        }
+
         // Determine which index to use based on bound variables
         match pattern {
             // FULLY BOUND (3 constants) - just check if triple exists
@@ -1282,8 +1668,8 @@ impl ExecutionEngine {
 
             // FULLY UNBOUND (0 constants, 3 variables) - table scan is appropriate
             (Term::Variable(s), Term::Variable(p), Term::Variable(o)) => {
-                println!("INFO: Full table scan for fully unbound pattern (? {}, ?{}, ?{})", s, p, o);
-                Self::execute_table_scan_with_ids(database, pattern)
+                debug!("Full table scan for fully unbound pattern (? {}, ?{}, ?{})", s, p, o);
+                Self::execute_table_scan_with_ids(database, pattern, cancel)
             }
 
             // Patterns containing QuotedTriple terms should be pre-resolved
@@ -1292,6 +1678,42 @@ impl ExecutionEngine {
         }
     }
 
+    /// Cache key of a pattern with at least one constant and no quoted triples;
+    /// fully unbound patterns are table scans and are not cached
+    fn pattern_cache_key(pattern: &TriplePattern) -> Option<PatternKey> {
+        let bound = |term: &Term| match term {
+            Term::Constant(id) => Ok(Some(*id)),
+            Term::Variable(_) => Ok(None),
+            Term::QuotedTriple(_) => Err(()),
+        };
+        let key = (bound(&pattern.0).ok()?, bound(&pattern.1).ok()?, bound(&pattern.2).ok()?);
+        (key != (None, None, None)).then_some(key)
+    }
+
+    /// Bindings of the pattern's variables for each cached match, in the same
+    /// shape the index scans produce
+    fn bind_cached_triples(pattern: &TriplePattern, triples: &[Triple]) -> Vec<HashMap<String, u32>> {
+        let variable = |term: &Term| match term {
+            Term::Variable(name) => Some(name.strip_prefix('?').unwrap_or(name).to_string()),
+            _ => None,
+        };
+        let variables = [variable(&pattern.0), variable(&pattern.1), variable(&pattern.2)];
+
+        triples
+            .iter()
+            .map(|triple| {
+                let mut result = HashMap::with_capacity(2);
+                let ids = [triple.subject, triple.predicate, triple.object];
+                for (name, id) in variables.iter().zip(ids) {
+                    if let Some(name) = name {
+                        result.insert(name.clone(), id);
+                    }
+                }
+                result
+            })
+            .collect()
+    }
+
     /// Scans SP index (Subject-Predicate -> Object)
     fn scan_sp_index_with_ids(
         database: &SparqlDatabase,
@@ -1370,6 +1792,62 @@ impl ExecutionEngine {
         }
     }
 
+    /// Scans `pattern` through `index` whatever positions are bound, as a
+    /// `USE_INDEX` hint asks: bound keys are looked up and unbound ones
+    /// iterated, so a poorly matching index walks most of the store.
+    fn execute_ordered_index_scan_with_ids(
+        database: &SparqlDatabase,
+        pattern: &TriplePattern,
+        index: IndexOrder,
+    ) -> Vec<HashMap<String, u32>> {
+        let indexes = &database.index_manager;
+        let map = match index {
+            IndexOrder::Spo => &indexes.spo,
+            IndexOrder::Sop => &indexes.sop,
+            IndexOrder::Pso => &indexes.pso,
+            IndexOrder::Pos => &indexes.pos,
+            IndexOrder::Osp => &indexes.osp,
+            IndexOrder::Ops => &indexes.ops,
+        };
+        let terms = [&pattern.0, &pattern.1, &pattern.2];
+        let positions = index.positions();
+        let keys = positions.map(|position| match terms[position] {
+            Term::Constant(id) => Some(*id),
+            _ => None,
+        });
+        fn entries<V>(map: &HashMap<u32, V>, key: Option<u32>) -> Box<dyn Iterator<Item = (&u32, &V)> + '_> {
+            match key {
+                Some(key) => Box::new(map.get_key_value(&key).into_iter()),
+                None => Box::new(map.iter()),
+            }
+        }
+
+        let mut results = Vec::new();
+        let mut push_row = |ids: [u32; 3]| {
+            let mut row = HashMap::with_capacity(3);
+            for (position, id) in positions.into_iter().zip(ids) {
+                if let Term::Variable(var) = terms[position] {
+                    let var = var.strip_prefix('?').unwrap_or(var);
+                    // A variable repeated in the pattern must bind one value
+                    if *row.entry(var.to_string()).or_insert(id) != id {
+                        return;
+                    }
+                }
+            }
+            results.push(row);
+        };
+        for (&first, second_map) in entries(map, keys[0]) {
+            for (&second, thirds) in entries(second_map, keys[1]) {
+                match keys[2] {
+                    Some(third) if thirds.contains(&third) => push_row([first, second, third]),
+                    Some(_) => {}
+                    None => thirds.iter().for_each(|&third| push_row([first, second, third])),
+                }
+            }
+        }
+        results
+    }
+
     /// Scans S index (Subject -> (Predicate, Object))
     fn scan_s_index_with_ids(
         database: &SparqlDatabase,
@@ -8,6 +8,11 @@
  * you can obtain one at https://mozilla.org/MPL/2.0/.
  */
 
+pub mod cancellation;
+pub mod distinct;
 pub mod engine;
+pub mod profiler;
 
+pub use cancellation::CancellationToken;
 pub use engine::ExecutionEngine;
+pub use profiler::{OperatorProfile, QueryProfiler};
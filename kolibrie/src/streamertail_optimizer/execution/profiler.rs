@@ -0,0 +1,181 @@
+/*
+ * Copyright © 2026 Volodymyr Kadzhaia
+ * Copyright © 2026 Pieter Bonte
+ * KU Leuven — Stream Intelligence Lab, Belgium
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this file,
+ * you can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use super::super::cost::CostEstimator;
+use super::super::operators::display::pattern_label;
+use super::super::operators::PhysicalOperator;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Runtime counters for one operator of a profiled plan
+#[derive(Debug, Clone, Default)]
+pub struct OperatorProfile {
+    /// Operator kind with its pattern or variables, e.g. `IndexScan ?s #4 ?o`
+    pub operator: String,
+    /// Nesting level in the plan; the root is 0
+    pub depth: usize,
+    /// Output cardinality predicted by the cost estimator
+    pub estimated_rows: u64,
+    pub invocations: u64,
+    /// Rows produced by the operator's direct inputs
+    pub rows_in: u64,
+    pub rows_out: u64,
+    /// Wall-clock time including the time spent in the inputs
+    pub elapsed_ns: u64,
+}
+
+impl OperatorProfile {
+    pub fn record(&mut self, rows_in: u64, rows_out: u64, elapsed: Duration) {
+        self.invocations += 1;
+        self.rows_in += rows_in;
+        self.rows_out += rows_out;
+        self.elapsed_ns += elapsed.as_nanos() as u64;
+    }
+}
+
+/// Collects an `OperatorProfile` per operator of the plans it instruments.
+///
+/// Set on `Streamertail::profiler` (or `SparqlDatabase::profiler` for the
+/// query entry points), the optimizer wraps every operator of its best plan in
+/// `PhysicalOperator::Profile`, and the execution engine records rows and
+/// timings as the plan runs. Comparing `estimated_rows` with `rows_out` shows
+/// where the cost model is off.
+#[derive(Debug, Default)]
+pub struct QueryProfiler {
+    operators: Mutex<Vec<Arc<Mutex<OperatorProfile>>>>,
+    plans: Mutex<Vec<String>>,
+}
+
+impl QueryProfiler {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Wrap `plan` and each of its inputs in a `Profile` operator
+    pub fn instrument(&self, plan: PhysicalOperator, estimator: &CostEstimator) -> PhysicalOperator {
+        self.plans.lock().unwrap().push(plan.to_string());
+        self.instrument_at(plan, 0, estimator)
+    }
+
+    fn instrument_at(
+        &self,
+        plan: PhysicalOperator,
+        depth: usize,
+        estimator: &CostEstimator,
+    ) -> PhysicalOperator {
+        // Register before the inputs so profiles are listed in plan order
+        let profile = Arc::new(Mutex::new(OperatorProfile {
+            operator: operator_label(&plan),
+            depth,
+            estimated_rows: estimator.estimate_output_cardinality(&plan),
+            ..OperatorProfile::default()
+        }));
+        self.operators.lock().unwrap().push(Arc::clone(&profile));
+
+        let inner = plan.map_children(|child| self.instrument_at(child, depth + 1, estimator));
+        PhysicalOperator::Profile {
+            inner: Box::new(inner),
+            profiler: profile,
+        }
+    }
+
+    /// Snapshot of the counters, in plan order
+    pub fn profiles(&self) -> Vec<OperatorProfile> {
+        self.operators
+            .lock()
+            .unwrap()
+            .iter()
+            .map(|profile| profile.lock().unwrap().clone())
+            .collect()
+    }
+
+    /// The instrumented plans rendered as operator trees, in the order they
+    /// were optimized; a query's subqueries come before the query itself
+    pub fn plans(&self) -> Vec<String> {
+        self.plans.lock().unwrap().clone()
+    }
+
+    /// The counters as a text table, one line per operator indented by depth
+    pub fn summary(&self) -> String {
+        let profiles = self.profiles();
+        let labels: Vec<String> = profiles
+            .iter()
+            .map(|p| format!("{}{}", "  ".repeat(p.depth), p.operator))
+            .collect();
+        let width = labels.iter().map(|l| l.chars().count()).max().unwrap_or(0).max(8);
+
+        let mut table = format!(
+            "{:<width$}  {:>10}  {:>6}  {:>10}  {:>10}  {:>10}\n",
+            "operator", "est. rows", "calls", "rows in", "rows out", "time (ms)",
+        );
+        for (label, p) in labels.iter().zip(&profiles) {
+            table.push_str(&format!(
+                "{:<width$}  {:>10}  {:>6}  {:>10}  {:>10}  {:>10.3}\n",
+                label,
+                p.estimated_rows,
+                p.invocations,
+                p.rows_in,
+                p.rows_out,
+                p.elapsed_ns as f64 / 1_000_000.0,
+            ));
+        }
+        table
+    }
+
+    pub fn print_summary(&self) {
+        print!("{}", self.summary());
+    }
+}
+
+/// Rows produced so far by the profiled direct inputs of `operator`
+pub(crate) fn profiled_input_rows(operator: &PhysicalOperator) -> u64 {
+    operator
+        .children()
+        .into_iter()
+        .map(|child| match child {
+            PhysicalOperator::Profile { profiler, .. } => profiler.lock().unwrap().rows_out,
+            _ => 0,
+        })
+        .sum()
+}
+
+fn operator_label(operator: &PhysicalOperator) -> String {
+    match operator {
+        PhysicalOperator::TableScan { pattern } => format!("TableScan {}", pattern_label(pattern)),
+        PhysicalOperator::IndexScan { pattern, index: None } => format!("IndexScan {}", pattern_label(pattern)),
+        PhysicalOperator::IndexScan { pattern, index: Some(index) } => {
+            format!("IndexScan[{}] {}", index, pattern_label(pattern))
+        }
+        PhysicalOperator::Filter { .. } => "Filter".to_string(),
+        PhysicalOperator::HashJoin { .. } => "HashJoin".to_string(),
+        PhysicalOperator::NestedLoopJoin { .. } => "NestedLoopJoin".to_string(),
+        PhysicalOperator::ParallelJoin { .. } => "ParallelJoin".to_string(),
+        PhysicalOperator::OptimizedHashJoin { .. } => "OptimizedHashJoin".to_string(),
+        PhysicalOperator::StarJoin { join_var, patterns } => {
+            format!("StarJoin on {} ({} patterns)", join_var, patterns.len())
+        }
+        PhysicalOperator::Projection { variables, distinct: false, .. } => {
+            format!("Projection {}", variables.join(" "))
+        }
+        PhysicalOperator::Projection { variables, distinct: true, .. } => {
+            format!("Projection DISTINCT {}", variables.join(" "))
+        }
+        PhysicalOperator::InMemoryBuffer { origin, .. } => format!("InMemoryBuffer {}", origin),
+        PhysicalOperator::Subquery { projected_vars, .. } => {
+            format!("Subquery {}", projected_vars.join(" "))
+        }
+        PhysicalOperator::Bind { function_name, output_variable, .. } => {
+            format!("Bind {} AS {}", function_name, output_variable)
+        }
+        PhysicalOperator::Values { variables, .. } => format!("Values {}", variables.join(" ")),
+        PhysicalOperator::MLPredict { model_name, .. } => format!("MLPredict {}", model_name),
+        PhysicalOperator::Profile { inner, .. } => operator_label(inner),
+    }
+}
@@ -18,12 +18,13 @@ pub mod utils;
 
 // Re-export main components for convenience
 pub use cost::{CostConstants, CostEstimator};
-pub use execution::ExecutionEngine;
+pub use execution::{CancellationToken, ExecutionEngine, OperatorProfile, QueryProfiler};
 pub use operators::{LogicalOperator, PhysicalOperator};
 pub use optimizer::Streamertail;
 pub use stats::DatabaseStats;
-pub use types::{Condition, IdResult};
+pub use types::{Condition, IdResult, IndexOrder, JoinAlgorithm, QueryHints};
 pub use utils::{
-    build_logical_plan, estimate_operator_selectivity,
+    build_logical_plan, build_logical_plan_with_subplans, estimate_operator_selectivity,
     extract_pattern, pattern_contains_variable, build_logical_plan_from_subquery,
+    convert_pattern_to_triple,
 };
@@ -0,0 +1,271 @@
+/*
+ * Copyright © 2026 Volodymyr Kadzhaia
+ * Copyright © 2026 Pieter Bonte
+ * KU Leuven — Stream Intelligence Lab, Belgium
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this file,
+ * you can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+//! Indented tree rendering of logical and physical plans, one operator per
+//! line with its inputs nested two spaces deeper:
+//!
+//! ```text
+//! Projection(?s, ?name)
+//!   HashJoin
+//!     IndexScan(?s, #3, ?class)
+//!     IndexScan(?s, #7, ?name)
+//! ```
+//!
+//! Constants are shown by dictionary ID; plans carry no dictionary.
+
+use super::{LogicalOperator, PhysicalOperator};
+use shared::query::{ArithmeticExpression, FilterExpression, StringExpression, StringFunction};
+use shared::terms::{Term, TriplePattern};
+use std::fmt;
+
+impl fmt::Display for LogicalOperator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut lines = Vec::new();
+        logical_lines(self, 0, &mut lines);
+        f.write_str(&lines.join("\n"))
+    }
+}
+
+impl fmt::Display for PhysicalOperator {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut lines = Vec::new();
+        physical_lines(self, 0, &mut lines);
+        f.write_str(&lines.join("\n"))
+    }
+}
+
+fn logical_lines(operator: &LogicalOperator, depth: usize, lines: &mut Vec<String>) {
+    let indent = "  ".repeat(depth);
+    let (label, inputs): (String, Vec<&LogicalOperator>) = match operator {
+        LogicalOperator::Scan { pattern } => (format!("Scan{}", pattern_tuple(pattern)), Vec::new()),
+        LogicalOperator::Selection { predicate, condition } => (
+            format!("Selection({})", filter_label(&condition.expression)),
+            vec![predicate],
+        ),
+        LogicalOperator::Projection { predicate, variables, distinct } => {
+            (projection_label(variables, *distinct), vec![predicate])
+        }
+        LogicalOperator::Join { left, right } => ("Join".to_string(), vec![left, right]),
+        LogicalOperator::Buffer { content, origin } => {
+            (format!("Buffer({}, {} rows)", origin, content.len()), Vec::new())
+        }
+        LogicalOperator::Subquery { inner, projected_vars } => {
+            (format!("Subquery({})", projected_vars.join(", ")), vec![inner])
+        }
+        LogicalOperator::Bind { input, function_name, arguments, output_variable } => (
+            format!("Bind({}({}) AS {})", function_name, arguments.join(", "), output_variable),
+            vec![input],
+        ),
+        LogicalOperator::Values { variables, values } => {
+            (format!("Values({}; {} rows)", variables.join(", "), values.len()), Vec::new())
+        }
+        LogicalOperator::MLPredict { input, model_name, input_variables, output_variable } => (
+            format!("MLPredict({}: {} -> {})", model_name, input_variables.join(", "), output_variable),
+            vec![input],
+        ),
+    };
+    lines.push(format!("{}{}", indent, label));
+    for input in inputs {
+        logical_lines(input, depth + 1, lines);
+    }
+}
+
+fn physical_lines(operator: &PhysicalOperator, depth: usize, lines: &mut Vec<String>) {
+    let indent = "  ".repeat(depth);
+    let label = match operator {
+        // Profiling wrappers are not part of the plan
+        PhysicalOperator::Profile { inner, .. } => return physical_lines(inner, depth, lines),
+        PhysicalOperator::TableScan { pattern } => format!("TableScan{}", pattern_tuple(pattern)),
+        PhysicalOperator::IndexScan { pattern, index: None } => format!("IndexScan{}", pattern_tuple(pattern)),
+        PhysicalOperator::IndexScan { pattern, index: Some(index) } => {
+            format!("IndexScan[{}]{}", index, pattern_tuple(pattern))
+        }
+        PhysicalOperator::Filter { condition, .. } => {
+            format!("Filter({})", filter_label(&condition.expression))
+        }
+        PhysicalOperator::HashJoin { .. } => "HashJoin".to_string(),
+        PhysicalOperator::NestedLoopJoin { .. } => "NestedLoopJoin".to_string(),
+        PhysicalOperator::ParallelJoin { .. } => "ParallelJoin".to_string(),
+        PhysicalOperator::OptimizedHashJoin { .. } => "OptimizedHashJoin".to_string(),
+        PhysicalOperator::StarJoin { join_var, patterns } => {
+            // The star's patterns are scanned by the join itself
+            lines.push(format!("{}StarJoin({})", indent, join_var));
+            for pattern in patterns {
+                lines.push(format!("{}  {}", indent, pattern_tuple(pattern)));
+            }
+            return;
+        }
+        PhysicalOperator::Projection { variables, distinct, .. } => projection_label(variables, *distinct),
+        PhysicalOperator::InMemoryBuffer { content, origin } => {
+            format!("InMemoryBuffer({}, {} rows)", origin, content.len())
+        }
+        PhysicalOperator::Subquery { projected_vars, .. } => {
+            format!("Subquery({})", projected_vars.join(", "))
+        }
+        PhysicalOperator::Bind { function_name, arguments, output_variable, .. } => {
+            format!("Bind({}({}) AS {})", function_name, arguments.join(", "), output_variable)
+        }
+        PhysicalOperator::Values { variables, values } => {
+            format!("Values({}; {} rows)", variables.join(", "), values.len())
+        }
+        PhysicalOperator::MLPredict { model_name, input_variables, output_variable, .. } => {
+            format!("MLPredict({}: {} -> {})", model_name, input_variables.join(", "), output_variable)
+        }
+    };
+    lines.push(format!("{}{}", indent, label));
+    for input in operator.children() {
+        physical_lines(input, depth + 1, lines);
+    }
+}
+
+/// `Projection(?s, ?o)`, or `Projection(DISTINCT ?s, ?o)` for `SELECT DISTINCT`
+fn projection_label(variables: &[String], distinct: bool) -> String {
+    let modifier = if distinct { "DISTINCT " } else { "" };
+    format!("Projection({}{})", modifier, variables.join(", "))
+}
+
+fn pattern_tuple(pattern: &TriplePattern) -> String {
+    format!("({}, {}, {})", term_label(&pattern.0), term_label(&pattern.1), term_label(&pattern.2))
+}
+
+/// The pattern as space-separated terms, e.g. `?s #4 ?o`
+pub(crate) fn pattern_label(pattern: &TriplePattern) -> String {
+    format!("{} {} {}", term_label(&pattern.0), term_label(&pattern.1), term_label(&pattern.2))
+}
+
+fn term_label(term: &Term) -> String {
+    match term {
+        Term::Variable(name) => format!("?{}", name.trim_start_matches('?')),
+        Term::Constant(id) => format!("#{}", id),
+        Term::QuotedTriple(inner) => format!("<< {} >>", pattern_label(inner)),
+    }
+}
+
+/// Compact text form of a FILTER expression, also used in plan memo keys
+pub(crate) fn filter_label(expr: &FilterExpression) -> String {
+    match expr {
+        FilterExpression::Comparison(var, op, value) => {
+            format!("{}{}'{}'", var, op, value)
+        }
+        FilterExpression::And(left, right) => {
+            format!("({} AND {})", filter_label(left), filter_label(right))
+        }
+        FilterExpression::Or(left, right) => {
+            format!("({} OR {})", filter_label(left), filter_label(right))
+        }
+        FilterExpression::Not(inner) => {
+            format!("NOT({})", filter_label(inner))
+        }
+        FilterExpression::ArithmeticExpr(expr) => {
+            format!("ARITH({})", arith_label(expr))
+        }
+        FilterExpression::FunctionCall(name, args) => {
+            format!("{}({})", name, args.join(", "))
+        }
+        FilterExpression::FunctionComparison(name, args, op, value) => {
+            format!("{}({}){}'{}'", name, args.join(", "), op, value)
+        }
+        FilterExpression::StringFunction(func, args) => string_call_label(*func, args),
+        FilterExpression::StringFunctionComparison(func, args, op, value) => {
+            format!("{}{}'{}'", string_call_label(*func, args), op, value)
+        }
+        FilterExpression::NumericFunction(func, args, op, value) => {
+            let args: Vec<String> = args.iter().map(arith_label).collect();
+            format!("{}({}){}'{}'", func.name(), args.join(", "), op, value)
+        }
+        FilterExpression::Coalesce(args) => format!("COALESCE({})", string_args_label(args)),
+        FilterExpression::If(condition, then, otherwise, comparison) => {
+            let branches = string_args_label(&[then.clone(), otherwise.clone()]);
+            comparison_label(format!("IF({}, {})", filter_label(condition), branches), *comparison)
+        }
+        FilterExpression::Iri(arg, comparison) => {
+            comparison_label(format!("IRI({})", string_args_label(std::slice::from_ref(arg))), *comparison)
+        }
+    }
+}
+
+// `call`, followed by what it is compared with, if anything
+fn comparison_label(call: String, comparison: Option<(&str, &str)>) -> String {
+    match comparison {
+        Some((op, value)) => format!("{}{}'{}'", call, op, value),
+        None => call,
+    }
+}
+
+fn arith_label(expr: &ArithmeticExpression) -> String {
+    use ArithmeticExpression as AE;
+    match expr {
+        AE::Operand(s) => s.to_string(),
+        AE::Add(l, r) => format!("({} + {})", arith_label(l), arith_label(r)),
+        AE::Subtract(l, r) => format!("({} - {})", arith_label(l), arith_label(r)),
+        AE::Multiply(l, r) => format!("({} * {})", arith_label(l), arith_label(r)),
+        AE::Divide(l, r) => format!("({} / {})", arith_label(l), arith_label(r)),
+    }
+}
+
+fn string_call_label(func: StringFunction, args: &[StringExpression]) -> String {
+    format!("{}({})", func.name(), string_args_label(args))
+}
+
+fn string_args_label(args: &[StringExpression]) -> String {
+    let args: Vec<String> = args
+        .iter()
+        .map(|arg| match arg {
+            StringExpression::Variable(var) => var.to_string(),
+            StringExpression::Literal(literal) => format!("\"{}\"", literal),
+            StringExpression::Call(func, args) => string_call_label(*func, args),
+        })
+        .collect();
+    args.join(", ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::super::{LogicalOperator, PhysicalOperator};
+    use shared::terms::Term;
+
+    fn var(name: &str) -> Term {
+        Term::Variable(name.to_string())
+    }
+
+    #[test]
+    fn renders_plans_as_indented_trees() {
+        let type_scan = (var("s"), Term::Constant(3), var("class"));
+        let label_scan = (var("s"), Term::Constant(7), var("name"));
+
+        let logical = LogicalOperator::projection(
+            LogicalOperator::join(
+                LogicalOperator::scan(type_scan.clone()),
+                LogicalOperator::scan(label_scan.clone()),
+            ),
+            vec!["?s".to_string(), "?name".to_string()],
+        );
+        assert_eq!(
+            logical.to_string(),
+            "Projection(?s, ?name)\n  Join\n    Scan(?s, #3, ?class)\n    Scan(?s, #7, ?name)"
+        );
+
+        let physical = PhysicalOperator::projection(
+            PhysicalOperator::hash_join(
+                PhysicalOperator::index_scan(type_scan.clone()),
+                PhysicalOperator::StarJoin {
+                    join_var: "?s".to_string(),
+                    patterns: vec![type_scan, label_scan],
+                },
+            ),
+            vec!["?s".to_string()],
+        );
+        assert_eq!(
+            physical.to_string(),
+            "Projection(?s)\n  HashJoin\n    IndexScan(?s, #3, ?class)\n    StarJoin(?s)\n      \
+             (?s, #3, ?class)\n      (?s, #7, ?name)"
+        );
+    }
+}
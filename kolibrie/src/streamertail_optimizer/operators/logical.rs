@@ -24,6 +24,8 @@ pub enum LogicalOperator {
     Projection {
         predicate: Box<LogicalOperator>,
         variables: Vec<String>,
+        /// `SELECT DISTINCT`: drop duplicate rows of the projected variables
+        distinct: bool,
     },
     Join {
         left: Box<LogicalOperator>,
@@ -74,6 +76,16 @@ impl LogicalOperator {
         Self::Projection {
             predicate: Box::new(predicate),
             variables,
+            distinct: false,
+        }
+    }
+
+    /// Creates a projection that also removes duplicate solutions (`SELECT DISTINCT`)
+    pub fn distinct_projection(predicate: LogicalOperator, variables: Vec<String>) -> Self {
+        Self::Projection {
+            predicate: Box::new(predicate),
+            variables,
+            distinct: true,
         }
     }
 
@@ -8,6 +8,7 @@
  * you can obtain one at https://mozilla.org/MPL/2.0/.
  */
 
+pub mod display;
 pub mod logical;
 pub mod physical;
 
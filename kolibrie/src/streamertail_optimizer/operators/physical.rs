@@ -8,8 +8,10 @@
  * you can obtain one at https://mozilla.org/MPL/2.0/.
  */
 
-use super::super::Condition;
+use super::super::execution::OperatorProfile;
+use super::super::{Condition, IndexOrder};
 use shared::terms::{Bindings, TriplePattern};
+use std::sync::{Arc, Mutex};
 
 /// Physical operators represent the actual execution plan after optimization
 #[derive(Debug, Clone)]
@@ -19,6 +21,9 @@ pub enum PhysicalOperator {
     },
     IndexScan {
         pattern: TriplePattern,
+        /// Index read whatever positions are bound, set by a `USE_INDEX`
+        /// hint; otherwise the index matching the bound positions
+        index: Option<IndexOrder>,
     },
     Filter {
         input: Box<PhysicalOperator>,
@@ -47,6 +52,7 @@ pub enum PhysicalOperator {
     Projection {
         input: Box<PhysicalOperator>,
         variables: Vec<String>,
+        distinct: bool,
     },
     InMemoryBuffer{
         content: Bindings,
@@ -73,6 +79,12 @@ pub enum PhysicalOperator {
         input_variables: Vec<String>,
         output_variable: String,
     },
+    /// Records rows and timings of `inner`; injected by the optimizer when a
+    /// `QueryProfiler` is set
+    Profile {
+        inner: Box<PhysicalOperator>,
+        profiler: Arc<Mutex<OperatorProfile>>,
+    },
 }
 
 impl PhysicalOperator {
@@ -83,7 +95,12 @@ impl PhysicalOperator {
 
     /// Creates a new index scan physical operator
     pub fn index_scan(pattern: TriplePattern) -> Self {
-        Self::IndexScan { pattern }
+        Self::IndexScan { pattern, index: None }
+    }
+
+    /// Creates an index scan that reads `index`
+    pub fn ordered_index_scan(pattern: TriplePattern, index: IndexOrder) -> Self {
+        Self::IndexScan { pattern, index: Some(index) }
     }
 
     /// Creates a new filter physical operator
@@ -131,6 +148,16 @@ impl PhysicalOperator {
         Self::Projection {
             input: Box::new(input),
             variables,
+            distinct: false,
+        }
+    }
+
+    /// Creates a projection physical operator that removes duplicate rows
+    pub fn distinct_projection(input: PhysicalOperator, variables: Vec<String>) -> Self {
+        Self::Projection {
+            input: Box::new(input),
+            variables,
+            distinct: true,
         }
     }
 
@@ -183,19 +210,85 @@ impl PhysicalOperator {
         }
     }
 
+    /// The operators whose rows feed this one
+    pub fn children(&self) -> Vec<&PhysicalOperator> {
+        match self {
+            Self::Filter { input, .. }
+            | Self::Projection { input, .. }
+            | Self::Bind { input, .. }
+            | Self::MLPredict { input, .. } => vec![input],
+            Self::HashJoin { left, right }
+            | Self::NestedLoopJoin { left, right }
+            | Self::ParallelJoin { left, right }
+            | Self::OptimizedHashJoin { left, right } => vec![left, right],
+            Self::Subquery { inner, .. } | Self::Profile { inner, .. } => vec![inner],
+            Self::TableScan { .. }
+            | Self::IndexScan { .. }
+            | Self::StarJoin { .. }
+            | Self::InMemoryBuffer { .. }
+            | Self::Values { .. } => Vec::new(),
+        }
+    }
+
+    /// Rebuilds the operator with `f` applied to each of its children
+    pub fn map_children(self, mut f: impl FnMut(PhysicalOperator) -> PhysicalOperator) -> Self {
+        let mut map = |op: Box<PhysicalOperator>| Box::new(f(*op));
+        match self {
+            Self::Filter { input, condition } => Self::Filter { input: map(input), condition },
+            Self::Projection { input, variables, distinct } => {
+                Self::Projection { input: map(input), variables, distinct }
+            }
+            Self::Bind { input, function_name, arguments, output_variable } => Self::Bind {
+                input: map(input),
+                function_name,
+                arguments,
+                output_variable,
+            },
+            Self::MLPredict { input, model_name, model_path, input_variables, output_variable } => {
+                Self::MLPredict {
+                    input: map(input),
+                    model_name,
+                    model_path,
+                    input_variables,
+                    output_variable,
+                }
+            }
+            Self::HashJoin { left, right } => Self::HashJoin { left: map(left), right: map(right) },
+            Self::NestedLoopJoin { left, right } => {
+                Self::NestedLoopJoin { left: map(left), right: map(right) }
+            }
+            Self::ParallelJoin { left, right } => Self::ParallelJoin { left: map(left), right: map(right) },
+            Self::OptimizedHashJoin { left, right } => {
+                Self::OptimizedHashJoin { left: map(left), right: map(right) }
+            }
+            Self::Subquery { inner, projected_vars } => Self::Subquery { inner: map(inner), projected_vars },
+            Self::Profile { inner, profiler } => Self::Profile { inner: map(inner), profiler },
+            leaf => leaf,
+        }
+    }
+
     /// Executes the physical operator and returns string-based results
     pub fn execute(
         &self,
-        database: &mut crate::sparql_database::SparqlDatabase,
-    ) -> Vec<std::collections::HashMap<String, String>> {
+        database: &crate::sparql_database::SparqlDatabase,
+    ) -> Result<Vec<std::collections::HashMap<String, String>>, crate::custom_error::SparqlError> {
         super::super::execution::ExecutionEngine::execute(self, database)
     }
 
+    /// Like `execute`, checking `token` instead of `database.cancellation`
+    pub fn execute_cancellable(
+        &self,
+        database: &crate::sparql_database::SparqlDatabase,
+        token: &super::super::execution::CancellationToken,
+    ) -> Result<Vec<std::collections::HashMap<String, String>>, crate::custom_error::SparqlError> {
+        super::super::execution::ExecutionEngine::execute_cancellable(self, database, token)
+    }
+
     /// Executes the physical operator and returns ID-based results for performance
     pub fn execute_with_ids(
         &self,
-        database: &mut crate::sparql_database::SparqlDatabase,
-    ) -> Vec<std::collections::HashMap<String, u32>> {
+        database: &crate::sparql_database::SparqlDatabase,
+    ) -> Result<Vec<std::collections::HashMap<String, u32>>, crate::custom_error::SparqlError> {
         super::super::execution::ExecutionEngine::execute_with_ids(self, database)
     }
 }
@@ -9,32 +9,44 @@
  */
 
 use super::cost::CostEstimator;
-use super::execution::ExecutionEngine;
+use super::execution::{ExecutionEngine, QueryProfiler};
+use super::operators::display::filter_label;
 use super::operators::{LogicalOperator, PhysicalOperator};
 use super::stats::DatabaseStats;
+use super::types::{Condition, QueryHints};
+use super::utils::scan_equality_binding;
 
+use crate::custom_error::SparqlError;
 use crate::sparql_database::SparqlDatabase;
+use log::{debug, warn};
 use shared::terms::{Term, TriplePattern};
-use shared::query::FilterExpression;
 use std::collections::{BTreeMap, HashMap, HashSet};
 use std::sync::Arc;
 
+/// Default for `Streamertail::dp_join_limit`
+pub const DEFAULT_DP_JOIN_LIMIT: usize = 8;
+
+/// Exhaustive join ordering keeps a plan per subset of inputs, so it is never
+/// used past this many, whatever `dp_join_limit` says
+const MAX_DP_JOIN_RELATIONS: usize = 16;
+
+/// Rough size of one row in a join's hash table, for `MAX_MEMORY_MB`
+const HASH_TABLE_BYTES_PER_ROW: u64 = 128;
+
 /// Volcano-style query optimizer with cost-based optimization
 pub struct Streamertail {
     pub memo: HashMap<String, PhysicalOperator>,
     pub selected_variables: Vec<String>,
     pub stats: Arc<DatabaseStats>,
-}
-
-fn serialize_arith_expr(expr: &shared::query::ArithmeticExpression) -> String {
-    use shared::query::ArithmeticExpression as AE;
-    match expr {
-        AE::Operand(s) => s.to_string(),
-        AE::Add(l, r) => format!("({} + {})", serialize_arith_expr(l), serialize_arith_expr(r)),
-        AE::Subtract(l, r) => format!("({} - {})", serialize_arith_expr(l), serialize_arith_expr(r)),
-        AE::Multiply(l, r) => format!("({} * {})", serialize_arith_expr(l), serialize_arith_expr(r)),
-        AE::Divide(l, r) => format!("({} / {})", serialize_arith_expr(l), serialize_arith_expr(r)),
-    }
+    /// When set, chosen plans are instrumented to record per-operator rows and timings
+    pub profiler: Option<Arc<QueryProfiler>>,
+    /// Joins of up to this many inputs are ordered by trying every left-deep
+    /// order; larger ones are ordered greedily by selectivity
+    pub dp_join_limit: usize,
+    /// Overrides from the query's `# HINT` lines. A join algorithm or index
+    /// hint also stops stars being planned as `StarJoin`; the join order is
+    /// still chosen by cost.
+    pub hints: QueryHints,
 }
 
 impl Streamertail {
@@ -45,6 +57,9 @@ impl Streamertail {
             memo: HashMap::new(),
             selected_variables: Vec::new(),
             stats,
+            profiler: None,
+            dp_join_limit: DEFAULT_DP_JOIN_LIMIT,
+            hints: QueryHints::default(),
         }
     }
 
@@ -53,20 +68,30 @@ impl Streamertail {
             memo: HashMap::new(),
             selected_variables: Vec::new(),
             stats,
+            profiler: None,
+            dp_join_limit: DEFAULT_DP_JOIN_LIMIT,
+            hints: QueryHints::default(),
         }
     }
 
     /// Finds the best physical plan for a logical plan
     pub fn find_best_plan(&mut self, logical_plan: &LogicalOperator) -> PhysicalOperator {
-        self.find_best_plan_recursive(logical_plan)
+        debug!("Logical plan:\n{}", logical_plan);
+        let plan = self.find_best_plan_recursive(logical_plan);
+        debug!("Physical plan:\n{}", plan);
+        // Instrument after memoization so cached plans stay unprofiled
+        match &self.profiler {
+            Some(profiler) => profiler.instrument(plan, &CostEstimator::new(&self.stats)),
+            None => plan,
+        }
     }
 
     /// Executes a physical plan and returns results
     pub fn execute_plan(
         &self,
         plan: &PhysicalOperator,
-        database: &mut SparqlDatabase,
-    ) -> Vec<HashMap<String, String>> {
+        database: &SparqlDatabase,
+    ) -> Result<Vec<HashMap<String, String>>, SparqlError> {
         ExecutionEngine::execute(plan, database)
     }
 
@@ -74,14 +99,17 @@ impl Streamertail {
     pub fn optimize_and_execute(
         &mut self,
         logical_plan: &LogicalOperator,
-        database: &mut SparqlDatabase,
-    ) -> Vec<HashMap<String, String>> {
+        database: &SparqlDatabase,
+    ) -> Result<Vec<HashMap<String, String>>, SparqlError> {
         let physical_plan = self.find_best_plan(logical_plan);
         self.execute_plan(&physical_plan, database)
     }
 
     /// Detects if a join tree is a star query pattern
     fn is_star_query(&self, plan: &LogicalOperator) -> Option<Vec<(String, Vec<TriplePattern>)>> {
+        if self.hints.join_algorithm.is_some() || self.hints.use_index.is_some() {
+            return None;
+        }
         let mut patterns = Vec::new();
         self.collect_patterns(plan, &mut patterns);
 
@@ -190,13 +218,26 @@ impl Streamertail {
             return plan.clone();
         }
 
-        if let LogicalOperator::Projection { predicate: proj_pred, variables } = logical_plan {
+        // Rewrite: filter the side of a join that binds the selection's variables
+        if let LogicalOperator::Selection { predicate, condition } = logical_plan {
+            if let Some(pushed) = self.push_down_selection(predicate, condition) {
+                let plan = self.find_best_plan_recursive(&pushed);
+                self.memo.insert(key, plan.clone());
+                return plan;
+            }
+        }
+
+        if let LogicalOperator::Projection { predicate: proj_pred, variables, distinct } = logical_plan {
             if let LogicalOperator::Selection { predicate: sel_pred, condition } = proj_pred.as_ref() {
                 if let Some(stars) = self.is_star_query(sel_pred) {
                     // Build: Projection(Filter(StarJoin))
                     let star_plan = self.build_star_join_from_patterns(stars, sel_pred);
                     let filtered_plan = PhysicalOperator::filter(star_plan, condition.clone());
-                    let projected_plan = PhysicalOperator::projection(filtered_plan, variables.clone());
+                    let projected_plan = PhysicalOperator::Projection {
+                        input: Box::new(filtered_plan),
+                        variables: variables.clone(),
+                        distinct: *distinct,
+                    };
                     self.memo.insert(key, projected_plan.clone());
                     return projected_plan;
                 }
@@ -234,63 +275,55 @@ impl Streamertail {
                 predicate,
                 condition,
             } => {
-                // Transformations: Push down selections
-                let best_child_plan = self.find_best_plan_recursive(predicate);
+                let best_child_plan = match predicate.as_ref() {
+                    // The engine answers `?v = "c"` by binding ?v in an index scan
+                    LogicalOperator::Scan { pattern } if scan_equality_binding(pattern, condition).is_some() => {
+                        self.index_scan(pattern)
+                    }
+                    _ => self.find_best_plan_recursive(predicate),
+                };
                 // Implementation rules: Apply selection as a filter
                 candidates.push(PhysicalOperator::filter(best_child_plan, condition.clone()));
             }
             LogicalOperator::Projection {
                 predicate,
                 variables,
+                distinct,
             } => {
                 let best_child_plan = self.find_best_plan_recursive(predicate);
-                candidates.push(PhysicalOperator::projection(
-                    best_child_plan,
-                    variables.clone(),
-                ));
+                candidates.push(PhysicalOperator::Projection {
+                    input: Box::new(best_child_plan),
+                    variables: variables.clone(),
+                    distinct: *distinct,
+                });
             }
             LogicalOperator::Join { left, right } => {
-                // Add join reordering based on cost
-                let left_cost = self.estimate_logical_cost(left);
-                let right_cost = self.estimate_logical_cost(right);
-
-                let (cheaper_side, expensive_side) = if left_cost <= right_cost {
-                    (left, right)
+                let mut relations = Vec::new();
+                self.collect_join_relations(logical_plan, &mut relations);
+                if relations.len() > 2 {
+                    let leaves = relations
+                        .into_iter()
+                        .map(|relation| {
+                            let variables = self.output_variables(relation).into_iter().map(str::to_string).collect();
+                            (self.find_best_plan_recursive(relation), variables)
+                        })
+                        .collect();
+                    candidates.push(self.order_joins(leaves));
                 } else {
-                    (right, left) // Swap for better order
-                };
-
-                let best_left_plan = self.find_best_plan_recursive(cheaper_side);
-                let best_right_plan = self.find_best_plan_recursive(expensive_side);
-
-                // Implementation rules: Different join algorithms
-                candidates.push(PhysicalOperator::optimized_hash_join(
-                    best_left_plan.clone(),
-                    best_right_plan.clone(),
-                ));
-
-                candidates.push(PhysicalOperator::hash_join(
-                    best_left_plan.clone(),
-                    best_right_plan.clone(),
-                ));
-
-                // Only use nested loop for small datasets
-                let left_cardinality = self.estimate_output_cardinality_from_logical(cheaper_side);
-                let right_cardinality =
-                    self.estimate_output_cardinality_from_logical(expensive_side);
-
-                if left_cardinality < 1000 && right_cardinality < 1000 {
-                    candidates.push(PhysicalOperator::nested_loop_join(
-                        best_left_plan.clone(),
-                        best_right_plan.clone(),
-                    ));
+                    // Add join reordering based on cost
+                    let left_cost = self.estimate_logical_cost(left);
+                    let right_cost = self.estimate_logical_cost(right);
+
+                    let (cheaper_side, expensive_side) = if left_cost <= right_cost {
+                        (left, right)
+                    } else {
+                        (right, left) // Swap for better order
+                    };
+
+                    let best_left_plan = self.find_best_plan_recursive(cheaper_side);
+                    let best_right_plan = self.find_best_plan_recursive(expensive_side);
+                    candidates.extend(self.join_candidates(best_left_plan, best_right_plan));
                 }
-
-                // Add parallel join option
-                candidates.push(PhysicalOperator::parallel_join(
-                    best_left_plan,
-                    best_right_plan,
-                ));
             }
             LogicalOperator::Buffer { content, origin} => {
                 let best_buffer = PhysicalOperator::InMemoryBuffer {content: content.clone(), origin: origin.clone()};
@@ -369,6 +402,103 @@ impl Streamertail {
         best_plan
     }
 
+    /// `input` filtered by `condition`, with the selection moved below a join
+    /// into the side that binds all of its variables; `None` if no join takes it
+    fn push_down_selection(&self, input: &LogicalOperator, condition: &Condition) -> Option<LogicalOperator> {
+        match input {
+            LogicalOperator::Join { left, right } => {
+                let variables: HashSet<&str> = condition
+                    .expression
+                    .variables()
+                    .into_iter()
+                    .map(|var| var.strip_prefix('?').unwrap_or(var))
+                    .collect();
+                if variables.is_empty() {
+                    return None;
+                }
+                let select = |side: &LogicalOperator| {
+                    self.push_down_selection(side, condition)
+                        .unwrap_or_else(|| LogicalOperator::selection(side.clone(), condition.clone()))
+                };
+                if variables.is_subset(&self.output_variables(left)) {
+                    Some(LogicalOperator::join(select(left), right.as_ref().clone()))
+                } else if variables.is_subset(&self.output_variables(right)) {
+                    Some(LogicalOperator::join(left.as_ref().clone(), select(right)))
+                } else {
+                    None
+                }
+            }
+            // Selections commute, so look past the ones already applied
+            LogicalOperator::Selection { predicate, condition: applied } => self
+                .push_down_selection(predicate, condition)
+                .map(|pushed| LogicalOperator::selection(pushed, applied.clone())),
+            _ => None,
+        }
+    }
+
+    /// Variables (without `?`) bound by every row of `plan`
+    fn output_variables<'a>(&self, plan: &'a LogicalOperator) -> HashSet<&'a str> {
+        let strip = |var: &'a String| var.strip_prefix('?').unwrap_or(var);
+        match plan {
+            LogicalOperator::Scan { pattern } => [&pattern.0, &pattern.1, &pattern.2]
+                .into_iter()
+                .filter_map(|term| match term {
+                    Term::Variable(var) => Some(strip(var)),
+                    _ => None,
+                })
+                .collect(),
+            LogicalOperator::Join { left, right } => {
+                let mut variables = self.output_variables(left);
+                variables.extend(self.output_variables(right));
+                variables
+            }
+            LogicalOperator::Selection { predicate, .. } => self.output_variables(predicate),
+            LogicalOperator::Projection { variables, .. } => variables.iter().map(strip).collect(),
+            LogicalOperator::Subquery { projected_vars, .. } => projected_vars.iter().map(strip).collect(),
+            LogicalOperator::Bind { input, output_variable, .. }
+            | LogicalOperator::MLPredict { input, output_variable, .. } => {
+                let mut variables = self.output_variables(input);
+                variables.insert(strip(output_variable));
+                variables
+            }
+            // A variable left UNDEF in some row is not bound by every row
+            LogicalOperator::Values { variables, values } => variables
+                .iter()
+                .enumerate()
+                .filter(|(i, _)| values.iter().all(|row| row.get(*i).is_some_and(Option::is_some)))
+                .map(|(_, var)| strip(var))
+                .collect(),
+            LogicalOperator::Buffer { .. } => HashSet::new(),
+        }
+    }
+
+    /// Inputs of the join tree rooted at `plan`, in query order
+    fn collect_join_relations<'a>(&self, plan: &'a LogicalOperator, relations: &mut Vec<&'a LogicalOperator>) {
+        match plan {
+            LogicalOperator::Join { left, right } => {
+                self.collect_join_relations(left, relations);
+                self.collect_join_relations(right, relations);
+            }
+            _ => relations.push(plan),
+        }
+    }
+
+    /// Conditions of the selections inside the join tree of `plan`, which a
+    /// star join built from its patterns has to apply again
+    fn collect_conditions(&self, plan: &LogicalOperator, conditions: &mut Vec<Condition>) {
+        match plan {
+            LogicalOperator::Join { left, right } => {
+                self.collect_conditions(left, conditions);
+                self.collect_conditions(right, conditions);
+            }
+            LogicalOperator::Selection { predicate, condition } => {
+                self.collect_conditions(predicate, conditions);
+                conditions.push(condition.clone());
+            }
+            _ => {}
+        }
+    }
+
     /// Discovers the model path from the model name
     fn discover_model_path(&self) -> String {
         let mut path = std::path::PathBuf::from(env!("CARGO_MANIFEST_DIR"));
@@ -387,7 +517,7 @@ impl Streamertail {
             }
             
             if !path.pop() {
-                eprintln!("Warning: Could not locate 'ml' directory!");
+                warn!("Could not locate 'ml' directory!");
                 break;
             }
         }
@@ -396,90 +526,211 @@ impl Streamertail {
         format!("ml/examples/models")
     }
 
-    /// Helper method to build a star join physical plan from detected star patterns
+    /// Helper method to build a star join physical plan from detected star patterns,
+    /// filtered by the selections found between the joins of `logical_plan`
     fn build_star_join_from_patterns(
         &mut self,
         stars: Vec<(String, Vec<TriplePattern>)>,
         logical_plan: &LogicalOperator,
+    ) -> PhysicalOperator {
+        let mut conditions = Vec::new();
+        self.collect_conditions(logical_plan, &mut conditions);
+        conditions
+            .into_iter()
+            .fold(self.build_star_join_tree(stars, logical_plan), PhysicalOperator::filter)
+    }
+
+    fn build_star_join_tree(
+        &mut self,
+        stars: Vec<(String, Vec<TriplePattern>)>,
+        logical_plan: &LogicalOperator,
     ) -> PhysicalOperator {
         let mut all_patterns = Vec::new();
         self.collect_patterns(logical_plan, &mut all_patterns);
 
+        // The star with the most bound patterns runs as one operator
+        let mut star_operators = stars;
+        star_operators.sort_by_key(|(_, patterns)| {
+            let bound_count = patterns.iter().filter(|p| {
+                matches!(p.0, Term::Constant(_)) ||
+                matches!(p.1, Term::Constant(_)) ||
+                matches!(p.2, Term::Constant(_))
+            }).count();
+            std::cmp::Reverse(bound_count)
+        });
+        if star_operators.is_empty() {
+            // Shouldn't happen, but return a dummy scan as fallback
+            return PhysicalOperator::table_scan((
+                Term::Variable("?s".to_string()),
+                Term::Variable("?p".to_string()),
+                Term::Variable("?o".to_string()),
+            ));
+        }
+        let (join_var, patterns) = star_operators.remove(0);
+
         let mut used_pattern_indices: HashSet<usize> = HashSet::new();
-        for (_, star_patterns) in &stars {
-            for star_pattern in star_patterns {
-                if let Some(idx) = all_patterns.iter().position(|p| p == star_pattern) {
-                    used_pattern_indices.insert(idx);
-                }
+        for star_pattern in &patterns {
+            if let Some(idx) = all_patterns.iter().position(|p| p == star_pattern) {
+                used_pattern_indices.insert(idx);
             }
         }
 
-        if stars.len() > 1 {
-            let mut star_operators: Vec<(String, Vec<TriplePattern>)> = stars;
-
-            star_operators.sort_by_key(|(_, patterns)| {
-                let bound_count = patterns.iter().filter(|p| {
-                    matches!(p.0, Term::Constant(_)) ||
-                    matches!(p.1, Term::Constant(_)) ||
-                    matches!(p.2, Term::Constant(_))
-                }).count();
-                std::cmp::Reverse(bound_count)
-            });
-
-            let (first_var, first_patterns) = star_operators.remove(0);
-            let mut result = PhysicalOperator::StarJoin {
-                join_var: first_var.clone(),
-                patterns: first_patterns,
-            };
+        // The other stars' patterns and those in no star are joined to it in cost order
+        let mut leaves = vec![(
+            PhysicalOperator::StarJoin { join_var, patterns: patterns.clone() },
+            Self::pattern_variables(&patterns),
+        )];
+        for (idx, pattern) in all_patterns.iter().enumerate() {
+            if !used_pattern_indices.contains(&idx) {
+                let variables = Self::pattern_variables(std::slice::from_ref(pattern));
+                leaves.push((PhysicalOperator::index_scan(pattern.clone()), variables));
+            }
+        }
 
-            for (_, patterns) in star_operators {
-                let star_scans: Vec<PhysicalOperator> = patterns
-                    .into_iter()
-                    .map(|pattern| PhysicalOperator::index_scan(pattern))
-                    .collect();
+        if leaves.len() == 1 {
+            leaves.pop().unwrap().0
+        } else {
+            self.order_joins(leaves)
+        }
+    }
 
-                for scan in star_scans {
-                    result = PhysicalOperator::parallel_join(result, scan);
+    /// Physical joins of `left` with `right` worth costing
+    fn join_candidates(&self, left: PhysicalOperator, right: PhysicalOperator) -> Vec<PhysicalOperator> {
+        if let Some(algorithm) = self.hints.join_algorithm {
+            return vec![algorithm.join(left, right)];
+        }
+        let cost_estimator = CostEstimator::new(&self.stats);
+        let left_cardinality = cost_estimator.estimate_output_cardinality(&left);
+        let right_cardinality = cost_estimator.estimate_output_cardinality(&right);
+
+        // Hash joins hash the smaller input; past the memory hint, only a
+        // nested loop (or a parallel bind join, which builds no table) is left
+        if let Some(megabytes) = self.hints.max_memory_mb {
+            let table_bytes = left_cardinality.min(right_cardinality).saturating_mul(HASH_TABLE_BYTES_PER_ROW);
+            if table_bytes > megabytes.saturating_mul(1024 * 1024) {
+                let mut candidates = vec![PhysicalOperator::nested_loop_join(left.clone(), right.clone())];
+                if matches!(right, PhysicalOperator::IndexScan { .. }) {
+                    candidates.push(PhysicalOperator::parallel_join(left, right));
                 }
+                return candidates;
             }
+        }
 
-            for (idx, pattern) in all_patterns.iter().enumerate() {
-                if !used_pattern_indices.contains(&idx) {
-                    let scan = PhysicalOperator::index_scan(pattern.clone());
-                    result = PhysicalOperator::parallel_join(result, scan);
-                }
-            }
+        // Implementation rules: Different join algorithms
+        let mut candidates = vec![
+            PhysicalOperator::optimized_hash_join(left.clone(), right.clone()),
+            PhysicalOperator::hash_join(left.clone(), right.clone()),
+        ];
+        // Only use nested loop for small datasets
+        if left_cardinality < 1000 && right_cardinality < 1000 {
+            candidates.push(PhysicalOperator::nested_loop_join(left.clone(), right.clone()));
+        }
+        // Add parallel join option
+        candidates.push(PhysicalOperator::parallel_join(left, right));
+        candidates
+    }
 
-            result
-        } else if stars.len() == 1 {
-            let (join_var, patterns) = stars.into_iter().next().unwrap();
+    /// The cheapest physical join of `left` with `right`, with its cost
+    fn cheapest_join(&self, left: PhysicalOperator, right: PhysicalOperator) -> (u64, PhysicalOperator) {
+        let cost_estimator = CostEstimator::new(&self.stats);
+        self.join_candidates(left, right)
+            .into_iter()
+            .map(|plan| (cost_estimator.estimate_cost(&plan), plan))
+            .min_by_key(|(cost, _)| *cost)
+            .unwrap()
+    }
 
-            if used_pattern_indices.len() < all_patterns.len() {
-                let mut result = PhysicalOperator::StarJoin { join_var, patterns };
+    /// Join `leaves` (plans with the variables they bind) as a left-deep tree.
+    /// Up to `dp_join_limit` leaves, every order is considered by dynamic
+    /// programming over subsets; beyond that, the most selective leaf starts and
+    /// the cheapest connected leaf is added next. A leaf sharing no variable
+    /// with the others is only joined as a cross product when nothing else is left.
+    fn order_joins(&self, leaves: Vec<(PhysicalOperator, HashSet<String>)>) -> PhysicalOperator {
+        if leaves.len() <= self.dp_join_limit.min(MAX_DP_JOIN_RELATIONS) {
+            self.order_joins_exhaustive(leaves)
+        } else {
+            self.order_joins_greedy(leaves)
+        }
+    }
 
-                for (idx, pattern) in all_patterns.iter().enumerate() {
-                    if !used_pattern_indices.contains(&idx) {
-                        let scan = PhysicalOperator::index_scan(pattern.clone());
-                        result = PhysicalOperator::parallel_join(result, scan);
-                    }
+    fn order_joins_exhaustive(&self, leaves: Vec<(PhysicalOperator, HashSet<String>)>) -> PhysicalOperator {
+        let cost_estimator = CostEstimator::new(&self.stats);
+        let n = leaves.len();
+        let shares_variable = |subset: usize, leaf: usize| {
+            (0..n).any(|i| subset & (1 << i) != 0 && !leaves[i].1.is_disjoint(&leaves[leaf].1))
+        };
+
+        // best[subset]: the cheapest left-deep join of the leaves in the bit set
+        let mut best: Vec<Option<(u64, PhysicalOperator)>> = vec![None; 1 << n];
+        for (i, (plan, _)) in leaves.iter().enumerate() {
+            best[1 << i] = Some((cost_estimator.estimate_cost(plan), plan.clone()));
+        }
+        for subset in 1usize..1 << n {
+            if subset.count_ones() < 2 {
+                continue;
+            }
+            let members = (0..n).filter(|&i| subset & (1 << i) != 0);
+            let connected: Vec<usize> = members.clone().filter(|&i| shares_variable(subset & !(1 << i), i)).collect();
+            let last = if connected.is_empty() { members.collect() } else { connected };
+            for i in last {
+                let Some((_, prefix)) = &best[subset & !(1 << i)] else {
+                    continue;
+                };
+                let (cost, plan) = self.cheapest_join(prefix.clone(), leaves[i].0.clone());
+                if best[subset].as_ref().is_none_or(|(best_cost, _)| cost < *best_cost) {
+                    best[subset] = Some((cost, plan));
                 }
-
-                result
-            } else {
-                PhysicalOperator::StarJoin { join_var, patterns }
             }
-        } else {
-            // Shouldn't happen, but return a dummy scan as fallback
-            PhysicalOperator::table_scan((
-                Term::Variable("?s".to_string()),
-                Term::Variable("?p".to_string()),
-                Term::Variable("?o".to_string()),
-            ))
+        }
+        best.pop().flatten().unwrap().1
+    }
+
+    fn order_joins_greedy(&self, mut leaves: Vec<(PhysicalOperator, HashSet<String>)>) -> PhysicalOperator {
+        let cost_estimator = CostEstimator::new(&self.stats);
+        let first = (0..leaves.len())
+            .min_by_key(|&i| cost_estimator.estimate_output_cardinality(&leaves[i].0))
+            .unwrap();
+        let (mut plan, mut variables) = leaves.remove(first);
+
+        while !leaves.is_empty() {
+            let connected: Vec<usize> = (0..leaves.len()).filter(|&i| !leaves[i].1.is_disjoint(&variables)).collect();
+            let next = if connected.is_empty() { (0..leaves.len()).collect() } else { connected };
+            let (i, (_, joined)) = next
+                .into_iter()
+                .map(|i| (i, self.cheapest_join(plan.clone(), leaves[i].0.clone())))
+                .min_by_key(|(_, (cost, _))| *cost)
+                .unwrap();
+            variables.extend(leaves.remove(i).1);
+            plan = joined;
+        }
+        plan
+    }
+
+    /// Variables (without `?`) of `patterns`
+    fn pattern_variables(patterns: &[TriplePattern]) -> HashSet<String> {
+        patterns
+            .iter()
+            .flat_map(|pattern| [&pattern.0, &pattern.1, &pattern.2])
+            .filter_map(|term| match term {
+                Term::Variable(var) => Some(var.strip_prefix('?').unwrap_or(var).to_string()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// An index scan of `pattern`, through the hinted index if there is one
+    fn index_scan(&self, pattern: &TriplePattern) -> PhysicalOperator {
+        match self.hints.use_index {
+            Some(index) => PhysicalOperator::ordered_index_scan(pattern.clone(), index),
+            None => PhysicalOperator::index_scan(pattern.clone()),
         }
     }
 
     /// Chooses the best scan method based on pattern selectivity
     fn choose_best_scan(&self, pattern: &TriplePattern) -> PhysicalOperator {
+        if self.hints.use_index.is_some() {
+            return self.index_scan(pattern);
+        }
         let bound_vars = self.count_bound_variables(pattern);
         let cost_estimator = CostEstimator::new(&self.stats);
         let estimated_size = cost_estimator.estimate_cardinality(pattern);
@@ -540,16 +791,18 @@ impl Streamertail {
                 format!(
                     "Selection([{}], {})",
                     self.serialize_logical_plan(predicate),
-                    self.serialize_filter_expression(&condition.expression)
+                    filter_label(&condition.expression)
                 )
             }
             LogicalOperator::Projection {
                 predicate,
                 variables,
+                distinct,
             } => {
                 format!(
-                    "Projection({:?},[{}])",
+                    "Projection({:?},{},[{}])",
                     variables,
+                    distinct,
                     self.serialize_logical_plan(predicate)
                 )
             }
@@ -607,38 +860,6 @@ impl Streamertail {
         }
     }
 
-    /// Serializes a filter expression to a string
-    fn serialize_filter_expression(&self, expr: &FilterExpression) -> String {
-        match expr {
-            FilterExpression::Comparison(var, op, value) => {
-                format!("{}{}'{}'", var, op, value)
-            }
-            FilterExpression::And(left, right) => {
-                format!(
-                    "({} AND {})",
-                    self.serialize_filter_expression(left),
-                    self.serialize_filter_expression(right)
-                )
-            }
-            FilterExpression::Or(left, right) => {
-                format!(
-                    "({} OR {})",
-                    self.serialize_filter_expression(left),
-                    self.serialize_filter_expression(right)
-                )
-            }
-            FilterExpression::Not(inner) => {
-                format!("NOT({})", self.serialize_filter_expression(inner))
-            }
-            FilterExpression::ArithmeticExpr(expr) => {
-                format!("ARITH({})", serialize_arith_expr(expr))
-            }
-            FilterExpression::FunctionCall(name, args) => {
-                format!("{}({})", name, args.join(", "))
-            }
-        }
-    }
-
     /// Estimates the cost of a logical plan
     fn estimate_logical_cost(&self, logical_plan: &LogicalOperator) -> u64 {
         let cost_estimator = CostEstimator::new(&self.stats);
@@ -789,6 +1010,7 @@ impl Streamertail {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use super::super::types::{IndexOrder, JoinAlgorithm};
     use shared::terms::Term;
 
     fn create_test_optimizer() -> Streamertail {
@@ -825,4 +1047,157 @@ mod tests {
         let pattern = (Term::Constant(1), Term::Constant(2), Term::Constant(3));
         assert_eq!(optimizer.count_bound_variables(&pattern), 3);
     }
+
+    #[test]
+    fn test_selection_is_pushed_into_the_join_side_binding_its_variables() {
+        let mut optimizer = create_test_optimizer();
+        let knows = LogicalOperator::scan((
+            Term::Variable("?s".to_string()),
+            Term::Variable("?p".to_string()),
+            Term::Variable("?o".to_string()),
+        ));
+        let named = LogicalOperator::scan((
+            Term::Variable("?o".to_string()),
+            Term::Constant(1),
+            Term::Variable("?name".to_string()),
+        ));
+        let plan = LogicalOperator::selection(
+            LogicalOperator::join(knows, named),
+            Condition::new("?p".to_string(), "=".to_string(), "http://example.org/knows".to_string()),
+        );
+
+        let physical = optimizer.find_best_plan(&plan);
+        let (left, right) = match &physical {
+            PhysicalOperator::OptimizedHashJoin { left, right }
+            | PhysicalOperator::HashJoin { left, right }
+            | PhysicalOperator::NestedLoopJoin { left, right }
+            | PhysicalOperator::ParallelJoin { left, right } => (left, right),
+            other => panic!("expected the filter below a join, got {:?}", other),
+        };
+        // The ?p side is filtered, and scanned by index so the engine can bind ?p
+        let bound_scan = |side: &PhysicalOperator| {
+            matches!(side, PhysicalOperator::Filter { input, .. }
+                if matches!(input.as_ref(), PhysicalOperator::IndexScan { pattern, .. } if pattern.1 == Term::Variable("?p".to_string())))
+        };
+        assert!(bound_scan(left) || bound_scan(right));
+    }
+
+    #[test]
+    fn test_selective_star_pattern_is_joined_first() {
+        let mut stats = DatabaseStats::new();
+        stats.total_triples = 30_005;
+        stats.predicate_cardinalities = HashMap::from([(1, 10_000), (2, 10_000), (3, 5), (4, 10_000)]);
+        let mut optimizer = Streamertail::with_cached_stats(Arc::new(stats));
+        let leaves = || {
+            (1..=4u32)
+                .map(|predicate| {
+                    let object = format!("?o{}", predicate);
+                    let variables = HashSet::from(["s".to_string(), object[1..].to_string()]);
+                    let pattern = (Term::Variable("?s".to_string()), Term::Constant(predicate), Term::Variable(object));
+                    (PhysicalOperator::index_scan(pattern), variables)
+                })
+                .collect::<Vec<_>>()
+        };
+        let first_joined = |plan: &PhysicalOperator| {
+            let mut plan = plan;
+            while let PhysicalOperator::OptimizedHashJoin { left, .. }
+            | PhysicalOperator::HashJoin { left, .. }
+            | PhysicalOperator::NestedLoopJoin { left, .. }
+            | PhysicalOperator::ParallelJoin { left, .. } = plan
+            {
+                plan = left;
+            }
+            match plan {
+                PhysicalOperator::IndexScan { pattern, .. } => pattern.1.clone(),
+                other => panic!("expected an index scan leaf, got {:?}", other),
+            }
+        };
+
+        // Every order is tried
+        assert_eq!(first_joined(&optimizer.order_joins(leaves())), Term::Constant(3));
+
+        // Greedy ordering past the limit starts from the same pattern
+        optimizer.dp_join_limit = 2;
+        assert_eq!(first_joined(&optimizer.order_joins(leaves())), Term::Constant(3));
+    }
+
+    #[test]
+    fn test_forced_join_algorithm_is_used_for_every_join() {
+        let mut optimizer = create_test_optimizer();
+        optimizer.hints.join_algorithm = Some(JoinAlgorithm::NestedLoop);
+        let scan = |predicate: u32, object: &str| {
+            LogicalOperator::scan((
+                Term::Variable("?s".to_string()),
+                Term::Constant(predicate),
+                Term::Variable(object.to_string()),
+            ))
+        };
+        let plan = LogicalOperator::join(
+            LogicalOperator::join(scan(1, "?a"), scan(2, "?b")),
+            scan(3, "?c"),
+        );
+
+        // Three patterns sharing ?s would otherwise be planned as a StarJoin
+        let physical = optimizer.find_best_plan(&plan);
+        match &physical {
+            PhysicalOperator::NestedLoopJoin { left, .. } => {
+                assert!(matches!(left.as_ref(), PhysicalOperator::NestedLoopJoin { .. }));
+            }
+            other => panic!("expected nested loop joins, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_use_index_hint_scans_every_pattern_through_that_index() {
+        let mut optimizer = create_test_optimizer();
+        optimizer.hints.use_index = Some(IndexOrder::Osp);
+        let scan = |predicate: u32, object: &str| {
+            LogicalOperator::scan((
+                Term::Variable("?s".to_string()),
+                Term::Constant(predicate),
+                Term::Variable(object.to_string()),
+            ))
+        };
+        let plan = LogicalOperator::join(
+            LogicalOperator::join(scan(1, "?a"), scan(2, "?b")),
+            scan(3, "?c"),
+        );
+
+        fn scans(plan: &PhysicalOperator, found: &mut Vec<Option<IndexOrder>>) {
+            match plan {
+                PhysicalOperator::IndexScan { index, .. } => found.push(*index),
+                PhysicalOperator::TableScan { .. } | PhysicalOperator::StarJoin { .. } => found.push(None),
+                other => other.children().into_iter().for_each(|child| scans(child, found)),
+            }
+        }
+        let mut found = Vec::new();
+        scans(&optimizer.find_best_plan(&plan), &mut found);
+        assert_eq!(found, vec![Some(IndexOrder::Osp); 3]);
+    }
+
+    #[test]
+    fn test_memory_hint_replaces_large_hash_joins() {
+        let mut stats = DatabaseStats::new();
+        stats.total_triples = 2_000_000;
+        stats.predicate_cardinalities = HashMap::from([(1, 1_000_000), (2, 1_000_000)]);
+        let mut optimizer = Streamertail::with_cached_stats(Arc::new(stats));
+        let scan = |predicate: u32, object: &str| {
+            PhysicalOperator::table_scan((
+                Term::Variable("?s".to_string()),
+                Term::Constant(predicate),
+                Term::Variable(object.to_string()),
+            ))
+        };
+        let builds_hash_table = |candidates: &[PhysicalOperator]| {
+            candidates.iter().any(|plan| {
+                matches!(plan, PhysicalOperator::HashJoin { .. } | PhysicalOperator::OptimizedHashJoin { .. })
+            })
+        };
+        assert!(builds_hash_table(&optimizer.join_candidates(scan(1, "?a"), scan(2, "?b"))));
+
+        // A million rows do not fit in a 1 MB hash table
+        optimizer.hints.max_memory_mb = Some(1);
+        let candidates = optimizer.join_candidates(scan(1, "?a"), scan(2, "?b"));
+        assert!(matches!(candidates[..], [PhysicalOperator::NestedLoopJoin { .. }]), "{:?}", candidates);
+    }
 }
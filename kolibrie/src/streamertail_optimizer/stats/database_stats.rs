@@ -9,10 +9,15 @@
  */
 
 use crate::sparql_database::SparqlDatabase;
-use rayon::prelude::*;
+use rand::rngs::StdRng;
+use rand::SeedableRng;
 use std::collections::HashMap;
 use std::sync::RwLock;
 
+/// About this many triples are read to estimate the statistics of a larger store
+const STATS_SAMPLE_SIZE: usize = 100_000;
+const STATS_SAMPLE_SEED: u64 = 0x5eed;
+
 /// Database statistics for cost-based optimization
 #[derive(Debug)]
 pub struct DatabaseStats {
@@ -41,55 +46,32 @@ impl DatabaseStats {
 
     /// Gathers statistics from the database using sampling for performance
     pub fn gather_stats_fast(database: &SparqlDatabase) -> Self {
-        let total_triples = database.triples.len() as u64;
-
-        // Convert BTreeSet to Vec for sampling
-        let triples_vec: Vec<_> = database.triples.iter().collect();
+        let total_triples = database.count_triples() as u64;
 
-        // Use sampling for large datasets instead of full scan
-        let sample_size = (total_triples as usize).min(100_000);
-        let step = if total_triples > sample_size as u64 {
-            total_triples as usize / sample_size
-        } else {
-            1
-        };
-
-        // Sample the data by stepping through the vector
-        let sampled_triples: Vec<_> = triples_vec.iter().step_by(step).take(sample_size).collect();
-
-        // Use parallel processing for stats gathering
-        let stats_data: Vec<_> = sampled_triples
-            .par_iter()
-            .map(|triple| {
-                let subject = triple.subject;
-                let predicate = triple.predicate;
-                let object = triple.object;
-                (subject, predicate, object)
-            })
-            .collect();
+        // Estimate from a subject-coherent sample on large datasets instead of a
+        // full scan; a fixed seed keeps plans reproducible
+        let mut rng = StdRng::seed_from_u64(STATS_SAMPLE_SEED);
+        let sampled_triples = database.sample_triples(STATS_SAMPLE_SIZE, &mut rng);
 
         // Build cardinality maps
         let mut predicate_cardinalities: HashMap<u32, u64> = HashMap::new();
         let mut subject_cardinalities: HashMap<u32, u64> = HashMap::new();
         let mut object_cardinalities: HashMap<u32, u64> = HashMap::new();
 
-        for (subject, predicate, object) in stats_data {
-            *predicate_cardinalities.entry(predicate).or_insert(0) += 1;
-            *subject_cardinalities.entry(subject).or_insert(0) += 1;
-            *object_cardinalities.entry(object).or_insert(0) += 1;
+        for triple in &sampled_triples {
+            *predicate_cardinalities.entry(triple.predicate).or_insert(0) += 1;
+            *subject_cardinalities.entry(triple.subject).or_insert(0) += 1;
+            *object_cardinalities.entry(triple.object).or_insert(0) += 1;
         }
 
-        // Scale up sampled statistics
-        let scale_factor = if step > 1 { step as u64 } else { 1 };
-        predicate_cardinalities
-            .values_mut()
-            .for_each(|v| *v *= scale_factor);
-        subject_cardinalities
-            .values_mut()
-            .for_each(|v| *v *= scale_factor);
-        object_cardinalities
-            .values_mut()
-            .for_each(|v| *v *= scale_factor);
+        // Scale up sampled statistics; a sampled subject brings all its
+        // triples, so its count is already exact
+        if sampled_triples.len() < database.triples.len() {
+            let scale_factor = database.triples.len() as f64 / sampled_triples.len().max(1) as f64;
+            let scale = |v: &mut u64| *v = (*v as f64 * scale_factor).round() as u64;
+            predicate_cardinalities.values_mut().for_each(scale);
+            object_cardinalities.values_mut().for_each(scale);
+        }
 
         let quoted_triple_count = database.quoted_triple_store.read().unwrap().len() as u64;
 
@@ -8,13 +8,26 @@
  * you can obtain one at https://mozilla.org/MPL/2.0/.
  */
 
-use shared::{dictionary::Dictionary, query::FilterExpression};
+use crate::utils::{
+    compare_datetime_values, compare_filter_values, evaluate_coalesce, evaluate_if_filter,
+    evaluate_iri_filter, evaluate_numeric_filter, evaluate_string_filter, lookup_udf,
+    parse_numeric_literal, parse_xsd_datetime, ClonableFn,
+};
+use crate::text_index::TextPattern;
+use super::operators::PhysicalOperator;
+use shared::dictionary::Dictionary;
+use shared::query::{FilterExpression, StringExpression, StringFunction};
 use std::collections::HashMap;
+use std::fmt;
+use std::str::FromStr;
 
 /// Represents a condition for filtering operations
 #[derive(Debug, Clone)]
 pub struct Condition {
     pub expression: FilterExpression<'static>,
+    /// Constants compared with `=`/`!=` whose equality is plain string
+    /// equality, so a dictionary ID match decides it without decoding
+    exact_constants: Vec<&'static str>,
 }
 
 /// ID-based result type for performance optimization
@@ -23,6 +36,176 @@ pub struct IdResult {
     pub bindings: HashMap<String, u32>, // Variable -> ID mapping
 }
 
+/// Join algorithm forced on every join of a query instead of the cheapest one,
+/// by a leading `# kolibrie:join <name>` line or `execute_query_with_hint`.
+/// Names:
+/// - `hash`: builds a hash table on the right input (`HashJoin`)
+/// - `optimized_hash`: hashes the smaller input on dictionary IDs (`OptimizedHashJoin`)
+/// - `nested_loop`: compares every pair of rows (`NestedLoopJoin`)
+/// - `parallel`: partitions the join across threads, binding index scans on
+///   the right from the left rows (`ParallelJoin`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JoinAlgorithm {
+    Hash,
+    OptimizedHash,
+    NestedLoop,
+    Parallel,
+}
+
+impl JoinAlgorithm {
+    /// Names accepted by `from_str`, in declaration order
+    pub const NAMES: [&'static str; 4] = ["hash", "optimized_hash", "nested_loop", "parallel"];
+
+    /// The physical join of `left` with `right` using this algorithm
+    pub fn join(self, left: PhysicalOperator, right: PhysicalOperator) -> PhysicalOperator {
+        match self {
+            JoinAlgorithm::Hash => PhysicalOperator::hash_join(left, right),
+            JoinAlgorithm::OptimizedHash => PhysicalOperator::optimized_hash_join(left, right),
+            JoinAlgorithm::NestedLoop => PhysicalOperator::nested_loop_join(left, right),
+            JoinAlgorithm::Parallel => PhysicalOperator::parallel_join(left, right),
+        }
+    }
+}
+
+impl FromStr for JoinAlgorithm {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name {
+            "hash" => Ok(JoinAlgorithm::Hash),
+            "optimized_hash" => Ok(JoinAlgorithm::OptimizedHash),
+            "nested_loop" => Ok(JoinAlgorithm::NestedLoop),
+            "parallel" => Ok(JoinAlgorithm::Parallel),
+            _ => Err(format!(
+                "unknown join algorithm `{}`, expected one of: {}",
+                name,
+                Self::NAMES.join(", ")
+            )),
+        }
+    }
+}
+
+impl fmt::Display for JoinAlgorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let index = match self {
+            JoinAlgorithm::Hash => 0,
+            JoinAlgorithm::OptimizedHash => 1,
+            JoinAlgorithm::NestedLoop => 2,
+            JoinAlgorithm::Parallel => 3,
+        };
+        f.write_str(Self::NAMES[index])
+    }
+}
+
+/// One of the six triple indexes, named by the order of its keys: `Spo` maps
+/// subject to predicate to objects
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum IndexOrder {
+    Spo,
+    Sop,
+    Pso,
+    Pos,
+    Osp,
+    Ops,
+}
+
+impl IndexOrder {
+    /// Names accepted by `from_str` (case-insensitively), in declaration order
+    pub const NAMES: [&'static str; 6] = ["SPO", "SOP", "PSO", "POS", "OSP", "OPS"];
+
+    /// The triple position (0 subject, 1 predicate, 2 object) of each key, outermost first
+    pub fn positions(self) -> [usize; 3] {
+        match self {
+            IndexOrder::Spo => [0, 1, 2],
+            IndexOrder::Sop => [0, 2, 1],
+            IndexOrder::Pso => [1, 0, 2],
+            IndexOrder::Pos => [1, 2, 0],
+            IndexOrder::Osp => [2, 0, 1],
+            IndexOrder::Ops => [2, 1, 0],
+        }
+    }
+}
+
+impl FromStr for IndexOrder {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        match name.to_ascii_uppercase().as_str() {
+            "SPO" => Ok(IndexOrder::Spo),
+            "SOP" => Ok(IndexOrder::Sop),
+            "PSO" => Ok(IndexOrder::Pso),
+            "POS" => Ok(IndexOrder::Pos),
+            "OSP" => Ok(IndexOrder::Osp),
+            "OPS" => Ok(IndexOrder::Ops),
+            _ => Err(format!(
+                "unknown index `{}`, expected one of: {}",
+                name,
+                Self::NAMES.join(", ")
+            )),
+        }
+    }
+}
+
+impl fmt::Display for IndexOrder {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let index = match self {
+            IndexOrder::Spo => 0,
+            IndexOrder::Sop => 1,
+            IndexOrder::Pso => 2,
+            IndexOrder::Pos => 3,
+            IndexOrder::Osp => 4,
+            IndexOrder::Ops => 5,
+        };
+        f.write_str(Self::NAMES[index])
+    }
+}
+
+/// Planner overrides for one query, read from the comment lines it starts
+/// with. `# kolibrie:join <name>` forces a join algorithm; `# HINT <hint>`
+/// lines take:
+/// - `USE_INDEX <order>`: every triple pattern is an index scan reading that
+///   index (`SPO`, `SOP`, `PSO`, `POS`, `OSP` or `OPS`), whichever positions are bound
+/// - `PARALLEL_JOIN`: every join is a `ParallelJoin`, like `kolibrie:join parallel`
+/// - `MAX_MEMORY_MB <n>`: joins whose estimated hash table is larger than `n`
+///   megabytes are planned as nested-loop joins
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct QueryHints {
+    pub join_algorithm: Option<JoinAlgorithm>,
+    pub use_index: Option<IndexOrder>,
+    pub max_memory_mb: Option<u64>,
+}
+
+impl QueryHints {
+    /// Apply the text of a `# HINT` line, e.g. `USE_INDEX POS`
+    pub fn apply(&mut self, hint: &str) -> Result<(), String> {
+        let mut words = hint.split_whitespace();
+        let name = words.next().unwrap_or_default();
+        let argument = words.next();
+        match (name.to_ascii_uppercase().as_str(), argument) {
+            ("USE_INDEX", Some(order)) => self.use_index = Some(order.parse()?),
+            ("PARALLEL_JOIN", None) => self.join_algorithm = Some(JoinAlgorithm::Parallel),
+            ("MAX_MEMORY_MB", Some(megabytes)) => {
+                let megabytes = megabytes
+                    .parse()
+                    .map_err(|_| format!("MAX_MEMORY_MB expects a whole number of megabytes, got `{}`", megabytes))?;
+                self.max_memory_mb = Some(megabytes);
+            }
+            ("USE_INDEX" | "MAX_MEMORY_MB", None) => return Err(format!("hint `{}` needs an argument", name)),
+            ("PARALLEL_JOIN", Some(_)) => return Err("hint `PARALLEL_JOIN` takes no argument".to_string()),
+            _ => {
+                return Err(format!(
+                    "unknown hint `{}`, expected USE_INDEX, PARALLEL_JOIN or MAX_MEMORY_MB",
+                    name
+                ))
+            }
+        }
+        if words.next().is_some() {
+            return Err(format!("too many arguments to hint `{}`", name));
+        }
+        Ok(())
+    }
+}
+
 impl Condition {
     /// Creates a new condition
     pub fn new(variable: String, operator: String, value: String) -> Self {
@@ -31,14 +214,86 @@ impl Condition {
         let op_static: &'static str = Box::leak(operator.into_boxed_str());
         let val_static: &'static str = Box::leak(value.into_boxed_str());
 
-        Self {
-            expression: FilterExpression::Comparison(var_static, op_static, val_static),
-        }
+        Self::from_filter(FilterExpression::Comparison(var_static, op_static, val_static))
     }
 
     /// Creates a new condition from a filter expression
     pub fn from_filter(filter: FilterExpression<'static>) -> Self {
-        Self { expression: filter }
+        let mut exact_constants = Vec::new();
+        Self::collect_exact_constants(&filter, &mut exact_constants);
+        Self {
+            expression: filter,
+            exact_constants,
+        }
+    }
+
+    // Numeric and dateTime constants compare by value ("1.0" = "1"), so only
+    // other constants can be matched by ID
+    fn collect_exact_constants(expr: &FilterExpression<'static>, constants: &mut Vec<&'static str>) {
+        match expr {
+            FilterExpression::Comparison(_, "=" | "!=", value)
+                if !value.starts_with(['?', '"'])
+                    && parse_numeric_literal(value).is_none()
+                    && parse_xsd_datetime(value).is_none()
+                    && !constants.contains(value) =>
+            {
+                constants.push(value);
+            }
+            FilterExpression::And(left, right) | FilterExpression::Or(left, right) => {
+                Self::collect_exact_constants(left, constants);
+                Self::collect_exact_constants(right, constants);
+            }
+            FilterExpression::Not(inner) => Self::collect_exact_constants(inner, constants),
+            _ => {}
+        }
+    }
+
+    /// The variable (without `?`) and constant of a condition that is exactly
+    /// `?v = "c"`, when `c` is compared by plain string equality
+    pub fn equality_binding(&self) -> Option<(&'static str, &'static str)> {
+        match self.expression {
+            FilterExpression::Comparison(var, "=", value) if self.exact_constants.contains(&value) => {
+                var.strip_prefix('?').map(|name| (name, value))
+            }
+            _ => None,
+        }
+    }
+
+    /// The variable (without `?`) and search of a condition that is exactly
+    /// `CONTAINS(?v, "text")` or `REGEX(?v, "pattern"[, "flags"])`
+    pub fn text_pattern(&self) -> Option<(&'static str, TextPattern<'static>)> {
+        let FilterExpression::StringFunction(func, args) = &self.expression else {
+            return None;
+        };
+        let (var, pattern) = match (func, args.as_slice()) {
+            (StringFunction::Contains, [StringExpression::Variable(var), StringExpression::Literal(text)]) => {
+                (*var, TextPattern::Contains(text))
+            }
+            (StringFunction::Regex, [StringExpression::Variable(var), StringExpression::Literal(pattern)]) => {
+                (*var, TextPattern::Regex(pattern, ""))
+            }
+            (
+                StringFunction::Regex,
+                [StringExpression::Variable(var), StringExpression::Literal(pattern), StringExpression::Literal(flags)],
+            ) => (*var, TextPattern::Regex(pattern, flags)),
+            _ => return None,
+        };
+        Some((var.strip_prefix('?')?, pattern))
+    }
+
+    /// Whether the term with ID `id` equals the constant `value`, decided
+    /// without parsing either side; `None` if `value` needs a value comparison
+    fn id_equality(&self, id: u32, value: &str, dictionary: &Dictionary) -> Option<bool> {
+        if !self.exact_constants.contains(&value) {
+            return None;
+        }
+        if dictionary.string_to_id.get(value) == Some(&id) {
+            return Some(true);
+        }
+        // A different term can still match through its lexical form, as
+        // `"true"^^xsd:boolean` does `true`
+        let decoded = dictionary.decode(id)?;
+        Some(decoded.starts_with('"') && compare_filter_values(decoded, "=", value))
     }
 
     /// Evaluates the condition against string-based results
@@ -52,17 +307,19 @@ impl Condition {
             FilterExpression::Comparison(var, op, value) => {
                 let var_name = var.strip_prefix('?').unwrap_or(var);
                 if let Some(result_value) = result.get(var_name) {
+                    if let Some(matched) = compare_datetime_values(result_value, op, value) {
+                        return matched;
+                    }
                     match *op {
-                        "=" => result_value == value,
-                        "!=" => result_value != value,
-                        ">" => result_value.parse::<f64>().unwrap_or(0.0) 
-                            > value.parse::<f64>().unwrap_or(0.0),
-                        ">=" => result_value.parse::<f64>().unwrap_or(0.0) 
-                            >= value.parse::<f64>().unwrap_or(0.0),
-                        "<" => result_value.parse::<f64>().unwrap_or(0.0) 
-                            < value.parse::<f64>().unwrap_or(0.0),
-                        "<=" => result_value.parse::<f64>().unwrap_or(0.0) 
-                            <= value.parse::<f64>().unwrap_or(0.0),
+                        "=" | "!=" => compare_filter_values(result_value, op, value),
+                        ">" => parse_numeric_literal(result_value).unwrap_or(0.0) 
+                            > parse_numeric_literal(value).unwrap_or(0.0),
+                        ">=" => parse_numeric_literal(result_value).unwrap_or(0.0) 
+                            >= parse_numeric_literal(value).unwrap_or(0.0),
+                        "<" => parse_numeric_literal(result_value).unwrap_or(0.0) 
+                            < parse_numeric_literal(value).unwrap_or(0.0),
+                        "<=" => parse_numeric_literal(result_value).unwrap_or(0.0) 
+                            <= parse_numeric_literal(value).unwrap_or(0.0),
                         _ => false,
                     }
                 } else {
@@ -81,7 +338,7 @@ impl Condition {
             FilterExpression::ArithmeticExpr(expr) => {
                 let resolver = |var: &str| -> Option<f64> {
                     let name = var.strip_prefix('?').unwrap_or(var);
-                    result.get(name)?.parse::<f64>().ok()
+                    parse_numeric_literal(result.get(name)?)
                 };
                 expr.evaluate(&resolver).map(|v| v != 0.0).unwrap_or(false)
             }
@@ -103,16 +360,45 @@ impl Condition {
                     _ => false,
                 }
             }
+            // Needs the database's UDF registry; see `evaluate_with_ids`
+            FilterExpression::FunctionComparison(..) => false,
+            FilterExpression::StringFunction(func, args) => {
+                let resolve = |var: &str| result.get(var.strip_prefix('?').unwrap_or(var)).cloned();
+                evaluate_string_filter(*func, args, None, &resolve)
+            }
+            FilterExpression::StringFunctionComparison(func, args, op, value) => {
+                let resolve = |var: &str| result.get(var.strip_prefix('?').unwrap_or(var)).cloned();
+                evaluate_string_filter(*func, args, Some((op, value)), &resolve)
+            }
+            FilterExpression::NumericFunction(func, args, op, value) => {
+                let resolve = |var: &str| result.get(var.strip_prefix('?').unwrap_or(var)).cloned();
+                evaluate_numeric_filter(*func, args, op, value, &resolve)
+            }
+            FilterExpression::Coalesce(args) => {
+                let resolve = |var: &str| result.get(var.strip_prefix('?').unwrap_or(var)).cloned();
+                evaluate_coalesce(args, &resolve).is_some()
+            }
+            FilterExpression::If(condition, then, otherwise, comparison) => {
+                let holds = self.evaluate_filter(condition, result);
+                let resolve = |var: &str| result.get(var.strip_prefix('?').unwrap_or(var)).cloned();
+                evaluate_if_filter(holds, then, otherwise, *comparison, &resolve)
+            }
+            FilterExpression::Iri(arg, comparison) => {
+                let resolve = |var: &str| result.get(var.strip_prefix('?').unwrap_or(var)).cloned();
+                evaluate_iri_filter(arg, *comparison, &resolve)
+            }
         }
     }
 
-    /// Evaluates the condition against ID-based results for performance
+    /// Evaluates the condition against ID-based results for performance.
+    /// `udfs` resolves function calls compared in the filter.
     pub fn evaluate_with_ids(
         &self,
         result: &HashMap<String, u32>,
         dictionary: &Dictionary,
+        udfs: &HashMap<String, ClonableFn>,
     ) -> bool {
-        self.evaluate_filter_with_ids(&self.expression, result, dictionary)
+        self.evaluate_filter_with_ids(&self.expression, result, dictionary, udfs)
     }
 
     /// Evaluates a filter expression with IDs recursively
@@ -121,23 +407,31 @@ impl Condition {
         expr: &FilterExpression,
         result: &HashMap<String, u32>,
         dictionary: &Dictionary,
+        udfs: &HashMap<String, ClonableFn>,
     ) -> bool {
         match expr {
             FilterExpression::Comparison(var, op, value) => {
                 let var_name = var.strip_prefix('?').unwrap_or(var);
                 if let Some(&id) = result.get(var_name) {
+                    if matches!(*op, "=" | "!=") {
+                        if let Some(equal) = self.id_equality(id, value, dictionary) {
+                            return equal == (*op == "=");
+                        }
+                    }
                     let decoded_value = dictionary.decode(id).unwrap();
+                    if let Some(matched) = compare_datetime_values(decoded_value, op, value) {
+                        return matched;
+                    }
                     match *op {
-                        "=" => decoded_value == *value,
-                        "!=" => decoded_value != *value,
-                        ">" => decoded_value.parse::<f64>().unwrap_or(0.0) 
-                            > value.parse::<f64>().unwrap_or(0.0),
-                        ">=" => decoded_value.parse::<f64>().unwrap_or(0.0) 
-                            >= value.parse::<f64>().unwrap_or(0.0),
-                        "<" => decoded_value.parse::<f64>().unwrap_or(0.0) 
-                            < value.parse::<f64>().unwrap_or(0.0),
-                        "<=" => decoded_value.parse::<f64>().unwrap_or(0.0) 
-                            <= value.parse::<f64>().unwrap_or(0.0),
+                        "=" | "!=" => compare_filter_values(decoded_value, op, value),
+                        ">" => parse_numeric_literal(decoded_value).unwrap_or(0.0) 
+                            > parse_numeric_literal(value).unwrap_or(0.0),
+                        ">=" => parse_numeric_literal(decoded_value).unwrap_or(0.0) 
+                            >= parse_numeric_literal(value).unwrap_or(0.0),
+                        "<" => parse_numeric_literal(decoded_value).unwrap_or(0.0) 
+                            < parse_numeric_literal(value).unwrap_or(0.0),
+                        "<=" => parse_numeric_literal(decoded_value).unwrap_or(0.0) 
+                            <= parse_numeric_literal(value).unwrap_or(0.0),
                         _ => false,
                     }
                 } else {
@@ -145,21 +439,21 @@ impl Condition {
                 }
             }
             FilterExpression::And(left, right) => {
-                self.evaluate_filter_with_ids(left, result, dictionary) 
-                    && self.evaluate_filter_with_ids(right, result, dictionary)
+                self.evaluate_filter_with_ids(left, result, dictionary, udfs) 
+                    && self.evaluate_filter_with_ids(right, result, dictionary, udfs)
             }
             FilterExpression::Or(left, right) => {
-                self.evaluate_filter_with_ids(left, result, dictionary) 
-                    || self.evaluate_filter_with_ids(right, result, dictionary)
+                self.evaluate_filter_with_ids(left, result, dictionary, udfs) 
+                    || self.evaluate_filter_with_ids(right, result, dictionary, udfs)
             }
             FilterExpression::Not(inner) => {
-                !self.evaluate_filter_with_ids(inner, result, dictionary)
+                !self.evaluate_filter_with_ids(inner, result, dictionary, udfs)
             }
             FilterExpression::ArithmeticExpr(expr) => {
                 let resolver = |var: &str| -> Option<f64> {
                     let name = var.strip_prefix('?').unwrap_or(var);
                     let &id = result.get(name)?;
-                    dictionary.decode(id)?.parse::<f64>().ok()
+                    parse_numeric_literal(dictionary.decode(id)?)
                 };
                 expr.evaluate(&resolver).map(|v| v != 0.0).unwrap_or(false)
             }
@@ -181,6 +475,68 @@ impl Condition {
                     _ => false,
                 }
             }
+            FilterExpression::FunctionComparison(func_name, args, op, value) => {
+                let Some(func) = lookup_udf(udfs, func_name) else {
+                    return false;
+                };
+                let resolve = |term: &str| -> Option<String> {
+                    match term.strip_prefix('?') {
+                        Some(name) => dictionary.decode(*result.get(name)?).map(str::to_string),
+                        None => Some(term.to_string()),
+                    }
+                };
+                let Some(resolved_args) = args.iter().map(|arg| resolve(arg)).collect::<Option<Vec<String>>>() else {
+                    return false;
+                };
+                let Some(rhs) = resolve(value) else {
+                    return false;
+                };
+                let output = func.call(resolved_args.iter().map(String::as_str).collect());
+                compare_filter_values(&output, op, &rhs)
+            }
+            FilterExpression::StringFunction(func, args) => {
+                let resolve = |var: &str| {
+                    let &id = result.get(var.strip_prefix('?').unwrap_or(var))?;
+                    dictionary.decode(id).map(str::to_string)
+                };
+                evaluate_string_filter(*func, args, None, &resolve)
+            }
+            FilterExpression::StringFunctionComparison(func, args, op, value) => {
+                let resolve = |var: &str| {
+                    let &id = result.get(var.strip_prefix('?').unwrap_or(var))?;
+                    dictionary.decode(id).map(str::to_string)
+                };
+                evaluate_string_filter(*func, args, Some((op, value)), &resolve)
+            }
+            FilterExpression::NumericFunction(func, args, op, value) => {
+                let resolve = |var: &str| {
+                    let &id = result.get(var.strip_prefix('?').unwrap_or(var))?;
+                    dictionary.decode(id).map(str::to_string)
+                };
+                evaluate_numeric_filter(*func, args, op, value, &resolve)
+            }
+            FilterExpression::Coalesce(args) => {
+                let resolve = |var: &str| {
+                    let &id = result.get(var.strip_prefix('?').unwrap_or(var))?;
+                    dictionary.decode(id).map(str::to_string)
+                };
+                evaluate_coalesce(args, &resolve).is_some()
+            }
+            FilterExpression::If(condition, then, otherwise, comparison) => {
+                let holds = self.evaluate_filter_with_ids(condition, result, dictionary, udfs);
+                let resolve = |var: &str| {
+                    let &id = result.get(var.strip_prefix('?').unwrap_or(var))?;
+                    dictionary.decode(id).map(str::to_string)
+                };
+                evaluate_if_filter(holds, then, otherwise, *comparison, &resolve)
+            }
+            FilterExpression::Iri(arg, comparison) => {
+                let resolve = |var: &str| {
+                    let &id = result.get(var.strip_prefix('?').unwrap_or(var))?;
+                    dictionary.decode(id).map(str::to_string)
+                };
+                evaluate_iri_filter(arg, *comparison, &resolve)
+            }
         }
     }
 }
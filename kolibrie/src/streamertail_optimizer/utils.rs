@@ -11,14 +11,15 @@
 use super::operators::{LogicalOperator, PhysicalOperator};
 use super::types::Condition;
 use crate::sparql_database::SparqlDatabase;
-use shared::query::{FilterExpression, SubQuery, ValuesClause};
+use crate::utils::{canonical_typed_literal, language_tag};
+use shared::query::{FilterExpression, StringExpression, SubQuery, ValuesClause};
 use shared::terms::{Term, TriplePattern};
 use std::collections::HashMap;
 
 /// Extracts a triple pattern from a physical operator if it's a scan operation
 pub fn extract_pattern(op: &PhysicalOperator) -> Option<&TriplePattern> {
     match op {
-        PhysicalOperator::TableScan { pattern } | PhysicalOperator::IndexScan { pattern } => {
+        PhysicalOperator::TableScan { pattern } | PhysicalOperator::IndexScan { pattern, .. } => {
             Some(pattern)
         }
         // If it's a Filter, keep searching in its child
@@ -34,6 +35,15 @@ pub fn pattern_contains_variable(pattern: &TriplePattern, var: &str) -> bool {
         || matches!(&pattern.2, Term::Variable(v) if v == var)
 }
 
+/// The variable (without `?`) and constant of a `?v = "c"` condition that a
+/// scan of `pattern` can answer by binding `?v`. `?v` has to be the subject
+/// or predicate, where no typed literal can match `c` by its lexical form.
+pub fn scan_equality_binding(pattern: &TriplePattern, condition: &Condition) -> Option<(&'static str, &'static str)> {
+    let (var, constant) = condition.equality_binding()?;
+    let is_var = |term: &Term| matches!(term, Term::Variable(v) if v.strip_prefix('?').unwrap_or(v) == var);
+    (is_var(&pattern.0) || is_var(&pattern.1)).then_some((var, constant))
+}
+
 /// Estimates the selectivity of an operator for optimization purposes
 pub fn estimate_operator_selectivity(op: &LogicalOperator, _database: &SparqlDatabase) -> u64 {
     match op {
@@ -103,10 +113,37 @@ pub fn build_logical_plan(
     patterns: Vec<(&str, &str, &str)>,
     filters: Vec<FilterExpression>,
     prefixes: &HashMap<String, String>,
-    database: &mut SparqlDatabase,
+    database: &SparqlDatabase,
+    binds: &[(&str, Vec<&str>, &str)],
+    values_clause: Option<&ValuesClause>,
+) -> LogicalOperator {
+    build_logical_plan_with_subplans(
+        variables,
+        patterns,
+        filters,
+        prefixes,
+        database,
+        binds,
+        values_clause,
+        Vec::new(),
+    )
+}
+
+/// Like `build_logical_plan`, but also joins `subplans` (e.g. evaluated
+/// subqueries) with the patterns before filters, binds and projection apply
+#[allow(clippy::too_many_arguments)]
+pub fn build_logical_plan_with_subplans(
+    variables: Vec<(&str, &str)>,
+    patterns: Vec<(&str, &str, &str)>,
+    filters: Vec<FilterExpression>,
+    prefixes: &HashMap<String, String>,
+    database: &SparqlDatabase,
     binds: &[(&str, Vec<&str>, &str)],
     values_clause: Option<&ValuesClause>,
+    subplans: Vec<LogicalOperator>,
 ) -> LogicalOperator {
+    let mut subplans = subplans.into_iter();
+
     // Create base operator from VALUES if present, otherwise empty join base
     let mut result = if let Some(values_clause) = values_clause {
         // Convert ValuesClause to LogicalOperator::Values
@@ -133,6 +170,10 @@ pub fn build_logical_plan(
     } else {
         // Start with first pattern as before
         let first_pattern = if patterns.is_empty() {
+            if let Some(subplan) = subplans.next() {
+                // Only subqueries in the WHERE clause
+                return finish_logical_plan(subplan, subplans, variables, filters, binds);
+            }
             // Empty query - return a minimal scan
             let pattern = (
                 Term::Variable("?s".to_string()),
@@ -170,6 +211,21 @@ pub fn build_logical_plan(
         result = LogicalOperator::join(result, scan_op);
     }
 
+    finish_logical_plan(result, subplans, variables, filters, binds)
+}
+
+/// Join the remaining subplans, then apply filters, binds and projection
+fn finish_logical_plan(
+    mut result: LogicalOperator,
+    subplans: impl Iterator<Item = LogicalOperator>,
+    variables: Vec<(&str, &str)>,
+    filters: Vec<FilterExpression>,
+    binds: &[(&str, Vec<&str>, &str)],
+) -> LogicalOperator {
+    for subplan in subplans {
+        result = LogicalOperator::join(result, subplan);
+    }
+
     // Apply filters that couldn't be pushed down
     for filter in filters {
         let condition = convert_filter_to_condition(&filter);
@@ -198,7 +254,7 @@ pub fn build_logical_plan(
 fn convert_term_star(
     term_str: &str,
     prefixes: &HashMap<String, String>,
-    database: &mut SparqlDatabase,
+    database: &SparqlDatabase,
 ) -> Term {
     let trimmed = term_str.trim();
     if trimmed.starts_with("<<") && trimmed.ends_with(">>") {
@@ -221,6 +277,14 @@ fn convert_term_star(
         }
     } else if trimmed.starts_with('?') {
         Term::Variable(trimmed.to_string())
+    } else if !language_tag(trimmed).is_empty() {
+        // Language-tagged literals are stored with their quotes: "chat"@fr
+        let mut dict = database.dictionary.write().unwrap();
+        Term::Constant(dict.encode(trimmed))
+    } else if let Some(typed) = canonical_typed_literal(trimmed, |datatype| resolve_with_prefixes(datatype, prefixes)) {
+        // Typed literals are stored with their datatype IRI: "42"^^<...#integer>
+        let mut dict = database.dictionary.write().unwrap();
+        Term::Constant(dict.encode(&typed))
     } else {
         // Strip angle brackets from URIs and quotes from literals before resolving
         let cleaned = if trimmed.starts_with('<') && trimmed.ends_with('>') && !trimmed.starts_with("<<") {
@@ -241,12 +305,12 @@ fn convert_term_star(
 }
 
 // Helper function to convert pattern strings to TriplePattern
-fn convert_pattern_to_triple(
+pub fn convert_pattern_to_triple(
     subject_str: &str,
     predicate_str: &str,
     object_str: &str,
     prefixes: &HashMap<String, String>,
-    database: &mut SparqlDatabase,
+    database: &SparqlDatabase,
 ) -> TriplePattern {
     let subject = convert_term_star(subject_str, prefixes, database);
     let predicate = convert_term_star(predicate_str, prefixes, database);
@@ -258,7 +322,7 @@ fn convert_pattern_to_triple(
 pub fn build_logical_plan_from_subquery(
     subquery: &SubQuery,
     prefixes: &HashMap<String, String>,
-    database: &mut SparqlDatabase,
+    database: &SparqlDatabase,
 ) -> LogicalOperator {
     // Build the inner logical plan from the subquery patterns
     let variables:  Vec<(&str, &str)> = subquery
@@ -346,6 +410,72 @@ fn make_filter_static(filter: &FilterExpression) -> FilterExpression<'static> {
                 .collect();
             FilterExpression::FunctionCall(name_static, args_static)
         }
+        FilterExpression::FunctionComparison(name, args, op, value) => {
+            let name_static: &'static str = Box::leak(name.to_string().into_boxed_str());
+            let args_static: Vec<&'static str> = args.iter()
+                .map(|a| -> &'static str { Box::leak(a.to_string().into_boxed_str()) })
+                .collect();
+            let op_static: &'static str = Box::leak(op.to_string().into_boxed_str());
+            let value_static: &'static str = Box::leak(value.to_string().into_boxed_str());
+            FilterExpression::FunctionComparison(name_static, args_static, op_static, value_static)
+        }
+        FilterExpression::StringFunction(func, args) => {
+            FilterExpression::StringFunction(*func, args.iter().map(make_string_expr_static).collect())
+        }
+        FilterExpression::StringFunctionComparison(func, args, op, value) => {
+            let op_static: &'static str = Box::leak(op.to_string().into_boxed_str());
+            let value_static: &'static str = Box::leak(value.to_string().into_boxed_str());
+            FilterExpression::StringFunctionComparison(
+                *func,
+                args.iter().map(make_string_expr_static).collect(),
+                op_static,
+                value_static,
+            )
+        }
+        FilterExpression::NumericFunction(func, args, op, value) => {
+            let op_static: &'static str = Box::leak(op.to_string().into_boxed_str());
+            let value_static: &'static str = Box::leak(value.to_string().into_boxed_str());
+            FilterExpression::NumericFunction(
+                *func,
+                args.iter().map(make_arith_static).collect(),
+                op_static,
+                value_static,
+            )
+        }
+        FilterExpression::Coalesce(args) => {
+            FilterExpression::Coalesce(args.iter().map(make_string_expr_static).collect())
+        }
+        FilterExpression::If(condition, then, otherwise, comparison) => {
+            FilterExpression::If(
+                Box::new(make_filter_static(condition)),
+                make_string_expr_static(then),
+                make_string_expr_static(otherwise),
+                comparison.map(make_comparison_static),
+            )
+        }
+        FilterExpression::Iri(arg, comparison) => {
+            FilterExpression::Iri(make_string_expr_static(arg), comparison.map(make_comparison_static))
+        }
+    }
+}
+
+/// Converts the `(operator, value)` an `IF` or `IRI` is compared with to 'static lifetime
+fn make_comparison_static((op, value): (&str, &str)) -> (&'static str, &'static str) {
+    (Box::leak(op.to_string().into_boxed_str()), Box::leak(value.to_string().into_boxed_str()))
+}
+
+/// Converts a string function argument with any lifetime to 'static lifetime
+fn make_string_expr_static(expr: &StringExpression) -> StringExpression<'static> {
+    match expr {
+        StringExpression::Variable(var) => {
+            StringExpression::Variable(Box::leak(var.to_string().into_boxed_str()))
+        }
+        StringExpression::Literal(literal) => {
+            StringExpression::Literal(Box::leak(literal.to_string().into_boxed_str()))
+        }
+        StringExpression::Call(func, args) => {
+            StringExpression::Call(*func, args.iter().map(make_string_expr_static).collect())
+        }
     }
 }
 
@@ -0,0 +1,252 @@
+/*
+ * Copyright © 2026 Volodymyr Kadzhaia
+ * Copyright © 2026 Pieter Bonte
+ * KU Leuven — Stream Intelligence Lab, Belgium
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this file,
+ * you can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use shared::dictionary::Dictionary;
+use std::collections::HashMap;
+
+/// A substring search a `TextIndex` can narrow down
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TextPattern<'a> {
+    /// Terms containing the text, as `CONTAINS` and `TripleFilter::Contains` test
+    Contains(&'a str),
+    /// Terms matching a `REGEX` pattern with SPARQL flags (`i`, `s`, `m`, `x`, `q`)
+    Regex(&'a str, &'a str),
+}
+
+/// Inverted index from the lowercase character trigrams of every dictionary
+/// term to the IDs of the terms containing them.
+///
+/// Enabled with `SparqlDatabase::enable_text_index` and rebuilt by
+/// `build_all_indexes` while enabled. It narrows `CONTAINS`/`REGEX` filters
+/// and `QueryBuilder` substring filters to a superset of the matching terms,
+/// which the caller still checks; terms added after the build are not
+/// covered, so callers fall back to scanning once `covers` is false.
+#[derive(Debug, Clone, Default)]
+pub struct TextIndex {
+    /// Ascending IDs of the terms containing each trigram
+    trigrams: HashMap<[char; 3], Vec<u32>>,
+    /// Dictionary size at build time
+    term_count: usize,
+}
+
+impl TextIndex {
+    /// Index every term of `dictionary`
+    pub fn build(dictionary: &Dictionary) -> Self {
+        let mut trigrams: HashMap<[char; 3], Vec<u32>> = HashMap::new();
+        for (&id, term) in &dictionary.id_to_string {
+            let mut seen = Vec::new();
+            for trigram in trigrams_of(term) {
+                if !seen.contains(&trigram) {
+                    seen.push(trigram);
+                    trigrams.entry(trigram).or_default().push(id);
+                }
+            }
+        }
+        for ids in trigrams.values_mut() {
+            ids.sort_unstable();
+        }
+        TextIndex {
+            trigrams,
+            term_count: dictionary.id_to_string.len(),
+        }
+    }
+
+    /// Whether every term of `dictionary` was indexed
+    pub fn covers(&self, dictionary: &Dictionary) -> bool {
+        self.term_count == dictionary.id_to_string.len()
+    }
+
+    /// Number of distinct trigrams indexed
+    pub fn trigram_count(&self) -> usize {
+        self.trigrams.len()
+    }
+
+    /// Ascending IDs of the terms that may match `pattern`; every matching
+    /// term is among them. `None` when the pattern has no literal text of at
+    /// least three characters to look up, so nothing can be pruned.
+    pub fn candidates(&self, pattern: &TextPattern) -> Option<Vec<u32>> {
+        let literals = match *pattern {
+            TextPattern::Contains(needle) => vec![needle.to_string()],
+            TextPattern::Regex(regex, flags) => required_literals(regex, flags),
+        };
+        let mut candidates: Option<Vec<u32>> = None;
+        for literal in &literals {
+            for trigram in trigrams_of(literal) {
+                let ids = self.trigrams.get(&trigram).map_or(&[][..], Vec::as_slice);
+                candidates = Some(match candidates {
+                    Some(previous) => intersect(&previous, ids),
+                    None => ids.to_vec(),
+                });
+            }
+        }
+        candidates
+    }
+}
+
+// Trigrams of `text` lowercased character by character, so that a substring's
+// trigrams are always among those of the text containing it
+fn trigrams_of(text: &str) -> Vec<[char; 3]> {
+    let chars: Vec<char> = text.chars().flat_map(char::to_lowercase).collect();
+    chars.windows(3).map(|window| [window[0], window[1], window[2]]).collect()
+}
+
+fn intersect(left: &[u32], right: &[u32]) -> Vec<u32> {
+    let (mut i, mut j) = (0, 0);
+    let mut both = Vec::new();
+    while i < left.len() && j < right.len() {
+        match left[i].cmp(&right[j]) {
+            std::cmp::Ordering::Less => i += 1,
+            std::cmp::Ordering::Greater => j += 1,
+            std::cmp::Ordering::Equal => {
+                both.push(left[i]);
+                i += 1;
+                j += 1;
+            }
+        }
+    }
+    both
+}
+
+/// Runs of plain characters every match of the regex `pattern` must contain.
+/// Anything that makes this unclear (alternation, inline flags, free-spacing
+/// mode, non-ASCII text under case-insensitive matching) yields no runs.
+fn required_literals(pattern: &str, flags: &str) -> Vec<String> {
+    if flags.contains('q') {
+        return vec![pattern.to_string()];
+    }
+    if flags.contains('x') || pattern.contains('|') || pattern.contains("(?") {
+        return Vec::new();
+    }
+    let case_insensitive = flags.contains('i');
+
+    let mut literals = Vec::new();
+    let mut run = String::new();
+    // Parenthesised groups may be optional or repeated as a whole
+    let mut depth = 0usize;
+    let mut chars = pattern.chars().peekable();
+    while let Some(c) = chars.next() {
+        let literal = match c {
+            '\\' => match chars.next() {
+                Some(escaped) if escaped.is_ascii_punctuation() => Some(escaped),
+                Some(class) => {
+                    // Skip the argument of `\x41`, `\u{263A}` or `\pL`
+                    let width = match class {
+                        'x' => 2,
+                        'u' => 4,
+                        'U' => 8,
+                        'p' | 'P' => 1,
+                        _ => 0,
+                    };
+                    if width > 0 && chars.peek() == Some(&'{') {
+                        chars.by_ref().find(|&close| close == '}');
+                    } else {
+                        chars.by_ref().take(width).for_each(drop);
+                    }
+                    None
+                }
+                None => None,
+            },
+            '(' => {
+                depth += 1;
+                None
+            }
+            ')' => {
+                depth = depth.saturating_sub(1);
+                None
+            }
+            '[' => {
+                // Skip the class; a `]` right after `[` or `[^` is a member
+                let mut first = true;
+                while let Some(member) = chars.next() {
+                    match member {
+                        '\\' => {
+                            chars.next();
+                        }
+                        '^' if first => continue,
+                        ']' if !first => break,
+                        _ => {}
+                    }
+                    first = false;
+                }
+                None
+            }
+            '?' | '*' | '{' => {
+                // The previous character may be absent
+                run.pop();
+                if c == '{' {
+                    chars.by_ref().find(|&close| close == '}');
+                }
+                None
+            }
+            '+' => {
+                // The previous character is present, but may repeat
+                literals.push(std::mem::take(&mut run));
+                None
+            }
+            '.' | '^' | '$' => None,
+            c if depth == 0 => Some(c),
+            _ => None,
+        };
+        match literal {
+            Some(c) if !case_insensitive || c.is_ascii() => run.push(c),
+            _ => literals.push(std::mem::take(&mut run)),
+        }
+    }
+    literals.push(run);
+    literals.retain(|literal| literal.chars().count() >= 3);
+    literals
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dictionary(terms: &[&str]) -> Dictionary {
+        let mut dictionary = Dictionary::new();
+        for term in terms {
+            dictionary.encode(term);
+        }
+        dictionary
+    }
+
+    #[test]
+    fn contains_candidates_include_every_match_in_any_case() {
+        let dict = dictionary(&["\"Alice\"", "\"Malice\"", "\"Bob\"", "http://example.org/ALICE"]);
+        let index = TextIndex::build(&dict);
+        let ids = |terms: &[&str]| -> Vec<u32> {
+            let mut ids: Vec<u32> = terms.iter().map(|term| dict.string_to_id[*term]).collect();
+            ids.sort_unstable();
+            ids
+        };
+
+        assert_eq!(
+            index.candidates(&TextPattern::Contains("lic")),
+            Some(ids(&["\"Alice\"", "\"Malice\"", "http://example.org/ALICE"]))
+        );
+        assert_eq!(index.candidates(&TextPattern::Contains("Bob")), Some(ids(&["\"Bob\""])));
+        assert_eq!(index.candidates(&TextPattern::Contains("xyz")), Some(Vec::new()));
+        assert_eq!(index.candidates(&TextPattern::Contains("li")), None);
+        assert!(index.covers(&dict));
+    }
+
+    #[test]
+    fn regex_literals_skip_optional_and_ambiguous_parts() {
+        assert_eq!(required_literals("^Ali(ce)?$", ""), vec!["Ali"]);
+        assert_eq!(required_literals("colou?r", ""), vec!["colo"]);
+        assert_eq!(required_literals("abc+def", ""), vec!["abc", "def"]);
+        assert_eq!(required_literals("[abc]hello\\.world", ""), vec!["hello.world"]);
+        assert_eq!(required_literals("a.b\\d{2}xyz", ""), vec!["xyz"]);
+        assert_eq!(required_literals("\\x41bcd\\pLxyz\\u{263A}", ""), vec!["bcd", "xyz"]);
+        assert_eq!(required_literals("smith|jones", ""), Vec::<String>::new());
+        assert_eq!(required_literals("(?i)smith", ""), Vec::<String>::new());
+        assert_eq!(required_literals("a+b", "q"), vec!["a+b"]);
+        assert_eq!(required_literals("Zoë", "i"), Vec::<String>::new());
+    }
+}
@@ -0,0 +1,48 @@
+/*
+ * Copyright © 2026 Volodymyr Kadzhaia
+ * Copyright © 2026 Pieter Bonte
+ * KU Leuven — Stream Intelligence Lab, Belgium
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this file,
+ * you can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+use crate::sparql_database::PARSE_PROGRESS_INTERVAL;
+
+/// Sizes used by bulk loading and the parallel joins, set with
+/// `SparqlDatabase::new_with_config`. The defaults suit most data; profile
+/// before changing them, as they trade memory against scheduling overhead
+/// rather than change any result.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TuningConfig {
+    /// Triples parsed from RDF/XML before they are handed over for insertion
+    /// as one batch (`parse_rdf`, `parse_rdf_from_file*`). Larger batches mean
+    /// fewer hand-offs on big files at the cost of holding more triples in
+    /// memory; with `parse_rdf_from_file_with_progress` it is also the number
+    /// of triples between two progress reports.
+    pub parse_batch_size: usize,
+    /// Smallest number of triples one rayon task scans in
+    /// `perform_join_par_simd_with_strict_filter_3` and `_4`; the stores are
+    /// otherwise split evenly over the threads. Raise it when many tiny
+    /// tasks dominate narrow joins, lower it to balance skewed wide ones.
+    pub join_chunk_size: usize,
+    /// Smallest initial capacity of the hash tables
+    /// `perform_join_par_simd_with_strict_filter_3` builds over the bindings it
+    /// joins with. Raising it avoids rehashing when many bindings share few
+    /// keys; lowering it saves memory on small joins.
+    pub join_hashmap_capacity: usize,
+    /// As `join_hashmap_capacity`, for `perform_join_par_simd_with_strict_filter_4`
+    pub folding_join_hashmap_capacity: usize,
+}
+
+impl Default for TuningConfig {
+    fn default() -> Self {
+        TuningConfig {
+            parse_batch_size: PARSE_PROGRESS_INTERVAL,
+            join_chunk_size: 1024,
+            join_hashmap_capacity: 4096,
+            folding_join_hashmap_capacity: 1024,
+        }
+    }
+}
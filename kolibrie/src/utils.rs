@@ -11,8 +11,18 @@
 use std::io::{Read, Write};
 use std::net::{TcpListener, TcpStream};
 use std::time::{SystemTime, UNIX_EPOCH};
+use std::collections::HashMap;
 use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering as AtomicOrdering};
 use std::fmt::{self, Debug};
+use std::cmp::Ordering;
+use std::cell::RefCell;
+use chrono::{DateTime, FixedOffset, NaiveDateTime};
+use percent_encoding::{utf8_percent_encode, AsciiSet, NON_ALPHANUMERIC};
+use rand::Rng;
+use regex::{Regex, RegexBuilder};
+use url::Url;
+use shared::query::{ArithmeticExpression, NumericFunction, StringExpression, StringFunction};
 use crate::sparql_database::SparqlDatabase;
 
 #[derive(Clone)]
@@ -38,6 +48,490 @@ impl Debug for ClonableFn {
     }
 }
 
+/// Find a registered UDF by the name used in a query. A prefixed name such as
+/// `ex:upper` also matches a UDF registered under its local part `upper`.
+pub fn lookup_udf<'a>(udfs: &'a HashMap<String, ClonableFn>, name: &str) -> Option<&'a ClonableFn> {
+    udfs.get(name).or_else(|| {
+        name.rsplit_once(':')
+            .and_then(|(_, local)| udfs.get(local))
+    })
+}
+
+/// Compare two FILTER operands, numerically when both sides parse as numbers.
+/// Typed literals such as `"true"^^xsd:boolean` compare by lexical form.
+pub fn compare_filter_values(left: &str, operator: &str, right: &str) -> bool {
+    if let Some(matched) = compare_datetime_values(left, operator, right) {
+        return matched;
+    }
+    let (left, right) = (untyped_form(left), untyped_form(right));
+    if let (Some(l), Some(r)) = (parse_numeric_literal(left), parse_numeric_literal(right)) {
+        return match operator {
+            "=" => l == r,
+            "!=" => l != r,
+            ">" => l > r,
+            ">=" => l >= r,
+            "<" => l < r,
+            "<=" => l <= r,
+            _ => false,
+        };
+    }
+    match operator {
+        "=" => left == right,
+        "!=" => left != right,
+        ">" => left > right,
+        ">=" => left >= right,
+        "<" => left < right,
+        "<=" => left <= right,
+        _ => false,
+    }
+}
+
+// `"true"^^xsd:boolean` becomes `true`; language-tagged literals are kept
+fn untyped_form(value: &str) -> &str {
+    match value.strip_prefix('"').and_then(|quoted| quoted.rsplit_once("\"^^")) {
+        Some((lexical, _)) => lexical,
+        None => value,
+    }
+}
+
+/// Parse a numeric value, accepting a quoted typed literal such as
+/// `"10"^^xsd:integer` as well as the bare lexical form.
+pub fn parse_numeric_literal(value: &str) -> Option<f64> {
+    let lexical = match value.strip_prefix('"') {
+        Some(quoted) => {
+            let (lexical, datatype) = quoted.split_once('"')?;
+            if !datatype.is_empty() && !datatype.starts_with("^^") {
+                return None;
+            }
+            lexical
+        }
+        None => value,
+    };
+    lexical.trim().parse::<f64>().ok()
+}
+
+/// Parse an `xsd:dateTime` value. Accepts the bare lexical form as well as a
+/// quoted literal typed `^^xsd:dateTime`; a value without a timezone is read
+/// as UTC.
+pub fn parse_xsd_datetime(value: &str) -> Option<DateTime<FixedOffset>> {
+    let lexical = match value.strip_prefix('"') {
+        Some(quoted) => {
+            let (lexical, datatype) = quoted.split_once('"')?;
+            if !datatype.is_empty()
+                && !matches!(datatype, "^^xsd:dateTime" | "^^<http://www.w3.org/2001/XMLSchema#dateTime>")
+            {
+                return None;
+            }
+            lexical
+        }
+        None => value,
+    };
+
+    // Cheap rejection for the numbers and IRIs most filters compare
+    let bytes = lexical.as_bytes();
+    if bytes.len() < 19 || bytes[4] != b'-' || bytes[10] != b'T' {
+        return None;
+    }
+    DateTime::parse_from_rfc3339(lexical).ok().or_else(|| {
+        NaiveDateTime::parse_from_str(lexical, "%Y-%m-%dT%H:%M:%S%.f")
+            .ok()
+            .map(|naive| naive.and_utc().fixed_offset())
+    })
+}
+
+/// Compare two `xsd:dateTime` values by the instant they denote, so
+/// `12:00:00+02:00` equals `10:00:00Z`. Returns `None` unless both sides are
+/// dateTimes, leaving other comparisons to the caller.
+pub fn compare_datetime_values(left: &str, operator: &str, right: &str) -> Option<bool> {
+    let ordering = parse_xsd_datetime(left)?.cmp(&parse_xsd_datetime(right)?);
+    Some(match operator {
+        "=" => ordering == Ordering::Equal,
+        "!=" => ordering != Ordering::Equal,
+        ">" => ordering == Ordering::Greater,
+        ">=" => ordering != Ordering::Less,
+        "<" => ordering == Ordering::Less,
+        "<=" => ordering != Ordering::Greater,
+        _ => false,
+    })
+}
+
+/// Characters `ENCODE_FOR_URI` leaves as-is: the RFC 3986 unreserved set
+const URI_UNRESERVED: &AsciiSet = &NON_ALPHANUMERIC.remove(b'-').remove(b'.').remove(b'_').remove(b'~');
+
+/// Evaluate a string function argument; `resolve` maps a variable (e.g.
+/// `?name`) to its bound value. `None` when a variable is unbound or a call
+/// has the wrong number of arguments.
+pub fn evaluate_string_expression<F: Fn(&str) -> Option<String>>(
+    expr: &StringExpression,
+    resolve: &F,
+) -> Option<String> {
+    match expr {
+        StringExpression::Variable(var) => resolve(var).map(|value| lexical_form(&value).to_string()),
+        StringExpression::Literal(literal) => Some(literal.to_string()),
+        StringExpression::Call(func, args) => evaluate_string_call(*func, args, resolve),
+    }
+}
+
+/// Evaluate a string function call on unevaluated arguments
+pub fn evaluate_string_call<F: Fn(&str) -> Option<String>>(
+    func: StringFunction,
+    args: &[StringExpression],
+    resolve: &F,
+) -> Option<String> {
+    // The tag belongs to the bound term, which the lexical form drops
+    if let (StringFunction::Lang, [StringExpression::Variable(var)]) = (func, args) {
+        return resolve(var).map(|value| language_tag(&value).to_string());
+    }
+    let values = args
+        .iter()
+        .map(|arg| evaluate_string_expression(arg, resolve))
+        .collect::<Option<Vec<String>>>()?;
+    evaluate_string_function(func, &values)
+}
+
+/// Apply a string function to evaluated arguments. Boolean results are
+/// `"true"`/`"false"` and lengths are plain integers; positions and lengths
+/// count characters, not bytes.
+pub fn evaluate_string_function(func: StringFunction, args: &[String]) -> Option<String> {
+    match (func, args) {
+        (StringFunction::Concat, _) => Some(args.concat()),
+        (StringFunction::Strlen, [s]) => Some(s.chars().count().to_string()),
+        (StringFunction::Substr, [s, start]) => substring(s, start, None),
+        (StringFunction::Substr, [s, start, length]) => substring(s, start, Some(length)),
+        (StringFunction::Ucase, [s]) => Some(s.to_uppercase()),
+        (StringFunction::Lcase, [s]) => Some(s.to_lowercase()),
+        (StringFunction::Contains, [s, needle]) => Some(s.contains(needle.as_str()).to_string()),
+        (StringFunction::StrStarts, [s, prefix]) => Some(s.starts_with(prefix.as_str()).to_string()),
+        (StringFunction::StrEnds, [s, suffix]) => Some(s.ends_with(suffix.as_str()).to_string()),
+        (StringFunction::EncodeForUri, [s]) => Some(utf8_percent_encode(s, URI_UNRESERVED).to_string()),
+        // Computed strings carry no language tag
+        (StringFunction::Lang, [_]) => Some(String::new()),
+        (StringFunction::LangMatches, [tag, range]) => Some(lang_matches(tag, range).to_string()),
+        (StringFunction::Regex, [s, pattern]) => regex_matches(s, pattern, "").map(|m| m.to_string()),
+        (StringFunction::Regex, [s, pattern, flags]) => regex_matches(s, pattern, flags).map(|m| m.to_string()),
+        _ => None,
+    }
+}
+
+thread_local! {
+    // Filters evaluate the same few patterns on every row
+    static COMPILED_REGEXES: RefCell<HashMap<(String, String), Option<Regex>>> = RefCell::new(HashMap::new());
+}
+
+/// Patterns kept compiled per thread before the cache is emptied
+const REGEX_CACHE_CAPACITY: usize = 64;
+
+/// Whether `s` matches the `REGEX` pattern under the SPARQL `flags` `i`, `s`,
+/// `m`, `x` and `q` (pattern taken literally); `None` for an invalid pattern or flag
+pub fn regex_matches(s: &str, pattern: &str, flags: &str) -> Option<bool> {
+    COMPILED_REGEXES.with(|cache| {
+        let mut cache = cache.borrow_mut();
+        if cache.len() >= REGEX_CACHE_CAPACITY {
+            cache.clear();
+        }
+        cache
+            .entry((pattern.to_string(), flags.to_string()))
+            .or_insert_with(|| compile_regex(pattern, flags))
+            .as_ref()
+            .map(|regex| regex.is_match(s))
+    })
+}
+
+fn compile_regex(pattern: &str, flags: &str) -> Option<Regex> {
+    let literal;
+    let pattern = if flags.contains('q') {
+        literal = regex::escape(pattern);
+        &literal
+    } else {
+        pattern
+    };
+    let mut builder = RegexBuilder::new(pattern);
+    for flag in flags.chars() {
+        match flag {
+            'i' => builder.case_insensitive(true),
+            's' => builder.dot_matches_new_line(true),
+            'm' => builder.multi_line(true),
+            'x' => builder.ignore_whitespace(true),
+            'q' => &mut builder,
+            _ => return None,
+        };
+    }
+    builder.build().ok()
+}
+
+// SUBSTR with a 1-based start; characters at positions start..start+length
+// are kept, so a start before 1 shortens the result
+fn substring(s: &str, start: &str, length: Option<&String>) -> Option<String> {
+    let start = start.trim().parse::<f64>().ok()?.round();
+    let end = match length {
+        Some(length) => start + length.trim().parse::<f64>().ok()?.round(),
+        None => f64::INFINITY,
+    };
+    Some(
+        s.chars()
+            .enumerate()
+            .filter(|(i, _)| {
+                let position = (*i + 1) as f64;
+                position >= start && position < end
+            })
+            .map(|(_, c)| c)
+            .collect(),
+    )
+}
+
+/// Evaluate a FILTER built on a string function: the function's effective
+/// boolean value, or its result compared against `value` when `comparison`
+/// is `Some((operator, value))`. Unbound variables make the filter false.
+pub fn evaluate_string_filter<F: Fn(&str) -> Option<String>>(
+    func: StringFunction,
+    args: &[StringExpression],
+    comparison: Option<(&str, &str)>,
+    resolve: &F,
+) -> bool {
+    evaluate_value_filter(evaluate_string_call(func, args, resolve), comparison, resolve)
+}
+
+// A FILTER on a computed value: its effective boolean value, or the value
+// compared against `comparison`; false when there is no value
+fn evaluate_value_filter<F: Fn(&str) -> Option<String>>(
+    output: Option<String>,
+    comparison: Option<(&str, &str)>,
+    resolve: &F,
+) -> bool {
+    let Some(output) = output else {
+        return false;
+    };
+    match comparison {
+        None => effective_boolean_value(&output),
+        Some((operator, value)) => match comparison_operand(value, resolve) {
+            Some(rhs) => compare_filter_values(&output, operator, &rhs),
+            None => false,
+        },
+    }
+}
+
+// The right-hand side of a function comparison: a literal as written, or the
+// lexical form of a bound variable
+fn comparison_operand<F: Fn(&str) -> Option<String>>(value: &str, resolve: &F) -> Option<String> {
+    if value.starts_with('?') {
+        resolve(value).map(|bound| lexical_form(&bound).to_string())
+    } else {
+        Some(value.to_string())
+    }
+}
+
+/// Apply a numeric function to evaluated arguments. `ROUND` rounds halves
+/// towards positive infinity, as SPARQL requires.
+pub fn evaluate_numeric_function(func: NumericFunction, args: &[f64]) -> Option<f64> {
+    let result = match (func, args) {
+        (NumericFunction::Abs, [x]) => x.abs(),
+        (NumericFunction::Ceil, [x]) => x.ceil(),
+        (NumericFunction::Floor, [x]) => x.floor(),
+        (NumericFunction::Round, [x]) => (x + 0.5).floor(),
+        (NumericFunction::Rand, []) => rand::rng().random::<f64>(),
+        _ => return None,
+    };
+    // Avoid printing "-0" for e.g. CEIL(-0.5)
+    Some(if result == 0.0 { 0.0 } else { result })
+}
+
+/// Evaluate a numeric function call; `resolve` maps a variable (e.g. `?x`) to
+/// its bound value. `None` when an argument is unbound or not a number.
+pub fn evaluate_numeric_call<F: Fn(&str) -> Option<String>>(
+    func: NumericFunction,
+    args: &[ArithmeticExpression],
+    resolve: &F,
+) -> Option<String> {
+    let numeric_resolve = |var: &str| resolve(var).and_then(|value| parse_numeric_literal(&value));
+    let values = args
+        .iter()
+        .map(|arg| arg.evaluate(&numeric_resolve).ok())
+        .collect::<Option<Vec<f64>>>()?;
+    evaluate_numeric_function(func, &values).map(|result| result.to_string())
+}
+
+/// Evaluate a FILTER comparing a numeric function's result against `value`.
+/// Unbound or non-numeric arguments make the filter false.
+pub fn evaluate_numeric_filter<F: Fn(&str) -> Option<String>>(
+    func: NumericFunction,
+    args: &[ArithmeticExpression],
+    operator: &str,
+    value: &str,
+    resolve: &F,
+) -> bool {
+    match (evaluate_numeric_call(func, args, resolve), comparison_operand(value, resolve)) {
+        (Some(output), Some(rhs)) => compare_filter_values(&output, operator, &rhs),
+        _ => false,
+    }
+}
+
+/// Evaluate `COALESCE`: the first argument that is bound and does not raise
+/// an error. Variables keep their language tag or datatype.
+pub fn evaluate_coalesce<F: Fn(&str) -> Option<String>>(
+    args: &[StringExpression],
+    resolve: &F,
+) -> Option<String> {
+    args.iter().find_map(|arg| match arg {
+        StringExpression::Variable(var) => resolve(var),
+        _ => evaluate_string_expression(arg, resolve),
+    })
+}
+
+/// Evaluate `IF`: `then` when `holds`, otherwise `otherwise`. Only the chosen
+/// branch is evaluated, so an unbound variable or failing call in the other
+/// one does not affect the result. Variables keep their language tag or datatype.
+pub fn evaluate_if<F: Fn(&str) -> Option<String>>(
+    holds: bool,
+    then: &StringExpression,
+    otherwise: &StringExpression,
+    resolve: &F,
+) -> Option<String> {
+    match if holds { then } else { otherwise } {
+        StringExpression::Variable(var) => resolve(var),
+        branch => evaluate_string_expression(branch, resolve),
+    }
+}
+
+/// Evaluate a FILTER built on `IF`: the chosen branch's effective boolean
+/// value, or its lexical form compared against `value` when `comparison` is
+/// `Some((operator, value))`
+pub fn evaluate_if_filter<F: Fn(&str) -> Option<String>>(
+    holds: bool,
+    then: &StringExpression,
+    otherwise: &StringExpression,
+    comparison: Option<(&str, &str)>,
+    resolve: &F,
+) -> bool {
+    let output = evaluate_if(holds, then, otherwise, resolve).map(|value| lexical_form(&value).to_string());
+    evaluate_value_filter(output, comparison, resolve)
+}
+
+/// Evaluate `IRI`: the lexical form of the argument when it is a well-formed
+/// IRI. There is no base IRI to resolve against, so relative ones are
+/// rejected; `None` for them, as SPARQL raises an error.
+pub fn evaluate_iri<F: Fn(&str) -> Option<String>>(arg: &StringExpression, resolve: &F) -> Option<String> {
+    evaluate_string_expression(arg, resolve).filter(|iri| is_well_formed_iri(iri))
+}
+
+/// Evaluate a FILTER built on `IRI`: whether the argument is a well-formed
+/// IRI, or that IRI compared against `value` when `comparison` is `Some((operator, value))`
+pub fn evaluate_iri_filter<F: Fn(&str) -> Option<String>>(
+    arg: &StringExpression,
+    comparison: Option<(&str, &str)>,
+    resolve: &F,
+) -> bool {
+    evaluate_value_filter(evaluate_iri(arg, resolve), comparison, resolve)
+}
+
+/// Whether `iri` is an absolute IRI: it has a scheme, parses, and contains
+/// none of the characters RFC 3987 excludes (spaces, controls, `<>"{}|^` and
+/// the backtick and backslash)
+pub fn is_well_formed_iri(iri: &str) -> bool {
+    !iri.chars().any(|c| c.is_whitespace() || c.is_control() || "<>\"{}|^`\\".contains(c))
+        && Url::parse(iri).is_ok()
+}
+
+/// A decoded dictionary term as an N-Triples (and Turtle) token: blank nodes
+/// (`_:b0`), quoted triples and quoted literals are already written that way,
+/// well-formed IRIs become `<iri>` and anything else a plain literal
+pub fn ntriples_term(term: &str) -> String {
+    if term.starts_with("<<") || term.starts_with("_:") || term.starts_with('"') {
+        return term.to_string();
+    }
+    if is_well_formed_iri(term) {
+        return format!("<{}>", term);
+    }
+
+    let mut literal = String::with_capacity(term.len() + 2);
+    literal.push('"');
+    for c in term.chars() {
+        match c {
+            '"' => literal.push_str("\\\""),
+            '\\' => literal.push_str("\\\\"),
+            '\n' => literal.push_str("\\n"),
+            '\r' => literal.push_str("\\r"),
+            '\t' => literal.push_str("\\t"),
+            c => literal.push(c),
+        }
+    }
+    literal.push('"');
+    literal
+}
+
+/// Effective boolean value of a function result: `true`/`false` as such,
+/// numbers are true unless zero, other strings unless empty
+pub fn effective_boolean_value(value: &str) -> bool {
+    match value {
+        "true" => true,
+        "false" => false,
+        _ => match value.parse::<f64>() {
+            Ok(number) => number != 0.0 && !number.is_nan(),
+            Err(_) => !value.is_empty(),
+        },
+    }
+}
+
+// `"chat"@fr` and `"7"^^xsd:integer` become `chat` and `7`
+fn lexical_form(value: &str) -> &str {
+    match value.strip_prefix('"').and_then(|quoted| quoted.rsplit_once('"')) {
+        Some((lexical, suffix))
+            if suffix.is_empty() || suffix.starts_with('@') || suffix.starts_with("^^") =>
+        {
+            lexical
+        }
+        _ => value,
+    }
+}
+
+/// Language tag of a literal stored as `"chat"@fr`; empty for other terms
+pub fn language_tag(value: &str) -> &str {
+    value
+        .strip_prefix('"')
+        .and_then(|quoted| quoted.rsplit_once('"'))
+        .and_then(|(_, suffix)| suffix.strip_prefix('@'))
+        .filter(|tag| !tag.is_empty() && tag.chars().all(|c| c.is_ascii_alphanumeric() || c == '-'))
+        .unwrap_or("")
+}
+
+/// `langMatches` by RFC 4647 basic filtering: `en` matches `en`, `en-US` and
+/// `EN-gb` but not `eng`; `*` matches any tag
+pub fn lang_matches(tag: &str, range: &str) -> bool {
+    if range == "*" {
+        return !tag.is_empty();
+    }
+    let tag = tag.to_ascii_lowercase();
+    let range = range.to_ascii_lowercase();
+    tag == range || tag.strip_prefix(range.as_str()).is_some_and(|rest| rest.starts_with('-'))
+}
+
+const XSD_NAMESPACE: &str = "http://www.w3.org/2001/XMLSchema#";
+
+/// Dictionary form of a typed literal: `"42"^^xsd:integer` is stored as
+/// `"42"^^<http://www.w3.org/2001/XMLSchema#integer>`, and an `xsd:string`
+/// literal as its bare lexical form. `expand` resolves a prefixed datatype
+/// name; `xsd:` falls back to the XML Schema namespace. `None` for terms that
+/// are not typed literals.
+pub fn canonical_typed_literal<F: Fn(&str) -> String>(value: &str, expand: F) -> Option<String> {
+    let (lexical, datatype) = value.strip_prefix('"')?.rsplit_once("\"^^")?;
+    if datatype.is_empty() {
+        return None;
+    }
+    let iri = match datatype.strip_prefix('<').and_then(|d| d.strip_suffix('>')) {
+        Some(iri) => iri.to_string(),
+        None => {
+            let expanded = expand(datatype);
+            match expanded.strip_prefix("xsd:") {
+                Some(local) => format!("{}{}", XSD_NAMESPACE, local),
+                None => expanded,
+            }
+        }
+    };
+    if iri.strip_prefix(XSD_NAMESPACE) == Some("string") {
+        return Some(lexical.to_string());
+    }
+    Some(format!("\"{}\"^^<{}>", lexical, iri))
+}
+
 // Basic HTTP server function
 pub fn run_server() {
     let listener = TcpListener::bind("127.0.0.1:7878").unwrap();
@@ -104,3 +598,28 @@ pub fn get_num_cpus() -> usize {
         1
     }
 }
+
+/// Thread count set with `set_num_threads`; 0 means one per CPU
+static NUM_THREADS: AtomicUsize = AtomicUsize::new(0);
+
+/// Cap the threads Kolibrie starts itself (the RDF/XML parser workers and
+/// the RSP window workers) at `threads`; 0 restores the default of
+/// `get_num_cpus`. Rayon work runs on the pool it is called from, so use
+/// `execute_query_in_pool` to bound a query's scans and joins as well.
+pub fn set_num_threads(threads: usize) {
+    NUM_THREADS.store(threads, AtomicOrdering::Relaxed);
+}
+
+/// Threads to start for parallel work: the `set_num_threads` value, or
+/// `get_num_cpus` when unset, and never more than the Rayon pool the caller
+/// runs on, so work started inside `execute_query_in_pool` stays within it.
+pub fn num_threads() -> usize {
+    let threads = match NUM_THREADS.load(AtomicOrdering::Relaxed) {
+        0 => get_num_cpus(),
+        threads => threads,
+    };
+    match rayon::current_thread_index() {
+        Some(_) => threads.min(rayon::current_num_threads()),
+        None => threads,
+    }
+}
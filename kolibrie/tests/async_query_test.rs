@@ -0,0 +1,143 @@
+/*
+ * Copyright © 2026 Volodymyr Kadzhaia
+ * Copyright © 2026 Pieter Bonte
+ * KU Leuven — Stream Intelligence Lab, Belgium
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this file,
+ * you can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+#![cfg(feature = "async")]
+
+extern crate kolibrie;
+use kolibrie::async_query::execute_query_async;
+use kolibrie::custom_error::SparqlError;
+use kolibrie::execute_query::execute_query_rayon_parallel2_volcano;
+use kolibrie::rsp_engine::{
+    OperationMode, QueryExecutionMode, RSPBuilder, RSPEngine, ResultConsumer, SimpleR2R,
+};
+use kolibrie::sparql_database::SparqlDatabase;
+use shared::triple::Triple;
+use std::sync::{Arc, Mutex, RwLock};
+
+fn runtime() -> tokio::runtime::Runtime {
+    tokio::runtime::Builder::new_current_thread()
+        .build()
+        .expect("Failed to build tokio runtime")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_execute_query_async_matches_sync() {
+        let mut db = SparqlDatabase::new();
+        db.add_triple_parts("http://example.org/alice", "http://example.org/age", "30");
+        db.add_triple_parts("http://example.org/bob", "http://example.org/age", "25");
+
+        let query = r#"PREFIX ex: <http://example.org/>
+SELECT ?person ?age
+WHERE {
+    ?person ex:age ?age .
+    FILTER(?age > 26)
+}"#;
+
+        let mut expected = execute_query_rayon_parallel2_volcano(query, &mut db.clone());
+        let shared_db = Arc::new(RwLock::new(db));
+        let mut results = runtime()
+            .block_on(execute_query_async(query.to_string(), shared_db.clone()))
+            .expect("query should succeed");
+
+        expected.sort();
+        results.sort();
+        assert_eq!(results, expected);
+        assert_eq!(results.len(), 1);
+
+        // The lock is released once the query completes
+        assert!(shared_db.try_write().is_ok());
+    }
+
+    #[test]
+    fn test_execute_query_async_reports_parse_errors() {
+        let shared_db = Arc::new(RwLock::new(SparqlDatabase::new()));
+        let result = runtime().block_on(execute_query_async("SELECT WHERE".to_string(), shared_db));
+        assert!(matches!(result, Err(SparqlError::ParseError(_))));
+    }
+
+    type WindowEngine = RSPEngine<Triple, Vec<(String, String)>>;
+    type WindowResults = Arc<Mutex<Vec<Vec<(String, String)>>>>;
+
+    fn window_engine(result_container: &WindowResults) -> WindowEngine {
+        let rc = Arc::clone(result_container);
+        let result_consumer = ResultConsumer {
+            function: Arc::new(move |r: Vec<(String, String)>| {
+                rc.lock().unwrap().push(r);
+            }),
+        };
+        let r2r = Box::new(SimpleR2R::with_execution_mode(QueryExecutionMode::Volcano));
+
+        let query = r#"
+            REGISTER RSTREAM <http://out/stream> AS
+            SELECT *
+            FROM NAMED WINDOW :w ON :stream1 [RANGE 4 STEP 2]
+            WHERE { WINDOW :w { ?s a <http://test/Type> . } }
+        "#;
+
+        RSPBuilder::new()
+            .add_rsp_ql_query(query)
+            .add_consumer(result_consumer)
+            .add_r2r(r2r)
+            .set_operation_mode(OperationMode::SingleThread)
+            .build()
+            .expect("Failed to build RSP engine")
+    }
+
+    async fn feed_events(engine: Arc<Mutex<WindowEngine>>) {
+        for i in 0..6 {
+            let data = format!("<http://test/s{}> a <http://test/Type> .", i);
+            let triples = engine.lock().unwrap().parse_data(&data);
+            for triple in triples {
+                RSPEngine::add_to_stream_async(Arc::clone(&engine), "stream1", triple, i).await;
+            }
+        }
+    }
+
+    fn assert_window_results(result_container: &Mutex<Vec<Vec<(String, String)>>>) {
+        let results = result_container.lock().unwrap();
+        assert!(
+            results.iter().any(|row| row.iter().any(|(k, _)| k == "s")),
+            "Expected window results bound to ?s, got: {:?}",
+            *results
+        );
+    }
+
+    #[test]
+    fn test_add_to_stream_async_feeds_windows() {
+        let result_container = Arc::new(Mutex::new(Vec::new()));
+        let engine = Arc::new(Mutex::new(window_engine(&result_container)));
+
+        runtime().block_on(feed_events(Arc::clone(&engine)));
+        engine.lock().unwrap().stop();
+
+        assert_window_results(&result_container);
+    }
+
+    #[test]
+    fn test_add_to_stream_async_on_a_multi_thread_runtime() {
+        let runtime = tokio::runtime::Builder::new_multi_thread()
+            .worker_threads(2)
+            .build()
+            .expect("Failed to build tokio runtime");
+        let result_container = Arc::new(Mutex::new(Vec::new()));
+        let engine = Arc::new(Mutex::new(window_engine(&result_container)));
+
+        // Fed from a spawned task, which the runtime may move between workers
+        let feeding = runtime.spawn(feed_events(Arc::clone(&engine)));
+        runtime.block_on(feeding).expect("feeding task should not panic");
+        engine.lock().unwrap().stop();
+
+        assert_window_results(&result_container);
+    }
+}
@@ -0,0 +1,161 @@
+/*
+ * Copyright © 2026 Volodymyr Kadzhaia
+ * Copyright © 2026 Pieter Bonte
+ * KU Leuven — Stream Intelligence Lab, Belgium
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this file,
+ * you can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+extern crate kolibrie;
+use kolibrie::execute_query::{execute_read_query, try_execute_query, QueryContext};
+use kolibrie::sparql_database::{SharedSparqlDatabase, SparqlDatabase};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use std::collections::HashSet;
+use std::sync::{Arc, RwLock};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
+
+const EX: &str = "http://example.org/";
+const READERS: u64 = 4;
+const WRITERS: u64 = 2;
+const SEED_SUBJECTS: usize = 200;
+const WRITER_SUBJECTS: u64 = 50;
+const OBJECTS: u64 = 5;
+const RUN_TIME: Duration = Duration::from_secs(10);
+// Time allowed past RUN_TIME for every thread to notice the deadline and stop
+const GRACE: Duration = Duration::from_secs(60);
+
+fn ex(local: &str) -> String {
+    format!("{}{}", EX, local)
+}
+
+fn setup_db() -> SparqlDatabase {
+    let mut db = SparqlDatabase::new();
+    for i in 0..SEED_SUBJECTS {
+        db.add_triple_parts(&ex(&format!("seed{}", i)), &ex("value"), &ex(&format!("o{}", i as u64 % OBJECTS)));
+    }
+    db.enable_query_cache();
+    db
+}
+
+/// Insertions and deletions a writer made that actually changed the store
+#[derive(Default)]
+struct WriteCounts {
+    insertions: usize,
+    deletions: usize,
+}
+
+// Each writer owns its own subjects, so it knows which of its triples are
+// stored and can tell whether an add or delete changes the store
+fn writer(db: SharedSparqlDatabase, id: u64, deadline: Instant) -> WriteCounts {
+    let mut rng = StdRng::seed_from_u64(id);
+    let mut stored = HashSet::new();
+    let mut counts = WriteCounts::default();
+    while Instant::now() < deadline {
+        let subject = ex(&format!("w{}/s{}", id, rng.random_range(0..WRITER_SUBJECTS)));
+        let object = ex(&format!("o{}", rng.random_range(0..OBJECTS)));
+        let mut db = db.write().unwrap();
+        if rng.random_bool(0.6) {
+            db.add_triple_parts(&subject, &ex("value"), &object);
+            if stored.insert((subject, object)) {
+                counts.insertions += 1;
+            }
+        } else {
+            let deleted = db.delete_triple_parts(&subject, &ex("value"), &object);
+            assert_eq!(deleted, stored.remove(&(subject, object)), "delete reported the wrong outcome");
+            if deleted {
+                counts.deletions += 1;
+            }
+        }
+    }
+    counts
+}
+
+fn reader(db: SharedSparqlDatabase, id: u64, deadline: Instant) -> usize {
+    let mut rng = StdRng::seed_from_u64(1000 + id);
+    let mut queries = 0;
+    while Instant::now() < deadline {
+        let object = ex(&format!("o{}", rng.random_range(0..OBJECTS)));
+        if rng.random_bool(0.5) {
+            let query = format!("PREFIX ex: <{}>\nSELECT ?s WHERE {{ ?s ex:value <{}> }}", EX, object);
+            let expected = |db: &SparqlDatabase| db.query().with_predicate(&ex("value")).with_object(&object).count();
+            // SELECT queries share the read lock once the database is prepared
+            // for them; otherwise the query prepares it under the write lock,
+            // and cached results must agree with the indexes
+            let read = db.read().unwrap();
+            if read.is_prepared_for_reads() {
+                let rows = execute_read_query(&query, &read, &QueryContext::default()).expect("query failed");
+                assert_eq!(rows.len(), expected(&read));
+            } else {
+                drop(read);
+                let mut db = db.write().unwrap();
+                let rows = try_execute_query(&query, &mut db).expect("query failed");
+                assert_eq!(rows.len(), expected(&db));
+                db.prepare_for_reads();
+            }
+        } else {
+            // Read-only lookups share the lock with each other
+            let db = db.read().unwrap();
+            let subjects = db.get_subjects_for_predicate_object(&ex("value"), &object);
+            assert_eq!(subjects.len(), db.query().with_predicate(&ex("value")).with_object(&object).count());
+            assert!(subjects.len() <= db.count_triples());
+        }
+        queries += 1;
+    }
+    queries
+}
+
+/// Join `handles`, failing if one is still running `timeout` from now
+fn join_all<T>(handles: Vec<JoinHandle<T>>, timeout: Duration) -> Vec<T> {
+    let give_up = Instant::now() + timeout;
+    while !handles.iter().all(JoinHandle::is_finished) {
+        assert!(Instant::now() < give_up, "worker threads did not finish; possible deadlock");
+        thread::sleep(Duration::from_millis(50));
+    }
+    handles
+        .into_iter()
+        .map(|handle| handle.join().unwrap_or_else(|panic| std::panic::resume_unwind(panic)))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_concurrent_reads_and_writes_keep_the_store_consistent() {
+        let db = Arc::new(RwLock::new(setup_db()));
+        let initial = db.read().unwrap().count_triples();
+        assert_eq!(initial, SEED_SUBJECTS);
+        let deadline = Instant::now() + RUN_TIME;
+
+        let writers: Vec<_> = (0..WRITERS)
+            .map(|id| {
+                let db = db.clone();
+                thread::spawn(move || writer(db, id, deadline))
+            })
+            .collect();
+        let readers: Vec<_> = (0..READERS)
+            .map(|id| {
+                let db = db.clone();
+                thread::spawn(move || reader(db, id, deadline))
+            })
+            .collect();
+
+        let counts = join_all(writers, RUN_TIME + GRACE);
+        let queries = join_all(readers, GRACE);
+        assert!(queries.iter().all(|&count| count > 0), "a reader never got the lock");
+
+        let insertions: usize = counts.iter().map(|counts| counts.insertions).sum();
+        let deletions: usize = counts.iter().map(|counts| counts.deletions).sum();
+        assert!(insertions > 0 && deletions > 0);
+
+        let mut db = db.write().unwrap();
+        assert_eq!(db.count_triples(), initial + insertions - deletions);
+        let all = format!("PREFIX ex: <{}>\nSELECT ?s ?o WHERE {{ ?s ex:value ?o }}", EX);
+        assert_eq!(try_execute_query(&all, &mut db).unwrap().len(), initial + insertions - deletions);
+    }
+}
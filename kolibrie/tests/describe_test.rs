@@ -0,0 +1,76 @@
+/*
+ * Copyright © 2026 Volodymyr Kadzhaia
+ * Copyright © 2026 Pieter Bonte
+ * KU Leuven — Stream Intelligence Lab, Belgium
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this file,
+ * you can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+extern crate kolibrie;
+use kolibrie::describe::ConciseBoundedDescription;
+use kolibrie::sparql_database::SparqlDatabase;
+
+fn described(db: &SparqlDatabase, resource: &str) -> Vec<String> {
+    let description = ConciseBoundedDescription::compute(resource, db);
+    let dict = description.dictionary.read().unwrap();
+    let mut triples: Vec<String> = description
+        .triples
+        .iter()
+        .map(|triple| dict.decode_triple(triple))
+        .collect();
+    triples.sort();
+    triples
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cbd_follows_blank_nodes_only() {
+        let mut db = SparqlDatabase::new();
+        db.parse_ntriples_and_add(
+            r#"<http://example.org/alice> <http://example.org/name> "Alice" .
+<http://example.org/alice> <http://example.org/knows> <http://example.org/bob> .
+<http://example.org/alice> <http://example.org/address> _:addr .
+_:addr <http://example.org/city> "Leuven" .
+_:addr <http://example.org/geo> _:point .
+_:point <http://example.org/lat> "50.88" .
+<http://example.org/bob> <http://example.org/name> "Bob" .
+"#,
+        );
+
+        let triples = described(&db, "http://example.org/alice");
+        assert_eq!(triples.len(), 6, "{:?}", triples);
+        assert!(triples.iter().any(|t| t.starts_with("_:point http://example.org/lat")));
+        // Bob is an IRI, so his own triples are not part of Alice's description
+        assert!(!triples.iter().any(|t| t.contains("Bob")));
+
+        // `<IRI>` and prefixed forms name the same resource
+        db.prefixes.insert("ex".to_string(), "http://example.org/".to_string());
+        assert_eq!(described(&db, "<http://example.org/alice>"), triples);
+        assert_eq!(described(&db, "ex:alice"), triples);
+
+        assert!(described(&db, "http://example.org/nobody").is_empty());
+    }
+
+    #[test]
+    fn test_cbd_terminates_on_blank_node_cycles() {
+        let mut db = SparqlDatabase::new();
+        db.parse_ntriples_and_add(
+            r#"<http://example.org/list> <http://example.org/first> _:a .
+_:a <http://example.org/next> _:b .
+_:b <http://example.org/next> _:a .
+_:b <http://example.org/value> "2" .
+"#,
+        );
+
+        let triples = described(&db, "http://example.org/list");
+        assert_eq!(triples.len(), 4, "{:?}", triples);
+
+        // A blank node can be described directly as well
+        assert_eq!(described(&db, "_:b").len(), 3);
+    }
+}
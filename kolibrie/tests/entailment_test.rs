@@ -0,0 +1,149 @@
+/*
+ * Copyright © 2026 Volodymyr Kadzhaia
+ * Copyright © 2026 Pieter Bonte
+ * KU Leuven — Stream Intelligence Lab, Belgium
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this file,
+ * you can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+extern crate kolibrie;
+use kolibrie::entailment::EntailmentRegime;
+use kolibrie::execute_query::execute_query_rayon_parallel2_volcano;
+use kolibrie::sparql_database::SparqlDatabase;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const TYPE_QUERY: &str = r#"
+        PREFIX rdf: <http://www.w3.org/1999/02/22-rdf-syntax-ns#>
+        PREFIX ex: <http://example.org/>
+        SELECT ?x
+        WHERE { ?x rdf:type ex:Person }
+    "#;
+
+    fn setup_db() -> SparqlDatabase {
+        let mut db = SparqlDatabase::new();
+        db.add_triple_parts(
+            "http://example.org/Student",
+            "http://www.w3.org/2000/01/rdf-schema#subClassOf",
+            "http://example.org/Person",
+        );
+        db.add_triple_parts(
+            "http://example.org/teaches",
+            "http://www.w3.org/2000/01/rdf-schema#domain",
+            "http://example.org/Person",
+        );
+        db.add_triple_parts(
+            "http://example.org/alice",
+            "http://www.w3.org/1999/02/22-rdf-syntax-ns#type",
+            "http://example.org/Student",
+        );
+        db.add_triple_parts(
+            "http://example.org/bob",
+            "http://example.org/teaches",
+            "http://example.org/course1",
+        );
+        db
+    }
+
+    fn subjects(rows: &[Vec<String>]) -> Vec<String> {
+        let mut subjects: Vec<String> = rows.iter().map(|row| row[0].clone()).collect();
+        subjects.sort();
+        subjects
+    }
+
+    #[test]
+    fn test_simple_regime_returns_asserted_answers_only() {
+        let mut db = setup_db();
+        let rows = execute_query_rayon_parallel2_volcano(TYPE_QUERY, &mut db);
+        assert!(rows.is_empty());
+        assert!(db.entailed_triples.is_empty());
+    }
+
+    #[test]
+    fn test_rdfs_regime_expands_subclass_and_domain() {
+        let mut db = setup_db();
+        db.set_entailment_regime(EntailmentRegime::Rdfs);
+
+        let rows = execute_query_rayon_parallel2_volcano(TYPE_QUERY, &mut db);
+        assert_eq!(
+            subjects(&rows),
+            vec!["http://example.org/alice", "http://example.org/bob"]
+        );
+    }
+
+    #[test]
+    fn test_entailment_follows_updates_and_regime_changes() {
+        let mut db = setup_db();
+        db.set_entailment_regime(EntailmentRegime::Rdfs);
+        execute_query_rayon_parallel2_volcano(TYPE_QUERY, &mut db);
+
+        db.delete_triple_parts(
+            "http://example.org/teaches",
+            "http://www.w3.org/2000/01/rdf-schema#domain",
+            "http://example.org/Person",
+        );
+        let rows = execute_query_rayon_parallel2_volcano(TYPE_QUERY, &mut db);
+        assert_eq!(subjects(&rows), vec!["http://example.org/alice"]);
+
+        db.set_entailment_regime(EntailmentRegime::Simple);
+        let rows = execute_query_rayon_parallel2_volcano(TYPE_QUERY, &mut db);
+        assert!(rows.is_empty());
+        assert!(db.entailed_triples.is_empty());
+        assert_eq!(db.triples.len(), 3);
+    }
+
+    #[test]
+    fn test_serializers_write_only_asserted_triples() {
+        let mut db = setup_db();
+        db.set_entailment_regime(EntailmentRegime::Rdfs);
+        execute_query_rayon_parallel2_volcano(TYPE_QUERY, &mut db);
+        assert!(!db.entailed_triples.is_empty());
+        assert_eq!(db.count_triples(), 4);
+
+        let mut copy = SparqlDatabase::new();
+        copy.parse_rdf(&db.generate_rdf_xml());
+        assert_eq!(copy.count_triples(), 4);
+
+        let mut copy = SparqlDatabase::new();
+        copy.parse_turtle(&db.generate_turtle());
+        assert_eq!(copy.count_triples(), 4);
+
+        let mut copy = SparqlDatabase::new();
+        copy.parse_ntriples_and_add(&db.generate_ntriples());
+        assert_eq!(copy.count_triples(), 4);
+
+        // The copy answers with the asserted data until its own regime entails more
+        let rows = execute_query_rayon_parallel2_volcano(TYPE_QUERY, &mut copy);
+        assert!(rows.is_empty());
+    }
+
+    #[test]
+    fn test_owl_rl_regime_applies_inverse_properties() {
+        let mut db = SparqlDatabase::new();
+        db.add_triple_parts(
+            "http://example.org/parentOf",
+            "http://www.w3.org/2002/07/owl#inverseOf",
+            "http://example.org/childOf",
+        );
+        db.add_triple_parts(
+            "http://example.org/carol",
+            "http://example.org/parentOf",
+            "http://example.org/dave",
+        );
+        db.set_entailment_regime(EntailmentRegime::OwlRl);
+
+        let rows = execute_query_rayon_parallel2_volcano(
+            r#"
+            PREFIX ex: <http://example.org/>
+            SELECT ?x
+            WHERE { ?x ex:childOf ex:carol }
+            "#,
+            &mut db,
+        );
+        assert_eq!(subjects(&rows), vec!["http://example.org/dave"]);
+    }
+}
@@ -9,9 +9,24 @@
  */
 
 extern crate kolibrie;
-use kolibrie::execute_query::execute_query_rayon_parallel2_volcano;
-use kolibrie::sparql_database::SparqlDatabase;
+use datalog::reasoning::materialisation::infer_generic::{BudgetExceeded, InferenceBudget};
+use kolibrie::custom_error::{CustomError, SparqlError};
+use kolibrie::execute_query::{
+    execute_query_profiled, execute_query_rayon_parallel2_volcano, execute_query_with_hint,
+    execute_query_with_timeout, execute_read_query, execute_shared_query, query_kind,
+    query_result_variables, try_execute_query, QueryContext, QueryKind,
+};
+use kolibrie::parser::{process_rule_definition, try_process_rule_definition};
+use kolibrie::sparql_database::{simd_eq, JoinPattern, SharedSparqlDatabase, SparqlDatabase, PARSE_PROGRESS_INTERVAL};
+use kolibrie::sparql_results::to_sparql_json;
+use kolibrie::streamertail_optimizer::{CancellationToken, JoinAlgorithm, QueryProfiler};
+use kolibrie::tuning::TuningConfig;
+use kolibrie::utils::compare_datetime_values;
+use shared::query::{Binding, FilterExpression};
 use shared::triple::Triple;
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
 
 #[cfg(test)]
 mod tests {
@@ -128,6 +143,86 @@ mod tests {
         assert_eq!(db.triples.len(), initial_count - 1);
     }
 
+    #[test]
+    fn test_diff_between_snapshots() {
+        let mut before = SparqlDatabase::new();
+        before.add_triple_parts("http://example.org/s1", "http://example.org/temp", "20");
+        before.add_triple_parts("http://example.org/s2", "http://example.org/temp", "21");
+
+        // A separately loaded snapshot has its own dictionary
+        let mut after = SparqlDatabase::new();
+        after.add_triple_parts("http://example.org/s3", "http://example.org/temp", "22");
+        after.add_triple_parts("http://example.org/s2", "http://example.org/temp", "21");
+
+        let (added, removed) = before.diff(&after);
+        let decode = |triples: &BTreeSet<Triple>| -> Vec<(String, String, String)> {
+            triples.iter().filter_map(|t| before.decode_triple(t)).collect()
+        };
+        let temp = |s: &str, o: &str| (format!("http://example.org/{}", s), "http://example.org/temp".to_string(), o.to_string());
+        assert_eq!(decode(&added), vec![temp("s3", "22")]);
+        assert_eq!(decode(&removed), vec![temp("s1", "20")]);
+
+        // A clone shares the dictionary, so its triples are compared as-is
+        let mut slid = before.clone();
+        assert!(slid.delete_triple_parts("http://example.org/s1", "http://example.org/temp", "20"));
+        slid.add_triple_parts("http://example.org/s4", "http://example.org/temp", "23");
+        let (added, removed) = before.diff(&slid);
+        assert_eq!(decode(&added), vec![temp("s4", "23")]);
+        assert_eq!(decode(&removed), vec![temp("s1", "20")]);
+        assert_eq!(before.diff(&before), (BTreeSet::new(), BTreeSet::new()));
+    }
+
+    #[test]
+    fn test_union_and_par_join_share_the_dictionary() {
+        let knows = "http://example.org/knows";
+        let mut left = SparqlDatabase::new();
+        left.add_triple_parts("http://example.org/alice", knows, "http://example.org/bob");
+        let mut right = SparqlDatabase::new();
+        right.add_triple_parts("http://example.org/bob", knows, "http://example.org/carol");
+
+        let mut union = left.union(&right);
+        assert!(Arc::ptr_eq(&union.dictionary, &left.dictionary));
+        assert_eq!(union.count_triples(), 2);
+        let mut rows = execute_query_rayon_parallel2_volcano(
+            "SELECT ?a ?b WHERE { ?a <http://example.org/knows> ?b }",
+            &mut union,
+        );
+        rows.sort();
+        assert_eq!(rows, vec![
+            vec!["http://example.org/alice".to_string(), "http://example.org/bob".to_string()],
+            vec!["http://example.org/bob".to_string(), "http://example.org/carol".to_string()],
+        ]);
+
+        let mut joined = union.par_join(&union, knows);
+        assert!(Arc::ptr_eq(&joined.dictionary, &left.dictionary));
+        let mut rows = execute_query_rayon_parallel2_volcano(
+            "SELECT ?b WHERE { <http://example.org/alice> <http://example.org/knows> ?b }",
+            &mut joined,
+        );
+        rows.sort();
+        assert_eq!(rows, vec![
+            vec!["http://example.org/bob".to_string()],
+            vec!["http://example.org/carol".to_string()],
+        ]);
+    }
+
+    #[test]
+    fn test_union_of_clones_keeps_their_ids() {
+        let knows = "http://example.org/knows";
+        let mut left = SparqlDatabase::new();
+        left.add_triple_parts("http://example.org/alice", knows, "http://example.org/bob");
+        // Clones share the dictionary, so their triples merge without re-encoding
+        let mut right = left.clone();
+        right.add_triple_parts("http://example.org/bob", knows, "http://example.org/carol");
+        assert!(left.shares_ids_with(&right));
+        assert!(!left.shares_ids_with(&SparqlDatabase::new()));
+
+        let terms_before = left.dictionary.read().unwrap().id_to_string.len();
+        let union = left.union(&right);
+        assert_eq!(union.triples, left.triples.union(&right.triples).cloned().collect());
+        assert_eq!(union.dictionary.read().unwrap().id_to_string.len(), terms_before);
+    }
+
     #[test]
     fn test_turtle_prefix_query_with_variable_predicate() {
         let mut db = SparqlDatabase::new();
@@ -153,6 +248,66 @@ mod tests {
         ]));
     }
 
+    #[test]
+    fn test_turtle_statements_spanning_lines_and_chunks() {
+        // Enough statements for several parse chunks; the prefix is only
+        // declared at the end
+        let mut turtle = String::new();
+        for i in 0..2500 {
+            turtle.push_str(&format!("ex:p{} ex:age {} ;\n    ex:knows ex:p{} .\n", i, i, i + 1));
+        }
+        turtle.push_str("@prefix ex: <http://example.org/> .\n");
+
+        let mut db = SparqlDatabase::new();
+        db.parse_turtle(&turtle);
+        assert_eq!(db.count_triples(), 5000);
+
+        let rows = execute_query_rayon_parallel2_volcano(r#"
+            PREFIX ex: <http://example.org/>
+            SELECT ?age ?friend
+            WHERE { ex:p1999 ex:age ?age . ex:p1999 ex:knows ?friend }
+        "#, &mut db);
+        assert_eq!(rows, vec![vec!["1999".to_string(), "http://example.org/p2000".to_string()]]);
+    }
+
+    #[test]
+    fn test_blank_nodes_are_written_as_labels() {
+        let turtle = r#"@prefix ex: <http://example.org/> .
+ex:alice ex:address _:b0 .
+_:b0 ex:city "Leuven" .
+"#;
+        let path = std::env::temp_dir().join(format!("kolibrie_blank_nodes_{}.ttl", std::process::id()));
+        std::fs::write(&path, turtle).unwrap();
+        let mut db = SparqlDatabase::new();
+        db.parse_turtle(&std::fs::read_to_string(&path).unwrap());
+        std::fs::remove_file(&path).unwrap();
+
+        let rows = execute_query_rayon_parallel2_volcano(r#"
+            PREFIX ex: <http://example.org/>
+            SELECT ?address ?city
+            WHERE {
+                ex:alice ex:address ?address .
+                ?address ex:city ?city .
+            }
+        "#, &mut db);
+        assert_eq!(rows, vec![vec!["_:b0".to_string(), "Leuven".to_string()]]);
+
+        // A blank node in the query is matched by its label
+        let rows = execute_query_rayon_parallel2_volcano(r#"
+            PREFIX ex: <http://example.org/>
+            SELECT ?city WHERE { _:b0 ex:city ?city . }
+        "#, &mut db);
+        assert_eq!(rows, vec![vec!["Leuven".to_string()]]);
+
+        let ntriples = db.generate_ntriples();
+        assert!(ntriples.contains("<http://example.org/alice> <http://example.org/address> _:b0 .\n"), "{}", ntriples);
+        assert!(ntriples.contains("_:b0 <http://example.org/city> \"Leuven\" .\n"), "{}", ntriples);
+        assert!(!ntriples.contains("<_:"), "{}", ntriples);
+        let turtle = db.generate_turtle();
+        assert!(turtle.contains("_:b0 <http://example.org/city> \"Leuven\" ."), "{}", turtle);
+        assert!(!turtle.contains("<_:"), "{}", turtle);
+    }
+
     #[test]
     fn test_turtle_semicolon_predicate_shorthand() {
         let mut db = SparqlDatabase::new();
@@ -269,6 +424,133 @@ mod tests {
         assert_eq!(numeric_objects.len(), 3);
     }
     
+    #[test]
+    fn test_exact_filters_read_the_indexes_when_current() {
+        let mut db = SparqlDatabase::new();
+        db.add_triple_parts("http://example.org/alice", "http://example.org/knows", "http://example.org/bob");
+        db.add_triple_parts("http://example.org/alice", "http://example.org/name", "Alice");
+        db.add_triple_parts("http://example.org/bob", "http://example.org/knows", "http://example.org/carol");
+        assert!(db.indexes_current());
+
+        let by_subject = db.query().with_subject("http://example.org/alice").get_objects();
+        assert_eq!(by_subject.len(), 2);
+        let by_predicate_object = db.query()
+            .with_predicate("http://example.org/knows")
+            .with_object("http://example.org/carol")
+            .get_subjects();
+        assert_eq!(by_predicate_object, vec!["http://example.org/bob".to_string()]);
+        // Exact and substring filters combine
+        let mixed = db.query()
+            .with_subject("http://example.org/alice")
+            .with_object_like("bo")
+            .get_objects();
+        assert_eq!(mixed, vec!["http://example.org/bob".to_string()]);
+        assert!(db.query().with_subject("http://example.org/nobody").get_triples().is_empty());
+
+        // A write that bypasses the indexes falls back to scanning
+        let mut dict = db.dictionary.write().unwrap();
+        let carol = dict.encode("http://example.org/carol");
+        let knows = dict.encode("http://example.org/knows");
+        let alice = dict.encode("http://example.org/alice");
+        drop(dict);
+        db.triples.insert(Triple { subject: carol, predicate: knows, object: alice });
+        assert!(!db.indexes_current());
+        assert_eq!(db.query().with_subject("http://example.org/carol").count(), 1);
+
+        db.build_all_indexes();
+        assert!(db.indexes_current());
+        assert_eq!(db.query().with_predicate("http://example.org/knows").count(), 3);
+    }
+
+    #[test]
+    fn test_text_index_narrows_substring_filters_without_changing_results() {
+        let mut db = SparqlDatabase::new();
+        db.parse_ntriples_and_add(r#"
+<http://example.org/alice> <http://example.org/name> "Alice" .
+<http://example.org/malice> <http://example.org/name> "Malice"@en .
+<http://example.org/bob> <http://example.org/name> "Bob" .
+<http://example.org/alice> <http://example.org/knows> <http://example.org/bob> .
+"#);
+        db.build_all_indexes();
+        let queries = [
+            r#"FILTER(CONTAINS(?name, "lic"))"#,
+            r#"FILTER(CONTAINS(?name, "li"))"#,
+            r#"FILTER(REGEX(?name, "ALI", "i"))"#,
+            r#"FILTER(REGEX(?name, "ice$"))"#,
+            r#"FILTER(REGEX(?name, "Bo?b"))"#,
+        ]
+        .map(|filter| format!(
+            "PREFIX ex: <http://example.org/>\nSELECT ?person ?name WHERE {{ ?person ex:name ?name . {} }}",
+            filter
+        ));
+        let run = |db: &mut SparqlDatabase| -> Vec<Vec<Vec<String>>> {
+            queries.iter().map(|query| {
+                let mut rows = try_execute_query(query, db).unwrap();
+                rows.sort();
+                rows
+            }).collect()
+        };
+        let scanned = run(&mut db);
+        assert_eq!(scanned.iter().map(Vec::len).collect::<Vec<_>>(), vec![2, 2, 2, 2, 1]);
+
+        db.enable_text_index();
+        assert!(db.text_index.as_ref().is_some_and(|index| index.trigram_count() > 0));
+        assert_eq!(run(&mut db), scanned);
+        assert_eq!(db.query().with_object_like("lic").count(), 2);
+        assert_eq!(db.query().with_subject_like("example.org/b").count(), 1);
+
+        // Terms added after the build are not indexed, so the filters scan again
+        db.add_triple_parts("http://example.org/alicia", "http://example.org/name", "Alicia");
+        let dict = db.dictionary.read().unwrap();
+        assert!(db.current_text_index(&dict).is_none());
+        drop(dict);
+        assert_eq!(run(&mut db)[0].len(), 3);
+        assert_eq!(db.query().with_object_like("lic").count(), 3);
+
+        db.build_all_indexes();
+        let dict = db.dictionary.read().unwrap();
+        assert!(db.current_text_index(&dict).is_some());
+        drop(dict);
+        assert_eq!(run(&mut db)[0].len(), 3);
+
+        db.disable_text_index();
+        assert!(db.text_index.is_none());
+    }
+
+    #[test]
+    fn test_sample_keeps_whole_subjects_and_only_their_terms() {
+        let mut db = SparqlDatabase::new();
+        for i in 0..200 {
+            let person = format!("http://example.org/person{}", i);
+            db.add_triple_parts(&person, "http://example.org/name", &format!("Person {}", i));
+            db.add_triple_parts(&person, "http://example.org/age", &(20 + i % 50).to_string());
+            db.add_triple_parts(&person, "http://example.org/knows", &format!("http://example.org/person{}", (i + 1) % 200));
+        }
+
+        let sampled = db.sample(60);
+        assert_eq!(sampled.count_triples(), 60);
+        let names = sampled.query().with_predicate("http://example.org/name").get_subjects();
+        assert_eq!(names.len(), 20);
+        for subject in &names {
+            assert_eq!(sampled.query().with_subject(subject).count(), 3, "{}", subject);
+        }
+
+        let dict = sampled.dictionary.read().unwrap();
+        let used: HashSet<u32> = sampled.triples.iter()
+            .flat_map(|triple| [triple.subject, triple.predicate, triple.object])
+            .collect();
+        assert_eq!(dict.id_to_string.len(), used.len());
+        drop(dict);
+
+        let rows = try_execute_query(
+            "PREFIX ex: <http://example.org/> SELECT ?person ?name WHERE { ?person ex:name ?name }",
+            &mut sampled.clone(),
+        ).unwrap();
+        assert_eq!(rows.len(), 20);
+
+        assert_eq!(db.sample(10_000).count_triples(), db.count_triples());
+    }
+
     #[test]
     fn test_combination_and_results() {
         let db = setup_test_db();
@@ -440,4 +722,1535 @@ mod tests {
         assert_eq!(dict.decode(young_subject_ids[0]).unwrap_or(""), "http://example.org/person2");
         drop(dict);
     }
+
+    #[test]
+    fn test_partitioned_store_matches_unpartitioned() {
+        let sparql = r#"
+            PREFIX ex: <http://example.org/>
+            SELECT ?person ?company ?industry
+            WHERE {
+                ?person ex:worksFor ?company .
+                ?company ex:industry ?industry
+            }
+        "#;
+
+        let mut db = SparqlDatabase::new();
+        for i in 0..200 {
+            db.add_triple_parts(
+                &format!("http://example.org/person{}", i),
+                "http://example.org/worksFor",
+                &format!("http://example.org/company{}", i % 7),
+            );
+        }
+        for c in 0..7 {
+            db.add_triple_parts(
+                &format!("http://example.org/company{}", c),
+                "http://example.org/industry",
+                &format!("industry{}", c % 3),
+            );
+        }
+
+        let mut expected = execute_query_rayon_parallel2_volcano(sparql, &mut db);
+        expected.sort();
+
+        db.enable_partitioning(4);
+        let mut partitioned = execute_query_rayon_parallel2_volcano(sparql, &mut db);
+        partitioned.sort();
+        assert_eq!(expected.len(), 200);
+        assert_eq!(partitioned, expected);
+
+        // Triples inserted behind the store's back are picked up when it is synced
+        let extra = {
+            let mut dict = db.dictionary.write().unwrap();
+            Triple {
+                subject: dict.encode("http://example.org/person999"),
+                predicate: dict.encode("http://example.org/worksFor"),
+                object: dict.encode("http://example.org/company0"),
+            }
+        };
+        db.triples.insert(extra.clone());
+        db.sync_partitions();
+        let store = db.partitioned_store.as_ref().unwrap();
+        assert_eq!(store.len(), db.triples.len());
+        assert!(store.contains(&extra));
+    }
+
+    #[test]
+    fn test_join_variants_agree() {
+        let mut db = SparqlDatabase::new();
+        for i in 0..50 {
+            db.add_triple_parts(
+                &format!("http://example.org/person{}", i),
+                "http://example.org/worksFor",
+                &format!("http://example.org/company{}", i % 5),
+            );
+        }
+        let mut dictionary = db.dictionary.read().unwrap().clone();
+        let triples: Vec<Triple> = db.triples.iter().cloned().collect();
+
+        // One result per way the pattern's variables can already be bound,
+        // plus one bound to a term no triple uses
+        let mut bind = |pairs: &[(&str, &str)]| Binding::encode(pairs.iter().copied(), &mut dictionary);
+        let final_results = vec![
+            bind(&[("?p", "http://example.org/person3"), ("?c", "http://example.org/company3")]),
+            bind(&[("?p", "http://example.org/person4")]),
+            bind(&[("?c", "http://example.org/company0")]),
+            bind(&[("?x", "unrelated")]),
+            bind(&[("?p", "http://example.org/nobody")]),
+        ];
+        let pattern = JoinPattern {
+            subject_var: "?p",
+            predicate: "http://example.org/worksFor".to_string(),
+            object_var: "?c",
+            literal_filter: None,
+        };
+
+        let mut expected = db.perform_join_sequential(
+            pattern.clone(), triples.clone(), &dictionary, final_results.clone(),
+        );
+        expected.sort();
+        assert_eq!(expected.len(), 1 + 1 + 10 + 50);
+        assert!(expected.iter().any(|result| {
+            result.get("?x", &dictionary) == Some("unrelated")
+                && result.get("?c", &dictionary) == Some("http://example.org/company0")
+        }));
+
+        let mut variants = [
+            db.perform_join_sequential_simd(pattern.clone(), triples.clone(), &dictionary, final_results.clone()),
+            db.perform_join_par_simd_with_strict_filter_1(pattern.clone(), triples.clone(), &db.dictionary, final_results.clone()),
+            db.perform_join_par_simd_with_strict_filter_2(pattern.clone(), triples.clone(), &dictionary, final_results.clone()),
+            db.perform_join_par_simd_with_strict_filter_3(pattern.clone(), triples.clone(), &dictionary, final_results.clone()),
+            db.perform_join_par_simd_with_strict_filter_4(pattern.clone(), triples.clone(), &dictionary, final_results.clone()),
+        ];
+        for results in variants.iter_mut() {
+            results.sort();
+            assert_eq!(*results, expected);
+        }
+
+        // Tiny chunks and hash tables split the work differently, not the results
+        let tuned = SparqlDatabase::new_with_config(TuningConfig {
+            join_chunk_size: 1,
+            join_hashmap_capacity: 1,
+            folding_join_hashmap_capacity: 1,
+            ..TuningConfig::default()
+        });
+        let mut variants = [
+            tuned.perform_join_par_simd_with_strict_filter_3(pattern.clone(), triples.clone(), &dictionary, final_results.clone()),
+            tuned.perform_join_par_simd_with_strict_filter_4(pattern.clone(), triples.clone(), &dictionary, final_results.clone()),
+        ];
+        for results in variants.iter_mut() {
+            results.sort();
+            assert_eq!(*results, expected);
+        }
+    }
+
+    #[test]
+    fn test_tuned_database_loads_and_joins_like_default() {
+        let mut rdf_xml = String::from(
+            r#"<?xml version="1.0"?>
+<rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#" xmlns:ex="http://example.org/">"#,
+        );
+        for i in 0..40 {
+            rdf_xml.push_str(&format!(
+                r#"<rdf:Description rdf:about="http://example.org/person{}"><ex:worksFor rdf:resource="http://example.org/company{}"/><ex:name>Person {}</ex:name></rdf:Description>"#,
+                i, i % 3, i
+            ));
+        }
+        rdf_xml.push_str("</rdf:RDF>");
+        let query = "PREFIX ex: <http://example.org/>
+SELECT ?name ?company WHERE { ?person ex:name ?name . ?person ex:worksFor ?company }";
+
+        let mut default_db = SparqlDatabase::new();
+        default_db.parse_rdf(&rdf_xml);
+        default_db.build_all_indexes();
+        // Batches and join chunks far smaller than the data
+        let mut tuned = SparqlDatabase::new_with_config(TuningConfig {
+            parse_batch_size: 7,
+            join_chunk_size: 3,
+            join_hashmap_capacity: 2,
+            folding_join_hashmap_capacity: 2,
+        });
+        tuned.parse_rdf(&rdf_xml);
+        tuned.build_all_indexes();
+        assert_eq!(tuned.triples.len(), 80);
+        assert_eq!(tuned.triples.len(), default_db.triples.len());
+
+        let mut expected = try_execute_query(query, &mut default_db).unwrap();
+        let mut results = try_execute_query(query, &mut tuned).unwrap();
+        expected.sort();
+        results.sort();
+        assert_eq!(results.len(), 40);
+        assert_eq!(results, expected);
+
+        // The chunked parallel joins over the loaded triples agree with the sequential one
+        let mut dictionary = tuned.dictionary.read().unwrap().clone();
+        let predicate = "http://example.org/worksFor".to_string();
+        let predicate_id = dictionary.string_to_id[&predicate];
+        let triples: Vec<Triple> = tuned.triples.iter().filter(|t| t.predicate == predicate_id).cloned().collect();
+        let final_results = vec![
+            Binding::encode([("?c", "http://example.org/company1")], &mut dictionary),
+            Binding::encode([("?p", "http://example.org/person7")], &mut dictionary),
+        ];
+        let pattern = JoinPattern { subject_var: "?p", predicate, object_var: "?c", literal_filter: None };
+        let mut expected = tuned.perform_join_sequential(
+            pattern.clone(), triples.clone(), &dictionary, final_results.clone(),
+        );
+        expected.sort();
+        assert_eq!(expected.len(), 14);
+        let mut variants = [
+            tuned.perform_join_par_simd_with_strict_filter_3(pattern.clone(), triples.clone(), &dictionary, final_results.clone()),
+            tuned.perform_join_par_simd_with_strict_filter_4(pattern.clone(), triples.clone(), &dictionary, final_results.clone()),
+        ];
+        for results in variants.iter_mut() {
+            results.sort();
+            assert_eq!(*results, expected);
+        }
+    }
+
+    #[test]
+    fn test_simd_eq_matches_slice_eq() {
+        // Lengths on both sides of the 16- and 64-byte vector widths, with a
+        // difference in the first, middle and last byte
+        let base: Vec<u8> = (0..300u32).map(|i| (i * 7 % 251) as u8).collect();
+        for len in 0..base.len() {
+            let a = &base[..len];
+            let copy = a.to_vec();
+            assert!(unsafe { simd_eq(a, &copy) }, "len {}", len);
+            for at in [0, len / 2, len.saturating_sub(1)] {
+                if at < len {
+                    let mut b = a.to_vec();
+                    b[at] ^= 1;
+                    assert!(!unsafe { simd_eq(a, &b) }, "len {} differing at {}", len, at);
+                }
+            }
+            if len > 0 {
+                assert!(!unsafe { simd_eq(a, &base[..len - 1]) });
+            }
+        }
+    }
+
+    #[test]
+    fn test_filter_compares_values_beyond_i32() {
+        let db = SparqlDatabase::new();
+        let rows: Vec<BTreeMap<&str, String>> = ["3000000000", "2999999999", "-3000000000", "42"]
+            .iter()
+            .map(|value| BTreeMap::from([("?v", value.to_string())]))
+            .collect();
+        let filter = |operator, value| {
+            let filters = vec![FilterExpression::Comparison("?v", operator, value)];
+            let mut kept: Vec<String> = db
+                .apply_filters_simd(rows.clone(), filters)
+                .into_iter()
+                .map(|row| row["?v"].clone())
+                .collect();
+            kept.sort();
+            kept
+        };
+
+        assert_eq!(filter(">", "2999999999"), vec!["3000000000"]);
+        assert_eq!(filter(">=", "2999999999"), vec!["2999999999", "3000000000"]);
+        assert_eq!(filter("<", "0"), vec!["-3000000000"]);
+        assert_eq!(filter("=", "3000000000"), vec!["3000000000"]);
+        assert_eq!(filter("!=", "3000000000"), vec!["-3000000000", "2999999999", "42"]);
+        assert_eq!(filter("<=", "42"), vec!["-3000000000", "42"]);
+    }
+
+    #[test]
+    fn test_udf_in_filter_comparison() {
+        let mut db = SparqlDatabase::new();
+        db.parse_turtle(r#"
+            @prefix ex: <http://example.org/> .
+            ex:alice ex:name "Alice" .
+            ex:bob ex:name "Bob" .
+        "#);
+        db.register_udf("upper", |args: Vec<&str>| args[0].to_uppercase());
+
+        let sparql = r#"
+            PREFIX ex: <http://example.org/>
+            PREFIX myns: <http://example.org/fn/>
+            SELECT ?person
+            WHERE {
+                ?person ex:name ?name .
+                FILTER(myns:upper(?name) = "ALICE")
+            }
+        "#;
+        let results = execute_query_rayon_parallel2_volcano(sparql, &mut db);
+        assert_eq!(results, vec![vec!["http://example.org/alice".to_string()]]);
+    }
+
+    #[test]
+    fn test_ntriples_streaming_matches_in_memory_parse() {
+        let mut data = String::from("# generated\n\n");
+        for i in 0..250 {
+            data.push_str(&format!(
+                "<http://example.org/s{}> <http://example.org/p{}> \"value {}\" .\n",
+                i,
+                i % 3,
+                i
+            ));
+        }
+
+        let mut in_memory = SparqlDatabase::new();
+        in_memory.parse_ntriples_and_add(&data);
+
+        let mut streamed = SparqlDatabase::new();
+        let mut reader = std::io::Cursor::new(data.as_bytes());
+        let parsed = streamed
+            .parse_ntriples_streaming_with_threshold(&mut reader, 16)
+            .expect("reading from memory cannot fail");
+
+        assert_eq!(parsed, 250);
+        assert_eq!(streamed.triples.len(), in_memory.triples.len());
+        let query = r#"SELECT ?s WHERE { ?s <http://example.org/p1> ?o }"#;
+        let mut expected = execute_query_rayon_parallel2_volcano(query, &mut in_memory);
+        let mut actual = execute_query_rayon_parallel2_volcano(query, &mut streamed);
+        expected.sort();
+        actual.sort();
+        assert_eq!(actual.len(), 83);
+        assert_eq!(actual, expected);
+    }
+
+    #[test]
+    fn test_subquery_aggregate_feeds_outer_filter() {
+        let mut db = SparqlDatabase::new();
+        db.parse_turtle(r#"
+            @prefix ex: <http://example.org/> .
+            ex:alice ex:name "Alice" ; ex:favourite ex:b9 .
+            ex:bob ex:name "Bob" ; ex:favourite ex:b9 .
+            ex:b1 ex:author ex:alice ; ex:pages "300" .
+            ex:b2 ex:author ex:alice ; ex:pages "400" .
+            ex:b3 ex:author ex:bob ; ex:pages "250" .
+            ex:b9 ex:pages "10" .
+        "#);
+
+        // ?book inside the subquery is not projected, so it must not join with
+        // the outer ?book
+        let sparql = r#"
+            PREFIX ex: <http://example.org/>
+            SELECT ?name ?total ?book
+            WHERE {
+                ?author ex:name ?name .
+                ?author ex:favourite ?book .
+                {
+                    SELECT ?author SUM(?pages) AS ?total
+                    WHERE {
+                        ?book ex:author ?author .
+                        ?book ex:pages ?pages
+                    }
+                    GROUPBY ?author
+                }
+                FILTER(?total > 500)
+            }
+        "#;
+        let results = execute_query_rayon_parallel2_volcano(sparql, &mut db);
+        assert_eq!(
+            results,
+            vec![vec!["Alice".to_string(), "700".to_string(), "http://example.org/b9".to_string()]]
+        );
+    }
+
+    #[test]
+    fn test_query_timeout_cancels_cartesian_product() {
+        let mut db = SparqlDatabase::new();
+        for i in 0..200 {
+            db.add_triple_parts(&format!("http://example.org/a{}", i), "http://example.org/p", "x");
+            db.add_triple_parts(&format!("http://example.org/b{}", i), "http://example.org/q", "y");
+        }
+
+        // ?a and ?b share no variable, so this is a 200 x 200 cross product
+        let sparql = r#"
+            PREFIX ex: <http://example.org/>
+            SELECT ?a ?b
+            WHERE {
+                ?a ex:p ?x .
+                ?b ex:q ?y
+            }
+        "#;
+
+        let err = execute_query_with_timeout(sparql, &mut db, Duration::ZERO).unwrap_err();
+        assert_eq!(err, CustomError::Timeout(Duration::ZERO));
+        assert!(db.cancellation.is_none());
+
+        let results = execute_query_with_timeout(sparql, &mut db, Duration::from_secs(60)).unwrap();
+        assert_eq!(results.len(), 200 * 200);
+    }
+
+    #[test]
+    fn test_read_queries_run_on_a_prepared_database() {
+        let mut db = SparqlDatabase::new();
+        for i in 0..20 {
+            db.add_triple_parts(&format!("http://example.org/p{}", i), "http://example.org/age", &(20 + i).to_string());
+        }
+        let select = "PREFIX ex: <http://example.org/>\nSELECT ?p ?age WHERE { ?p ex:age ?age . FILTER(?age > 30) }";
+        let insert = "INSERT { <http://example.org/q> <http://example.org/age> \"99\" } WHERE { }";
+        let all = "SELECT ?p ?age WHERE { ?p <http://example.org/age> ?age }";
+
+        // Nothing has gathered the optimizer statistics yet
+        assert!(!db.is_prepared_for_reads());
+        let err = execute_read_query(select, &db, &QueryContext::default()).unwrap_err();
+        assert!(matches!(err, SparqlError::ExecutionError(_)));
+        db.prepare_for_reads();
+        assert!(db.is_prepared_for_reads());
+
+        let mut expected = try_execute_query(select, &mut db.clone()).unwrap();
+        let mut results = execute_read_query(select, &db, &QueryContext::default()).unwrap();
+        expected.sort();
+        results.sort();
+        assert_eq!(results.len(), 9);
+        assert_eq!(results, expected);
+
+        // Queries are classified before they run
+        assert_eq!(query_kind(select, &db), Ok(QueryKind::Read));
+        assert_eq!(query_kind(insert, &db), Ok(QueryKind::Write));
+        assert!(matches!(query_kind("SELEC ?p WHERE { }", &db), Err(SparqlError::ParseError(_))));
+        let err = execute_read_query(insert, &db, &QueryContext::default()).unwrap_err();
+        assert!(matches!(err, SparqlError::UnsupportedFeature(_)));
+
+        // The context carries what the write path reads from the database
+        let profiler = Arc::new(QueryProfiler::new());
+        let context = QueryContext { profiler: Some(Arc::clone(&profiler)), ..QueryContext::default() };
+        assert_eq!(execute_read_query(select, &db, &context).unwrap().len(), 9);
+        assert!(!profiler.profiles().is_empty());
+        let context = QueryContext { timeout: Some(Duration::ZERO), ..QueryContext::default() };
+        assert_eq!(execute_read_query(select, &db, &context), Err(SparqlError::Timeout(Duration::ZERO)));
+
+        // Shared databases run updates under the write lock and stay prepared
+        let shared: SharedSparqlDatabase = Arc::new(RwLock::new(db));
+        assert!(execute_shared_query(insert, &shared, &QueryContext::default()).unwrap().is_empty());
+        assert!(shared.read().unwrap().is_prepared_for_reads());
+        assert_eq!(execute_shared_query(all, &shared, &QueryContext::default()).unwrap().len(), 21);
+    }
+
+    #[test]
+    fn test_oversized_cartesian_product_is_an_error() {
+        let mut db = SparqlDatabase::new();
+        for i in 0..20 {
+            db.add_triple_parts(&format!("http://example.org/a{}", i), "http://example.org/p", "x");
+            db.add_triple_parts(&format!("http://example.org/b{}", i), "http://example.org/q", "y");
+        }
+        let sparql = r#"
+            PREFIX ex: <http://example.org/>
+            SELECT ?a ?b
+            WHERE {
+                ?a ex:p ?x .
+                ?b ex:q ?y
+            }
+        "#;
+
+        db.max_join_results = 100;
+        let err = try_execute_query(sparql, &mut db).unwrap_err();
+        assert_eq!(
+            err,
+            SparqlError::CartesianProductTooLarge { left: 20, right: 20, limit: 100 }
+        );
+        // The deprecated-style entry point reports nothing rather than a truncated answer
+        assert!(execute_query_rayon_parallel2_volcano(sparql, &mut db).is_empty());
+
+        db.max_join_results = 400;
+        assert_eq!(try_execute_query(sparql, &mut db).unwrap().len(), 400);
+    }
+
+    #[test]
+    fn test_rule_inference_stops_at_the_budget() {
+        let mut db = SparqlDatabase::new();
+        for i in 0..64 {
+            db.add_triple_parts(
+                &format!("http://example.org/n{}", i),
+                "http://example.org/next",
+                &format!("http://example.org/n{}", i + 1),
+            );
+        }
+        let rule = r#"
+            PREFIX ex: <http://example.org/>
+            RULE :Reachable :-
+            CONSTRUCT { ?x ex:next ?z . }
+            WHERE { ?x ex:next ?y . ?y ex:next ?z . }
+        "#;
+
+        db.inference_budget = Some(InferenceBudget { max_rounds: 100, max_derived_facts: 500 });
+        let err = try_process_rule_definition(rule, &mut db).unwrap_err();
+        assert_eq!(err, SparqlError::InferenceBudgetExceeded(BudgetExceeded::DerivedFacts(500)));
+        assert_eq!(db.triples.len(), 64);
+
+        db.inference_budget = Some(InferenceBudget { max_rounds: 3, max_derived_facts: usize::MAX });
+        let err = process_rule_definition(rule, &mut db).unwrap_err();
+        assert!(err.contains("after the limit of 3 rounds"), "unexpected error: {}", err);
+        assert_eq!(db.triples.len(), 64);
+
+        // The transitive closure of 64 links holds 2080 pairs
+        db.inference_budget = Some(InferenceBudget::default());
+        let (_, inferred) = try_process_rule_definition(rule, &mut db).unwrap();
+        assert_eq!(inferred.len(), 2016);
+        assert_eq!(db.triples.len(), 2080);
+    }
+
+    #[test]
+    fn test_try_execute_query_reports_parse_errors() {
+        let mut db = setup_test_db();
+
+        let err = try_execute_query("SELECT ?s WHERE { ?s ?p", &mut db).unwrap_err();
+        match &err {
+            SparqlError::ParseError(message) => assert!(!message.is_empty()),
+            other => panic!("expected a parse error, got {:?}", other),
+        }
+        assert!(err.to_string().starts_with("Failed to parse the query"));
+
+        // A valid query that matches nothing is still a success
+        let results = try_execute_query(
+            "SELECT ?s WHERE { ?s <http://example.org/missing> ?o }",
+            &mut db,
+        )
+        .unwrap();
+        assert!(results.is_empty());
+    }
+
+    #[test]
+    fn test_datetime_filter_compares_instants() {
+        let mut db = SparqlDatabase::new();
+        db.add_triple_parts("http://example.org/e1", "http://example.org/at", "2023-12-31T23:00:00Z");
+        db.add_triple_parts("http://example.org/e2", "http://example.org/at", "2024-01-01T12:00:00+02:00");
+        db.add_triple_parts("http://example.org/e3", "http://example.org/at", "2024-03-15T08:30:00-05:00");
+
+        let mut query = |filter: &str| {
+            let sparql = format!(
+                "PREFIX ex: <http://example.org/>\nPREFIX xsd: <http://www.w3.org/2001/XMLSchema#>\nSELECT ?e WHERE {{ ?e ex:at ?t . FILTER({}) }}",
+                filter
+            );
+            let mut events: Vec<String> = execute_query_rayon_parallel2_volcano(&sparql, &mut db)
+                .into_iter()
+                .map(|row| row[0].clone())
+                .collect();
+            events.sort();
+            events
+        };
+
+        assert_eq!(
+            query(r#"?t > "2024-01-01T00:00:00Z"^^xsd:dateTime"#),
+            vec!["http://example.org/e2", "http://example.org/e3"]
+        );
+        // e2 sorts after the bound as text but is earlier as an instant
+        assert_eq!(
+            query(r#"?t < "2024-01-01T11:30:00+01:00"^^xsd:dateTime"#),
+            vec!["http://example.org/e1", "http://example.org/e2"]
+        );
+
+        // The same instant written with +00:00 and +02:00 offsets is equal
+        assert_eq!(
+            query(r#"?t = "2024-01-01T10:00:00+00:00"^^xsd:dateTime"#),
+            vec!["http://example.org/e2"]
+        );
+        assert!(compare_datetime_values("2024-01-01T10:00:00+00:00", "=", "2024-01-01T12:00:00+02:00").unwrap());
+        assert_eq!(compare_datetime_values("42", ">", "2024-01-01T00:00:00Z"), None);
+    }
+
+    #[test]
+    fn test_profiled_query_records_operator_rows() {
+        let mut db = SparqlDatabase::new();
+        for i in 0..10 {
+            let person = format!("http://example.org/person{}", i);
+            db.add_triple_parts(&person, "http://example.org/name", &format!("Person {}", i));
+            if i % 2 == 0 {
+                db.add_triple_parts(&person, "http://example.org/age", &(20 + i).to_string());
+            }
+        }
+        let sparql = r#"
+            PREFIX ex: <http://example.org/>
+            SELECT ?person ?name
+            WHERE {
+                ?person ex:name ?name .
+                ?person ex:age ?age
+            }
+        "#;
+
+        let (results, profiler) = execute_query_profiled(sparql, &mut db).unwrap();
+        assert!(db.profiler.is_none());
+
+        let profiles = profiler.profiles();
+        assert!(profiles.len() > 1);
+        let root = &profiles[0];
+        assert_eq!(root.depth, 0);
+        assert_eq!(root.invocations, 1);
+        assert_eq!(root.rows_out, results.len() as u64);
+        assert_eq!(results.len(), 5);
+
+        // Every executed operator's input is what its children produced
+        for (i, profile) in profiles.iter().enumerate() {
+            let children_rows: u64 = profiles[i + 1..]
+                .iter()
+                .take_while(|p| p.depth > profile.depth)
+                .filter(|p| p.depth == profile.depth + 1)
+                .map(|p| p.rows_out)
+                .sum();
+            if profile.invocations > 0 {
+                assert_eq!(profile.rows_in, children_rows, "{}", profile.operator);
+            }
+        }
+
+        let summary = profiler.summary();
+        assert!(summary.starts_with("operator"));
+        assert_eq!(summary.lines().count(), profiles.len() + 1);
+    }
+
+    #[test]
+    fn test_order_by_sorts_mixed_integers_and_decimals_numerically() {
+        let mut db = SparqlDatabase::new();
+        let integer_ten = "\"10\"^^<http://www.w3.org/2001/XMLSchema#integer>";
+        for (item, value) in [("a", integer_ten), ("b", "3"), ("c", "3.5"), ("d", "100")] {
+            db.add_triple_parts(&format!("http://example.org/{}", item), "http://example.org/value", value);
+        }
+
+        let mut sorted = |direction: &str| -> Vec<String> {
+            let sparql = format!(
+                "PREFIX ex: <http://example.org/>\nSELECT ?v WHERE {{ ?item ex:value ?v }} ORDER BY {}(?v)",
+                direction
+            );
+            execute_query_rayon_parallel2_volcano(&sparql, &mut db)
+                .into_iter()
+                .map(|row| row[0].clone())
+                .collect()
+        };
+
+        assert_eq!(sorted("ASC"), vec!["3", "3.5", integer_ten, "100"]);
+        assert_eq!(sorted("DESC"), vec!["100", integer_ten, "3.5", "3"]);
+    }
+
+    #[test]
+    fn test_string_functions_in_filter() {
+        let mut db = SparqlDatabase::new();
+        db.add_triple_parts("http://example.org/alice", "http://example.org/name", "Alice");
+        db.add_triple_parts("http://example.org/bjorn", "http://example.org/name", "Björn");
+        db.add_triple_parts("http://example.org/nandu", "http://example.org/name", "Ñandú");
+        db.add_triple_parts("http://example.org/anon", "http://example.org/name", "");
+
+        let mut query = |filter: &str| {
+            let sparql = format!(
+                "PREFIX ex: <http://example.org/>\nSELECT ?p WHERE {{ ?p ex:name ?name . FILTER({}) }}",
+                filter
+            );
+            let mut people: Vec<String> = execute_query_rayon_parallel2_volcano(&sparql, &mut db)
+                .into_iter()
+                .map(|row| row[0].trim_start_matches("http://example.org/").to_string())
+                .collect();
+            people.sort();
+            people
+        };
+
+        // Lengths and positions count characters, not UTF-8 bytes
+        assert_eq!(query("STRLEN(?name) = 5"), vec!["alice", "bjorn", "nandu"]);
+        assert_eq!(query("STRLEN(?name) = 0"), vec!["anon"]);
+        assert_eq!(query(r#"SUBSTR(?name, 2, 3) = "jör""#), vec!["bjorn"]);
+        assert_eq!(query(r#"SUBSTR(?name, 4) = "dú""#), vec!["nandu"]);
+
+        assert_eq!(query(r#"UCASE(?name) = "BJÖRN""#), vec!["bjorn"]);
+        assert_eq!(query(r#"CONTAINS(LCASE(?name), "ñ")"#), vec!["nandu"]);
+        assert_eq!(query(r#"STRSTARTS(?name, "A") || STRENDS(?name, "dú")"#), vec!["alice", "nandu"]);
+        assert_eq!(query(r#"CONCAT(?name, "!", "") = "Alice!""#), vec!["alice"]);
+        assert_eq!(query(r#"ENCODE_FOR_URI(?name) = "Bj%C3%B6rn""#), vec!["bjorn"]);
+
+        // Every string starts with and contains the empty string
+        assert_eq!(query(r#"STRSTARTS(?name, "")"#).len(), 4);
+        assert_eq!(query(r#"CONTAINS(?name, "")"#).len(), 4);
+        assert_eq!(query(r#"CONCAT(?name, "") = """#), vec!["anon"]);
+        // A missing argument makes the call an error, which filters the row out
+        assert!(query("STRLEN()").is_empty());
+    }
+
+    #[test]
+    fn test_language_tags() {
+        let mut db = SparqlDatabase::new();
+        db.parse_turtle(
+            "@prefix ex: <http://example.org/> .\n\
+             ex:us ex:label \"color\"@en-US .\n\
+             ex:gb ex:label \"colour\"@EN-gb .\n\
+             ex:fr ex:label \"couleur\"@fr .\n\
+             ex:plain ex:label \"colour\" .\n",
+        );
+        db.parse_ntriples_and_add("<http://example.org/en> <http://example.org/label> \"hue\"@en .\n");
+
+        let mut query = |filter: &str| {
+            let sparql = format!(
+                "PREFIX ex: <http://example.org/>\nSELECT ?s WHERE {{ ?s ex:label ?label . FILTER({}) }}",
+                filter
+            );
+            let mut subjects: Vec<String> = execute_query_rayon_parallel2_volcano(&sparql, &mut db)
+                .into_iter()
+                .map(|row| row[0].trim_start_matches("http://example.org/").to_string())
+                .collect();
+            subjects.sort();
+            subjects
+        };
+
+        assert_eq!(query(r#"LANG(?label) = "fr""#), vec!["fr"]);
+        assert_eq!(query(r#"LANG(?label) = """#), vec!["plain"]);
+        assert_eq!(query(r#"langMatches(lang(?label), "en")"#), vec!["en", "gb", "us"]);
+        assert_eq!(query(r#"langMatches(lang(?label), "en-us")"#), vec!["us"]);
+        assert_eq!(query(r#"langMatches(lang(?label), "*")"#), vec!["en", "fr", "gb", "us"]);
+        // The tag does not leak into the lexical form
+        assert_eq!(query(r#"STRLEN(?label) = 6"#), vec!["gb", "plain"]);
+
+        let tagged = execute_query_rayon_parallel2_volcano(
+            "SELECT ?s WHERE { ?s <http://example.org/label> \"couleur\"@fr }",
+            &mut db,
+        );
+        assert_eq!(tagged, vec![vec!["http://example.org/fr".to_string()]]);
+    }
+
+    #[test]
+    fn test_typed_literals_keep_their_datatype() {
+        let mut db = SparqlDatabase::new();
+        db.parse_turtle(
+            "@prefix ex: <http://example.org/> .\n\
+             @prefix xsd: <http://www.w3.org/2001/XMLSchema#> .\n\
+             ex:alice ex:name \"Alice\" .\n\
+             ex:alice ex:age \"42\"^^xsd:integer .\n\
+             ex:alice ex:active \"true\"^^xsd:boolean .\n",
+        );
+        db.parse_ntriples_and_add(
+            "<http://example.org/bob> <http://example.org/name> \"Bob\"^^<http://www.w3.org/2001/XMLSchema#string> .\n\
+             <http://example.org/bob> <http://example.org/age> \"7\"^^<http://www.w3.org/2001/XMLSchema#integer> .\n\
+             <http://example.org/bob> <http://example.org/active> \"false\"^^<http://www.w3.org/2001/XMLSchema#boolean> .\n",
+        );
+
+        let sparql = "PREFIX ex: <http://example.org/>\n\
+                      SELECT ?name ?age ?active WHERE { ?p ex:name ?name . ?p ex:age ?age . ?p ex:active ?active } ORDER BY ?name";
+        let rows = execute_query_rayon_parallel2_volcano(sparql, &mut db);
+        let json: serde_json::Value =
+            serde_json::from_str(&to_sparql_json(&query_result_variables(sparql), &rows)).unwrap();
+        let bindings = &json["results"]["bindings"];
+        assert_eq!(bindings.as_array().unwrap().len(), 2);
+
+        let alice = &bindings[0];
+        assert_eq!(alice["name"], serde_json::json!({ "type": "literal", "value": "Alice" }));
+        assert_eq!(alice["age"]["value"], "42");
+        assert_eq!(alice["age"]["datatype"], "http://www.w3.org/2001/XMLSchema#integer");
+        assert_eq!(alice["active"]["value"], "true");
+        assert_eq!(alice["active"]["datatype"], "http://www.w3.org/2001/XMLSchema#boolean");
+        // xsd:string is the datatype of a plain literal
+        assert!(bindings[1]["name"].get("datatype").is_none());
+        assert_eq!(bindings[1]["age"]["datatype"], "http://www.w3.org/2001/XMLSchema#integer");
+
+        let mut subjects = |query: &str| {
+            let sparql = format!(
+                "PREFIX ex: <http://example.org/>\nPREFIX xsd: <http://www.w3.org/2001/XMLSchema#>\n{}",
+                query
+            );
+            execute_query_rayon_parallel2_volcano(&sparql, &mut db)
+                .into_iter()
+                .map(|row| row[0].trim_start_matches("http://example.org/").to_string())
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(subjects("SELECT ?p WHERE { ?p ex:age \"42\"^^xsd:integer }"), vec!["alice"]);
+        assert_eq!(subjects("SELECT ?p WHERE { ?p ex:age ?age . FILTER(?age > 10) }"), vec!["alice"]);
+        assert_eq!(subjects("SELECT ?p WHERE { ?p ex:active ?a . FILTER(?a = \"false\"^^xsd:boolean) }"), vec!["bob"]);
+    }
+
+    #[test]
+    fn test_numeric_functions() {
+        let mut db = SparqlDatabase::new();
+        db.add_triple_parts("http://example.org/apple", "http://example.org/price", "2.5");
+        db.add_triple_parts("http://example.org/apple", "http://example.org/cost", "2.495");
+        db.add_triple_parts("http://example.org/pear", "http://example.org/price", "-2.5");
+        db.add_triple_parts("http://example.org/pear", "http://example.org/cost", "-1");
+        db.add_triple_parts(
+            "http://example.org/plum",
+            "http://example.org/price",
+            "\"3\"^^<http://www.w3.org/2001/XMLSchema#integer>",
+        );
+        db.add_triple_parts("http://example.org/plum", "http://example.org/cost", "7");
+
+        let mut query = |filter: &str| {
+            let sparql = format!(
+                "PREFIX ex: <http://example.org/>\nSELECT ?x WHERE {{ ?x ex:price ?a . ?x ex:cost ?b . FILTER({}) }}",
+                filter
+            );
+            let mut items: Vec<String> = execute_query_rayon_parallel2_volcano(&sparql, &mut db)
+                .into_iter()
+                .map(|row| row[0].trim_start_matches("http://example.org/").to_string())
+                .collect();
+            items.sort();
+            items
+        };
+
+        assert_eq!(query("ABS(?a - ?b) < 0.01"), vec!["apple"]);
+        assert_eq!(query("ABS(?a) = 2.5"), vec!["apple", "pear"]);
+        // Typed integer literals are read as numbers
+        assert_eq!(query("CEIL(?a) = 3"), vec!["apple", "plum"]);
+        assert_eq!(query("FLOOR(?a) = -3"), vec!["pear"]);
+        // ROUND rounds halves towards positive infinity
+        assert_eq!(query("ROUND(?a) = -2"), vec!["pear"]);
+        assert_eq!(query("RAND() >= 0 && RAND() < 1").len(), 3);
+
+        let sparql = "PREFIX ex: <http://example.org/>\nSELECT ?x (ROUND(?a) AS ?rounded) WHERE { ?x ex:price ?a }";
+        let mut rows: Vec<(String, String)> = execute_query_rayon_parallel2_volcano(sparql, &mut db)
+            .into_iter()
+            .map(|row| (row[0].trim_start_matches("http://example.org/").to_string(), row[1].clone()))
+            .collect();
+        rows.sort();
+        assert_eq!(
+            rows,
+            vec![
+                ("apple".to_string(), "3".to_string()),
+                ("pear".to_string(), "-2".to_string()),
+                ("plum".to_string(), "3".to_string()),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_coalesce() {
+        let mut db = SparqlDatabase::new();
+        db.parse_turtle(
+            "@prefix ex: <http://example.org/> .\n\
+             ex:alice ex:name \"Alice\"@en .\n\
+             ex:alice ex:nick \"Al\" .\n\
+             ex:bob ex:name \"Bob\" .\n",
+        );
+
+        let mut run = |sparql: &str| {
+            let mut rows: Vec<Vec<String>> = execute_query_rayon_parallel2_volcano(
+                &format!("PREFIX ex: <http://example.org/>\n{}", sparql),
+                &mut db,
+            )
+            .into_iter()
+            .map(|row| row.iter().map(|v| v.trim_start_matches("http://example.org/").to_string()).collect())
+            .collect();
+            rows.sort();
+            rows
+        };
+
+        // Unbound variables are skipped; the bound term keeps its language tag
+        assert_eq!(
+            run(r#"SELECT ?x (COALESCE(?missing, ?name, "anonymous") AS ?label) WHERE { ?x ex:name ?name }"#),
+            vec![vec!["alice".to_string(), "\"Alice\"@en".to_string()], vec!["bob".to_string(), "Bob".to_string()]]
+        );
+        assert_eq!(
+            run(r#"SELECT ?x (COALESCE(?missing, "anonymous") AS ?label) WHERE { ?x ex:nick ?nick }"#),
+            vec![vec!["alice".to_string(), "anonymous".to_string()]]
+        );
+        assert_eq!(
+            run(r#"SELECT ?x ?label WHERE { ?x ex:nick ?nick . BIND(COALESCE(?missing, ?nick) AS ?label) }"#),
+            vec![vec!["alice".to_string(), "Al".to_string()]]
+        );
+
+        // As a condition COALESCE is true when any argument is bound
+        assert_eq!(run("SELECT ?x WHERE { ?x ex:name ?name . FILTER(COALESCE(?missing, ?name)) }").len(), 2);
+        assert!(run("SELECT ?x WHERE { ?x ex:name ?name . FILTER(COALESCE(?missing)) }").is_empty());
+    }
+
+    #[test]
+    fn test_if_and_iri() {
+        let mut db = SparqlDatabase::new();
+        db.parse_turtle(
+            "@prefix ex: <http://example.org/> .\n\
+             ex:alice ex:age 30 .\n\
+             ex:alice ex:name \"alice\" .\n\
+             ex:alice ex:page \"http://example.org/home/alice\" .\n\
+             ex:bob ex:age 12 .\n\
+             ex:bob ex:name \"bob\" .\n\
+             ex:bob ex:page \"home of bob\" .\n",
+        );
+
+        let mut run = |sparql: &str| {
+            let mut rows: Vec<Vec<String>> = execute_query_rayon_parallel2_volcano(
+                &format!("PREFIX ex: <http://example.org/>\n{}", sparql),
+                &mut db,
+            )
+            .into_iter()
+            .map(|row| row.iter().map(|v| v.trim_start_matches("http://example.org/").to_string()).collect())
+            .collect();
+            rows.sort();
+            rows
+        };
+        let row = |values: &[&str]| values.iter().map(|v| v.to_string()).collect::<Vec<_>>();
+
+        assert_eq!(
+            run(r#"SELECT ?x (IF(?age >= 18, "adult", "minor") AS ?group) WHERE { ?x ex:age ?age }"#),
+            vec![row(&["alice", "adult"]), row(&["bob", "minor"])]
+        );
+        assert_eq!(
+            run(r#"SELECT ?x ?group WHERE { ?x ex:age ?age . BIND(IF(?age >= 18, "adult", ?name) AS ?group) }"#)
+                .len(),
+            2
+        );
+        // The branch not taken is never evaluated, so its errors do not matter
+        assert_eq!(
+            run(r#"SELECT ?group WHERE { ex:alice ex:age ?age . BIND(IF(?age < 18, ?missing, "adult") AS ?group) }"#),
+            vec![row(&["adult"])]
+        );
+        assert_eq!(
+            run(r#"SELECT ?x (IF(?age >= 18, ?name, SUBSTR(?missing, 1)) AS ?label) WHERE { ?x ex:age ?age . ?x ex:name ?name . FILTER(?age > 18) }"#),
+            vec![row(&["alice", "alice"])]
+        );
+        assert_eq!(
+            run(r#"SELECT ?x WHERE { ?x ex:age ?age . FILTER(IF(?age >= 18, "adult", "minor") = "minor") }"#),
+            vec![row(&["bob"])]
+        );
+
+        // IRI builds IRIs from strings and rejects malformed ones
+        assert_eq!(
+            run(r#"SELECT ?x ?iri WHERE { ?x ex:name ?name . BIND(IRI(CONCAT("http://example.org/people/", ?name)) AS ?iri) }"#),
+            vec![row(&["alice", "people/alice"]), row(&["bob", "people/bob"])]
+        );
+        assert_eq!(
+            run(r#"SELECT ?x (IRI(?page) AS ?iri) WHERE { ?x ex:page ?page . FILTER(IRI(?page)) }"#),
+            vec![row(&["alice", "home/alice"])]
+        );
+        assert_eq!(
+            run("SELECT ?x WHERE { ?x ex:page ?page . FILTER(IRI(?page) = <http://example.org/home/alice>) }"),
+            vec![row(&["alice"])]
+        );
+        assert!(run(r#"SELECT ?x WHERE { ?x ex:name ?name . FILTER(IRI(?name)) }"#).is_empty());
+    }
+
+    #[test]
+    fn test_select_star_matches_explicit_projection() {
+        let mut db = SparqlDatabase::new();
+        db.add_triple_parts("http://example.org/alice", "http://example.org/knows", "http://example.org/bob");
+        db.add_triple_parts("http://example.org/bob", "http://example.org/knows", "http://example.org/carol");
+        db.add_triple_parts("http://example.org/alice", "http://example.org/age", "30");
+
+        let mut run = |sparql: &str| {
+            let mut rows = execute_query_rayon_parallel2_volcano(sparql, &mut db);
+            rows.sort();
+            rows
+        };
+
+        let star = run("SELECT * WHERE { ?s ?p ?o }");
+        assert_eq!(star.len(), 3);
+        assert_eq!(star, run("SELECT ?s ?p ?o WHERE { ?s ?p ?o }"));
+        assert_eq!(query_result_variables("SELECT * WHERE { ?s ?p ?o }"), vec!["s", "p", "o"]);
+
+        // Constant terms are not projected
+        let star = run("PREFIX ex: <http://example.org/>\nSELECT * WHERE { ?s ex:knows ?o }");
+        assert_eq!(star, run("PREFIX ex: <http://example.org/>\nSELECT ?s ?o WHERE { ?s ex:knows ?o }"));
+        assert!(star.iter().all(|row| row.len() == 2));
+    }
+
+    #[test]
+    fn test_parse_rdf_from_compressed_files() {
+        use flate2::write::GzEncoder;
+        use kolibrie::compression::{read_rdf_file, Compression};
+        use std::io::Write;
+
+        let rdf_xml = r#"<?xml version="1.0"?>
+<rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#" xmlns:ex="http://example.org/">
+  <rdf:Description rdf:about="http://example.org/alice">
+    <ex:knows rdf:resource="http://example.org/bob"/>
+  </rdf:Description>
+</rdf:RDF>
+"#;
+        let mut gzip = GzEncoder::new(Vec::new(), flate2::Compression::default());
+        gzip.write_all(rdf_xml.as_bytes()).unwrap();
+        let files = [
+            ("rdf.gz", gzip.finish().unwrap()),
+            ("rdf.zst", zstd::encode_all(rdf_xml.as_bytes(), 0).unwrap()),
+            ("rdf", rdf_xml.as_bytes().to_vec()),
+        ];
+
+        for (extension, bytes) in files {
+            let path = std::env::temp_dir().join(format!(
+                "kolibrie_compressed_{}.{}",
+                std::process::id(),
+                extension
+            ));
+            std::fs::write(&path, bytes).unwrap();
+            assert_eq!(read_rdf_file(&path).unwrap(), rdf_xml);
+
+            let mut db = SparqlDatabase::new();
+            db.parse_rdf_from_file(path.to_str().unwrap());
+            std::fs::remove_file(&path).unwrap();
+            assert_eq!(db.triples.len(), 1, "{}", extension);
+        }
+
+        assert_eq!(Compression::from_path("dump.nt.GZ"), Compression::Gzip);
+        assert_eq!(Compression::from_path("dump.ttl.zst"), Compression::Zstd);
+        assert_eq!(Compression::from_path("dump.ttl"), Compression::None);
+    }
+
+    #[test]
+    fn test_execute_query_in_pool() {
+        use kolibrie::execute_query::execute_query_in_pool;
+        use kolibrie::utils::{num_threads, set_num_threads};
+
+        let pool = rayon::ThreadPoolBuilder::new().num_threads(2).build().unwrap();
+        let mut db = SparqlDatabase::new();
+        db.parse_ntriples_and_add(
+            "<http://example.org/alice> <http://example.org/name> \"Alice\" .\n\
+             <http://example.org/bob> <http://example.org/name> \"Bob\" .\n",
+        );
+        let query = "PREFIX ex: <http://example.org/>\nSELECT ?name WHERE { ?person ex:name ?name }";
+
+        let mut in_pool = execute_query_in_pool(query, &mut db, &pool).unwrap();
+        let mut global = try_execute_query(query, &mut db).unwrap();
+        in_pool.sort();
+        global.sort();
+        assert_eq!(in_pool.len(), 2);
+        assert_eq!(in_pool, global);
+
+        // Threads Kolibrie starts itself never exceed the surrounding pool
+        assert!(pool.install(num_threads) <= 2);
+        set_num_threads(1);
+        assert_eq!(num_threads(), 1);
+        set_num_threads(0);
+        assert_eq!(num_threads(), kolibrie::utils::get_num_cpus());
+    }
+
+    #[test]
+    fn test_write_rdf_xml_streams_one_description_per_subject() {
+        let mut db = SparqlDatabase::new();
+        db.prefixes.insert("ex".to_string(), "http://example.org/".to_string());
+        // Interleave subjects so grouping cannot rely on insertion order
+        db.add_triple_parts("ex:alice", "ex:name", "Alice");
+        db.add_triple_parts("ex:bob", "ex:name", "Bob");
+        db.add_triple_parts("ex:alice", "ex:age", "30");
+
+        let mut streamed = Vec::new();
+        db.write_rdf_xml(&mut streamed).unwrap();
+        let streamed = String::from_utf8(streamed).unwrap();
+
+        assert_eq!(streamed, db.generate_rdf_xml());
+        assert!(streamed.starts_with("<?xml version=\"1.0\"?>\n<rdf:RDF"));
+        assert!(streamed.ends_with("</rdf:RDF>\n"));
+        assert!(streamed.contains(" xmlns:ex=\"http://example.org/\""));
+        assert_eq!(streamed.matches("<rdf:Description ").count(), 2);
+        assert_eq!(streamed.matches("</rdf:Description>").count(), 2);
+        assert_eq!(streamed.matches("rdf:about=\"ex:alice\"").count(), 1);
+        assert!(streamed.contains("    <ex:age>30</ex:age>\n"));
+
+        let mut empty = Vec::new();
+        SparqlDatabase::new().write_rdf_xml(&mut empty).unwrap();
+        assert!(!String::from_utf8(empty).unwrap().contains("rdf:Description"));
+    }
+
+    #[test]
+    fn test_rdf_xml_round_trip_is_well_formed() {
+        use quick_xml::events::Event;
+        use quick_xml::Reader;
+
+        let decoded = |db: &SparqlDatabase| -> BTreeSet<(String, String, String)> {
+            let dict = db.dictionary.read().unwrap();
+            db.triples
+                .iter()
+                .map(|t| {
+                    (
+                        dict.decode(t.subject).unwrap().to_string(),
+                        dict.decode(t.predicate).unwrap().to_string(),
+                        dict.decode(t.object).unwrap().to_string(),
+                    )
+                })
+                .collect()
+        };
+
+        let mut db = SparqlDatabase::new();
+        db.parse_rdf(
+            r#"<?xml version="1.0"?>
+<rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#" xmlns:ex="http://example.org/">
+  <rdf:Description rdf:about="http://example.org/alice">
+    <ex:name>Alice &amp; Bob &lt;3</ex:name>
+    <ex:knows rdf:resource="http://example.org/bob?a=1&amp;b=2"/>
+    <rdf:type rdf:resource="http://example.org/Person"/>
+  </rdf:Description>
+</rdf:RDF>"#,
+        );
+        // Predicates outside every stored namespace need a generated one
+        db.add_triple_parts("http://example.org/alice", "http://other.org/vocab#score", "7");
+        db.add_triple_parts("http://example.org/alice", "urn:example:rating", "high");
+        let original = decoded(&db);
+        assert!(original.contains(&(
+            "http://example.org/alice".to_string(),
+            "http://example.org/name".to_string(),
+            "Alice & Bob <3".to_string()
+        )));
+
+        let xml = db.generate_rdf_xml();
+        assert!(!xml.contains("<http://"), "{}", xml);
+        assert!(xml.contains("<ex:name>Alice &amp; Bob &lt;3</ex:name>"), "{}", xml);
+        assert!(xml.contains("<rdf:type rdf:resource=\"http://example.org/Person\"/>"), "{}", xml);
+        assert_eq!(xml.matches("xmlns:rdf=").count(), 1, "{}", xml);
+
+        // Well-formed: every event reads cleanly and end tags match
+        let mut reader = Reader::from_str(&xml);
+        reader.config_mut().check_end_names = true;
+        let mut depth = 0i32;
+        loop {
+            match reader.read_event() {
+                Ok(Event::Start(_)) => depth += 1,
+                Ok(Event::End(_)) => depth -= 1,
+                Ok(Event::Eof) => break,
+                Ok(_) => {}
+                Err(e) => panic!("malformed RDF/XML: {:?}\n{}", e, xml),
+            }
+        }
+        assert_eq!(depth, 0);
+
+        let mut reparsed = SparqlDatabase::new();
+        reparsed.parse_rdf(&xml);
+        assert_eq!(decoded(&reparsed), original);
+    }
+
+    #[test]
+    fn test_rdf_xml_datatype_and_language_literals() {
+        let rdf_xml = r#"<?xml version="1.0"?>
+<rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#" xmlns:ex="http://example.org/">
+  <rdf:Description rdf:about="http://example.org/alice" xml:lang="fr">
+    <ex:age rdf:datatype="xsd:integer">42</ex:age>
+    <ex:height rdf:datatype="http://www.w3.org/2001/XMLSchema#decimal">1.70</ex:height>
+    <ex:nick rdf:datatype="http://www.w3.org/2001/XMLSchema#string">Al</ex:nick>
+    <ex:greeting xml:lang="en">Hello</ex:greeting>
+    <ex:motto>Bonjour</ex:motto>
+  </rdf:Description>
+  <rdf:Description rdf:about="http://example.org/bob">
+    <ex:motto>Hi</ex:motto>
+  </rdf:Description>
+</rdf:RDF>
+"#;
+        let objects = |db: &SparqlDatabase| -> BTreeSet<String> {
+            let dict = db.dictionary.read().unwrap();
+            db.triples.iter().map(|t| dict.decode(t.object).unwrap().to_string()).collect()
+        };
+        let expected: BTreeSet<String> = [
+            "\"42\"^^<http://www.w3.org/2001/XMLSchema#integer>",
+            "\"1.70\"^^<http://www.w3.org/2001/XMLSchema#decimal>",
+            "Al",
+            "\"Hello\"@en",
+            "\"Bonjour\"@fr",
+            "Hi",
+        ]
+        .iter()
+        .map(|o| o.to_string())
+        .collect();
+
+        let mut db = SparqlDatabase::new();
+        db.parse_rdf(rdf_xml);
+        assert_eq!(objects(&db), expected);
+
+        let path = std::env::temp_dir().join(format!("kolibrie_literals_{}.rdf", std::process::id()));
+        std::fs::write(&path, rdf_xml).unwrap();
+        let mut from_file = SparqlDatabase::new();
+        from_file.parse_rdf_from_file(path.to_str().unwrap());
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(objects(&from_file), expected);
+
+        // The datatype and language survive a write and re-parse
+        let xml = db.generate_rdf_xml();
+        assert!(
+            xml.contains("<ex:age rdf:datatype=\"http://www.w3.org/2001/XMLSchema#integer\">42</ex:age>"),
+            "{}",
+            xml
+        );
+        assert!(xml.contains("<ex:greeting xml:lang=\"en\">Hello</ex:greeting>"), "{}", xml);
+        let mut reparsed = SparqlDatabase::new();
+        reparsed.parse_rdf(&xml);
+        assert_eq!(objects(&reparsed), expected);
+    }
+
+    #[test]
+    fn test_select_distinct_removes_duplicate_rows() {
+        let mut db = SparqlDatabase::new();
+        db.parse_ntriples_and_add(
+            "<http://example.org/alice> <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> <http://example.org/Person> .\n\
+             <http://example.org/bob> <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> <http://example.org/Person> .\n\
+             <http://example.org/acme> <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> <http://example.org/Company> .\n\
+             <http://example.org/alice> <http://example.org/name> \"Alice\" .\n\
+             <http://example.org/bob> <http://example.org/name> \"Alice\" .\n",
+        );
+
+        let all = try_execute_query("SELECT ?type WHERE { ?s a ?type }", &mut db).unwrap();
+        assert_eq!(all.len(), 3);
+
+        let query = "SELECT DISTINCT ?type WHERE { ?s a ?type }";
+        let mut types = try_execute_query(query, &mut db).unwrap();
+        types.sort();
+        assert_eq!(
+            types,
+            vec![vec!["http://example.org/Company".to_string()], vec!["http://example.org/Person".to_string()]]
+        );
+
+        // Rows are distinct over every selected variable, not just the first
+        let pairs = "PREFIX ex: <http://example.org/>\nSELECT DISTINCT ?type ?name WHERE { ?s a ?type . ?s ex:name ?name }";
+        assert_eq!(try_execute_query(pairs, &mut db).unwrap().len(), 1);
+        let people = "PREFIX ex: <http://example.org/>\nSELECT DISTINCT ?s ?name WHERE { ?s a ?type . ?s ex:name ?name }";
+        assert_eq!(try_execute_query(people, &mut db).unwrap().len(), 2);
+
+        // Deduplicated before LIMIT, so the limit counts distinct rows
+        let limited = "SELECT DISTINCT ?type WHERE { ?s a ?type } LIMIT 2";
+        assert_eq!(try_execute_query(limited, &mut db).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_index_backed_subject_and_object_lookups() {
+        let mut db = SparqlDatabase::new();
+        db.parse_ntriples_and_add(
+            "<http://example.org/alice> <http://example.org/worksAt> <http://example.org/acme> .\n\
+             <http://example.org/bob> <http://example.org/worksAt> <http://example.org/acme> .\n\
+             <http://example.org/carol> <http://example.org/worksAt> <http://example.org/initech> .\n\
+             <http://example.org/alice> <http://example.org/knows> <http://example.org/bob> .\n\
+             <http://example.org/alice> <http://example.org/knows> <http://example.org/carol> .\n\
+             <http://example.org/alice> <http://example.org/name> \"Alice\" .\n",
+        );
+        db.prefixes.insert("ex".to_string(), "http://example.org/".to_string());
+
+        assert_eq!(
+            db.get_subjects_for_predicate_object("http://example.org/worksAt", "http://example.org/acme"),
+            vec!["http://example.org/alice", "http://example.org/bob"]
+        );
+        // Prefixed names and <...> IRIs are resolved as in a query
+        assert_eq!(
+            db.get_subjects_for_predicate_object("ex:worksAt", "<http://example.org/initech>"),
+            vec!["http://example.org/carol"]
+        );
+        assert_eq!(db.get_subjects_for_predicate_object("ex:name", "\"Alice\""), vec!["http://example.org/alice"]);
+        assert_eq!(
+            db.get_objects_for_subject_predicate("ex:alice", "ex:knows"),
+            vec!["http://example.org/bob", "http://example.org/carol"]
+        );
+        assert_eq!(
+            db.get_subjects_for_predicate("ex:worksAt"),
+            vec!["http://example.org/alice", "http://example.org/bob", "http://example.org/carol"]
+        );
+
+        // Unknown terms match nothing instead of being added to the dictionary
+        assert!(db.get_subjects_for_predicate_object("ex:worksAt", "ex:nowhere").is_empty());
+        assert!(db.get_objects_for_subject_predicate("ex:dave", "ex:knows").is_empty());
+        assert!(db.get_subjects_for_predicate("ex:manages").is_empty());
+        assert!(!db.dictionary.read().unwrap().string_to_id.contains_key("http://example.org/nowhere"));
+    }
+
+    #[test]
+    fn test_rdf_xml_node_ids_and_nested_descriptions() {
+        let rdf_xml = r#"<?xml version="1.0"?>
+<rdf:RDF xmlns:rdf="http://www.w3.org/1999/02/22-rdf-syntax-ns#" xmlns:ex="http://example.org/">
+  <rdf:Description rdf:about="http://example.org/alice">
+    <ex:name>Alice</ex:name>
+    <ex:address>
+      <rdf:Description>
+        <ex:city>Leuven</ex:city>
+        <ex:country>
+          <rdf:Description rdf:about="http://example.org/belgium">
+            <ex:label xml:lang="nl">België</ex:label>
+          </rdf:Description>
+        </ex:country>
+      </rdf:Description>
+    </ex:address>
+    <ex:knows rdf:nodeID="friend"/>
+  </rdf:Description>
+  <rdf:Description rdf:nodeID="friend">
+    <ex:name>Bob</ex:name>
+  </rdf:Description>
+</rdf:RDF>
+"#;
+        let mut db = SparqlDatabase::new();
+        db.parse_rdf(rdf_xml);
+        let triples: Vec<(String, String, String)> = {
+            let dict = db.dictionary.read().unwrap();
+            db.triples
+                .iter()
+                .map(|t| {
+                    (
+                        dict.decode(t.subject).unwrap().to_string(),
+                        dict.decode(t.predicate).unwrap().to_string(),
+                        dict.decode(t.object).unwrap().to_string(),
+                    )
+                })
+                .collect()
+        };
+        assert_eq!(triples.len(), 7, "{:?}", triples);
+        let object_of = |subject: &str, predicate: &str| -> String {
+            let predicate = format!("http://example.org/{}", predicate);
+            triples
+                .iter()
+                .find(|(s, p, _)| s == subject && *p == predicate)
+                .map(|(_, _, o)| o.clone())
+                .unwrap_or_else(|| panic!("no {} of {} in {:?}", predicate, subject, triples))
+        };
+
+        // The nested description is a blank node linked through ex:address
+        let address = object_of("http://example.org/alice", "address");
+        assert!(address.starts_with("_:"), "{}", address);
+        assert_eq!(object_of(&address, "city"), "Leuven");
+        assert_eq!(object_of(&address, "country"), "http://example.org/belgium");
+        assert_eq!(object_of("http://example.org/belgium", "label"), "\"België\"@nl");
+
+        // rdf:nodeID names the same blank node wherever it appears
+        assert_eq!(object_of("http://example.org/alice", "knows"), "_:friend");
+        assert_eq!(object_of("_:friend", "name"), "Bob");
+
+        // The file parser builds the same graph
+        let path = std::env::temp_dir().join(format!("kolibrie_nested_{}.rdf", std::process::id()));
+        std::fs::write(&path, rdf_xml).unwrap();
+        let mut from_file = SparqlDatabase::new();
+        from_file.parse_rdf_from_file(path.to_str().unwrap());
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(from_file.triples.len(), 7);
+
+        // A second document gets its own anonymous nodes
+        db.parse_rdf(rdf_xml);
+        assert_eq!(db.triples.len(), 10);
+    }
+
+    #[test]
+    fn test_equality_filter_against_constant_terms() {
+        let mut db = SparqlDatabase::new();
+        db.parse_ntriples_and_add(
+            "<http://example.org/alice> <http://example.org/name> \"Alice\" .\n\
+             <http://example.org/bob> <http://example.org/name> \"Bob\" .\n\
+             <http://example.org/carol> <http://example.org/name> \"Alice\"@en .\n\
+             <http://example.org/dave> <http://example.org/name> \"Alice\"^^<http://example.org/Nickname> .\n",
+        );
+
+        let mut subjects = |filter: &str| {
+            let sparql = format!(
+                "PREFIX ex: <http://example.org/>\nSELECT ?p WHERE {{ ?p ex:name ?name . FILTER({}) }}",
+                filter
+            );
+            let mut found: Vec<String> = execute_query_rayon_parallel2_volcano(&sparql, &mut db)
+                .into_iter()
+                .map(|row| row[0].trim_start_matches("http://example.org/").to_string())
+                .collect();
+            found.sort();
+            found
+        };
+
+        // A typed literal matches by its lexical form; a language-tagged one does not
+        assert_eq!(subjects("?name = \"Alice\""), vec!["alice", "dave"]);
+        assert_eq!(subjects("?name != \"Alice\""), vec!["bob", "carol"]);
+        assert!(subjects("?name = \"Zed\"").is_empty());
+        assert_eq!(subjects("?name != \"Zed\"").len(), 4);
+    }
+
+    #[test]
+    fn test_shared_database_serves_readers_during_writes() {
+        let shared: SharedSparqlDatabase = Arc::new(RwLock::new(SparqlDatabase::new()));
+
+        let writer = {
+            let shared = Arc::clone(&shared);
+            std::thread::spawn(move || {
+                for i in 0..100 {
+                    shared.write().unwrap().parse_ntriples_and_add(&format!(
+                        "<http://example.org/s{}> <http://example.org/p> <http://example.org/o> .\n",
+                        i
+                    ));
+                }
+            })
+        };
+        let readers: Vec<_> = (0..4)
+            .map(|_| {
+                let shared = Arc::clone(&shared);
+                std::thread::spawn(move || {
+                    let mut last = 0;
+                    for _ in 0..100 {
+                        let seen = shared
+                            .read()
+                            .unwrap()
+                            .get_subjects_for_predicate_object("http://example.org/p", "http://example.org/o")
+                            .len();
+                        assert!(seen >= last);
+                        last = seen;
+                    }
+                })
+            })
+            .collect();
+
+        writer.join().unwrap();
+        for reader in readers {
+            reader.join().unwrap();
+        }
+        assert_eq!(shared.read().unwrap().get_subjects_for_predicate("http://example.org/p").len(), 100);
+    }
+
+    #[test]
+    fn test_filters_pushed_below_joins_keep_their_results() {
+        let mut db = SparqlDatabase::new();
+        db.parse_ntriples_and_add(
+            "<http://example.org/alice> <http://example.org/knows> <http://example.org/bob> .\n\
+             <http://example.org/alice> <http://example.org/likes> <http://example.org/carol> .\n\
+             <http://example.org/alice> <http://example.org/age> \"30\" .\n\
+             <http://example.org/bob> <http://example.org/name> \"Bob\" .\n\
+             <http://example.org/bob> <http://example.org/age> \"25\" .\n\
+             <http://example.org/carol> <http://example.org/name> \"Carol\" .\n\
+             <http://example.org/carol> <http://example.org/age> \"35\" .\n",
+        );
+
+        let mut run = |query: &str| {
+            let sparql = format!("PREFIX ex: <http://example.org/>\n{}", query);
+            let mut rows = execute_query_rayon_parallel2_volcano(&sparql, &mut db);
+            rows.sort();
+            rows
+        };
+
+        // The equality on ?p binds the predicate of the first scan, and ?p stays in the results
+        assert_eq!(
+            run("SELECT ?p ?name WHERE { ex:alice ?p ?o . ?o ex:name ?name . FILTER(?p = \"http://example.org/knows\") }"),
+            vec![vec!["http://example.org/knows".to_string(), "Bob".to_string()]]
+        );
+        assert!(run("SELECT ?name WHERE { ex:alice ?p ?o . ?o ex:name ?name . FILTER(?p = \"http://example.org/hates\") }").is_empty());
+        // Each filter lands on a different side of the join
+        assert_eq!(
+            run("SELECT ?name WHERE { ex:alice ?p ?o . ?o ex:name ?name . FILTER(?p != \"http://example.org/knows\") FILTER(?name != \"Bob\") }"),
+            vec![vec!["Carol".to_string()]]
+        );
+        // A star join applies every filter, not only the outermost one
+        assert_eq!(
+            run("SELECT ?o WHERE { ?o ex:name ?name . ?o ex:age ?age . ?s ?p ?o . FILTER(?age > 20) FILTER(?name = \"Carol\") }"),
+            vec![vec!["http://example.org/carol".to_string()]]
+        );
+    }
+
+    #[test]
+    fn test_join_hints_force_an_algorithm_without_changing_results() {
+        let mut db = SparqlDatabase::new();
+        db.parse_ntriples_and_add(r#"
+<http://example.org/alice> <http://example.org/knows> <http://example.org/bob> .
+<http://example.org/bob> <http://example.org/knows> <http://example.org/carol> .
+<http://example.org/bob> <http://example.org/name> "Bob" .
+<http://example.org/carol> <http://example.org/name> "Carol" .
+<http://example.org/carol> <http://example.org/age> "30" .
+"#);
+        let query = r#"
+            PREFIX ex: <http://example.org/>
+            SELECT ?a ?name ?age
+            WHERE {
+                ?a ex:knows ?b .
+                ?b ex:name ?name .
+                ?b ex:age ?age
+            }
+        "#;
+        let expected = vec![vec![
+            "http://example.org/bob".to_string(),
+            "Carol".to_string(),
+            "30".to_string(),
+        ]];
+        assert_eq!(try_execute_query(query, &mut db).unwrap(), expected);
+
+        for name in JoinAlgorithm::NAMES {
+            let algorithm: JoinAlgorithm = name.parse().unwrap();
+            assert_eq!(algorithm.to_string(), name);
+            assert_eq!(execute_query_with_hint(query, &mut db, algorithm).unwrap(), expected);
+
+            let hinted = format!("# kolibrie:join {}\n{}", name, query);
+            assert_eq!(try_execute_query(&hinted, &mut db).unwrap(), expected);
+        }
+        assert_eq!(db.join_algorithm, None);
+
+        let err = try_execute_query(&format!("# kolibrie:join sort_merge\n{}", query), &mut db).unwrap_err();
+        match err {
+            SparqlError::ParseError(message) => assert!(message.contains("sort_merge")),
+            other => panic!("expected a parse error, got {:?}", other),
+        }
+    }
+
+    #[test]
+    fn test_hint_comments_steer_the_plan_without_changing_results() {
+        let mut db = SparqlDatabase::new();
+        db.parse_ntriples_and_add(r#"
+<http://example.org/alice> <http://example.org/knows> <http://example.org/bob> .
+<http://example.org/bob> <http://example.org/knows> <http://example.org/carol> .
+<http://example.org/bob> <http://example.org/name> "Bob" .
+<http://example.org/carol> <http://example.org/name> "Carol" .
+"#);
+        let query = r#"
+            PREFIX ex: <http://example.org/>
+            SELECT ?a ?name
+            WHERE {
+                ?a ex:knows ?b .
+                ?b ex:name ?name
+            }
+        "#;
+        let mut expected = try_execute_query(query, &mut db).unwrap();
+        expected.sort();
+        assert_eq!(expected.len(), 2);
+
+        for hints in [
+            "# HINT USE_INDEX OSP",
+            "# HINT use_index pos",
+            "# HINT PARALLEL_JOIN",
+            "# HINT MAX_MEMORY_MB 0",
+            "# HINT USE_INDEX SPO\n# HINT MAX_MEMORY_MB 0",
+        ] {
+            let mut rows = try_execute_query(&format!("{}\n{}", hints, query), &mut db).unwrap();
+            rows.sort();
+            assert_eq!(rows, expected, "{}", hints);
+        }
+
+        for (hint, reported) in [
+            ("USE_INDEX XYZ", "XYZ"),
+            ("USE_INDEX", "USE_INDEX"),
+            ("MAX_MEMORY_MB lots", "lots"),
+            ("FAST", "FAST"),
+        ] {
+            match try_execute_query(&format!("# HINT {}\n{}", hint, query), &mut db) {
+                Err(SparqlError::ParseError(message)) => assert!(message.contains(reported), "{}", message),
+                other => panic!("expected a parse error for `{}`, got {:?}", hint, other),
+            }
+        }
+    }
+
+    #[test]
+    fn test_parse_rdf_from_file_reports_progress_and_can_be_cancelled() {
+        let count = 2 * PARSE_PROGRESS_INTERVAL + 100;
+        let mut rdf_xml = String::from(
+            "<?xml version=\"1.0\"?>\n<rdf:RDF xmlns:rdf=\"http://www.w3.org/1999/02/22-rdf-syntax-ns#\" xmlns:ex=\"http://example.org/\">\n",
+        );
+        for i in 0..count {
+            rdf_xml.push_str(&format!(
+                "  <rdf:Description rdf:about=\"http://example.org/item{}\"><ex:index>{}</ex:index></rdf:Description>\n",
+                i, i
+            ));
+        }
+        rdf_xml.push_str("</rdf:RDF>\n");
+        let path = std::env::temp_dir().join(format!("kolibrie_progress_{}.rdf", std::process::id()));
+        std::fs::write(&path, rdf_xml).unwrap();
+        let path = path.to_str().unwrap();
+
+        let reports = RwLock::new(Vec::new());
+        let mut db = SparqlDatabase::new();
+        let read = db.parse_rdf_from_file_with_progress(path, |read| reports.write().unwrap().push(read), &CancellationToken::new());
+        assert_eq!(read, count);
+        assert_eq!(db.triples.len(), count);
+        assert_eq!(
+            reports.into_inner().unwrap(),
+            vec![PARSE_PROGRESS_INTERVAL, 2 * PARSE_PROGRESS_INTERVAL, count]
+        );
+
+        // Cancelling from the callback stops after the current batch
+        let cancellation = CancellationToken::new();
+        let mut db = SparqlDatabase::new();
+        let read = db.parse_rdf_from_file_with_progress(path, |_| cancellation.cancel(), &cancellation);
+        assert_eq!(read, PARSE_PROGRESS_INTERVAL);
+        assert_eq!(db.triples.len(), PARSE_PROGRESS_INTERVAL);
+
+        // A configured batch size sets the report interval
+        let reports = RwLock::new(Vec::new());
+        let mut db = SparqlDatabase::new_with_config(TuningConfig { parse_batch_size: 1000, ..TuningConfig::default() });
+        let read = db.parse_rdf_from_file_with_progress(path, |read| reports.write().unwrap().push(read), &CancellationToken::new());
+        std::fs::remove_file(path).unwrap();
+        assert_eq!(read, count);
+        let reports = reports.into_inner().unwrap();
+        assert_eq!(reports.len(), count.div_ceil(1000));
+        assert_eq!(reports[..2], [1000, 2000]);
+        assert_eq!(reports.last(), Some(&count));
+    }
+
+    #[test]
+    fn test_literals_with_spaces_quotes_and_tags_match_their_triples() {
+        let mut db = SparqlDatabase::new();
+        db.add_triple_parts("http://example.org/a", "http://example.org/label", "hello world");
+        db.add_triple_parts("http://example.org/b", "http://example.org/label", "say \"hi\"");
+        db.add_triple_parts("http://example.org/c", "http://example.org/label", "\"New York\"@en");
+        db.add_triple_parts("http://example.org/d", "http://example.org/label", "");
+
+        let subjects = |db: &mut SparqlDatabase, pattern: &str| -> Vec<Vec<String>> {
+            let query = format!("PREFIX ex: <http://example.org/>\nSELECT ?s WHERE {{ {} }}", pattern);
+            try_execute_query(&query, db).unwrap()
+        };
+        let only = |subject: &str| vec![vec![format!("http://example.org/{}", subject)]];
+
+        assert_eq!(subjects(&mut db, r#"?s ex:label "hello world""#), only("a"));
+        assert_eq!(subjects(&mut db, r#"?s ex:label 'hello world'"#), only("a"));
+        assert_eq!(subjects(&mut db, r#"?s ex:label ?l FILTER(?l = "hello world")"#), only("a"));
+        assert_eq!(subjects(&mut db, r#"?s ex:label "say \"hi\"""#), only("b"));
+        assert_eq!(subjects(&mut db, r#"?s ex:label ?l FILTER(CONTAINS(?l, "\"hi"))"#), only("b"));
+        assert_eq!(subjects(&mut db, r#"?s ex:label ?l FILTER(?l = "New York"@en)"#), only("c"));
+        assert_eq!(subjects(&mut db, r#"?s ex:label """#), only("d"));
+    }
 }
@@ -73,7 +73,21 @@ mod tests {
         let result = parse_literal("\"unterminated");
         assert!(result.is_err());
     }
-    
+
+    #[test]
+    fn test_literal_escapes_and_quotes() {
+        assert_eq!(parse_literal(r#""say \"hi\"" ."#), Ok((" .", "say \"hi\"")));
+        assert_eq!(parse_literal("'it\\'s'"), Ok(("", "it's")));
+        assert_eq!(parse_literal("\"\""), Ok(("", "")));
+        assert_eq!(parse_literal(r#""tab\there \u00E9 \q""#), Ok(("", "tab\there é \\q")));
+        assert!(parse_literal(r#""ends with \""#).is_err());
+
+        // Tagged literals are kept as written, and an escaped quote does not end them
+        assert_eq!(parse_language_literal(r#""say \"hi\""@en ."#), Ok((" .", r#""say \"hi\""@en"#)));
+        assert_eq!(parse_comparison_literal("\"New York\"@en)"), Ok((")", "\"New York\"@en")));
+        assert_eq!(parse_comparison_literal("\"42\"^^xsd:integer)"), Ok((")", "42")));
+    }
+
     #[test]
     fn test_uri_parsing() {
         let result = parse_uri("<http://example.org/person>");
@@ -140,6 +154,13 @@ mod tests {
         // Test SELECT with aggregation
         let result = parse_select("SELECT SUM(?salary) AS ?total");
         assert!(result.is_ok());
+
+        // Test SELECT DISTINCT
+        let (_, (distinct, variables)) = parse_select_clause("SELECT DISTINCT ?type").unwrap();
+        assert!(distinct);
+        assert_eq!(variables, vec![("VAR", "?type", None)]);
+        let (_, (distinct, _)) = parse_select_clause("SELECT ?type").unwrap();
+        assert!(!distinct);
     }
     
     #[test]
@@ -614,6 +635,34 @@ ML.PREDICT(MODEL "digit_model",
         assert_eq!(ml_predict.output, "?label");
     }
 
+    #[test]
+    fn test_rule_negated_body_forms() {
+        let input = r#"
+PREFIX ex: <http://example.org/>
+RULE :Active :-
+CONSTRUCT {
+    ?person ex:status ex:active .
+}
+WHERE {
+    ?person ex:member ex:club .
+    NOT ?person ex:banned ex:yes .
+    NOT { ?person ex:suspended ex:yes . ?person ex:left ex:yes }
+}
+        "#;
+
+        let (_, combined) = parse_combined_query(input).unwrap();
+        let rule = combined.rule.expect("rule should be parsed");
+        assert_eq!(rule.body.0.len(), 1);
+        assert_eq!(
+            rule.negated_body,
+            vec![
+                ("?person", "ex:banned", "ex:yes"),
+                ("?person", "ex:suspended", "ex:yes"),
+                ("?person", "ex:left", "ex:yes"),
+            ]
+        );
+    }
+
     #[test]
     fn lower_ml_predict_alias_test() {
         let predict_input = r#"
@@ -0,0 +1,85 @@
+/*
+ * Copyright © 2026 Volodymyr Kadzhaia
+ * Copyright © 2026 Pieter Bonte
+ * KU Leuven — Stream Intelligence Lab, Belgium
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this file,
+ * you can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+extern crate kolibrie;
+use kolibrie::execute_query::execute_query_rayon_parallel2_volcano;
+use kolibrie::pattern_cache::PatternKey;
+use kolibrie::sparql_database::SparqlDatabase;
+
+const NAMES: &str = "PREFIX ex: <http://example.org/>\nSELECT ?p ?name WHERE { ?p ex:name ?name }";
+const AGES: &str = "PREFIX ex: <http://example.org/>\nSELECT ?p ?age WHERE { ?p ex:age ?age }";
+
+fn setup_db() -> SparqlDatabase {
+    let mut db = SparqlDatabase::new();
+    db.add_triple_parts("http://example.org/alice", "http://example.org/name", "Alice");
+    db.add_triple_parts("http://example.org/alice", "http://example.org/age", "30");
+    db.add_triple_parts("http://example.org/bob", "http://example.org/name", "Bob");
+    db.enable_pattern_cache(Some(8));
+    db
+}
+
+fn predicate_key(db: &SparqlDatabase, predicate: &str) -> PatternKey {
+    (None, Some(db.dictionary.write().unwrap().encode(predicate)), None)
+}
+
+fn cached(db: &SparqlDatabase, key: &PatternKey) -> bool {
+    db.pattern_cache.as_ref().unwrap().contains(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_scans_are_served_from_the_cache() {
+        let mut db = setup_db();
+        let names = predicate_key(&db, "http://example.org/name");
+        assert_eq!(execute_query_rayon_parallel2_volcano(NAMES, &mut db).len(), 2);
+        assert!(cached(&db, &names));
+
+        let misses = db.pattern_cache.as_ref().unwrap().misses();
+        assert_eq!(execute_query_rayon_parallel2_volcano(NAMES, &mut db).len(), 2);
+        let cache = db.pattern_cache.as_ref().unwrap();
+        assert_eq!(cache.misses(), misses);
+        assert!(cache.hits() >= 1);
+    }
+
+    #[test]
+    fn test_writes_invalidate_matching_patterns() {
+        let mut db = setup_db();
+        let names = predicate_key(&db, "http://example.org/name");
+        let ages = predicate_key(&db, "http://example.org/age");
+        execute_query_rayon_parallel2_volcano(NAMES, &mut db);
+        execute_query_rayon_parallel2_volcano(AGES, &mut db);
+        assert!(cached(&db, &names) && cached(&db, &ages));
+
+        db.add_triple_parts("http://example.org/bob", "http://example.org/age", "25");
+        assert!(cached(&db, &names));
+        assert!(!cached(&db, &ages));
+        assert_eq!(execute_query_rayon_parallel2_volcano(AGES, &mut db).len(), 2);
+
+        assert!(db.delete_triple_parts("http://example.org/alice", "http://example.org/name", "Alice"));
+        assert!(!cached(&db, &names));
+        assert!(cached(&db, &ages));
+        assert_eq!(execute_query_rayon_parallel2_volcano(NAMES, &mut db).len(), 1);
+    }
+
+    #[test]
+    fn test_index_rebuild_drops_the_cache() {
+        let mut db = setup_db();
+        execute_query_rayon_parallel2_volcano(NAMES, &mut db);
+        assert!(!db.pattern_cache.as_ref().unwrap().is_empty());
+
+        db.parse_ntriples_and_add(
+            "<http://example.org/carol> <http://example.org/name> \"Carol\" .\n",
+        );
+        assert_eq!(execute_query_rayon_parallel2_volcano(NAMES, &mut db).len(), 3);
+    }
+}
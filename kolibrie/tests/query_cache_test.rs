@@ -0,0 +1,104 @@
+/*
+ * Copyright © 2026 Volodymyr Kadzhaia
+ * Copyright © 2026 Pieter Bonte
+ * KU Leuven — Stream Intelligence Lab, Belgium
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this file,
+ * you can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+extern crate kolibrie;
+use kolibrie::execute_query::{execute_query_rayon_parallel2_volcano, query_cache_key};
+use kolibrie::sparql_database::SparqlDatabase;
+
+const NAMES: &str = "PREFIX ex: <http://example.org/>\nSELECT ?p ?name WHERE { ?p ex:name ?name }";
+const AGES: &str = "PREFIX ex: <http://example.org/>\nSELECT ?p ?age WHERE { ?p ex:age ?age }";
+const ALICE: &str = "PREFIX ex: <http://example.org/>\nSELECT ?pred ?o WHERE { ex:alice ?pred ?o }";
+
+fn setup_db() -> SparqlDatabase {
+    let mut db = SparqlDatabase::new();
+    db.add_triple_parts("http://example.org/alice", "http://example.org/name", "Alice");
+    db.add_triple_parts("http://example.org/alice", "http://example.org/age", "30");
+    db.add_triple_parts("http://example.org/bob", "http://example.org/name", "Bob");
+    db.enable_query_cache();
+    db
+}
+
+fn cached(db: &SparqlDatabase, query: &str) -> bool {
+    db.query_cache.as_ref().unwrap().contains(&query_cache_key(query, &db.prefixes))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_write_invalidates_only_dependent_queries() {
+        let mut db = setup_db();
+        assert_eq!(execute_query_rayon_parallel2_volcano(NAMES, &mut db).len(), 2);
+        assert_eq!(execute_query_rayon_parallel2_volcano(AGES, &mut db).len(), 1);
+        assert_eq!(execute_query_rayon_parallel2_volcano(ALICE, &mut db).len(), 2);
+        assert!(cached(&db, NAMES) && cached(&db, AGES) && cached(&db, ALICE));
+
+        // Served from the cache the second time
+        execute_query_rayon_parallel2_volcano(NAMES, &mut db);
+        assert_eq!(db.query_cache.as_ref().unwrap().hits, 1);
+
+        // Bob's age touches the ex:age query only; the ex:alice pattern has a
+        // variable predicate but a different subject
+        db.add_triple_parts("http://example.org/bob", "http://example.org/age", "25");
+        assert!(cached(&db, NAMES));
+        assert!(!cached(&db, AGES));
+        assert!(cached(&db, ALICE));
+        assert_eq!(execute_query_rayon_parallel2_volcano(AGES, &mut db).len(), 2);
+
+        // Deleting one of Alice's triples invalidates both queries matching it
+        assert!(db.delete_triple_parts("http://example.org/alice", "http://example.org/name", "Alice"));
+        assert!(!cached(&db, NAMES));
+        assert!(!cached(&db, ALICE));
+        assert!(cached(&db, AGES));
+        assert_eq!(execute_query_rayon_parallel2_volcano(NAMES, &mut db).len(), 1);
+        assert_eq!(execute_query_rayon_parallel2_volcano(ALICE, &mut db).len(), 1);
+    }
+
+    #[test]
+    fn test_re_adding_an_existing_triple_keeps_the_cache() {
+        let mut db = setup_db();
+        execute_query_rayon_parallel2_volcano(NAMES, &mut db);
+        db.add_triple_parts("http://example.org/bob", "http://example.org/name", "Bob");
+        assert!(cached(&db, NAMES));
+    }
+
+    #[test]
+    fn test_bulk_load_drops_the_cache() {
+        let mut db = setup_db();
+        assert_eq!(execute_query_rayon_parallel2_volcano(NAMES, &mut db).len(), 2);
+
+        // Parsers write the triple set directly, bypassing add_triple
+        db.parse_ntriples_and_add(
+            "<http://example.org/carol> <http://example.org/name> \"Carol\" .\n",
+        );
+        assert_eq!(execute_query_rayon_parallel2_volcano(NAMES, &mut db).len(), 3);
+    }
+
+    #[test]
+    fn test_equivalent_queries_share_an_entry() {
+        let mut db = setup_db();
+        assert_eq!(execute_query_rayon_parallel2_volcano(NAMES, &mut db).len(), 2);
+
+        // Expanded IRIs, comments and a different layout normalise alike
+        let rewritten = "# same query\nSELECT ?p ?name\nWHERE {\n  ?p <http://example.org/name> ?name .\n}";
+        assert!(cached(&db, rewritten));
+        assert_eq!(execute_query_rayon_parallel2_volcano(rewritten, &mut db).len(), 2);
+        assert_eq!(db.query_cache.as_ref().unwrap().hits, 1);
+    }
+
+    #[test]
+    fn test_updates_are_not_cached() {
+        let mut db = setup_db();
+        let insert = "PREFIX ex: <http://example.org/>\nINSERT { ex:carol ex:name \"Carol\" } WHERE { }";
+        execute_query_rayon_parallel2_volcano(insert, &mut db);
+        assert!(db.query_cache.as_ref().unwrap().is_empty());
+    }
+}
@@ -73,4 +73,51 @@ mod tests {
 
         query.stop_stream();
     }
-}
\ No newline at end of file
+
+    fn decoded_with_predicate(db: &SparqlDatabase, predicate: &str) -> Vec<(String, String, String)> {
+        db.query().with_predicate(predicate).get_decoded_triples()
+    }
+
+    #[test]
+    fn test_update_requests_round_trip_through_the_update_executor() {
+        let mut db = SparqlDatabase::new();
+        db.prefixes.insert("ex".to_string(), "http://example.org/".to_string());
+        db.add_triple_parts("http://example.org/alice", "http://example.org/knows", "http://example.org/bob");
+        db.add_triple_parts("http://example.org/bob", "http://example.org/knows", "http://example.org/carol");
+
+        let update = db.query().insert_data(&[("ex:alice", "http://example.org/name", "Alice")]);
+        assert_eq!(
+            update,
+            "PREFIX ex: <http://example.org/>\nINSERT DATA {\n  ex:alice <http://example.org/name> \"Alice\" .\n}\n"
+        );
+        db.try_update(&update).unwrap();
+        assert_eq!(
+            decoded_with_predicate(&db, "http://example.org/name"),
+            vec![("http://example.org/alice".to_string(), "http://example.org/name".to_string(), "Alice".to_string())]
+        );
+
+        let update = db.query().insert_where(&[("?b", "ex:knownBy", "?a")], &[("?a", "ex:knows", "?b")]);
+        db.try_update(&update).unwrap();
+        assert_eq!(db.query().with_predicate("http://example.org/knownBy").count(), 2);
+
+        // Decoded triples from the builder are written back as they were stored
+        let knows = decoded_with_predicate(&db, "http://example.org/knows");
+        let knows: Vec<(&str, &str, &str)> = knows.iter().map(|(s, p, o)| (s.as_str(), p.as_str(), o.as_str())).collect();
+        let update = db.query().delete_data(&knows);
+        db.try_update(&update).unwrap();
+        assert_eq!(db.query().with_predicate("http://example.org/knows").count(), 0);
+
+        let update = db.query().delete_where(&[("?s", "ex:knownBy", "?o")]);
+        assert_eq!(update, "PREFIX ex: <http://example.org/>\nDELETE WHERE {\n  ?s ex:knownBy ?o .\n}\n");
+        db.try_update(&update).unwrap();
+        assert_eq!(db.query().with_predicate("http://example.org/knownBy").count(), 0);
+        assert_eq!(db.triples.len(), 1);
+    }
+
+    #[test]
+    fn test_update_literals_are_escaped() {
+        let db = SparqlDatabase::new();
+        let update = db.query().delete_data(&[("_:b0", "<http://example.org/says>", "say \"hi\"\n")]);
+        assert_eq!(update, "DELETE DATA {\n  _:b0 <http://example.org/says> \"say \\\"hi\\\"\\n\" .\n}\n");
+    }
+}
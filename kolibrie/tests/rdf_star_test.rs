@@ -447,6 +447,33 @@ fn test_generate_ntriples_star() {
     assert!(output.contains("<http://example.org/source>"), "Should contain outer predicate");
 }
 
+#[test]
+fn test_iter_triples_as_strings_decodes_each_triple() {
+    let mut db = SparqlDatabase::new();
+    let ntriples = r#"<http://example.org/alice> <http://example.org/name> "Alice" .
+<< <http://example.org/alice> <http://example.org/knows> <http://example.org/bob> >> <http://example.org/source> <http://example.org/doc1> .
+"#;
+    db.parse_ntriples_and_add(ntriples);
+
+    let triples: Vec<(String, String, String)> = db.iter_triples_as_strings().collect();
+    assert_eq!(triples.len(), db.triples.len());
+    assert!(triples.contains(&(
+        "http://example.org/alice".to_string(),
+        "http://example.org/name".to_string(),
+        "Alice".to_string(),
+    )));
+    let (quoted, predicate, object) = triples
+        .iter()
+        .find(|(subject, _, _)| subject.starts_with("<<"))
+        .expect("the quoted triple should be decoded");
+    assert!(quoted.contains("http://example.org/bob"));
+    assert_eq!(predicate, "http://example.org/source");
+    assert_eq!(object, "http://example.org/doc1");
+
+    // generate_ntriples writes one line per iterated triple
+    assert_eq!(db.generate_ntriples().lines().count(), triples.len());
+}
+
 #[test]
 fn test_generate_turtle_basic() {
     let mut db = SparqlDatabase::new();
@@ -183,10 +183,15 @@ fn rsp_ql_dstream_semantics() {
     );
 }
 
+use kolibrie::custom_error::SparqlError;
+use kolibrie::rsp::r2r::R2ROperator;
 use kolibrie::rsp_engine::{
-    OperationMode, QueryExecutionMode, RSPBuilder, RSPEngine, ResultConsumer, SimpleR2R,
+    OperationMode, QueryExecutionMode, RSPBuilder, RSPEngine, ResultConsumer, RspBuildError,
+    SimpleR2R,
 };
+use kolibrie::streamertail_optimizer::PhysicalOperator;
 use shared::query::{Fallback, SyncPolicy};
+use shared::terms::Term;
 use shared::triple::Triple;
 use std::sync::{Arc, Mutex};
 use std::thread;
@@ -635,6 +640,59 @@ fn rsp_ql_single_window_static_join() {
     );
 }
 
+/// With registered prefixes, events are read as Turtle: prefixed names and
+/// `a` resolve to the IRIs the query matches, and full IRIs still work.
+#[test]
+fn rsp_ql_turtle_events_with_registered_prefixes() {
+    let result_container = Arc::new(Mutex::new(Vec::new()));
+    let rc = Arc::clone(&result_container);
+    let result_consumer = ResultConsumer {
+        function: Arc::new(move |r: Vec<(String, String)>| {
+            rc.lock().unwrap().push(r);
+        }),
+    };
+    let r2r = Box::new(SimpleR2R::with_execution_mode(QueryExecutionMode::Volcano));
+
+    let query = r#"
+        REGISTER RSTREAM <http://out/stream> AS
+        SELECT ?s
+        FROM NAMED WINDOW :w ON ?stream [RANGE 10 STEP 10]
+        WHERE { WINDOW :w { ?s a <http://example.org/Sensor> . } }
+    "#;
+
+    let mut engine: RSPEngine<Triple, Vec<(String, String)>> = RSPBuilder::new()
+        .add_rsp_ql_query(query)
+        .add_consumer(result_consumer)
+        .add_r2r(r2r)
+        .add_prefix("ex", "http://example.org/")
+        .set_operation_mode(OperationMode::SingleThread)
+        .build()
+        .expect("Failed to build RSP engine");
+
+    let events = [
+        "ex:sensor1 a ex:Sensor .",
+        "ex:sensor2 a ex:Sensor ;\n    ex:locatedIn ex:room1 .",
+        "<http://example.org/sensor3> <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> <http://example.org/Sensor> .",
+    ];
+    for (ts, event) in events.iter().enumerate() {
+        for triple in engine.parse_data(event) {
+            engine.add(triple, ts + 1);
+        }
+    }
+    engine.stop();
+
+    let subjects: std::collections::HashSet<String> = result_container
+        .lock()
+        .unwrap()
+        .iter()
+        .flat_map(|row| row.iter().filter(|(k, _)| k == "s").map(|(_, v)| v.clone()))
+        .collect();
+    let expected: std::collections::HashSet<String> = (1..=3)
+        .map(|i| format!("http://example.org/sensor{}", i))
+        .collect();
+    assert_eq!(subjects, expected);
+}
+
 /// Steal policy: window A fires first, B never fires -> no emission
 /// (last_mat only has A, never reaches num_windows=2).
 #[test]
@@ -747,6 +805,80 @@ fn test_timeout_drop_policy() {
     );
 }
 
+/// A 10s window joined with a 5s window: each join combines what both windows
+/// held at the same tick, and is emitted only at ticks where both evaluate.
+#[test]
+fn rsp_ql_join_windows_of_different_sizes_aligns_ticks() {
+    let result_container = Arc::new(Mutex::new(Vec::<Vec<(String, String)>>::new()));
+    let rc = Arc::clone(&result_container);
+    let consumer = ResultConsumer {
+        function: Arc::new(move |r: Vec<(String, String)>| {
+            rc.lock().unwrap().push(r);
+        }),
+    };
+    let r2r = Box::new(SimpleR2R::with_execution_mode(QueryExecutionMode::Volcano));
+    let rsp_ql_query = r#"
+        REGISTER RSTREAM <http://out/stream> AS
+        SELECT *
+        FROM NAMED WINDOW :slow ON :temperature [RANGE 10 STEP 10]
+        FROM NAMED WINDOW :fast ON :humidity [RANGE 5 STEP 5]
+        WHERE {
+            WINDOW :slow { ?sensor <http://test/temperature> ?temp . }
+            WINDOW :fast { ?sensor <http://test/humidity> ?hum . }
+        }
+    "#;
+    let mut engine: RSPEngine<Triple, Vec<(String, String)>> = RSPBuilder::new()
+        .add_rsp_ql_query(rsp_ql_query)
+        .add_consumer(consumer)
+        .add_r2r(r2r)
+        .set_operation_mode(OperationMode::SingleThread)
+        .build()
+        .expect("Failed to build RSP engine");
+
+    // Every second each sensor reports a temperature, then a humidity
+    let feed = |engine: &mut RSPEngine<Triple, Vec<(String, String)>>, ts: usize| {
+        for (stream, predicate) in [("temperature", "temperature"), ("humidity", "humidity")] {
+            let data = format!("<http://test/sensor{}> <http://test/{}> \"{}\" .", ts, predicate, ts);
+            for triple in engine.parse_data(&data) {
+                engine.add_to_stream(stream, triple, ts);
+            }
+        }
+    };
+    let joined_sensors = |results: &Mutex<Vec<Vec<(String, String)>>>| {
+        let mut sensors: Vec<usize> = results
+            .lock()
+            .unwrap()
+            .drain(..)
+            .map(|row| {
+                let sensor = row.iter().find(|(k, _)| k == "sensor").unwrap();
+                sensor.1.trim_start_matches("http://test/sensor").parse().unwrap()
+            })
+            .collect();
+        sensors.sort();
+        sensors
+    };
+
+    for ts in 1..=11 {
+        feed(&mut engine, ts);
+    }
+    // Tick 10: the 10s window [0,10) joined with the 5s window [5,10), not
+    // with the 5s window of tick 5
+    assert_eq!(joined_sensors(&result_container), vec![5, 6, 7, 8, 9]);
+
+    for ts in 12..=19 {
+        feed(&mut engine, ts);
+    }
+    // Tick 15 only evaluates the 5s window
+    assert!(joined_sensors(&result_container).is_empty());
+
+    for ts in 20..=21 {
+        feed(&mut engine, ts);
+    }
+    assert_eq!(joined_sensors(&result_container), vec![15, 16, 17, 18, 19]);
+
+    engine.stop();
+}
+
 /// Two windows + static WHERE patterns: results must contain variables
 /// from both windows (?sensor, ?room) and confirm the static join filtered
 /// them correctly.
@@ -1411,3 +1543,234 @@ fn rsp_ql_reasoning_derives_types() {
         *results
     );
 }
+
+/// Triples inferred for one firing are dropped by the next one, unless the
+/// stream itself asserts them meanwhile.
+#[test]
+fn simple_r2r_drops_inferred_triples_between_firings() {
+    let mut r2r = SimpleR2R::with_execution_mode(QueryExecutionMode::Volcano);
+    r2r.load_rules(concat!(
+        "@prefix test: <http://test/>.\n",
+        "@prefix rdf: <http://www.w3.org/1999/02/22-rdf-syntax-ns#>.\n",
+        "{ ?s test:hasValue ?v . } => { ?s rdf:type test:HasValue . } .\n",
+    ))
+    .expect("rules should parse");
+
+    let reading = r2r.parse_data("<http://test/sensor1> <http://test/hasValue> \"42\" .");
+    let typed = r2r.parse_data(
+        "<http://test/sensor1> <http://www.w3.org/1999/02/22-rdf-syntax-ns#type> <http://test/HasValue> .",
+    );
+
+    for t in &reading {
+        r2r.add(t.clone());
+    }
+    assert_eq!(r2r.materialize(), typed);
+    assert!(r2r.item.triples.contains(&typed[0]));
+
+    // The reading leaves the window: its inference goes with it
+    for t in &reading {
+        r2r.remove(t);
+    }
+    assert!(r2r.materialize().is_empty());
+    assert!(!r2r.item.triples.contains(&typed[0]));
+
+    // Once streamed, the inferred triple is no longer evicted as derived
+    for t in &reading {
+        r2r.add(t.clone());
+    }
+    r2r.materialize();
+    r2r.add(typed[0].clone());
+    r2r.materialize();
+    assert!(r2r.item.triples.contains(&typed[0]));
+}
+
+#[test]
+fn rsp_build_rejects_unparsable_rules() {
+    let result = RSPBuilder::<Triple, Vec<(String, String)>>::new()
+        .add_rsp_ql_query(
+            r#"
+            REGISTER RSTREAM <http://out/stream> AS
+            SELECT *
+            FROM NAMED WINDOW :w ON ?stream [RANGE 10 STEP 1]
+            WHERE { WINDOW :w { ?s a <http://test/HasValue> . } }
+        "#,
+        )
+        .add_rules("{ ?s <http://test/hasValue> ?v . } => ")
+        .add_r2r(Box::new(SimpleR2R::with_execution_mode(QueryExecutionMode::Volcano)))
+        .build();
+    match result {
+        Err(RspBuildError::ParseError { clause, .. }) => assert_eq!(clause, "N3 rules"),
+        Err(other) => panic!("expected a rules parse error, got {:?}", other),
+        Ok(_) => panic!("unparsable rules should not build"),
+    }
+}
+
+#[test]
+fn rsp_multi_thread_delivers_every_event_from_concurrent_producers() {
+    let result_container = Arc::new(Mutex::new(Vec::<Vec<(String, String)>>::new()));
+    let rc = Arc::clone(&result_container);
+    let result_consumer = ResultConsumer {
+        function: Arc::new(move |r: Vec<(String, String)>| {
+            rc.lock().unwrap().push(r);
+        }),
+    };
+    let r2r = Box::new(SimpleR2R::with_execution_mode(QueryExecutionMode::Volcano));
+    let query = r#"
+        REGISTER RSTREAM <http://out/stream> AS
+        SELECT *
+        FROM NAMED WINDOW :w ON :stream [RANGE 10 STEP 10]
+        WHERE { WINDOW :w { ?s a <http://test/Reading> . } }
+    "#;
+
+    let mut engine: RSPEngine<Triple, Vec<(String, String)>> = RSPBuilder::new()
+        .add_rsp_ql_query(query)
+        .add_consumer(result_consumer)
+        .add_r2r(r2r)
+        .set_operation_mode(OperationMode::MultiThread)
+        .build()
+        .expect("Failed to build multi-threaded engine");
+    engine.parse_data("a a <http://test/Reading> .");
+
+    const PRODUCERS: usize = 4;
+    const EVENTS_PER_PRODUCER: usize = 250;
+    // Producers share the engine and draw timestamps under its lock, so the
+    // windows see events in timestamp order
+    let shared = Arc::new(Mutex::new((engine, 0usize)));
+    let producers: Vec<_> = (0..PRODUCERS)
+        .map(|producer| {
+            let shared = Arc::clone(&shared);
+            thread::spawn(move || {
+                for i in 0..EVENTS_PER_PRODUCER {
+                    let mut guard = shared.lock().unwrap();
+                    let (engine, clock) = &mut *guard;
+                    let data = format!(
+                        "<http://test/p{}e{}> a <http://test/Reading> .",
+                        producer, i
+                    );
+                    for triple in engine.parse_data(&data) {
+                        engine.add_to_stream(":stream", triple, *clock);
+                    }
+                    *clock += 1;
+                }
+            })
+        })
+        .collect();
+    for producer in producers {
+        producer.join().expect("producer thread panicked");
+    }
+
+    let (mut engine, _) = Arc::try_unwrap(shared)
+        .ok()
+        .expect("producers still hold the engine")
+        .into_inner()
+        .unwrap();
+    // No sleep: stop() returns once the workers delivered every evaluation
+    engine.stop();
+
+    let results = result_container.lock().unwrap();
+    let subjects: std::collections::HashSet<String> = results
+        .iter()
+        .flat_map(|row| row.iter().filter(|(k, _)| k == "s").map(|(_, v)| v.clone()))
+        .collect();
+    assert_eq!(
+        subjects.len(),
+        PRODUCERS * EVENTS_PER_PRODUCER,
+        "every pushed subject should reach the consumer"
+    );
+    // Tumbling windows hold each event once
+    assert_eq!(results.len(), PRODUCERS * EVENTS_PER_PRODUCER);
+}
+
+/// Runs a sliding-window join over the same events and returns each firing's
+/// rows, sorted, so incremental and full evaluation can be compared.
+fn sliding_join_firings(incremental: bool) -> Vec<Vec<Vec<(String, String)>>> {
+    let result_container = Arc::new(Mutex::new(Vec::<Vec<(String, String)>>::new()));
+    let rc = Arc::clone(&result_container);
+    let result_consumer = ResultConsumer {
+        function: Arc::new(move |r: Vec<(String, String)>| {
+            rc.lock().unwrap().push(r);
+        }),
+    };
+    let r2r = Box::new(SimpleR2R::with_execution_mode(QueryExecutionMode::Volcano));
+
+    let query = r#"
+        REGISTER RSTREAM <http://out/stream> AS
+        SELECT *
+        FROM NAMED WINDOW :w ON ?stream [RANGE 4 STEP 1]
+        WHERE {
+            WINDOW :w {
+                ?p <http://test/knows> ?f .
+                ?f <http://test/age> ?age .
+            }
+        }
+    "#;
+
+    let mut engine: RSPEngine<Triple, Vec<(String, String)>> = RSPBuilder::new()
+        .add_rsp_ql_query(query)
+        .add_consumer(result_consumer)
+        .add_r2r(r2r)
+        .set_operation_mode(OperationMode::SingleThread)
+        .set_incremental_evaluation(incremental)
+        .build()
+        .expect("Failed to build incremental engine");
+
+    let mut firings = Vec::new();
+    for ts in 1..16 {
+        // Friendships and ages arrive at different times, and some events
+        // repeat a triple that is still in the window
+        let data = format!(
+            "<http://test/person{}> <http://test/knows> <http://test/person{}> .\n\
+             <http://test/person{}> <http://test/age> \"{}\" .",
+            ts % 5,
+            (ts + 1) % 5,
+            ts % 3,
+            20 + ts % 4
+        );
+        for t in engine.parse_data(&data) {
+            engine.add(t, ts);
+        }
+        let mut rows: Vec<Vec<(String, String)>> = result_container.lock().unwrap().drain(..).collect();
+        rows.sort();
+        firings.push(rows);
+    }
+    firings
+}
+
+#[test]
+fn rsp_incremental_evaluation_matches_full_evaluation() {
+    let full = sliding_join_firings(false);
+    let incremental = sliding_join_firings(true);
+    assert!(
+        full.iter().any(|rows| !rows.is_empty()),
+        "the join should match in some windows"
+    );
+    assert_eq!(incremental, full);
+}
+
+#[test]
+fn simple_r2r_reports_oversized_joins_as_errors() {
+    let mut r2r = SimpleR2R::new();
+    for i in 0..2 {
+        r2r.item.add_triple_parts(&format!("http://test/a{}", i), "http://test/p", "http://test/x");
+        r2r.item.add_triple_parts(&format!("http://test/b{}", i), "http://test/q", "http://test/y");
+    }
+    let (p, q) = {
+        let mut dict = r2r.item.dictionary.write().unwrap();
+        (dict.encode("http://test/p"), dict.encode("http://test/q"))
+    };
+    let var = |name: &str| Term::Variable(name.to_string());
+    // ?a p ?x and ?b q ?y share no variable: a 2 x 2 Cartesian product
+    let plan = PhysicalOperator::hash_join(
+        PhysicalOperator::table_scan((var("a"), Term::Constant(p), var("x"))),
+        PhysicalOperator::table_scan((var("b"), Term::Constant(q), var("y"))),
+    );
+
+    r2r.item.max_join_results = 3;
+    assert_eq!(
+        r2r.execute_query(&plan),
+        Err(SparqlError::CartesianProductTooLarge { left: 2, right: 2, limit: 3 })
+    );
+
+    r2r.item.max_join_results = 4;
+    assert_eq!(r2r.execute_query(&plan).unwrap().len(), 4);
+}
@@ -0,0 +1,99 @@
+/*
+ * Copyright © 2026 Volodymyr Kadzhaia
+ * Copyright © 2026 Pieter Bonte
+ * KU Leuven — Stream Intelligence Lab, Belgium
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this file,
+ * you can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+extern crate kolibrie;
+use kolibrie::custom_error::SparqlError;
+use kolibrie::execute_query::execute_query_rayon_parallel2_volcano;
+use kolibrie::parser::process_rule_definition;
+use kolibrie::sparql_database::SparqlDatabase;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_why_follows_derivations_across_rules() {
+        let mut db = SparqlDatabase::new();
+        db.enable_derivation_tracking();
+        db.add_triple_parts("http://example.org/alice", "http://example.org/worksFor", "http://example.org/acme");
+        db.add_triple_parts("http://example.org/acme", "http://example.org/locatedIn", "http://example.org/leuven");
+
+        let employee_rule = r#"
+PREFIX ex: <http://example.org/>
+
+RULE :Employee :-
+CONSTRUCT {
+    ?person ex:employeeOf ?company .
+}
+WHERE {
+    ?person ex:worksFor ?company .
+}
+"#;
+        let city_rule = r#"
+PREFIX ex: <http://example.org/>
+
+RULE :WorksIn :-
+CONSTRUCT {
+    ?person ex:worksIn ?city .
+}
+WHERE {
+    ?person ex:employeeOf ?company .
+    ?company ex:locatedIn ?city .
+}
+"#;
+        process_rule_definition(employee_rule, &mut db).expect("rule processing failed");
+        let (_, inferred) = process_rule_definition(city_rule, &mut db).expect("rule processing failed");
+        assert_eq!(inferred.len(), 1);
+
+        let log = db.derivation_log.as_ref().unwrap();
+        assert_eq!(log.rules.len(), 2);
+        assert_eq!(log.get(&inferred[0]).unwrap().rule_id, 1);
+
+        let why = db.why(&inferred[0]);
+        let lines: Vec<&str> = why.lines().collect();
+        assert_eq!(lines.len(), 4, "{}", why);
+        assert!(lines[0].starts_with("(http://example.org/alice, http://example.org/worksIn, http://example.org/leuven) <- rule 1"));
+        assert!(lines[1].starts_with("  (http://example.org/alice, http://example.org/employeeOf, http://example.org/acme) <- rule 0"));
+        assert_eq!(lines[2], "    (http://example.org/alice, http://example.org/worksFor, http://example.org/acme) [asserted]");
+        assert_eq!(lines[3], "  (http://example.org/acme, http://example.org/locatedIn, http://example.org/leuven) [asserted]");
+    }
+
+    #[test]
+    fn test_apply_n3_rules_adds_inferred_triples() {
+        let mut db = SparqlDatabase::new();
+        db.add_triple_parts("http://example.org/alice", "http://example.org/parentOf", "http://example.org/bob");
+        db.add_triple_parts("http://example.org/bob", "http://example.org/parentOf", "http://example.org/carol");
+
+        let rules = r#"
+@prefix ex: <http://example.org/> .
+
+{ ?x ex:parentOf ?y } => { ?x ex:ancestorOf ?y } .
+{ ?x ex:ancestorOf ?y . ?y ex:ancestorOf ?z } => { ?x ex:ancestorOf ?z } .
+"#;
+        // alice->bob, bob->carol and, through the recursive rule, alice->carol
+        assert_eq!(db.apply_n3_rules(rules), Ok(3));
+        assert_eq!(db.prefixes.get("ex").map(String::as_str), Some("http://example.org/"));
+
+        let results = execute_query_rayon_parallel2_volcano(
+            "PREFIX ex: <http://example.org/>\nSELECT ?y WHERE { ex:alice ex:ancestorOf ?y }",
+            &mut db,
+        );
+        let mut ancestors: Vec<&str> = results.iter().map(|row| row[0].as_str()).collect();
+        ancestors.sort();
+        assert_eq!(ancestors, vec!["http://example.org/bob", "http://example.org/carol"]);
+
+        // Nothing new on a second run, and malformed rules are reported
+        assert_eq!(db.apply_n3_rules(rules), Ok(0));
+        assert!(matches!(
+            db.apply_n3_rules("{ ?x ex:parentOf ?y } =>"),
+            Err(SparqlError::ParseError(_))
+        ));
+    }
+}
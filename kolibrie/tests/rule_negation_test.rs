@@ -0,0 +1,75 @@
+/*
+ * Copyright © 2026 Volodymyr Kadzhaia
+ * Copyright © 2026 Pieter Bonte
+ * KU Leuven — Stream Intelligence Lab, Belgium
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this file,
+ * you can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+extern crate kolibrie;
+use kolibrie::parser::process_rule_definition;
+use kolibrie::sparql_database::SparqlDatabase;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn has_triple(db: &SparqlDatabase, s: &str, p: &str, o: &str) -> bool {
+        let dict = db.dictionary.read().unwrap();
+        db.triples.iter().any(|t| {
+            dict.decode(t.subject) == Some(s)
+                && dict.decode(t.predicate) == Some(p)
+                && dict.decode(t.object) == Some(o)
+        })
+    }
+
+    #[test]
+    fn test_rule_active_unless_suspended() {
+        let mut db = SparqlDatabase::new();
+        db.add_triple_parts("http://example.org/alice", "http://example.org/member", "http://example.org/club");
+        db.add_triple_parts("http://example.org/bob", "http://example.org/member", "http://example.org/club");
+        db.add_triple_parts("http://example.org/bob", "http://example.org/suspended", "true");
+
+        let rule = r#"
+PREFIX ex: <http://example.org/>
+
+RULE :ActiveMember :-
+CONSTRUCT {
+    ?person ex:status ex:active .
+}
+WHERE {
+    ?person ex:member ex:club .
+    NOT { ?person ex:suspended "true" . }
+}
+"#;
+        let (_, inferred) = process_rule_definition(rule, &mut db).expect("rule processing failed");
+
+        assert_eq!(inferred.len(), 1);
+        assert!(has_triple(&db, "http://example.org/alice", "http://example.org/status", "http://example.org/active"));
+        assert!(!has_triple(&db, "http://example.org/bob", "http://example.org/status", "http://example.org/active"));
+    }
+
+    #[test]
+    fn test_rule_with_unstratifiable_negation_is_rejected() {
+        let mut db = SparqlDatabase::new();
+        db.add_triple_parts("http://example.org/alice", "http://example.org/member", "http://example.org/club");
+
+        let rule = r#"
+PREFIX ex: <http://example.org/>
+
+RULE :Paradox :-
+CONSTRUCT {
+    ?person ex:status ex:active .
+}
+WHERE {
+    ?person ex:member ex:club .
+    NOT { ?person ex:status ex:active . }
+}
+"#;
+        let err = process_rule_definition(rule, &mut db).unwrap_err();
+        assert!(err.contains("unstratifiable negation"), "unexpected error: {}", err);
+        assert_eq!(db.triples.len(), 1);
+    }
+}
@@ -0,0 +1,753 @@
+/*
+ * Copyright © 2026 Volodymyr Kadzhaia
+ * Copyright © 2026 Pieter Bonte
+ * KU Leuven — Stream Intelligence Lab, Belgium
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this file,
+ * you can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+//! Manifest-driven conformance harness for the W3C SPARQL 1.1 test suite
+//! (https://www.w3.org/2009/sparql/docs/tests/).
+//!
+//! `bundled_conformance_tests` runs the suite in `tests/sparql_conformance`,
+//! which uses the W3C manifest layout. The full W3C suite is not vendored; to
+//! measure it, download and unpack `sparql11-test-suite` and run
+//!
+//! ```text
+//! KOLIBRIE_SPARQL11_TESTS=/path/to/sparql11/manifest-all.ttl \
+//!     cargo test -p kolibrie --test sparql_conformance -- --ignored --nocapture
+//! ```
+//!
+//! which prints how many tests pass. Query evaluation tests compare solutions
+//! as multisets: row order, literal datatypes and blank node labels are not
+//! compared. Update, federation, entailment and CSV/TSV result tests are
+//! reported as skipped.
+
+extern crate kolibrie;
+use kolibrie::custom_error::SparqlError;
+use kolibrie::execute_query::{execute_query_with_timeout, query_result_variables};
+use kolibrie::sparql_database::SparqlDatabase;
+use kolibrie::sparql_results::{classify_term, ResultTerm};
+use quick_xml::events::{BytesRef, BytesStart, Event};
+use quick_xml::escape::resolve_predefined_entity;
+use quick_xml::Reader;
+use std::collections::{BTreeMap, HashMap};
+use std::fs;
+use std::panic::{catch_unwind, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+const RDF: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#";
+const MF: &str = "http://www.w3.org/2001/sw/DataAccess/tests/test-manifest#";
+const QT: &str = "http://www.w3.org/2001/sw/DataAccess/tests/test-query#";
+
+/// Bundled tests of features the engine does not implement yet, by test id.
+/// They must fail; drop an entry once its feature lands.
+const EXPECTED_FAILURES: &[&str] = &[
+    // OPTIONAL is not parsed
+    "basic/optional",
+    // Prefixed names in VALUES are not expanded
+    "basic/values",
+    // `PREFIX :` declares nothing the parser can use
+    "syntax/syntax-empty-prefix-01",
+];
+
+/// Longest a single query may run before its test fails
+const QUERY_TIMEOUT: Duration = Duration::from_secs(10);
+
+// ---------------------------------------------------------------------------
+// Manifest reading
+// ---------------------------------------------------------------------------
+
+#[derive(Debug, Clone, PartialEq)]
+enum Node {
+    Iri(String),
+    Blank(usize),
+    Literal(String),
+}
+
+/// Triples of one Turtle document; enough of Turtle for test manifests
+/// (prefixes, `a`, `;`/`,` lists, `[ ]` blank nodes and `( )` collections)
+#[derive(Default)]
+struct Graph {
+    triples: Vec<(Node, String, Node)>,
+    blank_nodes: usize,
+}
+
+impl Graph {
+    fn objects<'g>(&'g self, subject: &'g Node, predicate: &'g str) -> impl Iterator<Item = &'g Node> + 'g {
+        self.triples
+            .iter()
+            .filter(move |(s, p, _)| s == subject && p == predicate)
+            .map(|(_, _, o)| o)
+    }
+
+    fn object(&self, subject: &Node, predicate: &str) -> Option<&Node> {
+        self.triples
+            .iter()
+            .find(|(s, p, _)| s == subject && p == predicate)
+            .map(|(_, _, o)| o)
+    }
+
+    /// Members of the RDF collection starting at `head`
+    fn list(&self, head: &Node) -> Vec<Node> {
+        let (first, rest) = (format!("{}first", RDF), format!("{}rest", RDF));
+        let mut members = Vec::new();
+        let mut node = head.clone();
+        while let Some(member) = self.object(&node, &first) {
+            members.push(member.clone());
+            match self.object(&node, &rest) {
+                Some(next) => node = next.clone(),
+                None => break,
+            }
+        }
+        members
+    }
+
+    fn fresh_blank(&mut self) -> Node {
+        self.blank_nodes += 1;
+        Node::Blank(self.blank_nodes)
+    }
+}
+
+struct TurtleReader<'t> {
+    tokens: Vec<String>,
+    position: usize,
+    base: &'t str,
+    prefixes: HashMap<String, String>,
+    graph: Graph,
+}
+
+impl<'t> TurtleReader<'t> {
+    fn parse(text: &str, base: &'t str) -> Result<Graph, String> {
+        let mut reader = TurtleReader {
+            tokens: tokenize_turtle(text)?,
+            position: 0,
+            base,
+            prefixes: HashMap::new(),
+            graph: Graph::default(),
+        };
+        while reader.peek().is_some() {
+            reader.statement()?;
+        }
+        Ok(reader.graph)
+    }
+
+    fn peek(&self) -> Option<&str> {
+        self.tokens.get(self.position).map(String::as_str)
+    }
+
+    fn next(&mut self) -> Result<String, String> {
+        let token = self.tokens.get(self.position).cloned().ok_or("unexpected end of Turtle")?;
+        self.position += 1;
+        Ok(token)
+    }
+
+    fn expect(&mut self, expected: &str) -> Result<(), String> {
+        match self.next()? {
+            token if token == expected => Ok(()),
+            token => Err(format!("expected `{}`, found `{}`", expected, token)),
+        }
+    }
+
+    fn statement(&mut self) -> Result<(), String> {
+        match self.peek() {
+            Some("@prefix") | Some("PREFIX") => {
+                let sparql_style = self.next()? == "PREFIX";
+                let prefix = self.next()?;
+                let iri = self.iri(&self.tokens[self.position].clone())?;
+                self.position += 1;
+                self.prefixes.insert(prefix.trim_end_matches(':').to_string(), iri);
+                if !sparql_style {
+                    self.expect(".")?;
+                }
+            }
+            Some("@base") | Some("BASE") => {
+                return Err("@base is not supported in manifests".to_string());
+            }
+            _ => {
+                let subject = self.subject()?;
+                if self.peek() != Some(".") {
+                    self.predicate_objects(&subject)?;
+                }
+                self.expect(".")?;
+            }
+        }
+        Ok(())
+    }
+
+    fn subject(&mut self) -> Result<Node, String> {
+        match self.peek() {
+            Some("[") => self.blank_node_properties(),
+            Some("(") => self.collection(),
+            _ => {
+                let token = self.next()?;
+                Ok(Node::Iri(self.iri(&token)?))
+            }
+        }
+    }
+
+    fn predicate_objects(&mut self, subject: &Node) -> Result<(), String> {
+        loop {
+            let token = self.next()?;
+            let predicate = if token == "a" { format!("{}type", RDF) } else { self.iri(&token)? };
+            loop {
+                let object = self.object()?;
+                self.graph.triples.push((subject.clone(), predicate.clone(), object));
+                if self.peek() != Some(",") {
+                    break;
+                }
+                self.position += 1;
+            }
+            // `;` may repeat and may end the list
+            let mut more = false;
+            while self.peek() == Some(";") {
+                self.position += 1;
+                more = true;
+            }
+            if !more || matches!(self.peek(), Some(".") | Some("]")) {
+                return Ok(());
+            }
+        }
+    }
+
+    fn object(&mut self) -> Result<Node, String> {
+        match self.peek() {
+            Some("[") => self.blank_node_properties(),
+            Some("(") => self.collection(),
+            Some(token) if token.starts_with('"') || token.starts_with('\'') => {
+                let literal = self.next()?;
+                // Datatypes and language tags are not needed from manifests
+                if self.peek() == Some("^^") {
+                    self.position += 2;
+                }
+                Ok(Node::Literal(literal))
+            }
+            Some(token) if token == "true" || token == "false" || token.starts_with(|c: char| c.is_ascii_digit() || c == '-' || c == '+') => {
+                Ok(Node::Literal(self.next()?))
+            }
+            _ => {
+                let token = self.next()?;
+                Ok(Node::Iri(self.iri(&token)?))
+            }
+        }
+    }
+
+    fn blank_node_properties(&mut self) -> Result<Node, String> {
+        self.expect("[")?;
+        let node = self.graph.fresh_blank();
+        if self.peek() != Some("]") {
+            self.predicate_objects(&node)?;
+        }
+        self.expect("]")?;
+        Ok(node)
+    }
+
+    fn collection(&mut self) -> Result<Node, String> {
+        self.expect("(")?;
+        let mut members = Vec::new();
+        while self.peek() != Some(")") {
+            members.push(self.object()?);
+        }
+        self.expect(")")?;
+
+        let mut list = Node::Iri(format!("{}nil", RDF));
+        for member in members.into_iter().rev() {
+            let cell = self.graph.fresh_blank();
+            self.graph.triples.push((cell.clone(), format!("{}first", RDF), member));
+            self.graph.triples.push((cell.clone(), format!("{}rest", RDF), list));
+            list = cell;
+        }
+        Ok(list)
+    }
+
+    /// Expand an IRI reference or prefixed name; relative IRIs are resolved
+    /// against the manifest's own IRI
+    fn iri(&self, token: &str) -> Result<String, String> {
+        if let Some(reference) = token.strip_prefix('<').and_then(|t| t.strip_suffix('>')) {
+            return Ok(resolve_iri(self.base, reference));
+        }
+        if token.starts_with('_') {
+            return Err(format!("labelled blank node `{}` in a manifest", token));
+        }
+        let (prefix, local) = token.split_once(':').ok_or_else(|| format!("unexpected `{}`", token))?;
+        let namespace = self.prefixes.get(prefix).ok_or_else(|| format!("unknown prefix `{}:`", prefix))?;
+        Ok(format!("{}{}", namespace, local))
+    }
+}
+
+/// Split Turtle into IRIs, names, literals and punctuation, dropping comments
+fn tokenize_turtle(text: &str) -> Result<Vec<String>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = text.chars().peekable();
+    while let Some(&ch) = chars.peek() {
+        match ch {
+            _ if ch.is_whitespace() => {
+                chars.next();
+            }
+            '#' => {
+                while chars.next().is_some_and(|c| c != '\n') {}
+            }
+            '<' => {
+                let mut token = String::new();
+                for c in chars.by_ref() {
+                    token.push(c);
+                    if c == '>' {
+                        break;
+                    }
+                }
+                tokens.push(token);
+            }
+            '"' | '\'' => {
+                chars.next();
+                let long = chars.clone().take(2).all(|c| c == ch);
+                if long {
+                    chars.next();
+                    chars.next();
+                }
+                let mut value = String::new();
+                loop {
+                    let c = chars.next().ok_or("unterminated string")?;
+                    if c == '\\' {
+                        match chars.next().ok_or("unterminated string")? {
+                            'n' => value.push('\n'),
+                            't' => value.push('\t'),
+                            other => value.push(other),
+                        }
+                    } else if c == ch && (!long || chars.clone().take(2).all(|c| c == ch)) {
+                        if long {
+                            chars.next();
+                            chars.next();
+                        }
+                        break;
+                    } else {
+                        value.push(c);
+                    }
+                }
+                tokens.push(format!("\"{}", value));
+                if chars.peek() == Some(&'@') {
+                    while chars.peek().is_some_and(|c| c.is_alphanumeric() || *c == '@' || *c == '-') {
+                        chars.next();
+                    }
+                }
+            }
+            '[' | ']' | '(' | ')' | ';' | ',' => {
+                tokens.push(ch.to_string());
+                chars.next();
+            }
+            '^' => {
+                chars.next();
+                chars.next();
+                tokens.push("^^".to_string());
+            }
+            _ => {
+                let mut token = String::new();
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() || matches!(c, '[' | ']' | '(' | ')' | ';' | ',' | '<' | '"') {
+                        break;
+                    }
+                    token.push(c);
+                    chars.next();
+                }
+                // A trailing `.` ends the statement unless the token is a number
+                if token.len() > 1 && token.ends_with('.') {
+                    token.pop();
+                    tokens.push(token);
+                    tokens.push(".".to_string());
+                } else {
+                    tokens.push(token);
+                }
+            }
+        }
+    }
+    Ok(tokens)
+}
+
+/// `reference` resolved against `base`; the empty reference is `base` itself
+fn resolve_iri(base: &str, reference: &str) -> String {
+    if reference.contains(':') {
+        return reference.to_string();
+    }
+    if reference.is_empty() {
+        return base.to_string();
+    }
+    if reference.starts_with('#') {
+        return format!("{}{}", base, reference);
+    }
+    let directory = base.rsplit_once('/').map_or("", |(directory, _)| directory);
+    format!("{}/{}", directory, reference)
+}
+
+fn file_iri(path: &Path) -> String {
+    format!("file://{}", path.display())
+}
+
+fn iri_path(node: &Node) -> Option<PathBuf> {
+    match node {
+        Node::Iri(iri) => iri.strip_prefix("file://").map(PathBuf::from),
+        _ => None,
+    }
+}
+
+#[derive(Debug)]
+enum TestKind {
+    QueryEvaluation,
+    PositiveSyntax,
+    NegativeSyntax,
+    /// Any other manifest test type, reported as skipped
+    Other(String),
+}
+
+#[derive(Debug)]
+struct TestCase {
+    /// `<manifest directory>/<mf:name>`, relative to the suite root
+    id: String,
+    kind: TestKind,
+    query: Option<PathBuf>,
+    data: Vec<PathBuf>,
+    result: Option<PathBuf>,
+}
+
+/// Tests of the manifest at `path` and of the manifests it includes
+fn load_manifest(path: &Path, root: &Path) -> Result<Vec<TestCase>, String> {
+    let text = fs::read_to_string(path).map_err(|err| format!("{}: {}", path.display(), err))?;
+    let base = file_iri(path);
+    let graph = TurtleReader::parse(&text, &base).map_err(|err| format!("{}: {}", path.display(), err))?;
+    let manifest = Node::Iri(base);
+    let directory = path
+        .parent()
+        .and_then(|dir| dir.strip_prefix(root).ok())
+        .map(|dir| dir.display().to_string())
+        .unwrap_or_default();
+
+    let mut tests = Vec::new();
+    for include in graph.objects(&manifest, &format!("{}include", MF)) {
+        for included in graph.list(include) {
+            let included = iri_path(&included).ok_or("mf:include must name files")?;
+            tests.extend(load_manifest(&included, root)?);
+        }
+    }
+
+    let entries = graph.object(&manifest, &format!("{}entries", MF)).map(|head| graph.list(head)).unwrap_or_default();
+    for entry in entries {
+        let name = match graph.object(&entry, &format!("{}name", MF)) {
+            Some(Node::Literal(name)) => name.trim_start_matches('"').to_string(),
+            _ => format!("{:?}", entry),
+        };
+        let kind = match graph.object(&entry, &format!("{}type", RDF)) {
+            Some(Node::Iri(iri)) => match iri.strip_prefix(MF).unwrap_or(iri) {
+                "QueryEvaluationTest" => TestKind::QueryEvaluation,
+                "PositiveSyntaxTest" | "PositiveSyntaxTest11" => TestKind::PositiveSyntax,
+                "NegativeSyntaxTest" | "NegativeSyntaxTest11" => TestKind::NegativeSyntax,
+                other => TestKind::Other(other.to_string()),
+            },
+            _ => TestKind::Other("untyped".to_string()),
+        };
+
+        // Syntax tests name the query directly, evaluation tests a blank node
+        let action = graph.object(&entry, &format!("{}action", MF));
+        let (query, data) = match action {
+            Some(Node::Blank(_)) => {
+                let action = action.unwrap();
+                let query = graph.object(action, &format!("{}query", QT)).and_then(iri_path);
+                let data = graph.objects(action, &format!("{}data", QT)).filter_map(iri_path).collect();
+                (query, data)
+            }
+            Some(node) => (iri_path(node), Vec::new()),
+            None => (None, Vec::new()),
+        };
+        let result = graph.object(&entry, &format!("{}result", MF)).and_then(iri_path);
+
+        let id = if directory.is_empty() { name } else { format!("{}/{}", directory, name) };
+        tests.push(TestCase { id, kind, query, data, result });
+    }
+    Ok(tests)
+}
+
+// ---------------------------------------------------------------------------
+// Expected results
+// ---------------------------------------------------------------------------
+
+/// One solution: variable name to normalised term (see `normalize_term`)
+type Solution = BTreeMap<String, String>;
+
+enum ExpectedResults {
+    Solutions(Vec<Solution>),
+    /// An ASK result, which the harness does not compare
+    Boolean,
+}
+
+fn literal_key(value: &str, lang: Option<&str>) -> String {
+    match lang {
+        Some(lang) => format!("\"{}\"@{}", value, lang.to_ascii_lowercase()),
+        None => format!("\"{}\"", value),
+    }
+}
+
+/// A result value from the engine in the form expected results are read into
+fn normalize_term(value: &str) -> String {
+    match classify_term(value) {
+        ResultTerm::Iri(iri) => format!("<{}>", iri),
+        ResultTerm::BlankNode(_) => "_:".to_string(),
+        ResultTerm::Literal { value, lang, .. } => literal_key(value, lang),
+    }
+}
+
+fn read_srx(text: &str) -> Result<ExpectedResults, String> {
+    let mut reader = Reader::from_str(text);
+    let mut solutions = Vec::new();
+    let mut solution = Solution::new();
+    let mut binding = String::new();
+    let mut lang: Option<String> = None;
+    let mut ask = false;
+    let mut value = String::new();
+
+    loop {
+        match reader.read_event().map_err(|err| err.to_string())? {
+            Event::Start(e) => {
+                value.clear();
+                match e.local_name().as_ref() {
+                    b"binding" => binding = attribute(&e, b"name").unwrap_or_default(),
+                    b"literal" => lang = attribute(&e, b"xml:lang"),
+                    _ => {}
+                }
+            }
+            Event::Empty(e) => match e.local_name().as_ref() {
+                b"literal" => {
+                    solution.insert(binding.clone(), literal_key("", attribute(&e, b"xml:lang").as_deref()));
+                }
+                b"result" => solutions.push(Solution::new()),
+                _ => {}
+            },
+            Event::Text(e) => value.push_str(&reader.decoder().decode(e.as_ref()).map_err(|err| err.to_string())?),
+            Event::GeneralRef(e) => push_reference(&mut value, &e),
+            Event::End(e) => match e.local_name().as_ref() {
+                b"uri" => {
+                    solution.insert(binding.clone(), format!("<{}>", value));
+                }
+                b"bnode" => {
+                    solution.insert(binding.clone(), "_:".to_string());
+                }
+                b"literal" => {
+                    solution.insert(binding.clone(), literal_key(&value, lang.take().as_deref()));
+                }
+                b"result" => solutions.push(std::mem::take(&mut solution)),
+                b"boolean" => ask = true,
+                _ => {}
+            },
+            Event::Eof => break,
+            _ => {}
+        }
+    }
+
+    if ask {
+        return Ok(ExpectedResults::Boolean);
+    }
+    Ok(ExpectedResults::Solutions(solutions))
+}
+
+fn attribute(element: &BytesStart, key: &[u8]) -> Option<String> {
+    element
+        .attributes()
+        .filter_map(Result::ok)
+        .find(|attr| attr.key.as_ref() == key)
+        .and_then(|attr| attr.unescape_value().ok().map(|value| value.into_owned()))
+}
+
+/// Append the text an entity or character reference stands for
+fn push_reference(value: &mut String, reference: &BytesRef) {
+    let name = reference.decode().unwrap_or_default();
+    if let Ok(Some(ch)) = reference.resolve_char_ref() {
+        value.push(ch);
+    } else if let Some(entity) = resolve_predefined_entity(&name) {
+        value.push_str(entity);
+    }
+}
+
+fn read_srj(text: &str) -> Result<ExpectedResults, String> {
+    let json: serde_json::Value = serde_json::from_str(text).map_err(|err| err.to_string())?;
+    if json.get("boolean").is_some() {
+        return Ok(ExpectedResults::Boolean);
+    }
+    let bindings = json["results"]["bindings"].as_array().ok_or("missing results.bindings")?;
+    let solutions = bindings
+        .iter()
+        .map(|binding| {
+            let binding = binding.as_object().ok_or("binding is not an object")?;
+            binding
+                .iter()
+                .map(|(name, term)| {
+                    let value = term["value"].as_str().unwrap_or_default();
+                    let normalized = match term["type"].as_str() {
+                        Some("uri") => format!("<{}>", value),
+                        Some("bnode") => "_:".to_string(),
+                        _ => literal_key(value, term["xml:lang"].as_str()),
+                    };
+                    Ok((name.clone(), normalized))
+                })
+                .collect::<Result<Solution, String>>()
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+    Ok(ExpectedResults::Solutions(solutions))
+}
+
+// ---------------------------------------------------------------------------
+// Running tests
+// ---------------------------------------------------------------------------
+
+enum Outcome {
+    Pass,
+    Fail(String),
+    Skip(String),
+}
+
+fn read(path: &Path) -> Result<String, String> {
+    fs::read_to_string(path).map_err(|err| format!("{}: {}", path.display(), err))
+}
+
+fn load_data(database: &mut SparqlDatabase, path: &Path) -> Result<(), String> {
+    let text = read(path)?;
+    match path.extension().and_then(|ext| ext.to_str()) {
+        Some("ttl") | Some("n3") => database.parse_turtle(&text),
+        Some("nt") => database.parse_ntriples_and_add(&text),
+        Some("rdf") => database.parse_rdf(&text),
+        other => return Err(format!("unsupported data format {:?}", other)),
+    }
+    Ok(())
+}
+
+fn run_query(query: &str, database: &mut SparqlDatabase) -> Result<Vec<Vec<String>>, String> {
+    catch_unwind(AssertUnwindSafe(|| execute_query_with_timeout(query, database, QUERY_TIMEOUT)))
+        .map_err(|_| "the engine panicked".to_string())?
+        .map_err(|err| err.to_string())
+}
+
+fn run_test(test: &TestCase) -> Outcome {
+    let Some(query_path) = &test.query else {
+        return Outcome::Skip("no query".to_string());
+    };
+    let query = match read(query_path) {
+        Ok(query) => query,
+        Err(err) => return Outcome::Fail(err),
+    };
+
+    match &test.kind {
+        TestKind::Other(kind) => Outcome::Skip(kind.clone()),
+        TestKind::PositiveSyntax | TestKind::NegativeSyntax => {
+            let positive = matches!(test.kind, TestKind::PositiveSyntax);
+            let parsed = match catch_unwind(AssertUnwindSafe(|| {
+                execute_query_with_timeout(&query, &mut SparqlDatabase::new(), QUERY_TIMEOUT)
+            })) {
+                Ok(Err(SparqlError::ParseError(_))) => false,
+                Ok(_) => true,
+                Err(_) => return Outcome::Fail("the engine panicked".to_string()),
+            };
+            match (positive, parsed) {
+                (true, false) => Outcome::Fail("valid query rejected".to_string()),
+                (false, true) => Outcome::Fail("invalid query accepted".to_string()),
+                _ => Outcome::Pass,
+            }
+        }
+        TestKind::QueryEvaluation => {
+            let Some(result_path) = &test.result else {
+                return Outcome::Skip("no expected result".to_string());
+            };
+            let expected = match result_path.extension().and_then(|ext| ext.to_str()) {
+                Some("srx") => read(result_path).and_then(|text| read_srx(&text)),
+                Some("srj") => read(result_path).and_then(|text| read_srj(&text)),
+                other => return Outcome::Skip(format!("{:?} results", other)),
+            };
+            let mut expected = match expected {
+                Ok(ExpectedResults::Solutions(solutions)) => solutions,
+                Ok(ExpectedResults::Boolean) => return Outcome::Skip("ASK".to_string()),
+                Err(err) => return Outcome::Fail(err),
+            };
+
+            let mut database = SparqlDatabase::new();
+            for data in &test.data {
+                if let Err(err) = load_data(&mut database, data) {
+                    return Outcome::Skip(err);
+                }
+            }
+            let rows = match run_query(&query, &mut database) {
+                Ok(rows) => rows,
+                Err(err) => return Outcome::Fail(err),
+            };
+
+            let variables = query_result_variables(&query);
+            let mut actual: Vec<Solution> = rows
+                .iter()
+                .map(|row| {
+                    variables
+                        .iter()
+                        .zip(row)
+                        .filter(|(_, value)| !value.is_empty())
+                        .map(|(name, value)| (name.clone(), normalize_term(value)))
+                        .collect()
+                })
+                .collect();
+            actual.sort();
+            expected.sort();
+            if actual == expected {
+                Outcome::Pass
+            } else {
+                Outcome::Fail(format!("expected {:?}, got {:?}", expected, actual))
+            }
+        }
+    }
+}
+
+/// Outcome of every test in the manifest at `path`, keyed by test id
+fn run_suite(path: &Path) -> Vec<(String, Outcome)> {
+    let root = path.parent().unwrap_or(Path::new(""));
+    let tests = load_manifest(path, root).unwrap_or_else(|err| panic!("cannot read the manifest: {}", err));
+    let outcomes: Vec<(String, Outcome)> = tests.iter().map(|test| (test.id.clone(), run_test(test))).collect();
+
+    let count = |wanted: fn(&Outcome) -> bool| outcomes.iter().filter(|(_, outcome)| wanted(outcome)).count();
+    println!(
+        "SPARQL conformance: {} passed, {} failed, {} skipped of {} tests",
+        count(|outcome| matches!(outcome, Outcome::Pass)),
+        count(|outcome| matches!(outcome, Outcome::Fail(_))),
+        count(|outcome| matches!(outcome, Outcome::Skip(_))),
+        outcomes.len(),
+    );
+    outcomes
+}
+
+#[test]
+fn bundled_conformance_tests() {
+    let manifest = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/sparql_conformance/manifest-all.ttl");
+    let outcomes = run_suite(&manifest);
+    assert!(outcomes.len() > EXPECTED_FAILURES.len());
+
+    let mut problems = Vec::new();
+    for (id, outcome) in &outcomes {
+        let expected_failure = EXPECTED_FAILURES.contains(&id.as_str());
+        match outcome {
+            Outcome::Pass if expected_failure => problems.push(format!("{}: passes, remove it from EXPECTED_FAILURES", id)),
+            Outcome::Fail(reason) if !expected_failure => problems.push(format!("{}: {}", id, reason)),
+            Outcome::Skip(reason) => problems.push(format!("{}: skipped ({})", id, reason)),
+            _ => {}
+        }
+    }
+    for id in EXPECTED_FAILURES {
+        if !outcomes.iter().any(|(test, _)| test == id) {
+            problems.push(format!("{}: listed in EXPECTED_FAILURES but not in the manifest", id));
+        }
+    }
+    assert!(problems.is_empty(), "conformance problems:\n{}", problems.join("\n"));
+}
+
+#[test]
+#[ignore = "needs a local copy of the W3C SPARQL 1.1 test suite"]
+fn w3c_sparql11_suite() {
+    let manifest = std::env::var("KOLIBRIE_SPARQL11_TESTS")
+        .expect("set KOLIBRIE_SPARQL11_TESTS to the suite's manifest-all.ttl");
+    for (id, outcome) in run_suite(Path::new(&manifest)) {
+        if let Outcome::Fail(reason) = outcome {
+            println!("FAIL {}: {}", id, reason);
+        }
+    }
+}
@@ -0,0 +1,42 @@
+#
+# Copyright © 2026 Volodymyr Kadzhaia
+# Copyright © 2026 Pieter Bonte
+# KU Leuven — Stream Intelligence Lab, Belgium
+# 
+# This Source Code Form is subject to the terms of the Mozilla Public
+# License, v. 2.0. If a copy of the MPL was not distributed with this file,
+# you can obtain one at https://mozilla.org/MPL/2.0/.
+# 
+
+'''
+Before running the test, run such commands in the terminal:
+1. cd python
+2. python3 -m venv .venv
+3. maturin develop
+4. source .venv/bin/activate
+5. run python script
+'''
+
+import kolibrie
+
+def test_iterate_triples():
+    db = kolibrie.SparqlDatabase()
+    db.parse_turtle("""
+        @prefix ex: <http://example.org/> .
+        ex:alice ex:name "Alice" .
+        ex:bob ex:name "Bob" .
+    """)
+
+    # Iterating a database yields (subject, predicate, object) string tuples
+    triples = set(db)
+    print(f"Triples: {triples}")
+    assert len(triples) == len(db)
+    assert ("http://example.org/alice", "http://example.org/name", "Alice") in triples
+
+def main():
+    print("Running triple iteration test...")
+    test_iterate_triples()
+    print("Triple iteration test passed")
+
+if __name__ == "__main__":
+    main()
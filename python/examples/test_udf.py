@@ -0,0 +1,94 @@
+#
+# Copyright © 2026 Volodymyr Kadzhaia
+# Copyright © 2026 Pieter Bonte
+# KU Leuven — Stream Intelligence Lab, Belgium
+# 
+# This Source Code Form is subject to the terms of the Mozilla Public
+# License, v. 2.0. If a copy of the MPL was not distributed with this file,
+# you can obtain one at https://mozilla.org/MPL/2.0/.
+# 
+
+'''
+Before running the test, run such commands in the terminal:
+1. cd python
+2. python3 -m venv .venv
+3. maturin develop
+4. source .venv/bin/activate
+5. run python script
+'''
+
+import kolibrie
+
+def test_python_udf_in_filter():
+    db = kolibrie.SparqlDatabase()
+    db.parse_turtle("""
+        @prefix ex: <http://example.org/> .
+        ex:alice ex:name "Alice" .
+        ex:bob ex:name "Bob" .
+    """)
+
+    # UDFs receive their arguments as a list of strings and return a string
+    db.register_udf("upper", lambda args: args[0].upper())
+
+    results = db.exec_query("""
+        PREFIX ex: <http://example.org/>
+        PREFIX myns: <http://example.org/fn/>
+        SELECT ?person
+        WHERE {
+            ?person ex:name ?name .
+            FILTER(myns:upper(?name) = "ALICE")
+        }
+    """)
+    print(f"Results: {results}")
+    assert results == [["http://example.org/alice"]]
+
+def test_python_udf_exception_is_raised():
+    db = kolibrie.SparqlDatabase()
+    db.parse_turtle("""
+        @prefix ex: <http://example.org/> .
+        ex:alice ex:name "Alice" .
+    """)
+
+    def fail(args):
+        raise KeyError("no such name")
+
+    db.register_udf("fail", fail)
+    db.register_udf("length", lambda args: len(args[0]))
+
+    query = """
+        PREFIX ex: <http://example.org/>
+        PREFIX myns: <http://example.org/fn/>
+        SELECT ?person
+        WHERE {
+            ?person ex:name ?name .
+            FILTER(myns:%s(?name) = "x")
+        }
+    """
+    # The UDF's own exception reaches the caller
+    try:
+        db.exec_query(query % "fail")
+    except KeyError as err:
+        print(f"Raised: {err!r}")
+    else:
+        raise AssertionError("a failing UDF must raise")
+
+    # So does the TypeError of a UDF that returns no string
+    try:
+        db.exec_query(query % "length")
+    except TypeError as err:
+        print(f"Raised: {err!r}")
+    else:
+        raise AssertionError("a UDF returning no string must raise")
+
+    # The error does not outlive the query that raised it
+    db.register_udf("upper", lambda args: args[0].upper())
+    assert db.exec_query(query.replace('"x"', '"ALICE"') % "upper") == [["http://example.org/alice"]]
+
+def main():
+    print("Running Python UDF test...")
+    test_python_udf_in_filter()
+    test_python_udf_exception_is_raised()
+    print("Python UDF test passed")
+
+if __name__ == "__main__":
+    main()
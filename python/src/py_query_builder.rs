@@ -8,11 +8,13 @@
  * you can obtain one at https://mozilla.org/MPL/2.0/.
  */
 
-use pyo3::exceptions::{PyOSError, PyRuntimeError, PyValueError};
+use pyo3::exceptions::{PyOSError, PyRuntimeError, PyTypeError, PyValueError};
 use pyo3::prelude::*;
 use pyo3::types::PyAny;
-use kolibrie_core::execute_query::execute_query_rayon_parallel2_volcano;
+use kolibrie_core::custom_error::SparqlError;
+use kolibrie_core::execute_query::try_execute_query;
 use kolibrie_core::sparql_database::SparqlDatabase;
+use shared::triple::Triple;
 use std::path::Path;
 use std::sync::{Arc, Mutex};
 use std::collections::HashMap;
@@ -51,6 +53,8 @@ fn normalize_load_format(path: &str, format: Option<&str>) -> PyResult<LoadForma
 #[pyclass(name = "SparqlDatabase")]
 pub struct PySparqlDatabase {
     db: Arc<Mutex<SparqlDatabase>>,
+    /// First exception a Python UDF raised during the running query
+    udf_error: Arc<Mutex<Option<PyErr>>>,
 }
 
 #[pymethods]
@@ -59,6 +63,7 @@ impl PySparqlDatabase {
     fn new() -> Self {
         PySparqlDatabase {
             db: Arc::new(Mutex::new(SparqlDatabase::new())),
+            udf_error: Arc::new(Mutex::new(None)),
         }
     }
 
@@ -69,6 +74,36 @@ impl PySparqlDatabase {
         }
     }
 
+    /// Number of triples in the database.
+    fn count_triples(&self) -> PyResult<usize> {
+        let db = self
+            .db
+            .lock()
+            .map_err(|_| PyRuntimeError::new_err("Failed to acquire database lock"))?;
+        Ok(db.count_triples())
+    }
+
+    fn is_empty(&self) -> PyResult<bool> {
+        Ok(self.count_triples()? == 0)
+    }
+
+    fn __len__(&self) -> PyResult<usize> {
+        self.count_triples()
+    }
+
+    /// Iterate over the triples as `(subject, predicate, object)` tuples of
+    /// strings. Triples added while iterating are not visited.
+    fn __iter__(&self) -> PyResult<PyTripleIterator> {
+        let db = self
+            .db
+            .lock()
+            .map_err(|_| PyRuntimeError::new_err("Failed to acquire database lock"))?;
+        Ok(PyTripleIterator {
+            db: Arc::clone(&self.db),
+            triples: db.asserted_triples().cloned().collect::<Vec<_>>().into_iter(),
+        })
+    }
+
     fn parse_turtle(&self, turtle: &str) {
         if let Ok(mut db) = self.db.lock() {
             db.parse_turtle(turtle);
@@ -111,12 +146,59 @@ impl PySparqlDatabase {
         Ok(())
     }
 
-    fn exec_query(&self, query: &str) -> Vec<Vec<String>> {
-        if let Ok(mut db) = self.db.lock() {
-            execute_query_rayon_parallel2_volcano(query, &mut db)
-        } else {
-            Vec::new()
+    /// Run a query and return its rows. Raises `ValueError` for malformed
+    /// queries, `RuntimeError` when evaluation fails and re-raises the first
+    /// exception of a UDF the query called.
+    fn exec_query(&self, py: Python<'_>, query: &str) -> PyResult<Vec<Vec<String>>> {
+        // Release the GIL so Python UDFs can be called from the worker threads
+        let (result, udf_error) = py.allow_threads(|| {
+            let Ok(mut db) = self.db.lock() else {
+                return (Err(SparqlError::ExecutionError("Failed to acquire database lock".to_string())), None);
+            };
+            self.udf_error.lock().unwrap().take();
+            let result = try_execute_query(query, &mut db);
+            // Taken while the database is still locked, so a query running
+            // on another thread cannot claim this query's UDF error
+            (result, self.udf_error.lock().unwrap().take())
+        });
+        if let Some(err) = udf_error {
+            return Err(err);
+        }
+        result.map_err(|err| match err {
+            SparqlError::ParseError(_) | SparqlError::TypeError(_) => PyValueError::new_err(err.to_string()),
+            _ => PyRuntimeError::new_err(err.to_string()),
+        })
+    }
+
+    /// Register a Python function as a SPARQL UDF. It receives the argument
+    /// values as a list of strings and must return a string; an exception it
+    /// raises, or a value that is not a string, is raised from `exec_query`.
+    fn register_udf(&self, name: &str, func: &Bound<'_, PyAny>) -> PyResult<()> {
+        if !func.is_callable() {
+            return Err(PyTypeError::new_err(format!("UDF '{name}' is not callable")));
         }
+        let func: Py<PyAny> = func.clone().unbind();
+        let udf_error = Arc::clone(&self.udf_error);
+        let udf = move |args: Vec<&str>| {
+            Python::with_gil(|py| {
+                match func.call1(py, (args,)).and_then(|value| value.extract::<String>(py)) {
+                    Ok(value) => value,
+                    Err(err) => {
+                        // The query finishes with an empty value and the
+                        // first error is raised once it returns
+                        udf_error.lock().unwrap().get_or_insert(err);
+                        String::new()
+                    }
+                }
+            })
+        };
+
+        let mut db = self
+            .db
+            .lock()
+            .map_err(|_| PyRuntimeError::new_err("Failed to acquire database lock"))?;
+        db.register_udf(name, udf);
+        Ok(())
     }
 
     /// Start building a query.
@@ -151,6 +233,36 @@ impl PySparqlDatabase {
     }
 }
 
+/// Iterator returned by `SparqlDatabase.__iter__`; holds the triples' IDs and
+/// decodes each one when it is reached
+#[pyclass(name = "TripleIterator")]
+pub struct PyTripleIterator {
+    db: Arc<Mutex<SparqlDatabase>>,
+    triples: std::vec::IntoIter<Triple>,
+}
+
+#[pymethods]
+impl PyTripleIterator {
+    fn __iter__(slf: PyRef<'_, Self>) -> PyRef<'_, Self> {
+        slf
+    }
+
+    fn __next__(&mut self) -> PyResult<Option<(String, String, String)>> {
+        let Some(triple) = self.triples.next() else {
+            return Ok(None);
+        };
+        let db = self
+            .db
+            .lock()
+            .map_err(|_| PyRuntimeError::new_err("Failed to acquire database lock"))?;
+        Ok(Some((
+            db.decode_any(triple.subject).unwrap_or_default(),
+            db.decode_any(triple.predicate).unwrap_or_default(),
+            db.decode_any(triple.object).unwrap_or_default(),
+        )))
+    }
+}
+
 #[pyclass(name = "SortDirection")]
 #[derive(Clone)]
 pub enum PySortDirection {
@@ -801,6 +913,7 @@ impl PyQueryBuilder {
 pub fn register(m: &Bound<'_, PyModule>) -> PyResult<()> {
     // Register classes
     m.add_class::<PySparqlDatabase>()?;
+    m.add_class::<PyTripleIterator>()?;
     m.add_class::<PyQueryBuilder>()?;
     m.add_class::<PyStreamingQuery>()?;
     m.add_class::<PyPeriodicReportStrategy>()?;
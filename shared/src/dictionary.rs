@@ -11,20 +11,21 @@
 use std::collections::HashMap;
 use crate::triple::Triple;
 use crate::quoted_triple_store::{QuotedTripleStore, is_quoted_triple_id, QUOTED_TRIPLE_ID_BIT};
+use crate::simd_hash::SimdFnvBuildHasher;
 
 // Dictionary for encoding and decoding strings
 #[derive(Debug, Default, Clone, PartialEq, Eq)]
 pub struct Dictionary {
-    pub string_to_id: HashMap<String, u32>,
-    pub id_to_string: HashMap<u32, String>,
+    pub string_to_id: HashMap<String, u32, SimdFnvBuildHasher>,
+    pub id_to_string: HashMap<u32, String, SimdFnvBuildHasher>,
     pub next_id: u32,
 }
 
 impl Dictionary {
     pub fn new() -> Self {
         Dictionary {
-            string_to_id: HashMap::new(),
-            id_to_string: HashMap::new(),
+            string_to_id: HashMap::default(),
+            id_to_string: HashMap::default(),
             next_id: 0,
         }
     }
@@ -23,6 +23,9 @@ pub struct UnifiedIndex {
     pub pso: HashMap<u32, HashMap<u32, HashSet<u32>>>,
     pub ops: HashMap<u32, HashMap<u32, HashSet<u32>>>,
     pub sop: HashMap<u32, HashMap<u32, HashSet<u32>>>,
+    /// Number of distinct triples indexed
+    #[serde(default)]
+    len: usize,
 }
 
 impl UnifiedIndex {
@@ -34,9 +37,19 @@ impl UnifiedIndex {
             pso: HashMap::new(),
             ops: HashMap::new(),
             sop: HashMap::new(),
+            len: 0,
         }
     }
 
+    /// Number of triples in the index
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
     /// Insert a single triple into all six indexes
     pub fn insert(&mut self, triple: &Triple) -> bool {
         let Triple { subject: s, predicate: p, object: o } = *triple;
@@ -53,6 +66,7 @@ impl UnifiedIndex {
         self.pso.entry(p).or_default().entry(s).or_default().insert(o);
         self.ops.entry(o).or_default().entry(p).or_default().insert(s);
         self.sop.entry(s).or_default().entry(o).or_default().insert(p);
+        self.len += 1;
         true
     }
 
@@ -76,7 +90,8 @@ impl UnifiedIndex {
         remove_from_index(&mut self.pso, p, s, o);
         remove_from_index(&mut self.ops, o, p, s);
         remove_from_index(&mut self.sop, s, o, p);
-        true 
+        self.len -= 1;
+        true
     }
 
     /// Bulk-build the index from a list of triples
@@ -173,6 +188,7 @@ impl UnifiedIndex {
                .entry(o).or_insert_with(|| HashSet::with_capacity(16))
                .insert(p);
         
+        self.len += 1;
         true
     }
     
@@ -366,6 +382,7 @@ impl UnifiedIndex {
         self.pso.clear();
         self.ops.clear();
         self.sop.clear();
+        self.len = 0;
     }
 
     /// Scan using the Subject-Predicate index (spo)
@@ -456,6 +473,9 @@ impl UnifiedIndex {
                 entry.entry(o).or_insert_with(HashSet::new).extend(pred_set);
             }
         }
+
+        // The two indexes may share triples, so count the merged result
+        self.len = self.spo.values().flat_map(|pred_map| pred_map.values()).map(HashSet::len).sum();
     }
 
     pub fn optimize(&mut self) {
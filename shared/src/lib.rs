@@ -9,6 +9,8 @@
  */
 
 pub mod dictionary;
+pub mod simd_hash;
+pub mod simd_scan;
 pub mod triple;
 pub mod index_manager;
 pub mod terms;
@@ -8,7 +8,9 @@
  * you can obtain one at https://mozilla.org/MPL/2.0/.
  */
 
-use std::collections::HashMap;
+use crate::dictionary::Dictionary;
+use std::collections::{BTreeMap, HashMap};
+use std::sync::Arc;
 use std::time::Duration;
 
 #[derive(Debug, Clone)]
@@ -19,6 +21,215 @@ pub enum FilterExpression<'a> {
     Not(Box<FilterExpression<'a>>),
     ArithmeticExpr(Box<ArithmeticExpression<'a>>),
     FunctionCall(&'a str, Vec<&'a str>),
+    /// Function call compared against a value, e.g. `ex:upper(?name) = "ALICE"`
+    FunctionComparison(&'a str, Vec<&'a str>, &'a str, &'a str),
+    /// Built-in string function used as a condition, e.g. `CONTAINS(?name, "li")`
+    StringFunction(StringFunction, Vec<StringExpression<'a>>),
+    /// Built-in string function compared against a value, e.g. `STRLEN(?name) > 3`
+    StringFunctionComparison(StringFunction, Vec<StringExpression<'a>>, &'a str, &'a str),
+    /// Built-in numeric function compared against a value, e.g. `ABS(?diff) < 0.01`
+    NumericFunction(NumericFunction, Vec<ArithmeticExpression<'a>>, &'a str, &'a str),
+    /// `COALESCE(?x, ?y)` used as a condition: true when any argument evaluates
+    Coalesce(Vec<StringExpression<'a>>),
+    /// `IF(cond, then, else)` used as a condition: the effective boolean value
+    /// of the chosen branch, or the branch compared against a value when the
+    /// last field is `Some((operator, value))`, e.g. `IF(?vip, ?rate, 0) > 5`
+    If(
+        Box<FilterExpression<'a>>,
+        StringExpression<'a>,
+        StringExpression<'a>,
+        Option<(&'a str, &'a str)>,
+    ),
+    /// `IRI(?str)` used as a condition: true when it builds a well-formed
+    /// IRI, or that IRI compared against a value, e.g. `IRI(?page) = <http://example.org/>`
+    Iri(StringExpression<'a>, Option<(&'a str, &'a str)>),
+}
+
+impl<'a> FilterExpression<'a> {
+    /// Variables (e.g. `?name`) the filter reads
+    pub fn variables(&self) -> Vec<&'a str> {
+        let var = |term: &&'a str| term.starts_with('?');
+        match self {
+            Self::Comparison(left, _, right) => [*left, *right].into_iter().filter(var).collect(),
+            Self::And(left, right) | Self::Or(left, right) => {
+                let mut vars = left.variables();
+                vars.extend(right.variables());
+                vars
+            }
+            Self::Not(inner) => inner.variables(),
+            Self::ArithmeticExpr(expr) => expr.variables(),
+            Self::FunctionCall(_, args) => args.iter().copied().filter(var).collect(),
+            Self::FunctionComparison(_, args, _, value) => {
+                args.iter().chain(std::iter::once(value)).copied().filter(var).collect()
+            }
+            Self::StringFunction(_, args) | Self::Coalesce(args) => {
+                args.iter().flat_map(|arg| arg.variables()).collect()
+            }
+            Self::StringFunctionComparison(_, args, _, value) => {
+                let mut vars: Vec<&'a str> = args.iter().flat_map(|arg| arg.variables()).collect();
+                vars.extend(Some(*value).filter(var));
+                vars
+            }
+            Self::NumericFunction(_, args, _, value) => {
+                let mut vars: Vec<&'a str> = args.iter().flat_map(|arg| arg.variables()).collect();
+                vars.extend(Some(*value).filter(var));
+                vars
+            }
+            Self::If(condition, then, otherwise, comparison) => {
+                let mut vars = condition.variables();
+                vars.extend(then.variables());
+                vars.extend(otherwise.variables());
+                vars.extend(comparison.map(|(_, value)| value).filter(var));
+                vars
+            }
+            Self::Iri(arg, comparison) => {
+                let mut vars = arg.variables();
+                vars.extend(comparison.map(|(_, value)| value).filter(var));
+                vars
+            }
+        }
+    }
+}
+
+/// SPARQL 1.1 built-in string functions
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum StringFunction {
+    Concat,
+    Strlen,
+    Substr,
+    Ucase,
+    Lcase,
+    Contains,
+    StrStarts,
+    StrEnds,
+    EncodeForUri,
+    Lang,
+    LangMatches,
+    Regex,
+}
+
+impl StringFunction {
+    pub const ALL: [StringFunction; 12] = [
+        Self::Concat,
+        Self::Strlen,
+        Self::Substr,
+        Self::Ucase,
+        Self::Lcase,
+        Self::Contains,
+        Self::StrStarts,
+        Self::StrEnds,
+        Self::EncodeForUri,
+        Self::Lang,
+        Self::LangMatches,
+        Self::Regex,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Concat => "CONCAT",
+            Self::Strlen => "STRLEN",
+            Self::Substr => "SUBSTR",
+            Self::Ucase => "UCASE",
+            Self::Lcase => "LCASE",
+            Self::Contains => "CONTAINS",
+            Self::StrStarts => "STRSTARTS",
+            Self::StrEnds => "STRENDS",
+            Self::EncodeForUri => "ENCODE_FOR_URI",
+            Self::Lang => "LANG",
+            Self::LangMatches => "LANGMATCHES",
+            Self::Regex => "REGEX",
+        }
+    }
+
+    /// Look a function up by its (case-insensitive) SPARQL name
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|f| f.name().eq_ignore_ascii_case(name))
+    }
+}
+
+/// Argument of a built-in string function
+#[derive(Debug, Clone)]
+pub enum StringExpression<'a> {
+    Variable(&'a str),
+    /// Lexical form of a string or numeric literal, without quotes
+    Literal(&'a str),
+    Call(StringFunction, Vec<StringExpression<'a>>),
+}
+
+impl<'a> StringExpression<'a> {
+    /// Variables (e.g. `?name`) read by the expression
+    pub fn variables(&self) -> Vec<&'a str> {
+        match self {
+            Self::Variable(var) => vec![*var],
+            Self::Literal(_) => Vec::new(),
+            Self::Call(_, args) => args.iter().flat_map(|arg| arg.variables()).collect(),
+        }
+    }
+}
+
+/// SPARQL 1.1 built-in numeric functions
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NumericFunction {
+    Abs,
+    Ceil,
+    Floor,
+    Round,
+    Rand,
+}
+
+impl NumericFunction {
+    pub const ALL: [NumericFunction; 5] = [
+        Self::Abs,
+        Self::Ceil,
+        Self::Floor,
+        Self::Round,
+        Self::Rand,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Abs => "ABS",
+            Self::Ceil => "CEIL",
+            Self::Floor => "FLOOR",
+            Self::Round => "ROUND",
+            Self::Rand => "RAND",
+        }
+    }
+
+    /// Look a function up by its (case-insensitive) SPARQL name
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|f| f.name().eq_ignore_ascii_case(name))
+    }
+}
+
+/// Computed projection in SELECT, e.g. `(ROUND(?price) AS ?rounded)`
+#[derive(Debug, Clone)]
+pub enum SelectExpression<'a> {
+    NumericFunction(NumericFunction, Vec<ArithmeticExpression<'a>>),
+    /// `COALESCE(?x, ?y, "default")`: the first argument that evaluates
+    Coalesce(Vec<StringExpression<'a>>),
+    /// `IF(?age >= 18, "adult", "minor")`: the second argument when the
+    /// condition holds, the third otherwise
+    If(FilterExpression<'a>, StringExpression<'a>, StringExpression<'a>),
+    /// `IRI(CONCAT("http://example.org/", ?id))`: the IRI the argument spells
+    Iri(StringExpression<'a>),
+}
+
+impl<'a> SelectExpression<'a> {
+    /// Variables the expression reads
+    pub fn variables(&self) -> Vec<&'a str> {
+        match self {
+            Self::NumericFunction(_, args) => args.iter().flat_map(|arg| arg.variables()).collect(),
+            Self::Coalesce(args) => args.iter().flat_map(|arg| arg.variables()).collect(),
+            Self::If(condition, then, otherwise) => {
+                let mut vars = condition.variables();
+                vars.extend(then.variables());
+                vars.extend(otherwise.variables());
+                vars
+            }
+            Self::Iri(arg) => arg.variables(),
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -31,6 +242,19 @@ pub enum ArithmeticExpression<'a> {
 }
 
 impl<'a> ArithmeticExpression<'a> {
+    /// Variables (e.g. `?x`) used as operands
+    pub fn variables(&self) -> Vec<&'a str> {
+        match self {
+            Self::Operand(s) if s.starts_with('?') => vec![*s],
+            Self::Operand(_) => Vec::new(),
+            Self::Add(l, r) | Self::Subtract(l, r) | Self::Multiply(l, r) | Self::Divide(l, r) => {
+                let mut vars = l.variables();
+                vars.extend(r.variables());
+                vars
+            }
+        }
+    }
+
     /// Evaluate the expression. `resolve` maps variable strings (e.g. `?x`) to f64 values.
     pub fn evaluate<F: Fn(&str) -> Option<f64>>(&self, resolve: &F) -> Result<f64, String> {
         match self {
@@ -56,6 +280,71 @@ impl<'a> ArithmeticExpression<'a> {
     }
 }
 
+/// One row of join results: each variable is bound to the ID of a dictionary
+/// term, so joins compare and clone IDs and only decode values when asked to
+#[derive(Debug, Clone, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Binding {
+    terms: BTreeMap<Arc<str>, u32>,
+}
+
+impl Binding {
+    pub fn new() -> Self {
+        Binding::default()
+    }
+
+    /// Encode a row of decoded values, adding any value the dictionary does
+    /// not know yet
+    pub fn encode<'r, I>(row: I, dictionary: &mut Dictionary) -> Self
+    where
+        I: IntoIterator<Item = (&'r str, &'r str)>,
+    {
+        let mut binding = Binding::new();
+        for (var, value) in row {
+            binding.bind(var, dictionary.encode(value));
+        }
+        binding
+    }
+
+    /// Bind `var` to a term ID, replacing any earlier binding. Passing an
+    /// `Arc<str>` lets callers share one allocation per variable.
+    pub fn bind(&mut self, var: impl Into<Arc<str>>, id: u32) {
+        self.terms.insert(var.into(), id);
+    }
+
+    pub fn id(&self, var: &str) -> Option<u32> {
+        self.terms.get(var).copied()
+    }
+
+    /// The decoded value bound to `var`
+    pub fn get<'d>(&self, var: &str, dictionary: &'d Dictionary) -> Option<&'d str> {
+        dictionary.decode(self.id(var)?)
+    }
+
+    pub fn contains(&self, var: &str) -> bool {
+        self.terms.contains_key(var)
+    }
+
+    pub fn len(&self) -> usize {
+        self.terms.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.terms.is_empty()
+    }
+
+    /// Variables and term IDs in variable order
+    pub fn iter(&self) -> impl Iterator<Item = (&str, u32)> {
+        self.terms.iter().map(|(var, id)| (var.as_ref(), *id))
+    }
+
+    /// Every binding decoded, or `None` if an ID is not a dictionary term
+    pub fn decode(&self, dictionary: &Dictionary) -> Option<BTreeMap<String, String>> {
+        self.iter()
+            .map(|(var, id)| Some((var.to_string(), dictionary.decode(id)?.to_string())))
+            .collect()
+    }
+}
+
 // Define the Value enum to represent terms or UNDEF in VALUES clause
 #[derive(Debug, Clone)]
 pub enum Value {
@@ -82,6 +371,17 @@ pub struct DeleteClause<'a> {
     pub triples: Vec<(&'a str, &'a str, &'a str)>,
 }
 
+// A SPARQL Update operation: INSERT DATA, DELETE DATA, DELETE WHERE or
+// DELETE { } INSERT { } WHERE { } with either template optional
+#[derive(Debug, Clone)]
+pub struct UpdateOperation<'a> {
+    pub prefixes: HashMap<String, String>,
+    pub delete_triples: Vec<(&'a str, &'a str, &'a str)>,
+    pub insert_triples: Vec<(&'a str, &'a str, &'a str)>,
+    /// Text inside the braces of the WHERE group; `None` for ground updates
+    pub where_clause: Option<&'a str>,
+}
+
 #[derive(Debug, Clone)]
 pub struct SubQuery<'a> {
     pub variables: Vec<(&'a str, &'a str, Option<&'a str>)>, // SELECT variables
@@ -89,7 +389,9 @@ pub struct SubQuery<'a> {
     pub filters: Vec<FilterExpression<'a>>,           // FILTER conditions
     pub binds: Vec<(&'a str, Vec<&'a str>, &'a str)>,        // BIND clauses
     pub _values_clause: Option<ValuesClause<'a>>,            // VALUES clause
+    pub group_vars: Vec<&'a str>,                            // GROUPBY variables
     pub limit: Option<usize>, // Add LIMIT support
+    pub distinct: bool,       // SELECT DISTINCT
 }
 
 #[derive(Debug, Clone)]
@@ -341,5 +643,7 @@ pub struct CombinedQuery<'a> {
         Vec<WindowBlock<'a>>,
         Vec<OrderCondition<'a>>,
     ),
+    /// The query is a `SELECT DISTINCT`
+    pub distinct: bool,
     pub delete_clause: Option<DeleteClause<'a>>,
 }
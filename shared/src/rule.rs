@@ -21,7 +21,7 @@ pub struct FilterCondition {
 #[derive(Debug, Clone)]
 pub struct Rule {
     pub premise: Vec<TriplePattern>,
-    /// Negated body atoms (NOT X) for single-stratum negation-as-failure.
+    /// Negated body atoms (NOT X) for stratified negation-as-failure.
     /// Every variable appearing here must also appear in `premise` (rule safety).
     pub negative_premise: Vec<TriplePattern>,
     pub filters: Vec<FilterCondition>,
@@ -54,4 +54,63 @@ pub fn check_rule_safety(rule: &Rule) -> Result<(), String> {
         }
     }
     Ok(())
-}
\ No newline at end of file
+}
+// Two atoms may unify unless some position holds two different constants.
+fn atoms_may_unify(a: &TriplePattern, b: &TriplePattern) -> bool {
+    fn compatible(x: &Term, y: &Term) -> bool {
+        match (x, y) {
+            (Term::Constant(l), Term::Constant(r)) => l == r,
+            _ => true,
+        }
+    }
+    compatible(&a.0, &b.0) && compatible(&a.1, &b.1) && compatible(&a.2, &b.2)
+}
+
+/// Partition `rules` into strata for stratified negation-as-failure.
+///
+/// A rule is placed at least as high as every rule whose conclusion can
+/// feed one of its positive premises, and strictly higher than every rule
+/// whose conclusion can match one of its negated premises. Returns the rule
+/// indices per stratum, lowest first, or `Err` when negation runs through a
+/// recursive cycle and no stratification exists.
+pub fn stratify_rules(rules: &[Rule]) -> Result<Vec<Vec<usize>>, String> {
+    let mut stratum = vec![0usize; rules.len()];
+    let mut changed = true;
+    while changed {
+        changed = false;
+        for (i, rule) in rules.iter().enumerate() {
+            for (j, producer) in rules.iter().enumerate() {
+                let feeds = |body: &[TriplePattern]| {
+                    body.iter().any(|atom| {
+                        producer.conclusion.iter().any(|head| atoms_may_unify(atom, head))
+                    })
+                };
+                let required = if feeds(&rule.negative_premise) {
+                    stratum[j] + 1
+                } else if feeds(&rule.premise) {
+                    stratum[j]
+                } else {
+                    continue;
+                };
+                if required > stratum[i] {
+                    // A stratum can only exceed the rule count on a negative cycle
+                    if required > rules.len() {
+                        return Err(format!(
+                            "unstratifiable negation: rule {} depends negatively on its own conclusions through a recursive cycle",
+                            i
+                        ));
+                    }
+                    stratum[i] = required;
+                    changed = true;
+                }
+            }
+        }
+    }
+
+    let height = stratum.iter().copied().max().map_or(0, |max| max + 1);
+    let mut strata = vec![Vec::new(); height];
+    for (i, level) in stratum.into_iter().enumerate() {
+        strata[level].push(i);
+    }
+    Ok(strata)
+}
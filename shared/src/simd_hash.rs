@@ -0,0 +1,203 @@
+/*
+ * Copyright © 2026 Volodymyr Kadzhaia
+ * Copyright © 2026 Pieter Bonte
+ * KU Leuven — Stream Intelligence Lab, Belgium
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this file,
+ * you can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+//! Lane-parallel FNV-1a hashing for dictionary keys.
+//!
+//! The input is consumed in 32-byte blocks, each split into eight 32-bit
+//! words that feed eight independent FNV-1a lanes. The lanes map directly
+//! onto one AVX2 register (or two NEON registers), so a block costs one xor
+//! and one multiply instead of 32 byte-wise rounds. The scalar fallback
+//! computes exactly the same value, so tables stay consistent whichever path
+//! a CPU takes.
+//!
+//! Like FNV itself this is not DoS resistant; it is meant for the engine's
+//! own term dictionary, not for maps keyed by untrusted client input.
+
+use std::hash::{BuildHasherDefault, Hasher};
+
+#[cfg(target_arch = "x86_64")]
+use std::arch::x86_64::*;
+#[cfg(target_arch = "aarch64")]
+use std::arch::aarch64::*;
+
+const LANES: usize = 8;
+const BLOCK: usize = LANES * 4;
+const FNV32_OFFSET: u32 = 0x811c_9dc5;
+const FNV32_PRIME: u32 = 0x0100_0193;
+const FNV64_OFFSET: u64 = 0xcbf2_9ce4_8422_2325;
+const FNV64_PRIME: u64 = 0x0000_0100_0000_01b3;
+
+/// Hash `bytes`, using AVX2 or NEON when the input spans at least one block.
+pub fn hash_bytes(bytes: &[u8]) -> u64 {
+    if bytes.len() >= BLOCK {
+        #[cfg(target_arch = "x86_64")]
+        {
+            if is_x86_feature_detected!("avx2") {
+                return unsafe { hash_bytes_avx2(bytes) };
+            }
+        }
+        #[cfg(target_arch = "aarch64")]
+        {
+            return unsafe { hash_bytes_neon(bytes) };
+        }
+    }
+    hash_bytes_scalar(bytes)
+}
+
+fn initial_lanes() -> [u32; LANES] {
+    let mut lanes = [0u32; LANES];
+    for (i, lane) in lanes.iter_mut().enumerate() {
+        *lane = FNV32_OFFSET ^ (i as u32).wrapping_mul(FNV32_PRIME);
+    }
+    lanes
+}
+
+/// Portable implementation; the reference for the SIMD paths.
+pub fn hash_bytes_scalar(bytes: &[u8]) -> u64 {
+    let mut lanes = initial_lanes();
+    let blocks = bytes.chunks_exact(BLOCK);
+    let tail = blocks.remainder();
+    for block in blocks {
+        for (lane, word) in lanes.iter_mut().zip(block.chunks_exact(4)) {
+            let word = u32::from_le_bytes([word[0], word[1], word[2], word[3]]);
+            *lane = (*lane ^ word).wrapping_mul(FNV32_PRIME);
+        }
+    }
+    combine(&lanes, tail, bytes.len())
+}
+
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx2")]
+unsafe fn hash_bytes_avx2(bytes: &[u8]) -> u64 {
+    let lanes = initial_lanes();
+    let mut state = _mm256_loadu_si256(lanes.as_ptr() as *const __m256i);
+    let prime = _mm256_set1_epi32(FNV32_PRIME as i32);
+
+    let blocks = bytes.len() / BLOCK;
+    for i in 0..blocks {
+        let words = _mm256_loadu_si256(bytes.as_ptr().add(i * BLOCK) as *const __m256i);
+        state = _mm256_mullo_epi32(_mm256_xor_si256(state, words), prime);
+    }
+
+    let mut lanes = [0u32; LANES];
+    _mm256_storeu_si256(lanes.as_mut_ptr() as *mut __m256i, state);
+    combine(&lanes, &bytes[blocks * BLOCK..], bytes.len())
+}
+
+#[cfg(target_arch = "aarch64")]
+#[target_feature(enable = "neon")]
+unsafe fn hash_bytes_neon(bytes: &[u8]) -> u64 {
+    let lanes = initial_lanes();
+    let mut low = vld1q_u32(lanes.as_ptr());
+    let mut high = vld1q_u32(lanes.as_ptr().add(4));
+    let prime = vdupq_n_u32(FNV32_PRIME);
+
+    let blocks = bytes.len() / BLOCK;
+    for i in 0..blocks {
+        let ptr = bytes.as_ptr().add(i * BLOCK);
+        let words_low = vreinterpretq_u32_u8(vld1q_u8(ptr));
+        let words_high = vreinterpretq_u32_u8(vld1q_u8(ptr.add(16)));
+        low = vmulq_u32(veorq_u32(low, words_low), prime);
+        high = vmulq_u32(veorq_u32(high, words_high), prime);
+    }
+
+    let mut lanes = [0u32; LANES];
+    vst1q_u32(lanes.as_mut_ptr(), low);
+    vst1q_u32(lanes.as_mut_ptr().add(4), high);
+    combine(&lanes, &bytes[blocks * BLOCK..], bytes.len())
+}
+
+/// Fold the lanes and the byte-wise FNV-1a hash of the tail into 64 bits.
+fn combine(lanes: &[u32; LANES], tail: &[u8], len: usize) -> u64 {
+    let mut hash = FNV64_OFFSET ^ len as u64;
+    for &lane in lanes {
+        hash = (hash ^ lane as u64).wrapping_mul(FNV64_PRIME);
+    }
+    for &byte in tail {
+        hash = (hash ^ byte as u64).wrapping_mul(FNV64_PRIME);
+    }
+    fmix64(hash)
+}
+
+// MurmurHash3 finalizer: spreads entropy into the high bits hashbrown uses
+// for its control bytes.
+fn fmix64(mut k: u64) -> u64 {
+    k ^= k >> 33;
+    k = k.wrapping_mul(0xff51_afd7_ed55_8ccd);
+    k ^= k >> 33;
+    k = k.wrapping_mul(0xc4ce_b9fe_1a85_ec53);
+    k ^ (k >> 33)
+}
+
+/// `Hasher` used by the term dictionary maps.
+#[derive(Debug, Clone, Copy)]
+pub struct SimdFnvHasher {
+    state: u64,
+}
+
+impl Default for SimdFnvHasher {
+    fn default() -> Self {
+        SimdFnvHasher { state: FNV64_OFFSET }
+    }
+}
+
+impl Hasher for SimdFnvHasher {
+    fn write(&mut self, bytes: &[u8]) {
+        self.state = (self.state ^ hash_bytes(bytes)).wrapping_mul(FNV64_PRIME);
+    }
+
+    fn write_u8(&mut self, i: u8) {
+        self.state = (self.state ^ i as u64).wrapping_mul(FNV64_PRIME);
+    }
+
+    fn write_u32(&mut self, i: u32) {
+        self.state = (self.state ^ i as u64).wrapping_mul(FNV64_PRIME);
+    }
+
+    fn write_u64(&mut self, i: u64) {
+        self.state = (self.state ^ i).wrapping_mul(FNV64_PRIME);
+    }
+
+    fn write_usize(&mut self, i: usize) {
+        self.write_u64(i as u64);
+    }
+
+    fn finish(&self) -> u64 {
+        fmix64(self.state)
+    }
+}
+
+pub type SimdFnvBuildHasher = BuildHasherDefault<SimdFnvHasher>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn sample(len: usize) -> Vec<u8> {
+        (0..len).map(|i| (i * 31 + 7) as u8).collect()
+    }
+
+    #[test]
+    fn simd_path_matches_scalar_reference() {
+        for len in [0, 1, 31, 32, 33, 63, 64, 100, 257, 4096] {
+            let bytes = sample(len);
+            assert_eq!(hash_bytes(&bytes), hash_bytes_scalar(&bytes), "length {}", len);
+        }
+    }
+
+    #[test]
+    fn distinguishes_similar_iris() {
+        let a = hash_bytes(b"http://example.org/resource/person/000000000001");
+        let b = hash_bytes(b"http://example.org/resource/person/000000000002");
+        let c = hash_bytes(b"http://example.org/resource/person/00000000001");
+        assert_ne!(a, b);
+        assert_ne!(a, c);
+    }
+}
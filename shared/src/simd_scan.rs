@@ -0,0 +1,258 @@
+/*
+ * Copyright © 2026 Volodymyr Kadzhaia
+ * Copyright © 2026 Pieter Bonte
+ * KU Leuven — Stream Intelligence Lab, Belgium
+ *
+ * This Source Code Form is subject to the terms of the Mozilla Public
+ * License, v. 2.0. If a copy of the MPL was not distributed with this file,
+ * you can obtain one at https://mozilla.org/MPL/2.0/.
+ */
+
+//! Matching triples against the bound positions of a pattern, a chunk at a
+//! time.
+//!
+//! A chunk of up to eight triples is split into subject, predicate and
+//! object columns. Each bound position is compared against its broadcast ID
+//! in one AVX2 register (two SSE2 or NEON registers) and the comparisons are
+//! and-ed into a bitmask with one bit per triple. Unbound positions are
+//! wildcards.
+
+use crate::triple::Triple;
+
+#[cfg(target_arch = "x86_64")]
+use std::arch::x86_64::*;
+#[cfg(target_arch = "aarch64")]
+use std::arch::aarch64::*;
+
+/// Triples compared per call to [`match_mask`].
+pub const SCAN_CHUNK: usize = 8;
+
+/// The IDs a pattern binds its subject, predicate and object to; `None` is
+/// a wildcard.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BoundIds {
+    pub subject: Option<u32>,
+    pub predicate: Option<u32>,
+    pub object: Option<u32>,
+}
+
+impl BoundIds {
+    pub fn is_unbound(&self) -> bool {
+        self.subject.is_none() && self.predicate.is_none() && self.object.is_none()
+    }
+
+    pub fn matches(&self, triple: &Triple) -> bool {
+        self.subject.is_none_or(|s| triple.subject == s)
+            && self.predicate.is_none_or(|p| triple.predicate == p)
+            && self.object.is_none_or(|o| triple.object == o)
+    }
+}
+
+/// Bit `i` is set when `chunk[i]` matches `bound`. Only the first
+/// [`SCAN_CHUNK`] triples of `chunk` are looked at.
+pub fn match_mask(chunk: &[Triple], bound: &BoundIds) -> u8 {
+    let chunk = &chunk[..chunk.len().min(SCAN_CHUNK)];
+    let mut mask = 0u8;
+    for_each_match(chunk, bound, |i| mask |= 1 << i);
+    mask
+}
+
+/// Call `on_match` with the index of every triple of `triples` that
+/// matches `bound`, in order.
+pub fn for_each_match<F: FnMut(usize)>(triples: &[Triple], bound: &BoundIds, on_match: F) {
+    #[cfg(target_arch = "x86_64")]
+    {
+        if is_x86_feature_detected!("avx2") {
+            return unsafe { for_each_match_avx2(triples, bound, on_match) };
+        }
+        unsafe { for_each_match_sse2(triples, bound, on_match) }
+    }
+    #[cfg(target_arch = "aarch64")]
+    {
+        unsafe { for_each_match_neon(triples, bound, on_match) }
+    }
+    #[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+    {
+        scan_chunks(triples, bound, on_match, match_columns_scalar)
+    }
+}
+
+/// Split `triples` into columns a chunk at a time and report the matches
+/// `match_columns` finds. Inlined into each SIMD entry point so the column
+/// comparison is compiled with that entry point's target features.
+#[inline(always)]
+fn scan_chunks<F, M>(triples: &[Triple], bound: &BoundIds, mut on_match: F, match_columns: M)
+where
+    F: FnMut(usize),
+    M: Fn(&[Column; 3]) -> u8,
+{
+    for (start, chunk) in (0..).step_by(SCAN_CHUNK).zip(triples.chunks(SCAN_CHUNK)) {
+        let mut subjects = [0u32; SCAN_CHUNK];
+        let mut predicates = [0u32; SCAN_CHUNK];
+        let mut objects = [0u32; SCAN_CHUNK];
+        for (i, triple) in chunk.iter().enumerate() {
+            subjects[i] = triple.subject;
+            predicates[i] = triple.predicate;
+            objects[i] = triple.object;
+        }
+        let columns = [
+            (bound.subject, &subjects),
+            (bound.predicate, &predicates),
+            (bound.object, &objects),
+        ];
+        let in_chunk = ((1u16 << chunk.len()) - 1) as u8;
+        let mut mask = match_columns(&columns) & in_chunk;
+        while mask != 0 {
+            on_match(start + mask.trailing_zeros() as usize);
+            mask &= mask - 1;
+        }
+    }
+}
+
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx2")]
+unsafe fn for_each_match_avx2<F: FnMut(usize)>(triples: &[Triple], bound: &BoundIds, on_match: F) {
+    scan_chunks(triples, bound, on_match, |columns| match_columns_avx2(columns))
+}
+
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "sse2")]
+unsafe fn for_each_match_sse2<F: FnMut(usize)>(triples: &[Triple], bound: &BoundIds, on_match: F) {
+    scan_chunks(triples, bound, on_match, |columns| match_columns_sse2(columns))
+}
+
+#[cfg(target_arch = "aarch64")]
+#[target_feature(enable = "neon")]
+unsafe fn for_each_match_neon<F: FnMut(usize)>(triples: &[Triple], bound: &BoundIds, on_match: F) {
+    scan_chunks(triples, bound, on_match, |columns| match_columns_neon(columns))
+}
+
+type Column<'c> = (Option<u32>, &'c [u32; SCAN_CHUNK]);
+
+/// Portable implementation; the reference for the SIMD paths.
+pub fn match_mask_scalar(chunk: &[Triple], bound: &BoundIds) -> u8 {
+    chunk
+        .iter()
+        .take(SCAN_CHUNK)
+        .enumerate()
+        .filter(|(_, triple)| bound.matches(triple))
+        .fold(0u8, |mask, (i, _)| mask | (1 << i))
+}
+
+#[cfg(not(any(target_arch = "x86_64", target_arch = "aarch64")))]
+fn match_columns_scalar(columns: &[Column; 3]) -> u8 {
+    let mut mask = u8::MAX;
+    for (id, values) in columns {
+        if let Some(id) = id {
+            for (i, value) in values.iter().enumerate() {
+                if value != id {
+                    mask &= !(1 << i);
+                }
+            }
+        }
+    }
+    mask
+}
+
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "avx2")]
+#[inline]
+unsafe fn match_columns_avx2(columns: &[Column; 3]) -> u8 {
+    let mut all = _mm256_set1_epi32(-1);
+    for (id, values) in columns {
+        if let Some(id) = id {
+            let lanes = _mm256_loadu_si256(values.as_ptr() as *const __m256i);
+            all = _mm256_and_si256(all, _mm256_cmpeq_epi32(lanes, _mm256_set1_epi32(*id as i32)));
+        }
+    }
+    _mm256_movemask_ps(_mm256_castsi256_ps(all)) as u8
+}
+
+#[cfg(target_arch = "x86_64")]
+#[target_feature(enable = "sse2")]
+#[inline]
+unsafe fn match_columns_sse2(columns: &[Column; 3]) -> u8 {
+    let mut low = _mm_set1_epi32(-1);
+    let mut high = _mm_set1_epi32(-1);
+    for (id, values) in columns {
+        if let Some(id) = id {
+            let target = _mm_set1_epi32(*id as i32);
+            let ptr = values.as_ptr() as *const __m128i;
+            low = _mm_and_si128(low, _mm_cmpeq_epi32(_mm_loadu_si128(ptr), target));
+            high = _mm_and_si128(high, _mm_cmpeq_epi32(_mm_loadu_si128(ptr.add(1)), target));
+        }
+    }
+    let low_bits = _mm_movemask_ps(_mm_castsi128_ps(low)) as u8;
+    let high_bits = _mm_movemask_ps(_mm_castsi128_ps(high)) as u8;
+    low_bits | (high_bits << 4)
+}
+
+#[cfg(target_arch = "aarch64")]
+#[target_feature(enable = "neon")]
+#[inline]
+unsafe fn match_columns_neon(columns: &[Column; 3]) -> u8 {
+    let mut low = vdupq_n_u32(u32::MAX);
+    let mut high = vdupq_n_u32(u32::MAX);
+    for (id, values) in columns {
+        if let Some(id) = id {
+            let target = vdupq_n_u32(*id);
+            low = vandq_u32(low, vceqq_u32(vld1q_u32(values.as_ptr()), target));
+            high = vandq_u32(high, vceqq_u32(vld1q_u32(values.as_ptr().add(4)), target));
+        }
+    }
+    // One bit per lane: keep bit i of lane i, then add the lanes up
+    let weights = [1u32, 2, 4, 8];
+    let weights = vld1q_u32(weights.as_ptr());
+    let low_bits = vaddvq_u32(vandq_u32(low, weights)) as u8;
+    let high_bits = vaddvq_u32(vandq_u32(high, weights)) as u8;
+    low_bits | (high_bits << 4)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn triple(subject: u32, predicate: u32, object: u32) -> Triple {
+        Triple { subject, predicate, object }
+    }
+
+    fn sample() -> Vec<Triple> {
+        (0..8).map(|i| triple(i % 3, i % 2, i % 4)).collect()
+    }
+
+    #[test]
+    fn simd_path_matches_scalar_reference() {
+        let triples = sample();
+        for subject in [None, Some(0), Some(1), Some(7)] {
+            for predicate in [None, Some(0), Some(1)] {
+                for object in [None, Some(0), Some(3)] {
+                    let bound = BoundIds { subject, predicate, object };
+                    for len in 0..=SCAN_CHUNK {
+                        assert_eq!(
+                            match_mask(&triples[..len], &bound),
+                            match_mask_scalar(&triples[..len], &bound),
+                            "{:?} over {} triples",
+                            bound,
+                            len
+                        );
+                    }
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn wildcards_match_every_triple_in_the_chunk() {
+        let triples = sample();
+        assert_eq!(match_mask(&triples, &BoundIds::default()), 0xFF);
+        assert_eq!(match_mask(&triples[..3], &BoundIds::default()), 0b111);
+    }
+
+    #[test]
+    fn bound_positions_are_combined() {
+        let triples = sample();
+        // Triples 1, 3, 5 and 7 have predicate 1; of those only 1 and 5 have object 1
+        let bound = BoundIds { subject: None, predicate: Some(1), object: Some(1) };
+        assert_eq!(match_mask(&triples, &bound), 0b0010_0010);
+    }
+}